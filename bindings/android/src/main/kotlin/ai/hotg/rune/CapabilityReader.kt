@@ -0,0 +1,24 @@
+package ai.hotg.rune
+
+import java.nio.ByteBuffer
+
+/**
+ * Something which provides data to a Rune's capabilities.
+ *
+ * The [buffer] points directly into the Rune's memory, so it must be filled
+ * before [read] returns and must not be used afterwards.
+ */
+fun interface CapabilityReader {
+    fun read(capabilityId: Int, kind: String, buffer: ByteBuffer)
+
+    companion object {
+        /** Dispatch to a different [CapabilityReader] based on the kind of
+         * capability (e.g. `"ACCEL"` or `"RAW"`). */
+        fun byKind(readers: Map<String, CapabilityReader>): CapabilityReader =
+            CapabilityReader { id, kind, buffer ->
+                val reader = readers[kind]
+                    ?: throw RuneException("No reader for \"$kind\" capabilities")
+                reader.read(id, kind, buffer)
+            }
+    }
+}
@@ -0,0 +1,19 @@
+package ai.hotg.rune
+
+/**
+ * The type of value stored in a tensor.
+ *
+ * The order of these variants must match the runtime's `ElementType`.
+ */
+enum class ElementType(val byteSize: Int) {
+    U8(1),
+    I8(1),
+    U16(2),
+    I16(2),
+    U32(4),
+    I32(4),
+    F32(4),
+    U64(8),
+    I64(8),
+    F64(8),
+}
@@ -0,0 +1,66 @@
+package ai.hotg.rune
+
+import android.annotation.SuppressLint
+import android.location.Location
+import android.location.LocationListener
+import android.location.LocationManager
+import android.os.Looper
+import java.nio.ByteBuffer
+import java.nio.ByteOrder
+
+/**
+ * A [CapabilityReader] which provides the device's location to `GPS`
+ * capabilities.
+ *
+ * The most recent fixes are kept in a ring buffer and each read fills the
+ * Rune's buffer with as many of the latest `[latitude, longitude, altitude,
+ * accuracy]` readings as will fit, oldest first, as little-endian `f64`s.
+ *
+ * The app must have been granted the `ACCESS_FINE_LOCATION` permission
+ * before calling [start].
+ */
+class LocationCapability(
+    private val locationManager: LocationManager,
+    private val provider: String = LocationManager.GPS_PROVIDER,
+    private val capacity: Int = 16,
+    private val minTimeMs: Long = 1000,
+) : CapabilityReader, LocationListener, AutoCloseable {
+    private val fixes = ArrayDeque<DoubleArray>(capacity)
+
+    @SuppressLint("MissingPermission")
+    fun start(looper: Looper = Looper.getMainLooper()) {
+        locationManager.requestLocationUpdates(provider, minTimeMs, 0f, this, looper)
+    }
+
+    override fun close() {
+        locationManager.removeUpdates(this)
+    }
+
+    override fun onLocationChanged(location: Location) {
+        val fix = doubleArrayOf(
+            location.latitude,
+            location.longitude,
+            location.altitude,
+            location.accuracy.toDouble(),
+        )
+
+        synchronized(fixes) {
+            if (fixes.size == capacity) {
+                fixes.removeFirst()
+            }
+            fixes.addLast(fix)
+        }
+    }
+
+    override fun read(capabilityId: Int, kind: String, buffer: ByteBuffer) {
+        val doubles = buffer.order(ByteOrder.LITTLE_ENDIAN).asDoubleBuffer()
+
+        synchronized(fixes) {
+            val count = minOf(fixes.size, doubles.capacity() / 4)
+
+            for (fix in fixes.takeLast(count)) {
+                doubles.put(fix)
+            }
+        }
+    }
+}
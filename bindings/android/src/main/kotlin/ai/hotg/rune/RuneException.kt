@@ -0,0 +1,4 @@
+package ai.hotg.rune
+
+/** An error raised while loading or running a Rune. */
+class RuneException(message: String) : RuntimeException(message)
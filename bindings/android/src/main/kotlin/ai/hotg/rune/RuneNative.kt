@@ -0,0 +1,26 @@
+package ai.hotg.rune
+
+/** The JNI functions exported by `librune_native.so`. */
+internal object RuneNative {
+    init {
+        System.loadLibrary("rune_native")
+    }
+
+    @JvmStatic external fun load(rune: ByteArray): Long
+    @JvmStatic external fun free(handle: Long)
+    @JvmStatic external fun capabilities(handle: Long): String
+    @JvmStatic external fun outputs(handle: Long): String
+    @JvmStatic external fun setInput(
+        handle: Long,
+        capabilityId: Int,
+        elementType: Int,
+        dimensions: IntArray,
+        data: ByteArray,
+    )
+    @JvmStatic external fun predict(handle: Long)
+    @JvmStatic external fun outputTensors(handle: Long): String
+    @JvmStatic external fun setCapabilityReader(
+        handle: Long,
+        reader: CapabilityReader,
+    )
+}
@@ -0,0 +1,87 @@
+package ai.hotg.rune
+
+import java.nio.ByteBuffer
+import java.nio.ByteOrder
+import org.json.JSONArray
+import org.json.JSONObject
+
+/** A node in the Rune's pipeline (i.e. a capability or output). */
+data class Node(val id: Int, val kind: String, val arguments: Map<String, String>)
+
+/** A loaded Rune. */
+class Runtime private constructor(private var handle: Long) : AutoCloseable {
+    companion object {
+        /** Load a Rune from its raw bytes. */
+        fun load(rune: ByteArray): Runtime = Runtime(RuneNative.load(rune))
+    }
+
+    /** The Rune's capabilities. */
+    val capabilities: List<Node>
+        get() = parseNodes(RuneNative.capabilities(checkedHandle()))
+
+    /** The Rune's outputs. */
+    val outputs: List<Node>
+        get() = parseNodes(RuneNative.outputs(checkedHandle()))
+
+    /** Set the raw bytes a capability will provide the next time the Rune is run. */
+    fun setInput(
+        capabilityId: Int,
+        elementType: ElementType,
+        dimensions: IntArray,
+        data: ByteArray,
+    ) {
+        RuneNative.setInput(checkedHandle(), capabilityId, elementType.ordinal, dimensions, data)
+    }
+
+    /** Set the `f32` values a capability will provide the next time the Rune is run. */
+    fun setInput(capabilityId: Int, dimensions: IntArray, data: FloatArray) {
+        val bytes = ByteBuffer.allocate(data.size * ElementType.F32.byteSize)
+            .order(ByteOrder.LITTLE_ENDIAN)
+        bytes.asFloatBuffer().put(data)
+        setInput(capabilityId, ElementType.F32, dimensions, bytes.array())
+    }
+
+    /** Read capability data from a [CapabilityReader] instead of the inputs. */
+    fun setCapabilityReader(reader: CapabilityReader) {
+        RuneNative.setCapabilityReader(checkedHandle(), reader)
+    }
+
+    /** Run the Rune. */
+    fun predict() {
+        RuneNative.predict(checkedHandle())
+    }
+
+    /**
+     * The tensors each output received the last time the Rune was run, as a
+     * JSON object keyed by output ID.
+     */
+    fun outputTensors(): JSONObject = JSONObject(RuneNative.outputTensors(checkedHandle()))
+
+    override fun close() {
+        if (handle != 0L) {
+            RuneNative.free(handle)
+            handle = 0
+        }
+    }
+
+    private fun checkedHandle(): Long {
+        if (handle == 0L) {
+            throw RuneException("The runtime has already been closed")
+        }
+        return handle
+    }
+
+    private fun parseNodes(json: String): List<Node> {
+        val nodes = JSONArray(json)
+
+        return (0 until nodes.length()).map { i ->
+            val node = nodes.getJSONObject(i)
+            val args = node.getJSONObject("arguments")
+            Node(
+                id = node.getInt("id"),
+                kind = node.getString("kind"),
+                arguments = args.keys().asSequence().associateWith { args.getString(it) },
+            )
+        }
+    }
+}
@@ -0,0 +1,59 @@
+package ai.hotg.rune
+
+import android.hardware.Sensor
+import android.hardware.SensorEvent
+import android.hardware.SensorEventListener
+import android.hardware.SensorManager
+import java.nio.ByteBuffer
+import java.nio.ByteOrder
+
+/**
+ * A [CapabilityReader] backed by one of the device's sensors (e.g.
+ * [Sensor.TYPE_ACCELEROMETER] for the `ACCEL` capability).
+ *
+ * The most recent readings are kept in a ring buffer and each read fills the
+ * Rune's buffer with as many of the latest readings as will fit, oldest
+ * first, as little-endian `f32`s.
+ */
+class SensorCapability(
+    private val sensorManager: SensorManager,
+    sensorType: Int,
+    private val capacity: Int = 128,
+    private val samplingPeriodUs: Int = SensorManager.SENSOR_DELAY_GAME,
+) : CapabilityReader, SensorEventListener, AutoCloseable {
+    private val sensor: Sensor = sensorManager.getDefaultSensor(sensorType)
+        ?: throw RuneException("The device doesn't have a sensor of type $sensorType")
+    private val readings = ArrayDeque<FloatArray>(capacity)
+
+    fun start() {
+        sensorManager.registerListener(this, sensor, samplingPeriodUs)
+    }
+
+    override fun close() {
+        sensorManager.unregisterListener(this)
+    }
+
+    override fun onSensorChanged(event: SensorEvent) {
+        synchronized(readings) {
+            if (readings.size == capacity) {
+                readings.removeFirst()
+            }
+            readings.addLast(event.values.copyOf())
+        }
+    }
+
+    override fun onAccuracyChanged(sensor: Sensor, accuracy: Int) {}
+
+    override fun read(capabilityId: Int, kind: String, buffer: ByteBuffer) {
+        val floats = buffer.order(ByteOrder.LITTLE_ENDIAN).asFloatBuffer()
+
+        synchronized(readings) {
+            val valuesPerReading = readings.firstOrNull()?.size ?: return
+            val count = minOf(readings.size, floats.capacity() / valuesPerReading)
+
+            for (reading in readings.takeLast(count)) {
+                floats.put(reading)
+            }
+        }
+    }
+}
@@ -0,0 +1,75 @@
+import CoreLocation
+import Foundation
+
+/// A `CapabilityReader` which provides the device's location from
+/// CoreLocation to `GPS` capabilities.
+///
+/// The most recent fixes are kept in a ring buffer and each read fills the
+/// Rune's buffer with as many of the latest `[latitude, longitude, altitude,
+/// accuracy]` readings as will fit, oldest first, as `f64`s.
+///
+/// The app is responsible for requesting location authorization before
+/// calling `start()`.
+public final class LocationCapability: NSObject, CapabilityReader,
+    CLLocationManagerDelegate
+{
+    private let manager: CLLocationManager
+    private let capacity: Int
+    private var fixes: [[Double]] = []
+    private let lock = NSLock()
+
+    public init(
+        manager: CLLocationManager = CLLocationManager(),
+        capacity: Int = 16
+    ) {
+        self.manager = manager
+        self.capacity = capacity
+        super.init()
+        manager.delegate = self
+        manager.desiredAccuracy = kCLLocationAccuracyBest
+    }
+
+    deinit {
+        stop()
+    }
+
+    public func start() {
+        manager.startUpdatingLocation()
+    }
+
+    public func stop() {
+        manager.stopUpdatingLocation()
+    }
+
+    public func locationManager(
+        _ manager: CLLocationManager,
+        didUpdateLocations locations: [CLLocation]
+    ) {
+        lock.lock()
+        defer { lock.unlock() }
+
+        for location in locations {
+            if fixes.count == capacity {
+                fixes.removeFirst()
+            }
+            fixes.append([
+                location.coordinate.latitude,
+                location.coordinate.longitude,
+                location.altitude,
+                location.horizontalAccuracy,
+            ])
+        }
+    }
+
+    public func read(node: Node, buffer: UnsafeMutableRawBufferPointer) throws {
+        lock.lock()
+        defer { lock.unlock() }
+
+        let doubles = buffer.bindMemory(to: Double.self)
+        let count = min(fixes.count, doubles.count / 4)
+
+        for (i, value) in fixes.suffix(count).joined().enumerated() {
+            doubles[i] = value
+        }
+    }
+}
@@ -0,0 +1,99 @@
+import CoreMotion
+import Foundation
+
+/// A `CapabilityReader` which provides accelerometer or gyroscope readings
+/// from CoreMotion to `ACCEL` or `GYRO` capabilities.
+///
+/// The most recent readings are kept in a ring buffer and each read fills the
+/// Rune's buffer with as many of the latest `[x, y, z]` readings as will fit,
+/// oldest first, as `f32`s.
+public final class MotionCapability: CapabilityReader {
+    /// Which of the device's motion sensors to read from.
+    public enum Sensor {
+        case accelerometer
+        case gyroscope
+    }
+
+    private let manager: CMMotionManager
+    private let sensor: Sensor
+    private let queue = OperationQueue()
+    private let capacity: Int
+    private var readings: [[Float]] = []
+    private let lock = NSLock()
+
+    public init(
+        manager: CMMotionManager = CMMotionManager(),
+        sensor: Sensor = .accelerometer,
+        updateInterval: TimeInterval = 1.0 / 60.0,
+        capacity: Int = 128
+    ) {
+        self.manager = manager
+        self.sensor = sensor
+        self.capacity = capacity
+
+        switch sensor {
+        case .accelerometer:
+            manager.accelerometerUpdateInterval = updateInterval
+        case .gyroscope:
+            manager.gyroUpdateInterval = updateInterval
+        }
+    }
+
+    deinit {
+        stop()
+    }
+
+    public func start() {
+        switch sensor {
+        case .accelerometer:
+            manager.startAccelerometerUpdates(to: queue) { [weak self] data, _ in
+                guard let data = data else { return }
+                self?.record([
+                    Float(data.acceleration.x),
+                    Float(data.acceleration.y),
+                    Float(data.acceleration.z),
+                ])
+            }
+        case .gyroscope:
+            manager.startGyroUpdates(to: queue) { [weak self] data, _ in
+                guard let data = data else { return }
+                self?.record([
+                    Float(data.rotationRate.x),
+                    Float(data.rotationRate.y),
+                    Float(data.rotationRate.z),
+                ])
+            }
+        }
+    }
+
+    public func stop() {
+        switch sensor {
+        case .accelerometer:
+            manager.stopAccelerometerUpdates()
+        case .gyroscope:
+            manager.stopGyroUpdates()
+        }
+    }
+
+    private func record(_ reading: [Float]) {
+        lock.lock()
+        defer { lock.unlock() }
+
+        if readings.count == capacity {
+            readings.removeFirst()
+        }
+        readings.append(reading)
+    }
+
+    public func read(node: Node, buffer: UnsafeMutableRawBufferPointer) throws {
+        lock.lock()
+        defer { lock.unlock() }
+
+        let floats = buffer.bindMemory(to: Float.self)
+        let count = min(readings.count, floats.count / 3)
+
+        for (i, value) in readings.suffix(count).joined().enumerated() {
+            floats[i] = value
+        }
+    }
+}
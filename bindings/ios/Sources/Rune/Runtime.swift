@@ -0,0 +1,257 @@
+import Foundation
+import RuneNative
+
+/// An error raised while loading or running a Rune.
+public struct RuneError: Error, CustomStringConvertible {
+    public let description: String
+
+    /// Take ownership of an error returned by the native library.
+    init(consuming error: OpaquePointer) {
+        let msg = rune_error_to_string_verbose(error)!
+        description = String(cString: msg)
+        free(msg)
+        rune_error_free(error)
+    }
+}
+
+/// The type of value stored in a tensor.
+public enum ElementType: UInt32 {
+    case u8, i8, u16, i16, u32, i32, f32, u64, i64, f64
+}
+
+/// A node in the Rune's pipeline (i.e. a capability or output).
+public struct Node {
+    public let id: UInt32
+    public let kind: String
+    public let arguments: [String: String]
+
+    init(_ node: OpaquePointer) {
+        id = rune_node_id(node)
+        kind = String(cString: rune_node_kind(node))
+
+        var arguments: [String: String] = [:]
+        for i in 0..<rune_node_argument_count(node) {
+            let name = String(cString: rune_node_get_argument_name(node, i))
+            let value = String(cString: rune_node_get_argument_value(node, i))
+            arguments[name] = value
+        }
+        self.arguments = arguments
+    }
+}
+
+/// A tensor written to one of the Rune's outputs.
+public enum OutputTensor {
+    case tensor(elementType: ElementType, dimensions: [Int], data: Data)
+    case strings(dimensions: [Int], strings: [String])
+}
+
+/// Something which provides data to a Rune's capabilities.
+///
+/// The buffer points directly into the Rune's memory, so it must be filled
+/// before `read()` returns and must not be used afterwards.
+public protocol CapabilityReader: AnyObject {
+    func read(node: Node, buffer: UnsafeMutableRawBufferPointer) throws
+}
+
+/// A loaded Rune.
+public final class Runtime {
+    private let runtime: OpaquePointer
+
+    /// Load a Rune from its raw bytes.
+    public init(rune: Data) throws {
+        var runtime: OpaquePointer? = nil
+        let error: OpaquePointer? = rune.withUnsafeBytes { bytes in
+            var cfg = Config(
+                rune: bytes.bindMemory(to: UInt8.self).baseAddress,
+                rune_len: Int32(bytes.count)
+            )
+            return rune_runtime_load(&cfg, &runtime)
+        }
+
+        if let error = error {
+            throw RuneError(consuming: error)
+        }
+        self.runtime = runtime!
+    }
+
+    deinit {
+        rune_runtime_free(runtime)
+    }
+
+    /// The Rune's capabilities.
+    public func capabilities() throws -> [Node] {
+        var metadata: OpaquePointer? = nil
+        try check(rune_runtime_inputs(runtime, &metadata))
+        return nodes(metadata!)
+    }
+
+    /// The Rune's outputs.
+    public func outputs() throws -> [Node] {
+        var metadata: OpaquePointer? = nil
+        try check(rune_runtime_outputs(runtime, &metadata))
+        return nodes(metadata!)
+    }
+
+    /// Set the data a capability will provide the next time the Rune is run.
+    public func setInput(
+        capabilityId: UInt32,
+        elementType: ElementType,
+        dimensions: [Int],
+        data: Data
+    ) {
+        let tensors = rune_runtime_input_tensors(runtime)
+        defer { rune_input_tensors_free(tensors) }
+
+        let tensor = dimensions.withUnsafeBufferPointer { dims in
+            rune_input_tensors_insert(
+                tensors,
+                capabilityId,
+                RuneNative.ElementType(elementType.rawValue),
+                dims.baseAddress,
+                Int32(dims.count)
+            )
+        }
+
+        let len = Int(rune_tensor_buffer_len(tensor))
+        precondition(
+            data.count == len,
+            "Expected \(len) bytes of input but received \(data.count)"
+        )
+        data.copyBytes(to: rune_tensor_buffer(tensor), count: len)
+    }
+
+    /// Set the `f32` values a capability will provide the next time the Rune
+    /// is run.
+    public func setInput(
+        capabilityId: UInt32,
+        dimensions: [Int],
+        values: [Float]
+    ) {
+        let data = values.withUnsafeBufferPointer { Data(buffer: $0) }
+        setInput(
+            capabilityId: capabilityId,
+            elementType: .f32,
+            dimensions: dimensions,
+            data: data
+        )
+    }
+
+    /// Read capability data from a `CapabilityReader` instead of the inputs.
+    public func setCapabilityReader(_ reader: CapabilityReader) {
+        let userData = Unmanaged.passRetained(ReaderBox(reader)).toOpaque()
+
+        rune_runtime_set_capability_callback(
+            runtime,
+            { userData, node, buffer, len in
+                let reader = Unmanaged<ReaderBox>.fromOpaque(userData!)
+                    .takeUnretainedValue()
+                    .reader
+                let buffer = UnsafeMutableRawBufferPointer(
+                    start: buffer,
+                    count: Int(len)
+                )
+
+                do {
+                    try reader.read(node: Node(node!), buffer: buffer)
+                    return nil
+                } catch {
+                    let msg = String(describing: error)
+                    return rune_error_new(msg, Int32(msg.utf8.count))
+                }
+            },
+            userData,
+            { userData in
+                Unmanaged<ReaderBox>.fromOpaque(userData!).release()
+            }
+        )
+    }
+
+    /// Run the Rune.
+    public func predict() throws {
+        try check(rune_runtime_predict(runtime))
+    }
+
+    /// The tensors each output received the last time the Rune was run,
+    /// keyed by output ID.
+    public func outputTensors() -> [UInt32: [OutputTensor]] {
+        let tensors = rune_runtime_output_tensors(runtime)
+        defer { rune_output_tensors_free(tensors) }
+
+        var outputs: [UInt32: [OutputTensor]] = [:]
+        var id: UInt32 = 0
+        var tensor: OpaquePointer? = nil
+
+        while rune_output_tensors_next(tensors, &id, &tensor) {
+            outputs[id, default: []].append(outputTensor(tensor!))
+        }
+
+        return outputs
+    }
+
+    private func check(_ error: OpaquePointer?) throws {
+        if let error = error {
+            throw RuneError(consuming: error)
+        }
+    }
+
+    private func nodes(_ metadata: OpaquePointer) -> [Node] {
+        defer { rune_metadata_free(metadata) }
+
+        return (0..<rune_metadata_node_count(metadata)).map { i in
+            Node(rune_metadata_get_node(metadata, i)!)
+        }
+    }
+
+    private func outputTensor(_ tensor: OpaquePointer) -> OutputTensor {
+        if let fixed = rune_output_tensor_as_fixed(tensor) {
+            let rank = Int(rune_tensor_rank(fixed))
+            let dimensions = Array(
+                UnsafeBufferPointer(
+                    start: rune_tensor_dimensions(fixed),
+                    count: rank
+                )
+            )
+            let data = Data(
+                bytes: rune_tensor_buffer_readonly(fixed),
+                count: Int(rune_tensor_buffer_len(fixed))
+            )
+            let elementType = ElementType(
+                rawValue: rune_tensor_element_type(fixed)
+            )!
+
+            return .tensor(
+                elementType: elementType,
+                dimensions: dimensions,
+                data: data
+            )
+        }
+
+        let strings = rune_output_tensor_as_string_tensor(tensor)!
+        defer { rune_string_tensor_free(strings) }
+
+        let dimensions = Array(
+            UnsafeBufferPointer(
+                start: rune_string_tensor_dimensions(strings),
+                count: Int(rune_string_tensor_rank(strings))
+            )
+        )
+        let count = dimensions.reduce(1, *)
+        let values: [String] = (0..<count).map { i in
+            var ptr: UnsafePointer<UInt8>? = nil
+            let len = Int(rune_string_tensor_get_by_index(strings, i, &ptr))
+            let bytes = UnsafeBufferPointer(start: ptr, count: len)
+            return String(decoding: bytes, as: UTF8.self)
+        }
+
+        return .strings(dimensions: dimensions, strings: values)
+    }
+}
+
+/// Lets a `CapabilityReader` be passed through the C API as user data.
+private final class ReaderBox {
+    let reader: CapabilityReader
+
+    init(_ reader: CapabilityReader) {
+        self.reader = reader
+    }
+}
@@ -0,0 +1,259 @@
+//! JNI bindings used by the `ai.hotg.rune.Runtime` Kotlin class.
+//!
+//! Each function takes a `handle`, a pointer to the [`Runtime`] that was
+//! returned by `load()`. Errors are thrown as an `ai.hotg.rune.RuneException`.
+
+use std::{convert::TryFrom, num::NonZeroUsize, ptr};
+
+use anyhow::{Context, Error};
+use hotg_rune_runtime::{ElementType, NodeMetadata, Tensor};
+use jni::{
+    objects::{GlobalRef, JClass, JObject, JValue},
+    sys::{jbyteArray, jint, jintArray, jlong, jstring},
+    JNIEnv,
+};
+use serde_json::{json, Value};
+
+use crate::Runtime;
+
+const EXCEPTION_CLASS: &str = "ai/hotg/rune/RuneException";
+const READ_SIGNATURE: &str = "(ILjava/lang/String;Ljava/nio/ByteBuffer;)V";
+
+/// Run `func`, throwing a `RuneException` and returning `fallback` if it
+/// fails.
+fn catch<T>(
+    env: &JNIEnv<'_>,
+    fallback: T,
+    func: impl FnOnce() -> Result<T, Error>,
+) -> T {
+    match func() {
+        Ok(value) => value,
+        Err(e) => {
+            // There's not much we can do if throwing the exception fails
+            let _ = env.throw_new(EXCEPTION_CLASS, format!("{:?}", e));
+            fallback
+        },
+    }
+}
+
+unsafe fn runtime<'rt>(handle: jlong) -> Result<&'rt mut Runtime, Error> {
+    (handle as *mut Runtime)
+        .as_mut()
+        .context("The runtime has already been closed")
+}
+
+#[no_mangle]
+pub unsafe extern "system" fn Java_ai_hotg_rune_RuneNative_load(
+    env: JNIEnv<'_>,
+    _class: JClass<'_>,
+    rune: jbyteArray,
+) -> jlong {
+    catch(&env, 0, || {
+        let rune = env.convert_byte_array(rune)?;
+        let inner = crate::runtime::load(&rune)?;
+        let runtime = Box::new(Runtime { inner });
+
+        Ok(Box::into_raw(runtime) as jlong)
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "system" fn Java_ai_hotg_rune_RuneNative_free(
+    _env: JNIEnv<'_>,
+    _class: JClass<'_>,
+    handle: jlong,
+) {
+    if handle != 0 {
+        let _ = Box::from_raw(handle as *mut Runtime);
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "system" fn Java_ai_hotg_rune_RuneNative_capabilities(
+    env: JNIEnv<'_>,
+    _class: JClass<'_>,
+    handle: jlong,
+) -> jstring {
+    catch(&env, ptr::null_mut(), || {
+        let runtime = runtime(handle)?;
+        let nodes = nodes_to_json(runtime.capabilities().iter());
+
+        Ok(env.new_string(nodes.to_string())?.into_inner())
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "system" fn Java_ai_hotg_rune_RuneNative_outputs(
+    env: JNIEnv<'_>,
+    _class: JClass<'_>,
+    handle: jlong,
+) -> jstring {
+    catch(&env, ptr::null_mut(), || {
+        let runtime = runtime(handle)?;
+        let nodes = nodes_to_json(runtime.outputs().iter());
+
+        Ok(env.new_string(nodes.to_string())?.into_inner())
+    })
+}
+
+fn nodes_to_json<'a>(
+    nodes: impl Iterator<Item = (&'a u32, &'a NodeMetadata)>,
+) -> Value {
+    nodes
+        .map(|(id, meta)| {
+            json!({
+                "id": id,
+                "kind": meta.kind,
+                "arguments": meta.arguments,
+            })
+        })
+        .collect()
+}
+
+#[no_mangle]
+pub unsafe extern "system" fn Java_ai_hotg_rune_RuneNative_setInput(
+    env: JNIEnv<'_>,
+    _class: JClass<'_>,
+    handle: jlong,
+    capability_id: jint,
+    element_type: jint,
+    dimensions: jintArray,
+    data: jbyteArray,
+) {
+    catch(&env, (), || {
+        let runtime = runtime(handle)?;
+        let element_type = element_type_from_ordinal(element_type)?;
+
+        let rank = env.get_array_length(dimensions)?;
+        let mut raw_dimensions = vec![0; rank as usize];
+        env.get_int_array_region(dimensions, 0, &mut raw_dimensions)?;
+        let dimensions = raw_dimensions
+            .iter()
+            .map(|&d| {
+                usize::try_from(d)
+                    .ok()
+                    .and_then(NonZeroUsize::new)
+                    .with_context(|| format!("Invalid dimension, {}", d))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        let buffer = env.convert_byte_array(data)?;
+        let num_elements: usize = dimensions.iter().map(|d| d.get()).product();
+        let expected_length = num_elements * element_type.byte_size();
+        anyhow::ensure!(
+            buffer.len() == expected_length,
+            "A {}{:?} tensor should take up {} bytes, but {} bytes were \
+             provided",
+            element_type,
+            raw_dimensions,
+            expected_length,
+            buffer.len(),
+        );
+
+        let tensor = Tensor::new_raw(element_type, dimensions, buffer);
+        runtime.input_tensors().insert(capability_id as u32, tensor);
+
+        Ok(())
+    })
+}
+
+/// Convert the ordinal of an `ai.hotg.rune.ElementType` back to an
+/// [`ElementType`].
+fn element_type_from_ordinal(ordinal: jint) -> Result<ElementType, Error> {
+    let element_type = match ordinal {
+        0 => ElementType::U8,
+        1 => ElementType::I8,
+        2 => ElementType::U16,
+        3 => ElementType::I16,
+        4 => ElementType::U32,
+        5 => ElementType::I32,
+        6 => ElementType::F32,
+        7 => ElementType::U64,
+        8 => ElementType::I64,
+        9 => ElementType::F64,
+        other => anyhow::bail!("Unknown element type, {}", other),
+    };
+
+    Ok(element_type)
+}
+
+#[no_mangle]
+pub unsafe extern "system" fn Java_ai_hotg_rune_RuneNative_predict(
+    env: JNIEnv<'_>,
+    _class: JClass<'_>,
+    handle: jlong,
+) {
+    catch(&env, (), || runtime(handle)?.predict())
+}
+
+#[no_mangle]
+pub unsafe extern "system" fn Java_ai_hotg_rune_RuneNative_outputTensors(
+    env: JNIEnv<'_>,
+    _class: JClass<'_>,
+    handle: jlong,
+) -> jstring {
+    catch(&env, ptr::null_mut(), || {
+        let runtime = runtime(handle)?;
+        let tensors = serde_json::to_string(runtime.output_tensors())?;
+
+        Ok(env.new_string(tensors)?.into_inner())
+    })
+}
+
+/// Use an `ai.hotg.rune.CapabilityReader` to provide data to capabilities
+/// instead of the input tensors.
+#[no_mangle]
+pub unsafe extern "system" fn Java_ai_hotg_rune_RuneNative_setCapabilityReader(
+    env: JNIEnv<'_>,
+    _class: JClass<'_>,
+    handle: jlong,
+    reader: JObject<'_>,
+) {
+    catch(&env, (), || {
+        let runtime = runtime(handle)?;
+        let vm = env.get_java_vm()?;
+        let reader = env.new_global_ref(reader)?;
+
+        runtime.set_capability_handler(move |id, meta, buffer| {
+            let env = vm.attach_current_thread()?;
+            read_capability(&env, &reader, id, &meta.kind, buffer)?;
+            Ok(buffer.len())
+        });
+
+        Ok(())
+    })
+}
+
+fn read_capability(
+    env: &JNIEnv<'_>,
+    reader: &GlobalRef,
+    id: u32,
+    kind: &str,
+    buffer: &mut [u8],
+) -> Result<(), Error> {
+    let kind = env.new_string(kind)?;
+    // Note: the ByteBuffer points directly at the Rune's buffer, so the reader
+    // mustn't hold onto it after read() returns.
+    let byte_buffer = env.new_direct_byte_buffer(buffer)?;
+
+    let result = env.call_method(
+        reader.as_obj(),
+        "read",
+        READ_SIGNATURE,
+        &[
+            JValue::Int(id as jint),
+            JValue::Object(kind.into()),
+            JValue::Object(byte_buffer.into()),
+        ],
+    );
+
+    if env.exception_check()? {
+        env.exception_describe()?;
+        env.exception_clear()?;
+        anyhow::bail!("Reading capability {} threw an exception", id);
+    }
+
+    result.with_context(|| format!("Unable to read capability {}", id))?;
+
+    Ok(())
+}
@@ -64,6 +64,19 @@ impl Error {
     }
 }
 
+impl Error {
+    /// Take ownership of an `Error` that was returned across the FFI
+    /// boundary (e.g. by a callback).
+    ///
+    /// # Safety
+    ///
+    /// The pointer must have come from [`Error::boxed()`] and can't be used
+    /// afterwards.
+    pub(crate) unsafe fn from_raw(error: *mut Error) -> anyhow::Error {
+        Box::from_raw(error).0
+    }
+}
+
 impl From<anyhow::Error> for Error {
     fn from(e: anyhow::Error) -> Error { Error(e) }
 }
@@ -183,6 +196,9 @@ fn rune_load_error(
         hotg_rune_runtime::LoadError::WasmerCompile(e) => {
             Some(wasmer_compile_error(e))
         },
+        hotg_rune_runtime::LoadError::UnsupportedAbiVersion { .. } => {
+            Some(ErrorKind::BadImports)
+        },
         _ => None,
     }
 }
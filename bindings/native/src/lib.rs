@@ -1,6 +1,8 @@
 #[macro_use]
 mod macros;
 
+#[cfg(feature = "android")]
+mod android;
 mod error;
 mod input_tensors;
 mod metadata;
@@ -18,27 +18,10 @@ impl std::ops::Deref for Metadata {
 
 impl From<&'_ HashMap<u32, NodeMetadata>> for Metadata {
     fn from(node_metadata: &'_ HashMap<u32, NodeMetadata>) -> Metadata {
-        let mut meta = Vec::new();
-
-        for (&id, m) in node_metadata {
-            let NodeMetadata {
-                kind, arguments, ..
-            } = m;
-            let kind = CString::new(kind.as_str()).unwrap();
-            let mut args = Vec::new();
-
-            for (key, value) in arguments {
-                let key = CString::new(key.as_str()).unwrap();
-                let value = CString::new(value.as_str()).unwrap();
-                args.push((key, value));
-            }
-
-            meta.push(Node {
-                id,
-                kind,
-                arguments: args,
-            });
-        }
+        let meta = node_metadata
+            .iter()
+            .map(|(&id, m)| Node::new(id, m))
+            .collect();
 
         Metadata(meta)
     }
@@ -94,6 +77,28 @@ pub struct Node {
     arguments: Vec<(CString, CString)>,
 }
 
+impl Node {
+    pub(crate) fn new(id: u32, meta: &NodeMetadata) -> Self {
+        let NodeMetadata {
+            kind, arguments, ..
+        } = meta;
+        let kind = CString::new(kind.as_str()).unwrap();
+        let mut args = Vec::new();
+
+        for (key, value) in arguments {
+            let key = CString::new(key.as_str()).unwrap();
+            let value = CString::new(value.as_str()).unwrap();
+            args.push((key, value));
+        }
+
+        Node {
+            id,
+            kind,
+            arguments: args,
+        }
+    }
+}
+
 /// Get the ID for this particular node.
 #[no_mangle]
 pub unsafe extern "C" fn rune_node_id(node: *const Node) -> u32 {
@@ -8,11 +8,11 @@ use hotg_rune_core::SerializableRecord;
 use hotg_rune_runtime::{LoadError, Runtime as RustRuntime};
 use log::Record;
 
-use crate::{Error, InputTensors, Metadata, OutputTensors};
+use crate::{Error, InputTensors, Metadata, Node, OutputTensors};
 
 /// A loaded Rune.
 pub struct Runtime {
-    inner: RustRuntime,
+    pub(crate) inner: RustRuntime,
 }
 
 impl Deref for Runtime {
@@ -150,7 +150,7 @@ pub unsafe extern "C" fn rune_runtime_load(
     }
 }
 
-fn load(wasm: &[u8]) -> Result<RustRuntime, LoadError> {
+pub(crate) fn load(wasm: &[u8]) -> Result<RustRuntime, LoadError> {
     cfg_if::cfg_if! {
         if #[cfg(feature = "wasmer")] {
             return RustRuntime::wasmer(wasm);
@@ -223,6 +223,133 @@ pub unsafe extern "C" fn rune_runtime_set_logger(
     runtime.set_logger(move |r| thunk.log(r));
 }
 
+/// A callback which fills `buffer` with data for a capability.
+///
+/// The callback should return `null` on success, or an `Error` (e.g. one
+/// created with `rune_error_new()`) if the data couldn't be read. The runtime
+/// takes ownership of any returned `Error`.
+pub type CapabilityCallback = unsafe extern "C" fn(
+    user_data: *mut c_void,
+    node: *const Node,
+    buffer: *mut u8,
+    buffer_len: c_int,
+) -> *mut Error;
+
+/// A callback which is given the data written to an output.
+///
+/// The callback should return `null` on success, or an `Error` (e.g. one
+/// created with `rune_error_new()`) if the data couldn't be handled. The
+/// runtime takes ownership of any returned `Error`.
+pub type OutputCallback = unsafe extern "C" fn(
+    user_data: *mut c_void,
+    node: *const Node,
+    data: *const u8,
+    data_len: c_int,
+) -> *mut Error;
+
+/// A callback plus the user data it was registered with.
+struct Thunk<F> {
+    callback: F,
+    user_data: *mut c_void,
+    destructor: Option<Destructor>,
+}
+
+impl<F> Drop for Thunk<F> {
+    fn drop(&mut self) {
+        if let Some(destructor) = self.destructor {
+            unsafe {
+                destructor(self.user_data);
+            }
+        }
+    }
+}
+
+// Safety: Ensured by the caller.
+unsafe impl<F> Send for Thunk<F> {}
+unsafe impl<F> Sync for Thunk<F> {}
+
+fn into_result(error: *mut Error) -> Result<(), anyhow::Error> {
+    if error.is_null() {
+        Ok(())
+    } else {
+        // Safety: the callback gave us ownership of the error.
+        Err(unsafe { Error::from_raw(error) })
+    }
+}
+
+/// Use a callback to provide data to capabilities instead of the input
+/// tensors.
+///
+/// The `destructor` (if provided) will be called on `user_data` when the
+/// callback is no longer needed.
+#[no_mangle]
+pub unsafe extern "C" fn rune_runtime_set_capability_callback(
+    runtime: *mut Runtime,
+    callback: CapabilityCallback,
+    user_data: *mut c_void,
+    destructor: Option<unsafe extern "C" fn(*mut c_void)>,
+) {
+    if runtime.is_null() {
+        return;
+    }
+
+    let runtime = &mut *runtime;
+    let thunk = Thunk {
+        callback,
+        user_data,
+        destructor,
+    };
+
+    runtime.set_capability_handler(move |id, meta, buffer| {
+        let node = Node::new(id, meta);
+        let error = (thunk.callback)(
+            thunk.user_data,
+            &node,
+            buffer.as_mut_ptr(),
+            buffer.len() as c_int,
+        );
+        into_result(error)?;
+
+        Ok(buffer.len())
+    });
+}
+
+/// Use a callback to receive the data written to outputs instead of saving it
+/// to the output tensors.
+///
+/// The `destructor` (if provided) will be called on `user_data` when the
+/// callback is no longer needed.
+#[no_mangle]
+pub unsafe extern "C" fn rune_runtime_set_output_callback(
+    runtime: *mut Runtime,
+    callback: OutputCallback,
+    user_data: *mut c_void,
+    destructor: Option<unsafe extern "C" fn(*mut c_void)>,
+) {
+    if runtime.is_null() {
+        return;
+    }
+
+    let runtime = &mut *runtime;
+    let thunk = Thunk {
+        callback,
+        user_data,
+        destructor,
+    };
+
+    runtime.set_output_handler(move |id, meta, data| {
+        let node = Node::new(id, meta);
+        let error = (thunk.callback)(
+            thunk.user_data,
+            &node,
+            data.as_ptr(),
+            data.len() as c_int,
+        );
+
+        into_result(error)
+    });
+}
+
 /// The WebAssembly edngine to use when running a Rune.
 #[derive(Debug, Copy, Clone, PartialEq)]
 #[repr(u32)]
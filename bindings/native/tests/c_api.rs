@@ -1,5 +1,9 @@
 use std::{
-    ffi::CStr, os::raw::c_int, path::Path, process::Command, ptr, slice,
+    ffi::CStr,
+    os::raw::{c_int, c_void},
+    path::Path,
+    process::Command,
+    ptr, slice,
 };
 
 use hotg_rune_runtime::ElementType;
@@ -220,3 +224,62 @@ fn run_the_sine_rune() {
         rune_runtime_free(runtime);
     }
 }
+
+#[test]
+fn run_the_sine_rune_with_callbacks() {
+    unsafe extern "C" fn read_capability(
+        _user_data: *mut c_void,
+        node: *const Node,
+        buffer: *mut u8,
+        buffer_len: c_int,
+    ) -> *mut Error {
+        assert_eq!(rune_node_id(node), 1);
+        let buffer = slice::from_raw_parts_mut(buffer, buffer_len as usize);
+        buffer.copy_from_slice(&1.0_f32.to_le_bytes());
+
+        ptr::null_mut()
+    }
+
+    unsafe extern "C" fn write_output(
+        user_data: *mut c_void,
+        node: *const Node,
+        _data: *const u8,
+        _data_len: c_int,
+    ) -> *mut Error {
+        assert_eq!(rune_node_id(node), 3);
+        *user_data.cast::<bool>() = true;
+
+        ptr::null_mut()
+    }
+
+    unsafe {
+        let mut runtime: *mut Runtime = ptr::null_mut();
+        let cfg = Config {
+            rune: SINE_RUNE.as_ptr(),
+            rune_len: SINE_RUNE.len() as c_int,
+        };
+
+        let error = rune_runtime_load(&cfg, &mut runtime);
+        assert!(error.is_null());
+
+        let mut output_written = false;
+        rune_runtime_set_capability_callback(
+            runtime,
+            read_capability,
+            ptr::null_mut(),
+            None,
+        );
+        rune_runtime_set_output_callback(
+            runtime,
+            write_output,
+            (&mut output_written as *mut bool).cast(),
+            None,
+        );
+
+        let error = rune_runtime_predict(runtime);
+        assert!(error.is_null());
+        assert!(output_written);
+
+        rune_runtime_free(runtime);
+    }
+}
@@ -0,0 +1,28 @@
+//! Python bindings to the Rune runtime.
+
+mod runtime;
+mod tensors;
+
+use pyo3::{create_exception, exceptions::PyException, prelude::*};
+
+pub use crate::runtime::{Node, Runtime};
+
+create_exception!(
+    rune,
+    RuneError,
+    PyException,
+    "An error raised while loading or running a Rune."
+);
+
+pub(crate) fn rune_error(e: impl Into<anyhow::Error>) -> PyErr {
+    RuneError::new_err(format!("{:?}", e.into()))
+}
+
+#[pymodule]
+fn rune(py: Python<'_>, m: &PyModule) -> PyResult<()> {
+    m.add_class::<Runtime>()?;
+    m.add_class::<Node>()?;
+    m.add("RuneError", py.get_type::<RuneError>())?;
+
+    Ok(())
+}
@@ -0,0 +1,142 @@
+use std::collections::HashMap;
+
+use hotg_rune_runtime::NodeMetadata;
+use pyo3::{
+    exceptions::{PyKeyError, PyValueError},
+    prelude::*,
+};
+
+use crate::{rune_error, tensors};
+
+/// A loaded Rune.
+#[pyclass(unsendable, module = "rune")]
+pub struct Runtime {
+    inner: hotg_rune_runtime::Runtime,
+}
+
+#[pymethods]
+impl Runtime {
+    /// Load a Rune from its raw bytes, optionally specifying which
+    /// WebAssembly engine to use (`"wasm3"` or `"wasmer"`).
+    #[staticmethod]
+    #[args(engine = "\"wasm3\"")]
+    fn load(rune: &[u8], engine: &str) -> PyResult<Self> {
+        let inner = match engine {
+            #[cfg(feature = "wasm3")]
+            "wasm3" => hotg_rune_runtime::Runtime::wasm3(rune),
+            #[cfg(feature = "wasmer")]
+            "wasmer" => hotg_rune_runtime::Runtime::wasmer(rune),
+            other => {
+                return Err(PyValueError::new_err(format!(
+                    "Unsupported engine, \"{}\"",
+                    other
+                )))
+            },
+        };
+
+        let inner = inner.map_err(rune_error)?;
+
+        Ok(Runtime { inner })
+    }
+
+    /// Metadata for each of the Rune's capabilities, keyed by ID.
+    #[getter]
+    fn capabilities(&self) -> HashMap<u32, Node> {
+        nodes(self.inner.capabilities())
+    }
+
+    /// Metadata for each of the Rune's outputs, keyed by ID.
+    #[getter]
+    fn outputs(&self) -> HashMap<u32, Node> { nodes(self.inner.outputs()) }
+
+    /// Set the data a capability will provide the next time the Rune is run.
+    fn set_input(&mut self, capability_id: u32, array: &PyAny) -> PyResult<()> {
+        if !self.inner.capabilities().contains_key(&capability_id) {
+            return Err(PyKeyError::new_err(format!(
+                "The Rune doesn't have a capability with ID {}",
+                capability_id
+            )));
+        }
+
+        let tensor = tensors::from_numpy(array)?;
+        self.inner.input_tensors().insert(capability_id, tensor);
+
+        Ok(())
+    }
+
+    /// Run the Rune.
+    fn predict(&mut self) -> PyResult<()> {
+        self.inner.predict().map_err(rune_error)
+    }
+
+    /// The tensors each output received the last time the Rune was run,
+    /// keyed by output ID.
+    fn output_tensors(
+        &self,
+        py: Python<'_>,
+    ) -> PyResult<HashMap<u32, Vec<PyObject>>> {
+        self.inner
+            .output_tensors()
+            .iter()
+            .map(|(&id, tensors)| {
+                let arrays = tensors
+                    .iter()
+                    .map(|t| tensors::to_numpy(py, t))
+                    .collect::<PyResult<Vec<_>>>()?;
+                Ok((id, arrays))
+            })
+            .collect()
+    }
+}
+
+fn nodes(metadata: &HashMap<u32, NodeMetadata>) -> HashMap<u32, Node> {
+    metadata
+        .iter()
+        .map(|(&id, meta)| (id, Node::new(id, meta)))
+        .collect()
+}
+
+/// A capability or output in the Rune's pipeline.
+#[pyclass(module = "rune")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Node {
+    #[pyo3(get)]
+    id: u32,
+    #[pyo3(get)]
+    kind: String,
+    #[pyo3(get)]
+    arguments: HashMap<String, String>,
+}
+
+impl Node {
+    fn new(id: u32, meta: &NodeMetadata) -> Self {
+        let NodeMetadata {
+            kind, arguments, ..
+        } = meta;
+
+        Node {
+            id,
+            kind: kind.clone(),
+            arguments: arguments.clone(),
+        }
+    }
+}
+
+#[pymethods]
+impl Node {
+    fn __repr__(&self) -> String {
+        let mut arguments: Vec<_> = self.arguments.iter().collect();
+        arguments.sort();
+        let arguments: Vec<_> = arguments
+            .into_iter()
+            .map(|(k, v)| format!("{:?}: {:?}", k, v))
+            .collect();
+
+        format!(
+            "Node(id={}, kind={:?}, arguments={{{}}})",
+            self.id,
+            self.kind,
+            arguments.join(", ")
+        )
+    }
+}
@@ -0,0 +1,152 @@
+//! Conversions between NumPy arrays and the runtime's tensors.
+
+use hotg_rune_runtime::{ElementType, OutputTensor, Tensor, TensorElement};
+use numpy::{Element, PyArray1, PyReadonlyArrayDyn};
+use pyo3::{
+    exceptions::{PyTypeError, PyValueError},
+    prelude::*,
+};
+
+/// Copy a NumPy array into a [`Tensor`].
+pub(crate) fn from_numpy(array: &PyAny) -> PyResult<Tensor> {
+    macro_rules! try_extract {
+        ($($ty:ty),* $(,)?) => {
+            $(
+                let extracted = array.extract::<PyReadonlyArrayDyn<'_, $ty>>();
+                if let Ok(array) = extracted {
+                    return to_tensor(array);
+                }
+            )*
+        };
+    }
+
+    try_extract!(u8, i8, u16, i16, u32, i32, f32, u64, i64, f64);
+
+    Err(PyTypeError::new_err(format!(
+        "Expected a NumPy array with a numeric element type, found {}",
+        array.repr()?
+    )))
+}
+
+fn to_tensor<E>(array: PyReadonlyArrayDyn<'_, E>) -> PyResult<Tensor>
+where
+    E: TensorElement + Element,
+{
+    let dimensions = array.shape().to_vec();
+
+    if dimensions.contains(&0) {
+        return Err(PyValueError::new_err(format!(
+            "All dimensions must be non-zero, but the array has shape {:?}",
+            dimensions
+        )));
+    }
+
+    let tensor = match array.as_slice() {
+        Ok(elements) => Tensor::new(elements, &dimensions),
+        Err(_) => {
+            // Non-contiguous arrays (e.g. slices with a stride) need to be
+            // copied into a contiguous buffer first.
+            let elements: Vec<E> = array.as_array().iter().copied().collect();
+            Tensor::new(&elements, &dimensions)
+        },
+    };
+
+    Ok(tensor)
+}
+
+/// Copy an [`OutputTensor`] into a new NumPy array.
+pub(crate) fn to_numpy(
+    py: Python<'_>,
+    tensor: &OutputTensor,
+) -> PyResult<PyObject> {
+    match tensor {
+        OutputTensor::Tensor(tensor) => tensor_to_numpy(py, tensor),
+        OutputTensor::StringTensor {
+            dimensions,
+            strings,
+        } => {
+            let array = py
+                .import("numpy")?
+                .getattr("array")?
+                .call1((strings.clone(),))?
+                .call_method1("reshape", (dimensions.clone(),))?;
+
+            Ok(array.into_py(py))
+        },
+    }
+}
+
+fn tensor_to_numpy(py: Python<'_>, tensor: &Tensor) -> PyResult<PyObject> {
+    let dimensions: Vec<usize> =
+        tensor.dimensions().iter().map(|d| d.get()).collect();
+
+    macro_rules! convert {
+        ($ty:ty) => {{
+            let elements = tensor
+                .elements::<$ty>()
+                .expect("The element type was already checked");
+            let array =
+                PyArray1::from_slice(py, elements).reshape(dimensions)?;
+            Ok(array.into_py(py))
+        }};
+    }
+
+    match tensor.element_type() {
+        ElementType::U8 => convert!(u8),
+        ElementType::I8 => convert!(i8),
+        ElementType::U16 => convert!(u16),
+        ElementType::I16 => convert!(i16),
+        ElementType::U32 => convert!(u32),
+        ElementType::I32 => convert!(i32),
+        ElementType::F32 => convert!(f32),
+        ElementType::U64 => convert!(u64),
+        ElementType::I64 => convert!(i64),
+        ElementType::F64 => convert!(f64),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use numpy::PyArrayDyn;
+
+    use super::*;
+
+    #[test]
+    fn round_trip_a_2d_array() {
+        pyo3::prepare_freethreaded_python();
+
+        Python::with_gil(|py| {
+            let array = PyArray1::from_slice(py, &[1.0_f32, 2.0, 3.0, 4.0])
+                .reshape([2, 2])
+                .unwrap();
+
+            let tensor = from_numpy(array).unwrap();
+
+            assert_eq!(tensor.element_type(), ElementType::F32);
+            assert_eq!(tensor.shape().to_string(), "f32[2, 2]");
+
+            let round_tripped =
+                to_numpy(py, &OutputTensor::Tensor(tensor)).unwrap();
+            let round_tripped: &PyArrayDyn<f32> =
+                round_tripped.extract(py).unwrap();
+            assert_eq!(
+                round_tripped.readonly().as_slice().unwrap(),
+                &[1.0, 2.0, 3.0, 4.0]
+            );
+            assert_eq!(round_tripped.shape(), &[2, 2]);
+        });
+    }
+
+    #[test]
+    fn non_numeric_arrays_are_rejected() {
+        pyo3::prepare_freethreaded_python();
+
+        Python::with_gil(|py| {
+            let strings = vec!["a", "b"].into_py(py);
+
+            let err = from_numpy(strings.as_ref(py)).unwrap_err();
+
+            assert!(err.is_instance_of::<PyTypeError>(py));
+        });
+    }
+}
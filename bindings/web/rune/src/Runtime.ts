@@ -12,7 +12,11 @@ export interface Output {
   */
 export interface Capability {
     generate(dest: Uint8Array): void;
-    setParameter(name: string, value: number): void;
+    /**
+     * Set a parameter. Lists and maps (e.g. `[0.485,0.456,0.406]`) are passed
+     * through as strings.
+     */
+    setParameter(name: string, value: number | string): void;
 }
 
 /**
@@ -207,6 +211,23 @@ function importsToHostFunctions(
             capability.setParameter(key, value);
         },
 
+        rune_capability_set_string_param(id: number,
+            keyPtr: number,
+            keyLength: number,
+            valuePtr: number,
+            valueLength: number) {
+            const key = decoder.decode(memory().subarray(keyPtr, keyPtr + keyLength));
+            const value = decoder.decode(memory().subarray(valuePtr, valuePtr + valueLength));
+
+            const capability = capabilities[id];
+
+            if (!capability) {
+                throw new Error(`Tried to set "${key}" to ${value} but capability ${id} doesn't exist`);
+            }
+
+            capability.setParameter(key, value);
+        },
+
         request_provider_response(buffer: number, len: number, id: number) {
             const cap = capabilities[id];
             if (!cap) {
@@ -14,6 +14,17 @@ export const Capabilities = {
     "image": 4,
     "raw": 5,
     "float-image": 6,
+    "gps": 7,
+    "gyro": 8,
+    "thermometer": 9,
+    "battery": 10,
+    "barometer": 11,
+    "proximity": 12,
+    "light": 13,
+    "video": 14,
+    "tensor": 15,
+    "tabular": 16,
+    "signal": 17,
 } as const;
 
 /**
@@ -21,7 +32,9 @@ export const Capabilities = {
  */
 export const Outputs = {
     "serial": 1,
+    "ble": 2,
     "tensor": 5,
+    "mqtt": 6,
 } as const;
 
 /**
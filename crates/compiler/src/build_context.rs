@@ -21,6 +21,10 @@ pub struct BuildContext {
     pub verbosity: Verbosity,
     /// The version of Rune being used.
     pub rune_version: Option<RuneVersion>,
+    /// The directory containing proc blocks installed with `rune install`,
+    /// used to resolve proc blocks referenced by a short name.
+    #[serde(default)]
+    pub install_dir: Option<PathBuf>,
 }
 
 impl BuildContext {
@@ -56,6 +60,7 @@ impl BuildContext {
             rune_version: Some(RuneVersion {
                 version: env!("CARGO_PKG_VERSION").to_string(),
             }),
+            install_dir: Some(crate::registry::default_install_dir()),
         })
     }
 
@@ -71,6 +76,7 @@ impl BuildContext {
             rune_version: Some(RuneVersion {
                 version: env!("CARGO_PKG_VERSION").to_string(),
             }),
+            install_dir: None,
         }
     }
 }
@@ -10,7 +10,7 @@ use hotg_rune_core::Shape;
 use serde::Serialize;
 
 use crate::{
-    lowering::{Name, Resource, SinkKind, SourceKind},
+    lowering::{ModelMetadata, Name, Resource, SinkKind, SourceKind},
     parse::{Path, ResourceOrString},
 };
 
@@ -138,6 +138,9 @@ pub struct ModelSummary {
     pub args: HashMap<String, ResourceOrString>,
     pub inputs: Vec<TensorId>,
     pub outputs: Vec<TensorId>,
+    /// Metadata embedded in the model itself.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<ModelMetadata>,
 }
 
 #[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
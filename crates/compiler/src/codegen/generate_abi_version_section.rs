@@ -0,0 +1,30 @@
+use legion::systems::CommandBuffer;
+
+use crate::codegen::CustomSection;
+
+/// Embed the [`hotg_rune_core::ABI_VERSION`] this Rune was compiled against
+/// so the runtime can check it is compatible.
+#[legion::system]
+pub(crate) fn run(cmd: &mut CommandBuffer) {
+    cmd.push((abi_version_section(),));
+}
+
+fn abi_version_section() -> CustomSection {
+    CustomSection::new(
+        hotg_rune_core::ABI_VERSION_CUSTOM_SECTION,
+        hotg_rune_core::ABI_VERSION.to_le_bytes().to_vec(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn embed_the_current_abi_version() {
+        let section = abi_version_section();
+
+        assert_eq!(section.section_name, ".rune_abi_version");
+        assert_eq!(&*section.value, &hotg_rune_core::ABI_VERSION.to_le_bytes());
+    }
+}
@@ -1,4 +1,7 @@
-use std::{collections::BTreeMap, path::Path};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
 
 use cargo_toml::{
     Badges, Dependency, DependencyDetail, DepsSet, Edition, FeatureSet,
@@ -8,7 +11,11 @@ use cargo_toml::{
 use legion::{systems::CommandBuffer, world::SubWorld, Query};
 
 use crate::{
-    codegen::File, lowering::ProcBlock, parse, BuildContext, FeatureFlags,
+    codegen::File,
+    lowering::{ProcBlock, ProcBlockLocation},
+    parse,
+    registry::{self, Installed},
+    BuildContext, FeatureFlags,
 };
 
 /// Generate a `Cargo.toml` file which includes all the relevant dependencies
@@ -43,10 +50,28 @@ pub(crate) fn run(
         );
     }
 
-    let proc_blocks = query.iter(world);
+    // Proc blocks provided by the host aren't compiled into the Rune
+    let embedded = |p: &&ProcBlock| p.location == ProcBlockLocation::Embedded;
+
+    let proc_blocks = query.iter(world).filter(embedded);
     let mut manifest =
         generate_manifest(proc_blocks, &ctx.name, &ctx.current_directory);
 
+    if let Some(install_dir) = ctx.install_dir.as_deref() {
+        match Installed::load(install_dir) {
+            Ok(installed) => use_installed_proc_blocks(
+                query.iter(world).filter(embedded),
+                &installed,
+                &mut manifest,
+            ),
+            Err(e) => log::warn!(
+                "Unable to read the proc blocks installed in \"{}\": {}",
+                install_dir.display(),
+                e
+            ),
+        }
+    }
+
     if let Some(hotg_repo_dir) = features.rune_repo_dir.as_deref() {
         patch_hotg_dependencies(hotg_repo_dir, &mut manifest);
     }
@@ -140,8 +165,16 @@ where
     );
 
     for proc_block in proc_blocks {
-        let dep = proc_block_dependency(&proc_block.path, current_dir);
         let name = proc_block.name();
+        let dep = if proc_block.is_python() {
+            // The generated crate is placed inside the project
+            DependencyDetail {
+                path: Some(format!("proc_blocks/{}", name)),
+                ..empty_dependency_detail()
+            }
+        } else {
+            proc_block_dependency(&proc_block.path, current_dir)
+        };
         deps.insert(name.to_string(), Dependency::Detailed(dep));
     }
 
@@ -259,6 +292,44 @@ fn path_dependency(path: impl AsRef<Path>) -> Dependency {
     })
 }
 
+/// Replace any proc blocks which were referenced by a short name (e.g.
+/// `hotg-ai/mfcc@^0.11`) with the matching copy installed by `rune install`.
+fn use_installed_proc_blocks<'rune, I>(
+    proc_blocks: I,
+    installed: &Installed,
+    manifest: &mut Manifest,
+) where
+    I: IntoIterator<Item = &'rune ProcBlock> + 'rune,
+{
+    for proc_block in proc_blocks {
+        let path = &proc_block.path;
+
+        if path.sub_path.is_some() || path.base.starts_with('.') {
+            continue;
+        }
+
+        let requirement =
+            match registry::parse_requirement(path.version.as_deref()) {
+                Ok(req) => req,
+                // Probably a git branch or commit hash
+                Err(_) => continue,
+            };
+
+        if let Some(found) = installed.find(&path.base, &requirement) {
+            log::debug!(
+                "Using version {} of \"{}\" from \"{}\"",
+                found.version,
+                path.base,
+                found.path.display()
+            );
+            manifest.dependencies.insert(
+                proc_block.name().to_string(),
+                path_dependency(&found.path),
+            );
+        }
+    }
+}
+
 fn patch_hotg_dependencies(hotg_repo_dir: &Path, manifest: &mut Manifest) {
     let mut overrides = BTreeMap::new();
 
@@ -300,6 +371,41 @@ fn patch_hotg_dependencies(hotg_repo_dir: &Path, manifest: &mut Manifest) {
     //
     // If so, just override with the patched version.
     manifest.dependencies.extend(overrides);
+
+    // Builtin proc-blocks live in the Rune repo, so use the local copies
+    for (name, dependency) in &mut manifest.dependencies {
+        if let Some(dir) = builtin_proc_block(hotg_repo_dir, name, dependency) {
+            *dependency = path_dependency(dir);
+        }
+    }
+}
+
+/// If this dependency was pulled from the `proc-blocks/` directory in the
+/// Rune repo (e.g. `hotg-ai/rune#proc-blocks/spectrogram`), find the local
+/// copy.
+///
+/// Only crates in `proc-blocks/` are used, so anything else from the Rune
+/// repo (or a proc block which doesn't exist) is left alone and reported by
+/// cargo.
+fn builtin_proc_block(
+    hotg_repo_dir: &Path,
+    name: &str,
+    dependency: &Dependency,
+) -> Option<PathBuf> {
+    match dependency {
+        Dependency::Detailed(DependencyDetail {
+            git: Some(repo), ..
+        }) if repo == "https://github.com/hotg-ai/rune.git" => {},
+        _ => return None,
+    }
+
+    let dir = hotg_repo_dir.join("proc-blocks").join(name);
+    let manifest = Manifest::from_path(dir.join("Cargo.toml")).ok()?;
+
+    match manifest.package {
+        Some(package) if package.name == name => Some(dir),
+        _ => None,
+    }
 }
 
 #[cfg(test)]
@@ -331,6 +437,23 @@ mod tests {
         );
     }
 
+    #[test]
+    fn python_proc_blocks_use_the_generated_crate() {
+        let proc_block = ProcBlock {
+            path: "./scale.py".parse().unwrap(),
+            parameters: Default::default(),
+            location: ProcBlockLocation::Embedded,
+        };
+        let should_be = Dependency::Detailed(DependencyDetail {
+            path: Some("proc_blocks/scale".to_string()),
+            ..empty_dependency_detail()
+        });
+
+        let got = dependencies(vec![&proc_block], Path::new("/project"));
+
+        assert_eq!(got["scale"].clone(), should_be);
+    }
+
     #[test]
     fn proc_block_from_crates_io() {
         let path = "whatever@1.2".parse().unwrap();
@@ -360,6 +483,110 @@ mod tests {
         assert_eq!(got, should_be);
     }
 
+    #[test]
+    fn builtin_proc_blocks_use_the_rune_repo_dir() {
+        let repo_root = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .parent()
+            .and_then(Path::parent)
+            .unwrap();
+        let proc_blocks: Vec<_> = [
+            "hotg-ai/rune#proc-blocks/spectrogram",
+            "hotg-ai/rune#proc-blocks/does-not-exist",
+            "hotg-ai/rune#crates/rune-core",
+        ]
+        .iter()
+        .map(|path| ProcBlock {
+            path: path.parse().unwrap(),
+            parameters: Default::default(),
+            location: ProcBlockLocation::Embedded,
+        })
+        .collect();
+        let mut manifest =
+            generate_manifest(&proc_blocks, "foo", Path::new("."));
+
+        patch_hotg_dependencies(repo_root, &mut manifest);
+
+        assert_eq!(
+            manifest.dependencies["spectrogram"],
+            path_dependency(repo_root.join("proc-blocks").join("spectrogram"))
+        );
+        // Only crates which are actually in proc-blocks/ get patched
+        let from_git = Dependency::Detailed(DependencyDetail {
+            git: Some("https://github.com/hotg-ai/rune.git".to_string()),
+            ..empty_dependency_detail()
+        });
+        assert_eq!(manifest.dependencies["does-not-exist"], from_git);
+        assert_eq!(manifest.dependencies["rune-core"], from_git);
+    }
+
+    #[test]
+    fn short_names_use_installed_proc_blocks() {
+        let mut installed = Installed::default();
+        installed.insert(
+            "hotg-ai/mfcc",
+            registry::InstalledProcBlock {
+                version: registry::Version::new(0, 11, 3),
+                path: "/cache/mfcc".into(),
+            },
+        );
+        let proc_blocks = vec![
+            ProcBlock {
+                path: "hotg-ai/mfcc@^0.11".parse().unwrap(),
+                parameters: Default::default(),
+                location: ProcBlockLocation::Embedded,
+            },
+            ProcBlock {
+                path: "hotg-ai/normalize@^0.11".parse().unwrap(),
+                parameters: Default::default(),
+                location: ProcBlockLocation::Embedded,
+            },
+        ];
+        let mut manifest =
+            generate_manifest(&proc_blocks, "foo", Path::new("."));
+
+        use_installed_proc_blocks(&proc_blocks, &installed, &mut manifest);
+
+        assert_eq!(
+            manifest.dependencies["mfcc"],
+            path_dependency("/cache/mfcc")
+        );
+        // Proc blocks which aren't installed fall back to using git
+        assert_eq!(
+            manifest.dependencies["normalize"],
+            Dependency::Detailed(DependencyDetail {
+                git: Some(
+                    "https://github.com/hotg-ai/normalize.git".to_string()
+                ),
+                rev: Some("^0.11".to_string()),
+                ..empty_dependency_detail()
+            })
+        );
+    }
+
+    #[test]
+    fn installed_versions_must_match() {
+        let mut installed = Installed::default();
+        installed.insert(
+            "hotg-ai/mfcc",
+            registry::InstalledProcBlock {
+                version: registry::Version::new(0, 10, 0),
+                path: "/cache/mfcc".into(),
+            },
+        );
+        let proc_blocks = vec![ProcBlock {
+            path: "hotg-ai/mfcc@0.11".parse().unwrap(),
+            parameters: Default::default(),
+            location: ProcBlockLocation::Embedded,
+        }];
+        let mut manifest =
+            generate_manifest(&proc_blocks, "foo", Path::new("."));
+        let original = manifest.dependencies["mfcc"].clone();
+
+        use_installed_proc_blocks(&proc_blocks, &installed, &mut manifest);
+
+        assert_eq!(manifest.dependencies["mfcc"], original);
+    }
+
     #[test]
     fn manifest_generates_cdylib() {
         let got = generate_manifest(Vec::new(), "foo", Path::new("."));
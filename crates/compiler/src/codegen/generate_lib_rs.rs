@@ -7,11 +7,11 @@ use proc_macro2::{Ident, Literal, Span, TokenStream};
 use quote::{quote, ToTokens};
 
 use crate::{
-    codegen::{CustomSection, File},
+    codegen::{generate_model_files::variant_file_name, CustomSection, File},
     lowering::{
-        Inputs, Mimetype, Model, ModelFile, Name, Outputs, PipelineNode,
-        ProcBlock, Resource, ResourceData, ResourceOrString, Sink, SinkKind,
-        Source, Tensor,
+        Inputs, Mimetype, Model, ModelFile, ModelLocation, Name, Outputs,
+        PipelineNode, ProcBlock, ProcBlockLocation, Resource, ResourceData,
+        ResourceOrString, Sink, SinkKind, Source, Tensor,
     },
     parse::ResourceType,
 };
@@ -34,6 +34,7 @@ pub(crate) fn run(
     resources: &mut Query<(&Name, &Resource, Option<&ResourceData>)>,
     capabilities: &mut Query<(&Name, &Source, &Outputs)>,
     proc_blocks: &mut Query<(&Name, &ProcBlock)>,
+    proc_block_entities: &mut Query<(Entity, &ProcBlock)>,
     outputs: &mut Query<(&Name, &Sink)>,
     pipeline_nodes: &mut Query<(
         Entity,
@@ -48,6 +49,11 @@ pub(crate) fn run(
     let resources: Vec<_> = resources.iter(world).collect();
     let capabilities: Vec<_> = capabilities.iter(world).collect();
     let proc_blocks: Vec<_> = proc_blocks.iter(world).collect();
+    let embedded_proc_blocks: HashSet<Entity> = proc_block_entities
+        .iter(world)
+        .filter(|(_, p)| p.location == ProcBlockLocation::Embedded)
+        .map(|(&ent, _)| ent)
+        .collect();
     let outputs: Vec<_> = outputs.iter(world).collect();
     let pipeline_nodes: Vec<_> = pipeline_nodes.iter(world).collect();
     let tensors: Vec<_> = tensors.iter(world).collect();
@@ -58,6 +64,7 @@ pub(crate) fn run(
         &resources,
         &capabilities,
         &proc_blocks,
+        &embedded_proc_blocks,
         &outputs,
         &pipeline_nodes,
         &tensors,
@@ -81,6 +88,7 @@ fn generate_lib_rs<'world>(
     resources: &[(&Name, &Resource, Option<&ResourceData>)],
     capabilities: &[(&Name, &Source, &Outputs)],
     proc_blocks: &[(&Name, &ProcBlock)],
+    embedded_proc_blocks: &HashSet<Entity>,
     outputs: &[(&Name, &Sink)],
     pipeline_nodes: &[Node<'_>],
     tensors: &[(&Entity, &Tensor, Option<&Inputs>, Option<&Outputs>)],
@@ -98,6 +106,7 @@ fn generate_lib_rs<'world>(
         models,
         capabilities,
         proc_blocks,
+        embedded_proc_blocks,
         outputs,
         pipeline_nodes,
         tensors,
@@ -105,6 +114,7 @@ fn generate_lib_rs<'world>(
         &mut get_tensor,
     );
     let call = generate_call_function();
+    let reset = generate_reset_function();
 
     quote! {
         #prelude
@@ -113,16 +123,21 @@ fn generate_lib_rs<'world>(
         #models_module
         #manifest
         #call
+        #reset
     }
 }
 
 /// Generate a `manifest()` function that initializes the various nodes in
 /// our pipeline then turns it into a closure that gets stored in the
 /// `PIPELINE` static variable.
+///
+/// The closure accepts a `reset` flag which tells it to reset each proc
+/// block's state instead of running the pipeline.
 fn generate_manifest_function<'world, F, T>(
     models: &[(&Name, &Model, &Mimetype, &Inputs, &Outputs)],
     capabilities: &[(&Name, &Source, &Outputs)],
     proc_blocks: &[(&Name, &ProcBlock)],
+    embedded_proc_blocks: &HashSet<Entity>,
     outputs: &[(&Name, &Sink)],
     pipeline_nodes: &[Node<'_>],
     tensors: &[(&Entity, &Tensor, Option<&Inputs>, Option<&Outputs>)],
@@ -135,6 +150,10 @@ where
 {
     let capabilities =
         initialize_capabilities(capabilities, get_tensor, get_name);
+    let proc_block_names: Vec<_> = proc_blocks
+        .iter()
+        .map(|(name, _)| Ident::new(name, Span::call_site()))
+        .collect();
     let proc_blocks = initialize_proc_blocks(proc_blocks, get_name);
     let models: TokenStream = models
         .iter()
@@ -143,7 +162,8 @@ where
         })
         .collect();
     let outputs = initialize_outputs(outputs);
-    let pipeline = execute_pipeline(pipeline_nodes, tensors);
+    let pipeline =
+        execute_pipeline(pipeline_nodes, tensors, embedded_proc_blocks);
 
     quote! {
         #[no_mangle]
@@ -154,7 +174,12 @@ where
             #models
             #outputs
 
-            let pipeline = move || {
+            let pipeline = move |reset: bool| {
+                if reset {
+                    #( #proc_block_names.reset(); )*
+                    return;
+                }
+
                 let _guard = hotg_runicos_base_wasm::PipelineGuard::default();
                 #pipeline
             };
@@ -177,6 +202,7 @@ fn execute_pipeline(
         &PipelineNode,
     )],
     tensors: &[(&Entity, &Tensor, Option<&Inputs>, Option<&Outputs>)],
+    embedded_proc_blocks: &HashSet<Entity>,
 ) -> TokenStream {
     let ExecutionOrder {
         order,
@@ -185,19 +211,123 @@ fn execute_pipeline(
         ..
     } = ExecutionOrder::calculate(pipeline_nodes, tensors);
 
-    order
+    chain_proc_blocks(&order, &pipeline_nodes, tensors, embedded_proc_blocks)
         .iter()
-        .map(|entity| {
-            execute_pipeline_node(
-                entity,
-                &pipeline_nodes,
-                &tensor_names,
-                tensors,
-            )
+        .map(|step| {
+            let body = match step.as_slice() {
+                [entity] => execute_pipeline_node(
+                    entity,
+                    &pipeline_nodes,
+                    &tensor_names,
+                    tensors,
+                ),
+                chain => execute_proc_block_chain(
+                    chain,
+                    &pipeline_nodes,
+                    &tensor_names,
+                    tensors,
+                ),
+            };
+
+            time_step(step, &pipeline_nodes, body)
         })
         .collect()
 }
 
+/// Let the runtime time how long a step takes.
+///
+/// Proc blocks in a chain may be fused into a single loop, so a chain is
+/// timed as a whole under each of its names joined by a `+`.
+fn time_step(
+    step: &[Entity],
+    pipeline_nodes: &HashMap<
+        Entity,
+        (&Name, Option<&Inputs>, Option<&Outputs>),
+    >,
+    body: TokenStream,
+) -> TokenStream {
+    let names: Vec<_> = step
+        .iter()
+        .map(|ent| pipeline_nodes[ent].0.as_str())
+        .collect();
+    let stage = names.join("+");
+
+    quote! {
+        hotg_runicos_base_wasm::time::stage_started(#stage);
+        #body
+        hotg_runicos_base_wasm::time::stage_finished(#stage);
+    }
+}
+
+/// Group the pipeline nodes into the steps used to execute them, where a
+/// chain of proc blocks compiled into the Rune becomes a single step.
+///
+/// A proc block can only be added to a chain when it has one input and one
+/// output, and the tensor it reads isn't used by anything else. Otherwise the
+/// intermediate tensor would still need to be created.
+///
+/// We can't tell which proc blocks are elementwise until the Rune is
+/// compiled, so the generated code decides whether a chain can actually be
+/// fused (see [`execute_proc_block_chain()`]).
+///
+/// The chain is executed at the position of its first proc block. That's
+/// okay because every later proc block in the chain only depends on the
+/// proc block before it.
+fn chain_proc_blocks(
+    order: &[Entity],
+    pipeline_nodes: &HashMap<
+        Entity,
+        (&Name, Option<&Inputs>, Option<&Outputs>),
+    >,
+    tensors: &[(&Entity, &Tensor, Option<&Inputs>, Option<&Outputs>)],
+    embedded_proc_blocks: &HashSet<Entity>,
+) -> Vec<Vec<Entity>> {
+    let consumers: HashMap<Entity, usize> = tensors
+        .iter()
+        .copied()
+        .map(|(ent, _, _, outputs)| {
+            (*ent, outputs.map(|o| o.tensors.len()).unwrap_or_default())
+        })
+        .collect();
+
+    let mut steps: Vec<Vec<Entity>> = Vec::new();
+    // Chains which can still be extended, keyed by their output tensor
+    let mut open_chains: HashMap<Entity, usize> = HashMap::new();
+
+    for &node in order {
+        let (input, output) = match pipeline_nodes[&node] {
+            (_, Some(inputs), Some(outputs))
+                if embedded_proc_blocks.contains(&node)
+                    && inputs.tensors.len() == 1
+                    && outputs.tensors.len() == 1 =>
+            {
+                (inputs.tensors[0], outputs.tensors[0])
+            },
+            _ => {
+                steps.push(vec![node]);
+                continue;
+            },
+        };
+
+        let step = match open_chains.remove(&input) {
+            Some(step) => {
+                steps[step].push(node);
+                step
+            },
+            None => {
+                steps.push(vec![node]);
+                steps.len() - 1
+            },
+        };
+
+        if consumers.get(&output).copied() == Some(1) {
+            open_chains.insert(output, step);
+        }
+    }
+
+    steps
+}
+
 fn execute_pipeline_node(
     node: &Entity,
     pipeline_nodes: &HashMap<
@@ -233,11 +363,62 @@ fn execute_pipeline_node(
     }
 }
 
+/// Run a chain of proc blocks, passing each element through every proc block
+/// in a single loop if they all implement `TransformElement`.
+///
+/// This uses the `hotg_rune_proc_blocks::fusion` module, which falls back to
+/// calling each proc block's `transform()` when the chain can't be fused.
+fn execute_proc_block_chain(
+    chain: &[Entity],
+    pipeline_nodes: &HashMap<
+        Entity,
+        (&Name, Option<&Inputs>, Option<&Outputs>),
+    >,
+    tensor_names: &HashMap<Entity, Ident>,
+    tensors: &[(&Entity, &Tensor, Option<&Inputs>, Option<&Outputs>)],
+) -> TokenStream {
+    let nodes: Vec<_> = chain.iter().map(|ent| pipeline_nodes[ent]).collect();
+
+    let (_, first_inputs, _) = nodes[0];
+    let (_, _, last_outputs) = nodes[nodes.len() - 1];
+    let inputs = first_inputs.expect("Chained proc blocks have inputs");
+    let outputs = last_outputs.expect("Chained proc blocks have outputs");
+
+    let input = &tensor_names[&inputs.tensors[0]];
+    let output_type = tensor_types(&outputs.tensors, tensors);
+    let output = tensor_name_or_tuple(&outputs.tensors, tensor_names);
+
+    let stages: Vec<_> = nodes.iter().map(|(name, ..)| name.as_str()).collect();
+
+    // Build the list of stages back to front, i.e. (first, (second, ()))
+    let list = stages.iter().rev().fold(quote!(()), |rest, stage| {
+        let name = Ident::new(stage, Span::call_site());
+        quote!((Stage::new(#stage, &mut #name), #rest))
+    });
+
+    let quoted: Vec<_> =
+        stages.iter().map(|name| format!("\"{}\"", name)).collect();
+    let msg = format!("Executing {} as a chain", quoted.join(", "));
+
+    quote! {
+        log::debug!(#msg);
+        let #output: #output_type = {
+            use hotg_rune_proc_blocks::fusion::{
+                Chain, RunFused as _, RunSequentially as _, Stage,
+            };
+
+            Chain::new(#list, hotg_runicos_base_wasm::set_current_stage)
+                .run(#input.clone())
+        };
+    }
+}
+
 fn execute_output(
     name: &Name,
     inputs: &Inputs,
     tensor_names: &HashMap<Entity, Ident>,
 ) -> TokenStream {
+    let stage = name.as_str();
     let name = Ident::new(name, Span::call_site());
     let inputs = input_bindings(&inputs.tensors, tensor_names);
 
@@ -245,6 +426,7 @@ fn execute_output(
 
     quote! {
         log::debug!(#msg);
+        hotg_runicos_base_wasm::set_current_stage(#stage);
         #name.consume(#inputs);
     }
 }
@@ -256,6 +438,7 @@ fn execute_model_or_proc_block(
     tensor_names: &HashMap<Entity, Ident>,
     tensors: &[(&Entity, &Tensor, Option<&Inputs>, Option<&Outputs>)],
 ) -> TokenStream {
+    let stage = name.as_str();
     let name = Ident::new(name, Span::call_site());
     let inputs = input_bindings(&inputs.tensors, tensor_names);
     let output_types = tensor_types(&outputs.tensors, tensors);
@@ -265,6 +448,7 @@ fn execute_model_or_proc_block(
 
     quote! {
         log::debug!(#msg);
+        hotg_runicos_base_wasm::set_current_stage(#stage);
         let #outputs: #output_types = #name.transform(#inputs);
     }
 }
@@ -317,6 +501,11 @@ fn shape_to_tensor_type(shape: &Shape) -> TokenStream {
         ElementType::I64 => quote!(i64),
         ElementType::F64 => quote!(f64),
         ElementType::String => quote!(alloc::borrow::Cow<'static, str>),
+        ElementType::F16 => quote!(hotg_rune_core::f16),
+        ElementType::BF16 => quote!(hotg_rune_core::bf16),
+        ElementType::Bool => quote!(bool),
+        ElementType::C64 => quote!(hotg_rune_core::Complex<f32>),
+        ElementType::C128 => quote!(hotg_rune_core::Complex<f64>),
     };
     quote!(Tensor<#element_type>)
 }
@@ -340,6 +529,7 @@ fn execute_capability(
     tensor_names: &HashMap<Entity, Ident>,
     tensors: &[(&Entity, &Tensor, Option<&Inputs>, Option<&Outputs>)],
 ) -> TokenStream {
+    let stage = name.as_str();
     let name = Ident::new(name, Span::call_site());
     let output_types = tensor_types(&outputs.tensors, tensors);
     let outputs = tensor_name_or_tuple(&outputs.tensors, tensor_names);
@@ -348,6 +538,7 @@ fn execute_capability(
 
     quote! {
         log::debug!(#msg);
+        hotg_runicos_base_wasm::set_current_stage(#stage);
         let #outputs: #output_types = #name.generate();
     }
 }
@@ -480,16 +671,71 @@ fn initialize_outputs(outputs: &[(&Name, &Sink)]) -> TokenStream {
 fn initialize_output(name: &Name, sink: &Sink) -> TokenStream {
     let name = Ident::new(name, Span::call_site());
     let type_name: TokenStream = sink_type_name(&sink.kind);
+    let options = serial_options(sink);
 
     quote! {
-        let mut #name = #type_name::default();
+        let mut #name = #type_name::default()#options;
     }
 }
 
+/// Builder methods for the `fields` and `format` arguments accepted by
+/// outputs which send serial messages.
+fn serial_options(sink: &Sink) -> TokenStream {
+    if !matches!(sink.kind, SinkKind::Serial | SinkKind::Mqtt | SinkKind::Ble)
+    {
+        return TokenStream::new();
+    }
+
+    let mut options = TokenStream::new();
+
+    match sink.args.get("fields") {
+        Some(ResourceOrString::String(fields)) if fields.starts_with('[') => {
+            match serde_json::from_str::<Vec<String>>(fields) {
+                Ok(fields) => {
+                    options.extend(quote!(.with_fields(&[#(#fields),*])))
+                },
+                Err(_) => options.extend(quote!(.with_fields(compile_error!(
+                    "The \"fields\" argument must be a list of names"
+                )))),
+            }
+        },
+        Some(ResourceOrString::String(fields)) => {
+            let fields =
+                fields.split(',').map(str::trim).filter(|f| !f.is_empty());
+            options.extend(quote!(.with_fields(&[#(#fields),*])));
+        },
+        Some(ResourceOrString::Resource(_)) => {
+            options.extend(quote!(.with_fields(compile_error!(
+                "The \"fields\" argument must be a comma-separated list"
+            ))));
+        },
+        None => {},
+    }
+
+    match sink.args.get("format") {
+        Some(ResourceOrString::String(format)) if format == "cbor" => {
+            options.extend(quote!(.with_encoding(
+                hotg_runicos_base_wasm::Encoding::Cbor
+            )));
+        },
+        Some(ResourceOrString::String(format)) if format == "json" => {},
+        Some(_) => {
+            options.extend(quote!(.with_encoding(compile_error!(
+                "The \"format\" argument must be either \"json\" or \"cbor\""
+            ))));
+        },
+        None => {},
+    }
+
+    options
+}
+
 fn sink_type_name(kind: &SinkKind) -> TokenStream {
     match kind {
         SinkKind::Serial => quote!(hotg_runicos_base_wasm::Serial),
         SinkKind::Tensor => quote!(hotg_runicos_base_wasm::TensorOutput),
+        SinkKind::Mqtt => quote!(hotg_runicos_base_wasm::Mqtt),
+        SinkKind::Ble => quote!(hotg_runicos_base_wasm::Ble),
         SinkKind::Other(other) => {
             unimplemented!("Unable to handle \"{}\" outputs", other)
         },
@@ -509,10 +755,33 @@ where
     N: FnMut(Entity) -> Option<&'world Name>,
     T: FnMut(Entity) -> Option<&'world Tensor>,
 {
+    let input_descriptors: TokenStream =
+        tensor_descriptors(&inputs.tensors, get_tensor);
+    let output_descriptors: TokenStream =
+        tensor_descriptors(&outputs.tensors, get_tensor);
+
+    let mimetype = mimetype.as_ref();
+
+    if model.location == ModelLocation::Host {
+        let model_name = name.as_str();
+        let name = Ident::new(name, Span::call_site());
+
+        return quote! {
+            let mut #name = hotg_runicos_base_wasm::Model::load_external(
+                #model_name,
+                #mimetype,
+                #input_descriptors,
+                #output_descriptors,
+            );
+        };
+    }
+
     let name = Ident::new(name, Span::call_site());
 
-    let path_to_model_bytes = match &model.model_file {
-        ModelFile::FromDisk(_) => quote!(crate::models::#name),
+    let mut path_to_model_bytes = match &model.model_file {
+        ModelFile::FromDisk(_) | ModelFile::Url { .. } => {
+            quote!(crate::models::#name)
+        },
         ModelFile::Resource(resource) => {
             let resource_name = get_name(*resource)
                 .expect("We should always be able to get a resource's name");
@@ -521,12 +790,45 @@ where
         },
     };
 
-    let input_descriptors: TokenStream =
-        tensor_descriptors(&inputs.tensors, get_tensor);
-    let output_descriptors: TokenStream =
-        tensor_descriptors(&outputs.tensors, get_tensor);
+    if !model.variants.is_empty() {
+        // Let the host pick which variant to use, where 0 is the default model
+        let model_name = name.to_string();
+        let variant_names: Vec<_> = model.variants.keys().collect();
+        let variant_bytes = variant_names.iter().map(|variant| {
+            let ident = variant_file_name(&model_name, variant);
+            let ident = Ident::new(&ident, Span::call_site());
+            quote!(&crate::models::#ident)
+        });
+        let count = variant_names.len() + 1;
+
+        path_to_model_bytes = quote! {
+            {
+                let variants: [&[u8]; #count] = [
+                    &#path_to_model_bytes,
+                    #( #variant_bytes ),*
+                ];
+                let index = hotg_runicos_base_wasm::model_variant(
+                    #model_name,
+                    &[ #( #variant_names ),* ],
+                );
+                variants[index]
+            }
+        };
+    }
 
-    let mimetype = mimetype.as_ref();
+    if model.encrypted {
+        let model_name = name.to_string();
+
+        return quote! {
+            let mut #name = hotg_runicos_base_wasm::Model::load_encrypted(
+                #model_name,
+                #mimetype,
+                &#path_to_model_bytes,
+                #input_descriptors,
+                #output_descriptors,
+            );
+        };
+    }
 
     quote! {
         let mut #name = hotg_runicos_base_wasm::Model::load(
@@ -567,6 +869,11 @@ fn element_type_to_tokens(element_type: ElementType) -> TokenStream {
         ElementType::F64 => "F64",
         ElementType::I64 => "I64",
         ElementType::String => "String",
+        ElementType::F16 => "F16",
+        ElementType::BF16 => "BF16",
+        ElementType::Bool => "Bool",
+        ElementType::C64 => "C64",
+        ElementType::C128 => "C128",
     };
     let ident = Ident::new(name, Span::call_site());
     quote!(hotg_rune_core::ElementType::#ident)
@@ -574,10 +881,23 @@ fn element_type_to_tokens(element_type: ElementType) -> TokenStream {
 
 fn shape_to_tokens(shape: &Shape<'_>) -> TokenStream {
     let element_type = element_type_to_tokens(shape.element_type());
-    let dimensions = shape.dimensions();
+
+    if let Some(dimensions) = shape.fixed_dimensions() {
+        return quote! {
+            hotg_rune_core::Shape::new(
+                #element_type,
+                [ #(#dimensions),* ].as_ref(),
+            )
+        };
+    }
+
+    let dimensions = shape.dimensions().iter().map(|d| match d.value() {
+        Some(length) => quote!(hotg_rune_core::Dim::Fixed(#length)),
+        None => quote!(hotg_rune_core::Dim::Unknown),
+    });
 
     quote! {
-        hotg_rune_core::Shape::new(
+        hotg_rune_core::Shape::with_dims(
             #element_type,
             [ #(#dimensions),* ].as_ref(),
         )
@@ -608,6 +928,10 @@ fn initialize_proc_block<'world, N>(
 where
     N: FnMut(Entity) -> Option<&'world Name>,
 {
+    if proc_block.location == ProcBlockLocation::Host {
+        return initialize_host_proc_block(name, proc_block, get_name);
+    }
+
     let ty = proc_block_type(proc_block);
 
     let name = Ident::new(name, Span::call_site());
@@ -625,6 +949,38 @@ where
     quote! {
         let mut #name = #ty::default();
         #( #setters )*
+        #name.init();
+    }
+}
+
+/// Ask the host to load a proc block which was compiled to a standalone
+/// WebAssembly module, passing its arguments along by name.
+///
+/// The host only loads the module when it is first used, so invalid
+/// arguments are reported by the first `transform()`.
+fn initialize_host_proc_block<'world, N>(
+    name: &Name,
+    proc_block: &ProcBlock,
+    get_name: &mut N,
+) -> TokenStream
+where
+    N: FnMut(Entity) -> Option<&'world Name>,
+{
+    let proc_block_name = name.as_str();
+    let name = Ident::new(name, Span::call_site());
+    let setters = proc_block.parameters.iter().map(|(key, value)| {
+        let value = proc_block_argument_to_tokens(value, get_name);
+        let key = key.replace("-", "_");
+        quote! {
+            #name.set_parameter(#key, #value);
+        }
+    });
+
+    quote! {
+        let mut #name = hotg_runicos_base_wasm::ProcBlock::load_external(
+            #proc_block_name,
+        );
+        #( #setters )*
     }
 }
 
@@ -687,9 +1043,22 @@ where
     let name = Ident::new(name, Span::call_site());
     let setters = source.parameters.iter().map(|(key, value)| {
         let key = key.replace("-", "_");
-        let value = capability_argument_to_tokens(value, get_name);
-        quote! {
-            #name.set_parameter(#key, #value);
+
+        match value {
+            // Lists and maps are parsed by the runtime
+            ResourceOrString::String(s)
+                if s.starts_with('[') || s.starts_with('{') =>
+            {
+                quote! {
+                    #name.set_string_parameter(#key, #s);
+                }
+            },
+            _ => {
+                let value = capability_argument_to_tokens(value, get_name);
+                quote! {
+                    #name.set_parameter(#key, #value);
+                }
+            },
         }
     });
 
@@ -774,7 +1143,7 @@ fn generate_prelude() -> TokenStream {
         use hotg_rune_core::PixelFormat;
         use hotg_rune_proc_blocks::*;
 
-        static mut PIPELINE: Option<Box<dyn FnMut()>> = None;
+        static mut PIPELINE: Option<Box<dyn FnMut(bool)>> = None;
     }
 }
 
@@ -791,7 +1160,24 @@ fn generate_call_function() -> TokenStream {
             unsafe {
                 let pipeline = PIPELINE.as_mut()
                     .expect("The rune hasn't been initialized");
-                pipeline();
+                pipeline(false);
+
+                0
+            }
+        }
+    }
+}
+
+/// The `_reset()` function - tells the `PIPELINE` to reset the state of each
+/// proc block (e.g. between items in a dataset).
+fn generate_reset_function() -> TokenStream {
+    quote! {
+        #[no_mangle]
+        pub extern "C" fn _reset() -> i32 {
+            unsafe {
+                let pipeline = PIPELINE.as_mut()
+                    .expect("The rune hasn't been initialized");
+                pipeline(true);
 
                 0
             }
@@ -917,8 +1303,9 @@ where
     M: Iterator<Item = (&'world Name, &'world Model)>,
     N: FnMut(Entity) -> Option<&'world Name>,
 {
-    let initializers =
-        models.map(|(name, model)| model_initializer(name, model, get_name));
+    let initializers = models
+        .filter(|(_, model)| model.location == ModelLocation::Embedded)
+        .map(|(name, model)| model_initializer(name, model, get_name));
 
     quote! {
         /// Lazily loaded accessors for all models used by this Rune.
@@ -938,14 +1325,26 @@ fn model_initializer<'world, N>(
 where
     N: FnMut(Entity) -> Option<&'world Name>,
 {
+    let variants = model.variants.keys().map(|variant| {
+        let file_name = variant_file_name(name, variant);
+        let path = format!("models/{}", file_name);
+        let ident = Ident::new(&file_name, Span::call_site());
+
+        quote! {
+            pub(crate) static ref #ident: &'static [u8] = include_bytes!(#path);
+        }
+    });
+    let variants = quote!(#(#variants)*);
+
     let name = Ident::new(name, Span::call_site());
 
     match &model.model_file {
-        ModelFile::FromDisk(_) => {
+        ModelFile::FromDisk(_) | ModelFile::Url { .. } => {
             let path = format!("models/{}", name);
 
             quote! {
                 pub(crate) static ref #name: &'static [u8] = include_bytes!(#path);
+                #variants
             }
         },
         ModelFile::Resource(resource) => {
@@ -954,6 +1353,7 @@ where
 
             quote! {
                 pub(crate) static ref #name: &'static [u8] = crate::resources::#resource_name.as_ref();
+                #variants
             }
         },
     }
@@ -1089,6 +1489,243 @@ mod tests {
         assert_eq!(tensor_names, tensor_names_should_be);
     }
 
+    /// Create a pipeline from a list of nodes and the nodes they read from,
+    /// where each node has a single output.
+    fn pipeline(
+        world: &mut World,
+        nodes: &[(&str, &[&str])],
+    ) -> HashMap<String, Entity> {
+        let mut resources = Resources::default();
+        let mut cmd = CommandBuffer::new(world);
+        let mut entities = HashMap::new();
+        let mut outputs = HashMap::new();
+
+        for (name, _) in nodes {
+            let node = cmd.push((Name::from(*name), PipelineNode));
+            let output = cmd.push((
+                Tensor("f32[4]".parse().unwrap()),
+                Inputs {
+                    tensors: vec![node],
+                },
+            ));
+            entities.insert(name.to_string(), node);
+            outputs.insert(name.to_string(), output);
+        }
+
+        for (name, inputs) in nodes {
+            let node = entities[*name];
+            let output = outputs[*name];
+            let consumers: Vec<_> = nodes
+                .iter()
+                .filter(|(_, inputs)| inputs.contains(name))
+                .map(|(consumer, _)| entities[*consumer])
+                .collect();
+
+            if !consumers.is_empty() {
+                cmd.add_component(
+                    node,
+                    Outputs {
+                        tensors: vec![output],
+                    },
+                );
+            }
+            cmd.add_component(output, Outputs { tensors: consumers });
+
+            if !inputs.is_empty() {
+                let tensors = inputs.iter().map(|i| outputs[*i]).collect();
+                cmd.add_component(node, Inputs { tensors });
+            }
+        }
+
+        cmd.flush(world, &mut resources);
+
+        entities
+    }
+
+    fn chained_steps(
+        nodes: &[(&str, &[&str])],
+        proc_blocks: &[&str],
+    ) -> Vec<Vec<String>> {
+        let mut world = World::default();
+        let entities = pipeline(&mut world, nodes);
+        let proc_blocks: HashSet<_> =
+            proc_blocks.iter().map(|name| entities[*name]).collect();
+
+        let pipeline_nodes: Vec<_> = <(
+            Entity,
+            &Name,
+            Option<&Inputs>,
+            Option<&Outputs>,
+            &PipelineNode,
+        )>::query()
+        .iter(&world)
+        .collect();
+        let tensors: Vec<_> =
+            <(Entity, &Tensor, Option<&Inputs>, Option<&Outputs>)>::query()
+                .iter(&world)
+                .collect();
+        let ExecutionOrder {
+            order,
+            pipeline_nodes,
+            ..
+        } = ExecutionOrder::calculate(&pipeline_nodes, &tensors);
+
+        chain_proc_blocks(&order, &pipeline_nodes, &tensors, &proc_blocks)
+            .into_iter()
+            .map(|step| {
+                step.iter()
+                    .map(|ent| pipeline_nodes[ent].0.to_string())
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn chain_adjacent_proc_blocks() {
+        let nodes: &[(&str, &[&str])] = &[
+            ("rand", &[]),
+            ("scale", &["rand"]),
+            ("clip", &["scale"]),
+            ("cast", &["clip"]),
+            ("serial", &["cast"]),
+        ];
+
+        let got = chained_steps(nodes, &["scale", "clip", "cast"]);
+
+        assert_eq!(
+            got,
+            vec![vec!["rand"], vec!["scale", "clip", "cast"], vec!["serial"]]
+        );
+    }
+
+    #[test]
+    fn tensors_used_by_other_nodes_arent_chained() {
+        let nodes: &[(&str, &[&str])] = &[
+            ("rand", &[]),
+            ("scale", &["rand"]),
+            ("clip", &["scale"]),
+            ("serial", &["scale", "clip"]),
+        ];
+
+        let got = chained_steps(nodes, &["scale", "clip"]);
+
+        assert_eq!(
+            got,
+            vec![vec!["rand"], vec!["scale"], vec!["clip"], vec!["serial"]]
+        );
+    }
+
+    #[test]
+    fn only_embedded_proc_blocks_are_chained() {
+        // Pretend "model" is a model or a proc block provided by the host
+        let nodes: &[(&str, &[&str])] = &[
+            ("rand", &[]),
+            ("scale", &["rand"]),
+            ("model", &["scale"]),
+            ("clip", &["model"]),
+            ("serial", &["clip"]),
+        ];
+
+        let got = chained_steps(nodes, &["scale", "clip"]);
+
+        assert_eq!(got.len(), 5);
+    }
+
+    #[test]
+    fn execute_a_chain_of_proc_blocks() {
+        let mut world = World::default();
+        let entities = pipeline(
+            &mut world,
+            &[
+                ("rand", &[]),
+                ("scale", &["rand"]),
+                ("clip", &["scale"]),
+                ("serial", &["clip"]),
+            ],
+        );
+        let pipeline_nodes: Vec<_> = <(
+            Entity,
+            &Name,
+            Option<&Inputs>,
+            Option<&Outputs>,
+            &PipelineNode,
+        )>::query()
+        .iter(&world)
+        .collect();
+        let tensors: Vec<_> =
+            <(Entity, &Tensor, Option<&Inputs>, Option<&Outputs>)>::query()
+                .iter(&world)
+                .collect();
+        let ExecutionOrder {
+            tensor_names,
+            pipeline_nodes,
+            ..
+        } = ExecutionOrder::calculate(&pipeline_nodes, &tensors);
+
+        let got = execute_proc_block_chain(
+            &[entities["scale"], entities["clip"]],
+            &pipeline_nodes,
+            &tensor_names,
+            &tensors,
+        );
+
+        let should_be = quote! {
+            log::debug!("Executing \"scale\", \"clip\" as a chain");
+            let clip_0: Tensor<f32> = {
+                use hotg_rune_proc_blocks::fusion::{
+                    Chain, RunFused as _, RunSequentially as _, Stage,
+                };
+
+                Chain::new(
+                    (
+                        Stage::new("scale", &mut scale),
+                        (Stage::new("clip", &mut clip), ())
+                    ),
+                    hotg_runicos_base_wasm::set_current_stage
+                )
+                .run(rand_0.clone())
+            };
+        };
+        assert_quote_eq!(got, should_be);
+    }
+
+    #[test]
+    fn chains_are_timed_as_a_whole() {
+        let mut world = World::default();
+        let entities = pipeline(
+            &mut world,
+            &[("rand", &[]), ("scale", &["rand"]), ("clip", &["scale"])],
+        );
+        let pipeline_nodes: Vec<_> = <(
+            Entity,
+            &Name,
+            Option<&Inputs>,
+            Option<&Outputs>,
+            &PipelineNode,
+        )>::query()
+        .iter(&world)
+        .collect();
+        let tensors: Vec<_> =
+            <(Entity, &Tensor, Option<&Inputs>, Option<&Outputs>)>::query()
+                .iter(&world)
+                .collect();
+        let ExecutionOrder { pipeline_nodes, .. } =
+            ExecutionOrder::calculate(&pipeline_nodes, &tensors);
+
+        let got = time_step(
+            &[entities["scale"], entities["clip"]],
+            &pipeline_nodes,
+            quote!(run_the_chain();),
+        );
+
+        let should_be = quote! {
+            hotg_runicos_base_wasm::time::stage_started("scale+clip");
+            run_the_chain();
+            hotg_runicos_base_wasm::time::stage_finished("scale+clip");
+        };
+        assert_quote_eq!(got, should_be);
+    }
+
     #[test]
     fn execute_a_capability() {
         let mut world = World::default();
@@ -1111,6 +1748,7 @@ mod tests {
 
         let should_be = quote! {
             log::debug!("Reading data from \"first\"");
+            hotg_runicos_base_wasm::set_current_stage("first");
             let first_0: Tensor<f32> = first.generate();
         };
         assert_quote_eq!(got, should_be);
@@ -1154,11 +1792,64 @@ mod tests {
 
         let should_be = quote! {
             log::debug!("Executing \"model\"");
+            hotg_runicos_base_wasm::set_current_stage("model");
             let model_output: Tensor<f32> = model.transform(model_input.clone());
         };
         assert_quote_eq!(got, should_be);
     }
 
+    #[test]
+    fn execute_proc_block_with_multiple_inputs_and_outputs() {
+        let mut world = World::default();
+        let mut resources = Resources::default();
+        let mut cmd = CommandBuffer::new(&world);
+        let shapes = [
+            ("image", "u8[1, 224, 224, 3]"),
+            ("boxes", "f32[10, 4]"),
+            ("cropped", "u8[10, 64, 64, 3]"),
+            ("scores", "f32[10]"),
+        ];
+        let tensors: Vec<_> = shapes
+            .iter()
+            .map(|(name, shape)| {
+                let tensor = Tensor(shape.parse().unwrap());
+                let ent = cmd.push((tensor.clone(),));
+                (ent, *name, tensor)
+            })
+            .collect();
+        cmd.flush(&mut world, &mut resources);
+        let inputs = Inputs {
+            tensors: vec![tensors[0].0, tensors[1].0],
+        };
+        let outputs = Outputs {
+            tensors: vec![tensors[2].0, tensors[3].0],
+        };
+        let tensor_names: HashMap<_, _> = tensors
+            .iter()
+            .map(|(ent, name, _)| (*ent, Ident::new(name, Span::call_site())))
+            .collect();
+        let all_tensors: Vec<_> = tensors
+            .iter()
+            .map(|(ent, _, tensor)| (ent, tensor, None, None))
+            .collect();
+
+        let got = execute_model_or_proc_block(
+            &Name::from("crop"),
+            &inputs,
+            &outputs,
+            &tensor_names,
+            &all_tensors,
+        );
+
+        let should_be = quote! {
+            log::debug!("Executing \"crop\"");
+            hotg_runicos_base_wasm::set_current_stage("crop");
+            let (cropped, scores): (Tensor<u8>, Tensor<f32>) =
+                crop.transform((image.clone(), boxes.clone()));
+        };
+        assert_quote_eq!(got, should_be);
+    }
+
     #[test]
     fn consume_multiple_outputs() {
         let mut world = World::default();
@@ -1190,11 +1881,85 @@ mod tests {
 
         let should_be = quote! {
             log::debug!("Sending results to the \"serial\" output");
+            hotg_runicos_base_wasm::set_current_stage("serial");
             serial.consume((first_input.clone(), second_input.clone()));
         };
         assert_quote_eq!(got, should_be);
     }
 
+    #[test]
+    fn structured_serial_output() {
+        let sink = Sink {
+            kind: SinkKind::Serial,
+            args: vec![
+                ("fields".to_string(), "label, confidence".into()),
+                ("format".to_string(), "cbor".into()),
+            ]
+            .into_iter()
+            .collect(),
+        };
+
+        let got = initialize_output(&Name::from("serial"), &sink);
+
+        let should_be = quote! {
+            let mut serial = hotg_runicos_base_wasm::Serial::default()
+                .with_fields(&["label", "confidence"])
+                .with_encoding(hotg_runicos_base_wasm::Encoding::Cbor);
+        };
+        assert_quote_eq!(got, should_be);
+    }
+
+    #[test]
+    fn host_proc_blocks_are_loaded_by_name() {
+        let proc_block = ProcBlock {
+            path: "hotg-ai/normalize@0.11".parse().unwrap(),
+            parameters: vec![("max-value".to_string(), "255".into())]
+                .into_iter()
+                .collect(),
+            location: ProcBlockLocation::Host,
+        };
+
+        let got = initialize_proc_block(
+            &Name::from("normalize"),
+            &proc_block,
+            &mut |_| None,
+        );
+
+        let should_be = quote! {
+            let mut normalize = hotg_runicos_base_wasm::ProcBlock::load_external(
+                "normalize",
+            );
+            normalize.set_parameter("max_value", "255");
+        };
+        assert_quote_eq!(got, should_be);
+    }
+
+    #[test]
+    fn embedded_proc_blocks_are_initialized_after_setting_parameters() {
+        let proc_block = ProcBlock {
+            path: "hotg-ai/normalize@0.11".parse().unwrap(),
+            parameters: vec![("max-value".to_string(), "255".into())]
+                .into_iter()
+                .collect(),
+            location: ProcBlockLocation::Embedded,
+        };
+
+        let got = initialize_proc_block(
+            &Name::from("normalize"),
+            &proc_block,
+            &mut |_| None,
+        );
+
+        let should_be = quote! {
+            let mut normalize = normalize::Normalize::default();
+            normalize
+                .set_max_value("255")
+                .expect("Unable to set normalize's \"max-value\" to \"255\"");
+            normalize.init();
+        };
+        assert_quote_eq!(got, should_be);
+    }
+
     #[test]
     fn tensor_shapes_as_rust_types() {
         let inputs = vec![
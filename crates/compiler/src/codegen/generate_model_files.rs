@@ -4,14 +4,39 @@ use legion::systems::CommandBuffer;
 
 use crate::{
     codegen::File,
-    lowering::{ModelData, Name},
+    lowering::{Model, ModelData, ModelLocation, Name, VariantData},
 };
 
 /// Create a [`File`] for each model with associated [`ModelData`] and put it in
 /// the `models/` directory.
+///
+/// Models run by the host aren't embedded in the Rune, so they are skipped.
+/// Each of a model's variants is saved as `models/<name>__<variant>`.
 #[legion::system(for_each)]
-pub(crate) fn run(cmd: &mut CommandBuffer, name: &Name, data: &ModelData) {
+pub(crate) fn run(
+    cmd: &mut CommandBuffer,
+    name: &Name,
+    data: &ModelData,
+    model: &Model,
+    variants: Option<&VariantData>,
+) {
+    if model.location == ModelLocation::Host {
+        return;
+    }
+
     let path = Path::new("models").join(name.as_str());
     let file = File::new(path, Arc::clone(&data.0));
     cmd.push((file,));
+
+    for (variant, data) in variants.iter().flat_map(|v| &v.0) {
+        let path = Path::new("models").join(variant_file_name(name, variant));
+        let file = File::new(path, Arc::clone(&data.0));
+        cmd.push((file,));
+    }
+}
+
+/// The name used for a model variant's file and its accessor in the
+/// generated `models` module.
+pub(crate) fn variant_file_name(name: &str, variant: &str) -> String {
+    format!("{}__{}", name, variant)
 }
@@ -0,0 +1,77 @@
+use std::path::Path;
+
+use codespan::Span;
+use codespan_reporting::diagnostic::{Diagnostic, Label};
+use legion::systems::CommandBuffer;
+
+use crate::{
+    codegen::File,
+    lowering::{Name, ProcBlock, ProcBlockLocation},
+    python, BuildContext, Diagnostics,
+};
+
+/// Translate each proc block written in Python into a Rust crate under the
+/// `proc_blocks/` directory.
+///
+/// Proc blocks provided by the host aren't compiled into the Rune, so they
+/// are skipped.
+#[legion::system(for_each)]
+pub(crate) fn run(
+    cmd: &mut CommandBuffer,
+    #[resource] diags: &mut Diagnostics,
+    #[resource] ctx: &BuildContext,
+    name: &Name,
+    proc_block: &ProcBlock,
+    &span: &Span,
+) {
+    if !proc_block.is_python() || proc_block.location == ProcBlockLocation::Host
+    {
+        return;
+    }
+
+    let filename = &proc_block.path.base;
+    let full_path = ctx.current_directory.join(filename);
+
+    let src = match std::fs::read_to_string(&full_path) {
+        Ok(src) => src,
+        Err(e) => {
+            let msg = format!(
+                "Unable to read \"{}\" for \"{}\": {}",
+                full_path.display(),
+                name,
+                e
+            );
+            let diag = Diagnostic::error()
+                .with_message(msg)
+                .with_labels(vec![Label::primary((), span)]);
+            diags.push(diag);
+            return;
+        },
+    };
+
+    let crate_name = proc_block.name();
+
+    match python::compile(crate_name, filename, &src) {
+        Ok(generated) => {
+            let dir = Path::new("proc_blocks").join(crate_name);
+            cmd.push((File::new(
+                dir.join("Cargo.toml"),
+                generated.cargo_toml.into_bytes(),
+            ),));
+            cmd.push((File::new(
+                dir.join("src").join("lib.rs"),
+                generated.lib_rs.into_bytes(),
+            ),));
+        },
+        Err(e) => {
+            let msg = format!(
+                "Unable to compile \"{}\" for \"{}\": {}",
+                filename, name, e
+            );
+            let diag = Diagnostic::error()
+                .with_message(msg)
+                .with_labels(vec![Label::primary((), span)]);
+            diags.push(diag);
+        },
+    }
+}
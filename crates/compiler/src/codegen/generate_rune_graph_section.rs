@@ -14,8 +14,8 @@ use crate::{
         ModelSummary, OutputSummary, ProcBlockSummary, RuneGraph, TensorId,
     },
     lowering::{
-        self, Inputs, Model, ModelFile, Name, Outputs, ProcBlock, Resource,
-        Sink, Source, Tensor,
+        self, Inputs, Model, ModelFile, ModelMetadata, Name, Outputs,
+        ProcBlock, Resource, Sink, Source, Tensor,
     },
     parse::{ResourceName, ResourceOrString},
     BuildContext,
@@ -29,7 +29,13 @@ pub(crate) fn run(
     #[resource] ctx: &BuildContext,
     capabilities: &mut Query<(&Name, &Source, &Outputs)>,
     tensors: &mut Query<(Entity, &Tensor)>,
-    models: &mut Query<(&Name, &Model, &Inputs, &Outputs)>,
+    models: &mut Query<(
+        &Name,
+        &Model,
+        &Inputs,
+        &Outputs,
+        Option<&ModelMetadata>,
+    )>,
     proc_blocks: &mut Query<(&Name, &ProcBlock, &Inputs, &Outputs)>,
     outputs: &mut Query<(&Name, &Sink, &Inputs)>,
     resources: &mut Query<(&Name, &Resource)>,
@@ -52,8 +58,8 @@ pub(crate) fn run(
             .collect(),
         models: models
             .iter(world)
-            .map(|(n, m, i, o)| {
-                model_summary(n, m, i, o, &mut resource_name, &canon)
+            .map(|(n, m, i, o, meta)| {
+                model_summary(n, m, i, o, meta, &mut resource_name, &canon)
             })
             .collect(),
         proc_blocks: proc_blocks
@@ -121,6 +127,7 @@ fn model_summary(
     model: &Model,
     inputs: &Inputs,
     outputs: &Outputs,
+    metadata: Option<&ModelMetadata>,
     mut resources: impl FnMut(Entity) -> ResourceName,
     get_tensor: &Canon,
 ) -> (Name, ModelSummary) {
@@ -128,6 +135,7 @@ fn model_summary(
         ModelFile::FromDisk(path) => {
             ResourceOrString::String(path.display().to_string())
         },
+        ModelFile::Url { url, .. } => ResourceOrString::String(url.clone()),
         ModelFile::Resource(entity) => {
             ResourceOrString::Resource(resources(*entity))
         },
@@ -138,6 +146,7 @@ fn model_summary(
         args: convert_args(&model.args, resources),
         inputs: tensor_shapes(&inputs.tensors, get_tensor),
         outputs: tensor_shapes(&outputs.tensors, get_tensor),
+        metadata: metadata.cloned(),
     };
 
     (name.clone(), summary)
@@ -5,10 +5,12 @@
 
 mod compile_generated_project;
 mod components;
+mod generate_abi_version_section;
 mod generate_cargo_config;
 mod generate_cargo_toml;
 mod generate_lib_rs;
 mod generate_model_files;
+mod generate_python_proc_blocks;
 mod generate_resource_section;
 mod generate_rune_graph_section;
 mod generate_rust_toolchain_toml;
@@ -23,10 +25,12 @@ pub fn phase() -> Phase {
     Phase::new()
         .and_then(generate_rust_toolchain_toml::run_system)
         .and_then(generate_cargo_config::run_system)
+        .and_then(generate_python_proc_blocks::run_system)
         .and_then(generate_cargo_toml::run_system)
         .and_then(generate_model_files::run_system)
         .and_then(generate_resource_section::run_system)
         .and_then(generate_version_section::run_system)
+        .and_then(generate_abi_version_section::run_system)
         .and_then(generate_rune_graph_section::run_system)
         .and_then(generate_lib_rs::run_system)
         .and_then(compile_generated_project::run_system)
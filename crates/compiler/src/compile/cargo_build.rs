@@ -8,11 +8,15 @@ use legion::systems::CommandBuffer;
 
 use crate::{
     compile::{CompilationResult, CompileError, CompiledBinary},
-    BuildContext, Verbosity,
+    BuildContext, Diagnostics, Verbosity,
 };
 
 #[legion::system]
-pub(crate) fn run(cmd: &mut CommandBuffer, #[resource] ctx: &BuildContext) {
+pub(crate) fn run(
+    cmd: &mut CommandBuffer,
+    #[resource] ctx: &BuildContext,
+    #[resource] diags: &Diagnostics,
+) {
     let BuildContext {
         working_directory,
         optimized,
@@ -21,9 +25,14 @@ pub(crate) fn run(cmd: &mut CommandBuffer, #[resource] ctx: &BuildContext) {
         ..
     } = ctx;
 
-    rustfmt(working_directory);
-
-    let result = build(name, working_directory, *optimized, *verbosity);
+    // There's no point compiling when we already know it will fail (e.g.
+    // because a proc block is incompatible)
+    let result = if diags.has_errors() {
+        Err(CompileError::Aborted)
+    } else {
+        rustfmt(working_directory);
+        build(name, working_directory, *optimized, *verbosity)
+    };
 
     // Note: the exec_mut() method takes a Fn() closure and not a FnOnce(), so
     // we need to use a Mutex<Option<_>> to move the result.
@@ -0,0 +1,931 @@
+use std::collections::HashMap;
+
+use codespan::Span;
+use codespan_reporting::diagnostic::{Diagnostic, Label};
+use heck::{ToSnakeCase, ToUpperCamelCase};
+use hotg_rune_core::Shape;
+use hotg_rune_proc_blocks::{
+    Dimension, Dimensions, ParameterDescriptor, ProcBlockDescriptor,
+    TensorDescriptor, TransformDescriptor,
+};
+use legion::{world::SubWorld, Entity, Query};
+use wasmparser::{Parser, Payload};
+
+use crate::{
+    compile::CompilationResult,
+    lowering::{
+        Inputs, Name, Outputs, ProcBlock, ProcBlockLocation, ResourceOrString,
+        Tensor,
+    },
+    parse::{DocumentV1, Stage},
+    Diagnostics,
+};
+
+/// Check each proc block's arguments and tensors against the
+/// [`ProcBlockDescriptor`] it embedded in the compiled Rune.
+#[legion::system]
+pub(crate) fn run(
+    world: &SubWorld,
+    #[resource] result: &CompilationResult,
+    #[resource] doc: &DocumentV1,
+    #[resource] diags: &mut Diagnostics,
+    proc_blocks: &mut Query<(
+        &Name,
+        &Span,
+        &ProcBlock,
+        Option<&Inputs>,
+        Option<&Outputs>,
+    )>,
+    tensors: &mut Query<(Entity, &Tensor)>,
+) {
+    let binary = match &result.0 {
+        Ok(binary) => binary,
+        Err(_) => return,
+    };
+
+    let descriptors = embedded_descriptors(binary);
+    if descriptors.is_empty() {
+        return;
+    }
+
+    let tensors: HashMap<Entity, &Shape<'static>> =
+        tensors.iter(world).map(|(&e, t)| (e, &t.0)).collect();
+    let shapes = |ents: Option<&Vec<Entity>>| -> Vec<&Shape<'static>> {
+        ents.into_iter()
+            .flatten()
+            .filter_map(|e| tensors.get(e).copied())
+            .collect()
+    };
+
+    for (name, &span, proc_block, inputs, outputs) in proc_blocks.iter(world) {
+        if proc_block.location != ProcBlockLocation::Embedded {
+            continue;
+        }
+
+        let type_name = proc_block.name().to_snake_case().to_upper_camel_case();
+        let descriptor = match descriptors.get(&type_name) {
+            Some(d) => d,
+            None => continue,
+        };
+
+        if let Err(diag) = check_abi_version(name, span, descriptor) {
+            diags.push(diag);
+            continue;
+        }
+
+        for (key, value) in &proc_block.parameters {
+            if let Err(diag) =
+                check_parameter(name, span, descriptor, key, value)
+            {
+                diags.push(diag);
+            }
+        }
+
+        let inputs = shapes(inputs.map(|i| &i.tensors));
+        let outputs = shapes(outputs.map(|o| &o.tensors));
+
+        if let Err(diag) =
+            check_transforms(name, span, descriptor, &inputs, &outputs)
+        {
+            diags.push(diag);
+            continue;
+        }
+
+        if let Some(stage) = doc.pipeline.get(name.as_str()) {
+            let input_names = input_names(doc, stage);
+            let output_names: Vec<_> = stage
+                .output_types()
+                .iter()
+                .map(|ty| ty.tensor_name.as_deref())
+                .collect();
+
+            if let Err(diag) = check_tensor_names(
+                name,
+                span,
+                descriptor,
+                (&inputs, &input_names),
+                (&outputs, &output_names),
+            ) {
+                diags.push(diag);
+            }
+        }
+    }
+}
+
+/// The Runefile's name for each of a stage's inputs, if it has one (e.g.
+/// `detector.boxes` or an output declared with `name: boxes`).
+fn input_names<'a>(
+    doc: &'a DocumentV1,
+    stage: &'a Stage,
+) -> Vec<Option<&'a str>> {
+    stage
+        .inputs()
+        .iter()
+        .map(|input| match &input.output_name {
+            Some(output_name) => Some(output_name.as_str()),
+            None => doc
+                .pipeline
+                .get(&input.name)?
+                .output_types()
+                .get(input.index.unwrap_or(0))?
+                .tensor_name
+                .as_deref(),
+        })
+        .collect()
+}
+
+/// Read the descriptor for every proc block compiled into the Rune, keyed by
+/// type name.
+///
+/// The linker concatenates the custom sections from each proc block, so the
+/// section contains a stream of JSON objects.
+fn embedded_descriptors(
+    wasm: &[u8],
+) -> HashMap<String, ProcBlockDescriptor<'static>> {
+    let mut descriptors = HashMap::new();
+
+    for payload in Parser::default().parse_all(wasm) {
+        let data = match payload {
+            Ok(Payload::CustomSection { name, data, .. })
+                if name == ProcBlockDescriptor::CUSTOM_SECTION_NAME =>
+            {
+                data
+            },
+            _ => continue,
+        };
+
+        let stream = serde_json::Deserializer::from_slice(data)
+            .into_iter::<ProcBlockDescriptor<'static>>();
+
+        for descriptor in stream {
+            match descriptor {
+                Ok(d) => {
+                    descriptors.insert(d.type_name.to_string(), d);
+                },
+                Err(e) => {
+                    log::warn!(
+                        "Unable to parse a proc block descriptor: {}",
+                        e
+                    );
+                    break;
+                },
+            }
+        }
+    }
+
+    descriptors
+}
+
+fn check_abi_version(
+    name: &Name,
+    span: Span,
+    descriptor: &ProcBlockDescriptor<'_>,
+) -> Result<(), Diagnostic<()>> {
+    if descriptor.is_abi_compatible() {
+        return Ok(());
+    }
+
+    let msg = format!(
+        "The \"{}\" proc block was compiled against version {} of the proc \
+         block ABI, but this version of Rune uses version {}",
+        name,
+        descriptor.abi_version,
+        ProcBlockDescriptor::ABI_VERSION
+    );
+
+    Err(Diagnostic::error()
+        .with_message(msg)
+        .with_labels(vec![Label::primary((), span)])
+        .with_notes(vec![format!(
+            "hint: rebuild the proc block against hotg-rune-proc-blocks {}",
+            hotg_rune_proc_blocks::VERSION
+        )]))
+}
+
+fn check_parameter(
+    name: &Name,
+    span: Span,
+    descriptor: &ProcBlockDescriptor<'_>,
+    key: &str,
+    value: &ResourceOrString,
+) -> Result<(), Diagnostic<()>> {
+    let property = key.replace('-', "_");
+
+    let parameter = descriptor
+        .parameters
+        .iter()
+        .find(|p| p.name == property)
+        .ok_or_else(|| {
+        unknown_parameter_diagnostic(name, span, key, &descriptor.parameters)
+    })?;
+
+    // Resources are only known at runtime
+    if let ResourceOrString::String(value) = value {
+        if let Some(reason) = parse_error(&parameter.type_name, value) {
+            return Err(invalid_argument_diagnostic(
+                name, span, key, value, parameter, &reason,
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Try to parse a value the same way the proc block's generated setter would,
+/// for the types we know about.
+fn parse_error(type_name: &str, value: &str) -> Option<String> {
+    fn check<T>(value: &str) -> Option<String>
+    where
+        T: std::str::FromStr,
+        T::Err: std::fmt::Display,
+    {
+        value.parse::<T>().err().map(|e| e.to_string())
+    }
+
+    match type_name {
+        "u8" => check::<u8>(value),
+        "i8" => check::<i8>(value),
+        "u16" => check::<u16>(value),
+        "i16" => check::<i16>(value),
+        "u32" => check::<u32>(value),
+        "i32" => check::<i32>(value),
+        "u64" => check::<u64>(value),
+        "i64" => check::<i64>(value),
+        "usize" => check::<usize>(value),
+        "isize" => check::<isize>(value),
+        "f32" => check::<f32>(value),
+        "f64" => check::<f64>(value),
+        "bool" => check::<bool>(value),
+        _ => None,
+    }
+}
+
+fn unknown_parameter_diagnostic(
+    name: &Name,
+    span: Span,
+    key: &str,
+    parameters: &[ParameterDescriptor<'_>],
+) -> Diagnostic<()> {
+    let msg = format!("\"{}\" doesn't have a \"{}\" parameter", name, key);
+    let available: Vec<_> = parameters.iter().map(|p| &*p.name).collect();
+
+    let note = if available.is_empty() {
+        "This proc block doesn't accept any parameters".to_string()
+    } else {
+        format!("Available parameters are: {}", available.join(", "))
+    };
+
+    Diagnostic::error()
+        .with_message(msg)
+        .with_labels(vec![Label::primary((), span)])
+        .with_notes(vec![note])
+}
+
+fn invalid_argument_diagnostic(
+    name: &Name,
+    span: Span,
+    key: &str,
+    value: &str,
+    parameter: &ParameterDescriptor<'_>,
+    reason: &str,
+) -> Diagnostic<()> {
+    let msg = format!(
+        "Unable to use {:?} for \"{}\"'s \"{}\" parameter: {}",
+        value, name, key, reason
+    );
+
+    let mut notes =
+        vec![format!("\"{}\" expects a {}", key, parameter.type_name)];
+    if !parameter.description.is_empty() {
+        notes.push(parameter.description.to_string());
+    }
+
+    Diagnostic::error()
+        .with_message(msg)
+        .with_labels(vec![Label::primary((), span)])
+        .with_notes(notes)
+}
+
+fn check_transforms(
+    name: &Name,
+    span: Span,
+    descriptor: &ProcBlockDescriptor<'_>,
+    inputs: &[&Shape<'_>],
+    outputs: &[&Shape<'_>],
+) -> Result<(), Diagnostic<()>> {
+    // Proc blocks which don't declare any transforms can't be checked
+    if descriptor.available_transforms.is_empty() {
+        return Ok(());
+    }
+
+    let matches = descriptor.available_transforms.iter().any(|t| {
+        tensors_match(&t.inputs, inputs) && tensors_match(&t.outputs, outputs)
+    });
+
+    if matches {
+        Ok(())
+    } else {
+        Err(no_matching_transform_diagnostic(
+            name,
+            span,
+            &descriptor.available_transforms,
+            inputs,
+            outputs,
+        ))
+    }
+}
+
+/// Use the names the proc block gave its tensors to make sure the Runefile
+/// didn't list its inputs or outputs in the wrong order.
+///
+/// Tensors are passed to a proc block by position, so unnamed tensors and
+/// names the proc block doesn't know about are fine. It's only an error when
+/// a tensor is at one position in the Runefile and the proc block expects it
+/// at another.
+fn check_tensor_names(
+    name: &Name,
+    span: Span,
+    descriptor: &ProcBlockDescriptor<'_>,
+    (inputs, input_names): (&[&Shape<'_>], &[Option<&str>]),
+    (outputs, output_names): (&[&Shape<'_>], &[Option<&str>]),
+) -> Result<(), Diagnostic<()>> {
+    let mut first_problem = None;
+
+    for transform in descriptor.available_transforms.iter() {
+        if !tensors_match(&transform.inputs, inputs)
+            || !tensors_match(&transform.outputs, outputs)
+        {
+            continue;
+        }
+
+        let problem = misplaced_tensor("input", &transform.inputs, input_names)
+            .or_else(|| {
+                misplaced_tensor("output", &transform.outputs, output_names)
+            });
+
+        match problem {
+            Some(problem) => {
+                first_problem.get_or_insert((transform, problem));
+            },
+            None => return Ok(()),
+        }
+    }
+
+    match first_problem {
+        Some((transform, problem)) => {
+            Err(misplaced_tensor_diagnostic(name, span, transform, problem))
+        },
+        None => Ok(()),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct MisplacedTensor<'a> {
+    kind: &'static str,
+    name: &'a str,
+    position: usize,
+    expected_position: usize,
+}
+
+fn misplaced_tensor<'a>(
+    kind: &'static str,
+    descriptors: &[TensorDescriptor<'_>],
+    names: &[Option<&'a str>],
+) -> Option<MisplacedTensor<'a>> {
+    names.iter().enumerate().find_map(|(position, name)| {
+        let name = (*name)?;
+
+        if descriptors.get(position).and_then(|d| d.name.as_deref())
+            == Some(name)
+        {
+            return None;
+        }
+
+        let expected_position = descriptors
+            .iter()
+            .position(|d| d.name.as_deref() == Some(name))?;
+
+        Some(MisplacedTensor {
+            kind,
+            name,
+            position,
+            expected_position,
+        })
+    })
+}
+
+fn misplaced_tensor_diagnostic(
+    name: &Name,
+    span: Span,
+    transform: &TransformDescriptor<'_>,
+    problem: MisplacedTensor<'_>,
+) -> Diagnostic<()> {
+    let MisplacedTensor {
+        kind,
+        name: tensor,
+        position,
+        expected_position,
+    } = problem;
+
+    let msg = format!(
+        "The \"{}\" tensor is \"{}\"'s {} {}, but the proc block expects it \
+         to be {} {}",
+        tensor, name, kind, position, kind, expected_position
+    );
+    let note = format!(
+        "Tensors are passed to a proc block in the order they are listed, and \
+         \"{}\" expects {} => {}",
+        name,
+        descriptor_list(&transform.inputs),
+        descriptor_list(&transform.outputs)
+    );
+
+    Diagnostic::error()
+        .with_message(msg)
+        .with_labels(vec![Label::primary((), span)])
+        .with_notes(vec![note])
+}
+
+fn tensors_match(
+    descriptors: &[TensorDescriptor<'_>],
+    shapes: &[&Shape<'_>],
+) -> bool {
+    descriptors.len() == shapes.len()
+        && descriptors
+            .iter()
+            .zip(shapes)
+            .all(|(d, s)| tensor_matches(d, s))
+}
+
+fn tensor_matches(
+    descriptor: &TensorDescriptor<'_>,
+    shape: &Shape<'_>,
+) -> bool {
+    if descriptor.element_type != shape.element_type() {
+        return false;
+    }
+
+    match &descriptor.dimensions {
+        Dimensions::Arbitrary => true,
+        Dimensions::Finite(dimensions) => {
+            dimensions.len() == shape.dimensions().len()
+                && dimensions.iter().zip(shape.dimensions()).all(
+                    |(expected, &actual)| match *expected {
+                        Dimension::Any => true,
+                        // dimensions that are only known at runtime can't be
+                        // checked
+                        Dimension::Value(expected) => {
+                            actual.value().map_or(true, |a| a == expected)
+                        },
+                    },
+                )
+        },
+    }
+}
+
+fn no_matching_transform_diagnostic(
+    name: &Name,
+    span: Span,
+    transforms: &[TransformDescriptor<'_>],
+    inputs: &[&Shape<'_>],
+    outputs: &[&Shape<'_>],
+) -> Diagnostic<()> {
+    let msg = format!(
+        "\"{}\" can't transform {} into {}",
+        name,
+        shape_list(inputs),
+        shape_list(outputs),
+    );
+
+    let mut notes = vec!["Supported transforms are:".to_string()];
+    for transform in transforms {
+        notes.push(format!(
+            "  {} => {}",
+            descriptor_list(&transform.inputs),
+            descriptor_list(&transform.outputs)
+        ));
+    }
+
+    Diagnostic::error()
+        .with_message(msg)
+        .with_labels(vec![Label::primary((), span)])
+        .with_notes(vec![notes.join("\n")])
+}
+
+fn shape_list(shapes: &[&Shape<'_>]) -> String {
+    let shapes: Vec<_> = shapes.iter().map(|s| s.to_string()).collect();
+    format!("({})", shapes.join(", "))
+}
+
+fn descriptor_list(tensors: &[TensorDescriptor<'_>]) -> String {
+    let tensors: Vec<_> = tensors
+        .iter()
+        .map(|t| match &t.name {
+            Some(name) => {
+                format!("{}: {}[{}]", name, t.element_type, t.dimensions)
+            },
+            None => format!("{}[{}]", t.element_type, t.dimensions),
+        })
+        .collect();
+    format!("({})", tensors.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use hotg_rune_core::ElementType;
+
+    use super::*;
+
+    fn descriptor() -> ProcBlockDescriptor<'static> {
+        ProcBlockDescriptor {
+            type_name: "Normalize".into(),
+            description: "".into(),
+            available_transforms: vec![TransformDescriptor {
+                inputs: TensorDescriptor {
+                    name: None,
+                    element_type: ElementType::F32,
+                    dimensions: Dimensions::Finite(Cow::Owned(vec![
+                        Dimension::Value(1),
+                        Dimension::Any,
+                    ])),
+                }
+                .into(),
+                outputs: TensorDescriptor {
+                    name: None,
+                    element_type: ElementType::F32,
+                    dimensions: Dimensions::Arbitrary,
+                }
+                .into(),
+            }]
+            .into(),
+            parameters: vec![ParameterDescriptor {
+                name: "max_value".into(),
+                description: "The largest value.".into(),
+                type_name: "f32".into(),
+                default_value: Some("1.0".into()),
+            }]
+            .into(),
+            abi_version: ProcBlockDescriptor::ABI_VERSION,
+        }
+    }
+
+    #[test]
+    fn proc_blocks_with_a_different_abi_version_are_rejected() {
+        let descriptor = ProcBlockDescriptor {
+            abi_version: ProcBlockDescriptor::ABI_VERSION + 1,
+            ..descriptor()
+        };
+
+        let diag = check_abi_version(
+            &Name::from("normalize"),
+            Span::default(),
+            &descriptor,
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            diag.message,
+            format!(
+                "The \"normalize\" proc block was compiled against version {} \
+                 of the proc block ABI, but this version of Rune uses version \
+                 {}",
+                ProcBlockDescriptor::ABI_VERSION + 1,
+                ProcBlockDescriptor::ABI_VERSION
+            )
+        );
+    }
+
+    #[test]
+    fn proc_blocks_without_an_abi_version_are_accepted() {
+        let descriptor = ProcBlockDescriptor {
+            abi_version: 0,
+            ..descriptor()
+        };
+
+        assert!(check_abi_version(
+            &Name::from("normalize"),
+            Span::default(),
+            &descriptor
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn valid_parameters_are_accepted() {
+        let descriptor = descriptor();
+        let value = ResourceOrString::String("255".to_string());
+
+        check_parameter(
+            &Name::from("normalize"),
+            Span::default(),
+            &descriptor,
+            "max-value",
+            &value,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn detect_unknown_parameters() {
+        let descriptor = descriptor();
+        let value = ResourceOrString::String("255".to_string());
+
+        let diag = check_parameter(
+            &Name::from("normalize"),
+            Span::default(),
+            &descriptor,
+            "min-value",
+            &value,
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            diag.message,
+            "\"normalize\" doesn't have a \"min-value\" parameter"
+        );
+        assert_eq!(diag.notes, vec!["Available parameters are: max_value"]);
+    }
+
+    #[test]
+    fn detect_values_that_cant_be_parsed() {
+        let descriptor = descriptor();
+        let value = ResourceOrString::String("lots".to_string());
+
+        let diag = check_parameter(
+            &Name::from("normalize"),
+            Span::default(),
+            &descriptor,
+            "max_value",
+            &value,
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            diag.message,
+            "Unable to use \"lots\" for \"normalize\"'s \"max_value\" \
+             parameter: invalid float literal"
+        );
+    }
+
+    #[test]
+    fn tensors_must_match_one_of_the_transforms() {
+        let descriptor = descriptor();
+        let good: Shape<'_> = "f32[1, 42]".parse().unwrap();
+        let bad: Shape<'_> = "f32[2, 42]".parse().unwrap();
+
+        assert!(check_transforms(
+            &Name::from("normalize"),
+            Span::default(),
+            &descriptor,
+            &[&good],
+            &[&good],
+        )
+        .is_ok());
+
+        let diag = check_transforms(
+            &Name::from("normalize"),
+            Span::default(),
+            &descriptor,
+            &[&bad],
+            &[&good],
+        )
+        .unwrap_err();
+        assert_eq!(
+            diag.message,
+            "\"normalize\" can't transform (f32[2, 42]) into (f32[1, 42])"
+        );
+    }
+
+    #[test]
+    fn transforms_with_several_named_tensors() {
+        let tensor = |name: &'static str, element_type, rank| {
+            TensorDescriptor {
+                name: Some(name.into()),
+                element_type,
+                dimensions: vec![Dimension::Any; rank].into(),
+            }
+        };
+        let descriptor = ProcBlockDescriptor {
+            available_transforms: vec![TransformDescriptor {
+                inputs: vec![
+                    tensor("image", ElementType::U8, 4),
+                    tensor("boxes", ElementType::F32, 2),
+                ]
+                .into_iter()
+                .collect(),
+                outputs: tensor("labels", ElementType::String, 1).into(),
+            }]
+            .into(),
+            ..descriptor()
+        };
+        let image: Shape<'_> = "u8[1, 224, 224, 3]".parse().unwrap();
+        let boxes: Shape<'_> = "f32[10, 4]".parse().unwrap();
+        let labels: Shape<'_> = "utf8[10]".parse().unwrap();
+
+        assert!(check_transforms(
+            &Name::from("annotate"),
+            Span::default(),
+            &descriptor,
+            &[&image, &boxes],
+            &[&labels],
+        )
+        .is_ok());
+
+        let diag = check_transforms(
+            &Name::from("annotate"),
+            Span::default(),
+            &descriptor,
+            &[&boxes, &image],
+            &[&labels],
+        )
+        .unwrap_err();
+        assert_eq!(
+            diag.notes,
+            vec![
+                "Supported transforms are:\n  (image: u8[_, _, _, _], boxes: \
+                 f32[_, _]) => (labels: utf8[_])"
+            ]
+        );
+    }
+
+    fn crop() -> ProcBlockDescriptor<'static> {
+        let tensor = |name: &'static str| TensorDescriptor {
+            name: Some(name.into()),
+            element_type: ElementType::F32,
+            dimensions: Dimensions::Arbitrary,
+        };
+
+        ProcBlockDescriptor {
+            available_transforms: vec![TransformDescriptor {
+                inputs: vec![tensor("image"), tensor("boxes")]
+                    .into_iter()
+                    .collect(),
+                outputs: vec![tensor("cropped"), tensor("scores")]
+                    .into_iter()
+                    .collect(),
+            }]
+            .into(),
+            ..descriptor()
+        }
+    }
+
+    #[test]
+    fn tensors_listed_in_the_right_order_are_accepted() {
+        let shape: Shape<'_> = "f32[10, 4]".parse().unwrap();
+        let shapes = [&shape, &shape];
+
+        let inputs = [Some("image"), Some("boxes")];
+        let outputs = [None, Some("something_else")];
+
+        assert!(check_tensor_names(
+            &Name::from("crop"),
+            Span::default(),
+            &crop(),
+            (&shapes, &inputs),
+            (&shapes, &outputs),
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn detect_swapped_inputs() {
+        let shape: Shape<'_> = "f32[10, 4]".parse().unwrap();
+        let shapes = [&shape, &shape];
+
+        let diag = check_tensor_names(
+            &Name::from("crop"),
+            Span::default(),
+            &crop(),
+            (&shapes, &[Some("boxes"), Some("image")]),
+            (&shapes, &[None, None]),
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            diag.message,
+            "The \"boxes\" tensor is \"crop\"'s input 0, but the proc block \
+             expects it to be input 1"
+        );
+        assert_eq!(
+            diag.notes,
+            vec![
+                "Tensors are passed to a proc block in the order they are \
+                 listed, and \"crop\" expects (image: f32[..], boxes: \
+                 f32[..]) => (cropped: f32[..], scores: f32[..])"
+            ]
+        );
+    }
+
+    #[test]
+    fn detect_swapped_outputs() {
+        let shape: Shape<'_> = "f32[10, 4]".parse().unwrap();
+        let shapes = [&shape, &shape];
+
+        let diag = check_tensor_names(
+            &Name::from("crop"),
+            Span::default(),
+            &crop(),
+            (&shapes, &[None, None]),
+            (&shapes, &[None, Some("cropped")]),
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            diag.message,
+            "The \"cropped\" tensor is \"crop\"'s output 1, but the proc \
+             block expects it to be output 0"
+        );
+    }
+
+    #[test]
+    fn input_names_come_from_the_runefile() {
+        let doc = crate::parse::Document::parse(
+            r#"
+version: 1
+image: runicos/base
+pipeline:
+  image:
+    capability: IMAGE
+    outputs:
+      - type: f32
+        dimensions: [10, 4]
+  detector:
+    model: ./detector.tflite
+    inputs:
+      - image
+    outputs:
+      - type: f32
+        dimensions: [10, 4]
+      - type: f32
+        dimensions: [10, 4]
+        name: boxes
+  crop:
+    proc-block: hotg-ai/rune#proc-blocks/crop
+    inputs:
+      - image
+      - detector.1
+      - detector.boxes
+    outputs:
+      - type: f32
+        dimensions: [10, 4]
+"#,
+        )
+        .unwrap()
+        .to_v1();
+
+        let got = input_names(&doc, &doc.pipeline["crop"]);
+
+        assert_eq!(got, vec![None, Some("boxes"), Some("boxes")]);
+    }
+
+    #[test]
+    fn descriptors_are_read_from_a_stream_of_json() {
+        let first = descriptor();
+        let second = ProcBlockDescriptor {
+            type_name: "Fft".into(),
+            ..descriptor()
+        };
+        let mut section = serde_json::to_vec(&first).unwrap();
+        section.extend(serde_json::to_vec(&second).unwrap());
+        let wasm = wasm_with_custom_section(
+            ProcBlockDescriptor::CUSTOM_SECTION_NAME,
+            &section,
+        );
+
+        let got = embedded_descriptors(&wasm);
+
+        assert_eq!(got.len(), 2);
+        assert_eq!(got["Normalize"], first);
+        assert_eq!(got["Fft"], second);
+    }
+
+    fn wasm_with_custom_section(name: &str, data: &[u8]) -> Vec<u8> {
+        let mut payload = Vec::new();
+        leb128(&mut payload, name.len());
+        payload.extend(name.as_bytes());
+        payload.extend(data);
+
+        let mut wasm = b"\0asm\x01\0\0\0".to_vec();
+        wasm.push(0);
+        leb128(&mut wasm, payload.len());
+        wasm.extend(payload);
+
+        wasm
+    }
+
+    fn leb128(buffer: &mut Vec<u8>, mut value: usize) {
+        loop {
+            let byte = (value & 0x7f) as u8;
+            value >>= 7;
+
+            if value == 0 {
+                buffer.push(byte);
+                return;
+            }
+
+            buffer.push(byte | 0x80);
+        }
+    }
+}
@@ -0,0 +1,230 @@
+use std::{
+    io::{Error, ErrorKind},
+    path::Path,
+    process::{Command, Stdio},
+};
+
+use codespan::Span;
+use codespan_reporting::diagnostic::{Diagnostic, Label};
+use legion::{world::SubWorld, Query};
+
+use crate::{
+    lowering::{Name, ProcBlock, ProcBlockLocation},
+    BuildContext, Diagnostics,
+};
+
+const PROC_BLOCKS_CRATE: &str = "hotg-rune-proc-blocks";
+
+/// Make sure every proc block was built against the same version of
+/// `hotg-rune-proc-blocks` as the Rune before we try to compile it.
+///
+/// Otherwise the proc block would implement a different copy of the
+/// `ProcBlock` and `Transform` traits, and the generated code would fail to
+/// compile with errors that don't mention the proc block at all.
+#[legion::system]
+pub(crate) fn run(
+    world: &SubWorld,
+    #[resource] ctx: &BuildContext,
+    #[resource] diags: &mut Diagnostics,
+    proc_blocks: &mut Query<(&Name, &Span, &ProcBlock)>,
+) {
+    let metadata = match cargo_metadata(&ctx.working_directory) {
+        Ok(m) => m,
+        Err(e) => {
+            log::warn!(
+                "Unable to check which version of \"{}\" each proc block \
+                 uses: {}",
+                PROC_BLOCKS_CRATE,
+                e
+            );
+            return;
+        },
+    };
+
+    for (name, &span, proc_block) in proc_blocks.iter(world) {
+        if proc_block.location != ProcBlockLocation::Embedded {
+            continue;
+        }
+
+        if let Some((expected, actual)) =
+            metadata.incompatible_version(proc_block.name())
+        {
+            let diag = incompatible_version_diagnostic(
+                name, span, proc_block, expected, actual,
+            );
+            diags.push(diag);
+        }
+    }
+}
+
+fn cargo_metadata(working_directory: &Path) -> Result<Metadata, Error> {
+    let mut cmd = Command::new("cargo");
+    cmd.arg("metadata")
+        .arg("--format-version=1")
+        .arg("--manifest-path")
+        .arg(working_directory.join("Cargo.toml"))
+        .current_dir(working_directory)
+        .stderr(Stdio::piped());
+
+    log::debug!("Executing {:?}", cmd);
+
+    let output = cmd.output()?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(Error::new(ErrorKind::Other, stderr.trim().to_string()));
+    }
+
+    let metadata = serde_json::from_slice(&output.stdout)?;
+    Ok(metadata)
+}
+
+fn incompatible_version_diagnostic(
+    name: &Name,
+    span: Span,
+    proc_block: &ProcBlock,
+    expected: &str,
+    actual: &str,
+) -> Diagnostic<()> {
+    let msg = format!(
+        "The \"{}\" proc block was built for Rune {}, but this is Rune {}",
+        name, actual, expected
+    );
+
+    Diagnostic::error()
+        .with_message(msg)
+        .with_labels(vec![Label::primary((), span)])
+        .with_notes(vec![format!(
+            "hint: use a version of \"{}\" which depends on {} {}",
+            proc_block.path, PROC_BLOCKS_CRATE, expected
+        )])
+}
+
+/// The bits we care about from `cargo metadata`'s output.
+#[derive(Debug, serde::Deserialize)]
+struct Metadata {
+    packages: Vec<Package>,
+    resolve: Option<Resolve>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct Package {
+    id: String,
+    name: String,
+    version: String,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct Resolve {
+    root: Option<String>,
+    nodes: Vec<Node>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct Node {
+    id: String,
+    /// The IDs of this package's dependencies.
+    dependencies: Vec<String>,
+}
+
+impl Metadata {
+    /// If the proc block uses a different `hotg-rune-proc-blocks` to the
+    /// Rune, get the version the Rune expects and the version the proc block
+    /// was built against.
+    fn incompatible_version(&self, proc_block: &str) -> Option<(&str, &str)> {
+        let root = self.resolve.as_ref()?.root.as_deref()?;
+        let expected = self.dependency(root, PROC_BLOCKS_CRATE)?;
+        let proc_block = self.dependency(root, proc_block)?;
+        let actual = self.dependency(&proc_block.id, PROC_BLOCKS_CRATE)?;
+
+        if actual.id == expected.id {
+            None
+        } else {
+            Some((&expected.version, &actual.version))
+        }
+    }
+
+    /// Find the package called `name` that the package with this `id`
+    /// depends on.
+    fn dependency(&self, id: &str, name: &str) -> Option<&Package> {
+        let node = self.resolve.as_ref()?.nodes.iter().find(|n| n.id == id)?;
+
+        node.dependencies
+            .iter()
+            .filter_map(|dep| self.packages.iter().find(|p| p.id == *dep))
+            .find(|p| p.name == name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata(normalize_uses: &str) -> Metadata {
+        let json = serde_json::json!({
+            "packages": [
+                { "id": "rune 0.0.0", "name": "rune", "version": "0.0.0" },
+                {
+                    "id": "hotg-rune-proc-blocks 0.11.3",
+                    "name": "hotg-rune-proc-blocks",
+                    "version": "0.11.3",
+                },
+                {
+                    "id": "hotg-rune-proc-blocks 0.3.0",
+                    "name": "hotg-rune-proc-blocks",
+                    "version": "0.3.0",
+                },
+                {
+                    "id": "normalize 0.1.0",
+                    "name": "normalize",
+                    "version": "0.1.0",
+                },
+            ],
+            "resolve": {
+                "root": "rune 0.0.0",
+                "nodes": [
+                    {
+                        "id": "rune 0.0.0",
+                        "dependencies": [
+                            "hotg-rune-proc-blocks 0.11.3",
+                            "normalize 0.1.0",
+                        ],
+                    },
+                    {
+                        "id": "normalize 0.1.0",
+                        "dependencies": [normalize_uses],
+                    },
+                ],
+            },
+        });
+
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn proc_blocks_using_the_same_crate_are_compatible() {
+        let metadata = metadata("hotg-rune-proc-blocks 0.11.3");
+
+        let got = metadata.incompatible_version("normalize");
+
+        assert_eq!(got, None);
+    }
+
+    #[test]
+    fn detect_proc_blocks_built_for_a_different_version() {
+        let metadata = metadata("hotg-rune-proc-blocks 0.3.0");
+
+        let got = metadata.incompatible_version("normalize");
+
+        assert_eq!(got, Some(("0.11.3", "0.3.0")));
+    }
+
+    #[test]
+    fn unknown_proc_blocks_are_ignored() {
+        let metadata = metadata("hotg-rune-proc-blocks 0.3.0");
+
+        let got = metadata.incompatible_version("fft");
+
+        assert_eq!(got, None);
+    }
+}
@@ -30,6 +30,8 @@ pub struct CompilationResult(pub Result<CompiledBinary, CompileError>);
 
 #[derive(Debug)]
 pub enum CompileError {
+    /// Compilation was skipped because of earlier errors.
+    Aborted,
     BuildFailed(ExitStatus),
     DidntStart(std::io::Error),
     UnableToReadBinary {
@@ -41,6 +43,9 @@ pub enum CompileError {
 impl Display for CompileError {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
+            CompileError::Aborted => {
+                f.write_str("Compilation was aborted because of earlier errors")
+            },
             CompileError::BuildFailed(exit) => match exit.code() {
                 Some(code) => {
                     write!(f, "Compilation failed with exit code {}", code,)
@@ -60,7 +65,7 @@ impl Display for CompileError {
 impl Error for CompileError {
     fn source(&self) -> Option<&(dyn Error + 'static)> {
         match self {
-            CompileError::BuildFailed(_) => None,
+            CompileError::Aborted | CompileError::BuildFailed(_) => None,
             CompileError::DidntStart(e) => Some(e),
             CompileError::UnableToReadBinary { error, .. } => Some(error),
         }
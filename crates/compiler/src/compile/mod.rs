@@ -1,4 +1,6 @@
 mod cargo_build;
+mod check_proc_block_descriptors;
+mod check_proc_block_versions;
 mod components;
 mod write_project_to_disk;
 
@@ -8,5 +10,7 @@ use crate::Phase;
 pub fn phase() -> Phase {
     Phase::new()
         .and_then(write_project_to_disk::run_system)
+        .and_then(check_proc_block_versions::run_system)
         .and_then(cargo_build::run_system)
+        .and_then(check_proc_block_descriptors::run_system)
 }
@@ -37,6 +37,8 @@ pub mod hooks;
 pub mod lowering;
 pub mod parse;
 mod phases;
+mod python;
+pub mod registry;
 pub mod serialize;
 mod toolchain;
 pub mod type_check;
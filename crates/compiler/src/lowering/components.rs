@@ -28,6 +28,8 @@ pub struct Sink {
 pub enum SinkKind {
     Serial,
     Tensor,
+    Mqtt,
+    Ble,
     Other(String),
 }
 
@@ -36,6 +38,8 @@ impl Display for SinkKind {
         match self {
             SinkKind::Serial => write!(f, "serial"),
             SinkKind::Tensor => write!(f, "tensor"),
+            SinkKind::Mqtt => write!(f, "mqtt"),
+            SinkKind::Ble => write!(f, "ble"),
             SinkKind::Other(s) => write!(f, "{}", s),
         }
     }
@@ -46,6 +50,8 @@ impl<'a> From<&'a str> for SinkKind {
         match s {
             "serial" | "SERIAL" => SinkKind::Serial,
             "tensor" | "TENSOR" => SinkKind::Tensor,
+            "mqtt" | "MQTT" => SinkKind::Mqtt,
+            "ble" | "BLE" => SinkKind::Ble,
             _ => SinkKind::Other(s.to_string()),
         }
     }
@@ -57,6 +63,33 @@ impl<'a> From<&'a str> for SinkKind {
 pub struct Model {
     pub model_file: ModelFile,
     pub args: IndexMap<String, ResourceOrString>,
+    #[serde(default)]
+    pub location: ModelLocation,
+    /// The model file was encrypted ahead of time and the host will need
+    /// to decrypt it.
+    #[serde(default)]
+    pub encrypted: bool,
+    /// Alternative model files the host can pick between when the Rune is
+    /// loaded, keyed by the variant's name.
+    #[serde(default)]
+    pub variants: IndexMap<String, PathBuf>,
+}
+
+/// Where a model gets run.
+#[derive(
+    Debug, Copy, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum ModelLocation {
+    /// The model is embedded in the Rune.
+    Embedded,
+    /// The model's weights are left out of the Rune and the host provides
+    /// the model file and runs inference with its own engine.
+    Host,
+}
+
+impl Default for ModelLocation {
+    fn default() -> Self { ModelLocation::Embedded }
 }
 
 /// Where to load a model from.
@@ -67,6 +100,9 @@ pub enum ModelFile {
     FromDisk(PathBuf),
     /// Load the model from a resource embedded/injected into the Rune.
     Resource(Entity),
+    /// Download the model at build time, making sure it has the expected
+    /// SHA-256 hash.
+    Url { url: String, sha256: String },
 }
 
 /// Something which can generate data.
@@ -86,6 +122,17 @@ pub enum SourceKind {
     Image,
     Raw,
     FloatImage,
+    Gps,
+    Gyroscope,
+    Thermometer,
+    Battery,
+    Barometer,
+    Proximity,
+    Light,
+    Video,
+    Tensor,
+    Tabular,
+    Signal,
     Other(String),
 }
 
@@ -102,6 +149,25 @@ impl SourceKind {
             SourceKind::FloatImage => {
                 Some(hotg_rune_core::capabilities::FLOAT_IMAGE)
             },
+            SourceKind::Gps => Some(hotg_rune_core::capabilities::GPS),
+            SourceKind::Gyroscope => Some(hotg_rune_core::capabilities::GYRO),
+            SourceKind::Thermometer => {
+                Some(hotg_rune_core::capabilities::THERMOMETER)
+            },
+            SourceKind::Battery => Some(hotg_rune_core::capabilities::BATTERY),
+            SourceKind::Barometer => {
+                Some(hotg_rune_core::capabilities::BAROMETER)
+            },
+            SourceKind::Proximity => {
+                Some(hotg_rune_core::capabilities::PROXIMITY)
+            },
+            SourceKind::Light => Some(hotg_rune_core::capabilities::LIGHT),
+            SourceKind::Video => Some(hotg_rune_core::capabilities::VIDEO),
+            SourceKind::Tensor => Some(hotg_rune_core::capabilities::TENSOR),
+            SourceKind::Tabular => {
+                Some(hotg_rune_core::capabilities::TABULAR)
+            },
+            SourceKind::Signal => Some(hotg_rune_core::capabilities::SIGNAL),
             _ => None,
         }
     }
@@ -121,6 +187,17 @@ impl<'a> From<&'a str> for SourceKind {
             "image" | "IMAGE" => SourceKind::Image,
             "raw" | "RAW" => SourceKind::Raw,
             "float-image" | "FLOAT_IMAGE" => SourceKind::FloatImage,
+            "gps" | "GPS" => SourceKind::Gps,
+            "gyro" | "gyroscope" | "GYRO" => SourceKind::Gyroscope,
+            "thermometer" | "THERMOMETER" => SourceKind::Thermometer,
+            "battery" | "BATTERY" => SourceKind::Battery,
+            "barometer" | "BAROMETER" => SourceKind::Barometer,
+            "proximity" | "PROXIMITY" => SourceKind::Proximity,
+            "light" | "LIGHT" => SourceKind::Light,
+            "video" | "VIDEO" => SourceKind::Video,
+            "tensor" | "TENSOR" => SourceKind::Tensor,
+            "tabular" | "csv" | "TABULAR" => SourceKind::Tabular,
+            "signal" | "SIGNAL" => SourceKind::Signal,
             _ => SourceKind::Other(s.to_string()),
         }
     }
@@ -140,6 +217,26 @@ impl Display for SourceKind {
 pub struct ProcBlock {
     pub path: Path,
     pub parameters: IndexMap<String, ResourceOrString>,
+    #[serde(default)]
+    pub location: ProcBlockLocation,
+}
+
+/// Where a proc block's code comes from.
+#[derive(
+    Debug, Copy, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum ProcBlockLocation {
+    /// The proc block is compiled into the Rune.
+    Embedded,
+    /// The proc block is compiled to a standalone WebAssembly module which
+    /// the host loads at runtime, so it can be swapped out without
+    /// rebuilding the Rune.
+    Host,
+}
+
+impl Default for ProcBlockLocation {
+    fn default() -> Self { ProcBlockLocation::Embedded }
 }
 
 impl ProcBlock {
@@ -147,8 +244,21 @@ impl ProcBlock {
     pub(crate) fn name(&self) -> &str {
         let full_name = self.path.sub_path.as_ref().unwrap_or(&self.path.base);
         let start_of_name = full_name.rfind('/').map(|ix| ix + 1).unwrap_or(0);
+        let name = &full_name[start_of_name..];
 
-        &full_name[start_of_name..]
+        if self.is_python() {
+            name.trim_end_matches(".py")
+        } else {
+            name
+        }
+    }
+
+    /// Is this a local `*.py` file which needs to be translated into a Rust
+    /// crate?
+    pub(crate) fn is_python(&self) -> bool {
+        self.path.base.starts_with('.')
+            && self.path.base.ends_with(".py")
+            && self.path.sub_path.is_none()
     }
 }
 
@@ -301,6 +411,52 @@ impl Deref for ModelData {
     fn deref(&self) -> &Self::Target { &self.0 }
 }
 
+/// The data for each of a model's [`Model::variants`].
+#[derive(
+    Debug, Default, Clone, PartialEq, serde::Serialize, serde::Deserialize,
+)]
+pub struct VariantData(pub IndexMap<String, ModelData>);
+
+/// Metadata embedded in a TensorFlow Lite model by the [TFLite Metadata
+/// Writer][writer].
+///
+/// [writer]: https://www.tensorflow.org/lite/models/convert/metadata
+#[derive(
+    Debug, Default, Clone, PartialEq, serde::Serialize, serde::Deserialize,
+)]
+pub struct ModelMetadata {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub version: Option<String>,
+    pub author: Option<String>,
+    pub license: Option<String>,
+    pub inputs: Vec<TensorMetadata>,
+    pub outputs: Vec<TensorMetadata>,
+    /// The labels for the model's output (e.g. from a `labels.txt` file
+    /// packed into the model).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub labels: Vec<String>,
+}
+
+/// Metadata about one of a model's input or output tensors.
+#[derive(
+    Debug, Default, Clone, PartialEq, serde::Serialize, serde::Deserialize,
+)]
+pub struct TensorMetadata {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    /// The mean to subtract from each channel when normalizing inputs.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mean: Vec<f32>,
+    /// The standard deviation to divide each channel by when normalizing
+    /// inputs.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub std: Vec<f32>,
+    /// The names of any files (e.g. labels) associated with this tensor.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub associated_files: Vec<String>,
+}
+
 #[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct Mimetype(Cow<'static, str>);
 
@@ -313,6 +469,17 @@ impl Mimetype {
         Mimetype(Cow::Borrowed(hotg_rune_core::TFJS_MIMETYPE));
     pub const TENSORFLOW_LITE: Mimetype =
         Mimetype(Cow::Borrowed(hotg_rune_core::TFLITE_MIMETYPE));
+    pub const TORCHSCRIPT: Mimetype =
+        Mimetype(Cow::Borrowed(hotg_rune_core::TORCHSCRIPT_MIMETYPE));
+
+    /// Guess a model's format from its file extension, defaulting to
+    /// TensorFlow Lite.
+    pub fn from_path(path: &std::path::Path) -> Mimetype {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("pt") | Some("torchscript") => Mimetype::TORCHSCRIPT,
+            _ => Mimetype::default(),
+        }
+    }
 }
 
 impl Deref for Mimetype {
@@ -0,0 +1,218 @@
+//! Convert TensorFlow SavedModels and frozen graphs to TensorFlow Lite so
+//! they can be used like any other model.
+
+use std::{
+    ffi::{OsStr, OsString},
+    path::Path,
+    process::Command,
+};
+
+use codespan::Span;
+use codespan_reporting::diagnostic::{Diagnostic, Label};
+use indexmap::IndexMap;
+
+use crate::lowering::{Mimetype, Name, ResourceOrString};
+
+/// The environment variable used to override which TFLite converter is used.
+pub const TFLITE_CONVERT_ENV: &str = "RUNE_TFLITE_CONVERT";
+
+/// A model which needs to be converted before it can be embedded in a Rune.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum TensorFlowModel {
+    /// A directory containing a `saved_model.pb`.
+    SavedModel,
+    /// A frozen `GraphDef` (`*.pb`), which also needs the `input_arrays` and
+    /// `output_arrays` arguments.
+    FrozenGraph,
+}
+
+impl TensorFlowModel {
+    /// Check whether a model should be converted.
+    ///
+    /// Models explicitly marked as `format: tensorflow` are always converted,
+    /// while models using the default format are only converted if they
+    /// look like a SavedModel or frozen graph.
+    pub(crate) fn detect(path: &Path, mimetype: &Mimetype) -> Option<Self> {
+        let is_tensorflow = *mimetype == Mimetype::TENSORFLOW;
+
+        if !is_tensorflow && *mimetype != Mimetype::TENSORFLOW_LITE {
+            return None;
+        }
+
+        if path.join("saved_model.pb").is_file() {
+            Some(TensorFlowModel::SavedModel)
+        } else if path.extension().map_or(false, |ext| ext == "pb") {
+            Some(TensorFlowModel::FrozenGraph)
+        } else if is_tensorflow && path.is_dir() {
+            Some(TensorFlowModel::SavedModel)
+        } else {
+            None
+        }
+    }
+}
+
+/// Run the TFLite converter, returning the converted model's bytes.
+pub(crate) fn convert(
+    kind: TensorFlowModel,
+    model: &Path,
+    args: &IndexMap<String, ResourceOrString>,
+    working_directory: &Path,
+    name: &Name,
+    span: Span,
+) -> Result<Vec<u8>, Diagnostic<()>> {
+    let output = working_directory
+        .join("models")
+        .join(format!("{}.tflite", name));
+
+    if let Some(parent) = output.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| {
+            conversion_diagnostic(name, span, e.to_string())
+        })?;
+    }
+
+    let converter = std::env::var_os(TFLITE_CONVERT_ENV)
+        .unwrap_or_else(|| OsString::from("tflite_convert"));
+    let mut cmd = Command::new(&converter);
+    cmd.args(
+        converter_args(kind, model, &output, args)
+            .map_err(|msg| conversion_diagnostic(name, span, msg))?,
+    );
+
+    log::debug!("Executing {:?}", cmd);
+
+    let result = cmd.output().map_err(|e| {
+        conversion_diagnostic(
+            name,
+            span,
+            format!(
+                "Unable to run {:?} ({}). Is TensorFlow installed? The \
+                 converter can be set with the {} environment variable.",
+                converter, e, TFLITE_CONVERT_ENV
+            ),
+        )
+    })?;
+
+    if !result.status.success() {
+        let stderr = String::from_utf8_lossy(&result.stderr);
+        return Err(conversion_diagnostic(
+            name,
+            span,
+            format!("The converter failed ({}): {}", result.status, stderr),
+        ));
+    }
+
+    std::fs::read(&output)
+        .map_err(|e| conversion_diagnostic(name, span, e.to_string()))
+}
+
+fn converter_args(
+    kind: TensorFlowModel,
+    model: &Path,
+    output: &Path,
+    args: &IndexMap<String, ResourceOrString>,
+) -> Result<Vec<OsString>, String> {
+    let mut cmd_args = vec![flag("output_file", output)];
+
+    match kind {
+        TensorFlowModel::SavedModel => {
+            cmd_args.push(flag("saved_model_dir", model));
+        },
+        TensorFlowModel::FrozenGraph => {
+            cmd_args.push(flag("graph_def_file", model));
+
+            for name in ["input_arrays", "output_arrays"] {
+                match args.get(name) {
+                    Some(ResourceOrString::String(value)) => {
+                        cmd_args.push(flag(name, value));
+                    },
+                    _ => {
+                        return Err(format!(
+                            "Frozen graphs need the \"{}\" argument",
+                            name
+                        ))
+                    },
+                }
+            }
+        },
+    }
+
+    Ok(cmd_args)
+}
+
+fn flag(name: &str, value: impl AsRef<OsStr>) -> OsString {
+    let mut arg = OsString::from(format!("--{}=", name));
+    arg.push(value);
+    arg
+}
+
+fn conversion_diagnostic(
+    name: &Name,
+    span: Span,
+    msg: impl std::fmt::Display,
+) -> Diagnostic<()> {
+    Diagnostic::error()
+        .with_message(format!(
+            "Unable to convert \"{}\" to TensorFlow Lite: {}",
+            name, msg
+        ))
+        .with_labels(vec![Label::primary((), span)])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_tensorflow_models_are_converted() {
+        let graph = Path::new("model.pb");
+
+        assert_eq!(
+            TensorFlowModel::detect(graph, &Mimetype::default()),
+            Some(TensorFlowModel::FrozenGraph)
+        );
+        assert_eq!(TensorFlowModel::detect(graph, &Mimetype::ONNX), None);
+        let tflite = Path::new("model.tflite");
+        assert_eq!(TensorFlowModel::detect(tflite, &Mimetype::default()), None);
+    }
+
+    #[test]
+    fn frozen_graphs_need_their_inputs_and_outputs() {
+        let mut args = IndexMap::new();
+        args.insert(
+            String::from("input_arrays"),
+            ResourceOrString::String(String::from("input")),
+        );
+
+        let err = converter_args(
+            TensorFlowModel::FrozenGraph,
+            Path::new("model.pb"),
+            Path::new("out.tflite"),
+            &args,
+        )
+        .unwrap_err();
+        assert!(err.contains("output_arrays"));
+
+        args.insert(
+            String::from("output_arrays"),
+            ResourceOrString::String(String::from("Identity")),
+        );
+        let got = converter_args(
+            TensorFlowModel::FrozenGraph,
+            Path::new("model.pb"),
+            Path::new("out.tflite"),
+            &args,
+        )
+        .unwrap();
+
+        let got: Vec<_> = got.iter().map(|arg| arg.to_str().unwrap()).collect();
+        assert_eq!(
+            got,
+            vec![
+                "--output_file=out.tflite",
+                "--graph_def_file=model.pb",
+                "--input_arrays=input",
+                "--output_arrays=Identity",
+            ]
+        );
+    }
+}
@@ -0,0 +1,168 @@
+//! Download models referenced by URL, caching them under `~/.rune/cache`.
+
+use std::{
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use codespan::Span;
+use codespan_reporting::diagnostic::{Diagnostic, Label};
+use sha2::{Digest, Sha256};
+
+use crate::lowering::Name;
+
+/// The environment variable used to override where downloaded models are
+/// cached.
+const CACHE_DIR_ENV: &str = "RUNE_CACHE_DIR";
+
+/// Does this look like a model which should be downloaded?
+pub(crate) fn is_url(path: &str) -> bool { path.starts_with("https://") }
+
+/// Get the model at `url`, making sure its SHA-256 hash is `sha256`.
+///
+/// Models are cached by hash, so they only get downloaded once.
+pub(crate) fn fetch(
+    url: &str,
+    sha256: &str,
+    name: &Name,
+    span: Span,
+) -> Result<Vec<u8>, Diagnostic<()>> {
+    let cached = cache_dir().map(|dir| dir.join(sha256.to_lowercase()));
+
+    if let Some(cached) = &cached {
+        if let Ok(data) = std::fs::read(cached) {
+            if hash_matches(&data, sha256) {
+                log::debug!(
+                    "Using the cached copy of \"{}\" at \"{}\"",
+                    url,
+                    cached.display()
+                );
+                return Ok(data);
+            }
+        }
+    }
+
+    log::info!("Downloading \"{}\" for \"{}\"", url, name);
+    let data = download(url)
+        .map_err(|e| download_failed_diagnostic(url, name, &*e, span))?;
+
+    if !hash_matches(&data, sha256) {
+        let diag = checksum_mismatch_diagnostic(url, name, sha256, &data, span);
+        return Err(diag);
+    }
+
+    if let Some(cached) = &cached {
+        if let Err(e) = save(cached, &data) {
+            log::warn!(
+                "Unable to cache \"{}\" at \"{}\": {}",
+                url,
+                cached.display(),
+                e
+            );
+        }
+    }
+
+    Ok(data)
+}
+
+fn cache_dir() -> Option<PathBuf> {
+    match std::env::var_os(CACHE_DIR_ENV) {
+        Some(dir) => Some(PathBuf::from(dir)),
+        None => dirs::home_dir().map(|home| home.join(".rune").join("cache")),
+    }
+}
+
+fn download(url: &str) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let response = ureq::get(url).call()?;
+
+    let mut data = Vec::new();
+    response.into_reader().read_to_end(&mut data)?;
+
+    Ok(data)
+}
+
+/// Write to a temporary file first so an interrupted build never leaves a
+/// truncated model in the cache.
+fn save(path: &Path, data: &[u8]) -> Result<(), std::io::Error> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let temp = path.with_extension("partial");
+    std::fs::write(&temp, data)?;
+    std::fs::rename(&temp, path)
+}
+
+fn sha256_hex(data: &[u8]) -> String { format!("{:x}", Sha256::digest(data)) }
+
+fn hash_matches(data: &[u8], expected: &str) -> bool {
+    sha256_hex(data).eq_ignore_ascii_case(expected.trim())
+}
+
+fn download_failed_diagnostic(
+    url: &str,
+    name: &Name,
+    e: &dyn std::error::Error,
+    span: Span,
+) -> Diagnostic<()> {
+    let msg =
+        format!("Unable to download \"{}\" for \"{}\": {}", url, name, e);
+
+    Diagnostic::error()
+        .with_message(msg)
+        .with_labels(vec![Label::primary((), span)])
+}
+
+fn checksum_mismatch_diagnostic(
+    url: &str,
+    name: &Name,
+    expected: &str,
+    data: &[u8],
+    span: Span,
+) -> Diagnostic<()> {
+    let msg = format!(
+        "The model downloaded from \"{}\" for \"{}\" doesn't match its sha256",
+        url, name
+    );
+
+    Diagnostic::error()
+        .with_message(msg)
+        .with_labels(vec![Label::primary((), span)])
+        .with_notes(vec![
+            format!("expected: {}", expected),
+            format!("found: {}", sha256_hex(data)),
+        ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checksums_are_case_insensitive() {
+        let hash =
+            "2CF24DBA5FB0A30E26E83B2AC5B9E29E1B161E5C1FA7425E73043362938B9824";
+
+        assert!(hash_matches(b"hello", hash));
+        assert!(hash_matches(b"hello", &hash.to_lowercase()));
+        assert!(!hash_matches(b"world", hash));
+    }
+
+    #[test]
+    fn cached_models_are_used_without_downloading() {
+        let dir = std::env::temp_dir().join("rune-fetch-model-test");
+        let hash = sha256_hex(b"cached model");
+        save(&dir.join(&hash), b"cached model").unwrap();
+        std::env::set_var(CACHE_DIR_ENV, &dir);
+
+        let got = fetch(
+            "https://example.invalid/model.tflite",
+            &hash,
+            &Name::from("model"),
+            Span::default(),
+        )
+        .unwrap();
+
+        assert_eq!(got, b"cached model");
+    }
+}
@@ -1,8 +1,13 @@
 use codespan::Span;
+use indexmap::IndexMap;
 use legion::{systems::CommandBuffer, Entity};
 
 use crate::{
-    lowering::{Model, ModelData, ModelFile, Name},
+    lowering::{
+        convert_tensorflow::{self, TensorFlowModel},
+        fetch_model, tflite_metadata, Mimetype, Model, ModelData, ModelFile,
+        Name, VariantData,
+    },
     BuildContext, Diagnostics,
 };
 
@@ -14,20 +19,99 @@ pub(crate) fn run(
     &entity: &Entity,
     name: &Name,
     model: &Model,
+    mimetype: &Mimetype,
     &span: &Span,
 ) {
     match &model.model_file {
         ModelFile::FromDisk(path) => {
-            match super::load_resource_data::load(
-                &build_ctx.current_directory,
-                path,
-                name,
-                span,
-            ) {
-                Ok(data) => cmd.add_component(entity, ModelData::from(data)),
+            let full_path = build_ctx.current_directory.join(path);
+
+            // Encrypted models are opaque until the host decrypts them
+            let mut is_tflite =
+                *mimetype == Mimetype::TENSORFLOW_LITE && !model.encrypted;
+
+            let loaded = match TensorFlowModel::detect(&full_path, mimetype) {
+                Some(kind) => {
+                    is_tflite = true;
+                    // The runtime only knows how to run TensorFlow Lite
+                    cmd.add_component(entity, Mimetype::TENSORFLOW_LITE);
+                    convert_tensorflow::convert(
+                        kind,
+                        &full_path,
+                        &model.args,
+                        &build_ctx.working_directory,
+                        name,
+                        span,
+                    )
+                },
+                None => super::load_resource_data::load(
+                    &build_ctx.current_directory,
+                    path,
+                    name,
+                    span,
+                ),
+            };
+
+            match loaded {
+                Ok(data) => add_model_data(cmd, entity, data, is_tflite),
+                Err(diag) => diags.push(diag),
+            }
+        },
+        ModelFile::Url { url, sha256 } => {
+            let is_tflite =
+                *mimetype == Mimetype::TENSORFLOW_LITE && !model.encrypted;
+
+            match fetch_model::fetch(url, sha256, name, span) {
+                Ok(data) => add_model_data(cmd, entity, data, is_tflite),
                 Err(diag) => diags.push(diag),
             }
         },
         ModelFile::Resource(_) => {},
     }
+
+    if !model.variants.is_empty() {
+        load_variants(cmd, diags, build_ctx, entity, model, name, span);
+    }
+}
+
+fn load_variants(
+    cmd: &mut CommandBuffer,
+    diags: &mut Diagnostics,
+    build_ctx: &BuildContext,
+    entity: Entity,
+    model: &Model,
+    name: &Name,
+    span: Span,
+) {
+    let mut variants = IndexMap::new();
+
+    for (variant, path) in &model.variants {
+        match super::load_resource_data::load(
+            &build_ctx.current_directory,
+            path,
+            name,
+            span,
+        ) {
+            Ok(data) => {
+                variants.insert(variant.clone(), ModelData::from(data));
+            },
+            Err(diag) => diags.push(diag),
+        }
+    }
+
+    cmd.add_component(entity, VariantData(variants));
+}
+
+fn add_model_data(
+    cmd: &mut CommandBuffer,
+    entity: Entity,
+    data: Vec<u8>,
+    is_tflite: bool,
+) {
+    if is_tflite {
+        if let Some(meta) = tflite_metadata::extract(&data) {
+            cmd.add_component(entity, meta);
+        }
+    }
+    cmd.add_component(entity, ModelData::from(data));
 }
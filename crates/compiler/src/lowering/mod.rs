@@ -1,12 +1,15 @@
 //! The lowering phase.
 
 mod components;
+mod convert_tensorflow;
+mod fetch_model;
 mod load_model_data;
 mod load_resource_data;
 mod register_names;
 mod register_resources;
 mod register_stages;
 mod register_tensors;
+mod tflite_metadata;
 mod update_nametable;
 
 pub use components::*;
@@ -25,6 +28,7 @@ pub fn phase() -> Phase {
     .and_then(register_tensors::run_system)
     .and_then(load_resource_data::run_system)
     .and_then(load_model_data::run_system)
+    .and_then(tflite_metadata::run_system)
 }
 
 pub(crate) fn register_components(registry: &mut Registry<String>) {
@@ -32,11 +36,13 @@ pub(crate) fn register_components(registry: &mut Registry<String>) {
         .register_with_type_name::<Inputs>()
         .register_with_type_name::<Model>()
         .register_with_type_name::<ModelFile>()
+        .register_with_type_name::<ModelLocation>()
         .register_with_type_name::<Name>()
         .register_with_type_name::<NameTable>()
         .register_with_type_name::<Outputs>()
         .register_with_type_name::<PipelineNode>()
         .register_with_type_name::<ProcBlock>()
+        .register_with_type_name::<ProcBlockLocation>()
         .register_with_type_name::<Resource>()
         .register_with_type_name::<ResourceSource>()
         .register_with_type_name::<Sink>()
@@ -46,5 +52,7 @@ pub(crate) fn register_components(registry: &mut Registry<String>) {
         .register_with_type_name::<Tensor>()
         .register_with_type_name::<ResourceData>()
         .register_with_type_name::<Mimetype>()
-        .register_with_type_name::<ModelData>();
+        .register_with_type_name::<ModelData>()
+        .register_with_type_name::<VariantData>()
+        .register_with_type_name::<ModelMetadata>();
 }
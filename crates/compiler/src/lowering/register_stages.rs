@@ -1,11 +1,14 @@
+use std::path::{Path, PathBuf};
+
 use codespan_reporting::diagnostic::{Diagnostic, Label};
 use indexmap::IndexMap;
 use legion::{systems::CommandBuffer, world::SubWorld, Entity, Query};
 
 use crate::{
     lowering::{
-        self, Mimetype, Model, ModelFile, NameTable, ProcBlock, Resource,
-        ResourceData, Sink, Source,
+        self, fetch_model, Mimetype, Model, ModelFile, ModelLocation,
+        NameTable, ProcBlock, ProcBlockLocation, Resource, ResourceData, Sink,
+        Source,
     },
     parse::{
         self, CapabilityStage, DocumentV1, ModelStage, OutStage,
@@ -32,7 +35,7 @@ pub(crate) fn run(
             None => continue,
         };
 
-        let args = match translate_args(stage.args(), names) {
+        let mut args = match translate_args(stage.args(), names) {
             Ok(a) => a,
             Err(diag) => {
                 diags.push(diag);
@@ -41,10 +44,17 @@ pub(crate) fn run(
         };
 
         match stage {
-            parse::Stage::Model(ModelStage { model, .. }) => {
-                match register_model(names, name, model, &args, |e: Entity| {
-                    resources.get(world, e).ok()
-                }) {
+            parse::Stage::Model(ModelStage {
+                model, variants, ..
+            }) => {
+                match register_model(
+                    names,
+                    name,
+                    model,
+                    variants,
+                    &args,
+                    |e: Entity| resources.get(world, e).ok(),
+                ) {
                     Ok((model, mimetype)) => {
                         cmd.add_component(ent, model);
                         cmd.add_component(ent, mimetype);
@@ -53,18 +63,30 @@ pub(crate) fn run(
                 }
             },
             parse::Stage::ProcBlock(ProcBlockStage { proc_block, .. }) => {
-                if proc_block.version.is_none() {
+                let is_python = proc_block.base.starts_with('.')
+                    && proc_block.base.ends_with(".py");
+
+                if proc_block.version.is_none() && !is_python {
                     let diag = warn_on_unversioned_proc_block_diagnostic(
                         name, proc_block,
                     );
                     diags.push(diag);
                 }
 
+                let location = match proc_block_location(name, &mut args) {
+                    Ok(location) => location,
+                    Err(diag) => {
+                        diags.push(diag);
+                        continue;
+                    },
+                };
+
                 cmd.add_component(
                     ent,
                     ProcBlock {
                         path: proc_block.clone(),
                         parameters: args,
+                        location,
                     },
                 )
             },
@@ -139,13 +161,12 @@ fn register_model<'a>(
     names: &NameTable,
     node_name: &str,
     model: &parse::ResourceOrString,
+    variants: &IndexMap<String, String>,
     args: &IndexMap<String, lowering::ResourceOrString>,
     mut get_resource: impl FnMut(Entity) -> Option<(&'a Resource, Option<&'a ResourceData>)>
         + 'a,
 ) -> Result<(Model, Mimetype), Diagnostic<()>> {
-    let (mimetype, args) = model_format_and_args(node_name, args, |e| {
-        get_resource(e).and_then(|r| r.1).cloned()
-    })?;
+    let mut args = args.clone();
 
     let model_file = match model {
         parse::ResourceOrString::Resource(resource_name) => {
@@ -153,15 +174,193 @@ fn register_model<'a>(
                 get_resource(e).map(|r| r.0)
             })?
         },
+        parse::ResourceOrString::String(s) if fetch_model::is_url(s) => {
+            ModelFile::Url {
+                url: s.clone(),
+                sha256: model_checksum(node_name, s, &mut args)?,
+            }
+        },
         parse::ResourceOrString::String(s) => ModelFile::FromDisk(s.into()),
     };
 
-    Ok((Model { model_file, args }, mimetype))
+    let default_mimetype = match &model_file {
+        ModelFile::FromDisk(path) => Mimetype::from_path(path),
+        ModelFile::Url { url, .. } => Mimetype::from_path(Path::new(url)),
+        ModelFile::Resource(_) => Mimetype::default(),
+    };
+
+    let (mimetype, mut args) =
+        model_format_and_args(node_name, &args, default_mimetype, |e| {
+            get_resource(e).and_then(|r| r.1).cloned()
+        })?;
+    let location = model_location(node_name, &mut args)?;
+    let encrypted = model_is_encrypted(node_name, &mut args)?;
+    let variants = model_variants(node_name, variants)?;
+
+    Ok((
+        Model {
+            model_file,
+            args,
+            location,
+            encrypted,
+            variants,
+        },
+        mimetype,
+    ))
+}
+
+/// Models downloaded from a URL must say what their SHA-256 hash should be,
+/// otherwise builds wouldn't be reproducible.
+fn model_checksum(
+    node_name: &str,
+    url: &str,
+    args: &mut IndexMap<String, lowering::ResourceOrString>,
+) -> Result<String, Diagnostic<()>> {
+    match args.remove("sha256") {
+        Some(lowering::ResourceOrString::String(sha256)) => Ok(sha256),
+        _ => Err(missing_checksum_diagnostic(node_name, url)),
+    }
+}
+
+fn missing_checksum_diagnostic(node_name: &str, url: &str) -> Diagnostic<()> {
+    let msg = format!(
+        "\"{}\" is downloaded from \"{}\", so it needs a \"sha256\" argument",
+        node_name, url
+    );
+
+    Diagnostic::error().with_message(msg).with_notes(vec![
+        "hint: run \"sha256sum\" on the model to get its hash".to_string(),
+    ])
+}
+
+/// Check whether the Runefile asked for the model to be run by the host
+/// (`location: host`) instead of being embedded in the Rune.
+fn model_location(
+    node_name: &str,
+    args: &mut IndexMap<String, lowering::ResourceOrString>,
+) -> Result<ModelLocation, Diagnostic<()>> {
+    match args.remove("location") {
+        None => Ok(ModelLocation::Embedded),
+        Some(lowering::ResourceOrString::String(location)) => {
+            match location.as_str() {
+                "embedded" => Ok(ModelLocation::Embedded),
+                "host" => Ok(ModelLocation::Host),
+                other => Err(unknown_location_diagnostic(node_name, other)),
+            }
+        },
+        Some(lowering::ResourceOrString::Resource(_)) => {
+            Err(unknown_location_diagnostic(node_name, "a resource"))
+        },
+    }
+}
+
+/// Check whether the Runefile asked for the proc block to be loaded by the
+/// host as a standalone WebAssembly module (`location: host`) instead of
+/// being compiled into the Rune.
+fn proc_block_location(
+    node_name: &str,
+    args: &mut IndexMap<String, lowering::ResourceOrString>,
+) -> Result<ProcBlockLocation, Diagnostic<()>> {
+    match args.remove("location") {
+        None => Ok(ProcBlockLocation::Embedded),
+        Some(lowering::ResourceOrString::String(location)) => {
+            match location.as_str() {
+                "embedded" => Ok(ProcBlockLocation::Embedded),
+                "host" => Ok(ProcBlockLocation::Host),
+                other => Err(unknown_location_diagnostic(node_name, other)),
+            }
+        },
+        Some(lowering::ResourceOrString::Resource(_)) => {
+            Err(unknown_location_diagnostic(node_name, "a resource"))
+        },
+    }
+}
+
+/// Check whether the model file was encrypted before being embedded in the
+/// Rune (`encrypted: true`).
+fn model_is_encrypted(
+    node_name: &str,
+    args: &mut IndexMap<String, lowering::ResourceOrString>,
+) -> Result<bool, Diagnostic<()>> {
+    match args.remove("encrypted") {
+        None => Ok(false),
+        Some(lowering::ResourceOrString::String(value)) => {
+            value.parse().map_err(|_| {
+                let msg = format!(
+                    "Expected \"encrypted\" to be true or false for \"{}\", \
+                     but found {:?}",
+                    node_name, value
+                );
+                Diagnostic::error().with_message(msg)
+            })
+        },
+        Some(lowering::ResourceOrString::Resource(_)) => {
+            let msg = format!(
+                "Whether \"{}\" is encrypted can't come from a resource",
+                node_name
+            );
+            Err(Diagnostic::error().with_message(msg))
+        },
+    }
+}
+
+/// Get the files for each of the model's variants, making sure their names
+/// can be used as identifiers in the generated code.
+fn model_variants(
+    node_name: &str,
+    variants: &IndexMap<String, String>,
+) -> Result<IndexMap<String, PathBuf>, Diagnostic<()>> {
+    let mut files = IndexMap::new();
+
+    for (name, path) in variants {
+        if !is_valid_variant_name(name) {
+            let msg = format!(
+                "\"{}\" isn't a valid name for one of \"{}\"'s variants",
+                name, node_name
+            );
+            return Err(Diagnostic::error().with_message(msg).with_notes(
+                vec![
+                    "hint: variant names may only contain letters, numbers, \
+                     and underscores"
+                        .to_string(),
+                ],
+            ));
+        }
+
+        files.insert(name.clone(), PathBuf::from(path));
+    }
+
+    Ok(files)
+}
+
+fn is_valid_variant_name(name: &str) -> bool {
+    let mut chars = name.chars();
+
+    match chars.next() {
+        Some(first) if first.is_ascii_alphabetic() || first == '_' => {
+            chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        },
+        _ => false,
+    }
+}
+
+fn unknown_location_diagnostic(
+    node_name: &str,
+    location: &str,
+) -> Diagnostic<()> {
+    let msg = format!(
+        "The location for \"{}\" should be either \"embedded\" or \"host\", \
+         but found {:?}",
+        node_name, location
+    );
+
+    Diagnostic::error().with_message(msg)
 }
 
 fn model_format_and_args(
     node_name: &str,
     args: &IndexMap<String, lowering::ResourceOrString>,
+    default_mimetype: Mimetype,
     get_resource_data: impl FnOnce(Entity) -> Option<ResourceData>,
 ) -> Result<
     (Mimetype, IndexMap<String, lowering::ResourceOrString>),
@@ -186,7 +385,7 @@ fn model_format_and_args(
                 },
             }
         },
-        None => Mimetype::default(),
+        None => default_mimetype,
     };
 
     Ok((mimetype, args))
@@ -207,6 +406,7 @@ fn mimetype_for_known_format(format: &str) -> Result<Mimetype, Diagnostic<()>> {
         ("tensorflow", hotg_rune_core::TF_MIMETYPE),
         ("tensorflow-js", hotg_rune_core::TFJS_MIMETYPE),
         ("tensorflow-lite", hotg_rune_core::TFLITE_MIMETYPE),
+        ("torchscript", hotg_rune_core::TORCHSCRIPT_MIMETYPE),
     ];
 
     known_formats
@@ -332,30 +532,35 @@ mod tests {
                     inputs: Vec::new(),
                     outputs: Vec::new(),
                     args: IndexMap::new(),
+                    variants: IndexMap::new(),
                 }),
                 model_from_resource: Stage::Model(ModelStage {
                     model: parse::ResourceOrString::Resource("$MODEL_FILE".parse().unwrap()),
                     inputs: Vec::new(),
                     outputs: Vec::new(),
                     args: IndexMap::new(),
+                    variants: IndexMap::new(),
                 }),
                 model_with_not_a_resource: Stage::Model(ModelStage {
                     model: parse::ResourceOrString::Resource("$cap".parse().unwrap()),
                     inputs: Vec::new(),
                     outputs: Vec::new(),
                     args: IndexMap::new(),
+                    variants: IndexMap::new(),
                 }),
                 model_with_missing_resource: Stage::Model(ModelStage {
                     model: parse::ResourceOrString::Resource("$NON_EXISTENT".parse().unwrap()),
                     inputs: Vec::new(),
                     outputs: Vec::new(),
                     args: IndexMap::new(),
+                    variants: IndexMap::new(),
                 }),
                 model_with_string_resource: Stage::Model(ModelStage {
                     model: parse::ResourceOrString::Resource("$STRING_RESOURCE".parse().unwrap()),
                     inputs: Vec::new(),
                     outputs: Vec::new(),
                     args: IndexMap::new(),
+                    variants: IndexMap::new(),
                 }),
                 serial: Stage::Out(OutStage {
                     out: "SERIAL".to_string(),
@@ -423,6 +628,7 @@ mod tests {
                 parameters: map! {
                     some_arg: "asdf".into(),
                 },
+                location: ProcBlockLocation::Embedded,
             },
         )];
         let got: Vec<_> = <(&Name, &ProcBlock)>::query()
@@ -437,6 +643,9 @@ mod tests {
                 Model {
                     model_file: ModelFile::FromDisk("model.tflite".into()),
                     args: IndexMap::new(),
+                    location: ModelLocation::Embedded,
+                    encrypted: false,
+                    variants: IndexMap::new(),
                 },
             ),
             (
@@ -449,6 +658,9 @@ mod tests {
                             .unwrap(),
                     ),
                     args: IndexMap::new(),
+                    location: ModelLocation::Embedded,
+                    encrypted: false,
+                    variants: IndexMap::new(),
                 },
             ),
         ];
@@ -486,4 +698,113 @@ mod tests {
             .collect();
         assert_eq!(got, sinks_should_be);
     }
+
+    #[test]
+    fn torchscript_models_are_detected_by_extension() {
+        let args = IndexMap::new();
+
+        let (mimetype, _) = model_format_and_args(
+            "model",
+            &args,
+            Mimetype::from_path("model.pt".as_ref()),
+            |_| None,
+        )
+        .unwrap();
+
+        assert_eq!(mimetype, Mimetype::TORCHSCRIPT);
+        assert_eq!(
+            Mimetype::from_path("model.tflite".as_ref()),
+            Mimetype::TENSORFLOW_LITE
+        );
+    }
+
+    #[test]
+    fn models_downloaded_from_a_url_need_a_checksum() {
+        let url = "https://example.com/model.tflite";
+        let mut args = IndexMap::new();
+
+        assert!(model_checksum("model", url, &mut args).is_err());
+
+        args.insert(
+            "sha256".to_string(),
+            lowering::ResourceOrString::String("abcd".to_string()),
+        );
+        let sha256 = model_checksum("model", url, &mut args).unwrap();
+
+        assert_eq!(sha256, "abcd");
+        assert!(args.is_empty());
+    }
+
+    #[test]
+    fn models_can_be_encrypted() {
+        let mut args = IndexMap::new();
+        assert!(!model_is_encrypted("model", &mut args).unwrap());
+
+        args.insert(
+            "encrypted".to_string(),
+            lowering::ResourceOrString::String("true".to_string()),
+        );
+        assert!(model_is_encrypted("model", &mut args).unwrap());
+        assert!(args.is_empty());
+
+        args.insert(
+            "encrypted".to_string(),
+            lowering::ResourceOrString::String("maybe".to_string()),
+        );
+        assert!(model_is_encrypted("model", &mut args).is_err());
+    }
+
+    #[test]
+    fn models_can_be_run_by_the_host() {
+        let mut args = IndexMap::new();
+        args.insert(
+            "location".to_string(),
+            lowering::ResourceOrString::String("host".to_string()),
+        );
+
+        let location = model_location("model", &mut args).unwrap();
+
+        assert_eq!(location, ModelLocation::Host);
+        assert!(args.is_empty());
+
+        args.insert(
+            "location".to_string(),
+            lowering::ResourceOrString::String("cloud".to_string()),
+        );
+        assert!(model_location("model", &mut args).is_err());
+    }
+
+    #[test]
+    fn proc_blocks_can_be_loaded_by_the_host() {
+        let mut args = IndexMap::new();
+        args.insert(
+            "location".to_string(),
+            lowering::ResourceOrString::String("host".to_string()),
+        );
+        args.insert(
+            "threshold".to_string(),
+            lowering::ResourceOrString::String("0.5".to_string()),
+        );
+
+        let location = proc_block_location("proc_block", &mut args).unwrap();
+
+        assert_eq!(location, ProcBlockLocation::Host);
+        assert_eq!(args.len(), 1);
+        assert!(args.contains_key("threshold"));
+    }
+
+    #[test]
+    fn model_variants_need_valid_names() {
+        let mut variants = IndexMap::new();
+        variants.insert("small".to_string(), "small.tflite".to_string());
+        variants.insert("large_v2".to_string(), "large.tflite".to_string());
+
+        let got = model_variants("model", &variants).unwrap();
+
+        assert_eq!(got["small"], PathBuf::from("small.tflite"));
+        assert_eq!(got["large_v2"], PathBuf::from("large.tflite"));
+
+        variants.insert("extra-large".to_string(), "xl.tflite".to_string());
+        assert!(model_variants("model", &variants).is_err());
+    }
 }
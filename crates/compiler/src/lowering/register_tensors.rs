@@ -1,7 +1,7 @@
 use std::collections::HashMap;
 
 use codespan_reporting::diagnostic::Diagnostic;
-use hotg_rune_core::{ElementType, Shape};
+use hotg_rune_core::{Dim, ElementType, Shape};
 use legion::{systems::CommandBuffer, Entity};
 
 use crate::{
@@ -64,6 +64,7 @@ fn register_node_inputs(
         match register_stage_inputs(
             name,
             stage.inputs(),
+            doc,
             names,
             output_tensors_by_node,
         ) {
@@ -82,6 +83,7 @@ fn register_node_inputs(
 fn register_stage_inputs(
     parent_name: &str,
     inputs: &[parse::Input],
+    doc: &DocumentV1,
     names: &NameTable,
     output_tensors_by_node: &HashMap<Entity, Outputs>,
 ) -> Result<Inputs, Diagnostic<()>> {
@@ -91,6 +93,7 @@ fn register_stage_inputs(
         let tensor = get_input_tensor(
             parent_name,
             input,
+            doc,
             names,
             output_tensors_by_node,
         )?;
@@ -103,6 +106,7 @@ fn register_stage_inputs(
 fn get_input_tensor(
     parent_name: &str,
     input: &parse::Input,
+    doc: &DocumentV1,
     names: &NameTable,
     output_tensors_by_node: &HashMap<Entity, Outputs>,
 ) -> Result<Entity, Diagnostic<()>> {
@@ -117,16 +121,43 @@ fn get_input_tensor(
         .get(&input_node)
         .ok_or_else(|| node_has_no_outputs_diagnostic(parent_name, input))?;
 
+    let index = match &input.output_name {
+        Some(output_name) => output_index(doc, input, output_name)?,
+        None => input.index.unwrap_or(0),
+    };
+
     // Finally, get the Entity for the index'th item
     let tensor = output_tensors
         .tensors
-        .get(input.index.unwrap_or(0))
+        .get(index)
         .copied()
         .ok_or_else(|| no_such_output_diagnostic(input))?;
 
     Ok(tensor)
 }
 
+/// Find the position of a named output.
+fn output_index(
+    doc: &DocumentV1,
+    input: &parse::Input,
+    output_name: &str,
+) -> Result<usize, Diagnostic<()>> {
+    doc.pipeline
+        .get(&input.name)
+        .and_then(|stage| {
+            stage
+                .output_types()
+                .iter()
+                .position(|ty| ty.tensor_name.as_deref() == Some(output_name))
+        })
+        .ok_or_else(|| {
+            Diagnostic::error().with_message(format!(
+                "The \"{}\" node has no output called \"{}\"",
+                input.name, output_name
+            ))
+        })
+}
+
 fn no_such_output_diagnostic(input: &parse::Input) -> Diagnostic<()> {
     Diagnostic::error().with_message(format!(
         "The \"{}\" node has no {}'th output",
@@ -169,6 +200,14 @@ fn register_node_outputs(
             None => continue,
         };
 
+        if let parse::Stage::Capability(_) = stage {
+            if let Err(diag) =
+                check_capability_outputs(name, stage.output_types())
+            {
+                diags.push(diag);
+            }
+        }
+
         match allocate_output_tensors(cmd, stage.output_types()) {
             Ok(outputs) if outputs.tensors.is_empty() => {},
             Ok(outputs) => {
@@ -182,6 +221,27 @@ fn register_node_outputs(
     node_to_output_tensors
 }
 
+/// Capabilities write into a buffer the Rune allocates up front, so we need
+/// to know exactly how big their outputs will be.
+fn check_capability_outputs(
+    name: &str,
+    output_types: &[parse::Type],
+) -> Result<(), Diagnostic<()>> {
+    for ty in output_types {
+        let Tensor(shape) = shape(ty)?;
+
+        if !shape.is_fixed() {
+            return Err(Diagnostic::error().with_message(format!(
+                "The \"{}\" capability's output must have a fixed shape, but \
+                 it was declared as {}",
+                name, shape,
+            )));
+        }
+    }
+
+    Ok(())
+}
+
 /// Allocate a new [`Tensor`] entity for each output that a node may have.
 fn allocate_output_tensors(
     cmd: &mut CommandBuffer,
@@ -204,10 +264,23 @@ fn shape(ty: &parse::Type) -> Result<Tensor, Diagnostic<()>> {
         .parse()
         .map_err(|_| unknown_element_type_diagnostic(&ty.name))?;
 
-    Ok(Tensor::from(Shape::new(
-        element_type,
-        ty.dimensions.clone(),
-    )))
+    let dimensions: Vec<Dim> =
+        ty.dimensions.iter().copied().map(Dim::from).collect();
+    let shape = Shape::with_dims(element_type, dimensions);
+
+    if ty.dimension_names.is_empty() {
+        return Ok(Tensor::from(shape));
+    }
+
+    let names = ty.dimension_names.iter().map(Option::as_deref);
+    let description = shape.to_string();
+
+    shape.with_names(names).map(Tensor::from).map_err(|e| {
+        Diagnostic::error().with_message(format!(
+            "Unable to name the dimensions of a {} tensor: {}",
+            description, e
+        ))
+    })
 }
 
 fn unknown_element_type_diagnostic(name: &str) -> Diagnostic<()> {
@@ -317,4 +390,121 @@ mod tests {
             assert_eq!(input_tensor, output_tensor);
         }
     }
+
+    #[test]
+    fn refer_to_outputs_by_name() {
+        let mut doc = doc();
+        if let parse::Stage::ProcBlock(transform) =
+            &mut doc.pipeline["transform"]
+        {
+            transform.outputs[1].tensor_name = Some("second".to_string());
+        }
+        if let parse::Stage::Out(output) = &mut doc.pipeline["output"] {
+            output.inputs = vec!["transform.second".parse().unwrap()];
+        }
+        let mut world = World::default();
+        let mut res = Resources::default();
+        res.insert(BuildContext::from_doc(doc.into()));
+        res.insert(NameTable::default());
+        crate::parse::phase().run(&mut world, &mut res);
+
+        Phase::new()
+            .and_then(lowering::register_names::run_system)
+            .and_then(lowering::update_nametable::run_system)
+            .and_then(lowering::register_stages::run_system)
+            .and_then(run_system)
+            .run(&mut world, &mut res);
+
+        assert!(res.get::<Diagnostics>().unwrap().is_empty());
+        let names = res.get::<NameTable>().unwrap();
+        let transform = <&Outputs>::query()
+            .get(&world, names["transform"])
+            .unwrap();
+        let output = <&Inputs>::query().get(&world, names["output"]).unwrap();
+        assert_eq!(output.tensors, vec![transform.tensors[1]]);
+    }
+
+    #[test]
+    fn capability_outputs_need_a_fixed_shape() {
+        let mut doc = doc();
+        if let parse::Stage::Capability(rand) = &mut doc.pipeline["rand"] {
+            rand.outputs[0].dimensions = vec![None, Some(128)];
+        }
+        if let parse::Stage::ProcBlock(transform) =
+            &mut doc.pipeline["transform"]
+        {
+            transform.outputs[0].dimensions = vec![None];
+        }
+        let mut world = World::default();
+        let mut res = Resources::default();
+        res.insert(BuildContext::from_doc(doc.into()));
+        res.insert(NameTable::default());
+        crate::parse::phase().run(&mut world, &mut res);
+
+        Phase::new()
+            .and_then(lowering::register_names::run_system)
+            .and_then(lowering::update_nametable::run_system)
+            .and_then(lowering::register_stages::run_system)
+            .and_then(run_system)
+            .run(&mut world, &mut res);
+
+        let diags = res.get::<Diagnostics>().unwrap();
+        let messages: Vec<_> = diags.iter().map(|d| &d.message).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "The \"rand\" capability's output must have a fixed shape, \
+                 but it was declared as f32[?, 128]"
+            ]
+        );
+        let names = res.get::<NameTable>().unwrap();
+        let transform = <&Outputs>::query()
+            .get(&world, names["transform"])
+            .unwrap();
+        let tensor = <&Tensor>::query()
+            .get(&world, transform.tensors[0])
+            .unwrap();
+        assert_eq!(tensor.0.to_string(), "u8[?]");
+    }
+
+    #[test]
+    fn dimensions_can_be_named() {
+        let mut doc = doc();
+        if let parse::Stage::Capability(rand) = &mut doc.pipeline["rand"] {
+            rand.outputs[0].dimension_names = vec![Some("samples".into())];
+        }
+        let mut world = World::default();
+        let mut res = Resources::default();
+        res.insert(BuildContext::from_doc(doc.into()));
+        res.insert(NameTable::default());
+        crate::parse::phase().run(&mut world, &mut res);
+
+        Phase::new()
+            .and_then(lowering::register_names::run_system)
+            .and_then(lowering::update_nametable::run_system)
+            .and_then(lowering::register_stages::run_system)
+            .and_then(run_system)
+            .run(&mut world, &mut res);
+
+        let diags = res.get::<Diagnostics>().unwrap();
+        assert!(diags.is_empty());
+        let names = res.get::<NameTable>().unwrap();
+        let rand = <&Outputs>::query().get(&world, names["rand"]).unwrap();
+        let tensor = <&Tensor>::query().get(&world, rand.tensors[0]).unwrap();
+        assert_eq!(tensor.0.to_string(), "f32[samples: 128]");
+    }
+
+    #[test]
+    fn every_dimension_name_needs_a_dimension() {
+        let mut ty = ty!(u8[2]);
+        ty.dimension_names = vec![None, Some("extra".into())];
+
+        let diag = shape(&ty).unwrap_err();
+
+        assert_eq!(
+            diag.message,
+            "Unable to name the dimensions of a u8[2] tensor: Found 2 \
+             dimension names for a shape with 1 dimensions"
+        );
+    }
 }
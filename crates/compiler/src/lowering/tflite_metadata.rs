@@ -0,0 +1,388 @@
+//! Read the [metadata][metadata] embedded in TensorFlow Lite models and use
+//! it to fill in the gaps in a Runefile.
+//!
+//! [metadata]: https://www.tensorflow.org/lite/models/convert/metadata
+
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    convert::TryInto,
+    io::{Cursor, Read},
+};
+
+use legion::{world::SubWorld, Entity, Query};
+
+use crate::lowering::{
+    Inputs, ModelMetadata, Name, Outputs, ProcBlock, ResourceOrString,
+    TensorMetadata,
+};
+
+/// The name of the buffer containing a TFLite model's metadata.
+const METADATA_BUFFER_NAME: &str = "TFLITE_METADATA";
+/// The `ProcessUnitOptions` union tag for `NormalizationOptions`.
+const NORMALIZATION_OPTIONS: u8 = 1;
+/// The `AssociatedFileType` for a file with one label per line.
+const TENSOR_AXIS_LABELS: u8 = 2;
+/// The argument the `label` proc block reads its labels from.
+const WORDLIST: &str = "wordlist";
+
+/// Give any `label` proc blocks fed by a model with labels in its metadata
+/// those labels, unless the Runefile already provides a `wordlist`.
+#[legion::system]
+pub(crate) fn run(
+    world: &mut SubWorld,
+    models: &mut Query<(Entity, &Name, &ModelMetadata)>,
+    producers: &mut Query<(Entity, &Outputs)>,
+    consumers: &mut Query<(Entity, &Inputs)>,
+    proc_blocks: &mut Query<(&Name, &Inputs, &mut ProcBlock)>,
+) {
+    let labels: HashMap<Entity, (Name, Vec<String>)> = models
+        .iter(world)
+        .filter(|(_, _, meta)| !meta.labels.is_empty())
+        .map(|(&e, name, meta)| (e, (name.clone(), meta.labels.clone())))
+        .collect();
+
+    if labels.is_empty() {
+        return;
+    }
+
+    let producer_of: HashMap<Entity, Entity> = producers
+        .iter(world)
+        .flat_map(|(&node, outputs)| {
+            outputs.tensors.iter().map(move |&tensor| (tensor, node))
+        })
+        .collect();
+    let inputs_of: HashMap<Entity, Vec<Entity>> = consumers
+        .iter(world)
+        .map(|(&node, inputs)| (node, inputs.tensors.clone()))
+        .collect();
+
+    for (name, inputs, proc_block) in proc_blocks.iter_mut(world) {
+        if proc_block.name() != "label"
+            || proc_block.parameters.contains_key(WORDLIST)
+        {
+            continue;
+        }
+
+        if let Some((model, labels)) =
+            upstream_labels(&inputs.tensors, &producer_of, &inputs_of, &labels)
+        {
+            log::debug!(
+                "Using the labels from \"{}\"'s metadata for \"{}\"",
+                model,
+                name
+            );
+            proc_block.parameters.insert(
+                WORDLIST.to_string(),
+                ResourceOrString::String(labels.join("\n")),
+            );
+        }
+    }
+}
+
+/// Walk up the pipeline until we find a model with labels.
+fn upstream_labels<'a>(
+    tensors: &[Entity],
+    producer_of: &HashMap<Entity, Entity>,
+    inputs_of: &HashMap<Entity, Vec<Entity>>,
+    labels: &'a HashMap<Entity, (Name, Vec<String>)>,
+) -> Option<&'a (Name, Vec<String>)> {
+    let mut to_visit: VecDeque<Entity> = tensors.iter().copied().collect();
+    let mut visited = HashSet::new();
+
+    while let Some(tensor) = to_visit.pop_front() {
+        let node = match producer_of.get(&tensor) {
+            Some(&node) if visited.insert(node) => node,
+            _ => continue,
+        };
+
+        if let Some(found) = labels.get(&node) {
+            return Some(found);
+        }
+
+        if let Some(inputs) = inputs_of.get(&node) {
+            to_visit.extend(inputs.iter().copied());
+        }
+    }
+
+    None
+}
+
+/// Extract the metadata from a TensorFlow Lite model, if it has any.
+pub(crate) fn extract(model: &[u8]) -> Option<ModelMetadata> {
+    let buffer = metadata_buffer(model)?;
+    let root = Table::root(buffer)?;
+
+    let mut meta = ModelMetadata {
+        name: root.string(0).map(String::from),
+        description: root.string(1).map(String::from),
+        version: root.string(2).map(String::from),
+        author: root.string(4).map(String::from),
+        license: root.string(5).map(String::from),
+        ..Default::default()
+    };
+
+    let mut label_files = Vec::new();
+
+    // Runes only ever use the first subgraph
+    if let Some(subgraph) = root.tables(3).into_iter().next() {
+        meta.inputs = subgraph.tables(2).iter().map(tensor_metadata).collect();
+
+        for tensor in subgraph.tables(3) {
+            label_files.extend(label_file_names(&tensor));
+            meta.outputs.push(tensor_metadata(&tensor));
+        }
+    }
+
+    // Associated files are packed into a zip archive appended to the model
+    meta.labels = label_files
+        .iter()
+        .find_map(|name| read_associated_file(model, name))
+        .map(|text| {
+            text.lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Some(meta)
+}
+
+/// Find the contents of the `TFLITE_METADATA` buffer.
+fn metadata_buffer(model: &[u8]) -> Option<&[u8]> {
+    if model.get(4..8)? != b"TFL3" {
+        return None;
+    }
+
+    let root = Table::root(model)?;
+    let buffers = root.tables(4);
+
+    let index = root.tables(6).into_iter().find_map(|metadata| {
+        if metadata.string(0)? == METADATA_BUFFER_NAME {
+            metadata.u32(1)
+        } else {
+            None
+        }
+    })?;
+    let buffer = buffers.get(index as usize)?;
+
+    match buffer.bytes(0) {
+        Some(data) => Some(data),
+        None => {
+            // Large models store their buffers after the flatbuffer
+            let offset: usize = buffer.u64(1)?.try_into().ok()?;
+            let size: usize = buffer.u64(2)?.try_into().ok()?;
+            model.get(offset..offset.checked_add(size)?)
+        },
+    }
+}
+
+fn tensor_metadata(tensor: &Table<'_>) -> TensorMetadata {
+    let mut meta = TensorMetadata {
+        name: tensor.string(0).map(String::from),
+        description: tensor.string(1).map(String::from),
+        associated_files: tensor
+            .tables(6)
+            .iter()
+            .filter_map(|file| file.string(0))
+            .map(String::from)
+            .collect(),
+        ..Default::default()
+    };
+
+    for unit in tensor.tables(4) {
+        if unit.u8(0) == Some(NORMALIZATION_OPTIONS) {
+            if let Some(options) = unit.table(1) {
+                meta.mean = options.floats(0);
+                meta.std = options.floats(1);
+            }
+        }
+    }
+
+    meta
+}
+
+fn label_file_names<'a>(tensor: &Table<'a>) -> Vec<&'a str> {
+    tensor
+        .tables(6)
+        .iter()
+        .filter(|file| file.u8(2) == Some(TENSOR_AXIS_LABELS))
+        .filter_map(|file| file.string(0))
+        .collect()
+}
+
+fn read_associated_file(model: &[u8], name: &str) -> Option<String> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(model)).ok()?;
+    let mut file = archive.by_name(name).ok()?;
+    let mut contents = String::new();
+    file.read_to_string(&mut contents).ok()?;
+
+    Some(contents)
+}
+
+/// A minimal, bounds-checked reader for a table in a [FlatBuffer][fb].
+///
+/// Fields are accessed by their index in the schema, and anything missing or
+/// malformed is treated as absent.
+///
+/// [fb]: https://google.github.io/flatbuffers/flatbuffers_internals.html
+#[derive(Debug, Copy, Clone)]
+struct Table<'a> {
+    buffer: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Table<'a> {
+    fn root(buffer: &'a [u8]) -> Option<Self> {
+        let position = read_u32(buffer, 0)? as usize;
+        Some(Table { buffer, position })
+    }
+
+    /// Get the absolute position of a field, if it is present.
+    fn field(&self, index: usize) -> Option<usize> {
+        let soffset = read_u32(self.buffer, self.position)? as i32;
+        let vtable = (self.position as i64).checked_sub(soffset as i64)?;
+        let vtable: usize = vtable.try_into().ok()?;
+        let vtable_length = read_u16(self.buffer, vtable)? as usize;
+
+        let entry = 4 + 2 * index;
+        if entry + 2 > vtable_length {
+            return None;
+        }
+
+        match read_u16(self.buffer, vtable + entry)? {
+            0 => None,
+            offset => Some(self.position + offset as usize),
+        }
+    }
+
+    /// Follow the offset stored in a field.
+    fn indirect(&self, index: usize) -> Option<usize> {
+        let field = self.field(index)?;
+        field.checked_add(read_u32(self.buffer, field)? as usize)
+    }
+
+    fn u8(&self, index: usize) -> Option<u8> {
+        self.buffer.get(self.field(index)?).copied()
+    }
+
+    fn u32(&self, index: usize) -> Option<u32> {
+        read_u32(self.buffer, self.field(index)?)
+    }
+
+    fn u64(&self, index: usize) -> Option<u64> {
+        let field = self.field(index)?;
+        let bytes = self.buffer.get(field..field + 8)?;
+        Some(u64::from_le_bytes(bytes.try_into().ok()?))
+    }
+
+    fn table(&self, index: usize) -> Option<Table<'a>> {
+        Some(Table {
+            buffer: self.buffer,
+            position: self.indirect(index)?,
+        })
+    }
+
+    fn bytes(&self, index: usize) -> Option<&'a [u8]> {
+        let (start, length) = self.vector(index)?;
+        self.buffer.get(start..start.checked_add(length)?)
+    }
+
+    fn string(&self, index: usize) -> Option<&'a str> {
+        std::str::from_utf8(self.bytes(index)?).ok()
+    }
+
+    fn floats(&self, index: usize) -> Vec<f32> {
+        let (start, length) = match self.vector(index) {
+            Some(v) => v,
+            None => return Vec::new(),
+        };
+
+        (0..length)
+            .map_while(|i| read_u32(self.buffer, start + 4 * i))
+            .map(f32::from_bits)
+            .collect()
+    }
+
+    fn tables(&self, index: usize) -> Vec<Table<'a>> {
+        let (start, length) = match self.vector(index) {
+            Some(v) => v,
+            None => return Vec::new(),
+        };
+
+        (0..length)
+            .map_while(|i| {
+                let element = start + 4 * i;
+                let position = element
+                    .checked_add(read_u32(self.buffer, element)? as usize)?;
+                Some(Table {
+                    buffer: self.buffer,
+                    position,
+                })
+            })
+            .collect()
+    }
+
+    /// Get the start and length of a vector field.
+    fn vector(&self, index: usize) -> Option<(usize, usize)> {
+        let position = self.indirect(index)?;
+        let length = read_u32(self.buffer, position)? as usize;
+        Some((position + 4, length))
+    }
+}
+
+fn read_u16(buffer: &[u8], position: usize) -> Option<u16> {
+    let bytes = buffer.get(position..position.checked_add(2)?)?;
+    Some(u16::from_le_bytes(bytes.try_into().ok()?))
+}
+
+fn read_u32(buffer: &[u8], position: usize) -> Option<u32> {
+    let bytes = buffer.get(position..position.checked_add(4)?)?;
+    Some(u32::from_le_bytes(bytes.try_into().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    #[test]
+    fn read_a_string_field() {
+        let buffer = [
+            12, 0, 0, 0, // offset to the root table
+            6, 0, 8, 0, 4, 0, // vtable: 1 field at offset 4
+            0, 0, // padding
+            8, 0, 0, 0, // the table, pointing back to its vtable
+            4, 0, 0, 0, // field 0: offset to the string
+            5, 0, 0, 0, b'h', b'e', b'l', b'l', b'o', 0,
+        ];
+
+        let table = Table::root(&buffer).unwrap();
+
+        assert_eq!(table.string(0), Some("hello"));
+        assert_eq!(table.string(1), None);
+        assert!(table.tables(1).is_empty());
+    }
+
+    #[test]
+    fn models_without_metadata_are_ignored() {
+        assert_eq!(extract(b"not a model"), None);
+    }
+
+    #[test]
+    fn labels_are_read_from_the_appended_zip_archive() {
+        let mut archive = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        archive
+            .start_file("labels.txt", zip::write::FileOptions::default())
+            .unwrap();
+        archive.write_all(b"cat\ndog\n").unwrap();
+        let zipped = archive.finish().unwrap().into_inner();
+
+        let got = read_associated_file(&zipped, "labels.txt").unwrap();
+
+        assert_eq!(got, "cat\ndog\n");
+        assert_eq!(read_associated_file(&zipped, "missing.txt"), None);
+    }
+}
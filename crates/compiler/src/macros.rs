@@ -17,13 +17,17 @@ macro_rules! ty {
         ($type:ident [$($dim:expr),*]) => {
             crate::parse::Type {
                 name: String::from(stringify!($type)),
-                dimensions: vec![ $($dim),*],
+                dimensions: vec![ $(Some($dim)),*],
+                dimension_names: vec![],
+                tensor_name: None,
             }
         };
         ($type:ident) => {
             crate::parse::Type {
                 name: String::from(stringify!($type)),
                 dimensions: vec![],
+                dimension_names: vec![],
+                tensor_name: None,
             }
         }
     }
@@ -242,7 +242,7 @@ static PATH_PATTERN: Lazy<Regex> = Lazy::new(|| {
     Regex::new(
         r"(?x)
         (?P<base>[\w\d:/_.-]+)
-        (?:@(?P<version>[\w\d./-]+))?
+        (?:@(?P<version>[\w\d./^~<>=*,-]+))?
         (?:\#(?P<sub_path>[\w\d._/-]+))?
         ",
     )
@@ -320,6 +320,10 @@ pub struct ModelStage {
     pub outputs: Vec<Type>,
     #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
     pub args: IndexMap<String, Argument>,
+    /// Alternative versions of the model (e.g. `small` and `large`) which the
+    /// host can choose between when the Rune is loaded.
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub variants: IndexMap<String, String>,
 }
 
 /// A stage which executes a procedural block.
@@ -498,7 +502,10 @@ impl<'de> Deserialize<'de> for ResourceOrString {
             type Value = ResourceOrString;
 
             fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-                write!(formatter, "a number, string, or \"$RESOURCE_NAME\"")
+                write!(
+                    formatter,
+                    "a number, string, list, map, or \"$RESOURCE_NAME\""
+                )
             }
 
             fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E>
@@ -538,11 +545,26 @@ impl<'de> Deserialize<'de> for ResourceOrString {
                 }
             }
 
-            fn visit_seq<A>(self, _: A) -> Result<Self::Value, A::Error>
+            fn visit_seq<A>(self, seq: A) -> Result<Self::Value, A::Error>
             where
                 A: serde::de::SeqAccess<'de>,
             {
-                Err(A::Error::custom("lists aren't supported"))
+                // Lists are passed through as JSON so the runtime can parse
+                // them (see hotg_rune_runtime::builtins::ArgumentValue)
+                let value = serde_json::Value::deserialize(
+                    serde::de::value::SeqAccessDeserializer::new(seq),
+                )?;
+                Ok(ResourceOrString::String(value.to_string()))
+            }
+
+            fn visit_map<A>(self, map: A) -> Result<Self::Value, A::Error>
+            where
+                A: serde::de::MapAccess<'de>,
+            {
+                let value = serde_json::Value::deserialize(
+                    serde::de::value::MapAccessDeserializer::new(map),
+                )?;
+                Ok(ResourceOrString::String(value.to_string()))
             }
         }
 
@@ -578,9 +600,17 @@ impl JsonSchema for Argument {
 
     fn json_schema(gen: &mut SchemaGenerator) -> Schema {
         let number = gen.subschema_for::<serde_json::Number>();
+        let list = gen.subschema_for::<Vec<serde_json::Value>>();
+        let map = gen
+            .subschema_for::<serde_json::Map<String, serde_json::Value>>();
 
         let mut schema = ResourceOrString::json_schema(gen).into_object();
-        schema.subschemas().any_of.as_mut().unwrap().push(number);
+        schema
+            .subschemas()
+            .any_of
+            .as_mut()
+            .unwrap()
+            .extend([number, list, map]);
 
         schema.into()
     }
@@ -610,18 +640,38 @@ impl Deref for Argument {
 pub struct Type {
     #[serde(rename = "type")]
     pub name: String,
+    /// The tensor's dimensions, where `null` means the dimension's length
+    /// will only be known at runtime.
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
-    pub dimensions: Vec<usize>,
+    pub dimensions: Vec<Option<usize>>,
+    /// Optional names for each of the tensor's dimensions (e.g. `batch` or
+    /// `height`), where `null` leaves a dimension unnamed.
+    #[serde(
+        rename = "dimension-names",
+        default,
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub dimension_names: Vec<Option<String>>,
+    /// An optional name for the tensor, so later stages can refer to it as
+    /// `stage.name` instead of by its position (e.g. `detector.boxes`).
+    #[serde(
+        rename = "name",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub tensor_name: Option<String>,
 }
 
 /// The name of a tensor.
 ///
-/// Typically something like "stage", or "stage.2" if the stage has multiple
-/// outputs.
+/// Typically something like "stage", or "stage.2" (or "stage.scores" if the
+/// output is named) if the stage has multiple outputs.
 #[derive(Debug, Clone, PartialEq, Hash, Eq, Ord, PartialOrd)]
 pub struct Input {
     pub name: String,
     pub index: Option<usize>,
+    /// The name of the output, for stages with named outputs.
+    pub output_name: Option<String>,
 }
 
 impl_json_schema_via_regex!(
@@ -630,7 +680,7 @@ impl_json_schema_via_regex!(
     r#"
 The name of a tensor.
 
-Typically something like "stage", or "stage.2" if the stage has multiple outputs.
+Typically something like "stage", or "stage.2" (or "stage.scores" if the output is named) if the stage has multiple outputs.
 "#
 );
 
@@ -642,12 +692,25 @@ impl Input {
         Input {
             name: name.into(),
             index: index.into(),
+            output_name: None,
+        }
+    }
+
+    /// Refer to one of a stage's named outputs.
+    pub fn named(name: impl Into<String>, output: impl Into<String>) -> Self {
+        Input {
+            name: name.into(),
+            index: None,
+            output_name: Some(output.into()),
         }
     }
 }
 
 static INPUT_PATTERN: Lazy<Regex> = Lazy::new(|| {
-    Regex::new(r"^(?P<name>[a-zA-Z_][\w-]*)(?:\.(?P<index>\d+))?$").unwrap()
+    Regex::new(
+        r"^(?P<name>[a-zA-Z_][\w-]*)(?:\.(?:(?P<index>\d+)|(?P<output>[a-zA-Z_][\w-]*)))?$",
+    )
+    .unwrap()
 });
 
 impl FromStr for Input {
@@ -659,6 +722,11 @@ impl FromStr for Input {
             .ok_or("Expected something like \"fft\" or \"fft.2\"")?;
 
         let name = &captures["name"];
+
+        if let Some(output) = captures.name("output") {
+            return Ok(Input::named(name, output.as_str()));
+        }
+
         let index = captures.name("index").map(|m| {
             m.as_str()
                 .parse::<usize>()
@@ -671,9 +739,10 @@ impl FromStr for Input {
 
 impl Display for Input {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        match self.index {
-            Some(index) => write!(f, "{}.{}", self.name, index),
-            None => write!(f, "{}", self.name),
+        match (self.index, &self.output_name) {
+            (_, Some(output)) => write!(f, "{}.{}", self.name, output),
+            (Some(index), None) => write!(f, "{}.{}", self.name, index),
+            (None, None) => write!(f, "{}", self.name),
         }
     }
 }
@@ -882,6 +951,17 @@ mod tests {
         assert_eq!(got.to_string(), src);
     }
 
+    #[test]
+    fn input_specifier_with_a_named_output() {
+        let src = "detector.scores";
+        let should_be = Input::named("detector", "scores");
+
+        let got = Input::from_str(src).unwrap();
+
+        assert_eq!(got, should_be);
+        assert_eq!(got.to_string(), src);
+    }
+
     #[test]
     fn parse_paths() {
         let inputs = vec![
@@ -925,6 +1005,19 @@ mod tests {
                     "refs/heads/master".to_string(),
                 ),
             ),
+            // Installed proc blocks can use a semver version requirement
+            (
+                "hotg-ai/mfcc@^0.11",
+                Path::new("hotg-ai/mfcc", None, Some(String::from("^0.11"))),
+            ),
+            (
+                "hotg-ai/mfcc@>=0.11,<0.13",
+                Path::new(
+                    "hotg-ai/mfcc",
+                    None,
+                    Some(String::from(">=0.11,<0.13")),
+                ),
+            ),
         ];
 
         for (src, should_be) in inputs {
@@ -1050,7 +1143,9 @@ mod tests {
             inputs: Vec::new(),
             outputs: vec![Type {
                 name: String::from("u8"),
-                dimensions: vec![1],
+                dimensions: vec![Some(1)],
+                dimension_names: Vec::new(),
+                tensor_name: None,
             }],
             args: vec![(
                 "word-list".to_string(),
@@ -1137,11 +1232,12 @@ pipeline:
                     inputs: vec!["fft".parse().unwrap()],
                     outputs: vec![ty!(i8[6])],
                     args: IndexMap::new(),
+                    variants: IndexMap::new(),
                 }),
                 label: Stage::ProcBlock(ProcBlockStage {
                     proc_block: "hotg-ai/rune#proc_blocks/ohv_label".parse().unwrap(),
                     inputs: vec!["model".parse().unwrap()],
-                    outputs: vec![Type { name: String::from("utf8"), dimensions: Vec::new() }],
+                    outputs: vec![Type { name: String::from("utf8"), dimensions: Vec::new(), dimension_names: Vec::new(), tensor_name: None }],
                     args: map! {
                         labels: "silence\nunknown\nup\ndown\nleft\nright".into()
                     },
@@ -1174,7 +1270,9 @@ pipeline:
             capability: String::from("SOUND"),
             outputs: vec![Type {
                 name: String::from("i16"),
-                dimensions: vec![16000],
+                dimensions: vec![Some(16000)],
+                dimension_names: Vec::new(),
+                tensor_name: None,
             }],
             args: map! { hz: "16000".into() },
         });
@@ -1233,4 +1331,18 @@ pipeline:
             .validate(&number)
             .unwrap_or_else(|e| handle_errors(e));
     }
+
+    #[test]
+    fn lists_and_maps_are_passed_through_as_json() {
+        let src = "[0.485, 0.456, 0.406]";
+        let got: Argument = serde_yaml::from_str(src).unwrap();
+        assert_eq!(got, Argument::from("[0.485,0.456,0.406]"));
+
+        let src = "{ size: [224, 224], mode: center }";
+        let got: Argument = serde_yaml::from_str(src).unwrap();
+        assert_eq!(
+            got,
+            Argument::from(r#"{"mode":"center","size":[224,224]}"#)
+        );
+    }
 }
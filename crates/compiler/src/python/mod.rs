@@ -0,0 +1,131 @@
+//! Support for proc blocks written in a restricted subset of Python.
+//!
+//! Instead of writing a Rust crate, a proc block can be defined in a single
+//! `*.py` file and referenced from the Runefile using a relative path.
+//!
+//! ```yaml
+//! scale:
+//!   proc-block: "./scale.py"
+//!   inputs:
+//!     - image
+//!   outputs:
+//!     - type: F32
+//!       dimensions: [1, 28, 28, 1]
+//!   args:
+//!     max-value: 255.0
+//! ```
+//!
+//! During `rune build`, the file is translated into a Rust crate (under
+//! `proc_blocks/` in the generated project) and compiled to WebAssembly like
+//! any other proc block.
+//!
+//! ```python
+//! """Rescale values to the range [0, 1]."""
+//!
+//! import numpy as np
+//!
+//! max_value: float = 255.0
+//! """The largest value the input can have."""
+//!
+//! def transform(x: u8) -> f32:
+//!     if x > max_value:
+//!         return 1.0
+//!     return x / max_value
+//! ```
+//!
+//! # The Supported Subset
+//!
+//! - The module docstring becomes the proc block's documentation
+//! - Module-level variables with a `float`, `int`, or `bool` annotation and a
+//!   literal default are parameters which can be set from the Runefile, with
+//!   the string immediately after a parameter used as its description
+//! - `import math` and `import numpy as np` are allowed so the file can still
+//!   be run and tested as normal Python, and functions from those modules are
+//!   treated like builtins
+//! - There must be a single `def transform(x: <type>) -> <type>` function,
+//!   where the type is one of `u8`, `i8`, `u16`, `i16`, `u32`, `i32`, `u64`,
+//!   `i64`, `f32`, `f64`, `float` (`f32`), or `int` (`i32`). The output may
+//!   also be `bool`
+//!
+//! The `transform()` function is called once for each element in the input
+//! tensor and the output tensor has the same shape as the input. All
+//! arithmetic is done using 64-bit floats and results are converted to the
+//! output type, saturating if they are out of range.
+//!
+//! Proc blocks that don't use any reductions are *elementwise*, so when one
+//! feeds directly into another the Rune fuses them into a single loop instead
+//! of allocating a tensor for each intermediate result.
+//!
+//! Inside the function you can use assignments, `if`/`elif`/`else`,
+//! `return`, `pass`, conditional expressions, comparisons (including chained
+//! comparisons like `0 < x < 1`), `and`/`or`/`not`, and the usual arithmetic
+//! operators (`+`, `-`, `*`, `/`, `//`, `%`, `**`).
+//!
+//! The following functions are available:
+//!
+//! - `abs()`, `sqrt()`, `exp()`, `log()`, `log2()`, `log10()`, `sin()`,
+//!   `cos()`, `tan()`, `tanh()`, `floor()`, `ceil()`, `round()`, `float()`,
+//!   `int()`, `bool()`, `pow()`, `min()`, `max()`, and `clip()`
+//! - The reductions `sum()`, `mean()`, `min()`, `max()`, `std()`, `amin()`, and
+//!   `amax()` can be called with the input tensor's name (e.g. `np.mean(x)`) to
+//!   use a statistic calculated from the entire tensor
+//!
+//! Loops, nested functions, classes, and other imports aren't supported.
+
+mod parse;
+mod translate;
+
+use std::fmt::{self, Display, Formatter};
+
+use self::parse::Position;
+
+/// The files for the Rust crate generated from a Python proc block.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct GeneratedCrate {
+    pub cargo_toml: String,
+    pub lib_rs: String,
+}
+
+/// Translate a Python proc block into a Rust crate called `name`.
+pub(crate) fn compile(
+    name: &str,
+    filename: &str,
+    src: &str,
+) -> Result<GeneratedCrate, PythonError> {
+    let module = parse::parse(src)?;
+    translate::translate(name, filename, &module)
+}
+
+/// An error encountered while compiling a Python proc block.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PythonError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl PythonError {
+    pub(crate) fn new(position: Position, message: impl Into<String>) -> Self {
+        PythonError {
+            line: position.line,
+            column: position.column,
+            message: message.into(),
+        }
+    }
+
+    #[cfg(test)]
+    pub(crate) fn position(&self) -> Position {
+        Position {
+            line: self.line,
+            column: self.column,
+        }
+    }
+}
+
+impl Display for PythonError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl std::error::Error for PythonError {}
@@ -0,0 +1,1373 @@
+//! A tokenizer and recursive descent parser for the subset of Python that
+//! can be used to write a proc block.
+
+use hotg_rune_core::ElementType;
+
+use crate::python::PythonError;
+
+/// A position in the Python file, starting from `1:1`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Module {
+    /// The module's docstring.
+    pub docstring: Option<String>,
+    pub parameters: Vec<Parameter>,
+    pub function: Function,
+}
+
+/// A module-level variable (e.g. `max_value: float = 255.0`) which can be
+/// set from the Runefile.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Parameter {
+    pub name: String,
+    pub ty: ParameterType,
+    pub default: Literal,
+    /// The docstring immediately after the parameter, if there was one.
+    pub description: Option<String>,
+    pub position: Position,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) enum ParameterType {
+    Float,
+    Int,
+    Bool,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) enum Literal {
+    Number(f64),
+    Bool(bool),
+}
+
+/// The `transform()` function.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Function {
+    pub input: String,
+    pub input_type: ElementType,
+    pub output_type: ElementType,
+    pub body: Vec<Stmt>,
+    pub position: Position,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Stmt {
+    Assign {
+        target: String,
+        value: Expr,
+        position: Position,
+    },
+    Return {
+        value: Expr,
+        position: Position,
+    },
+    If {
+        branches: Vec<(Expr, Vec<Stmt>)>,
+        otherwise: Option<Vec<Stmt>>,
+    },
+    Pass,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Expr {
+    Number(f64),
+    Bool(bool),
+    Name {
+        name: String,
+        position: Position,
+    },
+    Unary {
+        op: UnaryOp,
+        operand: Box<Expr>,
+        position: Position,
+    },
+    Binary {
+        op: BinaryOp,
+        left: Box<Expr>,
+        right: Box<Expr>,
+        position: Position,
+    },
+    /// A (possibly chained) comparison like `0 < x <= 1`.
+    Compare {
+        left: Box<Expr>,
+        comparisons: Vec<(CompareOp, Expr)>,
+        position: Position,
+    },
+    Conditional {
+        condition: Box<Expr>,
+        then: Box<Expr>,
+        otherwise: Box<Expr>,
+    },
+    Call {
+        function: String,
+        args: Vec<Expr>,
+        position: Position,
+    },
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) enum UnaryOp {
+    Negate,
+    Plus,
+    Not,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) enum BinaryOp {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    FloorDivide,
+    Modulo,
+    Power,
+    And,
+    Or,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) enum CompareOp {
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    Equal,
+    NotEqual,
+}
+
+/// Modules which can be imported so the file still runs as normal Python
+/// (e.g. for testing with NumPy). Their functions are treated like builtins.
+const ALLOWED_IMPORTS: &[&str] = &["math", "numpy"];
+
+pub(crate) fn parse(src: &str) -> Result<Module, PythonError> {
+    let tokens = tokenize(src)?;
+    Parser {
+        tokens,
+        cursor: 0,
+        aliases: Vec::new(),
+    }
+    .module()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    Name(String),
+    Number(String),
+    Str(String),
+    Op(&'static str),
+    Newline,
+    Indent,
+    Dedent,
+    Eof,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Token {
+    kind: TokenKind,
+    position: Position,
+}
+
+/// Operators, longest first so `**` is matched before `*`.
+const OPERATORS: &[&str] = &[
+    "**", "//", "->", "<=", ">=", "==", "!=", "+", "-", "*", "/", "%", "<",
+    ">", "=", "(", ")", ",", ":", ".",
+];
+
+fn tokenize(src: &str) -> Result<Vec<Token>, PythonError> {
+    let mut tokens = Vec::new();
+    let mut indents = vec![0];
+    let mut depth = 0;
+    let mut at_line_start = true;
+    let chars: Vec<char> = src.chars().collect();
+    let mut i = 0;
+    let mut line = 1;
+    let mut line_start = 0;
+
+    macro_rules! position {
+        ($ix:expr) => {
+            Position {
+                line,
+                column: $ix - line_start + 1,
+            }
+        };
+    }
+
+    while i < chars.len() {
+        if at_line_start && depth == 0 {
+            let start = i;
+            while i < chars.len() && chars[i] == ' ' {
+                i += 1;
+            }
+
+            match chars.get(i) {
+                Some('\t') => {
+                    return Err(PythonError::new(
+                        position!(i),
+                        "Indent using spaces instead of tabs",
+                    ))
+                },
+                // Blank lines and comments don't affect indentation
+                Some('\n') | Some('#') | Some('\r') | None => {},
+                Some(_) => {
+                    let indent = i - start;
+                    let current = *indents.last().unwrap();
+
+                    if indent > current {
+                        indents.push(indent);
+                        tokens.push(Token {
+                            kind: TokenKind::Indent,
+                            position: position!(i),
+                        });
+                    } else {
+                        while indent < *indents.last().unwrap() {
+                            indents.pop();
+                            tokens.push(Token {
+                                kind: TokenKind::Dedent,
+                                position: position!(i),
+                            });
+                        }
+
+                        if indent != *indents.last().unwrap() {
+                            return Err(PythonError::new(
+                                position!(i),
+                                "This line's indentation doesn't match any \
+                                 outer block",
+                            ));
+                        }
+                    }
+                },
+            }
+
+            at_line_start = false;
+            continue;
+        }
+
+        let c = chars[i];
+
+        match c {
+            '\n' => {
+                let is_blank = matches!(
+                    tokens.last().map(|t: &Token| &t.kind),
+                    None | Some(TokenKind::Newline)
+                        | Some(TokenKind::Indent)
+                        | Some(TokenKind::Dedent)
+                );
+                if depth == 0 && !is_blank {
+                    tokens.push(Token {
+                        kind: TokenKind::Newline,
+                        position: position!(i),
+                    });
+                }
+                i += 1;
+                line += 1;
+                line_start = i;
+                at_line_start = depth == 0;
+            },
+            ' ' | '\t' | '\r' => i += 1,
+            '#' => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+            },
+            '\\' if chars.get(i + 1) == Some(&'\n') => {
+                i += 2;
+                line += 1;
+                line_start = i;
+            },
+            '"' | '\'' => {
+                let position = position!(i);
+                let triple = chars.get(i + 1) == Some(&c)
+                    && chars.get(i + 2) == Some(&c);
+                let quote_len = if triple { 3 } else { 1 };
+                i += quote_len;
+
+                let mut value = String::new();
+                loop {
+                    match chars.get(i) {
+                        None => {
+                            return Err(PythonError::new(
+                                position,
+                                "Unterminated string",
+                            ))
+                        },
+                        Some('\n') if !triple => {
+                            return Err(PythonError::new(
+                                position,
+                                "Unterminated string",
+                            ))
+                        },
+                        Some(&q)
+                            if q == c
+                                && (!triple
+                                    || (chars.get(i + 1) == Some(&c)
+                                        && chars.get(i + 2) == Some(&c))) =>
+                        {
+                            i += quote_len;
+                            break;
+                        },
+                        Some('\\') if i + 1 < chars.len() => {
+                            match chars[i + 1] {
+                                'n' => value.push('\n'),
+                                't' => value.push('\t'),
+                                '\n' => {},
+                                other => value.push(other),
+                            }
+                            if chars[i + 1] == '\n' {
+                                line += 1;
+                                line_start = i + 2;
+                            }
+                            i += 2;
+                        },
+                        Some(&other) => {
+                            if other == '\n' {
+                                line += 1;
+                                line_start = i + 1;
+                            }
+                            value.push(other);
+                            i += 1;
+                        },
+                    }
+                }
+
+                tokens.push(Token {
+                    kind: TokenKind::Str(value),
+                    position,
+                });
+            },
+            c if c.is_ascii_digit()
+                || (c == '.'
+                    && chars
+                        .get(i + 1)
+                        .map_or(false, |c| c.is_ascii_digit())) =>
+            {
+                let start = i;
+                while i < chars.len()
+                    && (chars[i].is_ascii_alphanumeric()
+                        || chars[i] == '.'
+                        || chars[i] == '_'
+                        || ((chars[i] == '-' || chars[i] == '+')
+                            && matches!(chars[i - 1], 'e' | 'E')))
+                {
+                    i += 1;
+                }
+                let text: String = chars[start..i].iter().collect();
+                tokens.push(Token {
+                    kind: TokenKind::Number(text),
+                    position: position!(start),
+                });
+            },
+            c if c.is_alphabetic() || c == '_' => {
+                let start = i;
+                while i < chars.len()
+                    && (chars[i].is_alphanumeric() || chars[i] == '_')
+                {
+                    i += 1;
+                }
+                let text: String = chars[start..i].iter().collect();
+                tokens.push(Token {
+                    kind: TokenKind::Name(text),
+                    position: position!(start),
+                });
+            },
+            _ => {
+                let rest: String =
+                    chars[i..chars.len().min(i + 2)].iter().collect();
+                let op = OPERATORS
+                    .iter()
+                    .find(|op| rest.starts_with(**op))
+                    .ok_or_else(|| {
+                        PythonError::new(
+                            position!(i),
+                            format!("Unexpected character, {:?}", c),
+                        )
+                    })?;
+
+                match *op {
+                    "(" => depth += 1,
+                    ")" => depth -= 1,
+                    _ => {},
+                }
+
+                tokens.push(Token {
+                    kind: TokenKind::Op(op),
+                    position: position!(i),
+                });
+                i += op.len();
+            },
+        }
+    }
+
+    let end = position!(i);
+
+    if !matches!(
+        tokens.last().map(|t| &t.kind),
+        None | Some(TokenKind::Newline)
+    ) {
+        tokens.push(Token {
+            kind: TokenKind::Newline,
+            position: end,
+        });
+    }
+    for _ in 1..indents.len() {
+        tokens.push(Token {
+            kind: TokenKind::Dedent,
+            position: end,
+        });
+    }
+    tokens.push(Token {
+        kind: TokenKind::Eof,
+        position: end,
+    });
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    cursor: usize,
+    /// The names modules were imported as (e.g. `np`).
+    aliases: Vec<String>,
+}
+
+impl Parser {
+    fn peek(&self) -> &Token { &self.tokens[self.cursor] }
+
+    fn peek_kind(&self) -> &TokenKind { &self.peek().kind }
+
+    fn next(&mut self) -> Token {
+        let token = self.tokens[self.cursor].clone();
+        if self.cursor + 1 < self.tokens.len() {
+            self.cursor += 1;
+        }
+        token
+    }
+
+    fn is_op(&self, op: &str) -> bool {
+        matches!(self.peek_kind(), TokenKind::Op(o) if *o == op)
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek_kind(), TokenKind::Name(n) if n == keyword)
+    }
+
+    fn eat_op(&mut self, op: &str) -> bool {
+        let found = self.is_op(op);
+        if found {
+            self.next();
+        }
+        found
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        let found = self.is_keyword(keyword);
+        if found {
+            self.next();
+        }
+        found
+    }
+
+    fn expect_op(&mut self, op: &str) -> Result<Position, PythonError> {
+        if self.is_op(op) {
+            Ok(self.next().position)
+        } else {
+            Err(self.unexpected(&format!("\"{}\"", op)))
+        }
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<(), PythonError> {
+        if self.eat_keyword(keyword) {
+            Ok(())
+        } else {
+            Err(self.unexpected(&format!("\"{}\"", keyword)))
+        }
+    }
+
+    fn expect_name(&mut self) -> Result<(String, Position), PythonError> {
+        match self.peek_kind().clone() {
+            TokenKind::Name(name) if !is_keyword(&name) => {
+                let position = self.next().position;
+                Ok((name, position))
+            },
+            _ => Err(self.unexpected("a name")),
+        }
+    }
+
+    fn expect_newline(&mut self) -> Result<(), PythonError> {
+        match self.peek_kind() {
+            TokenKind::Newline => {
+                self.next();
+                Ok(())
+            },
+            TokenKind::Eof => Ok(()),
+            _ => Err(self.unexpected("the end of the line")),
+        }
+    }
+
+    fn unexpected(&self, expected: &str) -> PythonError {
+        let token = self.peek();
+        let found = match &token.kind {
+            TokenKind::Name(name) => format!("\"{}\"", name),
+            TokenKind::Number(number) => number.clone(),
+            TokenKind::Str(_) => "a string".to_string(),
+            TokenKind::Op(op) => format!("\"{}\"", op),
+            TokenKind::Newline => "the end of the line".to_string(),
+            TokenKind::Indent => "an indented block".to_string(),
+            TokenKind::Dedent => "the end of the block".to_string(),
+            TokenKind::Eof => "the end of the file".to_string(),
+        };
+
+        PythonError::new(
+            token.position,
+            format!("Expected {}, but found {}", expected, found),
+        )
+    }
+
+    fn module(mut self) -> Result<Module, PythonError> {
+        let docstring = self.docstring()?;
+        let mut parameters: Vec<Parameter> = Vec::new();
+        let mut function = None;
+
+        loop {
+            match self.peek_kind().clone() {
+                TokenKind::Eof => break,
+                TokenKind::Name(n) if n == "import" || n == "from" => {
+                    self.import()?
+                },
+                TokenKind::Name(n) if n == "def" => {
+                    let position = self.peek().position;
+                    if function.is_some() {
+                        return Err(PythonError::new(
+                            position,
+                            "Only the transform() function can be defined",
+                        ));
+                    }
+                    function = Some(self.function()?);
+                },
+                TokenKind::Name(_) => {
+                    let parameter = self.parameter()?;
+                    if parameters.iter().any(|p| p.name == parameter.name) {
+                        return Err(PythonError::new(
+                            parameter.position,
+                            format!(
+                                "The \"{}\" parameter was defined twice",
+                                parameter.name
+                            ),
+                        ));
+                    }
+                    parameters.push(parameter);
+                },
+                _ => {
+                    return Err(self.unexpected(
+                        "a parameter, import, or function definition",
+                    ))
+                },
+            }
+        }
+
+        let function = function.ok_or_else(|| {
+            PythonError::new(
+                self.peek().position,
+                "The file doesn't define a transform() function",
+            )
+        })?;
+
+        Ok(Module {
+            docstring,
+            parameters,
+            function,
+        })
+    }
+
+    /// Consume a string on a line by itself.
+    fn docstring(&mut self) -> Result<Option<String>, PythonError> {
+        match self.peek_kind().clone() {
+            TokenKind::Str(s) => {
+                self.next();
+                self.expect_newline()?;
+                Ok(Some(dedent(&s)))
+            },
+            _ => Ok(None),
+        }
+    }
+
+    fn import(&mut self) -> Result<(), PythonError> {
+        let position = self.peek().position;
+
+        if self.eat_keyword("from") {
+            return Err(PythonError::new(
+                position,
+                "Use \"import math\" or \"import numpy as np\" instead of \
+                 \"from ... import ...\"",
+            ));
+        }
+
+        self.expect_keyword("import")?;
+        let (module, position) = self.expect_name()?;
+
+        if !ALLOWED_IMPORTS.contains(&module.as_str()) {
+            return Err(PythonError::new(
+                position,
+                format!(
+                    "Unable to import \"{}\". Only {} can be imported",
+                    module,
+                    ALLOWED_IMPORTS.join(" and ")
+                ),
+            ));
+        }
+
+        let alias = if self.eat_keyword("as") {
+            self.expect_name()?.0
+        } else {
+            module
+        };
+        self.aliases.push(alias);
+
+        self.expect_newline()
+    }
+
+    fn parameter(&mut self) -> Result<Parameter, PythonError> {
+        let (name, position) = self.expect_name()?;
+
+        if !self.is_op(":") {
+            return Err(PythonError::new(
+                position,
+                format!(
+                    "Parameters need a type (e.g. \"{}: float = 1.0\")",
+                    name
+                ),
+            ));
+        }
+        self.expect_op(":")?;
+
+        let (ty_name, ty_position) = self.expect_name()?;
+        let ty = match ty_name.as_str() {
+            "float" => ParameterType::Float,
+            "int" => ParameterType::Int,
+            "bool" => ParameterType::Bool,
+            _ => {
+                return Err(PythonError::new(
+                    ty_position,
+                    format!(
+                        "Parameters can be a float, int, or bool, not \"{}\"",
+                        ty_name
+                    ),
+                ))
+            },
+        };
+
+        self.expect_op("=")?;
+        let default = self.literal()?;
+        self.expect_newline()?;
+        let description = self.docstring()?;
+
+        Ok(Parameter {
+            name,
+            ty,
+            default,
+            description,
+            position,
+        })
+    }
+
+    fn literal(&mut self) -> Result<Literal, PythonError> {
+        let negative = self.eat_op("-");
+
+        match self.peek_kind().clone() {
+            TokenKind::Number(text) if !negative || !text.is_empty() => {
+                let position = self.next().position;
+                let value = parse_number(&text, position)?;
+                Ok(Literal::Number(if negative { -value } else { value }))
+            },
+            TokenKind::Name(n) if !negative && n == "True" => {
+                self.next();
+                Ok(Literal::Bool(true))
+            },
+            TokenKind::Name(n) if !negative && n == "False" => {
+                self.next();
+                Ok(Literal::Bool(false))
+            },
+            _ => Err(self.unexpected("a number, True, or False")),
+        }
+    }
+
+    fn function(&mut self) -> Result<Function, PythonError> {
+        self.expect_keyword("def")?;
+        let (name, position) = self.expect_name()?;
+
+        if name != "transform" {
+            return Err(PythonError::new(
+                position,
+                format!(
+                    "Only the transform() function can be defined, not {}()",
+                    name
+                ),
+            ));
+        }
+
+        self.expect_op("(")?;
+        let (input, _) = self.expect_name()?;
+        self.expect_op(":")?;
+        let input_type = self.element_type(false)?;
+        if self.is_op(",") {
+            return Err(PythonError::new(
+                self.peek().position,
+                "transform() only accepts a single tensor. Use module-level \
+                 variables for parameters",
+            ));
+        }
+        self.expect_op(")")?;
+        self.expect_op("->")?;
+        let output_type = self.element_type(true)?;
+        self.expect_op(":")?;
+
+        let body = self.block()?;
+
+        Ok(Function {
+            input,
+            input_type,
+            output_type,
+            body,
+            position,
+        })
+    }
+
+    /// Parse a type annotation like `u8`, `"f32"`, or `float`.
+    fn element_type(
+        &mut self,
+        allow_bool: bool,
+    ) -> Result<ElementType, PythonError> {
+        let token = match self.peek_kind() {
+            TokenKind::Name(_) | TokenKind::Str(_) => self.next(),
+            _ => return Err(self.unexpected("a type (e.g. \"f32\")")),
+        };
+        let name = match &token.kind {
+            TokenKind::Name(name) | TokenKind::Str(name) => name.as_str(),
+            _ => unreachable!(),
+        };
+
+        let ty = match name {
+            "float" => ElementType::F32,
+            "int" => ElementType::I32,
+            "bool" if allow_bool => ElementType::Bool,
+            other => match other.parse() {
+                Ok(ty @ ElementType::U8)
+                | Ok(ty @ ElementType::I8)
+                | Ok(ty @ ElementType::U16)
+                | Ok(ty @ ElementType::I16)
+                | Ok(ty @ ElementType::U32)
+                | Ok(ty @ ElementType::I32)
+                | Ok(ty @ ElementType::U64)
+                | Ok(ty @ ElementType::I64)
+                | Ok(ty @ ElementType::F32)
+                | Ok(ty @ ElementType::F64) => ty,
+                _ => {
+                    return Err(PythonError::new(
+                        token.position,
+                        format!("\"{}\" isn't a supported tensor type", other),
+                    ))
+                },
+            },
+        };
+
+        Ok(ty)
+    }
+
+    fn block(&mut self) -> Result<Vec<Stmt>, PythonError> {
+        match self.peek_kind() {
+            TokenKind::Newline => {
+                self.next();
+            },
+            _ => return Err(self.unexpected("a new line")),
+        }
+        if !matches!(self.peek_kind(), TokenKind::Indent) {
+            return Err(self.unexpected("an indented block"));
+        }
+        self.next();
+
+        // docstrings and comments are ignored
+        self.docstring()?;
+
+        let mut body = Vec::new();
+        while !matches!(self.peek_kind(), TokenKind::Dedent | TokenKind::Eof) {
+            body.push(self.statement()?);
+        }
+        if matches!(self.peek_kind(), TokenKind::Dedent) {
+            self.next();
+        }
+
+        Ok(body)
+    }
+
+    fn statement(&mut self) -> Result<Stmt, PythonError> {
+        let position = self.peek().position;
+
+        if self.eat_keyword("return") {
+            let value = self.expression()?;
+            self.expect_newline()?;
+            return Ok(Stmt::Return { value, position });
+        }
+
+        if self.eat_keyword("pass") {
+            self.expect_newline()?;
+            return Ok(Stmt::Pass);
+        }
+
+        if self.eat_keyword("if") {
+            return self.if_statement();
+        }
+
+        if let TokenKind::Name(name) = self.peek_kind() {
+            if matches!(name.as_str(), "for" | "while" | "def" | "import") {
+                return Err(PythonError::new(
+                    position,
+                    format!(
+                        "\"{}\" isn't supported inside transform(), which is \
+                         called once for each element",
+                        name
+                    ),
+                ));
+            }
+        }
+
+        let (target, position) = self.expect_name()?;
+        if !self.is_op("=") {
+            return Err(self.unexpected("\"=\""));
+        }
+        self.expect_op("=")?;
+        let value = self.expression()?;
+        self.expect_newline()?;
+
+        Ok(Stmt::Assign {
+            target,
+            value,
+            position,
+        })
+    }
+
+    fn if_statement(&mut self) -> Result<Stmt, PythonError> {
+        let mut branches = Vec::new();
+
+        let condition = self.expression()?;
+        self.expect_op(":")?;
+        branches.push((condition, self.block()?));
+
+        while self.eat_keyword("elif") {
+            let condition = self.expression()?;
+            self.expect_op(":")?;
+            branches.push((condition, self.block()?));
+        }
+
+        let otherwise = if self.eat_keyword("else") {
+            self.expect_op(":")?;
+            Some(self.block()?)
+        } else {
+            None
+        };
+
+        Ok(Stmt::If {
+            branches,
+            otherwise,
+        })
+    }
+
+    fn expression(&mut self) -> Result<Expr, PythonError> {
+        let then = self.or_expression()?;
+
+        if self.eat_keyword("if") {
+            let condition = self.or_expression()?;
+            self.expect_keyword("else")?;
+            let otherwise = self.expression()?;
+
+            return Ok(Expr::Conditional {
+                condition: Box::new(condition),
+                then: Box::new(then),
+                otherwise: Box::new(otherwise),
+            });
+        }
+
+        Ok(then)
+    }
+
+    fn or_expression(&mut self) -> Result<Expr, PythonError> {
+        let mut left = self.and_expression()?;
+
+        while self.is_keyword("or") {
+            let position = self.next().position;
+            let right = self.and_expression()?;
+            left = binary(BinaryOp::Or, left, right, position);
+        }
+
+        Ok(left)
+    }
+
+    fn and_expression(&mut self) -> Result<Expr, PythonError> {
+        let mut left = self.not_expression()?;
+
+        while self.is_keyword("and") {
+            let position = self.next().position;
+            let right = self.not_expression()?;
+            left = binary(BinaryOp::And, left, right, position);
+        }
+
+        Ok(left)
+    }
+
+    fn not_expression(&mut self) -> Result<Expr, PythonError> {
+        if self.is_keyword("not") {
+            let position = self.next().position;
+            let operand = self.not_expression()?;
+            return Ok(Expr::Unary {
+                op: UnaryOp::Not,
+                operand: Box::new(operand),
+                position,
+            });
+        }
+
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<Expr, PythonError> {
+        let position = self.peek().position;
+        let left = self.arithmetic()?;
+        let mut comparisons = Vec::new();
+
+        loop {
+            let op = match self.peek_kind() {
+                TokenKind::Op("<") => CompareOp::Less,
+                TokenKind::Op("<=") => CompareOp::LessEqual,
+                TokenKind::Op(">") => CompareOp::Greater,
+                TokenKind::Op(">=") => CompareOp::GreaterEqual,
+                TokenKind::Op("==") => CompareOp::Equal,
+                TokenKind::Op("!=") => CompareOp::NotEqual,
+                _ => break,
+            };
+            self.next();
+            comparisons.push((op, self.arithmetic()?));
+        }
+
+        if comparisons.is_empty() {
+            Ok(left)
+        } else {
+            Ok(Expr::Compare {
+                left: Box::new(left),
+                comparisons,
+                position,
+            })
+        }
+    }
+
+    fn arithmetic(&mut self) -> Result<Expr, PythonError> {
+        let mut left = self.term()?;
+
+        loop {
+            let op = match self.peek_kind() {
+                TokenKind::Op("+") => BinaryOp::Add,
+                TokenKind::Op("-") => BinaryOp::Subtract,
+                _ => break,
+            };
+            let position = self.next().position;
+            let right = self.term()?;
+            left = binary(op, left, right, position);
+        }
+
+        Ok(left)
+    }
+
+    fn term(&mut self) -> Result<Expr, PythonError> {
+        let mut left = self.factor()?;
+
+        loop {
+            let op = match self.peek_kind() {
+                TokenKind::Op("*") => BinaryOp::Multiply,
+                TokenKind::Op("/") => BinaryOp::Divide,
+                TokenKind::Op("//") => BinaryOp::FloorDivide,
+                TokenKind::Op("%") => BinaryOp::Modulo,
+                _ => break,
+            };
+            let position = self.next().position;
+            let right = self.factor()?;
+            left = binary(op, left, right, position);
+        }
+
+        Ok(left)
+    }
+
+    fn factor(&mut self) -> Result<Expr, PythonError> {
+        let op = match self.peek_kind() {
+            TokenKind::Op("-") => UnaryOp::Negate,
+            TokenKind::Op("+") => UnaryOp::Plus,
+            _ => return self.power(),
+        };
+        let position = self.next().position;
+        let operand = self.factor()?;
+
+        Ok(Expr::Unary {
+            op,
+            operand: Box::new(operand),
+            position,
+        })
+    }
+
+    fn power(&mut self) -> Result<Expr, PythonError> {
+        let base = self.atom()?;
+
+        if self.is_op("**") {
+            let position = self.next().position;
+            // "**" is right-associative and binds tighter than unary minus
+            // on its left, but not on its right (i.e. 2**-1)
+            let exponent = self.factor()?;
+            return Ok(binary(BinaryOp::Power, base, exponent, position));
+        }
+
+        Ok(base)
+    }
+
+    fn atom(&mut self) -> Result<Expr, PythonError> {
+        let token = self.peek().clone();
+
+        match token.kind {
+            TokenKind::Number(text) => {
+                self.next();
+                Ok(Expr::Number(parse_number(&text, token.position)?))
+            },
+            TokenKind::Op("(") => {
+                self.next();
+                let expr = self.expression()?;
+                self.expect_op(")")?;
+                Ok(expr)
+            },
+            TokenKind::Name(name) if name == "True" || name == "False" => {
+                self.next();
+                Ok(Expr::Bool(name == "True"))
+            },
+            TokenKind::Name(_) => {
+                let (mut name, position) = self.expect_name()?;
+
+                if self.aliases.contains(&name) && self.is_op(".") {
+                    self.next();
+                    name = self.expect_name()?.0;
+
+                    match name.as_str() {
+                        "pi" => return Ok(Expr::Number(std::f64::consts::PI)),
+                        "e" => return Ok(Expr::Number(std::f64::consts::E)),
+                        "inf" => return Ok(Expr::Number(f64::INFINITY)),
+                        _ if !self.is_op("(") => {
+                            return Err(PythonError::new(
+                                position,
+                                format!("Unknown constant, \"{}\"", name),
+                            ))
+                        },
+                        _ => {},
+                    }
+                }
+
+                if self.eat_op("(") {
+                    let mut args = Vec::new();
+                    while !self.is_op(")") {
+                        args.push(self.expression()?);
+                        if !self.eat_op(",") {
+                            break;
+                        }
+                    }
+                    self.expect_op(")")?;
+
+                    return Ok(Expr::Call {
+                        function: name,
+                        args,
+                        position,
+                    });
+                }
+
+                Ok(Expr::Name { name, position })
+            },
+            _ => Err(self.unexpected("an expression")),
+        }
+    }
+}
+
+fn binary(op: BinaryOp, left: Expr, right: Expr, position: Position) -> Expr {
+    Expr::Binary {
+        op,
+        left: Box::new(left),
+        right: Box::new(right),
+        position,
+    }
+}
+
+fn is_keyword(name: &str) -> bool {
+    matches!(
+        name,
+        "and"
+            | "as"
+            | "def"
+            | "elif"
+            | "else"
+            | "False"
+            | "for"
+            | "from"
+            | "if"
+            | "import"
+            | "not"
+            | "or"
+            | "pass"
+            | "return"
+            | "True"
+            | "while"
+    )
+}
+
+fn parse_number(text: &str, position: Position) -> Result<f64, PythonError> {
+    text.replace('_', "").parse().map_err(|_| {
+        PythonError::new(position, format!("Invalid number, \"{}\"", text))
+    })
+}
+
+/// Remove the common indentation from a docstring, like
+/// `inspect.cleandoc()`.
+fn dedent(docstring: &str) -> String {
+    let lines: Vec<&str> = docstring.lines().collect();
+    let indent = lines
+        .iter()
+        .skip(1)
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+
+    let mut cleaned: Vec<&str> = lines
+        .iter()
+        .enumerate()
+        .map(|(i, line)| {
+            if i == 0 {
+                line.trim()
+            } else {
+                line.get(indent..).unwrap_or("").trim_end()
+            }
+        })
+        .collect();
+
+    while cleaned.first().map_or(false, |l| l.is_empty()) {
+        cleaned.remove(0);
+    }
+    while cleaned.last().map_or(false, |l| l.is_empty()) {
+        cleaned.pop();
+    }
+
+    cleaned.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn name(name: &str, line: usize, column: usize) -> Expr {
+        Expr::Name {
+            name: name.to_string(),
+            position: Position { line, column },
+        }
+    }
+
+    #[test]
+    fn parse_a_complete_proc_block() {
+        let src = r#"
+"""
+Scale values into the range [0, 1].
+"""
+
+import numpy as np
+
+max_value: float = 255.0
+"""The largest possible value."""
+
+def transform(x: u8) -> f32:
+    # values above the maximum are clipped
+    if x > max_value:
+        return 1.0
+    return x / max_value
+"#;
+
+        let got = parse(src).unwrap();
+
+        assert_eq!(
+            got.docstring.as_deref(),
+            Some("Scale values into the range [0, 1].")
+        );
+        assert_eq!(
+            got.parameters,
+            vec![Parameter {
+                name: "max_value".to_string(),
+                ty: ParameterType::Float,
+                default: Literal::Number(255.0),
+                description: Some("The largest possible value.".to_string()),
+                position: Position { line: 8, column: 1 },
+            }]
+        );
+        assert_eq!(got.function.input, "x");
+        assert_eq!(got.function.input_type, ElementType::U8);
+        assert_eq!(got.function.output_type, ElementType::F32);
+        assert_eq!(
+            got.function.body,
+            vec![
+                Stmt::If {
+                    branches: vec![(
+                        Expr::Compare {
+                            left: Box::new(name("x", 13, 8)),
+                            comparisons: vec![(
+                                CompareOp::Greater,
+                                name("max_value", 13, 12)
+                            )],
+                            position: Position {
+                                line: 13,
+                                column: 8
+                            },
+                        },
+                        vec![Stmt::Return {
+                            value: Expr::Number(1.0),
+                            position: Position {
+                                line: 14,
+                                column: 9
+                            },
+                        }]
+                    )],
+                    otherwise: None,
+                },
+                Stmt::Return {
+                    value: binary(
+                        BinaryOp::Divide,
+                        name("x", 15, 12),
+                        name("max_value", 15, 16),
+                        Position {
+                            line: 15,
+                            column: 14
+                        },
+                    ),
+                    position: Position {
+                        line: 15,
+                        column: 5
+                    },
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn operator_precedence() {
+        let src = "def transform(x: f32) -> f32:\n    return -x ** 2 + 1 * 3\n";
+
+        let got = parse(src).unwrap();
+
+        let position = |column| Position { line: 2, column };
+        let should_be = binary(
+            BinaryOp::Add,
+            Expr::Unary {
+                op: UnaryOp::Negate,
+                operand: Box::new(binary(
+                    BinaryOp::Power,
+                    name("x", 2, 13),
+                    Expr::Number(2.0),
+                    position(15),
+                )),
+                position: position(12),
+            },
+            binary(
+                BinaryOp::Multiply,
+                Expr::Number(1.0),
+                Expr::Number(3.0),
+                position(24),
+            ),
+            position(20),
+        );
+        assert_eq!(
+            got.function.body,
+            vec![Stmt::Return {
+                value: should_be,
+                position: position(5),
+            }]
+        );
+    }
+
+    #[test]
+    fn module_constants_are_inlined() {
+        let src =
+            "import math\ndef transform(x: f32) -> f32:\n    return math.pi\n";
+
+        let got = parse(src).unwrap();
+
+        assert_eq!(
+            got.function.body,
+            vec![Stmt::Return {
+                value: Expr::Number(std::f64::consts::PI),
+                position: Position { line: 3, column: 5 },
+            }]
+        );
+    }
+
+    #[test]
+    fn expressions_can_span_multiple_lines_inside_parentheses() {
+        let src =
+            "def transform(x: f32) -> f32:\n    return (x +\n        1)\n";
+
+        let got = parse(src).unwrap();
+
+        assert_eq!(got.function.body.len(), 1);
+    }
+
+    #[test]
+    fn loops_are_not_supported() {
+        let src =
+            "def transform(x: f32) -> f32:\n    for i in x:\n        pass\n";
+
+        let err = parse(src).unwrap_err();
+
+        assert_eq!(err.position(), Position { line: 2, column: 5 });
+        assert!(err.message.starts_with("\"for\" isn't supported"));
+    }
+
+    #[test]
+    fn only_math_and_numpy_can_be_imported() {
+        let err = parse("import os\n").unwrap_err();
+
+        assert_eq!(
+            err.message,
+            "Unable to import \"os\". Only math and numpy can be imported"
+        );
+    }
+
+    #[test]
+    fn parameters_need_a_type() {
+        let err = parse("threshold = 0.5\n").unwrap_err();
+
+        assert_eq!(err.position(), Position { line: 1, column: 1 });
+    }
+
+    #[test]
+    fn a_transform_function_is_required() {
+        let err = parse("threshold: float = 0.5\n").unwrap_err();
+
+        assert_eq!(
+            err.message,
+            "The file doesn't define a transform() function"
+        );
+    }
+
+    #[test]
+    fn inconsistent_indentation_is_an_error() {
+        let src = "def transform(x: f32) -> f32:\n    y = x\n  return y\n";
+
+        let err = parse(src).unwrap_err();
+
+        assert_eq!(err.position(), Position { line: 3, column: 3 });
+    }
+
+    #[test]
+    fn dedent_docstrings() {
+        let src = "\n    First line.\n\n    Second paragraph.\n    ";
+
+        assert_eq!(dedent(src), "First line.\n\nSecond paragraph.");
+    }
+}
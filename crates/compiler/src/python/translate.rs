@@ -0,0 +1,1061 @@
+//! Translate a parsed Python proc block into Rust.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use heck::{ToSnakeCase, ToUpperCamelCase};
+use hotg_rune_core::ElementType;
+use proc_macro2::{Ident, Literal as Lit, Span, TokenStream};
+use quote::{format_ident, quote};
+
+use crate::python::{
+    parse::{
+        BinaryOp, CompareOp, Expr, Function, Literal, Module, Parameter,
+        ParameterType, Position, Stmt, UnaryOp,
+    },
+    GeneratedCrate, PythonError,
+};
+
+pub(crate) fn translate(
+    name: &str,
+    filename: &str,
+    module: &Module,
+) -> Result<GeneratedCrate, PythonError> {
+    Ok(GeneratedCrate {
+        cargo_toml: cargo_toml(name),
+        lib_rs: lib_rs(name, filename, module)?,
+    })
+}
+
+fn cargo_toml(name: &str) -> String {
+    format!(
+        indoc::indoc!(
+            r#"
+            [package]
+            name = "{name}"
+            version = "0.0.0"
+            edition = "2018"
+            publish = false
+
+            [dependencies]
+            hotg-rune-core = {{ version = "^{core}", default-features = false }}
+            hotg-rune-proc-blocks = "^{proc_blocks}"
+            libm = "0.2.1"
+            "#
+        ),
+        name = name,
+        core = hotg_rune_core::VERSION,
+        proc_blocks = hotg_rune_proc_blocks::VERSION,
+    )
+}
+
+/// Generate the crate's `lib.rs`.
+fn lib_rs(
+    name: &str,
+    filename: &str,
+    module: &Module,
+) -> Result<String, PythonError> {
+    let Module {
+        docstring,
+        parameters,
+        function,
+    } = module;
+
+    let type_name = Ident::new(
+        &name.to_snake_case().to_upper_camel_case(),
+        Span::call_site(),
+    );
+    let crate_docs = doc_lines(docstring.as_deref());
+
+    let mut fields = Vec::new();
+    let mut defaults = Vec::new();
+
+    for parameter in parameters {
+        if parameter.name == function.input {
+            return Err(PythonError::new(
+                parameter.position,
+                format!(
+                    "The \"{}\" parameter has the same name as transform()'s \
+                     argument",
+                    parameter.name
+                ),
+            ));
+        }
+
+        let ident = rust_ident(&parameter.name, parameter.position)?;
+        let ty = parameter_type(parameter.ty);
+        let (default, default_str) = default_value(parameter)?;
+        let docs = doc_lines(parameter.description.as_deref());
+
+        fields.push(quote! {
+            #( #[doc = #docs] )*
+            #[proc_block(default = #default_str)]
+            pub #ident: #ty
+        });
+        defaults.push(quote!(#ident: #default));
+    }
+
+    let transform = Translator::new(module).transform(&type_name, function)?;
+
+    let tokens = quote! {
+        #( #![doc = #crate_docs] )*
+        #![no_std]
+        #![allow(
+            unused_mut,
+            unused_assignments,
+            unused_parens,
+            unreachable_code,
+            clippy::all
+        )]
+
+        extern crate alloc;
+
+        use hotg_rune_proc_blocks::{ProcBlock, Tensor, Transform};
+
+        #( #[doc = #crate_docs] )*
+        #[derive(Debug, Clone, PartialEq, ProcBlock)]
+        pub struct #type_name {
+            #( #fields ),*
+        }
+
+        impl Default for #type_name {
+            fn default() -> Self {
+                #type_name {
+                    #( #defaults ),*
+                }
+            }
+        }
+
+        #transform
+    };
+
+    let lib_rs = format!(
+        "// This file was generated from \"{}\" by `rune build`. DO NOT \
+         EDIT.\n\n{}\n",
+        filename, tokens
+    );
+
+    Ok(lib_rs)
+}
+
+fn doc_lines(docstring: Option<&str>) -> Vec<String> {
+    docstring
+        .into_iter()
+        .flat_map(|d| d.lines())
+        .map(|line| format!(" {}", line))
+        .collect()
+}
+
+fn parameter_type(ty: ParameterType) -> TokenStream {
+    match ty {
+        ParameterType::Float => quote!(f64),
+        ParameterType::Int => quote!(i64),
+        ParameterType::Bool => quote!(bool),
+    }
+}
+
+/// Get the Rust expression for a parameter's default value, plus the string
+/// used in the proc block's descriptor.
+fn default_value(
+    parameter: &Parameter,
+) -> Result<(TokenStream, String), PythonError> {
+    match (parameter.ty, parameter.default) {
+        (ParameterType::Float, Literal::Number(n)) => {
+            Ok((number(n), format!("{:?}", n)))
+        },
+        (ParameterType::Int, Literal::Number(n)) if n.fract() == 0.0 => {
+            let n = n as i64;
+            Ok((quote!(#n), n.to_string()))
+        },
+        (ParameterType::Bool, Literal::Bool(b)) => {
+            Ok((quote!(#b), b.to_string()))
+        },
+        (ty, _) => {
+            let expected = match ty {
+                ParameterType::Float => "a float",
+                ParameterType::Int => "a whole number",
+                ParameterType::Bool => "True or False",
+            };
+            Err(PythonError::new(
+                parameter.position,
+                format!(
+                    "The default value for \"{}\" should be {}",
+                    parameter.name, expected
+                ),
+            ))
+        },
+    }
+}
+
+fn number(n: f64) -> TokenStream {
+    if n == f64::INFINITY {
+        quote!(f64::INFINITY)
+    } else if n == f64::NEG_INFINITY {
+        quote!(f64::NEG_INFINITY)
+    } else {
+        let n = Lit::f64_suffixed(n);
+        quote!(#n)
+    }
+}
+
+/// Python identifiers which can't be used as-is in Rust.
+const RESERVED: &[&str] = &[
+    "_", "abstract", "async", "await", "become", "box", "break", "const",
+    "continue", "crate", "do", "dyn", "enum", "extern", "final", "fn", "impl",
+    "in", "let", "loop", "macro", "match", "mod", "move", "mut", "override",
+    "priv", "pub", "ref", "self", "Self", "static", "struct", "super", "trait",
+    "true", "false", "try", "type", "typeof", "unsafe", "unsized", "use",
+    "virtual", "where", "yield",
+];
+
+fn rust_ident(name: &str, position: Position) -> Result<Ident, PythonError> {
+    if RESERVED.contains(&name) || name.starts_with("__rune") {
+        return Err(PythonError::new(
+            position,
+            format!("\"{}\" is a reserved name", name),
+        ));
+    }
+
+    Ok(Ident::new(name, Span::call_site()))
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Type {
+    Number,
+    Bool,
+}
+
+impl Type {
+    fn rust_type(self) -> TokenStream {
+        match self {
+            Type::Number => quote!(f64),
+            Type::Bool => quote!(bool),
+        }
+    }
+}
+
+/// A statistic calculated from the entire input tensor.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum Reduction {
+    Sum,
+    Mean,
+    Min,
+    Max,
+    StdDev,
+}
+
+impl Reduction {
+    fn from_function(name: &str) -> Option<Self> {
+        match name {
+            "sum" => Some(Reduction::Sum),
+            "mean" => Some(Reduction::Mean),
+            "min" | "amin" => Some(Reduction::Min),
+            "max" | "amax" => Some(Reduction::Max),
+            "std" => Some(Reduction::StdDev),
+            _ => None,
+        }
+    }
+
+    fn variable(self) -> Ident {
+        match self {
+            Reduction::Sum => format_ident!("__rune_sum"),
+            Reduction::Mean => format_ident!("__rune_mean"),
+            Reduction::Min => format_ident!("__rune_min"),
+            Reduction::Max => format_ident!("__rune_max"),
+            Reduction::StdDev => format_ident!("__rune_std"),
+        }
+    }
+
+    fn calculate(self) -> TokenStream {
+        let values = quote!(input.elements().iter().map(|&v| v as f64));
+        let len = quote!((input.elements().len() as f64));
+
+        match self {
+            Reduction::Sum => quote!(#values.sum::<f64>()),
+            Reduction::Mean => quote!(#values.sum::<f64>() / #len),
+            Reduction::Min => quote!(#values.fold(f64::INFINITY, libm::fmin)),
+            Reduction::Max => {
+                quote!(#values.fold(f64::NEG_INFINITY, libm::fmax))
+            },
+            Reduction::StdDev => quote! {{
+                let mean = #values.sum::<f64>() / #len;
+                let variance = #values
+                    .map(|v| (v - mean) * (v - mean))
+                    .sum::<f64>() / #len;
+                libm::sqrt(variance)
+            }},
+        }
+    }
+}
+
+struct Translator<'m> {
+    module: &'m Module,
+    /// Every local variable that has been assigned to so far.
+    locals: BTreeMap<String, Type>,
+    reductions: BTreeSet<Reduction>,
+}
+
+impl<'m> Translator<'m> {
+    fn new(module: &'m Module) -> Self {
+        let mut locals = BTreeMap::new();
+        locals.insert(module.function.input.clone(), Type::Number);
+
+        Translator {
+            module,
+            locals,
+            reductions: BTreeSet::new(),
+        }
+    }
+
+    /// Generate the `Transform` implementation.
+    ///
+    /// Proc blocks which don't use any reductions are elementwise, so they
+    /// also implement `TransformElement` so the Rune can fuse them with
+    /// neighbouring elementwise proc blocks.
+    fn transform(
+        mut self,
+        type_name: &Ident,
+        function: &Function,
+    ) -> Result<TokenStream, PythonError> {
+        let input = rust_ident(&function.input, function.position)?;
+        let input_type =
+            Ident::new(function.input_type.rune_name(), Span::call_site());
+        let output_type =
+            Ident::new(function.output_type.rune_name(), Span::call_site());
+
+        let mut assigned = BTreeSet::new();
+        assigned.insert(function.input.clone());
+        let (body, always_returns) =
+            self.block(&function.body, &mut assigned)?;
+
+        if !always_returns {
+            return Err(PythonError::new(
+                function.position,
+                "transform() doesn't return a value on every path",
+            ));
+        }
+
+        let parameters = self.module.parameters.iter().map(|p| {
+            let ident = Ident::new(&p.name, Span::call_site());
+            match p.ty {
+                ParameterType::Float => quote!(let #ident: f64 = self.#ident;),
+                ParameterType::Int => {
+                    quote!(let #ident: f64 = self.#ident as f64;)
+                },
+                ParameterType::Bool => quote!(let #ident: bool = self.#ident;),
+            }
+        });
+
+        let declarations = self
+            .locals
+            .iter()
+            .filter(|(name, _)| **name != function.input)
+            .map(|(name, ty)| {
+                let ident = Ident::new(name, Span::call_site());
+                let ty = ty.rust_type();
+                quote!(let mut #ident: #ty;)
+            });
+
+        if self.reductions.is_empty() {
+            let tokens = quote! {
+                impl hotg_rune_proc_blocks::TransformElement for #type_name {
+                    type Input = #input_type;
+                    type Output = #output_type;
+
+                    fn transform_element(&mut self, #input: #input_type) -> #output_type {
+                        let mut #input = #input as f64;
+                        #( #parameters )*
+                        #( #declarations )*
+
+                        #body
+                    }
+                }
+
+                impl Transform<Tensor<#input_type>> for #type_name {
+                    type Output = Tensor<#output_type>;
+
+                    fn transform(&mut self, input: Tensor<#input_type>) -> Tensor<#output_type> {
+                        input.map(|_, &element| {
+                            hotg_rune_proc_blocks::TransformElement::transform_element(self, element)
+                        })
+                    }
+                }
+            };
+
+            return Ok(tokens);
+        }
+
+        let reductions = self.reductions.iter().map(|r| {
+            let variable = r.variable();
+            let value = r.calculate();
+            quote!(let #variable: f64 = #value;)
+        });
+
+        let tokens = quote! {
+            impl Transform<Tensor<#input_type>> for #type_name {
+                type Output = Tensor<#output_type>;
+
+                fn transform(&mut self, input: Tensor<#input_type>) -> Tensor<#output_type> {
+                    #( #reductions )*
+
+                    input.map(|_, &#input| {
+                        let mut #input = #input as f64;
+                        #( #parameters )*
+                        #( #declarations )*
+
+                        #body
+                    })
+                }
+            }
+        };
+
+        Ok(tokens)
+    }
+
+    /// Translate a block of statements, returning the generated code and
+    /// whether every path through the block returns.
+    ///
+    /// The `assigned` set is updated with the variables which are definitely
+    /// assigned by the end of the block.
+    fn block(
+        &mut self,
+        stmts: &[Stmt],
+        assigned: &mut BTreeSet<String>,
+    ) -> Result<(TokenStream, bool), PythonError> {
+        let mut tokens = TokenStream::new();
+        let mut always_returns = false;
+
+        for stmt in stmts {
+            let (stmt, returns) = self.statement(stmt, assigned)?;
+            tokens.extend(stmt);
+            always_returns |= returns;
+        }
+
+        Ok((tokens, always_returns))
+    }
+
+    fn statement(
+        &mut self,
+        stmt: &Stmt,
+        assigned: &mut BTreeSet<String>,
+    ) -> Result<(TokenStream, bool), PythonError> {
+        match stmt {
+            Stmt::Assign {
+                target,
+                value,
+                position,
+            } => {
+                if self.module.parameters.iter().any(|p| p.name == *target) {
+                    return Err(PythonError::new(
+                        *position,
+                        format!(
+                            "Unable to assign to the \"{}\" parameter",
+                            target
+                        ),
+                    ));
+                }
+
+                let ident = rust_ident(target, *position)?;
+                let (value, ty) = self.expression(value, assigned)?;
+
+                match self.locals.get(target) {
+                    Some(existing) if *existing != ty => {
+                        return Err(PythonError::new(
+                            *position,
+                            format!(
+                                "\"{}\" was previously a {} but is now being \
+                                 assigned a {}",
+                                target,
+                                type_name(*existing),
+                                type_name(ty)
+                            ),
+                        ));
+                    },
+                    Some(_) => {},
+                    None => {
+                        self.locals.insert(target.clone(), ty);
+                    },
+                }
+                assigned.insert(target.clone());
+
+                Ok((quote!(#ident = #value;), false))
+            },
+            Stmt::Return { value, .. } => {
+                let (value, ty) = self.expression(value, assigned)?;
+                let output_type = self.module.function.output_type;
+
+                let value = if output_type == ElementType::Bool {
+                    as_bool(value, ty)
+                } else {
+                    let value = as_number(value, ty);
+                    let output_type =
+                        Ident::new(output_type.rune_name(), Span::call_site());
+                    quote!(#value as #output_type)
+                };
+
+                Ok((quote!(return #value;), true))
+            },
+            Stmt::If {
+                branches,
+                otherwise,
+            } => {
+                let mut tokens = TokenStream::new();
+                // The variables assigned by each branch that falls through
+                let mut fallthrough = Vec::new();
+
+                for (i, (condition, body)) in branches.iter().enumerate() {
+                    let (condition, ty) =
+                        self.expression(condition, assigned)?;
+                    let condition = as_bool(condition, ty);
+
+                    let mut branch_assigned = assigned.clone();
+                    let (body, returns) =
+                        self.block(body, &mut branch_assigned)?;
+                    if !returns {
+                        fallthrough.push(branch_assigned);
+                    }
+
+                    if i == 0 {
+                        tokens.extend(quote!(if #condition { #body }));
+                    } else {
+                        tokens.extend(quote!(else if #condition { #body }));
+                    }
+                }
+
+                match otherwise {
+                    Some(body) => {
+                        let mut branch_assigned = assigned.clone();
+                        let (body, returns) =
+                            self.block(body, &mut branch_assigned)?;
+                        if !returns {
+                            fallthrough.push(branch_assigned);
+                        }
+                        tokens.extend(quote!(else { #body }));
+                    },
+                    None => fallthrough.push(assigned.clone()),
+                }
+
+                let always_returns = fallthrough.is_empty();
+                if let Some((first, rest)) = fallthrough.split_first() {
+                    *assigned = first
+                        .iter()
+                        .filter(|name| rest.iter().all(|s| s.contains(*name)))
+                        .cloned()
+                        .collect();
+                }
+
+                Ok((tokens, always_returns))
+            },
+            Stmt::Pass => Ok((TokenStream::new(), false)),
+        }
+    }
+
+    fn expression(
+        &mut self,
+        expr: &Expr,
+        assigned: &BTreeSet<String>,
+    ) -> Result<(TokenStream, Type), PythonError> {
+        match expr {
+            Expr::Number(n) => Ok((number(*n), Type::Number)),
+            Expr::Bool(b) => Ok((quote!(#b), Type::Bool)),
+            Expr::Name { name, position } => {
+                self.variable(name, *position, assigned)
+            },
+            Expr::Unary { op, operand, .. } => {
+                let (operand, ty) = self.expression(operand, assigned)?;
+
+                match op {
+                    UnaryOp::Negate => {
+                        let operand = as_number(operand, ty);
+                        Ok((quote!((-#operand)), Type::Number))
+                    },
+                    UnaryOp::Plus => Ok((as_number(operand, ty), Type::Number)),
+                    UnaryOp::Not => {
+                        let operand = as_bool(operand, ty);
+                        Ok((quote!((!#operand)), Type::Bool))
+                    },
+                }
+            },
+            Expr::Binary {
+                op, left, right, ..
+            } => {
+                let (left, left_ty) = self.expression(left, assigned)?;
+                let (right, right_ty) = self.expression(right, assigned)?;
+
+                if let BinaryOp::And | BinaryOp::Or = op {
+                    let left = as_bool(left, left_ty);
+                    let right = as_bool(right, right_ty);
+                    let tokens = match op {
+                        BinaryOp::And => quote!((#left && #right)),
+                        _ => quote!((#left || #right)),
+                    };
+                    return Ok((tokens, Type::Bool));
+                }
+
+                let a = as_number(left, left_ty);
+                let b = as_number(right, right_ty);
+
+                let tokens = match op {
+                    BinaryOp::Add => quote!((#a + #b)),
+                    BinaryOp::Subtract => quote!((#a - #b)),
+                    BinaryOp::Multiply => quote!((#a * #b)),
+                    BinaryOp::Divide => quote!((#a / #b)),
+                    BinaryOp::FloorDivide => quote!(libm::floor(#a / #b)),
+                    // Python's modulo takes the sign of the divisor
+                    BinaryOp::Modulo => quote! {{
+                        let (a, b): (f64, f64) = (#a, #b);
+                        a - b * libm::floor(a / b)
+                    }},
+                    BinaryOp::Power => quote!(libm::pow(#a, #b)),
+                    BinaryOp::And | BinaryOp::Or => unreachable!(),
+                };
+
+                Ok((tokens, Type::Number))
+            },
+            Expr::Compare {
+                left, comparisons, ..
+            } => {
+                let mut previous = self.expression(left, assigned)?;
+                let mut checks = Vec::new();
+
+                for (op, right) in comparisons {
+                    let right = self.expression(right, assigned)?;
+                    checks.push(compare(*op, previous, right.clone()));
+                    previous = right;
+                }
+
+                Ok((quote!((#( #checks )&&*)), Type::Bool))
+            },
+            Expr::Conditional {
+                condition,
+                then,
+                otherwise,
+            } => {
+                let (condition, ty) = self.expression(condition, assigned)?;
+                let condition = as_bool(condition, ty);
+                let (then, then_ty) = self.expression(then, assigned)?;
+                let (otherwise, otherwise_ty) =
+                    self.expression(otherwise, assigned)?;
+
+                if then_ty == otherwise_ty {
+                    Ok((
+                        quote!((if #condition { #then } else { #otherwise })),
+                        then_ty,
+                    ))
+                } else {
+                    let then = as_number(then, then_ty);
+                    let otherwise = as_number(otherwise, otherwise_ty);
+                    Ok((
+                        quote!((if #condition { #then } else { #otherwise })),
+                        Type::Number,
+                    ))
+                }
+            },
+            Expr::Call {
+                function,
+                args,
+                position,
+            } => self.call(function, args, *position, assigned),
+        }
+    }
+
+    fn variable(
+        &self,
+        name: &str,
+        position: Position,
+        assigned: &BTreeSet<String>,
+    ) -> Result<(TokenStream, Type), PythonError> {
+        let ident = rust_ident(name, position)?;
+
+        if let Some(p) = self.module.parameters.iter().find(|p| p.name == name)
+        {
+            let ty = match p.ty {
+                ParameterType::Bool => Type::Bool,
+                ParameterType::Float | ParameterType::Int => Type::Number,
+            };
+            return Ok((quote!(#ident), ty));
+        }
+
+        match self.locals.get(name) {
+            Some(ty) if assigned.contains(name) => Ok((quote!(#ident), *ty)),
+            Some(_) => Err(PythonError::new(
+                position,
+                format!("\"{}\" may be used before it is assigned", name),
+            )),
+            None => Err(PythonError::new(
+                position,
+                format!("Unknown variable, \"{}\"", name),
+            )),
+        }
+    }
+
+    fn call(
+        &mut self,
+        function: &str,
+        args: &[Expr],
+        position: Position,
+        assigned: &BTreeSet<String>,
+    ) -> Result<(TokenStream, Type), PythonError> {
+        if let Some(reduction) = Reduction::from_function(function) {
+            match args {
+                [Expr::Name { name, .. }]
+                    if *name == self.module.function.input =>
+                {
+                    self.reductions.insert(reduction);
+                    let variable = reduction.variable();
+                    return Ok((quote!(#variable), Type::Number));
+                },
+                _ if function == "min" || function == "max" => {},
+                _ => {
+                    return Err(PythonError::new(
+                        position,
+                        format!(
+                            "{}() can only be called with the input tensor, \
+                             \"{}\"",
+                            function, self.module.function.input
+                        ),
+                    ))
+                },
+            }
+        }
+
+        let mut translated = Vec::new();
+        for arg in args {
+            translated.push(self.expression(arg, assigned)?);
+        }
+
+        let arity = |expected: usize| {
+            if translated.len() == expected {
+                Ok(())
+            } else {
+                Err(PythonError::new(
+                    position,
+                    format!(
+                        "{}() takes {} argument{}, but {} were given",
+                        function,
+                        expected,
+                        if expected == 1 { "" } else { "s" },
+                        translated.len()
+                    ),
+                ))
+            }
+        };
+        let numbers: Vec<TokenStream> = translated
+            .iter()
+            .cloned()
+            .map(|(tokens, ty)| as_number(tokens, ty))
+            .collect();
+
+        let tokens = match function {
+            "abs" => {
+                arity(1)?;
+                let a = &numbers[0];
+                quote!(libm::fabs(#a))
+            },
+            "sqrt" | "exp" | "log2" | "log10" | "sin" | "cos" | "tan"
+            | "tanh" | "floor" | "ceil" => {
+                arity(1)?;
+                let a = &numbers[0];
+                let f = Ident::new(function, Span::call_site());
+                quote!(libm::#f(#a))
+            },
+            "log" if numbers.len() == 2 => {
+                let (a, base) = (&numbers[0], &numbers[1]);
+                quote!((libm::log(#a) / libm::log(#base)))
+            },
+            "log" => {
+                arity(1)?;
+                let a = &numbers[0];
+                quote!(libm::log(#a))
+            },
+            // Python rounds half-way cases to the nearest even number
+            "round" => {
+                arity(1)?;
+                let a = &numbers[0];
+                quote!(libm::rint(#a))
+            },
+            "float" => {
+                arity(1)?;
+                numbers[0].clone()
+            },
+            "int" => {
+                arity(1)?;
+                let a = &numbers[0];
+                quote!(libm::trunc(#a))
+            },
+            "bool" => {
+                arity(1)?;
+                let (a, ty) = translated[0].clone();
+                return Ok((as_bool(a, ty), Type::Bool));
+            },
+            "pow" | "power" => {
+                arity(2)?;
+                let (a, b) = (&numbers[0], &numbers[1]);
+                quote!(libm::pow(#a, #b))
+            },
+            "min" | "max" | "minimum" | "maximum" => {
+                if numbers.len() < 2 {
+                    return Err(PythonError::new(
+                        position,
+                        format!(
+                            "{}() needs at least 2 arguments or the input \
+                             tensor",
+                            function
+                        ),
+                    ));
+                }
+                let f = if function.starts_with("min") {
+                    quote!(libm::fmin)
+                } else {
+                    quote!(libm::fmax)
+                };
+                let first = &numbers[0];
+                numbers[1..]
+                    .iter()
+                    .fold(first.clone(), |acc, n| quote!(#f(#acc, #n)))
+            },
+            "clip" => {
+                arity(3)?;
+                let (a, low, high) = (&numbers[0], &numbers[1], &numbers[2]);
+                quote!(libm::fmin(libm::fmax(#a, #low), #high))
+            },
+            _ => {
+                return Err(PythonError::new(
+                    position,
+                    format!("Unknown function, \"{}\"", function),
+                ))
+            },
+        };
+
+        Ok((tokens, Type::Number))
+    }
+}
+
+fn compare(
+    op: CompareOp,
+    (left, left_ty): (TokenStream, Type),
+    (right, right_ty): (TokenStream, Type),
+) -> TokenStream {
+    let (left, right) = match (op, left_ty, right_ty) {
+        (CompareOp::Equal, Type::Bool, Type::Bool)
+        | (CompareOp::NotEqual, Type::Bool, Type::Bool) => (left, right),
+        _ => (as_number(left, left_ty), as_number(right, right_ty)),
+    };
+
+    match op {
+        CompareOp::Less => quote!(#left < #right),
+        CompareOp::LessEqual => quote!(#left <= #right),
+        CompareOp::Greater => quote!(#left > #right),
+        CompareOp::GreaterEqual => quote!(#left >= #right),
+        CompareOp::Equal => quote!(#left == #right),
+        CompareOp::NotEqual => quote!(#left != #right),
+    }
+}
+
+/// Convert a value to a number, treating `True` as `1.0` like Python does.
+fn as_number(tokens: TokenStream, ty: Type) -> TokenStream {
+    match ty {
+        Type::Number => tokens,
+        Type::Bool => quote!((if #tokens { 1.0_f64 } else { 0.0_f64 })),
+    }
+}
+
+/// Convert a value to a bool using Python's "truthiness" rules.
+fn as_bool(tokens: TokenStream, ty: Type) -> TokenStream {
+    match ty {
+        Type::Bool => tokens,
+        Type::Number => quote!((#tokens != 0.0_f64)),
+    }
+}
+
+fn type_name(ty: Type) -> &'static str {
+    match ty {
+        Type::Number => "number",
+        Type::Bool => "bool",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::python::parse::parse;
+
+    fn translate_src(src: &str) -> Result<GeneratedCrate, PythonError> {
+        let module = parse(src).unwrap();
+        translate("scale", "scale.py", &module)
+    }
+
+    fn normalize(tokens: &str) -> String {
+        tokens.parse::<TokenStream>().unwrap().to_string()
+    }
+
+    #[test]
+    fn generate_the_proc_block() {
+        let src = r#"
+"""Rescale values to the range [0, 1]."""
+
+max_value: float = 255.0
+"""The largest value."""
+
+def transform(x: u8) -> f32:
+    y = x / max_value
+    return y
+"#;
+
+        let got = translate_src(src).unwrap();
+
+        let lib_rs = got.lib_rs.splitn(2, '\n').nth(1).unwrap();
+        let should_be = quote! {
+            #![doc = " Rescale values to the range [0, 1]."]
+            #![no_std]
+            #![allow(
+                unused_mut,
+                unused_assignments,
+                unused_parens,
+                unreachable_code,
+                clippy::all
+            )]
+
+            extern crate alloc;
+
+            use hotg_rune_proc_blocks::{ProcBlock, Tensor, Transform};
+
+            #[doc = " Rescale values to the range [0, 1]."]
+            #[derive(Debug, Clone, PartialEq, ProcBlock)]
+            pub struct Scale {
+                #[doc = " The largest value."]
+                #[proc_block(default = "255.0")]
+                pub max_value: f64
+            }
+
+            impl Default for Scale {
+                fn default() -> Self {
+                    Scale { max_value: 255f64 }
+                }
+            }
+
+            impl hotg_rune_proc_blocks::TransformElement for Scale {
+                type Input = u8;
+                type Output = f32;
+
+                fn transform_element(&mut self, x: u8) -> f32 {
+                    let mut x = x as f64;
+                    let max_value: f64 = self.max_value;
+                    let mut y: f64;
+
+                    y = (x / max_value);
+                    return y as f32;
+                }
+            }
+
+            impl Transform<Tensor<u8>> for Scale {
+                type Output = Tensor<f32>;
+
+                fn transform(&mut self, input: Tensor<u8>) -> Tensor<f32> {
+                    input.map(|_, &element| {
+                        hotg_rune_proc_blocks::TransformElement::transform_element(self, element)
+                    })
+                }
+            }
+        };
+        assert_eq!(normalize(lib_rs), should_be.to_string());
+        assert!(got.lib_rs.starts_with(
+            "// This file was generated from \"scale.py\" by `rune build`."
+        ));
+        assert!(got.cargo_toml.contains("name = \"scale\""));
+    }
+
+    #[test]
+    fn reductions_are_calculated_once() {
+        let src = "import numpy as np\ndef transform(x: f32) -> f32:\n    \
+                   return (x - np.mean(x)) / np.std(x)\n";
+
+        let got = translate_src(src).unwrap();
+
+        assert_eq!(got.lib_rs.matches("let __rune_mean").count(), 1);
+        assert_eq!(got.lib_rs.matches("let __rune_std").count(), 1);
+        assert!(!got.lib_rs.contains("__rune_sum"));
+        // The whole tensor is needed, so it can't be fused
+        assert!(!got.lib_rs.contains("TransformElement"));
+    }
+
+    #[test]
+    fn all_paths_must_return() {
+        let src =
+            "def transform(x: f32) -> f32:\n    if x > 0:\n        return x\n";
+
+        let err = translate_src(src).unwrap_err();
+
+        assert_eq!(
+            err.message,
+            "transform() doesn't return a value on every path"
+        );
+    }
+
+    #[test]
+    fn variables_assigned_on_every_branch_can_be_used() {
+        let src = "def transform(x: f32) -> f32:\n    if x > 0:\n        y = \
+                   1\n    else:\n        y = -1\n    return y\n";
+
+        assert!(translate_src(src).is_ok());
+    }
+
+    #[test]
+    fn variables_assigned_on_some_branches_cant_be_used() {
+        let src = "def transform(x: f32) -> f32:\n    if x > 0:\n        y = \
+                   1\n    return y\n";
+
+        let err = translate_src(src).unwrap_err();
+
+        assert_eq!(
+            err.position(),
+            Position {
+                line: 4,
+                column: 12
+            }
+        );
+        assert_eq!(err.message, "\"y\" may be used before it is assigned");
+    }
+
+    #[test]
+    fn parameters_cant_be_reassigned() {
+        let src = "offset: int = 1\ndef transform(x: i32) -> i32:\n    offset \
+                   = 2\n    return x + offset\n";
+
+        let err = translate_src(src).unwrap_err();
+
+        assert_eq!(err.message, "Unable to assign to the \"offset\" parameter");
+    }
+
+    #[test]
+    fn variables_cant_change_type() {
+        let src =
+            "def transform(x: f32) -> f32:\n    y = 1\n    y = x > 0\n    \
+             return y\n";
+
+        let err = translate_src(src).unwrap_err();
+
+        assert_eq!(err.position(), Position { line: 3, column: 5 });
+    }
+
+    #[test]
+    fn unknown_functions_are_an_error() {
+        let src = "def transform(x: f32) -> f32:\n    return sigmoid(x)\n";
+
+        let err = translate_src(src).unwrap_err();
+
+        assert_eq!(err.message, "Unknown function, \"sigmoid\"");
+    }
+
+    #[test]
+    fn int_parameters_need_a_whole_number() {
+        let src =
+            "offset: int = 1.5\ndef transform(x: f32) -> f32:\n    return x\n";
+
+        let err = translate_src(src).unwrap_err();
+
+        assert_eq!(
+            err.message,
+            "The default value for \"offset\" should be a whole number"
+        );
+    }
+}
@@ -0,0 +1,384 @@
+//! Proc blocks that are referred to by a short name (e.g. `hotg-ai/mfcc`)
+//! instead of a full git URL.
+//!
+//! A registry is a JSON [`Index`] (usually served over HTTP) listing the
+//! releases for each proc block. The `rune install` command picks a release
+//! from the index, downloads it, and records where it was saved in the
+//! [`Installed`] manifest, which the compiler uses to resolve short names
+//! when a Rune is built.
+
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Display, Formatter},
+    path::{Path, PathBuf},
+};
+
+pub use semver::{Version, VersionReq};
+
+/// The name of the [`Installed`] manifest inside the install directory.
+pub const INSTALLED_MANIFEST: &str = "installed.json";
+
+/// The directory proc blocks are installed to by default.
+pub fn default_install_dir() -> PathBuf {
+    dirs::cache_dir()
+        .or_else(dirs::home_dir)
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("rune")
+        .join("registry")
+}
+
+/// Parse the version requirement from a proc block's path, where a missing
+/// version matches anything.
+///
+/// A leading `v` is ignored, so a tag like `v0.11.3` is treated like
+/// `^0.11.3`.
+pub fn parse_requirement(
+    version: Option<&str>,
+) -> Result<VersionReq, semver::Error> {
+    match version {
+        Some(version) => version.trim_start_matches('v').parse(),
+        None => Ok(VersionReq::STAR),
+    }
+}
+
+/// The list of proc blocks known to a registry.
+#[derive(
+    Debug, Default, Clone, PartialEq, serde::Serialize, serde::Deserialize,
+)]
+pub struct Index {
+    #[serde(rename = "proc-blocks")]
+    pub proc_blocks: BTreeMap<String, Vec<Release>>,
+}
+
+impl Index {
+    /// Find the latest release of a proc block which satisfies the
+    /// requirement.
+    pub fn resolve(
+        &self,
+        name: &str,
+        requirement: &VersionReq,
+    ) -> Result<&Release, ResolveError> {
+        let releases = self.proc_blocks.get(name).ok_or_else(|| {
+            ResolveError::UnknownProcBlock {
+                name: name.to_string(),
+            }
+        })?;
+
+        releases
+            .iter()
+            .filter(|r| requirement.matches(&r.version))
+            .max_by(|a, b| a.version.cmp(&b.version))
+            .ok_or_else(|| ResolveError::NoMatchingVersion {
+                name: name.to_string(),
+                requirement: requirement.clone(),
+                available: releases.iter().map(|r| r.version.clone()).collect(),
+            })
+    }
+}
+
+/// A single version of a proc block, and where to find its source code.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Release {
+    pub version: Version,
+    /// The git repository containing the proc block.
+    pub git: String,
+    /// The tag, branch, or commit to check out (defaults to the repository's
+    /// default branch).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rev: Option<String>,
+    /// The proc block's directory inside the repository, if it isn't at the
+    /// root.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+}
+
+/// The error returned when a proc block can't be found in an [`Index`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum ResolveError {
+    UnknownProcBlock {
+        name: String,
+    },
+    NoMatchingVersion {
+        name: String,
+        requirement: VersionReq,
+        available: Vec<Version>,
+    },
+}
+
+impl Display for ResolveError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ResolveError::UnknownProcBlock { name } => {
+                write!(f, "The registry doesn't contain \"{}\"", name)
+            },
+            ResolveError::NoMatchingVersion {
+                name,
+                requirement,
+                available,
+            } => {
+                write!(
+                    f,
+                    "No version of \"{}\" matches \"{}\" (available: ",
+                    name, requirement
+                )?;
+                for (i, version) in available.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", version)?;
+                }
+                write!(f, ")")
+            },
+        }
+    }
+}
+
+impl std::error::Error for ResolveError {}
+
+/// The proc blocks which have been installed locally.
+#[derive(
+    Debug, Default, Clone, PartialEq, serde::Serialize, serde::Deserialize,
+)]
+pub struct Installed {
+    #[serde(rename = "proc-blocks")]
+    pub proc_blocks: BTreeMap<String, Vec<InstalledProcBlock>>,
+}
+
+impl Installed {
+    /// Load the manifest from an install directory, returning an empty
+    /// manifest if nothing has been installed yet.
+    pub fn load(install_dir: &Path) -> Result<Self, std::io::Error> {
+        let filename = install_dir.join(INSTALLED_MANIFEST);
+
+        match std::fs::read(&filename) {
+            Ok(json) => serde_json::from_slice(&json).map_err(|e| {
+                std::io::Error::new(std::io::ErrorKind::InvalidData, e)
+            }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                Ok(Installed::default())
+            },
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn save(&self, install_dir: &Path) -> Result<(), std::io::Error> {
+        std::fs::create_dir_all(install_dir)?;
+        let json = serde_json::to_vec_pretty(self)
+            .expect("Serializing to JSON should never fail");
+
+        std::fs::write(install_dir.join(INSTALLED_MANIFEST), json)
+    }
+
+    /// Find the latest installed version of a proc block which satisfies the
+    /// requirement.
+    pub fn find(
+        &self,
+        name: &str,
+        requirement: &VersionReq,
+    ) -> Option<&InstalledProcBlock> {
+        self.proc_blocks
+            .get(name)?
+            .iter()
+            .filter(|p| requirement.matches(&p.version))
+            .max_by(|a, b| a.version.cmp(&b.version))
+    }
+
+    /// Record that a proc block was installed, replacing any existing entry
+    /// with the same version.
+    pub fn insert(&mut self, name: &str, proc_block: InstalledProcBlock) {
+        let versions = self.proc_blocks.entry(name.to_string()).or_default();
+        versions.retain(|p| p.version != proc_block.version);
+        versions.push(proc_block);
+        versions.sort_by(|a, b| a.version.cmp(&b.version));
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct InstalledProcBlock {
+    pub version: Version,
+    /// The directory containing the proc block's `Cargo.toml`.
+    pub path: PathBuf,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index() -> Index {
+        serde_json::from_str(
+            r#"{
+                "proc-blocks": {
+                    "hotg-ai/mfcc": [
+                        {
+                            "version": "0.11.3",
+                            "git": "https://github.com/hotg-ai/rune.git",
+                            "rev": "v0.11.3",
+                            "path": "proc-blocks/mfcc"
+                        },
+                        {
+                            "version": "0.12.0",
+                            "git": "https://github.com/hotg-ai/rune.git",
+                            "rev": "v0.12.0",
+                            "path": "proc-blocks/mfcc"
+                        },
+                        {
+                            "version": "0.11.0",
+                            "git": "https://github.com/hotg-ai/rune.git"
+                        }
+                    ]
+                }
+            }"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn resolve_the_latest_matching_release() {
+        let index = index();
+
+        let latest = index.resolve("hotg-ai/mfcc", &VersionReq::STAR).unwrap();
+        assert_eq!(latest.version, Version::new(0, 12, 0));
+
+        let req = parse_requirement(Some("^0.11")).unwrap();
+        let got = index.resolve("hotg-ai/mfcc", &req).unwrap();
+        assert_eq!(got.version, Version::new(0, 11, 3));
+        assert_eq!(got.rev.as_deref(), Some("v0.11.3"));
+    }
+
+    #[test]
+    fn resolve_errors() {
+        let index = index();
+
+        let err = index.resolve("hotg-ai/fft", &VersionReq::STAR).unwrap_err();
+        assert_eq!(
+            err,
+            ResolveError::UnknownProcBlock {
+                name: "hotg-ai/fft".to_string()
+            }
+        );
+
+        let req = parse_requirement(Some("^1.0")).unwrap();
+        let err = index.resolve("hotg-ai/mfcc", &req).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "No version of \"hotg-ai/mfcc\" matches \"^1.0\" (available: \
+             0.11.3, 0.12.0, 0.11.0)"
+        );
+    }
+
+    #[test]
+    fn git_tags_are_valid_requirements() {
+        let req = parse_requirement(Some("v0.11.3")).unwrap();
+
+        assert!(req.matches(&Version::new(0, 11, 5)));
+        assert!(!req.matches(&Version::new(0, 11, 2)));
+        assert!(parse_requirement(Some("refs/heads/master")).is_err());
+    }
+
+    #[test]
+    fn find_installed_proc_blocks() {
+        let mut installed = Installed::default();
+        for version in &[Version::new(0, 1, 0), Version::new(0, 1, 5)] {
+            let path = PathBuf::from(format!("mfcc-{}", version));
+            installed.insert(
+                "hotg-ai/mfcc",
+                InstalledProcBlock {
+                    version: version.clone(),
+                    path,
+                },
+            );
+        }
+
+        let got = installed.find("hotg-ai/mfcc", &VersionReq::STAR).unwrap();
+        assert_eq!(got.path, PathBuf::from("mfcc-0.1.5"));
+
+        let req = parse_requirement(Some("=0.1.0")).unwrap();
+        let got = installed.find("hotg-ai/mfcc", &req).unwrap();
+        assert_eq!(got.path, PathBuf::from("mfcc-0.1.0"));
+
+        assert!(installed.find("hotg-ai/fft", &VersionReq::STAR).is_none());
+    }
+
+    #[test]
+    fn reinstalling_replaces_the_old_entry() {
+        let mut installed = Installed::default();
+        let first = InstalledProcBlock {
+            version: Version::new(1, 0, 0),
+            path: PathBuf::from("first"),
+        };
+        let second = InstalledProcBlock {
+            path: PathBuf::from("second"),
+            ..first.clone()
+        };
+
+        installed.insert("hotg-ai/fft", first);
+        installed.insert("hotg-ai/fft", second.clone());
+
+        assert_eq!(installed.proc_blocks["hotg-ai/fft"], vec![second]);
+    }
+
+    #[test]
+    fn builtin_proc_blocks_are_in_the_default_index() {
+        let repo_root = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .parent()
+            .and_then(Path::parent)
+            .unwrap();
+        let proc_blocks = repo_root.join("proc-blocks");
+        let json = std::fs::read(proc_blocks.join("index.json")).unwrap();
+
+        let index: Index = serde_json::from_slice(&json).unwrap();
+
+        for entry in std::fs::read_dir(&proc_blocks).unwrap() {
+            let entry = entry.unwrap();
+            let manifest = entry.path().join("Cargo.toml");
+            if !manifest.exists() {
+                continue;
+            }
+
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let release = index
+                .resolve(&format!("hotg-ai/{}", name), &VersionReq::STAR)
+                .unwrap();
+            let path = release.path.as_deref().unwrap();
+            assert_eq!(path, format!("proc-blocks/{}", name));
+
+            let manifest = cargo_toml::Manifest::from_path(&manifest).unwrap();
+            let version = manifest.package.unwrap().version;
+            assert_eq!(release.version.to_string(), version, "{}", name);
+        }
+    }
+
+    #[test]
+    fn the_default_index_only_refers_to_the_branch_it_is_served_from() {
+        let repo_root = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .parent()
+            .and_then(Path::parent)
+            .unwrap();
+        let json =
+            std::fs::read(repo_root.join("proc-blocks").join("index.json"))
+                .unwrap();
+
+        let index: Index = serde_json::from_slice(&json).unwrap();
+
+        // The proc blocks were added after the last release was tagged, so
+        // pinning a tag would check out a tree without them. Instead, the
+        // index is served from the default branch and releases use whatever
+        // that branch contains.
+        for (name, releases) in &index.proc_blocks {
+            for release in releases {
+                assert_eq!(release.rev, None, "{} {}", name, release.version);
+                let path = release.path.as_deref().unwrap();
+                assert!(repo_root.join(path).join("Cargo.toml").exists());
+            }
+        }
+    }
+
+    #[test]
+    fn missing_manifest_means_nothing_is_installed() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("does-not-exist");
+
+        let got = Installed::load(&dir).unwrap();
+
+        assert_eq!(got, Installed::default());
+    }
+}
@@ -117,6 +117,7 @@ macro_rules! parse_and_analyse {
                     rune_version: Some(RuneVersion {
                         version: env!("CARGO_PKG_VERSION").to_string(),
                     }),
+                    install_dir: None,
                 }
             }
 
@@ -7,19 +7,19 @@ use syn::{
     parse::{Parse, ParseStream, Parser},
     punctuated::Punctuated,
     spanned::Spanned,
-    Attribute, DeriveInput, Error, ExprLit, Lit, LitStr, Path, Token,
+    Attribute, DeriveInput, Error, ExprLit, Lit, LitStr, Path, Token, Type,
     TypeArray, TypePath, TypeReference,
 };
 
 use crate::{
     descriptor::{
-        Dimension, Dimensions, ProcBlockDescriptor, TensorDescriptor,
-        TensorDescriptors, TransformDescriptor,
+        Dimension, Dimensions, ParameterDescriptor, ProcBlockDescriptor,
+        TensorDescriptor, TensorDescriptors, TransformDescriptor,
     },
     types::{
-        Assertions, CustomSection, DeriveOutput, ProcBlockImpl, Setter,
-        SetterAssertion, SetterAssertions, Setters, TransformAssertion,
-        TransformAssertions,
+        Assertions, CustomSection, DeriveOutput, ElementwiseImpl,
+        ProcBlockImpl, Setter, SetterAssertion, SetterAssertions, Setters,
+        TransformAssertion, TransformAssertions,
     },
 };
 
@@ -30,22 +30,39 @@ pub(crate) fn analyse(input: &DeriveInput) -> Result<DeriveOutput, Error> {
     let (description, available_transforms, transform_assertions) =
         analyse_struct_attributes(&input.ident, &exports, &input.attrs)?;
 
-    let (setters, setter_assertions) = analyse_properties(input)?;
+    let LifecycleHooks {
+        init,
+        reset,
+        elementwise,
+    } = lifecycle_hooks(&input.attrs)?;
+    let elementwise = elementwise
+        .map(|function| {
+            elementwise_impl(input, &exports, &available_transforms, function)
+        })
+        .transpose()?;
+
+    let (setters, setter_assertions, parameters) =
+        analyse_properties(input, &exports)?;
 
     let descriptor = ProcBlockDescriptor {
         type_name: type_name.to_string().into(),
         description: description.into(),
         available_transforms: available_transforms.into(),
+        parameters: parameters.into(),
+        abi_version: ProcBlockDescriptor::ABI_VERSION,
     };
 
     Ok(DeriveOutput {
         setters,
         custom_section: make_custom_section(&input.ident, &descriptor)?,
+        elementwise,
         trait_impl: ProcBlockImpl {
             exports,
             type_name,
             descriptor,
             generics: input.generics.clone(),
+            init,
+            reset,
         },
         assertions: Assertions {
             set: setter_assertions,
@@ -97,7 +114,56 @@ fn analyse_struct_attributes(
     ))
 }
 
+/// Get the element types for a `#[proc_block(elementwise = ...)]` proc block
+/// from its only transform.
+fn elementwise_impl(
+    input: &DeriveInput,
+    exports: &Path,
+    transforms: &[TransformDescriptor<'_>],
+    function: Path,
+) -> Result<ElementwiseImpl, Error> {
+    let (input_type, output_type) = match transforms {
+        [TransformDescriptor { inputs, outputs }] => {
+            match (inputs.as_ref(), outputs.as_ref()) {
+                ([input], [output]) => {
+                    (input.element_type, output.element_type)
+                },
+                _ => {
+                    return Err(Error::new(
+                        function.span(),
+                        "An elementwise proc block's transform must have a \
+                         single input and output",
+                    ))
+                },
+            }
+        },
+        _ => {
+            return Err(Error::new(
+                function.span(),
+                "An elementwise proc block must have exactly one \
+                 #[transform(...)] attribute",
+            ))
+        },
+    };
+
+    Ok(ElementwiseImpl {
+        type_name: input.ident.clone(),
+        exports: exports.clone(),
+        generics: input.generics.clone(),
+        function,
+        input: to_rust_element(exports, &input_type),
+        output: to_rust_element(exports, &output_type),
+    })
+}
+
 fn to_rust_tensor(exports: &Path, ty: &ElementType) -> syn::Type {
+    let element_type = to_rust_element(exports, ty);
+
+    syn::parse2(quote!(#exports::Tensor<#element_type>))
+        .expect("We should always be able to parse a type")
+}
+
+fn to_rust_element(exports: &Path, ty: &ElementType) -> Type {
     let element_type = match ty {
         ElementType::U8 => quote!(u8),
         ElementType::I8 => quote!(i8),
@@ -110,10 +176,14 @@ fn to_rust_tensor(exports: &Path, ty: &ElementType) -> syn::Type {
         ElementType::F64 => quote!(f64),
         ElementType::I64 => quote!(i64),
         ElementType::String => quote!(#exports::Cow<'static, str>),
+        ElementType::F16 => quote!(#exports::f16),
+        ElementType::BF16 => quote!(#exports::bf16),
+        ElementType::Bool => quote!(bool),
+        ElementType::C64 => quote!(#exports::Complex<f32>),
+        ElementType::C128 => quote!(#exports::Complex<f64>),
     };
 
-    syn::parse2(quote!(#exports::Tensor<#element_type>))
-        .expect("We should always be able to parse a type")
+    syn::parse2(element_type).expect("We should always be able to parse a type")
 }
 
 fn transforms(
@@ -184,8 +254,29 @@ fn parse_tensor_descriptors(
     }
 }
 
+/// Parse a tensor descriptor, optionally prefixed with a name (e.g.
+/// `boxes: [f32; 2]`).
 fn parse_tensor_descriptor(
     input: ParseStream,
+) -> Result<TensorDescriptor<'static>, Error> {
+    let name = if input.peek(syn::Ident)
+        && input.peek2(Token![:])
+        && !input.peek2(Token![::])
+    {
+        let name: Ident = input.parse()?;
+        let _: Token![:] = input.parse()?;
+        Some(name.to_string().into())
+    } else {
+        None
+    };
+
+    let tensor = parse_unnamed_tensor_descriptor(input)?;
+
+    Ok(TensorDescriptor { name, ..tensor })
+}
+
+fn parse_unnamed_tensor_descriptor(
+    input: ParseStream,
 ) -> Result<TensorDescriptor<'static>, Error> {
     if input.peek(syn::token::Bracket) {
         let TypeArray { elem, len, .. } = input.parse()?;
@@ -211,12 +302,14 @@ fn parse_tensor_descriptor(
         };
 
         Ok(TensorDescriptor {
+            name: None,
             element_type,
             dimensions,
         })
     } else {
         let element = input.parse()?;
         Ok(TensorDescriptor {
+            name: None,
             element_type: known_type_from_syn_type(&element)?,
             dimensions: vec![Dimension::Value(1)].into(),
         })
@@ -232,6 +325,10 @@ fn known_type_from_syn_type(ty: &syn::Type) -> Result<ElementType, Error> {
                 return ElementType::from_str(&name)
                     .map_err(|e| Error::new(ty.span(), e));
             }
+
+            if let Some(element_type) = complex_type(&p.path) {
+                return Ok(element_type);
+            }
         },
         syn::Type::Reference(TypeReference { elem, .. }) => match &**elem {
             syn::Type::Path(TypePath { path, .. }) if path.is_ident("str") => {
@@ -245,6 +342,90 @@ fn known_type_from_syn_type(ty: &syn::Type) -> Result<ElementType, Error> {
     Err(Error::new(ty.span(), "Unknown tensor element type"))
 }
 
+/// Recognise `Complex<f32>` and `Complex<f64>`.
+fn complex_type(path: &Path) -> Option<ElementType> {
+    let last = path.segments.last()?;
+
+    if last.ident != "Complex" {
+        return None;
+    }
+
+    let args = match &last.arguments {
+        syn::PathArguments::AngleBracketed(args) => &args.args,
+        _ => return None,
+    };
+
+    match args.iter().collect::<Vec<_>>().as_slice() {
+        [syn::GenericArgument::Type(syn::Type::Path(p))] => {
+            if p.path.is_ident("f32") {
+                Some(ElementType::C64)
+            } else if p.path.is_ident("f64") {
+                Some(ElementType::C128)
+            } else {
+                None
+            }
+        },
+        _ => None,
+    }
+}
+
+#[derive(Default)]
+struct LifecycleHooks {
+    init: Option<Path>,
+    reset: Option<Path>,
+    elementwise: Option<Path>,
+}
+
+/// Parse the `init = ..., reset = ..., elementwise = ...` from a
+/// `#[proc_block(...)]` attribute on the struct itself.
+fn lifecycle_hooks(attrs: &[Attribute]) -> Result<LifecycleHooks, Error> {
+    let mut hooks = LifecycleHooks::default();
+
+    for attr in attrs {
+        if !attr.path.is_ident("proc_block") {
+            continue;
+        }
+
+        let pairs = attr.parse_args_with(|input: ParseStream| {
+            Punctuated::<_, Token![,]>::parse_terminated_with(input, parse_hook)
+        })?;
+
+        for (ident, path) in pairs {
+            let slot = if ident == "init" {
+                &mut hooks.init
+            } else if ident == "reset" {
+                &mut hooks.reset
+            } else if ident == "elementwise" {
+                &mut hooks.elementwise
+            } else {
+                return Err(Error::new(
+                    ident.span(),
+                    format!("Unknown attribute, \"{}\"", ident),
+                ));
+            };
+
+            if slot.is_some() {
+                return Err(Error::new(
+                    ident.span(),
+                    format!("The \"{}\" hook was specified twice", ident),
+                ));
+            }
+
+            *slot = Some(path);
+        }
+    }
+
+    Ok(hooks)
+}
+
+fn parse_hook(input: ParseStream) -> Result<(Ident, Path), Error> {
+    let ident: Ident = input.parse()?;
+    let _: Token![=] = input.parse()?;
+    let path: Path = input.parse()?;
+
+    Ok((ident, path))
+}
+
 fn doc_comments(attrs: &[Attribute]) -> Result<String, Error> {
     let mut docs: Vec<String> = Vec::new();
 
@@ -293,7 +474,15 @@ fn remove_leading_space(s: &str) -> &str { s.strip_prefix(' ').unwrap_or(s) }
 
 fn analyse_properties(
     input: &DeriveInput,
-) -> Result<(Setters, SetterAssertions), Error> {
+    exports: &Path,
+) -> Result<
+    (
+        Setters,
+        SetterAssertions,
+        Vec<ParameterDescriptor<'static>>,
+    ),
+    Error,
+> {
     let data = match &input.data {
         syn::Data::Struct(s) => s,
         _ => return Err(Error::new(input.span(), "")),
@@ -301,6 +490,7 @@ fn analyse_properties(
 
     let mut setters = Vec::new();
     let mut assertions = Vec::new();
+    let mut parameters = Vec::new();
 
     for field in &data.fields {
         if let Some(parsed) = parse_parameter(field)? {
@@ -308,8 +498,18 @@ fn analyse_properties(
                 property,
                 property_type,
                 possible_types,
+                description,
+                validator,
+                default_value,
             } = parsed;
 
+            parameters.push(ParameterDescriptor {
+                name: property.to_string().into(),
+                description: description.into(),
+                type_name: type_name(&property_type).into(),
+                default_value: default_value.map(Into::into),
+            });
+
             let new_assertions =
                 possible_types.into_iter().map(|ty| SetterAssertion {
                     proc_block_type: input.ident.clone(),
@@ -321,6 +521,8 @@ fn analyse_properties(
             setters.push(Setter {
                 property,
                 property_type,
+                exports: exports.clone(),
+                validator,
             });
         }
     }
@@ -330,17 +532,29 @@ fn analyse_properties(
     Ok((
         Setters {
             type_name,
+            exports: exports.clone(),
             setters,
             generics: input.generics.clone(),
         },
         SetterAssertions(assertions),
+        parameters,
     ))
 }
 
+/// A human-friendly version of a type's name (e.g. `Vec<String>` instead of
+/// `Vec < String >`).
+fn type_name(ty: &syn::Type) -> String {
+    quote!(#ty).to_string().replace(' ', "")
+}
+
 struct ParsedField {
     property: Ident,
     property_type: syn::Type,
     possible_types: Vec<syn::Type>,
+    description: String,
+    validator: Option<Path>,
+    /// The value from `#[proc_block(default = "...")]`.
+    default_value: Option<String>,
 }
 
 fn parse_parameter(field: &syn::Field) -> Result<Option<ParsedField>, Error> {
@@ -352,6 +566,17 @@ fn parse_parameter(field: &syn::Field) -> Result<Option<ParsedField>, Error> {
         return Ok(None);
     }
 
+    let mut validator = None;
+    let mut default_value = None;
+
+    for attr in attrs {
+        match attr {
+            FieldAttribute::Validate(path) => validator = Some(path),
+            FieldAttribute::Default(value) => default_value = Some(value),
+            FieldAttribute::Skipped => {},
+        }
+    }
+
     let property = match &field.ident {
         Some(id) => id.clone(),
         None => {
@@ -368,6 +593,9 @@ fn parse_parameter(field: &syn::Field) -> Result<Option<ParsedField>, Error> {
         property,
         property_type,
         possible_types,
+        description: doc_comments(&field.attrs)?,
+        validator,
+        default_value,
     }))
 }
 
@@ -386,6 +614,8 @@ fn field_attributes(attrs: &[Attribute]) -> Result<Vec<FieldAttribute>, Error> {
 
 enum FieldAttribute {
     Skipped,
+    Validate(Path),
+    Default(String),
 }
 
 impl Parse for FieldAttribute {
@@ -393,13 +623,28 @@ impl Parse for FieldAttribute {
         let ident: Ident = input.parse()?;
 
         if input.peek(Token![=]) {
-            todo!("Handle key=value attributes: {:?}", input);
+            let _: Token![=] = input.parse()?;
+
+            return if ident == "validate" {
+                Ok(FieldAttribute::Validate(input.parse()?))
+            } else if ident == "default" {
+                let value: LitStr = input.parse()?;
+                Ok(FieldAttribute::Default(value.value()))
+            } else {
+                Err(Error::new(
+                    ident.span(),
+                    format!("Unknown attribute, \"{}\"", ident),
+                ))
+            };
         }
 
         if ident == "skip" {
             Ok(FieldAttribute::Skipped)
         } else {
-            todo!("Handle #[proc_block({})]", ident)
+            Err(Error::new(
+                ident.span(),
+                format!("Unknown attribute, \"{}\"", ident),
+            ))
         }
     }
 }
@@ -458,11 +703,13 @@ mod tests {
         let expected_transforms = &[
             TransformDescriptor {
                 inputs: TensorDescriptor {
+                    name: None,
                     element_type: ElementType::F32,
                     dimensions: vec![Dimension::Value(1)].into(),
                 }
                 .into(),
                 outputs: TensorDescriptor {
+                    name: None,
                     element_type: ElementType::U8,
                     dimensions: vec![Dimension::Any; 3].into(),
                 }
@@ -470,11 +717,13 @@ mod tests {
             },
             TransformDescriptor {
                 inputs: TensorDescriptor {
+                    name: None,
                     element_type: ElementType::F32,
                     dimensions: vec![Dimension::Value(1)].into(),
                 }
                 .into(),
                 outputs: TensorDescriptor {
+                    name: None,
                     element_type: ElementType::U8,
                     dimensions: vec![Dimension::Any; 2].into(),
                 }
@@ -519,6 +768,83 @@ mod tests {
         assert_eq!(transform_assertions, expected_assertions);
     }
 
+    #[test]
+    fn lifecycle_hook_attributes() {
+        let tokens = quote! {
+            #[proc_block(init = Self::allocate_window, reset = clear)]
+            struct Proc {}
+        };
+        let input: DeriveInput = syn::parse2(tokens).unwrap();
+
+        let LifecycleHooks {
+            init,
+            reset,
+            elementwise,
+        } = lifecycle_hooks(&input.attrs).unwrap();
+
+        assert_eq!(
+            init,
+            Some(syn::parse_str("Self::allocate_window").unwrap())
+        );
+        assert_eq!(reset, Some(syn::parse_str("clear").unwrap()));
+        assert_eq!(elementwise, None);
+    }
+
+    #[test]
+    fn elementwise_proc_block() {
+        let tokens = quote! {
+            #[proc_block(elementwise = Self::clip)]
+            #[transform(inputs = [f32; _], outputs = [u8; _])]
+            struct Proc {}
+        };
+        let input: DeriveInput = syn::parse2(tokens).unwrap();
+
+        let got = analyse(&input).unwrap().elementwise.unwrap();
+
+        assert_eq!(got.function, syn::parse_str("Self::clip").unwrap());
+        assert_eq!(got.input, syn::parse_str("f32").unwrap());
+        assert_eq!(got.output, syn::parse_str("u8").unwrap());
+    }
+
+    #[test]
+    fn elementwise_proc_blocks_need_a_single_input_and_output() {
+        let inputs = vec![
+            quote! {
+                #[proc_block(elementwise = Self::clip)]
+                struct Proc {}
+            },
+            quote! {
+                #[proc_block(elementwise = Self::clip)]
+                #[transform(inputs = f32, outputs = f32)]
+                #[transform(inputs = u8, outputs = u8)]
+                struct Proc {}
+            },
+            quote! {
+                #[proc_block(elementwise = Self::clip)]
+                #[transform(inputs = (f32, f32), outputs = f32)]
+                struct Proc {}
+            },
+        ];
+
+        for tokens in inputs {
+            let input: DeriveInput = syn::parse2(tokens).unwrap();
+
+            assert!(analyse(&input).is_err());
+        }
+    }
+
+    #[test]
+    fn duplicate_lifecycle_hooks_are_an_error() {
+        let tokens = quote! {
+            #[proc_block(reset = first)]
+            #[proc_block(reset = second)]
+            struct Proc {}
+        };
+        let input: DeriveInput = syn::parse2(tokens).unwrap();
+
+        assert!(lifecycle_hooks(&input.attrs).is_err());
+    }
+
     #[test]
     fn properties() {
         let tokens = quote! {
@@ -527,27 +853,80 @@ mod tests {
                 first: u32,
                 #[proc_block(skip)]
                 second: Vec<String>,
+                #[proc_block(validate = check_third, default = "a,b")]
+                third: Vec<String>,
             }
         };
         let input: DeriveInput = syn::parse2(tokens).unwrap();
+        let exports: Path = syn::parse_str("exports").unwrap();
         let expected_setters = Setters {
             type_name: syn::parse_str("Proc").unwrap(),
-            setters: vec![Setter {
-                property: syn::parse_str("first").unwrap(),
-                property_type: syn::parse_str("u32").unwrap(),
-            }],
+            exports: exports.clone(),
+            setters: vec![
+                Setter {
+                    property: syn::parse_str("first").unwrap(),
+                    property_type: syn::parse_str("u32").unwrap(),
+                    exports: exports.clone(),
+                    validator: None,
+                },
+                Setter {
+                    property: syn::parse_str("third").unwrap(),
+                    property_type: syn::parse_str("Vec<String>").unwrap(),
+                    exports: exports.clone(),
+                    validator: Some(syn::parse_str("check_third").unwrap()),
+                },
+            ],
             generics: Generics::default(),
         };
-        let expected_assertions = SetterAssertions(vec![SetterAssertion {
-            proc_block_type: syn::parse_str("Proc").unwrap(),
-            property: syn::parse_str("first").unwrap(),
-            setter_argument: syn::parse_str("u32").unwrap(),
-        }]);
+        let expected_assertions = SetterAssertions(vec![
+            SetterAssertion {
+                proc_block_type: syn::parse_str("Proc").unwrap(),
+                property: syn::parse_str("first").unwrap(),
+                setter_argument: syn::parse_str("u32").unwrap(),
+            },
+            SetterAssertion {
+                proc_block_type: syn::parse_str("Proc").unwrap(),
+                property: syn::parse_str("third").unwrap(),
+                setter_argument: syn::parse_str("Vec<String>").unwrap(),
+            },
+        ]);
+        let expected_parameters = vec![
+            ParameterDescriptor {
+                name: "first".into(),
+                description: "The first item.".into(),
+                type_name: "u32".into(),
+                default_value: None,
+            },
+            ParameterDescriptor {
+                name: "third".into(),
+                description: "".into(),
+                type_name: "Vec<String>".into(),
+                default_value: Some("a,b".into()),
+            },
+        ];
 
-        let (setters, assertions) = analyse_properties(&input).unwrap();
+        let (setters, assertions, parameters) =
+            analyse_properties(&input, &exports).unwrap();
 
         assert_eq!(setters, expected_setters);
         assert_eq!(assertions, expected_assertions);
+        assert_eq!(parameters, expected_parameters);
+    }
+
+    #[test]
+    fn unknown_field_attributes_are_errors() {
+        let tokens = quote! {
+            struct Proc {
+                #[proc_block(rename = "x")]
+                first: u32,
+            }
+        };
+        let input: DeriveInput = syn::parse2(tokens).unwrap();
+        let exports: Path = syn::parse_str("exports").unwrap();
+
+        let err = analyse_properties(&input, &exports).unwrap_err();
+
+        assert_eq!(err.to_string(), "Unknown attribute, \"rename\"");
     }
 
     #[test]
@@ -598,18 +977,22 @@ mod type_tests {
     }
 
     parse_tensor_type!(one_dimension_tensor, i16 => TensorDescriptor {
+       name: None,
        element_type: ElementType::I16,
        dimensions: vec![Dimension::Value(1)].into(),
     });
     parse_tensor_type!(parse_f32_rank_3, [f32; 3] => TensorDescriptor {
+       name: None,
        element_type: ElementType::F32,
        dimensions: vec![Dimension::Any, Dimension::Any, Dimension::Any].into(),
     });
     parse_tensor_type!(parse_arbitrary_length, [u8; _] => TensorDescriptor {
+       name: None,
        element_type: ElementType::U8,
        dimensions: Dimensions::Arbitrary,
     });
     parse_tensor_type!(parse_str_type, utf8 => TensorDescriptor {
+       name: None,
        element_type: ElementType::String,
        dimensions: vec![Dimension::Value(1)].into(),
     });
@@ -635,6 +1018,7 @@ mod type_tests {
         TransformDescriptor {
             inputs: TensorDescriptors(Cow::Borrowed(&[
                 TensorDescriptor {
+                    name: None,
                     element_type: ElementType::F32,
                     dimensions: Dimensions::Finite(Cow::Borrowed(&[
                         Dimension::Any,
@@ -643,6 +1027,7 @@ mod type_tests {
             ])),
             outputs: TensorDescriptors(Cow::Borrowed(&[
                 TensorDescriptor {
+                    name: None,
                     element_type: ElementType::F32,
                     dimensions: Dimensions::Finite(Cow::Borrowed(&[
                         Dimension::Any,
@@ -657,12 +1042,14 @@ mod type_tests {
         TransformDescriptor {
             inputs: TensorDescriptors(Cow::Borrowed(&[
                 TensorDescriptor {
+                    name: None,
                     element_type: ElementType::F32,
                     dimensions: Dimensions::Finite(Cow::Borrowed(&[
                         Dimension::Any,
                     ])),
                 },
                 TensorDescriptor {
+                    name: None,
                     element_type: ElementType::U8,
                     dimensions: Dimensions::Finite(Cow::Borrowed(&[
                         Dimension::Any,
@@ -672,6 +1059,7 @@ mod type_tests {
             ])),
             outputs: TensorDescriptors(Cow::Borrowed(&[
                 TensorDescriptor {
+                    name: None,
                     element_type: ElementType::F32,
                     dimensions: Dimensions::Finite(Cow::Borrowed(&[
                         Dimension::Any,
@@ -680,4 +1068,63 @@ mod type_tests {
             ])),
         },
     );
+
+    parse_transform_attribute!(transform_attribute_with_complex_outputs,
+        #[transform(inputs = [f32; 1], outputs = [Complex<f32>; 1])] =>
+        TransformDescriptor {
+            inputs: TensorDescriptors(Cow::Borrowed(&[
+                TensorDescriptor {
+                    name: None,
+                    element_type: ElementType::F32,
+                    dimensions: Dimensions::Finite(Cow::Borrowed(&[
+                        Dimension::Any,
+                    ])),
+                },
+            ])),
+            outputs: TensorDescriptors(Cow::Borrowed(&[
+                TensorDescriptor {
+                    name: None,
+                    element_type: ElementType::C64,
+                    dimensions: Dimensions::Finite(Cow::Borrowed(&[
+                        Dimension::Any,
+                    ])),
+                },
+            ])),
+        },
+    );
+
+    parse_transform_attribute!(transform_attribute_with_named_tensors,
+        #[transform(inputs = (image: [u8; 4], boxes: [f32; 2]), outputs = labels: utf8)] =>
+        TransformDescriptor {
+            inputs: TensorDescriptors(Cow::Borrowed(&[
+                TensorDescriptor {
+                    name: Some(Cow::Borrowed("image")),
+                    element_type: ElementType::U8,
+                    dimensions: Dimensions::Finite(Cow::Borrowed(&[
+                        Dimension::Any,
+                        Dimension::Any,
+                        Dimension::Any,
+                        Dimension::Any,
+                    ])),
+                },
+                TensorDescriptor {
+                    name: Some(Cow::Borrowed("boxes")),
+                    element_type: ElementType::F32,
+                    dimensions: Dimensions::Finite(Cow::Borrowed(&[
+                        Dimension::Any,
+                        Dimension::Any,
+                    ])),
+                },
+            ])),
+            outputs: TensorDescriptors(Cow::Borrowed(&[
+                TensorDescriptor {
+                    name: Some(Cow::Borrowed("labels")),
+                    element_type: ElementType::String,
+                    dimensions: Dimensions::Finite(Cow::Borrowed(&[
+                        Dimension::Value(1),
+                    ])),
+                },
+            ])),
+        },
+    );
 }
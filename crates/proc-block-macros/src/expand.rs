@@ -5,13 +5,13 @@ use syn::{Generics, LitByteStr, Path, Type};
 
 use crate::{
     descriptor::{
-        Dimension, Dimensions, ProcBlockDescriptor, TensorDescriptor,
-        TransformDescriptor,
+        Dimension, Dimensions, ParameterDescriptor, ProcBlockDescriptor,
+        TensorDescriptor, TransformDescriptor,
     },
     types::{
-        Assertions, CustomSection, DeriveOutput, ProcBlockImpl, Setter,
-        SetterAssertion, SetterAssertions, Setters, TransformAssertion,
-        TransformAssertions,
+        Assertions, CustomSection, DeriveOutput, ElementwiseImpl,
+        ProcBlockImpl, Setter, SetterAssertion, SetterAssertions, Setters,
+        TransformAssertion, TransformAssertions,
     },
 };
 
@@ -22,12 +22,14 @@ impl ToTokens for DeriveOutput {
             custom_section,
             setters,
             assertions,
+            elementwise,
         } = self;
 
         trait_impl.to_tokens(tokens);
         custom_section.to_tokens(tokens);
         setters.to_tokens(tokens);
         assertions.to_tokens(tokens);
+        elementwise.to_tokens(tokens);
     }
 }
 
@@ -49,15 +51,32 @@ impl ToTokens for Setters {
     fn to_tokens(&self, tokens: &mut TokenStream) {
         let Setters {
             type_name,
+            exports,
             setters,
             generics,
         } = self;
 
         let (impl_generics, type_generics) = generic_parameters(generics);
 
+        let names = setters.iter().map(|s| s.property.to_string());
+        let methods = setters.iter().map(|s| setter_name(&s.property));
+
         let t = quote! {
             impl #impl_generics #type_name #type_generics  {
                 #( #setters )*
+
+                /// Set a parameter using its name.
+                #[allow(unused_variables)]
+                pub fn set_parameter(
+                    &mut self,
+                    name: &str,
+                    value: &str,
+                ) -> Result<(), #exports::SetParameterError> {
+                    match name {
+                        #( #names => self.#methods(value), )*
+                        _ => Err(#exports::SetParameterError::unknown_parameter(name)),
+                    }
+                }
             }
         };
         tokens.extend(t);
@@ -91,8 +110,7 @@ impl ToTokens for SetterAssertion {
 
         let assertion_name = format!("_assert_{}_is_settable", property);
         let assertion_name = Ident::new(&assertion_name, property.span());
-        let setter_name = format!("set_{}", property);
-        let setter_name = Ident::new(&setter_name, property.span());
+        let setter_name = setter_name(property);
 
         let t = quote! {
             const _: () = {
@@ -139,22 +157,42 @@ impl ToTokens for CustomSection {
     }
 }
 
+fn setter_name(property: &Ident) -> Ident {
+    let name = format!("set_{}", property);
+    Ident::new(&name, property.span())
+}
+
 impl ToTokens for Setter {
     fn to_tokens(&self, tokens: &mut TokenStream) {
         let Setter {
             property,
             property_type,
+            exports,
+            validator,
         } = self;
 
-        let method = format!("set_{}", property);
-        let method = Ident::new(&method, property.span());
+        let method = setter_name(property);
+        let name = property.to_string();
+
+        let validation = validator.as_ref().map(|validator| {
+            quote! {
+                #validator(&parsed).map_err(|e| {
+                    #exports::SetParameterError::invalid_value(#name, value, e)
+                })?;
+            }
+        });
 
         let t = quote! {
             pub fn #property(&self) -> &#property_type { &self.#property }
 
-            pub fn #method(&mut self, #property: &str) -> Result<(), impl core::fmt::Debug>
+            pub fn #method(&mut self, value: &str) -> Result<(), #exports::SetParameterError>
             {
-                #property.parse().map(|value| { self.#property = value; })
+                let parsed: #property_type = value.parse().map_err(|e| {
+                    #exports::SetParameterError::invalid_value(#name, value, e)
+                })?;
+                #validation
+                self.#property = parsed;
+                Ok(())
             }
         };
         tokens.extend(t);
@@ -213,15 +251,65 @@ impl ToTokens for ProcBlockImpl {
             exports,
             descriptor,
             generics,
+            init,
+            reset,
         } = self;
 
         let descriptor = descriptor_to_tokens(exports, descriptor);
 
         let (impl_generics, type_generics) = generic_parameters(generics);
 
+        let init = init.as_ref().map(|init| {
+            quote! {
+                fn init(&mut self) { #init(self) }
+            }
+        });
+        let reset = reset.as_ref().map(|reset| {
+            quote! {
+                fn reset(&mut self) { #reset(self) }
+            }
+        });
+
         let t = quote! {
             impl #impl_generics #exports::ProcBlock for #type_name #type_generics  {
                 const DESCRIPTOR: #exports::ProcBlockDescriptor<'static> = #descriptor;
+                #init
+                #reset
+            }
+        };
+        tokens.extend(t);
+    }
+}
+
+impl ToTokens for ElementwiseImpl {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let ElementwiseImpl {
+            type_name,
+            exports,
+            generics,
+            function,
+            input,
+            output,
+        } = self;
+
+        let (impl_generics, type_generics) = generic_parameters(generics);
+
+        let t = quote! {
+            impl #impl_generics #exports::TransformElement for #type_name #type_generics {
+                type Input = #input;
+                type Output = #output;
+
+                fn transform_element(&mut self, element: #input) -> #output {
+                    #function(self, element)
+                }
+            }
+
+            impl #impl_generics #exports::Transform<#exports::Tensor<#input>> for #type_name #type_generics {
+                type Output = #exports::Tensor<#output>;
+
+                fn transform(&mut self, input: #exports::Tensor<#input>) -> #exports::Tensor<#output> {
+                    input.map(|_, element| #function(self, element.clone()))
+                }
             }
         };
         tokens.extend(t);
@@ -236,11 +324,16 @@ fn descriptor_to_tokens<'a, 'b: 'a>(
         type_name,
         description,
         available_transforms,
+        parameters,
+        abi_version,
     } = d;
 
     let available_transforms = available_transforms
         .iter()
         .map(|transform| transform_to_tokens(exports, transform));
+    let parameters = parameters
+        .iter()
+        .map(|parameter| parameter_to_tokens(exports, parameter));
 
     quote! {
         #exports::ProcBlockDescriptor {
@@ -249,6 +342,36 @@ fn descriptor_to_tokens<'a, 'b: 'a>(
             available_transforms: #exports::Cow::Borrowed(&[
                 #( #available_transforms ),*
             ]),
+            parameters: #exports::Cow::Borrowed(&[
+                #( #parameters ),*
+            ]),
+            abi_version: #abi_version,
+        }
+    }
+}
+
+fn parameter_to_tokens(
+    exports: &Path,
+    parameter: &ParameterDescriptor<'_>,
+) -> TokenStream {
+    let ParameterDescriptor {
+        name,
+        description,
+        type_name,
+        default_value,
+    } = parameter;
+
+    let default_value = match default_value {
+        Some(value) => quote!(Some(#exports::Cow::Borrowed(#value))),
+        None => quote!(None),
+    };
+
+    quote! {
+        #exports::ParameterDescriptor {
+            name: #exports::Cow::Borrowed(#name),
+            description: #exports::Cow::Borrowed(#description),
+            type_name: #exports::Cow::Borrowed(#type_name),
+            default_value: #default_value,
         }
     }
 }
@@ -276,14 +399,20 @@ fn tensor_descriptors_to_tokens(
 ) -> TokenStream {
     let descriptors = tensors.iter().map(
         |TensorDescriptor {
+             name,
              element_type,
              dimensions,
          }| {
+            let name = match name {
+                Some(name) => quote!(Some(#exports::Cow::Borrowed(#name))),
+                None => quote!(None),
+            };
             let element_type = element_type_to_tokens(exports, *element_type);
             let dimensions = dimensions_to_tokens(exports, dimensions);
 
             quote! {
                 #exports::TensorDescriptor {
+                    name: #name,
                     element_type: #element_type,
                     dimensions: #dimensions,
                 }
@@ -311,6 +440,11 @@ fn element_type_to_tokens(exports: &Path, ty: ElementType) -> TokenStream {
         ElementType::F64 => "F64",
         ElementType::I64 => "I64",
         ElementType::String => "String",
+        ElementType::F16 => "F16",
+        ElementType::BF16 => "BF16",
+        ElementType::Bool => "Bool",
+        ElementType::C64 => "C64",
+        ElementType::C128 => "C128",
     };
     let ident = Ident::new(name, Span::call_site());
     quote!(#exports::ElementType::#ident)
@@ -461,14 +595,50 @@ mod tests {
         let setter = Setter {
             property: syn::parse_str("first").unwrap(),
             property_type: syn::parse_str("f32").unwrap(),
+            exports: syn::parse_str("exports").unwrap(),
+            validator: None,
+        };
+        let should_be = quote! {
+            pub fn first(&self) -> &f32 { &self.first }
+            pub fn set_first(
+                &mut self,
+                value: &str,
+            ) -> Result<(), exports::SetParameterError> {
+                let parsed: f32 = value.parse().map_err(|e| {
+                    exports::SetParameterError::invalid_value("first", value, e)
+                })?;
+                self.first = parsed;
+                Ok(())
+            }
+        };
+
+        let got = setter.to_token_stream();
+
+        assert_eq_tok!(got, should_be);
+    }
+
+    #[test]
+    fn setter_with_validation() {
+        let setter = Setter {
+            property: syn::parse_str("first").unwrap(),
+            property_type: syn::parse_str("f32").unwrap(),
+            exports: syn::parse_str("exports").unwrap(),
+            validator: Some(syn::parse_str("check").unwrap()),
         };
         let should_be = quote! {
             pub fn first(&self) -> &f32 { &self.first }
             pub fn set_first(
                 &mut self,
-                first: &str,
-            ) -> Result<(), impl core::fmt::Debug> {
-                first.parse().map(|value| { self.first = value; })
+                value: &str,
+            ) -> Result<(), exports::SetParameterError> {
+                let parsed: f32 = value.parse().map_err(|e| {
+                    exports::SetParameterError::invalid_value("first", value, e)
+                })?;
+                check(&parsed).map_err(|e| {
+                    exports::SetParameterError::invalid_value("first", value, e)
+                })?;
+                self.first = parsed;
+                Ok(())
             }
         };
 
@@ -548,8 +718,12 @@ mod tests {
                 type_name: "Proc".into(),
                 description: "Hello, World!".into(),
                 available_transforms: Cow::default(),
+                parameters: Cow::default(),
+                abi_version: 1,
             },
             generics: Generics::default(),
+            init: None,
+            reset: None,
         };
         let should_be = quote! {
             impl exports::ProcBlock for Proc {
@@ -557,7 +731,78 @@ mod tests {
                     type_name: exports::Cow::Borrowed("Proc"),
                     description: exports::Cow::Borrowed("Hello, World!"),
                     available_transforms: exports::Cow::Borrowed(&[]),
+                    parameters: exports::Cow::Borrowed(&[]),
+                    abi_version: 1u32,
+                };
+            }
+        };
+
+        let got = input.to_token_stream();
+
+        assert_eq_tok!(got, should_be);
+    }
+
+    #[test]
+    fn implement_proc_block_trait_with_lifecycle_hooks() {
+        let input = ProcBlockImpl {
+            type_name: syn::parse_str("Proc").unwrap(),
+            exports: syn::parse_str("exports").unwrap(),
+            descriptor: ProcBlockDescriptor {
+                type_name: "Proc".into(),
+                description: "".into(),
+                available_transforms: Cow::default(),
+                parameters: Cow::default(),
+                abi_version: 1,
+            },
+            generics: Generics::default(),
+            init: Some(syn::parse_str("Self::allocate_window").unwrap()),
+            reset: Some(syn::parse_str("clear").unwrap()),
+        };
+        let should_be = quote! {
+            impl exports::ProcBlock for Proc {
+                const DESCRIPTOR: exports::ProcBlockDescriptor<'static> = exports::ProcBlockDescriptor {
+                    type_name: exports::Cow::Borrowed("Proc"),
+                    description: exports::Cow::Borrowed(""),
+                    available_transforms: exports::Cow::Borrowed(&[]),
+                    parameters: exports::Cow::Borrowed(&[]),
+                    abi_version: 1u32,
                 };
+                fn init(&mut self) { Self::allocate_window(self) }
+                fn reset(&mut self) { clear(self) }
+            }
+        };
+
+        let got = input.to_token_stream();
+
+        assert_eq_tok!(got, should_be);
+    }
+
+    #[test]
+    fn elementwise_impl() {
+        let input = ElementwiseImpl {
+            type_name: syn::parse_str("Proc").unwrap(),
+            exports: syn::parse_str("exports").unwrap(),
+            generics: Generics::default(),
+            function: syn::parse_str("Self::clip").unwrap(),
+            input: syn::parse_str("f32").unwrap(),
+            output: syn::parse_str("u8").unwrap(),
+        };
+        let should_be = quote! {
+            impl exports::TransformElement for Proc {
+                type Input = f32;
+                type Output = u8;
+
+                fn transform_element(&mut self, element: f32) -> u8 {
+                    Self::clip(self, element)
+                }
+            }
+
+            impl exports::Transform<exports::Tensor<f32>> for Proc {
+                type Output = exports::Tensor<u8>;
+
+                fn transform(&mut self, input: exports::Tensor<f32>) -> exports::Tensor<u8> {
+                    input.map(|_, element| Self::clip(self, element.clone()))
+                }
             }
         };
 
@@ -571,11 +816,13 @@ mod tests {
         let exports = syn::parse_str("exports").unwrap();
         let transform = TransformDescriptor {
             inputs: TensorDescriptor {
+                name: None,
                 element_type: ElementType::F32,
                 dimensions: Dimensions::Arbitrary,
             }
             .into(),
             outputs: TensorDescriptor {
+                name: None,
                 element_type: ElementType::U8,
                 dimensions: Dimensions::Finite(
                     vec![Dimension::Value(1980)].into(),
@@ -587,12 +834,14 @@ mod tests {
             exports::TransformDescriptor {
                 inputs: exports::TensorDescriptors(exports::Cow::Borrowed(&[
                     exports::TensorDescriptor {
+                        name: None,
                         element_type: exports::ElementType::F32,
                         dimensions: exports::Dimensions::Arbitrary,
                     },
                 ])),
                 outputs: exports::TensorDescriptors(exports::Cow::Borrowed(&[
                     exports::TensorDescriptor {
+                        name: None,
                         element_type: exports::ElementType::U8,
                         dimensions: exports::Dimensions::Finite(
                             exports::Cow::Borrowed(&[
@@ -9,6 +9,8 @@ pub(crate) struct DeriveOutput {
     pub custom_section: CustomSection,
     pub setters: Setters,
     pub assertions: Assertions,
+    /// Set when the proc block used `#[proc_block(elementwise = ...)]`.
+    pub elementwise: Option<ElementwiseImpl>,
 }
 
 #[derive(Debug)]
@@ -17,6 +19,24 @@ pub(crate) struct ProcBlockImpl {
     pub exports: Path,
     pub generics: Generics,
     pub descriptor: ProcBlockDescriptor<'static>,
+    /// A function from `#[proc_block(init = ...)]`.
+    pub init: Option<Path>,
+    /// A function from `#[proc_block(reset = ...)]`.
+    pub reset: Option<Path>,
+}
+
+/// Implementations of `TransformElement` and `Transform` which call the
+/// function from `#[proc_block(elementwise = ...)]`.
+#[derive(Debug, PartialEq)]
+pub(crate) struct ElementwiseImpl {
+    pub type_name: Ident,
+    pub exports: Path,
+    pub generics: Generics,
+    pub function: Path,
+    /// The input element type.
+    pub input: Type,
+    /// The output element type.
+    pub output: Type,
 }
 
 #[derive(Debug)]
@@ -28,6 +48,7 @@ pub(crate) struct CustomSection {
 #[derive(Debug, PartialEq)]
 pub(crate) struct Setters {
     pub type_name: Ident,
+    pub exports: Path,
     pub generics: Generics,
     pub setters: Vec<Setter>,
 }
@@ -36,6 +57,10 @@ pub(crate) struct Setters {
 pub(crate) struct Setter {
     pub property: Ident,
     pub property_type: syn::Type,
+    pub exports: Path,
+    /// A function from `#[proc_block(validate = ...)]` which checks the
+    /// parsed value.
+    pub validator: Option<Path>,
 }
 
 #[derive(Debug, PartialEq)]
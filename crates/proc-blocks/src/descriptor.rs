@@ -17,10 +17,51 @@ pub struct ProcBlockDescriptor<'a> {
     /// paragraphs.
     pub description: Cow<'a, str>,
     pub available_transforms: Cow<'a, [TransformDescriptor<'a>]>,
+    /// The parameters that can be set when the proc block is used in a
+    /// Runefile.
+    #[serde(default)]
+    pub parameters: Cow<'a, [ParameterDescriptor<'a>]>,
+    /// The [`ProcBlockDescriptor::ABI_VERSION`] this proc block was compiled
+    /// against.
+    ///
+    /// Proc blocks compiled before the version was recorded use `0`.
+    #[serde(default)]
+    pub abi_version: u32,
 }
 
 impl<'a> ProcBlockDescriptor<'a> {
+    /// The version of the interface between a proc block and the Rune it is
+    /// compiled into (i.e. the `ProcBlock` and `Transform` traits and this
+    /// descriptor).
+    ///
+    /// This should be incremented whenever that interface changes in an
+    /// incompatible way.
+    pub const ABI_VERSION: u32 = 1;
     pub const CUSTOM_SECTION_NAME: &'static str = ".rune_proc_block";
+
+    /// Can a proc block with this descriptor be used by something compiled
+    /// against the current [`ProcBlockDescriptor::ABI_VERSION`]?
+    ///
+    /// Proc blocks which don't record their ABI version are assumed to be
+    /// compatible.
+    pub fn is_abi_compatible(&self) -> bool {
+        self.abi_version == 0 || self.abi_version == Self::ABI_VERSION
+    }
+}
+
+/// A parameter which is set using one of the proc block's generated
+/// `set_*()` methods.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ParameterDescriptor<'a> {
+    pub name: Cow<'a, str>,
+    /// The field's doc comments.
+    pub description: Cow<'a, str>,
+    /// The type the parameter's value gets parsed into (e.g. `u32`).
+    pub type_name: Cow<'a, str>,
+    /// The value used when the Runefile doesn't set this parameter, as
+    /// documented by `#[proc_block(default = "...")]`.
+    #[serde(default)]
+    pub default_value: Option<Cow<'a, str>>,
 }
 
 #[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
@@ -56,6 +97,10 @@ impl<'a> Deref for TensorDescriptors<'a> {
 
 #[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct TensorDescriptor<'a> {
+    /// An optional name for the tensor, so proc blocks with several inputs
+    /// or outputs can say which is which (e.g. `image` and `boxes`).
+    #[serde(default)]
+    pub name: Option<Cow<'a, str>>,
     pub element_type: hotg_rune_core::ElementType,
     pub dimensions: Dimensions<'a>,
 }
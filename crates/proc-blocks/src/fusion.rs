@@ -0,0 +1,347 @@
+//! Run a chain of proc blocks where each proc block's only input is the
+//! previous proc block's output (e.g. `normalize → clip → cast`).
+//!
+//! If every proc block in the chain implements [`TransformElement`], each
+//! element is passed through the whole chain in a single loop instead of
+//! creating a tensor for every intermediate result. Otherwise, the proc
+//! blocks are run one after another using [`Transform`].
+//!
+//! The choice is made at compile time using [autoref-based
+//! specialization][autoref], which means the code generated for a Rune
+//! doesn't need to know which proc blocks are elementwise. Just make sure
+//! both [`RunFused`] and [`RunSequentially`] are in scope.
+//!
+//! ```rust
+//! use hotg_rune_core::Tensor;
+//! use hotg_rune_proc_blocks::{
+//!     fusion::{Chain, RunFused as _, RunSequentially as _, Stage},
+//!     ProcBlock, Transform,
+//! };
+//!
+//! #[derive(Default, hotg_rune_proc_block_macros::ProcBlock)]
+//! #[proc_block(elementwise = Self::double)]
+//! #[transform(inputs = [f32; _], outputs = [f32; _])]
+//! struct Double {}
+//!
+//! impl Double {
+//!     fn double(&mut self, element: f32) -> f32 { element * 2.0 }
+//! }
+//!
+//! #[derive(Default, hotg_rune_proc_block_macros::ProcBlock)]
+//! #[proc_block(elementwise = Self::round)]
+//! #[transform(inputs = [f32; _], outputs = [i32; _])]
+//! struct Round {}
+//!
+//! impl Round {
+//!     fn round(&mut self, element: f32) -> i32 { element.round() as i32 }
+//! }
+//!
+//! let mut double = Double::default();
+//! let mut round = Round::default();
+//!
+//! let output = Chain::new(
+//!     (
+//!         Stage::new("double", &mut double),
+//!         (Stage::new("round", &mut round), ()),
+//!     ),
+//!     |_stage| {},
+//! )
+//! .run(Tensor::new_vector(vec![0.2, 1.4]));
+//!
+//! assert_eq!(output, Tensor::new_vector(vec![0, 3]));
+//! ```
+//!
+//! [autoref]: https://github.com/dtolnay/case-studies/blob/master/autoref-specialization/README.md
+
+use hotg_rune_core::Tensor;
+
+use crate::{Transform, TransformElement};
+
+/// A proc block in a [`Chain`], along with the name of its pipeline stage.
+pub struct Stage<'a, P> {
+    name: &'static str,
+    proc_block: &'a mut P,
+}
+
+impl<'a, P> Stage<'a, P> {
+    pub fn new(name: &'static str, proc_block: &'a mut P) -> Self {
+        Stage { name, proc_block }
+    }
+}
+
+/// A sequence of [`Stage`]s, stored as a list like `(first, (second, ()))`.
+pub struct Chain<L> {
+    stages: L,
+    set_stage: fn(&'static str),
+}
+
+impl<L> Chain<L> {
+    /// Create a new [`Chain`], where `set_stage` is called with a stage's
+    /// name before it is run so any errors can be attributed to it.
+    pub fn new(stages: L, set_stage: fn(&'static str)) -> Self {
+        Chain { stages, set_stage }
+    }
+}
+
+/// Run the [`Chain`] in a single loop, passing each element through every
+/// stage.
+///
+/// This is only implemented when every stage implements
+/// [`TransformElement`].
+pub trait RunFused {
+    type Input;
+    type Output;
+
+    fn run(self, input: Tensor<Self::Input>) -> Tensor<Self::Output>;
+}
+
+impl<'a, P, Rest> RunFused for Chain<(Stage<'a, P>, Rest)>
+where
+    P: TransformElement,
+    Rest: Elements<P::Output>,
+{
+    type Input = P::Input;
+    type Output = Rest::Output;
+
+    fn run(self, input: Tensor<P::Input>) -> Tensor<Rest::Output> {
+        let Chain {
+            mut stages,
+            set_stage,
+        } = self;
+
+        input.map(|_, element| {
+            stages.transform_element(element.clone(), set_stage)
+        })
+    }
+}
+
+/// Run each stage in the [`Chain`] one after another, the fallback used when
+/// [`RunFused`] isn't implemented.
+pub trait RunSequentially<Input> {
+    type Output;
+
+    fn run(self, input: Input) -> Self::Output;
+}
+
+impl<L, Input> RunSequentially<Input> for &mut Chain<L>
+where
+    L: Tensors<Input>,
+{
+    type Output = L::Output;
+
+    fn run(self, input: Input) -> L::Output {
+        self.stages.transform(input, self.set_stage)
+    }
+}
+
+/// Pass a single element through a list of elementwise stages.
+#[doc(hidden)]
+pub trait Elements<Input> {
+    type Output;
+
+    fn transform_element(
+        &mut self,
+        element: Input,
+        set_stage: fn(&'static str),
+    ) -> Self::Output;
+}
+
+impl<Input> Elements<Input> for () {
+    type Output = Input;
+
+    fn transform_element(
+        &mut self,
+        element: Input,
+        _set_stage: fn(&'static str),
+    ) -> Input {
+        element
+    }
+}
+
+impl<'a, P, Rest> Elements<P::Input> for (Stage<'a, P>, Rest)
+where
+    P: TransformElement,
+    Rest: Elements<P::Output>,
+{
+    type Output = Rest::Output;
+
+    fn transform_element(
+        &mut self,
+        element: P::Input,
+        set_stage: fn(&'static str),
+    ) -> Rest::Output {
+        let (stage, rest) = self;
+
+        set_stage(stage.name);
+        let element = stage.proc_block.transform_element(element);
+
+        rest.transform_element(element, set_stage)
+    }
+}
+
+/// Pass a tensor through a list of stages.
+#[doc(hidden)]
+pub trait Tensors<Input> {
+    type Output;
+
+    fn transform(
+        &mut self,
+        input: Input,
+        set_stage: fn(&'static str),
+    ) -> Self::Output;
+}
+
+impl<Input> Tensors<Input> for () {
+    type Output = Input;
+
+    fn transform(
+        &mut self,
+        input: Input,
+        _set_stage: fn(&'static str),
+    ) -> Input {
+        input
+    }
+}
+
+impl<'a, P, Rest, Input> Tensors<Input> for (Stage<'a, P>, Rest)
+where
+    P: Transform<Input>,
+    Rest: Tensors<P::Output>,
+{
+    type Output = Rest::Output;
+
+    fn transform(
+        &mut self,
+        input: Input,
+        set_stage: fn(&'static str),
+    ) -> Rest::Output {
+        let (stage, rest) = self;
+
+        set_stage(stage.name);
+        let output = stage.proc_block.transform(input);
+
+        rest.transform(output, set_stage)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use alloc::{borrow::Cow, vec, vec::Vec};
+    use core::cell::RefCell;
+
+    use super::*;
+    use crate::{ProcBlock, ProcBlockDescriptor};
+
+    /// Implement [`ProcBlock`] by hand because the derive can't be used from
+    /// inside this crate.
+    macro_rules! proc_block {
+        ($name:ident) => {
+            impl ProcBlock for $name {
+                const DESCRIPTOR: ProcBlockDescriptor<'static> =
+                    ProcBlockDescriptor {
+                        type_name: Cow::Borrowed(stringify!($name)),
+                        description: Cow::Borrowed(""),
+                        available_transforms: Cow::Borrowed(&[]),
+                        parameters: Cow::Borrowed(&[]),
+                        abi_version: ProcBlockDescriptor::ABI_VERSION,
+                    };
+            }
+        };
+    }
+
+    #[derive(Default)]
+    struct AddOne {
+        calls: usize,
+    }
+
+    proc_block!(AddOne);
+
+    impl TransformElement for AddOne {
+        type Input = i32;
+        type Output = i32;
+
+        fn transform_element(&mut self, element: i32) -> i32 {
+            self.calls += 1;
+            element + 1
+        }
+    }
+
+    impl Transform<Tensor<i32>> for AddOne {
+        type Output = Tensor<i32>;
+
+        fn transform(&mut self, input: Tensor<i32>) -> Tensor<i32> {
+            self.calls += 1;
+            input.map(|_, &element| element + 1)
+        }
+    }
+
+    /// A proc block which needs the whole tensor.
+    #[derive(Default)]
+    struct Sum;
+
+    proc_block!(Sum);
+
+    impl Transform<Tensor<i32>> for Sum {
+        type Output = Tensor<i32>;
+
+        fn transform(&mut self, input: Tensor<i32>) -> Tensor<i32> {
+            Tensor::new_vector(vec![input.elements().iter().sum()])
+        }
+    }
+
+    std::thread_local! {
+        static STAGES: RefCell<Vec<&'static str>> =
+            const { RefCell::new(Vec::new()) };
+    }
+
+    fn record_stage(name: &'static str) {
+        STAGES.with(|s| s.borrow_mut().push(name));
+    }
+
+    fn recorded_stages() -> Vec<&'static str> {
+        STAGES.with(|s| s.borrow_mut().drain(..).collect())
+    }
+
+    #[test]
+    fn elementwise_stages_are_run_in_a_single_loop() {
+        let mut first = AddOne::default();
+        let mut second = AddOne::default();
+
+        let output = Chain::new(
+            (
+                Stage::new("first", &mut first),
+                (Stage::new("second", &mut second), ()),
+            ),
+            record_stage,
+        )
+        .run(Tensor::new_vector(vec![1, 2]));
+
+        assert_eq!(output, Tensor::new_vector(vec![3, 4]));
+        // transform_element() was called once per element
+        assert_eq!(first.calls, 2);
+        assert_eq!(second.calls, 2);
+        assert_eq!(recorded_stages(), ["first", "second", "first", "second"]);
+    }
+
+    #[test]
+    fn chains_containing_other_proc_blocks_are_run_sequentially() {
+        let mut add_one = AddOne::default();
+        let mut sum = Sum;
+
+        let output = Chain::new(
+            (
+                Stage::new("add_one", &mut add_one),
+                (Stage::new("sum", &mut sum), ()),
+            ),
+            record_stage,
+        )
+        .run(Tensor::new_vector(vec![1, 2, 3]));
+
+        assert_eq!(output, Tensor::new_vector(vec![9]));
+        // transform() was called once for the whole tensor
+        assert_eq!(add_one.calls, 1);
+        assert_eq!(recorded_stages(), ["add_one", "sum"]);
+    }
+}
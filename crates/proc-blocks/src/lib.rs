@@ -6,6 +6,13 @@
 //!
 //! - `derive` - re-export the `#[derive(ProcBlock)]` from the
 //!   `hotg-rune-proc-block-macros` crate
+//!
+//! # Standalone Proc Blocks
+//!
+//! Normally a proc block is compiled directly into the Rune that uses it, but
+//! the [`export_proc_block!()`] macro lets you compile it to a standalone
+//! WebAssembly module which is loaded by the host at runtime. See the
+//! [`standalone`] module for more.
 
 #![no_std]
 #![cfg_attr(feature = "unstable_doc_cfg", feature(doc_cfg))]
@@ -13,9 +20,14 @@
 extern crate alloc;
 
 mod descriptor;
+pub mod fusion;
+pub mod math;
+mod parameters;
+pub mod standalone;
 
 pub use descriptor::*;
 pub use hotg_rune_core::Tensor;
+pub use parameters::SetParameterError;
 #[cfg(feature = "derive")]
 pub use hotg_rune_proc_block_macros::ProcBlock;
 
@@ -23,12 +35,31 @@ pub use hotg_rune_proc_block_macros::ProcBlock;
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 /// Process some data, transforming it from one form to another.
+///
+/// Proc blocks with several inputs or outputs use a tuple of tensors for
+/// `Input` and `Output`.
 pub trait Transform<Input>: ProcBlock {
     type Output;
 
     fn transform(&mut self, input: Input) -> Self::Output;
 }
 
+/// A proc block which transforms each element of a tensor independently
+/// (e.g. scaling, clipping, or casting).
+///
+/// When several of these are chained together in a Rune, each element is
+/// passed through all of them in a single loop instead of creating a tensor
+/// for every intermediate result (see the [`fusion`] module).
+///
+/// Prefer the `#[proc_block(elementwise = ...)]` attribute over implementing
+/// this manually, because it will also implement [`Transform`] for you.
+pub trait TransformElement: ProcBlock {
+    type Input: Clone;
+    type Output;
+
+    fn transform_element(&mut self, element: Self::Input) -> Self::Output;
+}
+
 /// The base trait that all proc blocks must implement.
 ///
 /// This trait shouldn't be implemented manually, instead you should prefer the
@@ -107,11 +138,49 @@ pub trait Transform<Input>: ProcBlock {
 /// }
 /// ```
 ///
+/// A proc block can also accept several input tensors or produce several
+/// output tensors by wrapping them in parentheses, in which case the
+/// `Transform` implementation uses a tuple. Each tensor can be given a name
+/// so users know which is which, and these names are recorded in the proc
+/// block's [`TransformDescriptor`]. Tensors are still passed in by position,
+/// but `rune build` will use the names to catch a Runefile which lists them
+/// in the wrong order.
+///
+/// ```rust
+/// use hotg_rune_core::Tensor;
+/// use hotg_rune_proc_blocks::{ProcBlock, Transform};
+///
+/// #[derive(Default, hotg_rune_proc_block_macros::ProcBlock)]
+/// #[transform(
+///     inputs = (image: [u8; 4], boxes: [f32; 2]),
+///     outputs = (cropped: [u8; 4], scores: [f32; 1])
+/// )]
+/// struct Crop {}
+///
+/// impl Transform<(Tensor<u8>, Tensor<f32>)> for Crop {
+///     type Output = (Tensor<u8>, Tensor<f32>);
+///
+///     fn transform(
+///         &mut self,
+///         (_image, _boxes): (Tensor<u8>, Tensor<f32>),
+///     ) -> Self::Output {
+///         unimplemented!()
+///     }
+/// }
+///
+/// let transform = &Crop::DESCRIPTOR.available_transforms[0];
+/// assert_eq!(transform.inputs[1].name.as_deref(), Some("boxes"));
+/// assert_eq!(transform.outputs.len(), 2);
+/// ```
+///
 /// ## Field Attributes
 ///
 /// By default, all fields in a proc block struct will be registered as
 /// "properties" and will get some generated setters. These setters accept a
-/// string and use [`core::str::FromStr`] to parse it into the correct type.
+/// string and use [`core::str::FromStr`] to parse it into the correct type,
+/// returning a [`SetParameterError`] if that fails. Each property's name,
+/// type, and doc comments are also recorded in the proc block's
+/// [`ProcBlockDescriptor::parameters`].
 ///
 /// ```rust
 /// use hotg_rune_proc_blocks::ProcBlock;
@@ -145,9 +214,156 @@ pub trait Transform<Input>: ProcBlock {
 ///
 /// foo.set_skip_me("..."); // Error: no method named `set_skip_me` found for struct `Foo` in the current scope
 /// ```
+///
+/// Use `#[proc_block(validate = some_function)]` to check a value after it
+/// has been parsed. The function is given a reference to the new value and
+/// returns a `Result<(), E>`, where `E` implements [`core::fmt::Display`].
+///
+/// Use `#[proc_block(default = "...")]` to record the parameter's default
+/// value in the descriptor so tools like `rune inspect` can show it. This is
+/// purely informational, so it should match the value set by your `Default`
+/// implementation.
+///
+/// ```rust
+/// use hotg_rune_proc_blocks::ProcBlock;
+///
+/// #[derive(hotg_rune_proc_block_macros::ProcBlock)]
+/// struct Foo {
+///     #[proc_block(default = "0.5")]
+///     threshold: f32,
+/// }
+///
+/// impl Default for Foo {
+///     fn default() -> Self { Foo { threshold: 0.5 } }
+/// }
+///
+/// let parameter = &Foo::DESCRIPTOR.parameters[0];
+/// assert_eq!(parameter.default_value.as_deref(), Some("0.5"));
+/// ```
+///
+/// A `set_parameter()` method is also generated, which calls the correct
+/// setter for a parameter's name.
+///
+/// ```rust
+/// use hotg_rune_proc_blocks::{ProcBlock, SetParameterError};
+///
+/// #[derive(Default, hotg_rune_proc_block_macros::ProcBlock)]
+/// struct Foo {
+///     /// A value between 0 and 1.
+///     #[proc_block(validate = is_probability)]
+///     threshold: f32,
+/// }
+///
+/// fn is_probability(value: &f32) -> Result<(), &'static str> {
+///     if (0.0..=1.0).contains(value) {
+///         Ok(())
+///     } else {
+///         Err("must be between 0 and 1")
+///     }
+/// }
+///
+/// let mut foo = Foo::default();
+///
+/// foo.set_parameter("threshold", "0.75").unwrap();
+/// assert_eq!(foo.threshold, 0.75);
+///
+/// assert_eq!(
+///     foo.set_threshold("1.5").unwrap_err().to_string(),
+///     "Unable to set \"threshold\" to \"1.5\": must be between 0 and 1",
+/// );
+/// assert_eq!(
+///     foo.set_parameter("missing", "42"),
+///     Err(SetParameterError::unknown_parameter("missing")),
+/// );
+///
+/// let parameter = &Foo::DESCRIPTOR.parameters[0];
+/// assert_eq!(parameter.name, "threshold");
+/// assert_eq!(parameter.type_name, "f32");
+/// assert_eq!(parameter.description, "A value between 0 and 1.");
+/// ```
+///
+/// ## Elementwise Proc Blocks
+///
+/// A proc block which transforms each element independently can use
+/// `#[proc_block(elementwise = ...)]` to name a function that accepts
+/// `&mut Self` and a single element. This implements [`TransformElement`]
+/// and a [`Transform`] which calls the function for every element, letting a
+/// Rune fuse it with neighbouring elementwise proc blocks.
+///
+/// The proc block must have exactly one `#[transform(...)]` with a single
+/// input and output, which is used to find the element types.
+///
+/// ```rust
+/// use hotg_rune_core::Tensor;
+/// use hotg_rune_proc_blocks::{ProcBlock, Transform, TransformElement};
+///
+/// #[derive(Default, hotg_rune_proc_block_macros::ProcBlock)]
+/// #[proc_block(elementwise = Self::clip)]
+/// #[transform(inputs = [f32; _], outputs = [f32; _])]
+/// struct Clip {
+///     max: f32,
+/// }
+///
+/// impl Clip {
+///     fn clip(&mut self, element: f32) -> f32 { element.min(self.max) }
+/// }
+///
+/// let mut clip = Clip { max: 1.0 };
+///
+/// assert_eq!(clip.transform_element(1.5), 1.0);
+/// assert_eq!(
+///     clip.transform(Tensor::new_vector(vec![0.5, 2.0])),
+///     Tensor::new_vector(vec![0.5, 1.0]),
+/// );
+/// ```
+///
+/// ## Lifecycle Hooks
+///
+/// A proc block which keeps state between calls (e.g. a rolling window of
+/// samples) can use `#[proc_block(init = ..., reset = ...)]` on the struct to
+/// implement [`ProcBlock::init()`] and [`ProcBlock::reset()`]. Each hook is
+/// a function that accepts `&mut Self`.
+///
+/// ```rust
+/// use hotg_rune_proc_blocks::ProcBlock;
+///
+/// #[derive(Default, hotg_rune_proc_block_macros::ProcBlock)]
+/// #[proc_block(init = Self::allocate_window, reset = Self::clear)]
+/// struct RollingMean {
+///     window_size: usize,
+///     #[proc_block(skip)]
+///     window: Vec<f32>,
+/// }
+///
+/// impl RollingMean {
+///     fn allocate_window(&mut self) {
+///         self.window = Vec::with_capacity(self.window_size);
+///     }
+///
+///     fn clear(&mut self) { self.window.clear(); }
+/// }
+///
+/// let mut mean = RollingMean::default();
+/// mean.set_window_size("4").unwrap();
+/// mean.init();
+/// mean.window.push(1.0);
+///
+/// mean.reset();
+/// assert!(mean.window.is_empty());
+/// assert_eq!(mean.window_size, 4);
+/// ```
 pub trait ProcBlock: Default + 'static {
     /// A description of the proc block.
     const DESCRIPTOR: ProcBlockDescriptor<'static>;
+
+    /// Called once after all parameters have been set and before the first
+    /// call to [`Transform::transform()`].
+    fn init(&mut self) {}
+
+    /// Clear any state accumulated by previous transformations (e.g. when
+    /// moving on to the next item in a dataset) while keeping the proc
+    /// block's parameters.
+    fn reset(&mut self) {}
 }
 
 /// An internal module used by the `hotg_rune_proc_block_macros` crate
@@ -156,7 +372,10 @@ pub trait ProcBlock: Default + 'static {
 pub mod internal {
     pub use alloc::borrow::Cow;
 
-    pub use hotg_rune_core::{ElementType, Tensor};
+    pub use hotg_rune_core::{bf16, f16, Complex, ElementType, Tensor};
 
-    pub use crate::{descriptor::*, ProcBlock, Transform};
+    pub use crate::{
+        descriptor::*, standalone, ProcBlock, SetParameterError, Transform,
+        TransformElement,
+    };
 }
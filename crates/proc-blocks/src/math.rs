@@ -0,0 +1,347 @@
+//! Common maths operations on [`Tensor`]s.
+//!
+//! Reductions like [`mean()`] and [`argmax()`] treat the tensor as a flat
+//! list of elements in row-major order, which is what you want for the
+//! `[1, N]` tensors most models produce.
+//!
+//! ```rust
+//! use hotg_rune_proc_blocks::{math, Tensor};
+//!
+//! let logits = Tensor::new_vector(vec![1.0_f32, 3.0, 2.0]);
+//!
+//! let probabilities = math::softmax(&logits);
+//!
+//! assert_eq!(math::argmax(&probabilities), Some(1));
+//! ```
+
+use core::ops::{Add, Div, Mul, Sub};
+
+use hotg_rune_core::{ShapeError, Tensor};
+
+/// A floating point number that can be used with the functions in this
+/// module.
+pub trait Float:
+    Copy
+    + PartialOrd
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + sealed::Sealed
+{
+    const ZERO: Self;
+
+    fn exp(self) -> Self;
+    fn sqrt(self) -> Self;
+    fn from_usize(n: usize) -> Self;
+}
+
+impl Float for f32 {
+    const ZERO: Self = 0.0;
+
+    fn exp(self) -> Self { libm::expf(self) }
+
+    fn sqrt(self) -> Self { libm::sqrtf(self) }
+
+    fn from_usize(n: usize) -> Self { n as f32 }
+}
+
+impl Float for f64 {
+    const ZERO: Self = 0.0;
+
+    fn exp(self) -> Self { libm::exp(self) }
+
+    fn sqrt(self) -> Self { libm::sqrt(self) }
+
+    fn from_usize(n: usize) -> Self { n as f64 }
+}
+
+mod sealed {
+    pub trait Sealed {}
+
+    impl Sealed for f32 {}
+    impl Sealed for f64 {}
+}
+
+/// Add two tensors with the same dimensions, element by element.
+pub fn add<T>(
+    left: &Tensor<T>,
+    right: &Tensor<T>,
+) -> Result<Tensor<T>, ShapeError>
+where
+    T: Copy + Add<Output = T>,
+{
+    zip_with(left, right, |l, r| l + r)
+}
+
+/// Subtract one tensor from another with the same dimensions, element by
+/// element.
+pub fn sub<T>(
+    left: &Tensor<T>,
+    right: &Tensor<T>,
+) -> Result<Tensor<T>, ShapeError>
+where
+    T: Copy + Sub<Output = T>,
+{
+    zip_with(left, right, |l, r| l - r)
+}
+
+/// Multiply two tensors with the same dimensions, element by element.
+pub fn mul<T>(
+    left: &Tensor<T>,
+    right: &Tensor<T>,
+) -> Result<Tensor<T>, ShapeError>
+where
+    T: Copy + Mul<Output = T>,
+{
+    zip_with(left, right, |l, r| l * r)
+}
+
+/// Divide one tensor by another with the same dimensions, element by
+/// element.
+pub fn div<T>(
+    left: &Tensor<T>,
+    right: &Tensor<T>,
+) -> Result<Tensor<T>, ShapeError>
+where
+    T: Copy + Div<Output = T>,
+{
+    zip_with(left, right, |l, r| l / r)
+}
+
+/// Combine the corresponding elements from two tensors with the same
+/// dimensions.
+pub fn zip_with<L, R, Out, F>(
+    left: &Tensor<L>,
+    right: &Tensor<R>,
+    mut combine: F,
+) -> Result<Tensor<Out>, ShapeError>
+where
+    L: Copy,
+    R: Copy,
+    F: FnMut(L, R) -> Out,
+{
+    check_dimensions(left.dimensions(), right.dimensions())?;
+
+    let elements = left
+        .elements()
+        .iter()
+        .zip(right.elements())
+        .map(|(&l, &r)| combine(l, r))
+        .collect();
+
+    Ok(Tensor::new_row_major(elements, left.dimensions().to_vec()))
+}
+
+fn check_dimensions(
+    left: &[usize],
+    right: &[usize],
+) -> Result<(), ShapeError> {
+    if left.len() != right.len() {
+        return Err(ShapeError::RankMismatch {
+            left: left.len(),
+            right: right.len(),
+        });
+    }
+
+    match left.iter().zip(right).position(|(l, r)| l != r) {
+        Some(axis) => Err(ShapeError::DimensionMismatch {
+            axis,
+            left: left[axis].into(),
+            right: right[axis].into(),
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Limit every element to the range `min..=max`.
+pub fn clip<T>(tensor: &Tensor<T>, min: T, max: T) -> Tensor<T>
+where
+    T: Copy + PartialOrd,
+{
+    tensor.map(|_, &x| {
+        if x < min {
+            min
+        } else if x > max {
+            max
+        } else {
+            x
+        }
+    })
+}
+
+/// The index of the largest element, or `None` if the tensor is empty.
+///
+/// If several elements share the largest value the first one wins, and
+/// values that can't be compared (i.e. `NaN`) are skipped.
+pub fn argmax<T: PartialOrd>(tensor: &Tensor<T>) -> Option<usize> {
+    position_by(tensor.elements(), |candidate, best| candidate > best)
+}
+
+/// The index of the smallest element, or `None` if the tensor is empty.
+///
+/// If several elements share the smallest value the first one wins, and
+/// values that can't be compared (i.e. `NaN`) are skipped.
+pub fn argmin<T: PartialOrd>(tensor: &Tensor<T>) -> Option<usize> {
+    position_by(tensor.elements(), |candidate, best| candidate < best)
+}
+
+fn position_by<T: PartialOrd>(
+    elements: &[T],
+    is_better: impl Fn(&T, &T) -> bool,
+) -> Option<usize> {
+    let mut best: Option<usize> = None;
+
+    for (i, candidate) in elements.iter().enumerate() {
+        // Note: NaN can't even be compared with itself
+        if candidate.partial_cmp(candidate).is_none() {
+            continue;
+        }
+
+        best = match best {
+            Some(b) if !is_better(candidate, &elements[b]) => Some(b),
+            _ => Some(i),
+        };
+    }
+
+    best
+}
+
+/// Turn a tensor of scores (e.g. logits) into probabilities which sum to 1.
+///
+/// This subtracts the largest score before exponentiating, so large scores
+/// won't overflow.
+pub fn softmax<F: Float>(tensor: &Tensor<F>) -> Tensor<F> {
+    let max = match argmax(tensor) {
+        Some(ix) => tensor.elements()[ix],
+        None => return tensor.clone(),
+    };
+
+    let exponentiated = tensor.map(|_, &x| (x - max).exp());
+    let sum = exponentiated
+        .elements()
+        .iter()
+        .fold(F::ZERO, |sum, &x| sum + x);
+
+    exponentiated.map(|_, &x| x / sum)
+}
+
+/// The average of all elements, or `None` if the tensor is empty.
+pub fn mean<F: Float>(tensor: &Tensor<F>) -> Option<F> {
+    let elements = tensor.elements();
+
+    if elements.is_empty() {
+        return None;
+    }
+
+    let sum = elements.iter().fold(F::ZERO, |sum, &x| sum + x);
+    Some(sum / F::from_usize(elements.len()))
+}
+
+/// The (population) variance of all elements, or `None` if the tensor is
+/// empty.
+pub fn variance<F: Float>(tensor: &Tensor<F>) -> Option<F> {
+    let mean = mean(tensor)?;
+    let elements = tensor.elements();
+
+    let sum_of_squares = elements.iter().fold(F::ZERO, |sum, &x| {
+        let difference = x - mean;
+        sum + difference * difference
+    });
+
+    Some(sum_of_squares / F::from_usize(elements.len()))
+}
+
+/// The (population) standard deviation of all elements, or `None` if the
+/// tensor is empty.
+pub fn std_dev<F: Float>(tensor: &Tensor<F>) -> Option<F> {
+    variance(tensor).map(Float::sqrt)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+
+    #[test]
+    fn elementwise_operations() {
+        let a: Tensor<i32> = [[1, 2], [3, 4]].into();
+        let b: Tensor<i32> = [[10, 20], [30, 40]].into();
+
+        assert_eq!(add(&a, &b).unwrap(), Tensor::from([[11, 22], [33, 44]]));
+        assert_eq!(sub(&b, &a).unwrap(), Tensor::from([[9, 18], [27, 36]]));
+        assert_eq!(mul(&a, &b).unwrap(), Tensor::from([[10, 40], [90, 160]]));
+        assert_eq!(div(&b, &a).unwrap(), Tensor::from([[10, 10], [10, 10]]));
+    }
+
+    #[test]
+    fn elementwise_operations_need_the_same_dimensions() {
+        let a: Tensor<i32> = [[1, 2], [3, 4]].into();
+        let b: Tensor<i32> = [[1, 2, 3], [4, 5, 6]].into();
+        let c = Tensor::new_vector(vec![1, 2, 3, 4]);
+
+        assert_eq!(
+            add(&a, &b).unwrap_err(),
+            ShapeError::DimensionMismatch {
+                axis: 1,
+                left: 2.into(),
+                right: 3.into(),
+            }
+        );
+        assert_eq!(
+            add(&a, &c).unwrap_err(),
+            ShapeError::RankMismatch { left: 2, right: 1 }
+        );
+    }
+
+    #[test]
+    fn clip_to_a_range() {
+        let tensor = Tensor::new_vector(vec![-2.0_f32, 0.5, 3.0]);
+
+        let got = clip(&tensor, 0.0, 1.0);
+
+        assert_eq!(got, Tensor::new_vector(vec![0.0, 0.5, 1.0]));
+    }
+
+    #[test]
+    fn argmax_and_argmin() {
+        let tensor = Tensor::new_vector(vec![3, 7, 1, 7, 1]);
+
+        assert_eq!(argmax(&tensor), Some(1));
+        assert_eq!(argmin(&tensor), Some(2));
+        assert_eq!(argmax(&Tensor::<u8>::new_vector(vec![])), None);
+    }
+
+    #[test]
+    fn argmax_skips_nan() {
+        let tensor = Tensor::new_vector(vec![f32::NAN, 0.5, f32::NAN, 2.0]);
+
+        assert_eq!(argmax(&tensor), Some(3));
+        assert_eq!(argmin(&tensor), Some(1));
+    }
+
+    #[test]
+    fn softmax_sums_to_one() {
+        let tensor = Tensor::new_vector(vec![1000.0_f64, 1000.0, 998.0]);
+
+        let got = softmax(&tensor);
+
+        let sum: f64 = got.elements().iter().sum();
+        assert!(1.0 - 1e-12 < sum && sum < 1.0 + 1e-12);
+        assert_eq!(got.elements()[0], got.elements()[1]);
+        assert!(got.elements()[2] < got.elements()[0]);
+    }
+
+    #[test]
+    fn mean_and_standard_deviation() {
+        let tensor = Tensor::new_vector(vec![
+            2.0_f32, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0,
+        ]);
+
+        assert_eq!(mean(&tensor), Some(5.0));
+        assert_eq!(variance(&tensor), Some(4.0));
+        assert_eq!(std_dev(&tensor), Some(2.0));
+        assert_eq!(mean(&Tensor::<f32>::new_vector(vec![])), None);
+    }
+}
@@ -0,0 +1,58 @@
+use alloc::string::{String, ToString};
+use core::fmt::{self, Display, Formatter};
+
+/// The error returned by the `set_*()` methods generated by
+/// `#[derive(ProcBlock)]`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SetParameterError {
+    /// The proc block doesn't have a parameter with this name.
+    UnknownParameter { name: String },
+    /// The value couldn't be parsed, or was rejected by the parameter's
+    /// `#[proc_block(validate = ...)]` function.
+    InvalidValue {
+        name: &'static str,
+        value: String,
+        reason: String,
+    },
+}
+
+impl SetParameterError {
+    pub fn unknown_parameter(name: &str) -> Self {
+        SetParameterError::UnknownParameter {
+            name: name.to_string(),
+        }
+    }
+
+    pub fn invalid_value(
+        name: &'static str,
+        value: &str,
+        reason: impl Display,
+    ) -> Self {
+        SetParameterError::InvalidValue {
+            name,
+            value: value.to_string(),
+            reason: reason.to_string(),
+        }
+    }
+}
+
+impl Display for SetParameterError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            SetParameterError::UnknownParameter { name } => {
+                write!(f, "There is no \"{}\" parameter", name)
+            },
+            SetParameterError::InvalidValue {
+                name,
+                value,
+                reason,
+            } => write!(
+                f,
+                "Unable to set \"{}\" to \"{}\": {}",
+                name, value, reason
+            ),
+        }
+    }
+}
+
+impl hotg_rune_core::Error for SetParameterError {}
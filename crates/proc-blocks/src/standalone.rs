@@ -0,0 +1,262 @@
+//! Support for compiling a proc block to a standalone WebAssembly module
+//! which the host loads at runtime.
+//!
+//! A Rune uses one of these modules when its Runefile gives the proc block
+//! `location: host`, and the host is responsible for providing the module
+//! (e.g. `rune run --proc-block normalize=normalize.wasm`). That means the
+//! post-processing for an existing Rune can be swapped out without
+//! recompiling it.
+//!
+//! The host runs the module in its own WebAssembly instance, so when using
+//! `hotg-rune-runtime` this needs its `wasmer` feature (enabled by the `rune`
+//! CLI). Runtimes without it will fail to load the proc block.
+//!
+//! # ABI
+//!
+//! The module must export its memory and the following functions, all of
+//! which are generated by [`export_proc_block!()`](crate::export_proc_block):
+//!
+//! | Function                                                 | Notes |
+//! | -------------------------------------------------------- | ----- |
+//! | `rune_proc_block_alloc(len: u32) -> u32`                 | Allocate a buffer the host can write to |
+//! | `rune_proc_block_free(ptr: u32, len: u32)`               | Free a buffer from `rune_proc_block_alloc()` |
+//! | `rune_proc_block_set_parameter(key: u32, key_len: u32, value: u32, value_len: u32) -> i32` | Set a parameter using its name |
+//! | `rune_proc_block_transform(inputs: u32, len: u32) -> i32` | Run the proc block |
+//! | `rune_proc_block_reset()`                                | Clear any state from previous transforms |
+//! | `rune_proc_block_result_ptr() -> u32`                    | Where the last call's result starts |
+//! | `rune_proc_block_result_len() -> u32`                    | The length of the last call's result |
+//!
+//! Strings are UTF-8, and the inputs and outputs of
+//! `rune_proc_block_transform()` are tensors written using
+//! [`hotg_rune_core::encoding`]. Functions returning an `i32` return `0` on
+//! success, otherwise the result contains an error message.
+//!
+//! The proc block's [`ProcBlock::init()`] hook is called on the first
+//! `rune_proc_block_transform()`, after the host has set its parameters.
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt::Display;
+
+use hotg_rune_core::encoding::TensorTuple;
+
+use crate::{ProcBlock, Transform};
+
+/// The result of the most recent call, either an error message or the
+/// encoded outputs.
+static mut RESULT: Vec<u8> = Vec::new();
+
+/// Has [`ProcBlock::init()`] been called yet?
+static mut INITIALIZED: bool = false;
+
+pub fn alloc(len: u32) -> *mut u8 {
+    let mut buffer: Vec<u8> = Vec::with_capacity(len as usize);
+    let ptr = buffer.as_mut_ptr();
+    core::mem::forget(buffer);
+
+    ptr
+}
+
+/// # Safety
+///
+/// The pointer must have come from [`alloc()`] with the same length.
+pub unsafe fn free(ptr: *mut u8, len: u32) {
+    drop(Vec::from_raw_parts(ptr, 0, len as usize));
+}
+
+/// # Safety
+///
+/// The pointers must point to valid buffers of the given lengths.
+pub unsafe fn set_parameter<E, F>(
+    key: *const u8,
+    key_len: u32,
+    value: *const u8,
+    value_len: u32,
+    set: F,
+) -> i32
+where
+    F: FnOnce(&str, &str) -> Result<(), E>,
+    E: Display,
+{
+    let result = read_str(key, key_len).and_then(|key| {
+        let value = read_str(value, value_len)?;
+        set(key, value).map_err(|e| e.to_string())?;
+        Ok(Vec::new())
+    });
+
+    finish(result)
+}
+
+/// # Safety
+///
+/// The pointer must point to a valid buffer of the given length.
+pub unsafe fn transform<P, Inputs>(
+    proc_block: &mut P,
+    inputs: *const u8,
+    len: u32,
+) -> i32
+where
+    P: Transform<Inputs>,
+    Inputs: TensorTuple,
+    P::Output: TensorTuple,
+{
+    if !INITIALIZED {
+        proc_block.init();
+        INITIALIZED = true;
+    }
+
+    let bytes = core::slice::from_raw_parts(inputs, len as usize);
+
+    let result = Inputs::decode_tensors(bytes)
+        .map_err(|e| e.to_string())
+        .and_then(|(inputs, rest)| {
+            if !rest.is_empty() {
+                return Err(alloc::format!(
+                    "Found {} unexpected bytes after the inputs",
+                    rest.len()
+                ));
+            }
+
+            let outputs = proc_block.transform(inputs);
+            let mut buffer = Vec::new();
+            outputs
+                .encode_tensors(&mut buffer)
+                .map_err(|e| e.to_string())?;
+
+            Ok(buffer)
+        });
+
+    finish(result)
+}
+
+pub fn reset<P: ProcBlock>(proc_block: &mut P) { proc_block.reset(); }
+
+pub fn result_ptr() -> *const u8 { unsafe { RESULT.as_ptr() } }
+
+pub fn result_len() -> u32 { unsafe { RESULT.len() as u32 } }
+
+unsafe fn read_str<'a>(ptr: *const u8, len: u32) -> Result<&'a str, String> {
+    let bytes = core::slice::from_raw_parts(ptr, len as usize);
+    core::str::from_utf8(bytes).map_err(|e| e.to_string())
+}
+
+fn finish(result: Result<Vec<u8>, String>) -> i32 {
+    // Safety: WebAssembly modules are single-threaded and the host won't
+    // call back into the module while we're using the result.
+    unsafe {
+        match result {
+            Ok(buffer) => {
+                RESULT = buffer;
+                0
+            },
+            Err(msg) => {
+                RESULT = msg.into_bytes();
+                1
+            },
+        }
+    }
+}
+
+/// Generate the functions a host needs to use this proc block as a
+/// standalone WebAssembly module (see the [`standalone`](crate::standalone)
+/// module for the ABI).
+///
+/// The first argument is the proc block's type and the second is the inputs
+/// it should be given, which must match one of its `Transform`
+/// implementations.
+///
+/// ```rust
+/// use hotg_rune_proc_blocks::{ProcBlock, Tensor, Transform};
+///
+/// #[derive(Default, ProcBlock)]
+/// #[transform(inputs = f32, outputs = f32)]
+/// pub struct Double {}
+///
+/// impl Transform<Tensor<f32>> for Double {
+///     type Output = Tensor<f32>;
+///
+///     fn transform(&mut self, input: Tensor<f32>) -> Tensor<f32> {
+///         input.map(|_, &value| value * 2.0)
+///     }
+/// }
+///
+/// hotg_rune_proc_blocks::export_proc_block!(Double, Tensor<f32>);
+/// ```
+///
+/// Only one proc block can be exported per module, and because the generated
+/// functions would clash with those from other proc blocks when compiled into
+/// a Rune, this should normally be put behind a cargo feature that is only
+/// enabled when building the `cdylib`.
+#[macro_export]
+macro_rules! export_proc_block {
+    ($proc_block:ty, $inputs:ty $(,)?) => {
+        const _: () = {
+            use $crate::internal::standalone;
+
+            static mut PROC_BLOCK: Option<$proc_block> = None;
+
+            fn proc_block() -> &'static mut $proc_block {
+                // Safety: WebAssembly modules are single-threaded
+                unsafe { PROC_BLOCK.get_or_insert_with(Default::default) }
+            }
+
+            #[no_mangle]
+            pub extern "C" fn rune_proc_block_alloc(len: u32) -> *mut u8 {
+                standalone::alloc(len)
+            }
+
+            #[no_mangle]
+            pub unsafe extern "C" fn rune_proc_block_free(
+                ptr: *mut u8,
+                len: u32,
+            ) {
+                standalone::free(ptr, len)
+            }
+
+            #[no_mangle]
+            pub unsafe extern "C" fn rune_proc_block_set_parameter(
+                key: *const u8,
+                key_len: u32,
+                value: *const u8,
+                value_len: u32,
+            ) -> i32 {
+                standalone::set_parameter(
+                    key,
+                    key_len,
+                    value,
+                    value_len,
+                    |key, value| proc_block().set_parameter(key, value),
+                )
+            }
+
+            #[no_mangle]
+            pub unsafe extern "C" fn rune_proc_block_transform(
+                inputs: *const u8,
+                len: u32,
+            ) -> i32 {
+                standalone::transform::<$proc_block, $inputs>(
+                    proc_block(),
+                    inputs,
+                    len,
+                )
+            }
+
+            #[no_mangle]
+            pub extern "C" fn rune_proc_block_reset() {
+                standalone::reset(proc_block())
+            }
+
+            #[no_mangle]
+            pub extern "C" fn rune_proc_block_result_ptr() -> *const u8 {
+                standalone::result_ptr()
+            }
+
+            #[no_mangle]
+            pub extern "C" fn rune_proc_block_result_len() -> u32 {
+                standalone::result_len()
+            }
+        };
+    };
+}
@@ -5,8 +5,8 @@ use std::marker::PhantomData;
 
 use hotg_rune_core::{ElementType, Tensor};
 use hotg_rune_proc_blocks::{
-    Dimension, Dimensions, ProcBlock, ProcBlockDescriptor, TensorDescriptor,
-    Transform, TransformDescriptor,
+    Dimension, Dimensions, ParameterDescriptor, ProcBlock, ProcBlockDescriptor,
+    SetParameterError, TensorDescriptor, Transform, TransformDescriptor,
 };
 
 /// A dummy proc block.
@@ -58,11 +58,13 @@ fn generate_expected_descriptor() {
         available_transforms: vec![
             TransformDescriptor {
                 inputs: TensorDescriptor {
+                    name: None,
                     element_type: ElementType::F32,
                     dimensions: vec![Dimension::Any; 3].into(),
                 }
                 .into(),
                 outputs: TensorDescriptor {
+                    name: None,
                     element_type: ElementType::U8,
                     dimensions: vec![Dimension::Value(1)].into(),
                 }
@@ -70,11 +72,13 @@ fn generate_expected_descriptor() {
             },
             TransformDescriptor {
                 inputs: TensorDescriptor {
+                    name: None,
                     element_type: ElementType::U8,
                     dimensions: Dimensions::Arbitrary,
                 }
                 .into(),
                 outputs: TensorDescriptor {
+                    name: None,
                     element_type: ElementType::F32,
                     dimensions: vec![Dimension::Any].into(),
                 }
@@ -82,9 +86,38 @@ fn generate_expected_descriptor() {
             },
         ]
         .into(),
+        parameters: vec![ParameterDescriptor {
+            name: "a".into(),
+            description: "Some parameter.".into(),
+            type_name: "u32".into(),
+            default_value: None,
+        }]
+        .into(),
+        abi_version: ProcBlockDescriptor::ABI_VERSION,
     };
 
     let got = <Foo as ProcBlock>::DESCRIPTOR;
 
     assert_eq!(got, should_be);
 }
+
+#[test]
+fn set_parameters_by_name() {
+    let mut foo = Foo::default();
+
+    foo.set_parameter("a", "42").unwrap();
+    assert_eq!(foo.a, 42);
+
+    assert_eq!(
+        foo.set_parameter("a", "-1").unwrap_err(),
+        SetParameterError::InvalidValue {
+            name: "a",
+            value: "-1".into(),
+            reason: "invalid digit found in string".into(),
+        }
+    );
+    assert_eq!(
+        foo.set_parameter("skipped", "[]").unwrap_err(),
+        SetParameterError::unknown_parameter("skipped")
+    );
+}
@@ -0,0 +1,142 @@
+//! Drive a proc block through the functions generated by
+//! `export_proc_block!()`, the same way a host would.
+
+use hotg_rune_core::{encoding::TensorTuple, Tensor};
+use hotg_rune_proc_blocks::{ProcBlock, Transform};
+
+/// Keep a running total of everything it has been given.
+#[derive(Default, ProcBlock)]
+#[proc_block(reset = Self::clear)]
+#[transform(inputs = [f32; 1], outputs = [f32; 1])]
+pub struct Accumulate {
+    scale: f32,
+    #[proc_block(skip)]
+    total: f32,
+}
+
+impl Accumulate {
+    fn clear(&mut self) { self.total = 0.0; }
+}
+
+impl Transform<Tensor<f32>> for Accumulate {
+    type Output = Tensor<f32>;
+
+    fn transform(&mut self, input: Tensor<f32>) -> Tensor<f32> {
+        self.total += input.elements().iter().sum::<f32>() * self.scale;
+        Tensor::new_vector(vec![self.total])
+    }
+}
+
+hotg_rune_proc_blocks::export_proc_block!(Accumulate, Tensor<f32>);
+
+// The generated functions aren't nameable from Rust, so link to them like
+// any other foreign function.
+extern "C" {
+    fn rune_proc_block_alloc(len: u32) -> *mut u8;
+    fn rune_proc_block_free(ptr: *mut u8, len: u32);
+    fn rune_proc_block_set_parameter(
+        key: *const u8,
+        key_len: u32,
+        value: *const u8,
+        value_len: u32,
+    ) -> i32;
+    fn rune_proc_block_transform(inputs: *const u8, len: u32) -> i32;
+    fn rune_proc_block_reset();
+    fn rune_proc_block_result_ptr() -> *const u8;
+    fn rune_proc_block_result_len() -> u32;
+}
+
+/// Copy `data` into a buffer allocated by the proc block.
+fn write(data: &[u8]) -> (*mut u8, u32) {
+    let len = data.len() as u32;
+
+    unsafe {
+        let ptr = rune_proc_block_alloc(len);
+        std::slice::from_raw_parts_mut(ptr, data.len()).copy_from_slice(data);
+        (ptr, len)
+    }
+}
+
+fn result(status: i32) -> Result<Vec<u8>, String> {
+    let result = unsafe {
+        let ptr = rune_proc_block_result_ptr();
+        let len = rune_proc_block_result_len();
+        std::slice::from_raw_parts(ptr, len as usize).to_vec()
+    };
+
+    if status == 0 {
+        Ok(result)
+    } else {
+        Err(String::from_utf8(result).unwrap())
+    }
+}
+
+fn set_parameter(key: &str, value: &str) -> Result<(), String> {
+    let (key_ptr, key_len) = write(key.as_bytes());
+    let (value_ptr, value_len) = write(value.as_bytes());
+
+    let status = unsafe {
+        rune_proc_block_set_parameter(key_ptr, key_len, value_ptr, value_len)
+    };
+
+    unsafe {
+        rune_proc_block_free(key_ptr, key_len);
+        rune_proc_block_free(value_ptr, value_len);
+    }
+
+    result(status).map(|_| ())
+}
+
+fn transform(inputs: &[u8]) -> Result<Vec<u8>, String> {
+    let (ptr, len) = write(inputs);
+    let status = unsafe { rune_proc_block_transform(ptr, len) };
+    unsafe { rune_proc_block_free(ptr, len) };
+
+    result(status)
+}
+
+fn encode(elements: &[f32]) -> Vec<u8> {
+    let mut buffer = Vec::new();
+    Tensor::new_vector(elements.to_vec())
+        .encode_tensors(&mut buffer)
+        .unwrap();
+    buffer
+}
+
+fn decode(bytes: &[u8]) -> Vec<f32> {
+    let (tensor, rest) = Tensor::<f32>::decode_tensors(bytes).unwrap();
+    assert!(rest.is_empty());
+    tensor.elements().to_vec()
+}
+
+// Note: the generated functions share global state, so everything is done in
+// a single test.
+#[test]
+fn round_trip_through_the_exported_functions() {
+    set_parameter("scale", "2").unwrap();
+    assert_eq!(
+        set_parameter("scale", "a lot").unwrap_err(),
+        "Unable to set \"scale\" to \"a lot\": invalid float literal"
+    );
+    assert_eq!(
+        set_parameter("missing", "42").unwrap_err(),
+        "There is no \"missing\" parameter"
+    );
+
+    let outputs = transform(&encode(&[1.0, 2.0])).unwrap();
+    assert_eq!(decode(&outputs), &[6.0]);
+    let outputs = transform(&encode(&[0.5])).unwrap();
+    assert_eq!(decode(&outputs), &[7.0]);
+
+    unsafe { rune_proc_block_reset() };
+    let outputs = transform(&encode(&[0.5])).unwrap();
+    assert_eq!(decode(&outputs), &[1.0]);
+
+    let mut trailing = encode(&[1.0]);
+    trailing.push(0xff);
+    assert_eq!(
+        transform(&trailing).unwrap_err(),
+        "Found 1 unexpected bytes after the inputs"
+    );
+    assert!(transform(&[1, 2, 3]).is_err());
+}
@@ -1 +1,5 @@
-fn main() { build_info_build::build_script(); }
+fn main() {
+    build_info_build::build_script();
+    tonic_build::compile_protos("proto/rune.proto")
+        .expect("Unable to generate the gRPC bindings");
+}
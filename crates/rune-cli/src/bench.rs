@@ -0,0 +1,82 @@
+use std::io::Write;
+
+use anyhow::{Context, Error};
+use hotg_rune_runtime::ModelBenchmark;
+use structopt::StructOpt;
+use strum::VariantNames;
+
+use crate::{Format, Run};
+
+#[derive(Debug, Clone, PartialEq, StructOpt)]
+pub struct Bench {
+    #[structopt(
+        short = "n",
+        long,
+        default_value = "100",
+        help = "How many times to run each model"
+    )]
+    iterations: usize,
+    #[structopt(
+        short,
+        long,
+        help = "The format to print results in",
+        default_value = "text",
+        possible_values = Format::VARIANTS,
+        parse(try_from_str)
+    )]
+    format: Format,
+    #[structopt(flatten)]
+    run: Run,
+}
+
+impl Bench {
+    pub fn execute(self) -> Result<(), Error> {
+        // Note: use --warm-up to keep one-off setup costs out of the results
+        let mut runtime = self.run.load()?;
+
+        let benchmarks = runtime
+            .benchmark_models(self.iterations)
+            .context("Unable to benchmark the models")?;
+
+        match self.format {
+            Format::Text => {
+                for benchmark in &benchmarks {
+                    println!("{}", benchmark);
+                }
+            },
+            Format::Json => {
+                let results: Vec<_> =
+                    benchmarks.iter().map(BenchmarkResult::from).collect();
+                let mut stdout = std::io::stdout();
+                serde_json::to_writer_pretty(&mut stdout, &results)
+                    .context("Unable to print to stdout")?;
+                writeln!(stdout)?;
+            },
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, serde::Serialize)]
+struct BenchmarkResult {
+    model_id: u32,
+    iterations: usize,
+    p50_ms: f64,
+    p95_ms: f64,
+    max_ms: f64,
+    peak_memory_bytes: Option<u64>,
+}
+
+impl From<&ModelBenchmark> for BenchmarkResult {
+    fn from(b: &ModelBenchmark) -> Self {
+        BenchmarkResult {
+            model_id: b.model_id,
+            iterations: b.iterations,
+            p50_ms: b.p50.as_secs_f64() * 1000.0,
+            p95_ms: b.p95.as_secs_f64() * 1000.0,
+            max_ms: b.max.as_secs_f64() * 1000.0,
+            peak_memory_bytes: b.peak_memory,
+        }
+    }
+}
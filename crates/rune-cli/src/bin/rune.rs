@@ -1,8 +1,8 @@
 use anyhow::Error;
 use env_logger::Env;
 use hotg_rune_cli::{
-    Build, ColorChoice, Format, Graph, Inspect, ModelInfo, Run, Unstable,
-    Version,
+    Bench, Build, ColorChoice, Format, Graph, Inspect, Install, ModelInfo, New,
+    Run, Serve, Unstable, Version,
 };
 use log::LevelFilter;
 use structopt::{clap::AppSettings, StructOpt};
@@ -32,10 +32,14 @@ fn main() -> Result<(), Error> {
     match cmd {
         Some(Cmd::Build(build)) => build.execute(colour.into(), unstable),
         Some(Cmd::Run(run)) => run.execute(),
+        Some(Cmd::Bench(bench)) => bench.execute(),
+        Some(Cmd::Serve(serve)) => serve.execute(),
         Some(Cmd::Graph(graph)) => graph.execute(),
         Some(Cmd::Version(version)) => version.execute(),
         Some(Cmd::ModelInfo(m)) => m.execute(),
         Some(Cmd::Inspect(i)) => i.execute(),
+        Some(Cmd::Install(i)) => i.execute(),
+        Some(Cmd::New(n)) => n.execute(),
         None if version => {
             let v = Version {
                 format: Format::Text,
@@ -79,6 +83,10 @@ enum Cmd {
     Build(Build),
     /// Execute a Rune on the current device.
     Run(Run),
+    /// Measure how quickly each of a Rune's models runs.
+    Bench(Bench),
+    /// Load one or more Runes and serve them over the network.
+    Serve(Serve),
     /// Print version information about the rune CLI.
     Version(Version),
     /// Load a TensorFlow Lite model and print information about it.
@@ -88,4 +96,9 @@ enum Cmd {
     Inspect(Inspect),
     /// Visualise the flow of data through a Rune.
     Graph(Graph),
+    /// Download a proc block from the registry so Runefiles can refer to it
+    /// by name.
+    Install(Install),
+    /// Create a new project from a template.
+    New(New),
 }
@@ -16,7 +16,7 @@ use hotg_rune_compiler::{
         AfterCodegenContext, AfterLoweringContext, AfterParseContext,
         AfterTypeCheckingContext, Continuation,
     },
-    BuildContext, Verbosity,
+    registry, BuildContext, Verbosity,
 };
 use once_cell::sync::Lazy;
 
@@ -33,6 +33,9 @@ pub struct Build {
     /// The directory to use when caching builds.
     #[structopt(long, env)]
     cache_dir: Option<PathBuf>,
+    /// Where proc blocks installed with "rune install" are saved.
+    #[structopt(long, env, parse(from_os_str))]
+    install_dir: Option<PathBuf>,
     /// The directory that all paths are resolved relative to (Defaults to the
     /// Runefile's directory)
     #[structopt(short, long, env)]
@@ -106,6 +109,11 @@ impl Build {
             working_directory,
             optimized: !self.debug,
             rune_version: Some(RuneVersion::new(env!("CARGO_PKG_VERSION"))),
+            install_dir: Some(
+                self.install_dir
+                    .clone()
+                    .unwrap_or_else(registry::default_install_dir),
+            ),
         })
     }
 
@@ -283,6 +291,16 @@ impl hotg_rune_compiler::hooks::Hooks for Hooks {
     ) -> Continuation {
         let CompilationResult(result) = ctx.take_compilation_result();
 
+        // Arguments are checked against each proc block's descriptor after
+        // compiling, so there may be more diagnostics
+        let continuation = self.check_diagnostics(
+            ctx.diagnostics_mut().drain(),
+            &ctx.build_context(),
+        );
+        if continuation != Continuation::Continue {
+            return continuation;
+        }
+
         if let Err(err) = result
             .map_err(Error::from)
             .and_then(|c| self.save_binary(&c))
@@ -5,8 +5,8 @@ use std::{
 
 use anyhow::{Context, Error};
 use hotg_rune_proc_blocks::{
-    ProcBlockDescriptor, TensorDescriptor, TensorDescriptors,
-    TransformDescriptor,
+    ParameterDescriptor, ProcBlockDescriptor, TensorDescriptor,
+    TensorDescriptors, TransformDescriptor,
 };
 
 use crate::{inspect::wasm_custom_sections, Format};
@@ -76,6 +76,8 @@ fn print_descriptor(metadata: &ProcBlockDescriptor) {
         type_name,
         description,
         available_transforms,
+        parameters,
+        abi_version,
     } = metadata;
 
     println!("{}", type_name);
@@ -89,6 +91,11 @@ fn print_descriptor(metadata: &ProcBlockDescriptor) {
         println!();
     }
 
+    if *abi_version != 0 {
+        println!("ABI version: {}", abi_version);
+        println!();
+    }
+
     if available_transforms.is_empty() {
         println!("(no transforms registered)");
     } else {
@@ -98,6 +105,34 @@ fn print_descriptor(metadata: &ProcBlockDescriptor) {
             print_transform(transform);
         }
     }
+
+    if !parameters.is_empty() {
+        println!("Parameters:");
+
+        for parameter in parameters.iter() {
+            print_parameter(parameter);
+        }
+    }
+}
+
+fn print_parameter(parameter: &ParameterDescriptor) {
+    let ParameterDescriptor {
+        name,
+        description,
+        type_name,
+        default_value,
+    } = parameter;
+
+    match default_value {
+        Some(default_value) => {
+            println!("  {}: {} (default: {})", name, type_name, default_value)
+        },
+        None => println!("  {}: {}", name, type_name),
+    }
+
+    for line in description.lines() {
+        println!("    {}", line);
+    }
 }
 
 fn print_transform(transform: &TransformDescriptor) {
@@ -128,9 +163,14 @@ fn print_tensor_descriptors(tensors: &TensorDescriptors) {
 
 fn print_tensor_descriptor(tensor: &TensorDescriptor) {
     let TensorDescriptor {
+        name,
         element_type,
         dimensions,
     } = tensor;
+
+    if let Some(name) = name {
+        print!("{}: ", name);
+    }
     print!("{}[{}]", element_type, dimensions);
 }
 
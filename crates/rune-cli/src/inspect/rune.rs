@@ -6,7 +6,7 @@ use hotg_rune_compiler::{
         CapabilitySummary, ModelSummary, OutputSummary, ProcBlockSummary,
         RuneGraph, RuneVersion, TensorId,
     },
-    lowering::{Name, Resource},
+    lowering::{ModelMetadata, Name, Resource, TensorMetadata},
     parse::{ResourceOrString, ResourceType},
 };
 use hotg_rune_core::Shape;
@@ -107,6 +107,59 @@ fn print_models(
         println!("- {}: {}", name, model.file);
         print_tensors("Inputs", &model.inputs, tensors);
         print_tensors("Outputs", &model.outputs, tensors);
+
+        if let Some(meta) = &model.metadata {
+            print_model_metadata(meta);
+        }
+    }
+}
+
+fn print_model_metadata(meta: &ModelMetadata) {
+    println!("  Metadata:");
+
+    let fields = [
+        ("Name", &meta.name),
+        ("Description", &meta.description),
+        ("Version", &meta.version),
+        ("Author", &meta.author),
+        ("License", &meta.license),
+    ];
+
+    for (field, value) in fields {
+        if let Some(value) = value {
+            println!("    {}: {}", field, value);
+        }
+    }
+
+    for tensor in &meta.inputs {
+        print_tensor_metadata("Input", tensor);
+    }
+    for tensor in &meta.outputs {
+        print_tensor_metadata("Output", tensor);
+    }
+
+    if !meta.labels.is_empty() {
+        println!("    Labels: {}", meta.labels.join(", "));
+    }
+}
+
+fn print_tensor_metadata(kind: &str, tensor: &TensorMetadata) {
+    let name = tensor.name.as_deref().unwrap_or("(unnamed)");
+
+    match &tensor.description {
+        Some(description) => println!("    {} {}: {}", kind, name, description),
+        None => println!("    {} {}", kind, name),
+    }
+
+    if !tensor.mean.is_empty() || !tensor.std.is_empty() {
+        println!(
+            "      Normalization: mean={:?}, std={:?}",
+            tensor.mean, tensor.std
+        );
+    }
+
+    if !tensor.associated_files.is_empty() {
+        println!("      Files: {}", tensor.associated_files.join(", "));
     }
 }
 
@@ -0,0 +1,177 @@
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use anyhow::{Context, Error};
+use hotg_rune_compiler::{
+    parse,
+    registry::{self, Index, Installed, InstalledProcBlock, Release},
+};
+
+#[derive(Debug, Clone, PartialEq, structopt::StructOpt)]
+pub struct Install {
+    /// The proc block to install, with an optional version requirement
+    /// (e.g. "hotg-ai/mfcc" or "hotg-ai/mfcc@^0.11").
+    proc_block: parse::Path,
+    /// The registry index to use, either as a URL or a path on disk (defaults
+    /// to the index of builtin proc blocks in the Rune repo).
+    #[structopt(
+        long,
+        env = "RUNE_REGISTRY",
+        default_value = "https://raw.githubusercontent.com/hotg-ai/rune/master/proc-blocks/index.json"
+    )]
+    registry: String,
+    /// Where to save installed proc blocks.
+    #[structopt(long, env, parse(from_os_str))]
+    install_dir: Option<PathBuf>,
+    /// Install the proc block again, even if it is already installed.
+    #[structopt(short, long)]
+    force: bool,
+}
+
+impl Install {
+    pub fn execute(self) -> Result<(), Error> {
+        let parse::Path {
+            base: name,
+            sub_path,
+            version,
+        } = &self.proc_block;
+
+        if sub_path.is_some() {
+            anyhow::bail!(
+                "Proc blocks from a registry are referred to by name (e.g. \
+                 \"hotg-ai/mfcc\"), but found \"{}\"",
+                self.proc_block
+            );
+        }
+
+        let requirement = registry::parse_requirement(version.as_deref())
+            .with_context(|| {
+                format!("Unable to parse \"{}\" as a version", self.proc_block)
+            })?;
+
+        let index = load_index(&self.registry).with_context(|| {
+            format!("Unable to load the registry from \"{}\"", self.registry)
+        })?;
+        let release = index.resolve(name, &requirement)?;
+
+        let install_dir = self
+            .install_dir
+            .clone()
+            .unwrap_or_else(registry::default_install_dir);
+        let mut installed =
+            Installed::load(&install_dir).with_context(|| {
+                format!(
+                    "Unable to read the proc blocks installed in \"{}\"",
+                    install_dir.display()
+                )
+            })?;
+
+        let exact = format!("={}", release.version).parse()?;
+        if let Some(existing) = installed.find(name, &exact) {
+            if !self.force && existing.path.exists() {
+                log::info!(
+                    "Version {} of \"{}\" is already installed",
+                    release.version,
+                    name
+                );
+                return Ok(());
+            }
+        }
+
+        let checkout = install_dir.join(name).join(release.version.to_string());
+        let path = fetch(release, &checkout).with_context(|| {
+            format!("Unable to fetch \"{}\" from \"{}\"", name, release.git)
+        })?;
+
+        installed.insert(
+            name,
+            InstalledProcBlock {
+                version: release.version.clone(),
+                path: path.clone(),
+            },
+        );
+        installed.save(&install_dir).with_context(|| {
+            format!(
+                "Unable to save the list of installed proc blocks to \"{}\"",
+                install_dir.display()
+            )
+        })?;
+
+        println!(
+            "Installed version {} of \"{}\" to \"{}\"",
+            release.version,
+            name,
+            path.display()
+        );
+
+        Ok(())
+    }
+}
+
+fn load_index(registry: &str) -> Result<Index, Error> {
+    let index = if registry.starts_with("http://")
+        || registry.starts_with("https://")
+    {
+        log::debug!("Downloading the registry index from \"{}\"", registry);
+        ureq::get(registry).call()?.into_json()?
+    } else {
+        let json = std::fs::read(registry)?;
+        serde_json::from_slice(&json)?
+    };
+
+    Ok(index)
+}
+
+/// Check out a release into the `checkout` directory, returning the
+/// directory containing the proc block.
+fn fetch(release: &Release, checkout: &Path) -> Result<PathBuf, Error> {
+    if checkout.exists() {
+        std::fs::remove_dir_all(checkout).with_context(|| {
+            format!("Unable to remove \"{}\"", checkout.display())
+        })?;
+    }
+    std::fs::create_dir_all(checkout).with_context(|| {
+        format!("Unable to create the \"{}\" directory", checkout.display())
+    })?;
+
+    // Note: fetching the revision directly lets us do a shallow clone of a
+    // tag, branch, or commit.
+    let rev = release.rev.as_deref().unwrap_or("HEAD");
+    git(checkout, &["init", "--quiet"])?;
+    git(
+        checkout,
+        &["fetch", "--quiet", "--depth", "1", &release.git, rev],
+    )?;
+    git(checkout, &["checkout", "--quiet", "FETCH_HEAD"])?;
+
+    let proc_block_dir = match &release.path {
+        Some(path) => checkout.join(path),
+        None => checkout.to_path_buf(),
+    };
+
+    if !proc_block_dir.join("Cargo.toml").exists() {
+        anyhow::bail!(
+            "Expected to find a \"Cargo.toml\" in \"{}\"",
+            proc_block_dir.display()
+        );
+    }
+
+    Ok(proc_block_dir)
+}
+
+fn git(dir: &Path, args: &[&str]) -> Result<(), Error> {
+    let mut cmd = Command::new("git");
+    cmd.args(args).current_dir(dir);
+
+    log::debug!("Executing {:?}", cmd);
+
+    let status = cmd.status().context("Unable to start git")?;
+
+    if !status.success() {
+        anyhow::bail!("{:?} failed with {}", cmd, status);
+    }
+
+    Ok(())
+}
@@ -1,8 +1,13 @@
+mod bench;
 pub mod build;
 mod graph;
 mod inspect;
+mod install;
+mod live;
 mod model_info;
+mod new;
 pub mod run;
+mod serve;
 mod unstable;
 mod version;
 
@@ -10,8 +15,9 @@ use codespan_reporting::term::termcolor;
 use env_logger::WriteStyle;
 
 pub use crate::{
-    build::Build, graph::Graph, inspect::Inspect, model_info::ModelInfo,
-    run::Run, unstable::Unstable, version::Version,
+    bench::Bench, build::Build, graph::Graph, inspect::Inspect,
+    install::Install, model_info::ModelInfo, new::New, run::Run,
+    serve::Serve, unstable::Unstable, version::Version,
 };
 
 #[derive(
@@ -0,0 +1,178 @@
+//! Reading capability data from devices attached to the host (e.g. a webcam)
+//! or from streams (e.g. stdin) instead of from files.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::{Context, Error};
+use hotg_rune_runtime::{
+    builtins::RawStream,
+    sources::{Camera, Microphone, Recording},
+};
+use image::DynamicImage;
+
+/// A capability which is fed from a live device, specified on the command
+/// line as `KIND:DEVICE[:INDEX]` (e.g. `image:camera:0`).
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) enum LiveSource {
+    /// Provide images to the `IMAGE` capability from a webcam.
+    Camera { index: usize },
+    /// Provide audio to the `SOUND` capability from a microphone, using the
+    /// default input device if no index is given.
+    Microphone { index: Option<usize> },
+}
+
+impl FromStr for LiveSource {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let parts: Vec<&str> = s.split(':').collect();
+
+        let (kind, device, index) = match parts.as_slice() {
+            [kind, device] => (*kind, *device, None),
+            [kind, device, index] => {
+                let index = index.parse().with_context(|| {
+                    format!("\"{}\" isn't a valid device index", index)
+                })?;
+                (*kind, *device, Some(index))
+            },
+            _ => anyhow::bail!(
+                "Expected a live source in the form \"KIND:DEVICE[:INDEX]\" \
+                 (e.g. \"image:camera:0\"), found \"{}\"",
+                s
+            ),
+        };
+
+        match (kind.to_ascii_uppercase().as_str(), device) {
+            ("IMAGE", "camera") => Ok(LiveSource::Camera {
+                index: index.unwrap_or(0),
+            }),
+            ("SOUND", "microphone" | "mic") => {
+                Ok(LiveSource::Microphone { index })
+            },
+            _ => anyhow::bail!(
+                "Reading {} data from a \"{}\" isn't supported",
+                kind,
+                device
+            ),
+        }
+    }
+}
+
+/// The devices that were opened for each [`LiveSource`].
+///
+/// Devices aren't necessarily thread-safe, so they stay on the main thread
+/// and we capture a snapshot of their data with [`LiveDevices::capture()`]
+/// before the inputs are decoded.
+#[derive(Default)]
+pub(crate) struct LiveDevices {
+    cameras: Vec<Camera>,
+    microphones: Vec<Microphone>,
+    streams: HashMap<PathBuf, Arc<Mutex<RawStream>>>,
+}
+
+impl LiveDevices {
+    /// Open each [`LiveSource`], as well as any `RAW` inputs which need to be
+    /// read incrementally (see [`RawStream::is_stream()`]).
+    pub(crate) fn open(
+        sources: &[LiveSource],
+        raw: &[PathBuf],
+    ) -> Result<Self, Error> {
+        let mut devices = LiveDevices::default();
+
+        for path in raw {
+            if RawStream::is_stream(path) {
+                let stream = RawStream::open(path)?;
+                devices
+                    .streams
+                    .insert(path.clone(), Arc::new(Mutex::new(stream)));
+            }
+        }
+
+        for source in sources {
+            match *source {
+                LiveSource::Camera { index } => {
+                    devices.cameras.push(Camera::open(index)?);
+                },
+                LiveSource::Microphone { index } => {
+                    devices.microphones.push(Microphone::open(index)?);
+                },
+            }
+        }
+
+        Ok(devices)
+    }
+
+    pub(crate) fn capture(&mut self) -> Result<LiveInputs, Error> {
+        let images = self
+            .cameras
+            .iter_mut()
+            .map(|camera| camera.capture())
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        let recordings =
+            self.microphones.iter().map(Microphone::recording).collect();
+
+        Ok(LiveInputs {
+            images,
+            recordings,
+            streams: self.streams.clone(),
+        })
+    }
+}
+
+/// A snapshot of the data read from each [`LiveDevices`].
+#[derive(Default)]
+pub(crate) struct LiveInputs {
+    /// One frame from each camera, in the order they were specified.
+    pub images: Vec<DynamicImage>,
+    /// The audio being recorded by each microphone.
+    pub recordings: Vec<Recording>,
+    streams: HashMap<PathBuf, Arc<Mutex<RawStream>>>,
+}
+
+impl LiveInputs {
+    /// Get the [`RawStream`] for a `RAW` input, if it is being streamed.
+    pub(crate) fn stream(&self, path: &Path) -> Option<&Mutex<RawStream>> {
+        self.streams.get(path).map(|s| &**s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_live_sources() {
+        let inputs = vec![
+            ("image:camera", LiveSource::Camera { index: 0 }),
+            ("image:camera:2", LiveSource::Camera { index: 2 }),
+            ("IMAGE:camera:1", LiveSource::Camera { index: 1 }),
+            ("sound:microphone", LiveSource::Microphone { index: None }),
+            ("sound:mic:1", LiveSource::Microphone { index: Some(1) }),
+        ];
+
+        for (src, should_be) in inputs {
+            let got: LiveSource = src.parse().unwrap();
+            assert_eq!(got, should_be);
+        }
+    }
+
+    #[test]
+    fn unsupported_live_sources() {
+        let inputs = vec![
+            "image",
+            "image:camera:first",
+            "sound:camera:0",
+            "image:microphone",
+        ];
+
+        for src in inputs {
+            assert!(src.parse::<LiveSource>().is_err(), "{}", src);
+        }
+    }
+}
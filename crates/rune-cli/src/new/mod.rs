@@ -0,0 +1,24 @@
+mod proc_block;
+
+use anyhow::Error;
+
+#[derive(Debug, Clone, PartialEq, structopt::StructOpt)]
+pub struct New {
+    #[structopt(subcommand)]
+    template: Template,
+}
+
+impl New {
+    pub fn execute(self) -> Result<(), Error> {
+        match self.template {
+            Template::ProcBlock(p) => p.execute(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, structopt::StructOpt)]
+enum Template {
+    /// A proc block crate with tests, CI, and an example Runefile.
+    #[structopt(name = "proc-block")]
+    ProcBlock(proc_block::NewProcBlock),
+}
@@ -0,0 +1,345 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Error};
+
+#[derive(Debug, Clone, PartialEq, structopt::StructOpt)]
+pub struct NewProcBlock {
+    /// Where to create the proc block. The directory's name is used as the
+    /// crate name (e.g. "./my-proc-block").
+    #[structopt(parse(from_os_str))]
+    path: PathBuf,
+    /// Write the template even if the directory already has files in it.
+    #[structopt(short, long)]
+    force: bool,
+}
+
+impl NewProcBlock {
+    pub fn execute(self) -> Result<(), Error> {
+        let NewProcBlock { path, force } = self;
+
+        let name =
+            path.file_name().and_then(|s| s.to_str()).with_context(|| {
+                format!(
+                    "Unable to determine the crate name from \"{}\"",
+                    path.display()
+                )
+            })?;
+        validate_name(name)?;
+
+        if !force && !is_empty_dir(&path)? {
+            anyhow::bail!(
+                "\"{}\" already exists. Use --force to overwrite it",
+                path.display()
+            );
+        }
+
+        log::info!("Creating the \"{}\" proc block", name);
+
+        generate_project(&path, name)
+            .context("Unable to generate the proc block")?;
+
+        println!("Created \"{}\" in \"{}\"", name, path.display());
+        println!();
+        println!("Try it out with:");
+        println!();
+        println!("  cd {}", path.display());
+        println!("  cargo test");
+        println!("  rune build example/Runefile.yml");
+
+        Ok(())
+    }
+}
+
+fn validate_name(name: &str) -> Result<(), Error> {
+    let starts_with_letter = name
+        .chars()
+        .next()
+        .map(|c| c.is_ascii_alphabetic())
+        .unwrap_or(false);
+    let valid_chars = name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+
+    if !starts_with_letter || !valid_chars {
+        anyhow::bail!(
+            "\"{}\" isn't a valid crate name. Names must start with a letter \
+             and only contain letters, numbers, \"-\", and \"_\"",
+            name
+        );
+    }
+
+    Ok(())
+}
+
+fn is_empty_dir(path: &Path) -> Result<bool, Error> {
+    if !path.exists() {
+        return Ok(true);
+    }
+
+    let mut entries = path.read_dir().with_context(|| {
+        format!("Unable to read the \"{}\" directory", path.display())
+    })?;
+
+    Ok(entries.next().is_none())
+}
+
+/// Convert a crate name like `my-proc-block` into the name of its proc block
+/// type, `MyProcBlock`.
+fn type_name(name: &str) -> String {
+    name.split(|c| c == '-' || c == '_')
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            let first = chars.next().unwrap().to_ascii_uppercase();
+            std::iter::once(first).chain(chars).collect::<String>()
+        })
+        .collect()
+}
+
+const CARGO_TOML_TEMPLATE: &str = r#"[package]
+name = "$NAME"
+version = "0.1.0"
+edition = "2018"
+description = "A proc block for use in Runes."
+publish = false
+
+[dependencies]
+hotg-rune-proc-blocks = "^$PROC_BLOCKS_VERSION"
+"#;
+
+const LIB_RS_TEMPLATE: &str = r#"//! A proc block which multiplies every element in a tensor by a constant.
+//!
+//! # Example
+//!
+//! ```yaml
+//! $IDENT:
+//!   proc-block: "./$NAME"
+//!   inputs:
+//!     - rand
+//!   outputs:
+//!     - type: F32
+//!       dimensions: [1, 4]
+//!   args:
+//!     factor: 2.0
+//! ```
+
+#![no_std]
+
+extern crate alloc;
+
+use hotg_rune_proc_blocks::{ProcBlock, Tensor, Transform};
+
+/// Multiply every element in a tensor by a constant.
+#[derive(Debug, Clone, PartialEq, ProcBlock)]
+#[transform(inputs = [f32; _], outputs = [f32; _])]
+pub struct $TYPE_NAME {
+    /// The value to multiply each element by.
+    factor: f32,
+}
+
+impl Default for $TYPE_NAME {
+    fn default() -> Self { $TYPE_NAME { factor: 1.0 } }
+}
+
+impl Transform<Tensor<f32>> for $TYPE_NAME {
+    type Output = Tensor<f32>;
+
+    fn transform(&mut self, input: Tensor<f32>) -> Self::Output {
+        input.map(|_, &value| value * self.factor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+
+    #[test]
+    fn multiply_each_element() {
+        let mut proc_block = $TYPE_NAME { factor: 2.0 };
+        let input = Tensor::new_vector(vec![1.0, 2.0, 3.0]);
+
+        let got = proc_block.transform(input);
+
+        assert_eq!(got, Tensor::new_vector(vec![2.0, 4.0, 6.0]));
+    }
+
+    #[test]
+    fn set_the_factor_from_a_runefile() {
+        let mut proc_block = $TYPE_NAME::default();
+
+        proc_block.set_parameter("factor", "0.5").unwrap();
+
+        assert_eq!(proc_block.factor, 0.5);
+    }
+}
+"#;
+
+const RUNEFILE_TEMPLATE: &str = r#"# An example Rune which passes random data through the "$NAME" proc block.
+#
+# Compile it with "rune build example/Runefile.yml".
+version: 1
+image: runicos/base
+
+pipeline:
+  rand:
+    capability: RAND
+    outputs:
+      - type: F32
+        dimensions: [1, 4]
+  $IDENT:
+    proc-block: "../../$NAME"
+    inputs:
+      - rand
+    outputs:
+      - type: F32
+        dimensions: [1, 4]
+    args:
+      factor: 2.0
+  serial:
+    out: serial
+    inputs:
+      - $IDENT
+"#;
+
+const README_TEMPLATE: &str = r#"# $NAME
+
+A proc block for use in [Runes][rune].
+
+## Getting Started
+
+Proc blocks are normal Rust crates, so you can use the usual tools.
+
+```console
+$ cargo test
+$ cargo build --target wasm32-unknown-unknown
+```
+
+The `example/` directory contains a Runefile which uses this proc block.
+
+```console
+$ rune build example/Runefile.yml
+$ rune run example/example.rune
+```
+
+You can also check which inputs, outputs, and arguments the proc block
+accepts.
+
+```console
+$ rune inspect .
+```
+
+[rune]: https://github.com/hotg-ai/rune
+"#;
+
+const GITIGNORE_TEMPLATE: &str = "target/
+Cargo.lock
+*.rune
+";
+
+const CI_TEMPLATE: &str = r#"name: Continuous integration
+
+on:
+  push:
+    branches:
+      - master
+      - main
+  pull_request:
+
+jobs:
+  check:
+    name: Compile and Test
+    runs-on: ubuntu-latest
+    steps:
+      - uses: actions/checkout@v2
+      - uses: actions/cache@v2
+        with:
+          path: |
+            ~/.cargo/registry
+            ~/.cargo/git
+            target
+          key: ${{ runner.os }}-${{ github.workflow }}-${{ github.job }}
+      - name: Setup Rust
+        run: rustup show
+      - name: Check Formatting
+        run: cargo fmt --all -- --check
+      - name: Test
+        run: cargo test --verbose
+      - name: Build for WebAssembly
+        run: cargo build --verbose --target wasm32-unknown-unknown
+"#;
+
+fn generate_project(dest: &Path, name: &str) -> Result<(), Error> {
+    let ident = name.replace("-", "_");
+    let type_name = type_name(name);
+    let render = |template: &str| {
+        template
+            .replace("$PROC_BLOCKS_VERSION", hotg_rune_proc_blocks::VERSION)
+            .replace("$TYPE_NAME", &type_name)
+            .replace("$IDENT", &ident)
+            .replace("$NAME", name)
+    };
+
+    write(dest.join("Cargo.toml"), render(CARGO_TOML_TEMPLATE))?;
+    write(dest.join("src").join("lib.rs"), render(LIB_RS_TEMPLATE))?;
+    write(
+        dest.join("example").join("Runefile.yml"),
+        render(RUNEFILE_TEMPLATE),
+    )?;
+    write(dest.join("README.md"), render(README_TEMPLATE))?;
+    write(dest.join(".gitignore"), GITIGNORE_TEMPLATE)?;
+    write(
+        dest.join(".github").join("workflows").join("main.yml"),
+        CI_TEMPLATE,
+    )?;
+    write(
+        dest.join("rust-toolchain.toml"),
+        hotg_rune_compiler::rust_toolchain().to_string(),
+    )?;
+
+    Ok(())
+}
+
+fn write(
+    file: impl AsRef<Path>,
+    contents: impl AsRef<[u8]>,
+) -> Result<(), Error> {
+    let file = file.as_ref();
+
+    if let Some(parent) = file.parent() {
+        std::fs::create_dir_all(parent).with_context(|| {
+            format!("Unable to create the \"{}\" directory", parent.display())
+        })?;
+    }
+
+    std::fs::write(file, contents.as_ref())
+        .with_context(|| format!("Unable to write to \"{}\"", file.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crate_names_become_type_names() {
+        let inputs = vec![
+            ("scale", "Scale"),
+            ("my-proc-block", "MyProcBlock"),
+            ("fft_2d", "Fft2d"),
+        ];
+
+        for (name, should_be) in inputs {
+            assert_eq!(type_name(name), should_be);
+        }
+    }
+
+    #[test]
+    fn reject_invalid_crate_names() {
+        let names = vec!["", "2d-fft", "-scale", "my proc block", "scale.py"];
+
+        for name in names {
+            assert!(validate_name(name).is_err(), "{:?}", name);
+        }
+    }
+}
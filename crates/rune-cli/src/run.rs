@@ -1,14 +1,40 @@
-use std::{collections::HashMap, path::PathBuf, str::FromStr};
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufReader, BufWriter},
+    net::SocketAddr,
+    path::PathBuf,
+    str::FromStr,
+    time::{Duration, Instant},
+};
 
 use anyhow::{Context, Error};
+use hotg_rune_compiler::{lowering::SourceKind, parse::ResourceOrString};
 use hotg_rune_runtime::{
-    builtins::{self, AccelerometerSamples, Arguments, AudioClip},
+    builtins::{
+        self, AccelerometerSamples, Arguments, AudioClip, BatteryReading,
+        Dataset, DatasetItem, GpsTrack, GyroscopeSamples, NumpyArrays,
+        SensorReadings, SimulatedBattery, Table, VideoClip,
+    },
+    sinks::{
+        BleConfig, BleSink, BtleplugNotifier, FileFormat, FileSink,
+        FileSinkConfig, ImageFormat, ImageSink, ImageSinkConfig, MqttConfig,
+        MqttSink, PipeSink, WebSocketSink,
+    },
+    models::{Delegate, MODEL_VARIANTS_ENV, TFLITE_DELEGATES_ENV},
     LoadError, NodeMetadata, Runtime,
 };
 use once_cell::sync::Lazy;
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
 use regex::Regex;
 use structopt::StructOpt;
 use strum::VariantNames;
+use uuid::Uuid;
+
+use crate::{
+    inspect::Metadata,
+    live::{LiveDevices, LiveInputs, LiveSource},
+};
 
 #[derive(Debug, Clone, PartialEq, StructOpt)]
 pub struct Run {
@@ -19,10 +45,19 @@ pub struct Run {
         help = "A CSV file containing [X, Y, Z] vectors to be returned by the ACCEL capability"
     )]
     accelerometer: Vec<PathBuf>,
+    #[structopt(
+        long = "gyroscope",
+        aliases = &["gyro"],
+        parse(from_os_str),
+        help = "A CSV file containing [X, Y, Z] angular velocities to be \
+                returned by the GYRO capability"
+    )]
+    gyroscope: Vec<PathBuf>,
     #[structopt(
         long,
         parse(from_os_str),
-        help = "A WAV file containing samples returned by the SOUND capability"
+        help = "A WAV, MP3, OGG, or FLAC file containing samples returned by \
+                the SOUND capability"
     )]
     sound: Vec<PathBuf>,
     #[structopt(
@@ -32,13 +67,92 @@ pub struct Run {
         help = "An image to be returned by the IMAGE capability"
     )]
     image: Vec<PathBuf>,
+    #[structopt(
+        long,
+        parse(from_os_str),
+        help = "A video file (or animated GIF) who's frames will be returned \
+                by the VIDEO capability (formats other than GIF need ffmpeg)"
+    )]
+    video: Vec<PathBuf>,
     #[structopt(
         long,
         parse(from_os_str),
         help = "A file who's bytes will be returned as-is by the RAW \
-                capability"
+                capability. Use \"-\" or a named pipe to stream chunks of \
+                \"length\" bytes from stdin or another process"
     )]
     raw: Vec<PathBuf>,
+    #[structopt(
+        long,
+        parse(from_os_str),
+        help = "A GPX file or NMEA log to be played back by the GPS capability"
+    )]
+    gps: Vec<PathBuf>,
+    #[structopt(
+        long,
+        parse(from_os_str),
+        help = "A CSV file containing temperature (in degrees Celsius) and \
+                optionally humidity readings for the THERMOMETER capability"
+    )]
+    thermometer: Vec<PathBuf>,
+    #[structopt(
+        long,
+        parse(from_os_str),
+        help = "A CSV file containing [level, voltage] readings for the \
+                BATTERY capability (defaults to a simulated battery)"
+    )]
+    battery: Vec<PathBuf>,
+    #[structopt(
+        long,
+        parse(from_os_str),
+        help = "A CSV file containing air pressure readings (in hPa) for the \
+                BAROMETER capability"
+    )]
+    barometer: Vec<PathBuf>,
+    #[structopt(
+        long,
+        parse(from_os_str),
+        help = "A CSV file containing distances (in cm) for the PROXIMITY \
+                capability"
+    )]
+    proximity: Vec<PathBuf>,
+    #[structopt(
+        long,
+        parse(from_os_str),
+        help = "A CSV file containing ambient light readings (in lux) for \
+                the LIGHT capability"
+    )]
+    light: Vec<PathBuf>,
+    #[structopt(
+        long,
+        parse(from_os_str),
+        help = "A NumPy array (.npy) or archive (.npz) to be returned by the \
+                TENSOR capability"
+    )]
+    tensor: Vec<PathBuf>,
+    #[structopt(
+        long,
+        aliases = &["csv"],
+        parse(from_os_str),
+        help = "A CSV file to be returned by the TABULAR capability"
+    )]
+    tabular: Vec<PathBuf>,
+    #[structopt(
+        long = "input",
+        parse(try_from_str),
+        help = "Provide the file for the capability with this name in the \
+                Runefile (\"NAME=path\"), instead of relying on the order of \
+                \"--image\", \"--sound\", etc."
+    )]
+    inputs: Vec<FileResource>,
+    #[structopt(
+        long = "capability",
+        help = "Read a capability's data from a device attached to this \
+                machine instead of a file (e.g. \"image:camera:0\" for the \
+                first webcam or \"sound:microphone\" for the default \
+                microphone)"
+    )]
+    live: Vec<LiveSource>,
     #[structopt(
         long,
         aliases = &["rand"],
@@ -52,6 +166,15 @@ pub struct Run {
         default_value = "wasmer",
     )]
     engine: Engine,
+    #[structopt(
+        long = "delegate",
+        use_delimiter = true,
+        help = "Hardware accelerators TensorFlow Lite models should try to \
+                use, in order of preference (\"gpu\", \"nnapi\", or \
+                \"edgetpu\"). Models fall back to the CPU when none are \
+                available"
+    )]
+    delegates: Vec<Delegate>,
     #[structopt(
         long = "file-resource",
         parse(try_from_str),
@@ -64,97 +187,585 @@ pub struct Run {
         help = "Use the provided string as a resource"
     )]
     string_resources: Vec<StringResource>,
+    #[structopt(
+        long = "model",
+        parse(try_from_str),
+        help = "Provide the file for a model the Rune expects the host to run \
+                (\"NAME=path\")"
+    )]
+    models: Vec<FileResource>,
+    #[structopt(
+        long = "proc-block",
+        parse(try_from_str),
+        help = "Provide the WebAssembly module for a proc block the Rune \
+                expects the host to run (\"NAME=path\")"
+    )]
+    proc_blocks: Vec<FileResource>,
+    #[structopt(
+        long = "model-variant",
+        parse(try_from_str),
+        help = "Choose which variant of a model should be loaded \
+                (\"NAME=variant\")"
+    )]
+    model_variants: Vec<StringResource>,
+    #[structopt(
+        long,
+        help = "Print how long each stage of the pipeline took to stderr"
+    )]
+    timing: bool,
+    #[structopt(
+        long,
+        help = "The number of threads to use when decoding inputs (defaults \
+                to the number of CPUs). Models are always run one at a time"
+    )]
+    threads: Option<usize>,
+    #[structopt(
+        long,
+        parse(from_os_str),
+        conflicts_with = "replay",
+        help = "Record all data delivered by capabilities to a trace file"
+    )]
+    record: Option<PathBuf>,
+    #[structopt(
+        long,
+        parse(from_os_str),
+        help = "Replay capability data from a trace file created with \
+                \"--record\" instead of reading inputs"
+    )]
+    replay: Option<PathBuf>,
+    #[structopt(
+        long,
+        help = "The MQTT broker (\"host\" or \"host:port\") that MQTT \
+                outputs are published to"
+    )]
+    mqtt_broker: Option<String>,
+    #[structopt(
+        long,
+        default_value = "rune/outputs/{id}",
+        help = "The topic MQTT outputs are published to, where \"{id}\" is \
+                replaced with the output's ID"
+    )]
+    mqtt_topic: String,
+    #[structopt(
+        long,
+        default_value = "0",
+        possible_values = &["0", "1", "2"],
+        help = "The quality of service level to use when publishing to MQTT"
+    )]
+    mqtt_qos: u8,
+    #[structopt(
+        long,
+        help = "Stream each result to WebSocket clients connected to this \
+                address (e.g. \"127.0.0.1:9001\")"
+    )]
+    websocket: Option<SocketAddr>,
+    #[structopt(
+        long,
+        help = "The UUID of the BLE characteristic that BLE outputs are sent \
+                to"
+    )]
+    ble_characteristic: Option<Uuid>,
+    #[structopt(
+        long,
+        help = "Only send BLE outputs to the device advertising this name"
+    )]
+    ble_device: Option<String>,
+    #[structopt(
+        long,
+        parse(from_os_str),
+        help = "Append each result to this file"
+    )]
+    output_file: Option<PathBuf>,
+    #[structopt(
+        long,
+        possible_values = &["jsonl", "csv", "raw"],
+        help = "How results are written to \"--output-file\" (guessed from \
+                its extension by default)"
+    )]
+    output_format: Option<FileFormat>,
+    #[structopt(
+        long,
+        help = "Rotate \"--output-file\" once it would grow past this many \
+                bytes"
+    )]
+    rotate_bytes: Option<u64>,
+    #[structopt(
+        long,
+        default_value = "5",
+        help = "How many rotated output files to keep"
+    )]
+    keep_files: usize,
+    #[structopt(
+        long,
+        parse(from_os_str),
+        help = "Save each output tensor that looks like an image to this \
+                directory"
+    )]
+    save_images: Option<PathBuf>,
+    #[structopt(
+        long,
+        default_value = "png",
+        possible_values = &["png", "jpeg"],
+        help = "The format used when saving images"
+    )]
+    image_format: ImageFormat,
+    #[structopt(
+        long,
+        help = "Stretch saved images to cover the full brightness range \
+                (useful for segmentation masks)"
+    )]
+    normalize_images: bool,
+    #[structopt(
+        long,
+        help = "Deliver capability data at each capability's sample rate, \
+                like real hardware, instead of as fast as possible"
+    )]
+    realtime: bool,
+    #[structopt(
+        long,
+        help = "Run each model once with dummy inputs before starting, so the \
+                first prediction isn't slowed down by one-off setup costs"
+    )]
+    warm_up: bool,
+    #[structopt(
+        long,
+        help = "Write each result's tensors to stdout as raw bytes instead \
+                of JSON, so they can be read by another Rune (e.g. \
+                `rune run detector.rune --pipe | rune run classifier.rune \
+                --raw -`)"
+    )]
+    pipe: bool,
+    #[structopt(
+        long,
+        parse(from_os_str),
+        help = "A directory of images, audio clips, or CSV files to feed to \
+                the Rune one at a time (use with --loop to evaluate the whole \
+                dataset)"
+    )]
+    dataset: Option<PathBuf>,
+    #[structopt(
+        long = "loop",
+        help = "Keep running the Rune until it fails or is interrupted"
+    )]
+    repeat: bool,
+    #[structopt(
+        long,
+        help = "Reset any state kept by the Rune's proc blocks (e.g. rolling \
+                windows) before each dataset item or iteration of --loop, so \
+                every run is independent of the ones before it"
+    )]
+    reset_state: bool,
     #[structopt(help = "The Rune to run")]
     rune: PathBuf,
 }
 
 impl Run {
-    pub fn execute(self) -> Result<(), Error> {
+    pub fn execute(mut self) -> Result<(), Error> {
         log::info!("Running rune: {}", self.rune.display());
 
-        let rune = std::fs::read(&self.rune).with_context(|| {
-            format!("Unable to read \"{}\"", self.rune.display())
-        })?;
+        if let Some(threads) = self.threads {
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build_global()
+                .context("Unable to initialize the thread pool")?;
+        }
 
-        let mut runtime: Runtime = self
-            .load_runtime(&rune)
-            .context("Unable to load the Runtime")?;
+        let mut runtime = self.load()?;
+        runtime.pace_capabilities(self.realtime);
+
+        for meta in runtime.capabilities().values() {
+            let args = Arguments(meta.arguments.clone());
+            builtins::validate_arguments(&meta.kind, &args)?;
+        }
+
+        let declared = self.declared_capabilities(runtime.capabilities())?;
+        self.bind_named_inputs(&declared)?;
+
+        if self.replay.is_none() && self.dataset.is_none() {
+            self.check_inputs_are_bound(&declared)?;
+        }
 
         self.load_resources(runtime.resources())?;
 
-        let caps = runtime.capabilities().clone();
-        log::debug!("Loading capabilities {:?}", caps);
-        runtime.input_tensors().extend(self.load_inputs(caps)?);
+        if let Some(path) = &self.record {
+            let f = File::create(path).with_context(|| {
+                format!("Unable to create \"{}\"", path.display())
+            })?;
+            runtime.record_capabilities(BufWriter::new(f));
+        }
 
-        runtime.predict().context("Prediction failed")?;
+        if let Some(path) = &self.replay {
+            let f = File::open(path).with_context(|| {
+                format!("Unable to open \"{}\"", path.display())
+            })?;
+            runtime.replay_capabilities(BufReader::new(f));
+        }
 
-        let outputs = runtime.output_tensors();
+        let mut devices = LiveDevices::open(&self.live, &self.raw)?;
+        let mut mqtt = self.mqtt_sink(runtime.outputs())?;
+        let mut ble = self.ble_sink(runtime.outputs())?;
+        let websocket = self
+            .websocket
+            .map(WebSocketSink::bind)
+            .transpose()
+            .context("Unable to start the WebSocket server")?;
+        let mut output_file = self.file_sink()?;
+        let mut pipe = self.pipe.then(|| PipeSink::new(std::io::stdout()));
+        let mut images = self
+            .save_images
+            .as_ref()
+            .map(|directory| {
+                ImageSink::new(ImageSinkConfig {
+                    format: self.image_format,
+                    normalize: self.normalize_images,
+                    ..ImageSinkConfig::new(directory)
+                })
+            })
+            .transpose()?;
 
-        let serialized = serde_json::to_string(outputs)
-            .context("Unable to serialize the output tensors to JSON")?;
-        println!("{}", serialized);
+        let mut dataset = self.dataset.as_ref().map(Dataset::open).transpose()?;
+        let mut first_run = true;
 
-        Ok(())
+        loop {
+            let item = match &mut dataset {
+                Some(dataset) => match dataset.next() {
+                    Some(item) => {
+                        log::info!(
+                            "Evaluating \"{}\" ({} remaining)",
+                            item.path.display(),
+                            dataset.remaining()
+                        );
+                        Some(item)
+                    },
+                    None => {
+                        log::info!("Reached the end of the dataset");
+                        return Ok(());
+                    },
+                },
+                None => None,
+            };
+
+            if self.reset_state && !first_run {
+                log::debug!("Resetting the Rune's state");
+                runtime.reset()?;
+            }
+            first_run = false;
+
+            if self.replay.is_none() {
+                let caps = runtime.capabilities().clone();
+                log::debug!("Loading capabilities {:?}", caps);
+                let live = devices.capture()?;
+                let inputs = self.load_inputs(caps, &live, item.as_ref())?;
+                runtime.input_tensors().extend(inputs);
+            }
+
+            runtime.predict().context("Prediction failed")?;
+
+            if self.timing {
+                eprintln!("{}", runtime.metrics());
+            }
+
+            let outputs = runtime.output_tensors();
+
+            if let Some(mqtt) = &mut mqtt {
+                for (id, meta) in runtime.outputs() {
+                    if meta.kind == "MQTT" {
+                        let tensors = outputs.get(id).map(|t| t.as_slice());
+                        mqtt.publish(*id, tensors.unwrap_or_default())?;
+                    }
+                }
+            }
+
+            if let Some(ble) = &mut ble {
+                for (id, meta) in runtime.outputs() {
+                    if meta.kind == "BLE" {
+                        let tensors = outputs.get(id).map(|t| t.as_slice());
+                        ble.publish(tensors.unwrap_or_default())?;
+                    }
+                }
+            }
+
+            let serialized = serde_json::to_string(outputs)
+                .context("Unable to serialize the output tensors to JSON")?;
+
+            if let Some(file) = &mut output_file {
+                file.write(outputs).with_context(|| {
+                    format!(
+                        "Unable to save results to \"{}\"",
+                        file.path().display()
+                    )
+                })?;
+            }
+
+            if let Some(images) = &mut images {
+                for path in images.write(outputs)? {
+                    log::info!("Saved \"{}\"", path.display());
+                }
+            }
+
+            if let Some(websocket) = &websocket {
+                websocket.broadcast(&serialized);
+            }
+
+            match &mut pipe {
+                Some(pipe) => pipe.write(outputs)?,
+                None => println!("{}", serialized),
+            }
+
+            if !self.repeat {
+                return Ok(());
+            }
+        }
     }
 
-    fn load_inputs(
-        self,
-        caps: HashMap<u32, NodeMetadata>,
-    ) -> Result<HashMap<u32, hotg_rune_runtime::Tensor>, Error> {
-        let mut inputs = HashMap::new();
+    /// Connect to the BLE device if the Rune has any BLE outputs.
+    fn ble_sink(
+        &self,
+        outputs: &HashMap<u32, NodeMetadata>,
+    ) -> Result<Option<BleSink<BtleplugNotifier>>, Error> {
+        if !outputs.values().any(|meta| meta.kind == "BLE") {
+            return Ok(None);
+        }
 
-        for (id, metadata) in caps {
-            log::debug!("Loading {:?}", metadata);
-            let NodeMetadata {
-                kind, arguments, ..
-            } = metadata;
-            let args = Arguments(arguments);
+        let characteristic = self.ble_characteristic.context(
+            "The Rune has a BLE output, but no characteristic was provided \
+             with \"--ble-characteristic\"",
+        )?;
 
-            let tensor = self.load_input(&kind, &args).with_context(|| {
-                format!("Unable to load the \"{}\" input", kind)
-            })?;
+        let config = BleConfig {
+            device_name: self.ble_device.clone(),
+            ..BleConfig::new(characteristic)
+        };
+
+        let notifier = BtleplugNotifier::connect(&config)
+            .context("Unable to connect to the BLE device")?;
+
+        Ok(Some(BleSink::new(notifier)))
+    }
+
+    fn file_sink(&self) -> Result<Option<FileSink>, Error> {
+        let path = match &self.output_file {
+            Some(path) => path,
+            None => return Ok(None),
+        };
+
+        let defaults = FileSinkConfig::new(path);
+        let config = FileSinkConfig {
+            format: self.output_format.unwrap_or(defaults.format),
+            max_bytes: self.rotate_bytes,
+            max_files: self.keep_files,
+            ..defaults
+        };
 
-            inputs.insert(id, tensor);
+        FileSink::open(config).map(Some)
+    }
+
+    /// Connect to the MQTT broker if the Rune has any MQTT outputs.
+    fn mqtt_sink(
+        &self,
+        outputs: &HashMap<u32, NodeMetadata>,
+    ) -> Result<Option<MqttSink>, Error> {
+        if !outputs.values().any(|meta| meta.kind == "MQTT") {
+            return Ok(None);
         }
 
-        Ok(inputs)
+        let broker = self.mqtt_broker.as_deref().context(
+            "The Rune has an MQTT output, but no broker was provided with \
+             \"--mqtt-broker\"",
+        )?;
+
+        let config = MqttConfig {
+            topic: self.mqtt_topic.clone(),
+            qos: self.mqtt_qos,
+            ..MqttConfig::new(broker)
+        };
+
+        MqttSink::connect(config)
+            .with_context(|| format!("Unable to connect to \"{}\"", broker))
+            .map(Some)
+    }
+
+    /// Load the input for each capability, decoding them in parallel on
+    /// rayon's thread pool.
+    ///
+    /// Only the decoding is parallel. The Rune calls back into the host for
+    /// each model invocation during [`Runtime::predict()`], so inference is
+    /// sequential.
+    fn load_inputs(
+        &self,
+        caps: HashMap<u32, NodeMetadata>,
+        live: &LiveInputs,
+        item: Option<&DatasetItem>,
+    ) -> Result<HashMap<u32, hotg_rune_runtime::Tensor>, Error> {
+        caps.into_par_iter()
+            .map(|(id, metadata)| {
+                log::debug!("Loading {:?}", metadata);
+                let NodeMetadata {
+                    kind, arguments, ..
+                } = metadata;
+                let args = Arguments(arguments);
+
+                let tensor = builtins::batch(&args, |args| {
+                    self.load_input(&kind, args, live, item)
+                })
+                .with_context(|| {
+                    format!("Unable to load the \"{}\" input", kind)
+                })?;
+
+                Ok((id, tensor))
+            })
+            .collect()
     }
 
     fn load_input(
         &self,
         kind: &str,
         args: &Arguments,
+        live: &LiveInputs,
+        item: Option<&DatasetItem>,
     ) -> Result<hotg_rune_runtime::Tensor, Error> {
+        if let Some(item) = item.filter(|item| item.kind.capability() == kind) {
+            return item.load(args);
+        }
+
         match kind {
-            "IMAGE" => builtins::source(&self.image, args)
-                .and_then(|path| {
-                    image::open(path).with_context(|| {
-                        format!("Unable to read \"{}\"", path.display())
-                    })
+            "IMAGE" if !live.images.is_empty() => {
+                let args = &without_batch_index(args);
+                builtins::load_sources(&live.images, args, |img| {
+                    builtins::image(args, img)
                 })
-                .and_then(|img| builtins::image(args, &img)),
+            },
+
+            "IMAGE" => builtins::load_sources(&self.image, args, |path| {
+                let img = image::open(path).with_context(|| {
+                    format!("Unable to read \"{}\"", path.display())
+                })?;
+                builtins::image(args, &img)
+            }),
+
+            "VIDEO" => builtins::load_sources(&self.video, args, |path| {
+                let max_frames = match args.0.get("frames") {
+                    Some(_) => Some(args.parse("frames")?),
+                    None => None,
+                };
+                let clip = VideoClip::from_file(path, max_frames)?;
+                builtins::video(args, &clip)
+            }),
+
+            "SOUND" if !live.recordings.is_empty() => {
+                let args = &without_batch_index(args);
+                builtins::load_sources(&live.recordings, args, |recording| {
+                    let hz = args.parse("hz")?;
+                    let ms = args.parse("sample_duration_ms")?;
+                    let clip = recording.clip(hz, Duration::from_millis(ms))?;
+                    builtins::sound(args, &clip)
+                })
+            },
+
+            "SOUND" => builtins::load_sources(&self.sound, args, |path| {
+                let audio = AudioClip::from_file(path)?;
+                builtins::sound(args, &audio)
+            }),
+
+            "ACCEL" => {
+                builtins::load_sources(&self.accelerometer, args, |path| {
+                    let samples = AccelerometerSamples::from_file(path)
+                        .with_context(|| {
+                            format!("Unable to read \"{}\"", path.display())
+                        })?;
+                    builtins::accelerometer(args, &samples)
+                })
+            },
+
+            "RAW" => {
+                let streaming =
+                    self.raw.iter().any(|path| live.stream(path).is_some());
+                let args = &match streaming {
+                    true => without_batch_index(args),
+                    false => args.clone(),
+                };
 
-            "SOUND" => builtins::source(&self.sound, args)
-                .and_then(|path| AudioClip::from_wav_file(path))
-                .and_then(|audio| builtins::sound(args, &audio)),
+                builtins::load_sources(&self.raw, args, |path| {
+                    if let Some(stream) = live.stream(path) {
+                        let length: usize = args.parse("length").context(
+                            "The \"length\" argument is required when \
+                             streaming RAW data",
+                        )?;
+                        let data = stream.lock().unwrap().read_chunk(length)?;
+                        return builtins::raw(args, &data);
+                    }
 
-            "ACCEL" => builtins::source(&self.accelerometer, args)
-                .and_then(|path| {
-                    AccelerometerSamples::from_file(path).with_context(|| {
+                    let data = std::fs::read(path).with_context(|| {
                         format!("Unable to read \"{}\"", path.display())
-                    })
+                    })?;
+                    builtins::raw(args, &data)
                 })
-                .and_then(|samples| {
-                    builtins::accelerometer(args, dbg!(&samples))
-                }),
+            },
 
-            "RAW" => builtins::source(&self.raw, args)
-                .and_then(|path| {
-                    std::fs::read(path).with_context(|| {
+            "GYRO" => builtins::load_sources(&self.gyroscope, args, |path| {
+                let samples = GyroscopeSamples::from_file(path)
+                    .with_context(|| {
                         format!("Unable to read \"{}\"", path.display())
-                    })
+                    })?;
+                builtins::gyroscope(args, &samples)
+            }),
+
+            "GPS" => builtins::load_sources(&self.gps, args, |path| {
+                let track = GpsTrack::from_file(path)?;
+                builtins::gps(args, &track)
+            }),
+
+            "THERMOMETER" => {
+                builtins::load_sources(&self.thermometer, args, |path| {
+                    let readings = SensorReadings::from_file(path)?;
+                    builtins::thermometer(args, &readings)
+                })
+            },
+
+            "BATTERY" if self.battery.is_empty() => {
+                static STARTED: Lazy<Instant> = Lazy::new(Instant::now);
+                let reading =
+                    SimulatedBattery::default().reading(STARTED.elapsed());
+                log::debug!("Using a simulated battery, {:?}", reading);
+                Ok(builtins::battery(args, reading))
+            },
+
+            "BATTERY" => builtins::load_sources(&self.battery, args, |path| {
+                let readings = SensorReadings::from_file(path)?;
+                let reading = BatteryReading::from_readings(&readings)?;
+                Ok(builtins::battery(args, reading))
+            }),
+
+            "BAROMETER" => {
+                builtins::load_sources(&self.barometer, args, |path| {
+                    let readings = SensorReadings::from_file(path)?;
+                    builtins::barometer(args, &readings)
                 })
-                .and_then(|data| builtins::raw(args, &data)),
+            },
+
+            "PROXIMITY" => {
+                builtins::load_sources(&self.proximity, args, |path| {
+                    let readings = SensorReadings::from_file(path)?;
+                    builtins::proximity(args, &readings)
+                })
+            },
+
+            "LIGHT" => builtins::load_sources(&self.light, args, |path| {
+                let readings = SensorReadings::from_file(path)?;
+                builtins::light(args, &readings)
+            }),
+
+            "TENSOR" => builtins::load_sources(&self.tensor, args, |path| {
+                let arrays = NumpyArrays::from_file(path)?;
+                builtins::tensor(args, &arrays)
+            }),
+
+            "TABULAR" => builtins::load_sources(&self.tabular, args, |path| {
+                let table = Table::from_file(path)?;
+                builtins::tabular(args, &table)
+            }),
+
+            "SIGNAL" => builtins::signal(args),
 
             "RAND" => match self.random {
                 Some(seed) => builtins::seeded_random(args, seed),
@@ -165,14 +776,277 @@ impl Run {
         }
     }
 
+    /// Figure out which capabilities the Rune reads from, using the names from
+    /// its Runefile when they were embedded in the Rune.
+    fn declared_capabilities(
+        &self,
+        caps: &HashMap<u32, NodeMetadata>,
+    ) -> Result<Vec<DeclaredCapability>, Error> {
+        let rune = std::fs::read(&self.rune).with_context(|| {
+            format!("Unable to read \"{}\"", self.rune.display())
+        })?;
+
+        let graph = match Metadata::from_wasm_binary(&rune) {
+            Ok(meta) => meta.rune,
+            Err(e) => {
+                log::warn!("Unable to read the Rune's custom sections: {}", e);
+                None
+            },
+        };
+
+        let mut declared: Vec<_> = match graph {
+            Some(graph) => graph
+                .capabilities
+                .into_iter()
+                .map(|(name, summary)| {
+                    let kind = match &summary.kind {
+                        SourceKind::Other(kind) => kind.clone(),
+                        other => other
+                            .as_capability_name()
+                            .unwrap_or_default()
+                            .to_string(),
+                    };
+                    let source = match summary.args.get("source") {
+                        Some(ResourceOrString::String(s)) => Some(s.as_str()),
+                        _ => None,
+                    };
+
+                    DeclaredCapability {
+                        name: Some(name.to_string()),
+                        kind,
+                        sources: source_indices(source),
+                    }
+                })
+                .collect(),
+            None => caps
+                .values()
+                .map(|meta| DeclaredCapability {
+                    name: None,
+                    kind: meta.kind.clone(),
+                    sources: source_indices(
+                        meta.arguments.get("source").map(String::as_str),
+                    ),
+                })
+                .collect(),
+        };
+
+        declared.sort_by(|a, b| (&a.name, &a.kind).cmp(&(&b.name, &b.kind)));
+
+        Ok(declared)
+    }
+
+    /// Add each `--input NAME=path` to the files for its capability's kind,
+    /// at the position the capability reads from.
+    fn bind_named_inputs(
+        &mut self,
+        declared: &[DeclaredCapability],
+    ) -> Result<(), Error> {
+        let mut bindings = Vec::new();
+
+        for input in &self.inputs {
+            let cap = declared
+                .iter()
+                .find(|cap| cap.name.as_deref() == Some(input.name.as_str()))
+                .with_context(|| unknown_capability(&input.name, declared))?;
+
+            let times_bound = self
+                .inputs
+                .iter()
+                .filter(|other| other.name == input.name)
+                .count();
+            if times_bound > 1 {
+                anyhow::bail!(
+                    "More than one file was provided for the \"{}\" capability",
+                    input.name
+                );
+            }
+
+            let index = match cap.sources.as_slice() {
+                [index] => *index,
+                _ => anyhow::bail!(
+                    "The \"{}\" capability reads from several sources, so its \
+                     files need to be provided with \"{}\"",
+                    input.name,
+                    input_flag(&cap.kind).unwrap_or("--input"),
+                ),
+            };
+
+            if let Some((_, other, ..)) = bindings
+                .iter()
+                .find(|(i, _, kind, _)| *i == index && *kind == cap.kind)
+            {
+                anyhow::bail!(
+                    "The \"{}\" and \"{}\" capabilities both read from {} \
+                     source {}, so they can't be given different files",
+                    other,
+                    input.name,
+                    cap.kind,
+                    index
+                );
+            }
+
+            bindings.push((
+                index,
+                input.name.clone(),
+                cap.kind.clone(),
+                input.path.clone(),
+            ));
+        }
+
+        // Insert from the lowest index up so earlier insertions don't shift
+        // a later capability's file out of position
+        bindings.sort_by_key(|(index, ..)| *index);
+
+        for (index, name, kind, path) in bindings {
+            let files = self.files_mut(&kind).with_context(|| {
+                format!(
+                    "Files can't be provided for the \"{}\" capability \
+                     because {} capabilities don't read from files",
+                    name, kind
+                )
+            })?;
+
+            log::debug!("Using \"{}\" for \"{}\"", path.display(), name);
+            let index = index.min(files.len());
+            files.insert(index, path);
+        }
+
+        Ok(())
+    }
+
+    /// Make sure every capability has something to read from before we
+    /// start, so the user finds out about all the missing inputs at once.
+    fn check_inputs_are_bound(
+        &self,
+        declared: &[DeclaredCapability],
+    ) -> Result<(), Error> {
+        let unbound: Vec<_> = declared
+            .iter()
+            .filter(|cap| !self.has_live_device(&cap.kind))
+            .filter(|cap| match self.files(&cap.kind) {
+                Some(files) => cap.sources.iter().any(|&i| i >= files.len()),
+                // Capabilities that don't read from files generate their own
+                // data or are reported when they are loaded
+                None => false,
+            })
+            .collect();
+
+        if unbound.is_empty() {
+            return Ok(());
+        }
+
+        let mut msg = String::from("No input was provided for ");
+        if unbound.len() == 1 {
+            msg.push_str("this capability:");
+        } else {
+            msg.push_str("these capabilities:");
+        }
+
+        for cap in unbound {
+            msg.push_str("\n  - ");
+            msg.push_str(&describe_unbound(cap));
+        }
+
+        Err(Error::msg(msg))
+    }
+
+    fn has_live_device(&self, kind: &str) -> bool {
+        self.live.iter().any(|source| match source {
+            LiveSource::Camera { .. } => kind == "IMAGE",
+            LiveSource::Microphone { .. } => kind == "SOUND",
+        })
+    }
+
+    /// The files that were provided for a kind of capability.
+    fn files(&self, kind: &str) -> Option<&[PathBuf]> {
+        let files = match kind {
+            "ACCEL" => &self.accelerometer,
+            "GYRO" => &self.gyroscope,
+            "SOUND" => &self.sound,
+            "IMAGE" => &self.image,
+            "VIDEO" => &self.video,
+            "RAW" => &self.raw,
+            "GPS" => &self.gps,
+            "THERMOMETER" => &self.thermometer,
+            "BAROMETER" => &self.barometer,
+            "PROXIMITY" => &self.proximity,
+            "LIGHT" => &self.light,
+            "TENSOR" => &self.tensor,
+            "TABULAR" => &self.tabular,
+            // Note: BATTERY falls back to a simulated battery
+            _ => return None,
+        };
+
+        Some(files)
+    }
+
+    fn files_mut(&mut self, kind: &str) -> Option<&mut Vec<PathBuf>> {
+        let files = match kind {
+            "ACCEL" => &mut self.accelerometer,
+            "GYRO" => &mut self.gyroscope,
+            "SOUND" => &mut self.sound,
+            "IMAGE" => &mut self.image,
+            "VIDEO" => &mut self.video,
+            "RAW" => &mut self.raw,
+            "GPS" => &mut self.gps,
+            "THERMOMETER" => &mut self.thermometer,
+            "BATTERY" => &mut self.battery,
+            "BAROMETER" => &mut self.barometer,
+            "PROXIMITY" => &mut self.proximity,
+            "LIGHT" => &mut self.light,
+            "TENSOR" => &mut self.tensor,
+            "TABULAR" => &mut self.tabular,
+            _ => return None,
+        };
+
+        Some(files)
+    }
+
+    /// Read the Rune from disk and load it, providing any models the host
+    /// is expected to run and warming them up if requested.
+    pub(crate) fn load(&self) -> Result<Runtime, Error> {
+        let rune = std::fs::read(&self.rune).with_context(|| {
+            format!("Unable to read \"{}\"", self.rune.display())
+        })?;
+
+        let mut runtime: Runtime = self
+            .load_runtime(&rune)
+            .context("Unable to load the Runtime")?;
+
+        runtime.set_logger(|record| log::logger().log(record));
+        runtime.set_max_log_level(log::max_level());
+        self.load_models(runtime.models())?;
+        self.load_proc_blocks(runtime.proc_blocks())?;
+
+        if self.warm_up {
+            runtime.warm_up().context("Unable to warm up the models")?;
+        }
+
+        Ok(runtime)
+    }
+
     pub(crate) fn load_runtime(
         &self,
         rune: &[u8],
     ) -> Result<Runtime, LoadError> {
-        match self.engine {
-            Engine::Wasm3 => Runtime::wasm3(rune),
-            Engine::Wasmer => Runtime::wasmer(rune),
+        if !self.delegates.is_empty() {
+            // The default model handler reads its delegates from the
+            // environment when the Rune loads its models
+            let delegates: Vec<_> =
+                self.delegates.iter().map(|d| d.to_string()).collect();
+            std::env::set_var(TFLITE_DELEGATES_ENV, delegates.join(","));
+        }
+
+        if !self.model_variants.is_empty() {
+            let variants: Vec<_> = self
+                .model_variants
+                .iter()
+                .map(|v| format!("{}={}", v.name, v.value))
+                .collect();
+            std::env::set_var(MODEL_VARIANTS_ENV, variants.join(","));
         }
+
+        self.engine.load(rune)
     }
 
     pub(crate) fn load_resources(
@@ -192,6 +1066,136 @@ impl Run {
 
         Ok(())
     }
+
+    /// Read the files for any models the Rune expects the host to run.
+    pub(crate) fn load_models(
+        &self,
+        models: &mut HashMap<String, Vec<u8>>,
+    ) -> Result<(), Error> {
+        for m in &self.models {
+            let value = std::fs::read(&m.path).with_context(|| {
+                format!("Unable to read \"{}\"", m.path.display())
+            })?;
+            models.insert(m.name.clone(), value);
+        }
+
+        Ok(())
+    }
+
+    /// Read the WebAssembly modules for any proc blocks the Rune expects the
+    /// host to run.
+    pub(crate) fn load_proc_blocks(
+        &self,
+        proc_blocks: &mut HashMap<String, Vec<u8>>,
+    ) -> Result<(), Error> {
+        for p in &self.proc_blocks {
+            let value = std::fs::read(&p.path).with_context(|| {
+                format!("Unable to read \"{}\"", p.path.display())
+            })?;
+            proc_blocks.insert(p.name.clone(), value);
+        }
+
+        Ok(())
+    }
+}
+
+/// A capability the Rune reads its input from.
+#[derive(Debug, Clone, PartialEq)]
+struct DeclaredCapability {
+    /// The capability's name in the Runefile, if it was embedded in the Rune.
+    name: Option<String>,
+    kind: String,
+    /// Which of the files provided for this kind of capability it reads.
+    sources: Vec<usize>,
+}
+
+/// Live sources provide their next chunk for each item in a batch, so unlike
+/// files they shouldn't be stepped through using the `"batch_index"`.
+fn without_batch_index(args: &Arguments) -> Arguments {
+    let mut args = args.clone();
+    args.0.remove("batch_index");
+    args
+}
+
+/// Parse a capability's `"source"` argument, defaulting to the first source.
+fn source_indices(source: Option<&str>) -> Vec<usize> {
+    let indices: Vec<usize> = source
+        .unwrap_or_default()
+        .split(',')
+        .filter_map(|index| index.trim().parse().ok())
+        .collect();
+
+    if indices.is_empty() {
+        vec![0]
+    } else {
+        indices
+    }
+}
+
+/// The flag used to provide files for a kind of capability.
+fn input_flag(kind: &str) -> Option<&'static str> {
+    match kind {
+        "ACCEL" => Some("--accelerometer"),
+        "GYRO" => Some("--gyroscope"),
+        "SOUND" => Some("--sound"),
+        "IMAGE" => Some("--image"),
+        "VIDEO" => Some("--video"),
+        "RAW" => Some("--raw"),
+        "GPS" => Some("--gps"),
+        "THERMOMETER" => Some("--thermometer"),
+        "BATTERY" => Some("--battery"),
+        "BAROMETER" => Some("--barometer"),
+        "PROXIMITY" => Some("--proximity"),
+        "LIGHT" => Some("--light"),
+        "TENSOR" => Some("--tensor"),
+        "TABULAR" => Some("--tabular"),
+        _ => None,
+    }
+}
+
+fn describe_unbound(cap: &DeclaredCapability) -> String {
+    let flag = input_flag(&cap.kind).unwrap_or("--input");
+    let sources: Vec<_> = cap.sources.iter().map(|i| i.to_string()).collect();
+    let sources = sources.join(", ");
+
+    match &cap.name {
+        Some(name) if cap.sources.len() == 1 => format!(
+            "\"{}\" ({}, source {}): use \"--input {}=<path>\" or \"{} \
+             <path>\"",
+            name, cap.kind, sources, name, flag
+        ),
+        Some(name) => format!(
+            "\"{}\" ({}, sources {}): use \"{} <path>\" once for each source",
+            name, cap.kind, sources, flag
+        ),
+        None => format!(
+            "{} (source {}): use \"{} <path>\"",
+            cap.kind, sources, flag
+        ),
+    }
+}
+
+fn unknown_capability(name: &str, declared: &[DeclaredCapability]) -> String {
+    let names: Vec<_> = declared
+        .iter()
+        .filter_map(|cap| cap.name.as_deref())
+        .map(|name| format!("\"{}\"", name))
+        .collect();
+
+    if names.is_empty() {
+        format!(
+            "Unable to find the \"{}\" capability because the Rune doesn't \
+             say what its capabilities are called",
+            name
+        )
+    } else {
+        format!(
+            "The Rune doesn't have a capability called \"{}\" (expected one \
+             of {})",
+            name,
+            names.join(", ")
+        )
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -234,13 +1238,13 @@ impl FromStr for StringResource {
 
 fn parse_key_value_pair(s: &str) -> Result<(&str, &str), Error> {
     static PATTERN: Lazy<Regex> =
-        Lazy::new(|| Regex::new(r"([a-zA-Z_][a-zA-Z0-9]*)=(.*)").unwrap());
+        Lazy::new(|| Regex::new(r"^([a-zA-Z_][a-zA-Z0-9_-]*)=(.*)$").unwrap());
 
     let captures = PATTERN
         .captures(s)
         .context("Expected a resource in the form \"NAME=value\"")?;
-    let key = captures.get(0).unwrap().as_str();
-    let value = captures.get(1).unwrap().as_str();
+    let key = captures.get(1).unwrap().as_str();
+    let value = captures.get(2).unwrap().as_str();
 
     Ok((key, value))
 }
@@ -249,7 +1253,139 @@ fn parse_key_value_pair(s: &str) -> Result<(&str, &str), Error> {
     Debug, Copy, Clone, PartialEq, strum::EnumVariantNames, strum::EnumString,
 )]
 #[strum(serialize_all = "kebab-case")]
-enum Engine {
+pub(crate) enum Engine {
     Wasm3,
     Wasmer,
 }
+
+impl Engine {
+    pub(crate) fn load(self, rune: &[u8]) -> Result<Runtime, LoadError> {
+        match self {
+            Engine::Wasm3 => Runtime::wasm3(rune),
+            Engine::Wasmer => Runtime::wasmer(rune),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(args: &[&str]) -> Run {
+        // Note: the rune goes first because "--image" and friends take
+        // several values
+        let args = ["run", "example.rune"].iter().chain(args).copied();
+
+        Run::from_iter_safe(args).unwrap()
+    }
+
+    fn capability(
+        name: &str,
+        kind: &str,
+        sources: &[usize],
+    ) -> DeclaredCapability {
+        DeclaredCapability {
+            name: Some(name.to_string()),
+            kind: kind.to_string(),
+            sources: sources.to_vec(),
+        }
+    }
+
+    #[test]
+    fn parse_a_named_input() {
+        let got: FileResource = "front_camera=photo.png".parse().unwrap();
+
+        assert_eq!(
+            got,
+            FileResource {
+                name: "front_camera".to_string(),
+                path: PathBuf::from("photo.png"),
+            }
+        );
+    }
+
+    #[test]
+    fn named_inputs_are_inserted_at_their_source_index() {
+        let mut run = run(&[
+            "--image",
+            "other.png",
+            "--input",
+            "right=right.png",
+            "--input",
+            "left=left.png",
+        ]);
+        let declared = vec![
+            capability("left", "IMAGE", &[0]),
+            capability("right", "IMAGE", &[1]),
+        ];
+
+        run.bind_named_inputs(&declared).unwrap();
+
+        let should_be: Vec<PathBuf> =
+            vec!["left.png".into(), "right.png".into(), "other.png".into()];
+        assert_eq!(run.image, should_be);
+    }
+
+    #[test]
+    fn binding_an_unknown_capability_lists_the_known_ones() {
+        let mut run = run(&["--input", "camera=photo.png"]);
+        let declared = vec![capability("audio", "SOUND", &[0])];
+
+        let err = run.bind_named_inputs(&declared).unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "The Rune doesn't have a capability called \"camera\" (expected \
+             one of \"audio\")"
+        );
+    }
+
+    #[test]
+    fn capabilities_sharing_a_source_cant_both_be_bound() {
+        let mut run =
+            run(&["--input", "left=left.png", "--input", "right=right.png"]);
+        let declared = vec![
+            capability("left", "IMAGE", &[0]),
+            capability("right", "IMAGE", &[0]),
+            // the same index for a different kind is fine
+            capability("audio", "SOUND", &[0]),
+        ];
+
+        let err = run.bind_named_inputs(&declared).unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "The \"left\" and \"right\" capabilities both read from IMAGE \
+             source 0, so they can't be given different files"
+        );
+    }
+
+    #[test]
+    fn every_unbound_capability_is_reported() {
+        let run = run(&["--sound", "clip.wav"]);
+        let declared = vec![
+            capability("audio", "SOUND", &[0]),
+            capability("photo", "IMAGE", &[0]),
+            capability("rand", "RAND", &[0]),
+            capability("stereo", "SOUND", &[0, 1]),
+        ];
+
+        let err = run.check_inputs_are_bound(&declared).unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "No input was provided for these capabilities:\n  - \"photo\" \
+             (IMAGE, source 0): use \"--input photo=<path>\" or \"--image \
+             <path>\"\n  - \"stereo\" (SOUND, sources 0, 1): use \"--sound \
+             <path>\" once for each source"
+        );
+    }
+
+    #[test]
+    fn live_devices_count_as_inputs() {
+        let run = run(&["--capability", "image:camera:0"]);
+        let declared = vec![capability("photo", "IMAGE", &[0])];
+
+        run.check_inputs_are_bound(&declared).unwrap();
+    }
+}
@@ -0,0 +1,295 @@
+use std::{
+    collections::HashMap, convert::TryFrom, net::SocketAddr, num::NonZeroUsize,
+};
+
+use anyhow::{Context, Error};
+use hotg_rune_runtime::{ElementType, Metrics, NodeMetadata, OutputTensor};
+use tonic::{transport::Server, Request, Response, Status};
+
+use crate::serve::{
+    nanoseconds,
+    worker::{Input, RuneHandle},
+    Runes,
+};
+
+pub(crate) mod proto {
+    tonic::include_proto!("rune.serve.v1");
+}
+
+use self::proto::rune_service_server::{RuneService, RuneServiceServer};
+
+/// Serve the gRPC API until the process receives a Ctrl-C.
+pub(crate) async fn serve(addr: SocketAddr, runes: Runes) -> Result<(), Error> {
+    log::info!("Serving the gRPC API on {}", addr);
+
+    Server::builder()
+        .add_service(RuneServiceServer::new(GrpcService { runes }))
+        .serve_with_shutdown(addr, async {
+            let _ = tokio::signal::ctrl_c().await;
+        })
+        .await
+        .with_context(|| format!("Unable to serve the gRPC API on {}", addr))
+}
+
+struct GrpcService {
+    runes: Runes,
+}
+
+impl GrpcService {
+    fn rune(&self, name: &str) -> Result<&RuneHandle, Status> {
+        self.runes.get(name).ok_or_else(|| {
+            Status::not_found(format!("There is no Rune called \"{}\"", name))
+        })
+    }
+}
+
+#[tonic::async_trait]
+impl RuneService for GrpcService {
+    async fn list_runes(
+        &self,
+        _request: Request<proto::ListRunesRequest>,
+    ) -> Result<Response<proto::ListRunesResponse>, Status> {
+        let runes = self
+            .runes
+            .values()
+            .map(|rune| proto::RuneInfo {
+                name: rune.name.clone(),
+                capabilities: nodes(&rune.capabilities),
+                outputs: nodes(&rune.outputs),
+            })
+            .collect();
+
+        Ok(Response::new(proto::ListRunesResponse { runes }))
+    }
+
+    async fn predict(
+        &self,
+        request: Request<proto::PredictRequest>,
+    ) -> Result<Response<proto::PredictResponse>, Status> {
+        let proto::PredictRequest { rune, inputs } = request.into_inner();
+        let rune = self.rune(&rune)?;
+
+        let mut parsed = HashMap::new();
+
+        for input in inputs {
+            let proto::Input {
+                capability_id,
+                value,
+            } = input;
+
+            let input = match value {
+                Some(proto::input::Value::Tensor(t)) => {
+                    Input::Tensor(tensor_from_proto(t)?)
+                },
+                Some(proto::input::Value::File(data)) => Input::File(data),
+                None => {
+                    return Err(Status::invalid_argument(format!(
+                        "No value was provided for capability {}",
+                        capability_id
+                    )))
+                },
+            };
+
+            parsed.insert(capability_id, input);
+        }
+
+        let prediction = rune
+            .predict(parsed)
+            .await
+            .map_err(|e| Status::internal(format!("{:#}", e)))?;
+
+        let outputs = prediction
+            .outputs
+            .into_iter()
+            .map(|(output_id, tensors)| proto::Output {
+                output_id,
+                tensors: tensors.into_iter().map(tensor_to_proto).collect(),
+            })
+            .collect();
+
+        Ok(Response::new(proto::PredictResponse {
+            outputs,
+            timing: Some(timing(&prediction.metrics)),
+        }))
+    }
+}
+
+fn nodes(nodes: &HashMap<u32, NodeMetadata>) -> Vec<proto::Node> {
+    nodes
+        .iter()
+        .map(|(&id, meta)| proto::Node {
+            id,
+            kind: meta.kind.clone(),
+            arguments: meta.arguments.clone(),
+        })
+        .collect()
+}
+
+fn tensor_from_proto(
+    tensor: proto::Tensor,
+) -> Result<hotg_rune_runtime::Tensor, Status> {
+    let proto::Tensor {
+        element_type,
+        dimensions,
+        data,
+        ..
+    } = tensor;
+
+    let element_type = match proto::ElementType::from_i32(element_type) {
+        Some(proto::ElementType::U8) => ElementType::U8,
+        Some(proto::ElementType::I8) => ElementType::I8,
+        Some(proto::ElementType::U16) => ElementType::U16,
+        Some(proto::ElementType::I16) => ElementType::I16,
+        Some(proto::ElementType::U32) => ElementType::U32,
+        Some(proto::ElementType::I32) => ElementType::I32,
+        Some(proto::ElementType::F32) => ElementType::F32,
+        Some(proto::ElementType::U64) => ElementType::U64,
+        Some(proto::ElementType::I64) => ElementType::I64,
+        Some(proto::ElementType::F64) => ElementType::F64,
+        _ => {
+            return Err(Status::invalid_argument(format!(
+                "Capabilities can't accept tensors with element type {}",
+                element_type
+            )))
+        },
+    };
+
+    let dimensions = dimensions
+        .into_iter()
+        .map(|d| {
+            usize::try_from(d)
+                .ok()
+                .and_then(NonZeroUsize::new)
+                .ok_or_else(|| {
+                    Status::invalid_argument("All dimensions must be non-zero")
+                })
+        })
+        .collect::<Result<Vec<_>, Status>>()?;
+
+    let num_elements: usize = dimensions.iter().map(|d| d.get()).product();
+    let expected_length = num_elements * element_type.byte_size();
+
+    if data.len() != expected_length {
+        return Err(Status::invalid_argument(format!(
+            "A {}{:?} tensor should take up {} bytes, but {} bytes were \
+             provided",
+            element_type,
+            dimensions,
+            expected_length,
+            data.len()
+        )));
+    }
+
+    Ok(hotg_rune_runtime::Tensor::new_raw(
+        element_type,
+        dimensions,
+        data,
+    ))
+}
+
+fn tensor_to_proto(tensor: OutputTensor) -> proto::Tensor {
+    match tensor {
+        OutputTensor::Tensor(t) => {
+            let element_type = match t.element_type() {
+                ElementType::U8 => proto::ElementType::U8,
+                ElementType::I8 => proto::ElementType::I8,
+                ElementType::U16 => proto::ElementType::U16,
+                ElementType::I16 => proto::ElementType::I16,
+                ElementType::U32 => proto::ElementType::U32,
+                ElementType::I32 => proto::ElementType::I32,
+                ElementType::F32 => proto::ElementType::F32,
+                ElementType::U64 => proto::ElementType::U64,
+                ElementType::I64 => proto::ElementType::I64,
+                ElementType::F64 => proto::ElementType::F64,
+            };
+
+            proto::Tensor {
+                element_type: element_type as i32,
+                dimensions: t
+                    .dimensions()
+                    .iter()
+                    .map(|d| d.get() as u32)
+                    .collect(),
+                data: t.into_buffer(),
+                strings: Vec::new(),
+                record: String::new(),
+            }
+        },
+        OutputTensor::StringTensor {
+            dimensions,
+            strings,
+        } => proto::Tensor {
+            element_type: proto::ElementType::Utf8 as i32,
+            dimensions: dimensions.into_iter().map(|d| d as u32).collect(),
+            data: Vec::new(),
+            strings,
+            record: String::new(),
+        },
+        OutputTensor::Record(record) => proto::Tensor {
+            element_type: proto::ElementType::Record as i32,
+            dimensions: vec![1],
+            data: Vec::new(),
+            strings: Vec::new(),
+            record: serde_json::to_string(&record)
+                .expect("Serializing a record to JSON should never fail"),
+        },
+    }
+}
+
+fn timing(metrics: &Metrics) -> proto::Timing {
+    proto::Timing {
+        total_ns: metrics.total.as_nanos() as u64,
+        capabilities_ns: nanoseconds(&metrics.capabilities),
+        models_ns: nanoseconds(&metrics.models),
+        outputs_ns: nanoseconds(&metrics.outputs),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_a_tensor() {
+        let tensor = proto::Tensor {
+            element_type: proto::ElementType::I16 as i32,
+            dimensions: vec![1, 2],
+            data: vec![1, 0, 2, 0],
+            strings: Vec::new(),
+            record: String::new(),
+        };
+
+        let got = tensor_from_proto(tensor).unwrap();
+
+        assert_eq!(got, hotg_rune_runtime::Tensor::new(&[1_i16, 2], &[1, 2]));
+    }
+
+    #[test]
+    fn tensors_with_the_wrong_length_are_rejected() {
+        let tensor = proto::Tensor {
+            element_type: proto::ElementType::F32 as i32,
+            dimensions: vec![2],
+            data: vec![0; 4],
+            strings: Vec::new(),
+            record: String::new(),
+        };
+
+        let err = tensor_from_proto(tensor).unwrap_err();
+
+        assert_eq!(err.code(), tonic::Code::InvalidArgument);
+    }
+
+    #[test]
+    fn round_trip_string_outputs() {
+        let output = OutputTensor::StringTensor {
+            dimensions: vec![2],
+            strings: vec!["a".to_string(), "b".to_string()],
+        };
+
+        let got = tensor_to_proto(output);
+
+        assert_eq!(got.element_type, proto::ElementType::Utf8 as i32);
+        assert_eq!(got.dimensions, vec![2]);
+        assert_eq!(got.strings, vec!["a", "b"]);
+    }
+}
@@ -0,0 +1,286 @@
+use std::{collections::HashMap, convert::Infallible, net::SocketAddr};
+
+use anyhow::{Context, Error};
+use axum::{
+    body::Bytes,
+    extract::{Extension, Path, Query},
+    http::{header::CONTENT_TYPE, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    AddExtensionLayer, Json, Router,
+};
+use hotg_rune_runtime::{Metrics, NodeMetadata, OutputTensor};
+
+use crate::serve::{
+    nanoseconds,
+    worker::{Input, RuneHandle},
+    Runes,
+};
+
+/// Serve the HTTP+JSON API until the process receives a Ctrl-C.
+pub(crate) async fn serve(addr: SocketAddr, runes: Runes) -> Result<(), Error> {
+    log::info!("Serving the HTTP API on {}", addr);
+
+    axum::Server::try_bind(&addr)
+        .with_context(|| format!("Unable to bind to {}", addr))?
+        .serve(router(runes).into_make_service())
+        .with_graceful_shutdown(async {
+            let _ = tokio::signal::ctrl_c().await;
+        })
+        .await
+        .with_context(|| format!("Unable to serve the HTTP API on {}", addr))
+}
+
+fn router(runes: Runes) -> Router {
+    Router::new()
+        .route("/runes", get(list_runes))
+        .route("/runes/:name/predict", post(predict))
+        .layer(AddExtensionLayer::new(runes))
+}
+
+#[derive(Debug, serde::Serialize)]
+struct RuneInfo<'a> {
+    name: &'a str,
+    capabilities: Vec<Node<'a>>,
+    outputs: Vec<Node<'a>>,
+}
+
+#[derive(Debug, serde::Serialize)]
+struct Node<'a> {
+    id: u32,
+    kind: &'a str,
+    arguments: &'a HashMap<String, String>,
+}
+
+async fn list_runes(Extension(runes): Extension<Runes>) -> Response {
+    let info: Vec<_> = runes
+        .values()
+        .map(|rune| RuneInfo {
+            name: &rune.name,
+            capabilities: nodes(&rune.capabilities),
+            outputs: nodes(&rune.outputs),
+        })
+        .collect();
+
+    Json(info).into_response()
+}
+
+fn nodes(nodes: &HashMap<u32, NodeMetadata>) -> Vec<Node<'_>> {
+    nodes
+        .iter()
+        .map(|(&id, meta)| Node {
+            id,
+            kind: &meta.kind,
+            arguments: &meta.arguments,
+        })
+        .collect()
+}
+
+#[derive(Debug, Default, serde::Deserialize)]
+struct PredictQuery {
+    /// Which capability a non-multipart body should be passed to.
+    capability: Option<u32>,
+}
+
+#[derive(Debug, serde::Serialize)]
+struct PredictResponse {
+    outputs: HashMap<u32, Vec<OutputTensor>>,
+    timing: Timing,
+}
+
+#[derive(Debug, serde::Serialize)]
+struct Timing {
+    total_ns: u64,
+    capabilities_ns: HashMap<u32, u64>,
+    models_ns: HashMap<u32, u64>,
+    outputs_ns: HashMap<u32, u64>,
+}
+
+impl From<&Metrics> for Timing {
+    fn from(metrics: &Metrics) -> Self {
+        Timing {
+            total_ns: metrics.total.as_nanos() as u64,
+            capabilities_ns: nanoseconds(&metrics.capabilities),
+            models_ns: nanoseconds(&metrics.models),
+            outputs_ns: nanoseconds(&metrics.outputs),
+        }
+    }
+}
+
+async fn predict(
+    Extension(runes): Extension<Runes>,
+    Path(name): Path<String>,
+    Query(query): Query<PredictQuery>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<PredictResponse>, HttpError> {
+    let rune = runes.get(&name).ok_or_else(|| {
+        HttpError::new(
+            StatusCode::NOT_FOUND,
+            format!("There is no Rune called \"{}\"", name),
+        )
+    })?;
+
+    let content_type = headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+
+    let inputs = match multer::parse_boundary(content_type) {
+        Ok(boundary) => multipart_inputs(boundary, body).await?,
+        Err(_) => single_input(rune, query.capability, body)?,
+    };
+
+    let prediction = rune
+        .predict(inputs)
+        .await
+        .map_err(|e| HttpError::internal(format!("{:#}", e)))?;
+
+    Ok(Json(PredictResponse {
+        timing: Timing::from(&prediction.metrics),
+        outputs: prediction.outputs,
+    }))
+}
+
+/// Each part of a `multipart/form-data` body is the file for the capability
+/// whose ID is the part's name.
+async fn multipart_inputs(
+    boundary: String,
+    body: Bytes,
+) -> Result<HashMap<u32, Input>, HttpError> {
+    let stream =
+        futures::stream::once(async move { Ok::<_, Infallible>(body) });
+    let mut multipart = multer::Multipart::new(stream, boundary);
+    let mut inputs = HashMap::new();
+
+    while let Some(field) =
+        multipart.next_field().await.map_err(HttpError::bad_request)?
+    {
+        let name = field.name().unwrap_or_default().to_string();
+        let id: u32 = name.parse().map_err(|_| {
+            HttpError::bad_request(format!(
+                "Expected the multipart field name to be a capability ID, \
+                 found \"{}\"",
+                name
+            ))
+        })?;
+        let data = field.bytes().await.map_err(HttpError::bad_request)?;
+
+        inputs.insert(id, Input::File(data.to_vec()));
+    }
+
+    Ok(inputs)
+}
+
+/// A plain body goes to the capability from the `?capability=` query
+/// parameter, or the Rune's only capability if there is just one.
+fn single_input(
+    rune: &RuneHandle,
+    capability: Option<u32>,
+    body: Bytes,
+) -> Result<HashMap<u32, Input>, HttpError> {
+    let id = match capability {
+        Some(id) => id,
+        None if rune.capabilities.len() == 1 => {
+            *rune.capabilities.keys().next().unwrap()
+        },
+        None => {
+            return Err(HttpError::bad_request(format!(
+                "The \"{}\" Rune has {} capabilities, so either use a \
+                 multipart body or specify one with \"?capability=ID\"",
+                rune.name,
+                rune.capabilities.len()
+            )))
+        },
+    };
+
+    let mut inputs = HashMap::new();
+    inputs.insert(id, Input::File(body.to_vec()));
+
+    Ok(inputs)
+}
+
+#[derive(Debug)]
+struct HttpError {
+    status: StatusCode,
+    message: String,
+}
+
+impl HttpError {
+    fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        HttpError {
+            status,
+            message: message.into(),
+        }
+    }
+
+    fn bad_request(message: impl ToString) -> Self {
+        HttpError::new(StatusCode::BAD_REQUEST, message.to_string())
+    }
+
+    fn internal(message: impl Into<String>) -> Self {
+        HttpError::new(StatusCode::INTERNAL_SERVER_ERROR, message)
+    }
+}
+
+impl IntoResponse for HttpError {
+    fn into_response(self) -> Response {
+        let body = serde_json::json!({ "error": self.message });
+        (self.status, Json(body)).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hotg_rune_runtime::Tensor;
+
+    use super::*;
+
+    #[test]
+    fn timing_is_in_nanoseconds() {
+        let mut metrics = Metrics::default();
+        metrics.total = std::time::Duration::from_micros(3);
+        metrics
+            .models
+            .insert(1, std::time::Duration::from_nanos(42));
+
+        let got = Timing::from(&metrics);
+
+        assert_eq!(got.total_ns, 3000);
+        assert_eq!(got.models_ns[&1], 42);
+    }
+
+    #[tokio::test]
+    async fn multipart_fields_are_keyed_by_capability_id() {
+        let body = Bytes::from(
+            "--XXX\r\n\
+             Content-Disposition: form-data; name=\"1\"; filename=\"a.bin\"\r\n\
+             \r\n\
+             abc\r\n\
+             --XXX--\r\n",
+        );
+
+        let got = multipart_inputs("XXX".to_string(), body).await.unwrap();
+
+        assert_eq!(got.len(), 1);
+        assert_eq!(got[&1], Input::File(b"abc".to_vec()));
+    }
+
+    #[test]
+    fn outputs_serialize_as_json() {
+        let mut outputs = HashMap::new();
+        outputs.insert(
+            2,
+            vec![OutputTensor::Tensor(Tensor::new(&[1_u8, 2], &[2]))],
+        );
+        let response = PredictResponse {
+            outputs,
+            timing: Timing::from(&Metrics::default()),
+        };
+
+        let json = serde_json::to_value(&response).unwrap();
+
+        assert!(json["outputs"]["2"].is_array());
+        assert_eq!(json["timing"]["total_ns"], 0);
+    }
+}
@@ -0,0 +1,99 @@
+use std::io::Cursor;
+
+use anyhow::{Context, Error};
+use hotg_rune_runtime::{
+    builtins::{
+        self, AccelerometerSamples, Arguments, AudioClip, BatteryReading,
+        GpsTrack, GyroscopeSamples, NumpyArrays, SensorReadings, Table,
+        VideoClip,
+    },
+    Tensor,
+};
+use hound::WavReader;
+
+/// Decode the contents of a file the same way `rune run` would if it was
+/// passed as the input for a capability.
+pub(crate) fn decode(
+    kind: &str,
+    args: &Arguments,
+    data: &[u8],
+) -> Result<Tensor, Error> {
+    match kind {
+        "IMAGE" => {
+            let img = image::load_from_memory(data)
+                .context("Unable to decode the image")?;
+            builtins::image(args, &img)
+        },
+        "VIDEO" => {
+            let clip = VideoClip::from_gif(data, None)
+                .context("Only animated GIFs can be used as video inputs")?;
+            builtins::video(args, &clip)
+        },
+        "SOUND" if data.starts_with(b"RIFF") => {
+            let reader = WavReader::new(Cursor::new(data))
+                .context("Unable to read the WAV file")?;
+            let audio = AudioClip::load(reader)?;
+            builtins::sound(args, &audio)
+        },
+        "SOUND" => {
+            let audio = AudioClip::decode(Cursor::new(data.to_vec()), None)?;
+            builtins::sound(args, &audio)
+        },
+        "ACCEL" => {
+            let samples = AccelerometerSamples::from_reader(data)?;
+            builtins::accelerometer(args, &samples)
+        },
+        "GYRO" => {
+            let samples = GyroscopeSamples::from_reader(data)?;
+            builtins::gyroscope(args, &samples)
+        },
+        "GPS" => {
+            let text = std::str::from_utf8(data)
+                .context("The GPS track should be UTF-8 text")?;
+            let track = if text.trim_start().starts_with('<') {
+                GpsTrack::from_gpx(text)?
+            } else {
+                GpsTrack::from_nmea(text)?
+            };
+            builtins::gps(args, &track)
+        },
+        "THERMOMETER" => {
+            let readings = SensorReadings::from_reader(data)?;
+            builtins::thermometer(args, &readings)
+        },
+        "BATTERY" => {
+            let readings = SensorReadings::from_reader(data)?;
+            let reading = BatteryReading::from_readings(&readings)?;
+            Ok(builtins::battery(args, reading))
+        },
+        "BAROMETER" => {
+            let readings = SensorReadings::from_reader(data)?;
+            builtins::barometer(args, &readings)
+        },
+        "PROXIMITY" => {
+            let readings = SensorReadings::from_reader(data)?;
+            builtins::proximity(args, &readings)
+        },
+        "LIGHT" => {
+            let readings = SensorReadings::from_reader(data)?;
+            builtins::light(args, &readings)
+        },
+        "TENSOR" => {
+            let arrays = if data.starts_with(b"\x93NUMPY") {
+                let array = builtins::read_npy(data)?;
+                NumpyArrays(vec![(String::new(), array)])
+            } else {
+                NumpyArrays::from_npz(Cursor::new(data))?
+            };
+            builtins::tensor(args, &arrays)
+        },
+        "TABULAR" => {
+            let table = Table::from_reader(data)?;
+            builtins::tabular(args, &table)
+        },
+        "RAW" => builtins::raw(args, data),
+        "SIGNAL" => builtins::signal(args),
+        "RAND" => builtins::random(args),
+        _ => anyhow::bail!("Unknown input type, \"{}\"", kind),
+    }
+}
@@ -0,0 +1,157 @@
+//! The `rune serve` command.
+
+mod grpc;
+mod http;
+mod inputs;
+mod worker;
+
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+    time::Duration,
+};
+
+use anyhow::{Context, Error};
+use structopt::StructOpt;
+use strum::VariantNames;
+
+use crate::{run::Engine, serve::worker::RuneHandle};
+
+/// Every Rune being served, keyed by name.
+pub(crate) type Runes = Arc<HashMap<String, RuneHandle>>;
+
+#[derive(Debug, Clone, PartialEq, StructOpt)]
+pub struct Serve {
+    #[structopt(
+        long,
+        default_value = "127.0.0.1:50051",
+        help = "The address to serve the gRPC API on"
+    )]
+    grpc: SocketAddr,
+    #[structopt(
+        long,
+        help = "Also serve a HTTP+JSON API on this address (e.g. \
+                \"127.0.0.1:8080\")"
+    )]
+    http: Option<SocketAddr>,
+    #[structopt(
+        long,
+        help = "The WebAssembly engine to use",
+        possible_values = Engine::VARIANTS,
+        default_value = "wasmer",
+    )]
+    engine: Engine,
+    #[structopt(
+        required = true,
+        help = "The Runes to serve, either as a path or \"NAME=path\" (the \
+                name defaults to the file name without its extension)"
+    )]
+    runes: Vec<RuneSpec>,
+}
+
+impl Serve {
+    pub fn execute(self) -> Result<(), Error> {
+        let runtime = tokio::runtime::Runtime::new()
+            .context("Unable to start the async runtime")?;
+
+        runtime.block_on(self.serve())
+    }
+
+    async fn serve(self) -> Result<(), Error> {
+        let runes = self.load_runes()?;
+
+        match self.http {
+            Some(http) => {
+                tokio::try_join!(
+                    grpc::serve(self.grpc, Arc::clone(&runes)),
+                    http::serve(http, runes),
+                )?;
+                Ok(())
+            },
+            None => grpc::serve(self.grpc, runes).await,
+        }
+    }
+
+    fn load_runes(&self) -> Result<Runes, Error> {
+        let mut runes = HashMap::new();
+
+        for RuneSpec { name, path } in &self.runes {
+            log::info!("Loading \"{}\" from \"{}\"", name, path.display());
+
+            let wasm = std::fs::read(path).with_context(|| {
+                format!("Unable to read \"{}\"", path.display())
+            })?;
+            let handle = RuneHandle::spawn(name.clone(), wasm, self.engine)?;
+
+            if runes.insert(name.clone(), handle).is_some() {
+                anyhow::bail!("Multiple Runes are called \"{}\"", name);
+            }
+        }
+
+        Ok(Arc::new(runes))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct RuneSpec {
+    name: String,
+    path: PathBuf,
+}
+
+impl FromStr for RuneSpec {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        if let Some((name, path)) = s.split_once('=') {
+            return Ok(RuneSpec {
+                name: name.to_string(),
+                path: PathBuf::from(path),
+            });
+        }
+
+        let path = Path::new(s);
+        let name = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .with_context(|| {
+                format!("Unable to figure out a name for \"{}\"", s)
+            })?;
+
+        Ok(RuneSpec {
+            name: name.to_string(),
+            path: path.to_path_buf(),
+        })
+    }
+}
+
+/// Convert a set of per-node timings into nanoseconds for the serve APIs.
+fn nanoseconds(durations: &HashMap<u32, Duration>) -> HashMap<u32, u64> {
+    durations
+        .iter()
+        .map(|(&id, d)| (id, d.as_nanos() as u64))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn name_defaults_to_the_file_stem() {
+        let got: RuneSpec = "examples/sine/sine.rune".parse().unwrap();
+
+        assert_eq!(got.name, "sine");
+        assert_eq!(got.path, PathBuf::from("examples/sine/sine.rune"));
+    }
+
+    #[test]
+    fn explicit_names() {
+        let got: RuneSpec = "wave=sine.rune".parse().unwrap();
+
+        assert_eq!(got.name, "wave");
+        assert_eq!(got.path, PathBuf::from("sine.rune"));
+    }
+}
@@ -0,0 +1,148 @@
+use std::{collections::HashMap, sync::mpsc::sync_channel, thread};
+
+use anyhow::{Context, Error};
+use hotg_rune_runtime::{
+    builtins::Arguments, Metrics, NodeMetadata, OutputTensor, Runtime, Tensor,
+};
+use tokio::sync::{mpsc, oneshot};
+
+use crate::{run::Engine, serve::inputs::decode};
+
+/// Data provided to a capability.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Input {
+    /// A tensor which is passed to the capability as-is.
+    Tensor(Tensor),
+    /// The contents of a file which needs to be decoded based on the
+    /// capability's type and arguments.
+    File(Vec<u8>),
+}
+
+/// The result of running a Rune once.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Prediction {
+    pub outputs: HashMap<u32, Vec<OutputTensor>>,
+    pub metrics: Metrics,
+}
+
+struct Job {
+    inputs: HashMap<u32, Input>,
+    respond: oneshot::Sender<Result<Prediction, Error>>,
+}
+
+/// A handle to a Rune that is running on its own thread.
+///
+/// The [`Runtime`] can't be moved between threads, so requests are sent to a
+/// dedicated worker thread and handled one at a time.
+#[derive(Debug)]
+pub(crate) struct RuneHandle {
+    pub name: String,
+    pub capabilities: HashMap<u32, NodeMetadata>,
+    pub outputs: HashMap<u32, NodeMetadata>,
+    jobs: mpsc::UnboundedSender<Job>,
+}
+
+impl RuneHandle {
+    pub fn spawn(
+        name: String,
+        rune: Vec<u8>,
+        engine: Engine,
+    ) -> Result<Self, Error> {
+        let (jobs, mut receiver) = mpsc::unbounded_channel::<Job>();
+        let (loaded_tx, loaded_rx) = sync_channel(1);
+        let rune_name = name.clone();
+
+        thread::Builder::new()
+            .name(format!("rune-{}", name))
+            .spawn(move || {
+                let mut runtime = match engine.load(&rune) {
+                    Ok(r) => r,
+                    Err(e) => {
+                        let _ = loaded_tx.send(Err(Error::from(e)));
+                        return;
+                    },
+                };
+
+                runtime.set_logger(|record| log::logger().log(record));
+                runtime.set_max_log_level(log::max_level());
+
+                // Make sure the first request isn't unusually slow
+                if let Err(e) = runtime.warm_up() {
+                    log::warn!("Unable to warm up \"{}\": {:?}", rune_name, e);
+                }
+
+                let metadata = (
+                    runtime.capabilities().clone(),
+                    runtime.outputs().clone(),
+                );
+                let _ = loaded_tx.send(Ok(metadata));
+
+                while let Some(Job { inputs, respond }) =
+                    receiver.blocking_recv()
+                {
+                    let _ = respond.send(predict(&mut runtime, inputs));
+                }
+            })
+            .context("Unable to start the worker thread")?;
+
+        let (capabilities, outputs) = loaded_rx
+            .recv()
+            .context("The worker thread stopped unexpectedly")?
+            .with_context(|| format!("Unable to load the \"{}\" Rune", name))?;
+
+        Ok(RuneHandle {
+            name,
+            capabilities,
+            outputs,
+            jobs,
+        })
+    }
+
+    /// Run the Rune, keying inputs by capability ID.
+    pub async fn predict(
+        &self,
+        inputs: HashMap<u32, Input>,
+    ) -> Result<Prediction, Error> {
+        let (respond, response) = oneshot::channel();
+
+        self.jobs
+            .send(Job { inputs, respond })
+            .map_err(|_| Error::msg("The worker thread has stopped"))?;
+
+        response.await.context("The worker thread has stopped")?
+    }
+}
+
+fn predict(
+    runtime: &mut Runtime,
+    inputs: HashMap<u32, Input>,
+) -> Result<Prediction, Error> {
+    let capabilities = runtime.capabilities().clone();
+    let input_tensors = runtime.input_tensors();
+    input_tensors.clear();
+
+    for (id, input) in inputs {
+        let meta = capabilities.get(&id).with_context(|| {
+            format!("The Rune doesn't have a capability with ID {}", id)
+        })?;
+
+        let tensor = match input {
+            Input::Tensor(tensor) => tensor,
+            Input::File(data) => {
+                let args = Arguments(meta.arguments.clone());
+                decode(&meta.kind, &args, &data).with_context(|| {
+                    format!("Unable to decode the input for capability {}", id)
+                })?
+            },
+        };
+
+        input_tensors.insert(id, tensor);
+    }
+
+    runtime.predict()?;
+
+    Ok(Prediction {
+        outputs: runtime.output_tensors().clone(),
+        metrics: runtime.metrics().clone(),
+    })
+}
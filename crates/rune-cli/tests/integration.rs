@@ -86,3 +86,29 @@ fn build_all_examples() {
             .success();
     }
 }
+
+#[test]
+fn build_the_proc_block_template() {
+    let proc_block_dir = cache_dir().join("new").join("my-proc-block");
+    let runefile = proc_block_dir.join("example").join("Runefile.yml");
+
+    let mut cmd = Command::cargo_bin("rune").unwrap();
+    cmd.arg("new")
+        .arg("proc-block")
+        .arg(&proc_block_dir)
+        .arg("--force")
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("rune").unwrap();
+    cmd.arg("build")
+        .arg(&runefile)
+        .arg("--colour=never")
+        .arg("--cache-dir")
+        .arg(cache_dir().join("new").join("cache"))
+        .arg("--unstable")
+        .arg("--rune-repo-dir")
+        .arg(project_root())
+        .assert()
+        .success();
+}
@@ -4,6 +4,8 @@ use core::{
     str::FromStr,
 };
 
+use num_complex::Complex;
+
 /// A type that is associated with an [`ElementType`].
 pub trait AsElementType {
     const TYPE: ElementType;
@@ -32,7 +34,20 @@ pub enum ElementType {
     U64,
     F64,
     I64,
+    /// A UTF-8 string (e.g. a classification label).
     String,
+    /// An IEEE 754 half-precision float.
+    F16,
+    /// A "brain floating point" number, a 16-bit float with the same range
+    /// as an `f32` but less precision.
+    BF16,
+    /// A boolean (e.g. a mask), stored as a single byte that is either `0`
+    /// or `1`.
+    Bool,
+    /// A complex number made from two `f32`s.
+    C64,
+    /// A complex number made from two `f64`s.
+    C128,
 }
 
 impl ElementType {
@@ -88,6 +103,11 @@ impl ElementType {
             ElementType::F64 => Some(core::mem::size_of::<f64>()),
             ElementType::I64 => Some(core::mem::size_of::<i64>()),
             ElementType::String => None,
+            ElementType::F16 => Some(core::mem::size_of::<half::f16>()),
+            ElementType::BF16 => Some(core::mem::size_of::<half::bf16>()),
+            ElementType::Bool => Some(core::mem::size_of::<bool>()),
+            ElementType::C64 => Some(core::mem::size_of::<Complex<f32>>()),
+            ElementType::C128 => Some(core::mem::size_of::<Complex<f64>>()),
         }
     }
 
@@ -104,6 +124,11 @@ impl ElementType {
             ElementType::I64 => "i64",
             ElementType::F64 => "f64",
             ElementType::String => "utf8",
+            ElementType::F16 => "f16",
+            ElementType::BF16 => "bf16",
+            ElementType::Bool => "bool",
+            ElementType::C64 => "c64",
+            ElementType::C128 => "c128",
         }
     }
 
@@ -120,6 +145,11 @@ impl ElementType {
             "i64" => Some(ElementType::I64),
             "f64" => Some(ElementType::F64),
             "utf8" => Some(ElementType::String),
+            "f16" => Some(ElementType::F16),
+            "bf16" => Some(ElementType::BF16),
+            "bool" => Some(ElementType::Bool),
+            "c64" => Some(ElementType::C64),
+            "c128" => Some(ElementType::C128),
             _ => None,
         }
     }
@@ -187,6 +217,26 @@ impl AsElementType for i64 {
     const TYPE: ElementType = ElementType::I64;
 }
 
+impl AsElementType for bool {
+    const TYPE: ElementType = ElementType::Bool;
+}
+
+impl AsElementType for half::f16 {
+    const TYPE: ElementType = ElementType::F16;
+}
+
+impl AsElementType for half::bf16 {
+    const TYPE: ElementType = ElementType::BF16;
+}
+
+impl AsElementType for Complex<f32> {
+    const TYPE: ElementType = ElementType::C64;
+}
+
+impl AsElementType for Complex<f64> {
+    const TYPE: ElementType = ElementType::C128;
+}
+
 impl AsElementType for alloc::borrow::Cow<'static, str> {
     const TYPE: ElementType = ElementType::String;
 }
@@ -200,5 +250,4 @@ impl Display for UnknownElementType {
     }
 }
 
-#[cfg(feature = "std")]
-impl std::error::Error for UnknownElementType {}
+impl crate::Error for UnknownElementType {}
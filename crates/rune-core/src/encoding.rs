@@ -0,0 +1,878 @@
+//! A compact binary encoding for tensors.
+//!
+//! Each tensor is written as a header followed by its elements, and several
+//! tensors can be written back-to-back in the same buffer.
+//!
+//! | Field        | Type          | Notes                              |
+//! | ------------ | ------------- | ---------------------------------- |
+//! | magic        | `[u8; 4]`     | Always [`MAGIC`]                   |
+//! | version      | `u8`          | Currently [`VERSION`]              |
+//! | element type | `u8`          | See [`element_type_tag()`]         |
+//! | rank         | `u16`         | The number of dimensions           |
+//! | dimensions   | `[u32; rank]` |                                    |
+//! | quantization | `u8`          | See below                          |
+//! | parameters   | variable      | See below                          |
+//! | data length  | `u32`         | The number of bytes in `data`      |
+//! | data         | `[u8]`        | The elements, in row-major order   |
+//!
+//! All integers in the header are little-endian, and so are the elements.
+//! Booleans are a single byte that is either `0` or `1`, complex numbers are
+//! stored as their real part followed by their imaginary part, and each
+//! string is a `u32` length followed by that many bytes of UTF-8.
+//!
+//! The quantization field is `0` for tensors that aren't quantized, `1` if
+//! the tensor has per-tensor [`Quantization`] parameters (an `f32` scale
+//! followed by an `i32` zero point), or `2` if it has per-channel parameters
+//! (the channel axis as a `u16`, then a scale and zero point for each
+//! channel).
+
+use alloc::{borrow::Cow, string::String, vec::Vec};
+use core::{
+    convert::TryInto,
+    fmt::{self, Display, Formatter},
+    str::Utf8Error,
+};
+
+use half::{bf16, f16};
+use num_complex::Complex;
+
+use crate::{
+    AsElementType, Dim, ElementType, Quantization, Shape, ShapeError, Tensor,
+};
+
+/// The bytes every encoded tensor starts with.
+pub const MAGIC: [u8; 4] = *b"RTNS";
+
+/// The version of the encoding written by this crate.
+///
+/// This should be incremented whenever the format changes in an incompatible
+/// way.
+pub const VERSION: u8 = 1;
+
+/// The number of bytes in the header before the dimensions.
+const FIXED_HEADER_LENGTH: usize = 4 + 1 + 1 + 2;
+
+/// The stable number used to identify an [`ElementType`] in the encoding.
+pub fn element_type_tag(element_type: ElementType) -> u8 {
+    match element_type {
+        ElementType::U8 => 1,
+        ElementType::I8 => 2,
+        ElementType::U16 => 3,
+        ElementType::I16 => 4,
+        ElementType::U32 => 5,
+        ElementType::I32 => 6,
+        ElementType::F32 => 7,
+        ElementType::U64 => 8,
+        ElementType::I64 => 9,
+        ElementType::F64 => 10,
+        ElementType::String => 11,
+        ElementType::F16 => 12,
+        ElementType::BF16 => 13,
+        ElementType::Bool => 14,
+        ElementType::C64 => 15,
+        ElementType::C128 => 16,
+    }
+}
+
+/// The inverse of [`element_type_tag()`].
+pub fn element_type_from_tag(tag: u8) -> Option<ElementType> {
+    match tag {
+        1 => Some(ElementType::U8),
+        2 => Some(ElementType::I8),
+        3 => Some(ElementType::U16),
+        4 => Some(ElementType::I16),
+        5 => Some(ElementType::U32),
+        6 => Some(ElementType::I32),
+        7 => Some(ElementType::F32),
+        8 => Some(ElementType::U64),
+        9 => Some(ElementType::I64),
+        10 => Some(ElementType::F64),
+        11 => Some(ElementType::String),
+        12 => Some(ElementType::F16),
+        13 => Some(ElementType::BF16),
+        14 => Some(ElementType::Bool),
+        15 => Some(ElementType::C64),
+        16 => Some(ElementType::C128),
+        _ => None,
+    }
+}
+
+/// Append a tensor to `buffer`, where `data` contains its elements already
+/// encoded as described in the [module docs](self).
+pub fn encode(
+    shape: &Shape<'_>,
+    data: &[u8],
+    buffer: &mut Vec<u8>,
+) -> Result<(), EncodeError> {
+    if let Some(expected) = shape.size() {
+        if expected != data.len() {
+            return Err(EncodeError::DataLengthMismatch {
+                expected,
+                found: data.len(),
+            });
+        }
+    }
+
+    let start = write_header(shape, buffer)?;
+    buffer.extend_from_slice(data);
+    write_data_length(buffer, start)
+}
+
+/// Append a [`Tensor`] to `buffer`.
+pub fn encode_tensor<T: Element>(
+    tensor: &Tensor<T>,
+    buffer: &mut Vec<u8>,
+) -> Result<(), EncodeError> {
+    let start = write_header(&tensor.shape(), buffer)?;
+
+    for element in tensor.elements() {
+        element.write_le(buffer);
+    }
+
+    write_data_length(buffer, start)
+}
+
+/// Write everything up to the data length (which is left as zero), returning
+/// the index the data starts at.
+fn write_header(
+    shape: &Shape<'_>,
+    buffer: &mut Vec<u8>,
+) -> Result<usize, EncodeError> {
+    let rank: u16 = shape
+        .rank()
+        .try_into()
+        .map_err(|_| EncodeError::TooManyDimensions { rank: shape.rank() })?;
+
+    buffer.extend_from_slice(&MAGIC);
+    buffer.push(VERSION);
+    buffer.push(element_type_tag(shape.element_type()));
+    buffer.extend_from_slice(&rank.to_le_bytes());
+
+    for (axis, dim) in shape.dimensions().iter().enumerate() {
+        let length = dim.value().ok_or(EncodeError::UnknownDimension { axis })?;
+        let length: u32 = length
+            .try_into()
+            .map_err(|_| EncodeError::TooLarge { length })?;
+        buffer.extend_from_slice(&length.to_le_bytes());
+    }
+
+    write_quantization(shape.quantization(), buffer);
+    buffer.extend_from_slice(&0_u32.to_le_bytes());
+
+    Ok(buffer.len())
+}
+
+fn write_quantization(
+    quantization: Option<&Quantization>,
+    buffer: &mut Vec<u8>,
+) {
+    match quantization {
+        None => buffer.push(0),
+        Some(Quantization::PerTensor { scale, zero_point }) => {
+            buffer.push(1);
+            buffer.extend_from_slice(&scale.to_le_bytes());
+            buffer.extend_from_slice(&zero_point.to_le_bytes());
+        },
+        Some(Quantization::PerChannel {
+            axis,
+            scales,
+            zero_points,
+        }) => {
+            // Note: the shape already made sure the axis is in bounds
+            let axis = *axis as u16;
+
+            buffer.push(2);
+            buffer.extend_from_slice(&axis.to_le_bytes());
+
+            for (scale, zero_point) in scales.iter().zip(zero_points) {
+                buffer.extend_from_slice(&scale.to_le_bytes());
+                buffer.extend_from_slice(&zero_point.to_le_bytes());
+            }
+        },
+    }
+}
+
+fn write_data_length(
+    buffer: &mut [u8],
+    start: usize,
+) -> Result<(), EncodeError> {
+    let length = buffer.len() - start;
+    let length: u32 = length
+        .try_into()
+        .map_err(|_| EncodeError::TooLarge { length })?;
+    buffer[start - 4..start].copy_from_slice(&length.to_le_bytes());
+
+    Ok(())
+}
+
+/// A tensor which has been read from an encoded buffer, but whose elements
+/// haven't been decoded yet.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EncodedTensor<'a> {
+    pub shape: Shape<'static>,
+    /// The tensor's elements, encoded as described in the
+    /// [module docs](self).
+    pub data: &'a [u8],
+}
+
+/// Read the first tensor in `bytes`, returning it and whatever bytes are
+/// left over.
+pub fn decode(bytes: &[u8]) -> Result<(EncodedTensor<'_>, &[u8]), DecodeError> {
+    let (header, rest) = split(bytes, FIXED_HEADER_LENGTH)?;
+
+    if header[..4] != MAGIC {
+        return Err(DecodeError::BadMagic);
+    }
+    if header[4] != VERSION {
+        return Err(DecodeError::UnsupportedVersion { found: header[4] });
+    }
+    let element_type = element_type_from_tag(header[5])
+        .ok_or(DecodeError::UnknownElementType { tag: header[5] })?;
+    let rank = u16::from_le_bytes([header[6], header[7]]);
+
+    let mut dimensions = Vec::with_capacity(rank.into());
+    let mut rest = rest;
+
+    for _ in 0..rank {
+        let (length, r) = read_u32(rest)?;
+        dimensions.push(Dim::Fixed(length as usize));
+        rest = r;
+    }
+
+    let (quantization, rest) = read_quantization(rest, &dimensions)?;
+    let (data_length, rest) = read_u32(rest)?;
+    let (data, rest) = split(rest, data_length as usize)?;
+    let mut shape = Shape::with_dims(element_type, dimensions);
+
+    if let Some(quantization) = quantization {
+        shape = shape.with_quantization(quantization).map_err(|reason| {
+            DecodeError::IncompatibleQuantization { reason }
+        })?;
+    }
+
+    if let Some(expected) = shape.size() {
+        if expected != data.len() {
+            return Err(DecodeError::DataLengthMismatch {
+                expected,
+                found: data.len(),
+            });
+        }
+    }
+
+    Ok((EncodedTensor { shape, data }, rest))
+}
+
+fn read_quantization<'a>(
+    bytes: &'a [u8],
+    dimensions: &[Dim],
+) -> Result<(Option<Quantization>, &'a [u8]), DecodeError> {
+    let (tag, rest) = split(bytes, 1)?;
+
+    match tag[0] {
+        0 => Ok((None, rest)),
+        1 => {
+            let (scale, rest) = f32::read_le(rest)?;
+            let (zero_point, rest) = i32::read_le(rest)?;
+            Ok((Some(Quantization::per_tensor(scale, zero_point)), rest))
+        },
+        2 => {
+            let (axis, mut rest) = u16::read_le(rest)?;
+            let axis = usize::from(axis);
+            let channels = dimensions
+                .get(axis)
+                .and_then(|d| d.value())
+                .ok_or(DecodeError::InvalidQuantization)?;
+
+            let mut scales = Vec::new();
+            let mut zero_points = Vec::new();
+
+            for _ in 0..channels {
+                let (scale, r) = f32::read_le(rest)?;
+                let (zero_point, r) = i32::read_le(r)?;
+                scales.push(scale);
+                zero_points.push(zero_point);
+                rest = r;
+            }
+
+            let quantization =
+                Quantization::per_channel(axis, scales, zero_points);
+            Ok((Some(quantization), rest))
+        },
+        _ => Err(DecodeError::InvalidQuantization),
+    }
+}
+
+/// Read every tensor in `bytes`.
+pub fn decode_all(bytes: &[u8]) -> Result<Vec<EncodedTensor<'_>>, DecodeError> {
+    let mut tensors = Vec::new();
+    let mut rest = bytes;
+
+    while !rest.is_empty() {
+        let (tensor, r) = decode(rest)?;
+        tensors.push(tensor);
+        rest = r;
+    }
+
+    Ok(tensors)
+}
+
+/// Read the first tensor in `bytes` as a [`Tensor<T>`], returning it and
+/// whatever bytes are left over.
+pub fn decode_tensor<T: Element>(
+    bytes: &[u8],
+) -> Result<(Tensor<T>, &[u8]), DecodeError> {
+    let (EncodedTensor { shape, data }, rest) = decode(bytes)?;
+
+    if shape.element_type() != T::TYPE {
+        return Err(DecodeError::ElementTypeMismatch {
+            expected: T::TYPE,
+            found: shape.element_type(),
+        });
+    }
+
+    let dimensions = shape
+        .fixed_dimensions()
+        .expect("Decoded dimensions are always known");
+    let count: usize = dimensions.iter().product();
+
+    if count == 0 || dimensions.is_empty() {
+        // Tensors always have at least one element
+        return Err(DecodeError::Empty);
+    }
+
+    let mut elements = Vec::with_capacity(count);
+    let mut data = data;
+
+    for _ in 0..count {
+        let (element, d) = T::read_le(data)?;
+        elements.push(element);
+        data = d;
+    }
+
+    if !data.is_empty() {
+        return Err(DecodeError::TrailingData { bytes: data.len() });
+    }
+
+    let tensor = Tensor::new_row_major(elements.into(), dimensions);
+    let tensor = match shape.quantization() {
+        Some(q) => tensor
+            .with_quantization(q.clone())
+            .expect("Already checked while decoding the header"),
+        None => tensor,
+    };
+
+    Ok((tensor, rest))
+}
+
+/// A [`Tensor`], or tuple of tensors, which can be written to a buffer and
+/// read back again.
+///
+/// This is used when tensors need to cross a WebAssembly boundary as a single
+/// buffer (e.g. the inputs and outputs of a standalone proc block).
+pub trait TensorTuple: Sized {
+    /// Append each tensor to `buffer`, in order.
+    fn encode_tensors(&self, buffer: &mut Vec<u8>) -> Result<(), EncodeError>;
+
+    /// Read the tensors back in, returning them and whatever bytes are left
+    /// over.
+    fn decode_tensors(bytes: &[u8]) -> Result<(Self, &[u8]), DecodeError>;
+}
+
+impl<T: Element> TensorTuple for Tensor<T> {
+    fn encode_tensors(&self, buffer: &mut Vec<u8>) -> Result<(), EncodeError> {
+        encode_tensor(self, buffer)
+    }
+
+    fn decode_tensors(bytes: &[u8]) -> Result<(Self, &[u8]), DecodeError> {
+        decode_tensor(bytes)
+    }
+}
+
+macro_rules! tensor_tuples {
+    ($( ( $($name:ident),+ ) ),* $(,)?) => {
+        $(
+            #[allow(non_snake_case)]
+            impl<$($name: Element),+> TensorTuple for ($(Tensor<$name>,)+) {
+                fn encode_tensors(
+                    &self,
+                    buffer: &mut Vec<u8>,
+                ) -> Result<(), EncodeError> {
+                    let ($($name,)+) = self;
+                    $( encode_tensor($name, buffer)?; )+
+                    Ok(())
+                }
+
+                fn decode_tensors(
+                    bytes: &[u8],
+                ) -> Result<(Self, &[u8]), DecodeError> {
+                    let rest = bytes;
+                    $( let ($name, rest) = decode_tensor::<$name>(rest)?; )+
+                    Ok((($($name,)+), rest))
+                }
+            }
+        )*
+    };
+}
+
+tensor_tuples!(
+    (A),
+    (A, B),
+    (A, B, C),
+    (A, B, C, D),
+    (A, B, C, D, E),
+    (A, B, C, D, E, F),
+    (A, B, C, D, E, F, G),
+    (A, B, C, D, E, F, G, H),
+);
+
+/// Convert the little-endian elements from an [`EncodedTensor`] to the
+/// current platform's byte order, in place.
+///
+/// This is a no-op on little-endian platforms (e.g. WebAssembly, x86, and
+/// most ARM devices). Strings are left untouched.
+pub fn to_native_endian(element_type: ElementType, data: &mut [u8]) {
+    if cfg!(target_endian = "little") {
+        return;
+    }
+
+    let component_size = match element_type {
+        ElementType::String => return,
+        ElementType::C64 => core::mem::size_of::<f32>(),
+        ElementType::C128 => core::mem::size_of::<f64>(),
+        other => other.size_of().unwrap_or(1),
+    };
+
+    for component in data.chunks_mut(component_size) {
+        component.reverse();
+    }
+}
+
+fn split(bytes: &[u8], length: usize) -> Result<(&[u8], &[u8]), DecodeError> {
+    if bytes.len() < length {
+        Err(DecodeError::Truncated)
+    } else {
+        Ok(bytes.split_at(length))
+    }
+}
+
+fn read_u32(bytes: &[u8]) -> Result<(u32, &[u8]), DecodeError> {
+    let (head, rest) = split(bytes, 4)?;
+    let value = u32::from_le_bytes(head.try_into().unwrap());
+    Ok((value, rest))
+}
+
+/// An element which can be written using this encoding.
+pub trait Element: AsElementType + Sized {
+    fn write_le(&self, buffer: &mut Vec<u8>);
+    fn read_le(bytes: &[u8]) -> Result<(Self, &[u8]), DecodeError>;
+}
+
+macro_rules! le_elements {
+    ($($type:ty),* $(,)?) => {
+        $(
+            impl Element for $type {
+                fn write_le(&self, buffer: &mut Vec<u8>) {
+                    buffer.extend_from_slice(&self.to_le_bytes());
+                }
+
+                fn read_le(bytes: &[u8]) -> Result<(Self, &[u8]), DecodeError> {
+                    let (head, rest) =
+                        split(bytes, core::mem::size_of::<$type>())?;
+                    let value =
+                        <$type>::from_le_bytes(head.try_into().unwrap());
+                    Ok((value, rest))
+                }
+            }
+        )*
+    };
+}
+
+le_elements!(u8, i8, u16, i16, u32, i32, f32, u64, i64, f64, f16, bf16);
+
+impl Element for bool {
+    fn write_le(&self, buffer: &mut Vec<u8>) { buffer.push(u8::from(*self)); }
+
+    fn read_le(bytes: &[u8]) -> Result<(Self, &[u8]), DecodeError> {
+        let (value, rest) = u8::read_le(bytes)?;
+
+        match value {
+            0 => Ok((false, rest)),
+            1 => Ok((true, rest)),
+            other => Err(DecodeError::InvalidBool { found: other }),
+        }
+    }
+}
+
+impl<T> Element for Complex<T>
+where
+    T: Element,
+    Complex<T>: AsElementType,
+{
+    fn write_le(&self, buffer: &mut Vec<u8>) {
+        self.re.write_le(buffer);
+        self.im.write_le(buffer);
+    }
+
+    fn read_le(bytes: &[u8]) -> Result<(Self, &[u8]), DecodeError> {
+        let (re, rest) = T::read_le(bytes)?;
+        let (im, rest) = T::read_le(rest)?;
+        Ok((Complex::new(re, im), rest))
+    }
+}
+
+impl Element for Cow<'static, str> {
+    fn write_le(&self, buffer: &mut Vec<u8>) {
+        buffer.extend_from_slice(&(self.len() as u32).to_le_bytes());
+        buffer.extend_from_slice(self.as_bytes());
+    }
+
+    fn read_le(bytes: &[u8]) -> Result<(Self, &[u8]), DecodeError> {
+        let (length, rest) = read_u32(bytes)?;
+        let (text, rest) = split(rest, length as usize)?;
+        let text = core::str::from_utf8(text)
+            .map_err(|reason| DecodeError::InvalidUtf8 { reason })?;
+
+        Ok((Cow::Owned(String::from(text)), rest))
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum EncodeError {
+    /// The tensor's shape has a [`Dim::Unknown`] dimension.
+    UnknownDimension { axis: usize },
+    /// There are more than `u16::MAX` dimensions.
+    TooManyDimensions { rank: usize },
+    /// A dimension or the tensor's data doesn't fit in a `u32`.
+    TooLarge { length: usize },
+    DataLengthMismatch { expected: usize, found: usize },
+}
+
+impl Display for EncodeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            EncodeError::UnknownDimension { axis } => {
+                write!(f, "The length of dimension {} isn't known", axis)
+            },
+            EncodeError::TooManyDimensions { rank } => {
+                write!(f, "Unable to encode a tensor with {} dimensions", rank)
+            },
+            EncodeError::TooLarge { length } => {
+                write!(f, "{} is too large to be encoded as a u32", length)
+            },
+            EncodeError::DataLengthMismatch { expected, found } => write!(
+                f,
+                "Expected {} bytes of data, but found {}",
+                expected, found
+            ),
+        }
+    }
+}
+
+impl crate::Error for EncodeError {}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DecodeError {
+    /// The buffer ended part way through a tensor.
+    Truncated,
+    /// The buffer doesn't start with [`MAGIC`].
+    BadMagic,
+    UnsupportedVersion {
+        found: u8,
+    },
+    UnknownElementType {
+        tag: u8,
+    },
+    ElementTypeMismatch {
+        expected: ElementType,
+        found: ElementType,
+    },
+    DataLengthMismatch {
+        expected: usize,
+        found: usize,
+    },
+    TrailingData {
+        bytes: usize,
+    },
+    /// The tensor doesn't have any elements.
+    Empty,
+    InvalidBool {
+        found: u8,
+    },
+    InvalidUtf8 {
+        reason: Utf8Error,
+    },
+    /// The quantization parameters were malformed.
+    InvalidQuantization,
+    /// The quantization parameters don't match the tensor's dimensions.
+    IncompatibleQuantization {
+        reason: ShapeError,
+    },
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::Truncated => {
+                write!(f, "The buffer ended part way through a tensor")
+            },
+            DecodeError::BadMagic => write!(f, "This isn't an encoded tensor"),
+            DecodeError::UnsupportedVersion { found } => write!(
+                f,
+                "Expected version {} of the tensor encoding, but found {}",
+                VERSION, found
+            ),
+            DecodeError::UnknownElementType { tag } => {
+                write!(f, "Unknown element type, {}", tag)
+            },
+            DecodeError::ElementTypeMismatch { expected, found } => write!(
+                f,
+                "Expected a {} tensor, but found {}",
+                expected, found
+            ),
+            DecodeError::DataLengthMismatch { expected, found } => write!(
+                f,
+                "Expected {} bytes of data, but found {}",
+                expected, found
+            ),
+            DecodeError::TrailingData { bytes } => {
+                write!(f, "Found {} unused bytes after the elements", bytes)
+            },
+            DecodeError::Empty => write!(f, "The tensor has no elements"),
+            DecodeError::InvalidBool { found } => write!(
+                f,
+                "Expected a boolean to be 0 or 1, but found {}",
+                found
+            ),
+            DecodeError::InvalidUtf8 { .. } => {
+                write!(f, "A string wasn't valid UTF-8")
+            },
+            DecodeError::InvalidQuantization => {
+                write!(f, "Invalid quantization parameters")
+            },
+            DecodeError::IncompatibleQuantization { .. } => write!(
+                f,
+                "The quantization parameters don't match the tensor's \
+                 dimensions"
+            ),
+        }
+    }
+}
+
+impl crate::Error for DecodeError {
+    fn source(&self) -> Option<&(dyn crate::Error + 'static)> {
+        match self {
+            DecodeError::InvalidUtf8 { reason } => Some(reason),
+            DecodeError::IncompatibleQuantization { reason } => Some(reason),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::prelude::v1::*;
+
+    use super::*;
+
+    #[test]
+    fn known_header() {
+        let tensor: Tensor<u16> = [[1, 2], [3, 4], [5, 6]].into();
+        let mut buffer = Vec::new();
+
+        encode_tensor(&tensor, &mut buffer).unwrap();
+
+        let should_be = [
+            b'R', b'T', b'N', b'S', // magic
+            1,    // version
+            3,    // element type
+            2, 0, // rank
+            3, 0, 0, 0, 2, 0, 0, 0, // dimensions
+            0,    // quantization
+            12, 0, 0, 0, // data length
+            1, 0, 2, 0, 3, 0, 4, 0, 5, 0, 6, 0, // data
+        ];
+        assert_eq!(buffer, should_be);
+    }
+
+    #[test]
+    fn round_trip_several_tensors() {
+        let floats: Tensor<f32> = [[0.0, 1.5], [-2.0, 3.25]].into();
+        let strings: Tensor<Cow<'static, str>> =
+            Tensor::new_vector(vec!["cat".into(), "".into(), "dog".into()]);
+        let bools = Tensor::new_vector(vec![true, false]);
+        let complex = Tensor::single(Complex::new(1.0_f64, -1.0));
+        let mut buffer = Vec::new();
+
+        encode_tensor(&floats, &mut buffer).unwrap();
+        encode_tensor(&strings, &mut buffer).unwrap();
+        encode_tensor(&bools, &mut buffer).unwrap();
+        encode_tensor(&complex, &mut buffer).unwrap();
+
+        let (got_floats, rest) = decode_tensor::<f32>(&buffer).unwrap();
+        let (got_strings, rest) =
+            decode_tensor::<Cow<'static, str>>(rest).unwrap();
+        let (got_bools, rest) = decode_tensor::<bool>(rest).unwrap();
+        let (got_complex, rest) = decode_tensor::<Complex<f64>>(rest).unwrap();
+        assert_eq!(got_floats, floats);
+        assert_eq!(got_strings, strings);
+        assert_eq!(got_bools, bools);
+        assert_eq!(got_complex, complex);
+        assert!(rest.is_empty());
+
+        let shapes: Vec<_> = decode_all(&buffer)
+            .unwrap()
+            .into_iter()
+            .map(|t| t.shape.to_string())
+            .collect();
+        assert_eq!(shapes, ["f32[2, 2]", "utf8[3]", "bool[2]", "c128[1]"]);
+    }
+
+    #[test]
+    fn round_trip_a_tuple_of_tensors() {
+        let tensors: (Tensor<u8>, Tensor<f32>) = (
+            Tensor::new_vector(vec![1, 2, 3]),
+            [[0.5, -0.5], [1.0, 2.0]].into(),
+        );
+        let mut buffer = Vec::new();
+
+        tensors.encode_tensors(&mut buffer).unwrap();
+        let (got, rest) =
+            <(Tensor<u8>, Tensor<f32>)>::decode_tensors(&buffer).unwrap();
+
+        assert_eq!(got, tensors);
+        assert!(rest.is_empty());
+        assert_eq!(
+            <(Tensor<u8>, Tensor<u8>)>::decode_tensors(&buffer).unwrap_err(),
+            DecodeError::ElementTypeMismatch {
+                expected: ElementType::U8,
+                found: ElementType::F32,
+            }
+        );
+    }
+
+    #[test]
+    fn encode_raw_bytes() {
+        let shape = Shape::new(ElementType::I16, [2_usize].as_ref());
+        let data = [0xff, 0xff, 0x01, 0x00];
+        let mut buffer = Vec::new();
+
+        encode(&shape, &data, &mut buffer).unwrap();
+
+        let (tensor, _) = decode_tensor::<i16>(&buffer).unwrap();
+        assert_eq!(tensor.elements(), [-1, 1]);
+        assert_eq!(
+            encode(&shape, &data[..3], &mut Vec::new()),
+            Err(EncodeError::DataLengthMismatch {
+                expected: 4,
+                found: 3
+            })
+        );
+    }
+
+    #[test]
+    fn decoding_errors() {
+        let tensor = Tensor::new_vector(vec![1_u8, 2, 3]);
+        let mut buffer = Vec::new();
+        encode_tensor(&tensor, &mut buffer).unwrap();
+
+        assert_eq!(
+            decode(&buffer[..buffer.len() - 1]).unwrap_err(),
+            DecodeError::Truncated
+        );
+        assert_eq!(decode(b"JSON").unwrap_err(), DecodeError::Truncated);
+        assert_eq!(decode(b"{\"a\": 42}").unwrap_err(), DecodeError::BadMagic);
+        assert_eq!(
+            decode_tensor::<f32>(&buffer).unwrap_err(),
+            DecodeError::ElementTypeMismatch {
+                expected: ElementType::F32,
+                found: ElementType::U8,
+            }
+        );
+
+        let mut future_version = buffer.clone();
+        future_version[4] = VERSION + 1;
+        assert_eq!(
+            decode(&future_version).unwrap_err(),
+            DecodeError::UnsupportedVersion { found: VERSION + 1 }
+        );
+    }
+
+    #[test]
+    fn invalid_strings_report_the_utf8_error() {
+        let strings: Tensor<Cow<'static, str>> =
+            Tensor::new_vector(vec!["cat".into()]);
+        let mut buffer = Vec::new();
+        encode_tensor(&strings, &mut buffer).unwrap();
+        *buffer.last_mut().unwrap() = 0xff;
+
+        let err = decode_tensor::<Cow<'static, str>>(&buffer).unwrap_err();
+
+        let reason = core::str::from_utf8(b"ca\xff").unwrap_err();
+        assert_eq!(err, DecodeError::InvalidUtf8 { reason });
+        assert_eq!(
+            crate::error::display_chain(&err).to_string(),
+            format!("A string wasn't valid UTF-8: {}", reason)
+        );
+    }
+
+    #[test]
+    fn quantization_round_trips() {
+        let per_tensor = Tensor::new_vector(vec![1_u8, 2, 3])
+            .with_quantization(Quantization::per_tensor(0.5, 128))
+            .unwrap();
+        let per_channel: Tensor<i8> = [[1, 2], [3, 4]].into();
+        let per_channel = per_channel
+            .with_quantization(Quantization::per_channel(
+                0,
+                vec![0.25, 4.0],
+                vec![-1, 1],
+            ))
+            .unwrap();
+        let mut buffer = Vec::new();
+
+        encode_tensor(&per_tensor, &mut buffer).unwrap();
+        encode_tensor(&per_channel, &mut buffer).unwrap();
+
+        let (got_per_tensor, rest) = decode_tensor::<u8>(&buffer).unwrap();
+        let (got_per_channel, rest) = decode_tensor::<i8>(rest).unwrap();
+        assert_eq!(got_per_tensor, per_tensor);
+        assert_eq!(got_per_channel, per_channel);
+        assert!(rest.is_empty());
+    }
+
+    #[test]
+    fn unknown_dimensions_cant_be_encoded() {
+        let shape: Shape = "u8[?]".parse().unwrap();
+
+        let err = encode(&shape, &[1, 2, 3], &mut Vec::new()).unwrap_err();
+
+        assert_eq!(err, EncodeError::UnknownDimension { axis: 0 });
+    }
+
+    #[test]
+    fn element_type_tags_round_trip() {
+        let types = [
+            ElementType::U8,
+            ElementType::I8,
+            ElementType::U16,
+            ElementType::I16,
+            ElementType::U32,
+            ElementType::I32,
+            ElementType::F32,
+            ElementType::U64,
+            ElementType::I64,
+            ElementType::F64,
+            ElementType::String,
+            ElementType::F16,
+            ElementType::BF16,
+            ElementType::Bool,
+            ElementType::C64,
+            ElementType::C128,
+        ];
+
+        for ty in types {
+            let tag = element_type_tag(ty);
+            assert_eq!(element_type_from_tag(tag), Some(ty));
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use core::fmt::{self, Display, Formatter};
+#[cfg(not(feature = "std"))]
+use core::fmt::Debug;
+
+/// The `std::error::Error` trait, or an equivalent trait with the same
+/// methods when compiling without the standard library.
+///
+/// Every error in this crate implements it, so a Rune can propagate errors
+/// with `?` (keeping track of what caused them) instead of panicking.
+#[cfg(feature = "std")]
+pub use std::error::Error;
+
+/// A `no_std` stand-in for `std::error::Error`.
+///
+/// When the `std` feature is enabled this is a re-export of
+/// `std::error::Error` instead, so code written against this trait will
+/// compile either way.
+#[cfg(not(feature = "std"))]
+pub trait Error: Debug + Display {
+    /// The lower-level error that caused this one, if there is one.
+    fn source(&self) -> Option<&(dyn Error + 'static)> { None }
+}
+
+#[cfg(not(feature = "std"))]
+mod core_errors {
+    use super::Error;
+
+    impl Error for core::fmt::Error {}
+    impl Error for core::num::ParseFloatError {}
+    impl Error for core::num::ParseIntError {}
+    impl Error for core::num::TryFromIntError {}
+    impl Error for core::str::Utf8Error {}
+    impl Error for alloc::string::FromUtf8Error {}
+}
+
+/// Iterate over an error and everything that caused it, starting with the
+/// error itself.
+pub fn sources<'a>(
+    error: &'a (dyn Error + 'static),
+) -> impl Iterator<Item = &'a (dyn Error + 'static)> + 'a {
+    core::iter::successors(Some(error), |&e| e.source())
+}
+
+/// Display an error followed by each of its causes (e.g. `"\"x\" isn't a
+/// valid dimension (at byte 4): invalid digit found in string"`), which is
+/// handy when an error needs to be reported as a single message.
+pub fn display_chain<'a>(
+    error: &'a (dyn Error + 'static),
+) -> impl Display + 'a {
+    DisplayChain(error)
+}
+
+struct DisplayChain<'a>(&'a (dyn Error + 'static));
+
+impl Display for DisplayChain<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (i, error) in sources(self.0).enumerate() {
+            if i > 0 {
+                write!(f, ": ")?;
+            }
+            write!(f, "{}", error)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::prelude::v1::*;
+
+    use super::*;
+    use crate::PixelFormatConversionError;
+
+    #[test]
+    fn walk_the_chain() {
+        let inner = "x".parse::<u8>().unwrap_err();
+        let error = crate::FormatError::BadDimension {
+            found: String::from("x"),
+            offset: 4,
+            reason: inner.clone(),
+        };
+
+        let messages: Vec<_> =
+            sources(&error).map(|e| e.to_string()).collect();
+
+        assert_eq!(
+            messages,
+            vec![error.to_string(), inner.to_string()],
+        );
+        assert_eq!(
+            display_chain(&error).to_string(),
+            "\"x\" isn't a valid dimension (at byte 4): invalid digit found \
+             in string"
+        );
+    }
+
+    #[test]
+    fn errors_without_a_source() {
+        let error = PixelFormatConversionError::InvalidConstant { value: 42 };
+
+        assert_eq!(sources(&error).count(), 1);
+        assert_eq!(
+            display_chain(&error).to_string(),
+            "42 isn't a valid pixel format"
+        );
+    }
+}
@@ -4,8 +4,8 @@
 //!
 //! This crate has the following cargo feature flags:
 //!
-//! - `std` - enables functionality that requires the standard library
-//!   (typically implementations of `std::error::Error`)
+//! - `std` - enables functionality that requires the standard library (e.g.
+//!   [`Error`] becomes a re-export of `std::error::Error`)
 
 #![cfg_attr(not(feature = "std"), no_std)]
 #![cfg_attr(feature = "unstable_doc_cfg", feature(doc_cfg))]
@@ -17,21 +17,29 @@ extern crate std;
 extern crate alloc;
 
 mod element_type;
+pub mod encoding;
+pub mod error;
 mod logging;
 mod pixel_format;
+mod quantization;
 mod resources;
 mod shape;
 mod tensor;
 mod tensor_list;
 mod value;
 
+pub use half::{bf16, f16};
+pub use num_complex::Complex;
+
 pub use crate::{
     element_type::{AsElementType, ElementType, UnknownElementType},
+    error::Error,
     logging::SerializableRecord,
     pixel_format::{PixelFormat, PixelFormatConversionError},
+    quantization::Quantization,
     resources::{decode_inline_resource, InlineResource},
-    shape::Shape,
-    tensor::{Tensor, TensorView, TensorViewMut},
+    shape::{Dim, FormatError, Shape, ShapeError},
+    tensor::{IndexError, Tensor, TensorView, TensorViewMut},
     tensor_list::{TensorList, TensorListMut},
     value::{AsType, InvalidConversionError, Type, Value},
 };
@@ -44,10 +52,24 @@ pub const TF_MIMETYPE: &str = "application/tf-model";
 pub const ONNX_MIMETYPE: &str = "application/onnx-model";
 /// The mimetype used for a TensorFlow JS model.
 pub const TFJS_MIMETYPE: &str = "application/tfjs-model";
+/// The mimetype used for a TorchScript model (e.g. from `torch.jit.save()`).
+pub const TORCHSCRIPT_MIMETYPE: &str = "application/torchscript-model";
 
 /// The version number for this crate.
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 
+/// The version of the interface between a Rune and the runtime (i.e. the
+/// host functions a Rune imports and the functions it exports).
+///
+/// This is embedded in every Rune as a little-endian `u32` in the
+/// [`ABI_VERSION_CUSTOM_SECTION`] custom section and should be incremented
+/// whenever that interface changes in an incompatible way. Runes that predate
+/// the custom section are treated as version `0`.
+pub const ABI_VERSION: u32 = 1;
+
+/// The name of the custom section containing a Rune's [`ABI_VERSION`].
+pub const ABI_VERSION_CUSTOM_SECTION: &str = ".rune_abi_version";
+
 macro_rules! constants {
     ($name:ident { $(
         $(#[$constant_meta:meta])*
@@ -99,6 +121,34 @@ constants! {
         IMAGE = 4,
         RAW = 5,
         FLOAT_IMAGE = 6,
+        /// The device's location, as `[latitude, longitude, altitude,
+        /// accuracy]` readings.
+        GPS = 7,
+        /// Angular velocity readings from a gyroscope, as `[x, y, z]`
+        /// vectors.
+        GYRO = 8,
+        /// Temperature and, optionally, relative humidity readings.
+        THERMOMETER = 9,
+        /// The battery's charge level and voltage.
+        BATTERY = 10,
+        /// Air pressure readings, in hectopascals.
+        BAROMETER = 11,
+        /// The distance to the nearest object, in centimetres.
+        PROXIMITY = 12,
+        /// Ambient light readings, in lux.
+        LIGHT = 13,
+        /// A sequence of video frames, as a
+        /// `u8[frames, height, width, channels]` tensor.
+        VIDEO = 14,
+        /// Arbitrary tensors, typically loaded from NumPy `.npy` or `.npz`
+        /// files.
+        TENSOR = 15,
+        /// Rows of tabular data (e.g. from a CSV file), as a
+        /// `[rows, columns]` tensor.
+        TABULAR = 16,
+        /// A synthetic waveform (e.g. a sine wave or noise), as a
+        /// `f32[1, samples]` tensor.
+        SIGNAL = 17,
     }
 }
 
@@ -106,6 +156,8 @@ constants! {
     outputs {
         /// A serial device which consumes JSON-encoded data.
         SERIAL = 1,
+        /// A Bluetooth Low Energy characteristic which consumes JSON-encoded
+        /// data, in the same format as [`SERIAL`].
         BLE = 2,
         PIN = 3,
         WIFI = 4,
@@ -123,5 +175,8 @@ constants! {
         /// This pattern may be repeated an arbitrary number of times, depending
         /// on how many tensors are being outputted.
         TENSOR = 5,
+        /// An MQTT broker which consumes JSON-encoded data, in the same
+        /// format as [`SERIAL`].
+        MQTT = 6,
     }
 }
@@ -11,6 +11,9 @@ pub enum PixelFormat {
     RGB = 0,
     BGR = 1,
     GrayScale = 2,
+    RGBA = 3,
+    GrayScale16 = 4,
+    YUV420 = 5,
 }
 
 impl From<PixelFormat> for i32 {
@@ -33,6 +36,9 @@ impl TryFrom<i32> for PixelFormat {
             0 => Ok(PixelFormat::RGB),
             1 => Ok(PixelFormat::BGR),
             2 => Ok(PixelFormat::GrayScale),
+            3 => Ok(PixelFormat::RGBA),
+            4 => Ok(PixelFormat::GrayScale16),
+            5 => Ok(PixelFormat::YUV420),
             _ => Err(PixelFormatConversionError::InvalidConstant { value: i }),
         }
     }
@@ -72,9 +78,8 @@ impl Display for PixelFormatConversionError {
     }
 }
 
-#[cfg(feature = "std")]
-impl std::error::Error for PixelFormatConversionError {
-    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+impl crate::Error for PixelFormatConversionError {
+    fn source(&self) -> Option<&(dyn crate::Error + 'static)> {
         match self {
             PixelFormatConversionError::InvalidConstant { .. } => None,
             PixelFormatConversionError::Value(e) => Some(e),
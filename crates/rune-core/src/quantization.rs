@@ -0,0 +1,243 @@
+use alloc::vec::Vec;
+use core::{
+    hash::{Hash, Hasher},
+    ops::Range,
+};
+
+use crate::shape::{Dim, ShapeError};
+
+/// The parameters needed to interpret a quantized tensor's integer elements,
+/// where `real_value = scale * (quantized_value - zero_point)`.
+///
+/// Attaching these to a [`Shape`](crate::Shape) or [`Tensor`](crate::Tensor)
+/// means a proc block (or whoever consumes a Rune's outputs) can recover the
+/// real values without needing to know how the model was trained.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Quantization {
+    /// Every element in the tensor uses the same parameters.
+    PerTensor { scale: f32, zero_point: i32 },
+    /// Each slice along `axis` (typically a convolution's output channels)
+    /// has its own parameters.
+    PerChannel {
+        axis: usize,
+        scales: Vec<f32>,
+        zero_points: Vec<i32>,
+    },
+}
+
+impl Quantization {
+    pub const fn per_tensor(scale: f32, zero_point: i32) -> Self {
+        Quantization::PerTensor { scale, zero_point }
+    }
+
+    pub fn per_channel(
+        axis: usize,
+        scales: Vec<f32>,
+        zero_points: Vec<i32>,
+    ) -> Self {
+        Quantization::PerChannel {
+            axis,
+            scales,
+            zero_points,
+        }
+    }
+
+    /// The `(scale, zero_point)` pair used by a particular channel.
+    ///
+    /// The channel is ignored for [`Quantization::PerTensor`].
+    pub fn parameters(&self, channel: usize) -> Option<(f32, i32)> {
+        match self {
+            Quantization::PerTensor { scale, zero_point } => {
+                Some((*scale, *zero_point))
+            },
+            Quantization::PerChannel {
+                scales,
+                zero_points,
+                ..
+            } => Some((*scales.get(channel)?, *zero_points.get(channel)?)),
+        }
+    }
+
+    /// Convert a quantized value from a particular channel back into the
+    /// real number it represents.
+    pub fn dequantize(&self, value: i32, channel: usize) -> Option<f32> {
+        let (scale, zero_point) = self.parameters(channel)?;
+        Some(scale * (value - zero_point) as f32)
+    }
+
+    /// Make sure these parameters can be used with a tensor that has the
+    /// provided dimensions.
+    pub fn check(&self, dimensions: &[Dim]) -> Result<(), ShapeError> {
+        match self {
+            Quantization::PerTensor { .. } => Ok(()),
+            Quantization::PerChannel {
+                axis,
+                scales,
+                zero_points,
+            } => {
+                let length = crate::shape::check_axis(*axis, dimensions)?;
+
+                if scales.len() == zero_points.len()
+                    && length.accepts(scales.len())
+                {
+                    Ok(())
+                } else {
+                    Err(ShapeError::QuantizationMismatch {
+                        axis: *axis,
+                        scales: scales.len(),
+                        zero_points: zero_points.len(),
+                        length,
+                    })
+                }
+            },
+        }
+    }
+
+    /// The parameters to use after rearranging a tensor's dimensions with
+    /// [`Shape::transpose()`](crate::Shape::transpose).
+    pub(crate) fn transposed(&self, axes: &[usize]) -> Quantization {
+        match self {
+            Quantization::PerChannel {
+                axis,
+                scales,
+                zero_points,
+            } => {
+                let axis = axes
+                    .iter()
+                    .position(|a| a == axis)
+                    .expect("The permutation has already been checked");
+
+                Quantization::per_channel(
+                    axis,
+                    scales.clone(),
+                    zero_points.clone(),
+                )
+            },
+            other => other.clone(),
+        }
+    }
+
+    /// The parameters to use after taking `range` from the dimension at
+    /// `axis`.
+    pub(crate) fn sliced(
+        &self,
+        axis: usize,
+        range: Range<usize>,
+    ) -> Quantization {
+        match self {
+            Quantization::PerChannel {
+                axis: channel_axis,
+                scales,
+                zero_points,
+            } if *channel_axis == axis => Quantization::per_channel(
+                axis,
+                scales[range.clone()].to_vec(),
+                zero_points[range].to_vec(),
+            ),
+            other => other.clone(),
+        }
+    }
+
+    /// The parameters to keep when a tensor's dimensions are changed in a way
+    /// that loses track of which axis is which (e.g. a reshape).
+    pub(crate) fn without_channels(&self) -> Option<Quantization> {
+        match self {
+            Quantization::PerTensor { .. } => Some(self.clone()),
+            Quantization::PerChannel { .. } => None,
+        }
+    }
+}
+
+impl Hash for Quantization {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        core::mem::discriminant(self).hash(state);
+
+        match self {
+            Quantization::PerTensor { scale, zero_point } => {
+                scale.to_bits().hash(state);
+                zero_point.hash(state);
+            },
+            Quantization::PerChannel {
+                axis,
+                scales,
+                zero_points,
+            } => {
+                axis.hash(state);
+                for scale in scales {
+                    scale.to_bits().hash(state);
+                }
+                zero_points.hash(state);
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::prelude::v1::*;
+
+    use super::*;
+
+    #[test]
+    fn dequantize_per_tensor() {
+        let q = Quantization::per_tensor(0.5, -10);
+
+        assert_eq!(q.dequantize(-10, 0), Some(0.0));
+        assert_eq!(q.dequantize(0, 42), Some(5.0));
+    }
+
+    #[test]
+    fn dequantize_per_channel() {
+        let q = Quantization::per_channel(1, vec![0.5, 2.0], vec![0, 1]);
+
+        assert_eq!(q.dequantize(4, 0), Some(2.0));
+        assert_eq!(q.dequantize(4, 1), Some(6.0));
+        assert_eq!(q.dequantize(4, 2), None);
+    }
+
+    #[test]
+    fn per_channel_parameters_must_match_the_channel_axis() {
+        let q = Quantization::per_channel(1, vec![0.5, 2.0], vec![0, 1]);
+
+        assert!(q.check(&[Dim::Fixed(3), Dim::Fixed(2)]).is_ok());
+        assert!(q.check(&[Dim::Fixed(3), Dim::Unknown]).is_ok());
+        assert_eq!(
+            q.check(&[Dim::Fixed(2), Dim::Fixed(3)]).unwrap_err(),
+            ShapeError::QuantizationMismatch {
+                axis: 1,
+                scales: 2,
+                zero_points: 2,
+                length: Dim::Fixed(3),
+            }
+        );
+        assert_eq!(
+            q.check(&[Dim::Fixed(2)]).unwrap_err(),
+            ShapeError::AxisOutOfBounds { axis: 1, rank: 1 }
+        );
+    }
+
+    #[test]
+    fn transposing_moves_the_channel_axis() {
+        let q = Quantization::per_channel(0, vec![0.5, 2.0], vec![0, 1]);
+
+        let got = q.transposed(&[2, 1, 0]);
+
+        assert_eq!(
+            got,
+            Quantization::per_channel(2, vec![0.5, 2.0], vec![0, 1])
+        );
+    }
+
+    #[test]
+    fn slicing_the_channel_axis_slices_the_parameters() {
+        let q =
+            Quantization::per_channel(0, vec![0.5, 1.0, 2.0], vec![0, 1, 2]);
+
+        assert_eq!(
+            q.sliced(0, 1..3),
+            Quantization::per_channel(0, vec![1.0, 2.0], vec![1, 2])
+        );
+        assert_eq!(q.sliced(1, 0..1), q);
+    }
+}
@@ -4,59 +4,721 @@ use alloc::{
     vec::Vec,
 };
 use core::{
-    fmt::{self, Display, Formatter},
+    fmt::{self, Debug, Display, Formatter},
     num::ParseIntError,
+    ops::Range,
     str::FromStr,
 };
 
-use crate::element_type::ElementType;
+use crate::{element_type::ElementType, Quantization};
 
-/// A tensor's shape.
+/// A single dimension in a [`Shape`].
 #[derive(
-    Debug, Clone, PartialEq, Hash, serde::Serialize, serde::Deserialize,
+    Copy, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize,
 )]
+#[serde(from = "Option<usize>", into = "Option<usize>")]
+pub enum Dim {
+    /// A dimension whose length is known ahead of time.
+    Fixed(usize),
+    /// A dimension which will only be known at runtime (e.g. the batch size
+    /// or the length of an audio clip), written as `?`.
+    Unknown,
+}
+
+impl Dim {
+    /// The length of this dimension, if it is known.
+    pub fn value(self) -> Option<usize> {
+        match self {
+            Dim::Fixed(n) => Some(n),
+            Dim::Unknown => None,
+        }
+    }
+
+    pub fn is_fixed(self) -> bool { self.value().is_some() }
+
+    /// Can a tensor with `length` items along this dimension be used here?
+    pub fn accepts(self, length: usize) -> bool {
+        match self {
+            Dim::Fixed(n) => n == length,
+            Dim::Unknown => true,
+        }
+    }
+
+    /// Combine two dimensions which should have the same length, keeping
+    /// whichever length is known.
+    pub fn merge(self, other: Dim) -> Option<Dim> {
+        match (self, other) {
+            (Dim::Fixed(a), Dim::Fixed(b)) if a != b => None,
+            (Dim::Unknown, other) => Some(other),
+            (this, _) => Some(this),
+        }
+    }
+
+    /// The dimension you get when broadcasting these two dimensions together
+    /// using NumPy's rules, where a dimension of length `1` is stretched to
+    /// match the other one.
+    ///
+    /// Unknown dimensions are assumed to broadcast successfully, deferring
+    /// the check until the actual lengths are known.
+    pub fn broadcast(self, other: Dim) -> Option<Dim> {
+        match (self, other) {
+            (Dim::Fixed(1), other) => Some(other),
+            (this, Dim::Fixed(1)) => Some(this),
+            (Dim::Fixed(a), Dim::Fixed(b)) if a != b => None,
+            (Dim::Unknown, other) => Some(other),
+            (this, _) => Some(this),
+        }
+    }
+}
+
+impl From<usize> for Dim {
+    fn from(n: usize) -> Self { Dim::Fixed(n) }
+}
+
+impl From<Option<usize>> for Dim {
+    fn from(n: Option<usize>) -> Self {
+        n.map(Dim::Fixed).unwrap_or(Dim::Unknown)
+    }
+}
+
+impl From<Dim> for Option<usize> {
+    fn from(d: Dim) -> Self { d.value() }
+}
+
+impl Display for Dim {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Dim::Fixed(n) => write!(f, "{}", n),
+            Dim::Unknown => write!(f, "?"),
+        }
+    }
+}
+
+impl Debug for Dim {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        // Note: Shape's Debug output is much easier to read when dimensions
+        // are printed as "[1, ?, 3]" instead of "[Fixed(1), Unknown, ..]"
+        Display::fmt(self, f)
+    }
+}
+
+/// A tensor's shape.
+///
+/// Some dimensions may be [`Dim::Unknown`], in which case the shape
+/// describes a family of tensors (e.g. `f32[?, 1960]`) rather than one
+/// particular tensor.
+#[derive(Clone, PartialEq, Hash, serde::Serialize, serde::Deserialize)]
 pub struct Shape<'a> {
     element_type: ElementType,
-    dimensions: Cow<'a, [usize]>,
+    dimensions: Cow<'a, [Dim]>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    quantization: Option<Cow<'a, Quantization>>,
+    /// Human-friendly names for each dimension (e.g. `"batch"`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    dimension_names: Option<Cow<'a, [Option<String>]>>,
 }
 
 impl<'a> Shape<'a> {
+    /// Create a [`Shape`] where every dimension is known.
     pub fn new(
         element_type: ElementType,
         dimensions: impl Into<Cow<'a, [usize]>>,
+    ) -> Self {
+        let dimensions: Vec<Dim> =
+            dimensions.into().iter().copied().map(Dim::Fixed).collect();
+
+        Shape {
+            element_type,
+            dimensions: dimensions.into(),
+            quantization: None,
+            dimension_names: None,
+        }
+    }
+
+    /// Create a [`Shape`] which may contain [`Dim::Unknown`] dimensions.
+    pub fn with_dims(
+        element_type: ElementType,
+        dimensions: impl Into<Cow<'a, [Dim]>>,
     ) -> Self {
         Shape {
             element_type,
             dimensions: dimensions.into(),
+            quantization: None,
+            dimension_names: None,
+        }
+    }
+
+    /// Attach the parameters needed to interpret a quantized tensor's
+    /// elements.
+    pub fn with_quantization(
+        self,
+        quantization: Quantization,
+    ) -> Result<Self, ShapeError> {
+        quantization.check(&self.dimensions)?;
+
+        Ok(Shape {
+            quantization: Some(Cow::Owned(quantization)),
+            ..self
+        })
+    }
+
+    /// Give some or all of the dimensions a name (e.g. `"batch"` or
+    /// `"height"`), which will be shown when the shape is displayed.
+    ///
+    /// ```rust
+    /// use hotg_rune_core::Shape;
+    ///
+    /// let shape: Shape = "f32[?, 1960]".parse().unwrap();
+    ///
+    /// let named = shape.with_names([Some("batch"), None]).unwrap();
+    ///
+    /// assert_eq!(named.to_string(), "f32[batch: ?, 1960]");
+    /// assert_eq!(named.axis("batch"), Some(0));
+    /// ```
+    pub fn with_names<I, S>(self, names: I) -> Result<Self, ShapeError>
+    where
+        I: IntoIterator<Item = Option<S>>,
+        S: Into<String>,
+    {
+        let names: Vec<Option<String>> = names
+            .into_iter()
+            .map(|name| name.map(Into::into))
+            .collect();
+
+        if names.len() != self.rank() {
+            return Err(ShapeError::NameCountMismatch {
+                names: names.len(),
+                rank: self.rank(),
+            });
+        }
+
+        if let Some(name) = names.iter().flatten().find(|n| !is_valid_name(n))
+        {
+            return Err(ShapeError::InvalidDimensionName { name: name.clone() });
+        }
+
+        Ok(Shape {
+            dimension_names: Some(names.into()),
+            ..self
+        })
+    }
+
+    /// Borrow quantization parameters which are already known to match this
+    /// shape.
+    pub(crate) fn quantized_by(self, quantization: &'a Quantization) -> Self {
+        Shape {
+            quantization: Some(Cow::Borrowed(quantization)),
+            ..self
         }
     }
 
     pub fn element_type(&self) -> ElementType { self.element_type }
 
-    pub fn dimensions(&self) -> &[usize] { &self.dimensions }
+    pub fn dimensions(&self) -> &[Dim] { &self.dimensions }
+
+    /// The parameters for interpreting a quantized tensor, if there are any.
+    pub fn quantization(&self) -> Option<&Quantization> {
+        self.quantization.as_deref()
+    }
+
+    /// The name of each dimension, if any were provided.
+    pub fn dimension_names(&self) -> Option<&[Option<String>]> {
+        self.dimension_names.as_deref()
+    }
 
-    /// The number of bytes this tensor would take up, if it has a fized size.
+    /// The name of the dimension at `axis`, if it has one.
+    pub fn dimension_name(&self, axis: usize) -> Option<&str> {
+        self.dimension_names()?.get(axis)?.as_deref()
+    }
+
+    /// Find the dimension with a particular name.
+    pub fn axis(&self, name: &str) -> Option<usize> {
+        self.dimension_names()?
+            .iter()
+            .position(|n| n.as_deref() == Some(name))
+    }
+
+    pub fn rank(&self) -> usize { self.dimensions.len() }
+
+    /// Is the length of every dimension known?
+    pub fn is_fixed(&self) -> bool {
+        self.dimensions.iter().all(|d| d.is_fixed())
+    }
+
+    /// The dimensions of this shape, if they are all known.
+    pub fn fixed_dimensions(&self) -> Option<Vec<usize>> {
+        self.dimensions.iter().map(|d| d.value()).collect()
+    }
+
+    /// The number of bytes this tensor would take up, if it has a fixed size.
     pub fn size(&self) -> Option<usize> {
         let element_size = self.element_type.size_of()?;
+        let mut elements = 1;
+
+        for dim in self.dimensions.iter() {
+            elements *= dim.value()?;
+        }
 
-        Some(self.dimensions.iter().product::<usize>() * element_size)
+        Some(elements * element_size)
+    }
+
+    /// Check whether a tensor with the `other` shape can be used where this
+    /// shape is expected.
+    ///
+    /// The element types and ranks must match, and every known dimension
+    /// must have the same length. A [`Dim::Unknown`] dimension accepts any
+    /// length, but an unknown dimension in `other` is only accepted if this
+    /// dimension is also unknown.
+    ///
+    /// ```rust
+    /// use hotg_rune_core::Shape;
+    ///
+    /// let expected: Shape = "f32[?, 1960]".parse().unwrap();
+    ///
+    /// assert!(expected.accepts(&"f32[1, 1960]".parse().unwrap()));
+    /// assert!(expected.accepts(&"f32[?, 1960]".parse().unwrap()));
+    /// assert!(!expected.accepts(&"f32[1, 42]".parse().unwrap()));
+    /// assert!(!expected.accepts(&"u8[1, 1960]".parse().unwrap()));
+    /// ```
+    pub fn accepts(&self, other: &Shape<'_>) -> bool {
+        self.element_type == other.element_type
+            && self.rank() == other.rank()
+            && self.dimensions.iter().zip(other.dimensions.iter()).all(
+                |(&expected, &actual)| match actual {
+                    Dim::Fixed(length) => expected.accepts(length),
+                    Dim::Unknown => expected == Dim::Unknown,
+                },
+            )
+    }
+
+    /// Could both shapes describe the same tensor?
+    ///
+    /// This is like [`Shape::accepts()`], except an unknown dimension on
+    /// either side is compatible with anything.
+    pub fn is_compatible_with(&self, other: &Shape<'_>) -> bool {
+        self.merge(other).is_ok()
+    }
+
+    /// Combine two shapes which should describe the same tensor, filling in
+    /// any unknown dimensions with the lengths from the other shape.
+    ///
+    /// ```rust
+    /// use hotg_rune_core::Shape;
+    ///
+    /// let a: Shape = "f32[?, 3, ?]".parse().unwrap();
+    /// let b: Shape = "f32[1, ?, ?]".parse().unwrap();
+    ///
+    /// assert_eq!(a.merge(&b).unwrap().to_string(), "f32[1, 3, ?]");
+    /// ```
+    pub fn merge(
+        &self,
+        other: &Shape<'_>,
+    ) -> Result<Shape<'static>, ShapeError> {
+        self.check_element_types(other)?;
+
+        if self.rank() != other.rank() {
+            return Err(ShapeError::RankMismatch {
+                left: self.rank(),
+                right: other.rank(),
+            });
+        }
+
+        let dimensions = self
+            .dimensions
+            .iter()
+            .zip(other.dimensions.iter())
+            .enumerate()
+            .map(|(axis, (&left, &right))| {
+                left.merge(right).ok_or(ShapeError::DimensionMismatch {
+                    axis,
+                    left,
+                    right,
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let quantization = self.quantization().or_else(|| other.quantization());
+        let names = collect_names((0..self.rank()).map(|axis| {
+            self.dimension_name(axis)
+                .or_else(|| other.dimension_name(axis))
+        }));
+
+        Ok(Shape::from_parts(
+            self.element_type,
+            dimensions,
+            quantization.cloned(),
+            names,
+        ))
+    }
+
+    /// Figure out the shape you would get by combining two tensors with
+    /// NumPy-style broadcasting.
+    ///
+    /// The shapes are aligned on their trailing dimensions, and each pair of
+    /// dimensions must either be equal or one of them must be `1`.
+    ///
+    /// ```rust
+    /// use hotg_rune_core::Shape;
+    ///
+    /// let a: Shape = "f32[8, 1, 6, 1]".parse().unwrap();
+    /// let b: Shape = "f32[7, 1, 5]".parse().unwrap();
+    ///
+    /// let got = a.broadcast_with(&b).unwrap();
+    ///
+    /// assert_eq!(got.to_string(), "f32[8, 7, 6, 5]");
+    /// ```
+    pub fn broadcast_with(
+        &self,
+        other: &Shape<'_>,
+    ) -> Result<Shape<'static>, ShapeError> {
+        self.check_element_types(other)?;
+
+        let rank = core::cmp::max(self.rank(), other.rank());
+        let mut dimensions = Vec::with_capacity(rank);
+        let mut names = Vec::with_capacity(rank);
+
+        for i in 0..rank {
+            // Note: missing leading dimensions behave like they are 1
+            let left = trailing_dimension(&self.dimensions, i);
+            let right = trailing_dimension(&other.dimensions, i);
+
+            let dim = left.broadcast(right).ok_or(
+                ShapeError::DimensionMismatch {
+                    axis: rank - i - 1,
+                    left,
+                    right,
+                },
+            )?;
+            dimensions.push(dim);
+            names.push(
+                self.trailing_name(i).or_else(|| other.trailing_name(i)),
+            );
+        }
+
+        dimensions.reverse();
+        names.reverse();
+
+        Ok(Shape::from_parts(
+            self.element_type,
+            dimensions,
+            None,
+            collect_names(names),
+        ))
+    }
+
+    /// Give the shape new dimensions without changing the number of
+    /// elements.
+    ///
+    /// If the number of elements is known, a single [`Dim::Unknown`] in
+    /// `dimensions` will be inferred from the others.
+    ///
+    /// ```rust
+    /// use hotg_rune_core::{Dim, Shape};
+    ///
+    /// let shape: Shape = "f32[1, 28, 28]".parse().unwrap();
+    ///
+    /// let got = shape.reshape(&[Dim::Fixed(1), Dim::Unknown]).unwrap();
+    ///
+    /// assert_eq!(got.to_string(), "f32[1, 784]");
+    /// ```
+    pub fn reshape(
+        &self,
+        dimensions: &[Dim],
+    ) -> Result<Shape<'static>, ShapeError> {
+        let mut dimensions = dimensions.to_vec();
+
+        let original = match self.fixed_dimensions() {
+            Some(d) => d.iter().product::<usize>(),
+            // We can't check anything without knowing the element count
+            None => return Ok(self.reshaped(dimensions)),
+        };
+        let known: usize =
+            dimensions.iter().filter_map(|d| d.value()).product();
+        let unknown: Vec<usize> = dimensions
+            .iter()
+            .enumerate()
+            .filter(|(_, d)| !d.is_fixed())
+            .map(|(i, _)| i)
+            .collect();
+
+        match unknown.as_slice() {
+            [] if known == original => {},
+            [axis] if known != 0 && original % known == 0 => {
+                dimensions[*axis] = Dim::Fixed(original / known);
+            },
+            [] | [_] => {
+                return Err(ShapeError::ElementCountMismatch {
+                    original,
+                    reshaped: known,
+                });
+            },
+            // There are multiple ways to fill in the unknowns, so leave them
+            _ => {},
+        }
+
+        Ok(self.reshaped(dimensions))
+    }
+
+    /// Remove dimensions with a length of `1`, either the one at `axis` or
+    /// all of them if no axis is specified.
+    ///
+    /// Tensors always have at least one dimension, so squeezing a shape
+    /// where every dimension is `1` will leave a single dimension.
+    pub fn squeeze(
+        &self,
+        axis: Option<usize>,
+    ) -> Result<Shape<'static>, ShapeError> {
+        let axes: Vec<usize> = (0..self.rank()).collect();
+        let kept = squeeze(&axes, axis, |&i| self.dimensions[i])?;
+
+        let dimensions = kept.iter().map(|&i| self.dimensions[i]).collect();
+        let names = collect_names(kept.iter().map(|&i| self.dimension_name(i)));
+
+        Ok(Shape {
+            dimension_names: names.map(Cow::Owned),
+            ..self.reshaped(dimensions)
+        })
+    }
+
+    /// Rearrange the dimensions so the `i`'th dimension in the new shape
+    /// is `axes[i]` in the old one.
+    pub fn transpose(
+        &self,
+        axes: &[usize],
+    ) -> Result<Shape<'static>, ShapeError> {
+        check_permutation(axes, self.rank())?;
+        let dimensions: Vec<Dim> =
+            axes.iter().map(|&axis| self.dimensions[axis]).collect();
+        let quantization = self.quantization().map(|q| q.transposed(axes));
+        let names =
+            collect_names(axes.iter().map(|&axis| self.dimension_name(axis)));
+
+        Ok(Shape::from_parts(
+            self.element_type,
+            dimensions,
+            quantization,
+            names,
+        ))
+    }
+
+    /// The shape you get when taking `range` from the dimension at `axis`.
+    pub fn slice(
+        &self,
+        axis: usize,
+        range: Range<usize>,
+    ) -> Result<Shape<'static>, ShapeError> {
+        let length = check_axis(axis, self.dimensions())?;
+        check_range(axis, &range, length)?;
+
+        let mut dimensions = self.dimensions.to_vec();
+        dimensions[axis] = Dim::Fixed(range.end - range.start);
+        let quantization = self.quantization().map(|q| q.sliced(axis, range));
+        let names = self.dimension_names().map(|names| names.to_vec());
+
+        Ok(Shape::from_parts(
+            self.element_type,
+            dimensions,
+            quantization,
+            names,
+        ))
+    }
+
+    /// A shape with new dimensions, keeping any quantization parameters that
+    /// don't depend on a particular axis.
+    ///
+    /// Dimension names are dropped because there's no way to know which of
+    /// the new dimensions they belong to.
+    fn reshaped(&self, dimensions: Vec<Dim>) -> Shape<'static> {
+        let quantization =
+            self.quantization().and_then(Quantization::without_channels);
+
+        Shape::from_parts(self.element_type, dimensions, quantization, None)
+    }
+
+    /// The name of the `i`'th dimension, counting backwards from the end.
+    fn trailing_name(&self, i: usize) -> Option<&str> {
+        let axis = self.rank().checked_sub(i + 1)?;
+        self.dimension_name(axis)
+    }
+
+    fn from_parts(
+        element_type: ElementType,
+        dimensions: Vec<Dim>,
+        quantization: Option<Quantization>,
+        dimension_names: Option<Vec<Option<String>>>,
+    ) -> Shape<'static> {
+        Shape {
+            element_type,
+            dimensions: dimensions.into(),
+            quantization: quantization.map(Cow::Owned),
+            dimension_names: dimension_names.map(Cow::Owned),
+        }
+    }
+
+    fn check_element_types(&self, other: &Shape<'_>) -> Result<(), ShapeError> {
+        if self.element_type == other.element_type {
+            Ok(())
+        } else {
+            Err(ShapeError::ElementTypeMismatch {
+                left: self.element_type,
+                right: other.element_type,
+            })
+        }
     }
 
     pub fn to_owned(&self) -> Shape<'static> {
         let Shape {
             element_type,
             dimensions,
+            quantization,
+            dimension_names,
         } = self;
 
-        Shape::new(*element_type, dimensions.clone().into_owned())
+        Shape::from_parts(
+            *element_type,
+            dimensions.clone().into_owned(),
+            quantization.clone().map(Cow::into_owned),
+            dimension_names.clone().map(Cow::into_owned),
+        )
+    }
+}
+
+pub(crate) fn check_axis<D: Copy>(
+    axis: usize,
+    dimensions: &[D],
+) -> Result<D, ShapeError> {
+    dimensions
+        .get(axis)
+        .copied()
+        .ok_or(ShapeError::AxisOutOfBounds {
+            axis,
+            rank: dimensions.len(),
+        })
+}
+
+pub(crate) fn check_permutation(
+    axes: &[usize],
+    rank: usize,
+) -> Result<(), ShapeError> {
+    let mut seen = alloc::vec![false; rank];
+
+    for &axis in axes {
+        match seen.get_mut(axis) {
+            Some(slot) if !*slot => *slot = true,
+            _ => break,
+        }
+    }
+
+    if axes.len() == rank && seen.iter().all(|&s| s) {
+        Ok(())
+    } else {
+        Err(ShapeError::InvalidPermutation {
+            axes: axes.to_vec(),
+            rank,
+        })
+    }
+}
+
+pub(crate) fn check_range(
+    axis: usize,
+    range: &Range<usize>,
+    length: impl Into<Dim>,
+) -> Result<(), ShapeError> {
+    let length = length.into();
+    let in_bounds = match length {
+        Dim::Fixed(n) => range.end <= n,
+        Dim::Unknown => true,
+    };
+
+    if range.start <= range.end && in_bounds {
+        Ok(())
+    } else {
+        Err(ShapeError::OutOfRange {
+            axis,
+            start: range.start,
+            end: range.end,
+            length,
+        })
+    }
+}
+
+/// Remove the dimensions with a length of `1`, as described in
+/// [`Shape::squeeze()`].
+pub(crate) fn squeeze<D: Copy>(
+    dimensions: &[D],
+    axis: Option<usize>,
+    to_dim: impl Fn(&D) -> Dim,
+) -> Result<Vec<D>, ShapeError> {
+    let is_one = |d: &D| to_dim(d) == Dim::Fixed(1);
+
+    let mut squeezed: Vec<D> = match axis {
+        Some(axis) => {
+            let dim = check_axis(axis, dimensions)?;
+
+            if !is_one(&dim) {
+                return Err(ShapeError::NotSqueezable {
+                    axis,
+                    dim: to_dim(&dim),
+                });
+            }
+
+            let mut squeezed = dimensions.to_vec();
+            squeezed.remove(axis);
+            squeezed
+        },
+        None => dimensions.iter().filter(|d| !is_one(d)).copied().collect(),
+    };
+
+    if squeezed.is_empty() {
+        if let Some(&first) = dimensions.first() {
+            squeezed.push(first);
+        }
+    }
+
+    Ok(squeezed)
+}
+
+/// Get the `i`'th dimension, counting backwards from the end.
+fn trailing_dimension(dimensions: &[Dim], i: usize) -> Dim {
+    dimensions
+        .len()
+        .checked_sub(i + 1)
+        .map(|ix| dimensions[ix])
+        .unwrap_or(Dim::Fixed(1))
+}
+
+/// Copy a list of dimension names, returning `None` if none of the dimensions
+/// are named.
+fn collect_names<'n>(
+    names: impl IntoIterator<Item = Option<&'n str>>,
+) -> Option<Vec<Option<String>>> {
+    let names: Vec<Option<String>> = names
+        .into_iter()
+        .map(|name| name.map(String::from))
+        .collect();
+
+    if names.iter().any(Option::is_some) {
+        Some(names)
+    } else {
+        None
     }
 }
 
+/// Dimension names can't contain anything that would confuse
+/// [`Shape::from_str()`].
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.trim() == name
+        && !name.contains(|c| matches!(c, ',' | ':' | '[' | ']'))
+}
+
 impl<'a> Display for Shape<'a> {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         let Shape {
             element_type,
             dimensions,
+            quantization: _,
+            dimension_names: _,
         } = self;
         write!(f, "{}[", element_type)?;
 
@@ -65,6 +727,10 @@ impl<'a> Display for Shape<'a> {
                 write!(f, ", ")?;
             }
 
+            if let Some(name) = self.dimension_name(i) {
+                write!(f, "{}: ", name)?;
+            }
+
             write!(f, "{}", dim)?;
         }
 
@@ -73,71 +739,200 @@ impl<'a> Display for Shape<'a> {
     }
 }
 
+/// Parse a shape like `f32[1, 28, 28]`.
+///
+/// Whitespace is ignored, `?`, `_`, and `*` can all be used for unknown
+/// dimensions, dimensions may be named (`f32[batch: ?, 1960]`), a trailing
+/// comma is allowed, and `f32[]` is a scalar.
 impl FromStr for Shape<'static> {
     type Err = FormatError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let opening_bracket = s.find('[').ok_or(FormatError::Malformed)?;
-        let element_type = s[..opening_bracket].trim();
+        let opening_bracket = s
+            .find('[')
+            .ok_or(FormatError::Malformed { offset: s.len() })?;
+        // Check the brackets first, otherwise something like "f32]1[" would
+        // be reported as an unknown element type
+        let closing_bracket = match s.rfind(']') {
+            Some(ix) if ix > opening_bracket => ix,
+            _ => return Err(FormatError::Malformed { offset: s.len() }),
+        };
+        let (trailing, offset) = trimmed(s, closing_bracket + 1..s.len());
+        if !trailing.is_empty() {
+            return Err(FormatError::Malformed { offset });
+        }
+
+        let (element_type, type_offset) = trimmed(s, 0..opening_bracket);
         let ty = element_type.parse().map_err(|_| {
             FormatError::UnknownElementType {
                 found: element_type.to_string(),
+                offset: type_offset,
             }
         })?;
 
-        let closing_bracket = s.rfind(']').ok_or(FormatError::Malformed)?;
+        let mut dimensions = Vec::new();
+        let mut names = Vec::new();
+        let mut segment_start = opening_bracket + 1;
+        let segments: Vec<&str> =
+            s[segment_start..closing_bracket].split(',').collect();
+
+        for (i, segment) in segments.iter().enumerate() {
+            let range = segment_start..segment_start + segment.len();
+            segment_start = range.end + 1;
 
-        let between_brackets = &s[opening_bracket + 1..closing_bracket];
+            let (word, offset) = trimmed(s, range.clone());
+            let is_last = i + 1 == segments.len();
 
-        let mut dimensions = Vec::new();
+            if word.is_empty() && is_last {
+                // Allow "f32[]" and trailing commas like "f32[1, 2,]"
+                break;
+            }
+
+            let (name, (word, offset)) = match word.find(':') {
+                Some(colon) => {
+                    let name = trimmed(s, offset..offset + colon);
+                    (Some(name), trimmed(s, offset + colon + 1..range.end))
+                },
+                None => (None, (word, offset)),
+            };
+
+            if let Some((name, offset)) = name {
+                if !is_valid_name(name) {
+                    return Err(FormatError::BadName {
+                        found: name.to_string(),
+                        offset,
+                    });
+                }
+            }
+            names.push(name.map(|(name, _)| name));
+
+            if matches!(word, "?" | "_" | "*") {
+                dimensions.push(Dim::Unknown);
+                continue;
+            }
 
-        for word in between_brackets.split(',') {
-            let word = word.trim();
             let dimension = word.parse::<usize>().map_err(|e| {
                 FormatError::BadDimension {
                     found: word.to_string(),
+                    offset,
                     reason: e,
                 }
             })?;
-            dimensions.push(dimension);
+            dimensions.push(Dim::Fixed(dimension));
         }
 
-        Ok(Shape {
-            element_type: ty,
-            dimensions: dimensions.into(),
-        })
+        let shape = Shape::with_dims(ty, dimensions);
+
+        if names.iter().all(Option::is_none) {
+            return Ok(shape);
+        }
+
+        Ok(shape
+            .with_names(names)
+            .expect("The names were already checked"))
     }
 }
 
+/// Get `s[range]` without any leading or trailing whitespace, plus the byte
+/// offset it starts at.
+fn trimmed(s: &str, range: Range<usize>) -> (&str, usize) {
+    let text = &s[range.clone()];
+    let without_leading = text.trim_start();
+    let offset = range.start + (text.len() - without_leading.len());
+
+    (without_leading.trim_end(), offset)
+}
+
+impl<'a> Debug for Shape<'a> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let Shape {
+            element_type,
+            dimensions,
+            quantization,
+            dimension_names,
+        } = self;
+
+        let mut s = f.debug_struct("Shape");
+        s.field("element_type", element_type)
+            .field("dimensions", dimensions);
+
+        // Most tensors aren't quantized or named, so we only mention it when
+        // they are
+        if let Some(quantization) = quantization {
+            s.field("quantization", quantization);
+        }
+        if let Some(dimension_names) = dimension_names {
+            s.field("dimension_names", dimension_names);
+        }
+
+        s.finish()
+    }
+}
+
+/// The error returned when a [`Shape`] can't be parsed.
+///
+/// Each variant records the byte offset of the problem so callers can point
+/// at the exact location in the original text.
 #[derive(Debug, Clone, PartialEq)]
 pub enum FormatError {
-    Malformed,
+    /// The shape wasn't of the form `type[dimensions]`.
+    Malformed {
+        offset: usize,
+    },
     UnknownElementType {
         found: String,
+        offset: usize,
     },
     BadDimension {
         found: String,
+        offset: usize,
         reason: ParseIntError,
     },
+    BadName {
+        found: String,
+        offset: usize,
+    },
+}
+
+impl FormatError {
+    /// The byte offset of the problem within the original string.
+    pub fn offset(&self) -> usize {
+        match self {
+            FormatError::Malformed { offset }
+            | FormatError::UnknownElementType { offset, .. }
+            | FormatError::BadDimension { offset, .. }
+            | FormatError::BadName { offset, .. } => *offset,
+        }
+    }
 }
 
 impl Display for FormatError {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
-            FormatError::Malformed => write!(f, "Malformed shape"),
-            FormatError::UnknownElementType { found } => {
-                write!(f, "Couldn't recognise the \"{}\" element type", found)
-            },
-            FormatError::BadDimension { found, .. } => {
-                write!(f, "\"{}\" isn't a valid dimension", found)
+            FormatError::Malformed { offset } => {
+                write!(f, "Malformed shape at byte {}", offset)
             },
+            FormatError::UnknownElementType { found, offset } => write!(
+                f,
+                "Couldn't recognise the \"{}\" element type at byte {}",
+                found, offset
+            ),
+            FormatError::BadDimension { found, offset, .. } => write!(
+                f,
+                "\"{}\" isn't a valid dimension (at byte {})",
+                found, offset
+            ),
+            FormatError::BadName { found, offset } => write!(
+                f,
+                "\"{}\" isn't a valid dimension name (at byte {})",
+                found, offset
+            ),
         }
     }
 }
 
-#[cfg(feature = "std")]
-impl std::error::Error for FormatError {
-    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+impl crate::Error for FormatError {
+    fn source(&self) -> Option<&(dyn crate::Error + 'static)> {
         match self {
             FormatError::BadDimension { reason, .. } => Some(reason),
             _ => None,
@@ -145,27 +940,233 @@ impl std::error::Error for FormatError {
     }
 }
 
+/// The error returned when two [`Shape`]s can't be combined, or a [`Shape`]
+/// can't be manipulated in the requested way.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ShapeError {
+    ElementTypeMismatch {
+        left: ElementType,
+        right: ElementType,
+    },
+    RankMismatch {
+        left: usize,
+        right: usize,
+    },
+    DimensionMismatch {
+        axis: usize,
+        left: Dim,
+        right: Dim,
+    },
+    AxisOutOfBounds {
+        axis: usize,
+        rank: usize,
+    },
+    ElementCountMismatch {
+        original: usize,
+        reshaped: usize,
+    },
+    NotSqueezable {
+        axis: usize,
+        dim: Dim,
+    },
+    InvalidPermutation {
+        axes: Vec<usize>,
+        rank: usize,
+    },
+    OutOfRange {
+        axis: usize,
+        start: usize,
+        end: usize,
+        length: Dim,
+    },
+    QuantizationMismatch {
+        axis: usize,
+        scales: usize,
+        zero_points: usize,
+        length: Dim,
+    },
+    NameCountMismatch {
+        names: usize,
+        rank: usize,
+    },
+    InvalidDimensionName {
+        name: String,
+    },
+}
+
+impl Display for ShapeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ShapeError::ElementTypeMismatch { left, right } => write!(
+                f,
+                "Expected both element types to be the same, but found {} \
+                 and {}",
+                left, right
+            ),
+            ShapeError::RankMismatch { left, right } => write!(
+                f,
+                "Expected both shapes to have the same rank, but found {} \
+                 and {}",
+                left, right
+            ),
+            ShapeError::DimensionMismatch { axis, left, right } => write!(
+                f,
+                "Dimension {} has incompatible lengths, {} and {}",
+                axis, left, right
+            ),
+            ShapeError::AxisOutOfBounds { axis, rank } => write!(
+                f,
+                "Axis {} is out of bounds for a shape with {} dimensions",
+                axis, rank
+            ),
+            ShapeError::ElementCountMismatch { original, reshaped } => write!(
+                f,
+                "Unable to reshape {} elements into a shape with {} elements",
+                original, reshaped
+            ),
+            ShapeError::NotSqueezable { axis, dim } => write!(
+                f,
+                "Unable to squeeze dimension {} because its length is {}, \
+                 not 1",
+                axis, dim
+            ),
+            ShapeError::InvalidPermutation { axes, rank } => write!(
+                f,
+                "{:?} isn't a valid ordering for {} dimensions",
+                axes, rank
+            ),
+            ShapeError::OutOfRange {
+                axis,
+                start,
+                end,
+                length,
+            } => write!(
+                f,
+                "Unable to take {}..{} from dimension {} because its length \
+                 is {}",
+                start, end, axis, length
+            ),
+            ShapeError::QuantizationMismatch {
+                axis,
+                scales,
+                zero_points,
+                length,
+            } => write!(
+                f,
+                "Found {} scales and {} zero points for dimension {}, but \
+                 its length is {}",
+                scales, zero_points, axis, length
+            ),
+            ShapeError::NameCountMismatch { names, rank } => write!(
+                f,
+                "Found {} dimension names for a shape with {} dimensions",
+                names, rank
+            ),
+            ShapeError::InvalidDimensionName { name } => {
+                write!(f, "\"{}\" isn't a valid dimension name", name)
+            },
+        }
+    }
+}
+
+impl crate::Error for ShapeError {}
+
 #[cfg(test)]
 mod tests {
     use std::prelude::v1::*;
 
-    use super::*;
+    use super::{
+        Dim::{Fixed, Unknown},
+        *,
+    };
 
     const SHAPES: &[(Shape, &str)] = &[
         (
             Shape {
                 element_type: ElementType::F32,
-                dimensions: Cow::Borrowed(&[1, 2, 3]),
+                dimensions: Cow::Borrowed(&[Fixed(1), Fixed(2), Fixed(3)]),
+                quantization: None,
+                dimension_names: None,
             },
             "f32[1, 2, 3]",
         ),
         (
             Shape {
                 element_type: ElementType::U8,
-                dimensions: Cow::Borrowed(&[42]),
+                dimensions: Cow::Borrowed(&[Fixed(42)]),
+                quantization: None,
+                dimension_names: None,
             },
             "u8[42]",
         ),
+        (
+            Shape {
+                element_type: ElementType::F16,
+                dimensions: Cow::Borrowed(&[
+                    Fixed(1),
+                    Fixed(224),
+                    Fixed(224),
+                    Fixed(3),
+                ]),
+                quantization: None,
+                dimension_names: None,
+            },
+            "f16[1, 224, 224, 3]",
+        ),
+        (
+            Shape {
+                element_type: ElementType::BF16,
+                dimensions: Cow::Borrowed(&[Fixed(8)]),
+                quantization: None,
+                dimension_names: None,
+            },
+            "bf16[8]",
+        ),
+        (
+            Shape {
+                element_type: ElementType::Bool,
+                dimensions: Cow::Borrowed(&[Fixed(1), Fixed(64), Fixed(64)]),
+                quantization: None,
+                dimension_names: None,
+            },
+            "bool[1, 64, 64]",
+        ),
+        (
+            Shape {
+                element_type: ElementType::String,
+                dimensions: Cow::Borrowed(&[Fixed(5)]),
+                quantization: None,
+                dimension_names: None,
+            },
+            "utf8[5]",
+        ),
+        (
+            Shape {
+                element_type: ElementType::C64,
+                dimensions: Cow::Borrowed(&[Fixed(257)]),
+                quantization: None,
+                dimension_names: None,
+            },
+            "c64[257]",
+        ),
+        (
+            Shape {
+                element_type: ElementType::C128,
+                dimensions: Cow::Borrowed(&[Fixed(2), Fixed(1024)]),
+                quantization: None,
+                dimension_names: None,
+            },
+            "c128[2, 1024]",
+        ),
+        (
+            Shape {
+                element_type: ElementType::F32,
+                dimensions: Cow::Borrowed(&[Unknown, Fixed(1960)]),
+                quantization: None,
+                dimension_names: None,
+            },
+            "f32[?, 1960]",
+        ),
     ];
 
     #[test]
@@ -183,4 +1184,316 @@ mod tests {
             assert_eq!(got, should_be);
         }
     }
+
+    #[test]
+    fn forgiving_parsing() {
+        let inputs = [
+            ("  f32 [ 1 ,\n 2 ]  ", "f32[1, 2]"),
+            ("u8[_, *, ?]", "u8[?, ?, ?]"),
+            ("i16[3,]", "i16[3]"),
+            ("f32[]", "f32[]"),
+            ("f32[ ]", "f32[]"),
+            ("u8[ batch :?, 3]", "u8[batch: ?, 3]"),
+        ];
+
+        for (src, should_be) in inputs {
+            let got: Shape = src.parse().unwrap();
+            assert_eq!(got.to_string(), should_be, "{:?}", src);
+        }
+
+        let scalar: Shape = "f64[]".parse().unwrap();
+        assert_eq!(scalar.rank(), 0);
+        assert_eq!(scalar.size(), Some(8));
+    }
+
+    #[test]
+    fn parse_errors_point_at_the_problem() {
+        let inputs = [
+            ("f32", 3),
+            ("f32[1, 2", 8),
+            ("f32]1[", 6),
+            ("f32[1] extra", 7),
+            ("  flaot[1]", 2),
+            ("f32[1,  x, 3]", 8),
+            ("f32[1,, 3]", 6),
+            ("f32[ : 3]", 5),
+        ];
+
+        for (src, offset) in inputs {
+            let err = src.parse::<Shape>().unwrap_err();
+            assert_eq!(err.offset(), offset, "{:?} => {}", src, err);
+        }
+
+        assert_eq!(
+            "f32[1,  x]".parse::<Shape>().unwrap_err().to_string(),
+            "\"x\" isn't a valid dimension (at byte 8)"
+        );
+    }
+
+    #[test]
+    fn unknown_dimensions_have_no_size() {
+        let fixed: Shape = "f32[2, 3]".parse().unwrap();
+        let dynamic: Shape = "f32[?, 3]".parse().unwrap();
+
+        assert_eq!(fixed.size(), Some(2 * 3 * 4));
+        assert_eq!(fixed.fixed_dimensions(), Some(vec![2, 3]));
+        assert_eq!(dynamic.size(), None);
+        assert_eq!(dynamic.fixed_dimensions(), None);
+    }
+
+    #[test]
+    fn unknown_dimensions_accept_any_length() {
+        let expected: Shape = "f32[?, 1960]".parse().unwrap();
+        let inputs = [
+            ("f32[1, 1960]", true),
+            ("f32[42, 1960]", true),
+            ("f32[?, 1960]", true),
+            ("f32[1, 1961]", false),
+            ("f32[1960]", false),
+            ("i8[1, 1960]", false),
+        ];
+
+        for (src, should_be) in inputs {
+            let actual: Shape = src.parse().unwrap();
+            assert_eq!(expected.accepts(&actual), should_be, "{}", src);
+        }
+
+        let fixed: Shape = "f32[1, 1960]".parse().unwrap();
+        assert!(!fixed.accepts(&expected));
+    }
+
+    #[test]
+    fn merge_partially_known_shapes() {
+        let inputs = [
+            ("f32[1, 2]", "f32[1, 2]", Some("f32[1, 2]")),
+            ("f32[?, 2]", "f32[1, ?]", Some("f32[1, 2]")),
+            ("f32[?, ?]", "f32[?, 5]", Some("f32[?, 5]")),
+            ("f32[1, 2]", "f32[1, 3]", None),
+            ("f32[1, 2]", "f32[1, 2, 1]", None),
+            ("f32[1, 2]", "u8[1, 2]", None),
+        ];
+
+        for (left, right, should_be) in inputs {
+            let left: Shape = left.parse().unwrap();
+            let right: Shape = right.parse().unwrap();
+
+            let got = left.merge(&right).ok().map(|s| s.to_string());
+
+            assert_eq!(got.as_deref(), should_be, "{} vs {}", left, right);
+            assert_eq!(left.is_compatible_with(&right), should_be.is_some());
+            assert_eq!(right.is_compatible_with(&left), should_be.is_some());
+        }
+    }
+
+    #[test]
+    fn broadcasting() {
+        let inputs = [
+            ("f32[256, 256, 3]", "f32[3]", Some("f32[256, 256, 3]")),
+            ("f32[8, 1, 6, 1]", "f32[7, 1, 5]", Some("f32[8, 7, 6, 5]")),
+            ("f32[5, 4]", "f32[1]", Some("f32[5, 4]")),
+            ("f32[15, 3, 5]", "f32[15, 1, 5]", Some("f32[15, 3, 5]")),
+            ("f32[?, 3]", "f32[4, 1]", Some("f32[4, 3]")),
+            ("f32[?, 3]", "f32[1, 3]", Some("f32[?, 3]")),
+            ("f32[3]", "f32[4]", None),
+            ("f32[2, 1]", "f32[8, 4, 3]", None),
+            ("f32[3]", "i32[3]", None),
+        ];
+
+        for (left, right, should_be) in inputs {
+            let left: Shape = left.parse().unwrap();
+            let right: Shape = right.parse().unwrap();
+
+            let got = left.broadcast_with(&right).ok().map(|s| s.to_string());
+            let reversed =
+                right.broadcast_with(&left).ok().map(|s| s.to_string());
+
+            assert_eq!(got.as_deref(), should_be, "{} vs {}", left, right);
+            assert_eq!(got, reversed);
+        }
+    }
+
+    #[test]
+    fn broadcast_errors_point_at_the_bad_axis() {
+        let left: Shape = "f32[2, 1]".parse().unwrap();
+        let right: Shape = "f32[8, 4, 3]".parse().unwrap();
+
+        let err = left.broadcast_with(&right).unwrap_err();
+
+        assert_eq!(
+            err,
+            ShapeError::DimensionMismatch {
+                axis: 1,
+                left: Dim::Fixed(2),
+                right: Dim::Fixed(4),
+            }
+        );
+    }
+
+    #[test]
+    fn reshape_shapes() {
+        let inputs = [
+            ("f32[2, 3, 4]", "f32[6, 4]", Some("f32[6, 4]")),
+            ("f32[2, 3, 4]", "f32[?, 4]", Some("f32[6, 4]")),
+            ("f32[2, 3, 4]", "f32[?, ?]", Some("f32[?, ?]")),
+            ("f32[?, 1960]", "f32[?, 49, 40]", Some("f32[?, 49, 40]")),
+            ("f32[2, 3, 4]", "f32[5, 5]", None),
+            ("f32[2, 3, 4]", "f32[?, 5]", None),
+        ];
+
+        for (src, target, should_be) in inputs {
+            let shape: Shape = src.parse().unwrap();
+            let target: Shape = target.parse().unwrap();
+
+            let got = shape
+                .reshape(target.dimensions())
+                .ok()
+                .map(|s| s.to_string());
+
+            assert_eq!(got.as_deref(), should_be, "{} => {}", shape, target);
+        }
+    }
+
+    #[test]
+    fn squeeze_shapes() {
+        let shape: Shape = "f32[1, 49, 1, 40]".parse().unwrap();
+
+        assert_eq!(shape.squeeze(None).unwrap().to_string(), "f32[49, 40]");
+        assert_eq!(
+            shape.squeeze(Some(2)).unwrap().to_string(),
+            "f32[1, 49, 40]"
+        );
+        assert_eq!(
+            shape.squeeze(Some(1)).unwrap_err(),
+            ShapeError::NotSqueezable {
+                axis: 1,
+                dim: Fixed(49)
+            }
+        );
+        assert_eq!(
+            shape.squeeze(Some(4)).unwrap_err(),
+            ShapeError::AxisOutOfBounds { axis: 4, rank: 4 }
+        );
+
+        let ones: Shape = "u8[1, 1]".parse().unwrap();
+        assert_eq!(ones.squeeze(None).unwrap().to_string(), "u8[1]");
+    }
+
+    #[test]
+    fn transpose_shapes() {
+        let shape: Shape = "f32[1, 3, 224, ?]".parse().unwrap();
+
+        let got = shape.transpose(&[0, 2, 3, 1]).unwrap();
+
+        assert_eq!(got.to_string(), "f32[1, 224, ?, 3]");
+        assert!(shape.transpose(&[0, 1, 2]).is_err());
+        assert!(shape.transpose(&[0, 1, 1, 2]).is_err());
+        assert!(shape.transpose(&[0, 1, 2, 4]).is_err());
+    }
+
+    #[test]
+    fn slice_shapes() {
+        let shape: Shape = "f32[?, 16000]".parse().unwrap();
+
+        assert_eq!(
+            shape.slice(1, 0..8000).unwrap().to_string(),
+            "f32[?, 8000]"
+        );
+        assert_eq!(shape.slice(0, 2..3).unwrap().to_string(), "f32[1, 16000]");
+        assert_eq!(
+            shape.slice(1, 0..16001).unwrap_err(),
+            ShapeError::OutOfRange {
+                axis: 1,
+                start: 0,
+                end: 16001,
+                length: Fixed(16000),
+            }
+        );
+        assert!(shape.slice(2, 0..1).is_err());
+    }
+
+    #[test]
+    fn quantization_follows_the_channel_axis() {
+        let shape: Shape = "u8[1, 3, 224, 224]".parse().unwrap();
+        let quantization =
+            Quantization::per_channel(1, vec![0.5, 1.0, 2.0], vec![0, 0, 0]);
+        let shape = shape.with_quantization(quantization).unwrap();
+
+        let transposed = shape.transpose(&[0, 2, 3, 1]).unwrap();
+        assert_eq!(
+            transposed.quantization(),
+            Some(&Quantization::per_channel(
+                3,
+                vec![0.5, 1.0, 2.0],
+                vec![0, 0, 0]
+            ))
+        );
+
+        let sliced = shape.slice(1, 1..2).unwrap();
+        assert_eq!(
+            sliced.quantization(),
+            Some(&Quantization::per_channel(1, vec![1.0], vec![0]))
+        );
+
+        // We can't keep track of the channels after a reshape
+        assert_eq!(shape.squeeze(None).unwrap().quantization(), None);
+
+        let wrong_axis = Quantization::per_channel(0, vec![0.5], vec![0, 1]);
+        assert!(shape.with_quantization(wrong_axis).is_err());
+    }
+
+    #[test]
+    fn named_dimensions_round_trip() {
+        let src = "u8[batch: ?, height: 224, width: 224, 3]";
+
+        let shape: Shape = src.parse().unwrap();
+
+        assert_eq!(shape.dimension_name(0), Some("batch"));
+        assert_eq!(shape.dimension_name(3), None);
+        assert_eq!(shape.axis("width"), Some(2));
+        assert_eq!(shape.to_string(), src);
+        assert_eq!(
+            shape.fixed_dimensions(),
+            "u8[?, 224, 224, 3]".parse::<Shape>().unwrap().fixed_dimensions()
+        );
+    }
+
+    #[test]
+    fn names_follow_their_dimensions() {
+        let shape: Shape = "f32[batch: 1, channels: 3, 224]".parse().unwrap();
+
+        assert_eq!(
+            shape.transpose(&[0, 2, 1]).unwrap().to_string(),
+            "f32[batch: 1, 224, channels: 3]"
+        );
+        assert_eq!(
+            shape.squeeze(None).unwrap().to_string(),
+            "f32[channels: 3, 224]"
+        );
+        assert_eq!(
+            shape.reshape(&[Fixed(672)]).unwrap().to_string(),
+            "f32[672]"
+        );
+
+        let other: Shape = "f32[224]".parse().unwrap();
+        assert_eq!(
+            shape.broadcast_with(&other).unwrap().to_string(),
+            "f32[batch: 1, channels: 3, 224]"
+        );
+    }
+
+    #[test]
+    fn invalid_dimension_names() {
+        let shape: Shape = "f32[1, 2]".parse().unwrap();
+
+        assert_eq!(
+            shape.clone().with_names([Some("batch")]).unwrap_err(),
+            ShapeError::NameCountMismatch { names: 1, rank: 2 }
+        );
+        assert_eq!(
+            shape.with_names([Some("a,b"), None]).unwrap_err(),
+            ShapeError::InvalidDimensionName {
+                name: String::from("a,b")
+            }
+        );
+    }
 }
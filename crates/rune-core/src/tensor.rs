@@ -3,18 +3,24 @@ use core::{
     convert::TryInto,
     fmt::{self, Display, Formatter},
     iter::FromIterator,
-    ops::{Index, IndexMut},
+    ops::{Index, IndexMut, Range},
 };
 
+use half::{bf16, f16};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
-use crate::{element_type::AsElementType, Shape};
+use crate::{
+    element_type::AsElementType,
+    shape::{self, Dim, ShapeError},
+    Quantization, Shape,
+};
 
 /// A multidimensional array with copy-on-write semantics.
 #[derive(Debug, Clone, PartialEq)]
 pub struct Tensor<T> {
     elements: Arc<[T]>,
     dimensions: Vec<usize>,
+    quantization: Option<Quantization>,
 }
 
 impl<T> Tensor<T> {
@@ -34,6 +40,7 @@ impl<T> Tensor<T> {
         Tensor {
             elements,
             dimensions,
+            quantization: None,
         }
     }
 
@@ -69,7 +76,34 @@ impl<T> Tensor<T> {
     where
         T: AsElementType,
     {
-        Shape::new(T::TYPE, self.dimensions())
+        let shape = Shape::new(T::TYPE, self.dimensions());
+
+        match &self.quantization {
+            Some(quantization) => shape.quantized_by(quantization),
+            None => shape,
+        }
+    }
+
+    /// Attach the parameters needed to interpret this tensor's quantized
+    /// elements.
+    pub fn with_quantization(
+        self,
+        quantization: Quantization,
+    ) -> Result<Self, ShapeError> {
+        let dimensions: Vec<Dim> =
+            self.dimensions.iter().copied().map(Dim::Fixed).collect();
+        quantization.check(&dimensions)?;
+
+        Ok(Tensor {
+            quantization: Some(quantization),
+            ..self
+        })
+    }
+
+    /// The parameters for interpreting this tensor's elements, if it is
+    /// quantized.
+    pub fn quantization(&self) -> Option<&Quantization> {
+        self.quantization.as_ref()
     }
 
     /// Get the [`Tensor`]'s dimensions.
@@ -197,6 +231,49 @@ impl<T> Tensor<T> {
         Tensor::new_row_major(elements.collect(), self.dimensions.clone())
     }
 
+    /// Give the tensor new dimensions without changing the number of
+    /// elements (see [`Shape::reshape()`]).
+    ///
+    /// The tensor's elements are shared with the original, so this is cheap.
+    pub fn reshape(
+        &self,
+        dimensions: Vec<usize>,
+    ) -> Result<Tensor<T>, ShapeError> {
+        let reshaped: usize = dimensions.iter().product();
+
+        if dimensions.is_empty() || reshaped != self.elements.len() {
+            return Err(ShapeError::ElementCountMismatch {
+                original: self.elements.len(),
+                reshaped,
+            });
+        }
+
+        Ok(self.reshaped(dimensions))
+    }
+
+    /// Remove dimensions with a length of `1`, either the one at `axis` or
+    /// all of them if no axis is specified (see [`Shape::squeeze()`]).
+    pub fn squeeze(
+        &self,
+        axis: Option<usize>,
+    ) -> Result<Tensor<T>, ShapeError> {
+        let dimensions =
+            shape::squeeze(&self.dimensions, axis, |&d| Dim::Fixed(d))?;
+
+        Ok(self.reshaped(dimensions))
+    }
+
+    /// A new tensor sharing this tensor's elements, keeping any quantization
+    /// parameters that don't depend on a particular axis.
+    fn reshaped(&self, dimensions: Vec<usize>) -> Tensor<T> {
+        Tensor {
+            quantization: self
+                .quantization()
+                .and_then(Quantization::without_channels),
+            ..Tensor::new_row_major(Arc::clone(&self.elements), dimensions)
+        }
+    }
+
     /// Get a reference to the element with this particular index.
     pub fn get(&self, indices: &[usize]) -> Option<&T> {
         let element_index = index_of(self.dimensions(), indices).ok()?;
@@ -210,6 +287,13 @@ impl<T> Tensor<T> {
         self.elements.get(element_index)
     }
 
+    /// Like [`Tensor::get()`], except the error says why the index was
+    /// invalid.
+    pub fn try_get(&self, indices: &[usize]) -> Result<&T, IndexError> {
+        let element_index = index_of(self.dimensions(), indices)?;
+        Ok(&self.elements[element_index])
+    }
+
     /// Get a mutable reference to the element with this particular index.
     pub fn get_mut(&mut self, indices: &[usize]) -> Option<&mut T>
     where
@@ -220,6 +304,55 @@ impl<T> Tensor<T> {
     }
 }
 
+impl<T: Copy + Into<i32>> Tensor<T> {
+    /// Convert a quantized tensor back into the real numbers it represents,
+    /// returning `None` if the tensor doesn't have [`Quantization`]
+    /// parameters.
+    #[must_use]
+    pub fn dequantize(&self) -> Option<Tensor<f32>> {
+        let quantization = self.quantization()?;
+        let channel_axis = match quantization {
+            Quantization::PerChannel { axis, .. } => Some(*axis),
+            Quantization::PerTensor { .. } => None,
+        };
+
+        let dequantized = self.map(|index, &value| {
+            let channel = channel_axis.map(|axis| index[axis]).unwrap_or(0);
+            quantization
+                .dequantize(value.into(), channel)
+                .expect("The parameters were checked against our dimensions")
+        });
+
+        Some(dequantized)
+    }
+}
+
+impl Tensor<f32> {
+    /// Convert each element to a half-precision float, rounding to the
+    /// nearest representable value.
+    #[must_use]
+    pub fn to_f16(&self) -> Tensor<f16> { self.map(|_, &x| f16::from_f32(x)) }
+
+    /// Convert each element to a [`bf16`], rounding to the nearest
+    /// representable value.
+    #[must_use]
+    pub fn to_bf16(&self) -> Tensor<bf16> {
+        self.map(|_, &x| bf16::from_f32(x))
+    }
+}
+
+impl Tensor<f16> {
+    /// Convert each element to a single-precision float (this is lossless).
+    #[must_use]
+    pub fn to_f32(&self) -> Tensor<f32> { self.map(|_, x| x.to_f32()) }
+}
+
+impl Tensor<bf16> {
+    /// Convert each element to a single-precision float (this is lossless).
+    #[must_use]
+    pub fn to_f32(&self) -> Tensor<f32> { self.map(|_, x| x.to_f32()) }
+}
+
 /// The index math that powers [`Tensor::slice()`] and
 /// [`Tensor::slice_mut()`].
 ///
@@ -346,6 +479,67 @@ impl<T: Clone> Tensor<T> {
 
         Arc::get_mut(&mut self.elements).expect("Guaranteed to be unique")
     }
+
+    /// Rearrange the tensor's dimensions so the `i`'th dimension in the new
+    /// tensor is `axes[i]` in the old one (see [`Shape::transpose()`]).
+    ///
+    /// This copies every element.
+    pub fn transpose(&self, axes: &[usize]) -> Result<Tensor<T>, ShapeError> {
+        shape::check_permutation(axes, self.rank())?;
+
+        let dimensions: Vec<usize> =
+            axes.iter().map(|&axis| self.dimensions[axis]).collect();
+        let strides = strides(&self.dimensions);
+        let mut counter = Counter::new(&dimensions);
+
+        let elements = self.elements.iter().map(|_| {
+            let index = counter
+                .next()
+                .expect("The counter should be in sync with iteration");
+            let offset: usize = index
+                .iter()
+                .zip(axes)
+                .map(|(&ix, &axis)| ix * strides[axis])
+                .sum();
+            self.elements[offset].clone()
+        });
+        let elements: Arc<[T]> = elements.collect();
+
+        Ok(Tensor {
+            quantization: self.quantization().map(|q| q.transposed(axes)),
+            ..Tensor::new_row_major(elements, dimensions)
+        })
+    }
+
+    /// Copy the elements in `range` along the dimension at `axis` into a new
+    /// tensor (see [`Shape::slice()`]).
+    pub fn slice_axis(
+        &self,
+        axis: usize,
+        range: Range<usize>,
+    ) -> Result<Tensor<T>, ShapeError> {
+        let length = shape::check_axis(axis, &self.dimensions)?;
+        shape::check_range(axis, &range, length)?;
+
+        let outer: usize = self.dimensions[..axis].iter().product();
+        let inner: usize = self.dimensions[axis + 1..].iter().product();
+        let mut elements =
+            Vec::with_capacity(outer * (range.end - range.start) * inner);
+
+        for i in 0..outer {
+            let start = (i * length + range.start) * inner;
+            let end = (i * length + range.end) * inner;
+            elements.extend_from_slice(&self.elements[start..end]);
+        }
+
+        let mut dimensions = self.dimensions.clone();
+        dimensions[axis] = range.end - range.start;
+
+        Ok(Tensor {
+            quantization: self.quantization().map(|q| q.sliced(axis, range)),
+            ..Tensor::new_row_major(elements.into(), dimensions)
+        })
+    }
 }
 
 impl<T, const N: usize> From<[T; N]> for Tensor<T> {
@@ -466,8 +660,10 @@ fn on_index_error(
     );
 }
 
+/// The reasons an index can't be used to look up an element in a
+/// [`Tensor`].
 #[derive(Debug, Copy, Clone, PartialEq)]
-enum IndexError {
+pub enum IndexError {
     MismatchedRank {
         dimension_length: usize,
         indices_length: usize,
@@ -508,6 +704,8 @@ impl Display for IndexError {
     }
 }
 
+impl crate::Error for IndexError {}
+
 /// An immutable view into a [`Tensor`] with a particular rank (number of
 /// dimensions).
 #[derive(Debug, Copy, Clone, PartialEq)]
@@ -678,11 +876,14 @@ impl<T: Serialize> Serialize for Tensor<T> {
         struct S<'a, Item> {
             elements: &'a [Item],
             dimensions: &'a [usize],
+            #[serde(skip_serializing_if = "Option::is_none")]
+            quantization: Option<&'a Quantization>,
         }
 
         let s = S {
             elements: self.elements(),
             dimensions: self.dimensions(),
+            quantization: self.quantization(),
         };
 
         s.serialize(serializer)
@@ -698,17 +899,35 @@ impl<'de, T: Deserialize<'de>> Deserialize<'de> for Tensor<T> {
         struct D<Item> {
             elements: Vec<Item>,
             dimensions: Vec<usize>,
+            #[serde(default)]
+            quantization: Option<Quantization>,
         }
 
         let D {
             elements,
             dimensions,
+            quantization,
         } = D::deserialize(de)?;
 
-        Ok(Tensor::<T>::new_row_major(elements.into(), dimensions))
+        let tensor = Tensor::<T>::new_row_major(elements.into(), dimensions);
+
+        match quantization {
+            Some(q) => tensor
+                .with_quantization(q)
+                .map_err(serde::de::Error::custom),
+            None => Ok(tensor),
+        }
     }
 }
 
+/// How many elements you need to skip to move one step along each dimension
+/// in a row-major tensor.
+fn strides(dimensions: &[usize]) -> Vec<usize> {
+    (0..dimensions.len())
+        .map(|i| dimensions[i + 1..].iter().product())
+        .collect()
+}
+
 #[derive(Debug, Clone, PartialEq)]
 struct Counter<'a> {
     current: Vec<usize>,
@@ -753,6 +972,19 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn round_trip_through_half_precision() {
+        let tensor = Tensor::new_vector(vec![0.0_f32, 1.5, -2.25, 65504.0]);
+
+        let f16s = tensor.to_f16();
+        let bf16s = tensor.to_bf16();
+
+        assert_eq!(f16s.dimensions(), tensor.dimensions());
+        assert_eq!(f16s.to_f32(), tensor);
+        assert_eq!(bf16s.elements()[1], bf16::from_f32(1.5));
+        assert_eq!(bf16s.to_f32().elements()[..3], tensor.elements()[..3]);
+    }
+
     #[test]
     fn indices_for_2d_view() {
         let tensor: Tensor<u32> = Tensor::zeroed(vec![2, 3]);
@@ -808,6 +1040,28 @@ mod tests {
         let _ = view[[2, 0]];
     }
 
+    #[test]
+    fn invalid_indices_explain_the_problem() {
+        let tensor: Tensor<u32> = Tensor::zeroed(vec![2_usize, 3]);
+
+        assert_eq!(tensor.try_get(&[1, 2]), Ok(&0));
+        assert_eq!(
+            tensor.try_get(&[1, 3]),
+            Err(IndexError::IndexTooLarge {
+                dimension: 1,
+                max_value: 3,
+                found: 3,
+            })
+        );
+        assert_eq!(
+            tensor.try_get(&[1]),
+            Err(IndexError::MismatchedRank {
+                dimension_length: 2,
+                indices_length: 1,
+            })
+        );
+    }
+
     #[test]
     fn map_the_elements() {
         let tensor: Tensor<u32> =
@@ -960,4 +1214,80 @@ mod tests {
             assert_eq!(got, should_be);
         }
     }
+
+    #[test]
+    fn reshape_and_squeeze_share_elements() {
+        let tensor: Tensor<u32> = (0..6).collect();
+
+        let reshaped = tensor.reshape(vec![1, 2, 3]).unwrap();
+        assert_eq!(reshaped.dimensions(), [1, 2, 3]);
+        assert_eq!(reshaped[[0, 1, 2]], 5);
+        assert!(Arc::ptr_eq(&tensor.elements, &reshaped.elements));
+
+        let squeezed = reshaped.squeeze(None).unwrap();
+        assert_eq!(squeezed.dimensions(), [2, 3]);
+
+        assert!(tensor.reshape(vec![4, 2]).is_err());
+        assert!(reshaped.squeeze(Some(1)).is_err());
+    }
+
+    #[test]
+    fn transpose_a_matrix() {
+        let tensor: Tensor<u32> = [[0, 1, 2], [3, 4, 5]].into();
+
+        let got = tensor.transpose(&[1, 0]).unwrap();
+
+        let should_be: Tensor<u32> = [[0, 3], [1, 4], [2, 5]].into();
+        assert_eq!(got, should_be);
+        assert!(tensor.transpose(&[0, 0]).is_err());
+    }
+
+    #[test]
+    fn transpose_channels_last_to_channels_first() {
+        let tensor: Tensor<u32> = (0..12).collect();
+        let tensor = tensor.reshape(vec![2, 2, 3]).unwrap();
+
+        let got = tensor.transpose(&[2, 0, 1]).unwrap();
+
+        assert_eq!(got.dimensions(), [3, 2, 2]);
+        for c in 0..3 {
+            for y in 0..2 {
+                for x in 0..2 {
+                    assert_eq!(got[[c, y, x]], tensor[[y, x, c]]);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn slice_along_an_axis() {
+        let tensor: Tensor<u32> =
+            [[0, 1, 2, 3], [4, 5, 6, 7], [8, 9, 10, 11]].into();
+
+        let columns = tensor.slice_axis(1, 1..3).unwrap();
+        let rows = tensor.slice_axis(0, 2..3).unwrap();
+
+        let should_be: Tensor<u32> = [[1, 2], [5, 6], [9, 10]].into();
+        assert_eq!(columns, should_be);
+        assert_eq!(rows.dimensions(), [1, 4]);
+        assert_eq!(rows.elements(), [8, 9, 10, 11]);
+        assert!(tensor.slice_axis(1, 3..5).is_err());
+        assert!(tensor.slice_axis(2, 0..1).is_err());
+    }
+
+    #[test]
+    fn dequantize_each_channel() {
+        let tensor: Tensor<u8> = [[0, 10], [20, 30]].into();
+        let quantization =
+            Quantization::per_channel(1, vec![0.5, 2.0], vec![0, 10]);
+
+        assert_eq!(tensor.dequantize(), None);
+
+        let tensor = tensor.with_quantization(quantization).unwrap();
+        let got = tensor.dequantize().unwrap();
+
+        let should_be: Tensor<f32> = [[0.0, 0.0], [10.0, 40.0]].into();
+        assert_eq!(got, should_be);
+        assert_eq!(tensor.shape().quantization(), tensor.quantization());
+    }
 }
@@ -7,6 +7,8 @@
 //!
 //! I apologise in advance for all the generic gymnastics.
 
+use alloc::vec::Vec;
+
 use crate::{element_type::AsElementType, Shape, Tensor};
 
 /// A helper trait which lets us get the shape from a tuple of different
@@ -77,7 +79,7 @@ where
         match shape {
             [s] => {
                 assert_eq!(s.element_type(), T::TYPE, "Incorrect element type");
-                Tensor::zeroed(s.dimensions().to_vec())
+                Tensor::zeroed(fixed_dimensions(s))
             },
             _ => panic!("Expected a shape with 1 element, found {:?}", shape),
         }
@@ -88,6 +90,14 @@ where
     }
 }
 
+/// Get the dimensions for a tensor we need to allocate, panicking if any of
+/// them aren't known.
+fn fixed_dimensions(shape: &Shape<'_>) -> Vec<usize> {
+    shape.fixed_dimensions().unwrap_or_else(|| {
+        panic!("Unable to allocate a tensor with the shape {}", shape)
+    })
+}
+
 /// Count the number of identifiers that were passed in.
 macro_rules! count {
     ($first:ident $(, $rest:ident)* $(,)*) => { 1 + count!($($rest),*) };
@@ -139,10 +149,10 @@ macro_rules! reflection_type_list {
                 match shape {
                     [$first, $($dim),*] => {
                         assert_eq!($first.element_type(), <$first>::TYPE, "Incorrect element type");
-                        let $first = Tensor::zeroed($first.dimensions().to_vec());
+                        let $first = Tensor::zeroed(fixed_dimensions($first));
                         $(
                             assert_eq!($dim.element_type(), <$dim>::TYPE, "Incorrect element type");
-                            let $dim = Tensor::zeroed($dim.dimensions().to_vec());
+                            let $dim = Tensor::zeroed(fixed_dimensions($dim));
                         )*
 
                         ($first, $($dim),*)
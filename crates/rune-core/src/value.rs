@@ -235,5 +235,4 @@ impl Display for InvalidConversionError {
     }
 }
 
-#[cfg(feature = "std")]
-impl std::error::Error for InvalidConversionError {}
+impl crate::Error for InvalidConversionError {}
@@ -0,0 +1,121 @@
+//! Making sure a Rune speaks the same language as the runtime.
+//!
+//! Every Rune embeds the [`hotg_rune_core::ABI_VERSION`] it was compiled
+//! against in a custom section. Runes compiled before the custom section was
+//! introduced are treated as version `0` and get compatibility shims where
+//! their behaviour differs.
+
+use std::convert::TryInto;
+
+use anyhow::Context;
+use hotg_rune_core::{ABI_VERSION, ABI_VERSION_CUSTOM_SECTION};
+use wasmparser::{Parser, Payload};
+
+use crate::LoadError;
+
+/// The oldest ABI version this runtime can load.
+pub(crate) const MIN_ABI_VERSION: u32 = 0;
+
+/// Runes without an ABI version.
+///
+/// These Runes don't report errors using `rune_error()` and the value
+/// returned from `_call()` isn't meaningful, so it is ignored.
+pub(crate) const LEGACY_ABI_VERSION: u32 = 0;
+
+/// Find out which ABI version a Rune was compiled against, making sure the
+/// runtime supports it.
+pub(crate) fn negotiate(wasm: &[u8]) -> Result<u32, LoadError> {
+    let version = abi_version(wasm)?;
+
+    if (MIN_ABI_VERSION..=ABI_VERSION).contains(&version) {
+        Ok(version)
+    } else {
+        Err(LoadError::UnsupportedAbiVersion {
+            rune: version,
+            min: MIN_ABI_VERSION,
+            max: ABI_VERSION,
+        })
+    }
+}
+
+fn abi_version(wasm: &[u8]) -> Result<u32, LoadError> {
+    for payload in Parser::default().parse_all(wasm) {
+        if let Ok(Payload::CustomSection { name, data, .. }) = payload {
+            if name != ABI_VERSION_CUSTOM_SECTION {
+                continue;
+            }
+
+            let bytes: [u8; 4] = data.try_into().with_context(|| {
+                format!(
+                    "The \"{}\" section should contain a 4-byte integer, \
+                     but it was {} bytes long",
+                    ABI_VERSION_CUSTOM_SECTION,
+                    data.len()
+                )
+            })?;
+
+            return Ok(u32::from_le_bytes(bytes));
+        }
+    }
+
+    Ok(LEGACY_ABI_VERSION)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EMPTY_MODULE: &[u8] = b"\0asm\x01\0\0\0";
+
+    /// Create an empty WebAssembly module with a custom section containing
+    /// `data`.
+    fn module_with_version(data: &[u8]) -> Vec<u8> {
+        let name = ABI_VERSION_CUSTOM_SECTION.as_bytes();
+        // Note: everything is small enough that each LEB128 length fits in a
+        // single byte
+        let section_len = 1 + name.len() + data.len();
+
+        let mut wasm = EMPTY_MODULE.to_vec();
+        wasm.push(0);
+        wasm.push(section_len as u8);
+        wasm.push(name.len() as u8);
+        wasm.extend_from_slice(name);
+        wasm.extend_from_slice(data);
+
+        wasm
+    }
+
+    #[test]
+    fn runes_without_a_version_are_legacy() {
+        assert_eq!(negotiate(EMPTY_MODULE).unwrap(), LEGACY_ABI_VERSION);
+    }
+
+    #[test]
+    fn read_the_current_version() {
+        let wasm = module_with_version(&ABI_VERSION.to_le_bytes());
+
+        assert_eq!(negotiate(&wasm).unwrap(), ABI_VERSION);
+    }
+
+    #[test]
+    fn refuse_newer_runes() {
+        let wasm = module_with_version(&(ABI_VERSION + 1).to_le_bytes());
+
+        let err = negotiate(&wasm).unwrap_err();
+
+        match err {
+            LoadError::UnsupportedAbiVersion { rune, max, .. } => {
+                assert_eq!(rune, ABI_VERSION + 1);
+                assert_eq!(max, ABI_VERSION);
+            },
+            other => panic!("Unexpected error: {}", other),
+        }
+    }
+
+    #[test]
+    fn malformed_sections_are_an_error() {
+        let wasm = module_with_version(&[1, 2]);
+
+        assert!(negotiate(&wasm).is_err());
+    }
+}
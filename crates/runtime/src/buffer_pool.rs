@@ -0,0 +1,243 @@
+use alloc::{vec, vec::Vec};
+
+/// A pool of reusable byte buffers.
+///
+/// Running a Rune in a loop will typically create tensors with the same sizes
+/// over and over again, so keeping the old buffers around means steady-state
+/// inference doesn't need to allocate.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BufferPool {
+    buffers: Vec<Vec<u8>>,
+    max_buffers: usize,
+    is_static: bool,
+    stats: PoolStats,
+}
+
+impl BufferPool {
+    /// The default value for [`BufferPool::max_buffers()`].
+    pub const DEFAULT_MAX_BUFFERS: usize = 32;
+
+    pub fn new() -> Self {
+        BufferPool::with_max_buffers(Self::DEFAULT_MAX_BUFFERS)
+    }
+
+    pub fn with_max_buffers(max_buffers: usize) -> Self {
+        BufferPool {
+            buffers: Vec::new(),
+            max_buffers,
+            is_static: false,
+            stats: PoolStats::default(),
+        }
+    }
+
+    /// Create a pool which allocates a buffer for each of the provided sizes
+    /// up front and will never allocate again.
+    ///
+    /// This is intended for microcontrollers, where it's better to fail
+    /// loudly than to fragment a few hundred KB of heap. Acquiring a buffer
+    /// when none of the free buffers are big enough will panic.
+    pub fn with_static_buffers(sizes: &[usize]) -> Self {
+        let buffers: Vec<Vec<u8>> =
+            sizes.iter().map(|&len| Vec::with_capacity(len)).collect();
+        let bytes_available: usize =
+            buffers.iter().map(|b| b.capacity()).sum();
+
+        BufferPool {
+            max_buffers: buffers.len(),
+            buffers,
+            is_static: true,
+            stats: PoolStats {
+                bytes_allocated: bytes_available as u64,
+                bytes_available,
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Was this pool created with [`BufferPool::with_static_buffers()`]?
+    pub fn is_static(&self) -> bool { self.is_static }
+
+    /// The maximum number of unused buffers the pool will hold on to.
+    pub fn max_buffers(&self) -> usize { self.max_buffers }
+
+    /// Get a zeroed buffer with the provided length, reusing a previously
+    /// released buffer if one is large enough.
+    ///
+    /// # Panics
+    ///
+    /// This will panic if the pool [is static][BufferPool::is_static] and
+    /// there are no free buffers with room for `len` bytes.
+    pub fn acquire(&mut self, len: usize) -> Vec<u8> {
+        if let Some(buffer) = self.try_acquire(len) {
+            return buffer;
+        }
+
+        assert!(
+            !self.is_static,
+            "The static buffer pool has no free buffers with room for {} \
+             bytes",
+            len
+        );
+
+        self.stats.bytes_allocated += len as u64;
+        vec![0; len]
+    }
+
+    /// Try to get a zeroed buffer with the provided length from the pool,
+    /// returning `None` instead of allocating when no free buffer is large
+    /// enough.
+    pub fn try_acquire(&mut self, len: usize) -> Option<Vec<u8>> {
+        // Use the smallest buffer that is big enough so large buffers are
+        // available for large tensors.
+        let best_fit = self
+            .buffers
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| b.capacity() >= len)
+            .min_by_key(|(_, b)| b.capacity())
+            .map(|(i, _)| i);
+
+        match best_fit {
+            Some(index) => {
+                let mut buffer = self.buffers.swap_remove(index);
+                self.stats.hits += 1;
+                self.stats.bytes_available -= buffer.capacity();
+                buffer.clear();
+                buffer.resize(len, 0);
+                Some(buffer)
+            },
+            None => {
+                self.stats.misses += 1;
+                None
+            },
+        }
+    }
+
+    /// Give a buffer back to the pool so it can be reused.
+    pub fn release(&mut self, buffer: Vec<u8>) {
+        if buffer.capacity() == 0 {
+            return;
+        }
+
+        if self.buffers.len() >= self.max_buffers {
+            self.stats.discarded += 1;
+            return;
+        }
+
+        self.stats.bytes_available += buffer.capacity();
+        self.buffers.push(buffer);
+    }
+
+    /// Statistics which can be used to tune the pool.
+    pub fn stats(&self) -> PoolStats {
+        PoolStats {
+            buffers_available: self.buffers.len(),
+            ..self.stats
+        }
+    }
+}
+
+impl Default for BufferPool {
+    fn default() -> Self { BufferPool::new() }
+}
+
+/// Statistics about a [`BufferPool`]'s usage.
+#[derive(Debug, Default, Copy, Clone, PartialEq, serde::Serialize)]
+pub struct PoolStats {
+    /// The number of times a buffer was reused.
+    pub hits: u64,
+    /// The number of times a new buffer needed to be allocated.
+    pub misses: u64,
+    /// The total number of bytes allocated by the pool.
+    pub bytes_allocated: u64,
+    /// The number of buffers that were dropped because the pool was full.
+    pub discarded: u64,
+    /// The number of unused buffers currently in the pool.
+    pub buffers_available: usize,
+    /// The total capacity of all unused buffers currently in the pool.
+    pub bytes_available: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reuse_released_buffers() {
+        let mut pool = BufferPool::new();
+
+        let first = pool.acquire(1024);
+        pool.release(first);
+        let second = pool.acquire(512);
+
+        assert_eq!(second.len(), 512);
+        assert!(second.iter().all(|&b| b == 0));
+        assert_eq!(
+            pool.stats(),
+            PoolStats {
+                hits: 1,
+                misses: 1,
+                bytes_allocated: 1024,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn buffers_that_are_too_small_are_skipped() {
+        let mut pool = BufferPool::new();
+        pool.release(vec![0; 8]);
+
+        let buffer = pool.acquire(16);
+
+        assert_eq!(buffer.len(), 16);
+        assert_eq!(pool.stats().misses, 1);
+        assert_eq!(pool.stats().buffers_available, 1);
+    }
+
+    #[test]
+    fn pick_the_smallest_buffer_that_fits() {
+        let mut pool = BufferPool::new();
+        pool.release(Vec::with_capacity(1024));
+        pool.release(Vec::with_capacity(64));
+
+        let buffer = pool.acquire(32);
+
+        assert_eq!(buffer.capacity(), 64);
+    }
+
+    #[test]
+    fn full_pools_discard_buffers() {
+        let mut pool = BufferPool::with_max_buffers(1);
+
+        pool.release(vec![0; 8]);
+        pool.release(vec![0; 8]);
+
+        assert_eq!(pool.stats().buffers_available, 1);
+        assert_eq!(pool.stats().discarded, 1);
+    }
+
+    #[test]
+    fn static_pools_reuse_their_buffers() {
+        let mut pool = BufferPool::with_static_buffers(&[64, 256]);
+
+        let small = pool.acquire(32);
+        let large = pool.acquire(200);
+        assert_eq!(pool.try_acquire(1), None);
+
+        pool.release(small);
+        pool.release(large);
+
+        assert_eq!(pool.acquire(100).capacity(), 256);
+        assert_eq!(pool.stats().bytes_allocated, 64 + 256);
+        assert_eq!(pool.stats().hits, 3);
+    }
+
+    #[test]
+    #[should_panic]
+    fn static_pools_never_allocate() {
+        let mut pool = BufferPool::with_static_buffers(&[64]);
+
+        let _ = pool.acquire(128);
+    }
+}
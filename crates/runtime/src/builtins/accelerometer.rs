@@ -13,10 +13,28 @@ use csv::{Position, StringRecord};
 use crate::{builtins::Arguments, Tensor};
 
 /// Load an input tensor from a set of accelerometer samples.
+///
+/// If the samples were recorded with timestamps, the `"rate"` argument (in
+/// Hz) can be used to resample them to the rate the Rune expects, using the
+/// [`Interpolation`] method from the `"interpolation"` argument. The
+/// `"samples"` argument sets how many readings are provided (defaulting to
+/// all of them) and the result is a `f32[samples, 3]` tensor.
 pub fn accelerometer(
     args: &Arguments,
     samples: &AccelerometerSamples,
 ) -> Result<Tensor, Error> {
+    let resampled;
+    let samples = match args.0.get("rate") {
+        Some(_) if samples.has_timestamps() => {
+            let rate: f64 = args.parse("rate")?;
+            let interpolation = args
+                .parse_or_default("interpolation", Interpolation::default())?;
+            resampled = samples.resample(rate, interpolation)?;
+            &resampled
+        },
+        _ => samples,
+    };
+
     let requested_samples: usize =
         args.parse_or_default("samples", samples.len())?;
 
@@ -28,29 +46,139 @@ pub fn accelerometer(
         );
     }
 
-    let mut buffer = Vec::with_capacity(samples.len() * 3);
+    let mut buffer = Vec::with_capacity(requested_samples * 3);
 
     for sample in &samples[..requested_samples] {
-        let AccelerometerSample { x, y, z } = *sample;
+        let AccelerometerSample { x, y, z, .. } = *sample;
         buffer.push(x);
         buffer.push(y);
         buffer.push(z);
     }
 
-    Ok(Tensor::new(&buffer, &[samples.len(), 3]))
+    Ok(Tensor::new(&buffer, &[requested_samples, 3]))
 }
 
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
 pub struct AccelerometerSample {
+    /// When the sample was taken, in seconds.
+    pub timestamp: Option<f64>,
     pub x: f32,
     pub y: f32,
     pub z: f32,
 }
 
+impl AccelerometerSample {
+    fn lerp(self, other: AccelerometerSample, t: f32) -> AccelerometerSample {
+        AccelerometerSample {
+            timestamp: None,
+            x: self.x + (other.x - self.x) * t,
+            y: self.y + (other.y - self.y) * t,
+            z: self.z + (other.z - self.z) * t,
+        }
+    }
+}
+
+/// How to fill in values when resampling.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Interpolation {
+    /// Linearly interpolate between the samples on either side.
+    Linear,
+    /// Use whichever sample is closest in time.
+    Nearest,
+    /// Hold the most recent sample's value (a zero-order hold).
+    Previous,
+}
+
+impl Default for Interpolation {
+    fn default() -> Self { Interpolation::Linear }
+}
+
+impl FromStr for Interpolation {
+    type Err = UnknownInterpolation;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "linear" => Ok(Interpolation::Linear),
+            "nearest" => Ok(Interpolation::Nearest),
+            "previous" | "hold" => Ok(Interpolation::Previous),
+            _ => Err(UnknownInterpolation),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, thiserror::Error)]
+#[error("Expected one of \"linear\", \"nearest\", or \"previous\"")]
+pub struct UnknownInterpolation;
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct AccelerometerSamples(pub Vec<AccelerometerSample>);
 
 impl AccelerometerSamples {
+    /// Were these samples recorded with a timestamp column?
+    pub fn has_timestamps(&self) -> bool {
+        !self.is_empty() && self.iter().all(|s| s.timestamp.is_some())
+    }
+
+    /// Resample timestamped readings so they are evenly spaced at `rate`
+    /// samples per second, starting from the first reading.
+    pub fn resample(
+        &self,
+        rate: f64,
+        interpolation: Interpolation,
+    ) -> Result<AccelerometerSamples, Error> {
+        anyhow::ensure!(
+            rate > 0.0,
+            "The sampling rate must be positive, not {}",
+            rate
+        );
+        anyhow::ensure!(
+            self.has_timestamps(),
+            "Only samples with timestamps can be resampled"
+        );
+
+        let timestamps: Vec<f64> =
+            self.iter().filter_map(|s| s.timestamp).collect();
+        let start = timestamps[0];
+        let end = timestamps[timestamps.len() - 1];
+        let count = ((end - start) * rate).floor() as usize + 1;
+
+        let mut resampled = Vec::with_capacity(count);
+        // Index of the last reading at or before the current time
+        let mut i = 0;
+
+        for n in 0..count {
+            let t = start + n as f64 / rate;
+
+            while i + 1 < timestamps.len() && timestamps[i + 1] <= t {
+                i += 1;
+            }
+
+            let before = self[i];
+            let sample = match self.get(i + 1) {
+                Some(&after) => {
+                    let span = timestamps[i + 1] - timestamps[i];
+                    let fraction = ((t - timestamps[i]) / span) as f32;
+
+                    match interpolation {
+                        Interpolation::Linear => before.lerp(after, fraction),
+                        Interpolation::Nearest if fraction >= 0.5 => after,
+                        Interpolation::Nearest | Interpolation::Previous => {
+                            before
+                        },
+                    }
+                },
+                None => before,
+            };
+
+            resampled.push(AccelerometerSample {
+                timestamp: Some(t),
+                ..sample
+            });
+        }
+
+        Ok(AccelerometerSamples(resampled))
+    }
+
     pub fn from_file(
         path: impl AsRef<Path>,
     ) -> Result<Self, AccelerometerParseError> {
@@ -65,28 +193,61 @@ impl AccelerometerSamples {
         AccelerometerSamples::from_reader(f)
     }
 
+    /// Parse samples from CSV, where each row is either `x,y,z` or
+    /// `timestamp,x,y,z` (with the timestamp in seconds).
+    ///
+    /// A header row is skipped if present, and timestamps must be strictly
+    /// increasing.
     pub fn from_reader(
         reader: impl Read,
     ) -> Result<AccelerometerSamples, AccelerometerParseError> {
-        let mut samples = Vec::new();
+        let mut samples: Vec<AccelerometerSample> = Vec::new();
 
         let mut reader = csv::ReaderBuilder::default()
             .has_headers(false)
+            .trim(csv::Trim::All)
             .from_reader(reader);
         let mut record = StringRecord::new();
+        let mut expected_fields = None;
+        let mut first_record = true;
 
         while reader.read_record(&mut record)? {
             let pos = reader.position();
 
-            if record.len() != 3 {
+            if std::mem::take(&mut first_record) && is_header(&record) {
+                continue;
+            }
+
+            let expected = match expected_fields {
+                Some(expected) => expected,
+                None if record.len() == 4 => 4,
+                None => 3,
+            };
+
+            if record.len() != expected {
                 return Err(AccelerometerParseError::IncorrectNumberOfFields {
                     actual: record.len(),
-                    expected: 3,
+                    expected,
                     line: pos.line(),
                 });
             }
+            expected_fields = Some(expected);
 
             let sample = parse_sample(&record, pos)?;
+
+            if let (Some(previous), Some(current)) = (
+                samples.last().and_then(|s| s.timestamp),
+                sample.timestamp,
+            ) {
+                if current <= previous {
+                    return Err(
+                        AccelerometerParseError::TimestampsNotIncreasing {
+                            line: pos.line(),
+                        },
+                    );
+                }
+            }
+
             samples.push(sample);
         }
 
@@ -115,11 +276,30 @@ fn parse_sample(
 ) -> Result<AccelerometerSample, AccelerometerParseError> {
     let line = pos.line();
 
-    let x = parse_field(&record[0], line)?;
-    let y = parse_field(&record[1], line)?;
-    let z = parse_field(&record[2], line)?;
+    let (timestamp, offset) = match record.len() {
+        4 => {
+            let timestamp = record[0].parse().map_err(|reason| {
+                AccelerometerParseError::InvalidSample {
+                    line,
+                    value: record[0].to_string(),
+                    reason,
+                }
+            })?;
+            (Some(timestamp), 1)
+        },
+        _ => (None, 0),
+    };
+
+    let x = parse_field(&record[offset], line)?;
+    let y = parse_field(&record[offset + 1], line)?;
+    let z = parse_field(&record[offset + 2], line)?;
+
+    Ok(AccelerometerSample { timestamp, x, y, z })
+}
 
-    Ok(AccelerometerSample { x, y, z })
+/// Does this look like a header row (e.g. `time,x,y,z`)?
+fn is_header(record: &StringRecord) -> bool {
+    record.iter().all(|field| field.parse::<f64>().is_err())
 }
 
 fn parse_field(value: &str, line: u64) -> Result<f32, AccelerometerParseError> {
@@ -152,6 +332,8 @@ pub enum AccelerometerParseError {
         actual: usize,
         line: u64,
     },
+    #[error("The timestamp on line {} isn't after the previous one", line)]
+    TimestampsNotIncreasing { line: u64 },
     #[error("Unable to open \"{}\"", filename.display())]
     OpenFile {
         filename: PathBuf,
@@ -161,3 +343,74 @@ pub enum AccelerometerParseError {
     #[error(transparent)]
     Csv(#[from] csv::Error),
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_shape_matches_the_requested_samples() {
+        let samples: AccelerometerSamples =
+            "1,2,3\n4,5,6\n7,8,9\n".parse().unwrap();
+
+        let got = accelerometer(&Arguments::from([("samples", "2")]), &samples)
+            .unwrap();
+
+        let should_be =
+            Tensor::new(&[1.0_f32, 2.0, 3.0, 4.0, 5.0, 6.0], &[2, 3]);
+        assert_eq!(got, should_be);
+    }
+
+    #[test]
+    fn parse_timestamps_and_skip_the_header() {
+        let src = "time,x,y,z\n0.0,1,2,3\n0.5,4,5,6\n";
+
+        let got: AccelerometerSamples = src.parse().unwrap();
+
+        assert!(got.has_timestamps());
+        assert_eq!(got[1].timestamp, Some(0.5));
+        assert_eq!(got[1].x, 4.0);
+    }
+
+    #[test]
+    fn timestamps_must_increase() {
+        let src = "0.0,1,2,3\n0.0,4,5,6\n";
+
+        let err = src.parse::<AccelerometerSamples>().unwrap_err();
+
+        assert!(matches!(
+            err,
+            AccelerometerParseError::TimestampsNotIncreasing { .. }
+        ));
+    }
+
+    #[test]
+    fn resample_irregular_readings() {
+        let samples: AccelerometerSamples =
+            "0.0,0,0,0\n0.3,3,0,0\n0.4,0,0,0\n".parse().unwrap();
+
+        let linear = samples.resample(10.0, Interpolation::Linear).unwrap();
+        let previous = samples.resample(10.0, Interpolation::Previous).unwrap();
+
+        let xs = |s: &AccelerometerSamples| -> Vec<f32> {
+            s.iter().map(|s| (s.x * 100.0).round() / 100.0).collect()
+        };
+        assert_eq!(xs(&linear), vec![0.0, 1.0, 2.0, 3.0, 0.0]);
+        assert_eq!(xs(&previous), vec![0.0, 0.0, 0.0, 3.0, 0.0]);
+    }
+
+    #[test]
+    fn the_rate_argument_resamples_timestamped_data() {
+        let samples: AccelerometerSamples =
+            "0.0,0,0,0\n1.0,4,4,4\n".parse().unwrap();
+
+        let got =
+            accelerometer(&Arguments::from([("rate", "2")]), &samples).unwrap();
+
+        let should_be = Tensor::new(
+            &[0.0_f32, 0.0, 0.0, 2.0, 2.0, 2.0, 4.0, 4.0, 4.0],
+            &[3, 3],
+        );
+        assert_eq!(got, should_be);
+    }
+}
@@ -1,4 +1,9 @@
-use std::{collections::HashMap, str::FromStr};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::{self, Display, Formatter},
+    iter::FromIterator,
+    str::FromStr,
+};
 
 use anyhow::{Context, Error};
 
@@ -7,6 +12,27 @@ use anyhow::{Context, Error};
 #[repr(transparent)]
 pub struct Arguments(pub HashMap<String, String>);
 
+impl<K, V> FromIterator<(K, V)> for Arguments
+where
+    K: Into<String>,
+    V: Into<String>,
+{
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(pairs: I) -> Self {
+        Arguments(
+            pairs
+                .into_iter()
+                .map(|(key, value)| (key.into(), value.into()))
+                .collect(),
+        )
+    }
+}
+
+impl<'a, const N: usize> From<[(&'a str, &'a str); N]> for Arguments {
+    fn from(pairs: [(&'a str, &'a str); N]) -> Self {
+        pairs.iter().copied().collect()
+    }
+}
+
 impl Arguments {
     pub fn parse<T>(&self, name: &str) -> Result<T, Error>
     where
@@ -45,4 +71,392 @@ impl Arguments {
             None => Ok(default),
         }
     }
+
+    /// Get an argument as a structured [`ArgumentValue`], so lists like
+    /// `[0.485, 0.456, 0.406]` and maps like `{size: [224, 224]}` don't need
+    /// to be split up by hand.
+    pub fn value(&self, name: &str) -> Result<Option<ArgumentValue>, Error> {
+        self.0
+            .get(name)
+            .map(|value| {
+                value.parse::<ArgumentValue>().with_context(|| {
+                    format!(
+                        "Unable to parse {:?} as the \"{}\" argument",
+                        value, name
+                    )
+                })
+            })
+            .transpose()
+    }
+
+    /// Parse every item in a list argument.
+    ///
+    /// For backwards compatibility, a plain comma-separated string (e.g.
+    /// `0,1,2`) is also accepted.
+    pub fn parse_list<T>(&self, name: &str) -> Result<Vec<T>, Error>
+    where
+        T: FromStr,
+        T::Err: std::error::Error + Send + Sync + 'static,
+    {
+        match self.value(name)? {
+            Some(value) => value.parse_list().with_context(|| {
+                format!("Unable to parse the \"{}\" argument", name)
+            }),
+            None => {
+                Err(anyhow::anyhow!("The \"{}\" argument wasn't set", name))
+            },
+        }
+    }
+
+    pub fn parse_list_or_default<T>(
+        &self,
+        name: &str,
+        default: Vec<T>,
+    ) -> Result<Vec<T>, Error>
+    where
+        T: FromStr,
+        T::Err: std::error::Error + Send + Sync + 'static,
+    {
+        if self.0.contains_key(name) {
+            self.parse_list(name)
+        } else {
+            Ok(default)
+        }
+    }
+}
+
+/// A structured argument value.
+///
+/// Lists and maps use the same syntax as JSON, except quotes around strings
+/// and keys are optional (`[rgb, bgr]` and `{mode: center}` are both
+/// accepted). This is also how the Rune compiler passes along lists and maps
+/// from a Runefile.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ArgumentValue {
+    Scalar(String),
+    List(Vec<ArgumentValue>),
+    Map(BTreeMap<String, ArgumentValue>),
+}
+
+impl ArgumentValue {
+    pub fn as_scalar(&self) -> Option<&str> {
+        match self {
+            ArgumentValue::Scalar(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_list(&self) -> Option<&[ArgumentValue]> {
+        match self {
+            ArgumentValue::List(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    pub fn as_map(&self) -> Option<&BTreeMap<String, ArgumentValue>> {
+        match self {
+            ArgumentValue::Map(map) => Some(map),
+            _ => None,
+        }
+    }
+
+    /// Look up a key in a map.
+    pub fn get(&self, key: &str) -> Option<&ArgumentValue> {
+        self.as_map().and_then(|map| map.get(key))
+    }
+
+    /// Parse a scalar value.
+    pub fn parse<T>(&self) -> Result<T, Error>
+    where
+        T: FromStr,
+        T::Err: std::error::Error + Send + Sync + 'static,
+    {
+        match self {
+            ArgumentValue::Scalar(s) => s
+                .parse()
+                .with_context(|| format!("Unable to parse {:?}", s)),
+            other => Err(anyhow::anyhow!("Expected a value, found {}", other)),
+        }
+    }
+
+    /// Parse each item in a list, treating a scalar as a comma-separated
+    /// list.
+    pub fn parse_list<T>(&self) -> Result<Vec<T>, Error>
+    where
+        T: FromStr,
+        T::Err: std::error::Error + Send + Sync + 'static,
+    {
+        match self {
+            ArgumentValue::List(items) => {
+                items.iter().map(ArgumentValue::parse).collect()
+            },
+            ArgumentValue::Scalar(s) => s
+                .split(',')
+                .map(|item| {
+                    item.trim()
+                        .parse()
+                        .with_context(|| format!("Unable to parse {:?}", item))
+                })
+                .collect(),
+            ArgumentValue::Map(_) => {
+                Err(anyhow::anyhow!("Expected a list, found {}", self))
+            },
+        }
+    }
+}
+
+impl FromStr for ArgumentValue {
+    type Err = ArgumentValueError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+
+        if !s.starts_with(|c| c == '[' || c == '{') {
+            return Ok(ArgumentValue::Scalar(s.to_string()));
+        }
+
+        let mut parser = Parser { src: s, position: 0 };
+        let value = parser.value()?;
+        parser.skip_whitespace();
+
+        if parser.position < s.len() {
+            return Err(parser.error("Unexpected trailing characters"));
+        }
+
+        Ok(value)
+    }
+}
+
+impl Display for ArgumentValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ArgumentValue::Scalar(s) => write!(f, "{:?}", s),
+            ArgumentValue::List(items) => {
+                write!(f, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "]")
+            },
+            ArgumentValue::Map(map) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in map.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{:?}:{}", key, value)?;
+                }
+                write!(f, "}}")
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+#[error("{message} at position {position}")]
+pub struct ArgumentValueError {
+    pub message: &'static str,
+    pub position: usize,
+}
+
+struct Parser<'a> {
+    src: &'a str,
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn rest(&self) -> &'a str { &self.src[self.position..] }
+
+    fn peek(&self) -> Option<char> { self.rest().chars().next() }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.position += rest.len() - rest.trim_start().len();
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), ArgumentValueError> {
+        self.skip_whitespace();
+
+        if self.peek() == Some(c) {
+            self.position += c.len_utf8();
+            Ok(())
+        } else {
+            Err(self.error(match c {
+                ']' => "Expected a \"]\"",
+                '}' => "Expected a \"}\"",
+                ':' => "Expected a \":\"",
+                _ => "Unexpected character",
+            }))
+        }
+    }
+
+    fn error(&self, message: &'static str) -> ArgumentValueError {
+        ArgumentValueError {
+            message,
+            position: self.position,
+        }
+    }
+
+    fn value(&mut self) -> Result<ArgumentValue, ArgumentValueError> {
+        self.skip_whitespace();
+
+        match self.peek() {
+            Some('[') => self.list(),
+            Some('{') => self.map(),
+            _ => self.scalar().map(ArgumentValue::Scalar),
+        }
+    }
+
+    fn list(&mut self) -> Result<ArgumentValue, ArgumentValueError> {
+        self.expect('[')?;
+        let mut items = Vec::new();
+
+        loop {
+            self.skip_whitespace();
+            if self.peek() == Some(']') {
+                break;
+            }
+
+            items.push(self.value()?);
+
+            self.skip_whitespace();
+            if self.peek() == Some(',') {
+                self.position += 1;
+            } else {
+                break;
+            }
+        }
+
+        self.expect(']')?;
+        Ok(ArgumentValue::List(items))
+    }
+
+    fn map(&mut self) -> Result<ArgumentValue, ArgumentValueError> {
+        self.expect('{')?;
+        let mut map = BTreeMap::new();
+
+        loop {
+            self.skip_whitespace();
+            if self.peek() == Some('}') {
+                break;
+            }
+
+            let key = self.scalar()?;
+            self.expect(':')?;
+            let value = self.value()?;
+            map.insert(key, value);
+
+            self.skip_whitespace();
+            if self.peek() == Some(',') {
+                self.position += 1;
+            } else {
+                break;
+            }
+        }
+
+        self.expect('}')?;
+        Ok(ArgumentValue::Map(map))
+    }
+
+    /// A string in double quotes, or everything up to the next delimiter.
+    fn scalar(&mut self) -> Result<String, ArgumentValueError> {
+        self.skip_whitespace();
+
+        if self.peek() == Some('"') {
+            return self.quoted();
+        }
+
+        let rest = self.rest();
+        let end = rest
+            .find(|c| matches!(c, ',' | ':' | '[' | ']' | '{' | '}'))
+            .unwrap_or(rest.len());
+        let scalar = rest[..end].trim();
+
+        if scalar.is_empty() {
+            return Err(self.error("Expected a value"));
+        }
+
+        self.position += end;
+        Ok(scalar.to_string())
+    }
+
+    fn quoted(&mut self) -> Result<String, ArgumentValueError> {
+        let start = self.position;
+        self.position += 1;
+        let mut value = String::new();
+        let mut chars = self.rest().char_indices();
+
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.position += i + 1;
+                    return Ok(value);
+                },
+                '\\' => match chars.next() {
+                    Some((_, 'n')) => value.push('\n'),
+                    Some((_, 't')) => value.push('\t'),
+                    Some((_, escaped)) => value.push(escaped),
+                    None => break,
+                },
+                other => value.push(other),
+            }
+        }
+
+        self.position = start;
+        Err(self.error("Unterminated string"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(name: &str, value: &str) -> Arguments {
+        Arguments::from([(name, value)])
+    }
+
+    #[test]
+    fn parse_lists() {
+        let args = args("mean", "[0.485, 0.456, 0.406]");
+
+        let got: Vec<f32> = args.parse_list("mean").unwrap();
+
+        assert_eq!(got, vec![0.485, 0.456, 0.406]);
+    }
+
+    #[test]
+    fn comma_separated_strings_are_lists() {
+        let args = args("source", "0, 1,2");
+
+        let got: Vec<usize> = args.parse_list("source").unwrap();
+
+        assert_eq!(got, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn parse_nested_maps() {
+        let args = args(
+            "crop",
+            r#"{"size": [224, 224], mode: center, label: "a, b"}"#,
+        );
+
+        let got = args.value("crop").unwrap().unwrap();
+
+        assert_eq!(got.get("mode").unwrap().as_scalar(), Some("center"));
+        assert_eq!(got.get("label").unwrap().as_scalar(), Some("a, b"));
+        let size: Vec<u32> = got.get("size").unwrap().parse_list().unwrap();
+        assert_eq!(size, vec![224, 224]);
+    }
+
+    #[test]
+    fn malformed_lists_are_errors() {
+        let args = args("mean", "[1, 2");
+
+        let err = args.parse_list::<f32>("mean").unwrap_err();
+
+        assert!(format!("{:#}", err).contains("Expected a \"]\""));
+    }
 }
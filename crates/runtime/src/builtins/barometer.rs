@@ -0,0 +1,89 @@
+use anyhow::Error;
+
+use crate::{
+    builtins::{readings::readings_tensor, Arguments, SensorReadings},
+    Tensor,
+};
+
+/// The standard atmospheric pressure at sea level, in hectopascals.
+pub const STANDARD_SEA_LEVEL_PRESSURE: f32 = 1013.25;
+
+/// Load an input tensor from a log of air pressure readings (in hPa).
+///
+/// The `"samples"` argument sets how many readings are provided (defaulting
+/// to a single reading). If `"altitude"` is `true`, the result is a
+/// `f32[samples, 2]` tensor of `[pressure, altitude]` pairs where the
+/// altitude (in metres) is derived from the pressure and the `"sea_level"`
+/// pressure, otherwise it is a `f32[samples, 1]` tensor of pressures.
+pub fn barometer(
+    args: &Arguments,
+    readings: &SensorReadings,
+) -> Result<Tensor, Error> {
+    let samples: usize = args.parse_or_default("samples", 1)?;
+    let altitude: bool = args.parse_or_default("altitude", false)?;
+    let sea_level: f32 =
+        args.parse_or_default("sea_level", STANDARD_SEA_LEVEL_PRESSURE)?;
+
+    anyhow::ensure!(
+        sea_level > 0.0,
+        "The sea level pressure must be positive, not {}",
+        sea_level
+    );
+
+    let columns = if altitude { 2 } else { 1 };
+    let readings = readings.take(samples, 1)?;
+
+    let mut values = Vec::with_capacity(samples * columns);
+
+    for reading in readings {
+        let pressure = reading[0];
+        values.push(pressure);
+
+        if altitude {
+            values.push(pressure_to_altitude(pressure, sea_level));
+        }
+    }
+
+    Ok(readings_tensor(values, samples, columns))
+}
+
+/// Estimate the altitude (in metres) from the air pressure using the
+/// international barometric formula.
+pub fn pressure_to_altitude(pressure: f32, sea_level: f32) -> f32 {
+    44_330.0 * (1.0 - (pressure / sea_level).powf(1.0 / 5.255))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pressure_readings() {
+        let readings: SensorReadings = "1000\n1001.5\n".parse().unwrap();
+
+        let got =
+            barometer(&Arguments::from([("samples", "2")]), &readings).unwrap();
+
+        assert_eq!(got, Tensor::new(&[1000.0_f32, 1001.5], &[2, 1]));
+    }
+
+    #[test]
+    fn derive_the_altitude() {
+        let readings: SensorReadings = "1013.25\n".parse().unwrap();
+
+        let got =
+            barometer(&Arguments::from([("altitude", "true")]), &readings)
+                .unwrap();
+
+        assert_eq!(got, Tensor::new(&[1013.25_f32, 0.0], &[1, 2]));
+    }
+
+    #[test]
+    fn altitude_decreases_as_pressure_increases() {
+        let high = pressure_to_altitude(900.0, STANDARD_SEA_LEVEL_PRESSURE);
+        let low = pressure_to_altitude(1000.0, STANDARD_SEA_LEVEL_PRESSURE);
+
+        assert!((high - 988.5).abs() < 1.0, "{}", high);
+        assert!(low < high);
+    }
+}
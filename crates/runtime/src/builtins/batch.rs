@@ -0,0 +1,165 @@
+//! Batching several consecutive inputs into a single tensor so a Rune can
+//! process them with one call.
+
+use std::num::NonZeroUsize;
+
+use anyhow::{Context, Error};
+
+use crate::{
+    builtins::{combine, fan_in::declared_shape, Arguments, CombinePolicy},
+    Tensor,
+};
+
+/// Call `load` once for each item in a batch and [`stack()`] the results.
+///
+/// The `"batch_size"` argument sets how many items are loaded. Each call to
+/// `load` gets a copy of the arguments with `"batch_index"` set, which
+/// [`crate::builtins::sources()`] uses to step through consecutive sources
+/// (e.g. one image per item), while streaming sources will naturally provide
+/// their next chunk. If `"batch_size"` isn't set, this is the same as calling
+/// `load` directly.
+///
+/// When the Rune declares a `"shape"`, its leading dimension must match the
+/// batch size.
+pub fn batch(
+    args: &Arguments,
+    mut load: impl FnMut(&Arguments) -> Result<Tensor, Error>,
+) -> Result<Tensor, Error> {
+    if !args.0.contains_key("batch_size") {
+        return load(args);
+    }
+
+    let batch_size: usize = args.parse("batch_size")?;
+    anyhow::ensure!(batch_size > 0, "The batch size must be positive");
+
+    if let Some(declared) = declared_shape(args)? {
+        let leading = declared.dimensions().first().and_then(|d| d.value());
+
+        if let Some(leading) = leading {
+            anyhow::ensure!(
+                leading == batch_size,
+                "The Rune expects {}, but the batch size is {}",
+                declared,
+                batch_size,
+            );
+        }
+    }
+
+    let tensors = (0..batch_size)
+        .map(|i| {
+            let mut item_args = args.clone();
+            item_args.0.insert(String::from("batch_index"), i.to_string());
+            load(&item_args)
+                .with_context(|| format!("Unable to load batch item {}", i))
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    stack(tensors)
+}
+
+/// Stack tensors with the same shape along a leading batch dimension.
+///
+/// Most builtins already give their tensors a leading dimension of `1` (e.g.
+/// `u8[1, 224, 224, 3]`), which becomes the batch dimension. Otherwise a new
+/// dimension is added (e.g. three `f32[128, 3]` tensors become `f32[3, 128,
+/// 3]`).
+pub fn stack(tensors: Vec<Tensor>) -> Result<Tensor, Error> {
+    let count = tensors.len();
+    let first = tensors.first().context("Nothing to stack")?;
+    let item_dimensions = first.dimensions().to_vec();
+
+    let stacked = combine(CombinePolicy::Concatenate, tensors)?;
+
+    match item_dimensions.first() {
+        Some(d) if d.get() == 1 => {
+            let mut dimensions = item_dimensions;
+            dimensions[0] = NonZeroUsize::new(count).unwrap();
+            Ok(Tensor::new_raw(
+                stacked.element_type(),
+                dimensions,
+                stacked.buffer().to_vec(),
+            ))
+        },
+        _ => {
+            let dimensions = std::iter::once(NonZeroUsize::new(count).unwrap())
+                .chain(item_dimensions)
+                .collect();
+            Ok(Tensor::new_raw(
+                stacked.element_type(),
+                dimensions,
+                stacked.buffer().to_vec(),
+            ))
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builtins::load_sources;
+
+    #[test]
+    fn stack_along_an_existing_leading_dimension() {
+        let a = Tensor::new(&[1_u8, 2], &[1, 2]);
+        let b = Tensor::new(&[3_u8, 4], &[1, 2]);
+
+        let got = stack(vec![a, b]).unwrap();
+
+        assert_eq!(got, Tensor::new(&[1_u8, 2, 3, 4], &[2, 2]));
+    }
+
+    #[test]
+    fn stack_adds_a_batch_dimension() {
+        let a = Tensor::new(&[1.0_f32, 2.0], &[2, 1]);
+        let b = Tensor::new(&[3.0_f32, 4.0], &[2, 1]);
+
+        let got = stack(vec![a, b]).unwrap();
+
+        assert_eq!(got, Tensor::new(&[1.0_f32, 2.0, 3.0, 4.0], &[2, 2, 1]));
+    }
+
+    fn batch_of(sources: &[u8], args: &Arguments) -> Result<Tensor, Error> {
+        batch(args, |args| {
+            load_sources(sources, args, |&s| Ok(Tensor::new(&[s], &[1, 1])))
+        })
+    }
+
+    #[test]
+    fn batches_step_through_consecutive_sources() {
+        let sources = [1_u8, 2, 3, 4];
+        let args = Arguments::from([("batch_size", "3"), ("source", "1")]);
+
+        let got = batch_of(&sources, &args).unwrap();
+
+        assert_eq!(got, Tensor::new(&[2_u8, 3, 4], &[3, 1]));
+    }
+
+    #[test]
+    fn batches_need_a_source_for_every_item() {
+        let sources = [1_u8, 2, 3];
+        let args = Arguments::from([("batch_size", "3"), ("source", "1")]);
+
+        let err = batch_of(&sources, &args).unwrap_err();
+
+        assert_eq!(
+            format!("{:#}", err),
+            "Unable to load batch item 2: Batch item 2 needs source 3, but \
+             there are only 3 sources available"
+        );
+    }
+
+    #[test]
+    fn batch_size_must_match_the_declared_shape() {
+        let sources = [1_u8, 2, 3];
+        let matching =
+            Arguments::from([("batch_size", "3"), ("shape", "u8[3, 1]")]);
+        let mismatched =
+            Arguments::from([("batch_size", "2"), ("shape", "u8[3, 1]")]);
+
+        assert!(batch_of(&sources, &matching).is_ok());
+        assert_eq!(
+            batch_of(&sources, &mismatched).unwrap_err().to_string(),
+            "The Rune expects u8[3, 1], but the batch size is 2"
+        );
+    }
+}
@@ -0,0 +1,107 @@
+use std::time::Duration;
+
+use anyhow::Error;
+
+use crate::{
+    builtins::{readings::readings_tensor, Arguments, SensorReadings},
+    Tensor,
+};
+
+/// Load an input tensor from a battery reading.
+///
+/// The result is a `f32[1, 2]` tensor containing the charge level (as a
+/// percentage) and the battery's voltage.
+pub fn battery(_args: &Arguments, reading: BatteryReading) -> Tensor {
+    let BatteryReading { level, voltage } = reading;
+    readings_tensor(vec![level, voltage], 1, 2)
+}
+
+/// The state of the device's battery.
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct BatteryReading {
+    /// The charge level, from `0` (empty) to `100` (full).
+    pub level: f32,
+    /// The battery's voltage, in volts.
+    pub voltage: f32,
+}
+
+impl BatteryReading {
+    /// Use the first row of a `level,voltage` CSV log.
+    pub fn from_readings(readings: &SensorReadings) -> Result<Self, Error> {
+        let first = readings.take(1, 2)?;
+
+        Ok(BatteryReading {
+            level: first[0][0],
+            voltage: first[0][1],
+        })
+    }
+}
+
+/// A fake battery for testing power-aware Runes on a desktop machine.
+///
+/// The battery starts fully charged and discharges linearly, with its voltage
+/// interpolated between [`SimulatedBattery::empty_voltage`] and
+/// [`SimulatedBattery::full_voltage`] (a typical Li-ion cell by default).
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SimulatedBattery {
+    /// How much charge is lost each minute, as a percentage.
+    pub drain_per_minute: f32,
+    pub full_voltage: f32,
+    pub empty_voltage: f32,
+}
+
+impl SimulatedBattery {
+    /// Read the battery after it has been discharging for `elapsed`.
+    pub fn reading(&self, elapsed: Duration) -> BatteryReading {
+        let minutes = elapsed.as_secs_f32() / 60.0;
+        let level = (100.0 - minutes * self.drain_per_minute).clamp(0.0, 100.0);
+        let voltage = self.empty_voltage
+            + (self.full_voltage - self.empty_voltage) * level / 100.0;
+
+        BatteryReading { level, voltage }
+    }
+}
+
+impl Default for SimulatedBattery {
+    fn default() -> Self {
+        SimulatedBattery {
+            drain_per_minute: 1.0,
+            full_voltage: 4.2,
+            empty_voltage: 3.3,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn simulated_battery_drains_over_time() {
+        let battery = SimulatedBattery {
+            drain_per_minute: 10.0,
+            ..Default::default()
+        };
+
+        let full = battery.reading(Duration::from_secs(0));
+        let half = battery.reading(Duration::from_secs(5 * 60));
+        let empty = battery.reading(Duration::from_secs(60 * 60));
+
+        assert_eq!(full.level, 100.0);
+        assert!((full.voltage - 4.2).abs() < 1e-6);
+        assert_eq!(half.level, 50.0);
+        assert!((half.voltage - 3.75).abs() < 1e-6);
+        assert_eq!(empty.level, 0.0);
+        assert!((empty.voltage - 3.3).abs() < 1e-6);
+    }
+
+    #[test]
+    fn read_from_a_log() {
+        let readings: SensorReadings = "87.5,3.9\n80,3.85\n".parse().unwrap();
+
+        let reading = BatteryReading::from_readings(&readings).unwrap();
+        let got = battery(&Arguments::from([]), reading);
+
+        assert_eq!(got, Tensor::new(&[87.5_f32, 3.9], &[1, 2]));
+    }
+}
@@ -0,0 +1,190 @@
+use std::{
+    collections::VecDeque,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Error};
+
+use crate::{
+    builtins::{self, Arguments, AudioClip, Table},
+    Tensor,
+};
+
+/// A directory of images, audio clips, and CSV files which are fed to a Rune
+/// one item at a time (e.g. to evaluate a model against a test set).
+///
+/// Subdirectories are included, and items are visited in order of their path
+/// so results are reproducible. Once every item has been visited,
+/// [`Dataset::next()`] returns `None` to tell the host it has reached the end
+/// of the dataset.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dataset {
+    remaining: VecDeque<DatasetItem>,
+    total: usize,
+}
+
+impl Dataset {
+    pub fn open(directory: impl AsRef<Path>) -> Result<Self, Error> {
+        let directory = directory.as_ref();
+        let mut items = Vec::new();
+        let mut to_visit = vec![directory.to_path_buf()];
+
+        while let Some(dir) = to_visit.pop() {
+            let entries = std::fs::read_dir(&dir).with_context(|| {
+                format!("Unable to read the \"{}\" directory", dir.display())
+            })?;
+
+            for entry in entries {
+                let path = entry?.path();
+
+                if path.is_dir() {
+                    to_visit.push(path);
+                } else if let Some(kind) = DatasetKind::from_path(&path) {
+                    items.push(DatasetItem { path, kind });
+                } else {
+                    log::debug!("Skipping \"{}\"", path.display());
+                }
+            }
+        }
+
+        anyhow::ensure!(
+            !items.is_empty(),
+            "No images, audio clips, or CSV files were found in \"{}\"",
+            directory.display()
+        );
+
+        Ok(Dataset::from_items(items))
+    }
+
+    pub fn from_items(mut items: Vec<DatasetItem>) -> Self {
+        items.sort_by(|left, right| left.path.cmp(&right.path));
+
+        Dataset {
+            total: items.len(),
+            remaining: items.into(),
+        }
+    }
+
+    /// The total number of items in the dataset.
+    pub fn len(&self) -> usize { self.total }
+
+    pub fn is_empty(&self) -> bool { self.total == 0 }
+
+    /// The number of items which haven't been visited yet.
+    pub fn remaining(&self) -> usize { self.remaining.len() }
+
+    /// Have we reached the end of the dataset?
+    pub fn is_finished(&self) -> bool { self.remaining.is_empty() }
+}
+
+impl Iterator for Dataset {
+    type Item = DatasetItem;
+
+    fn next(&mut self) -> Option<Self::Item> { self.remaining.pop_front() }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining(), Some(self.remaining()))
+    }
+}
+
+/// A single file from a [`Dataset`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct DatasetItem {
+    pub path: PathBuf,
+    pub kind: DatasetKind,
+}
+
+impl DatasetItem {
+    /// Load the item using the builtin for its capability.
+    pub fn load(&self, args: &Arguments) -> Result<Tensor, Error> {
+        let path = &self.path;
+
+        match self.kind {
+            DatasetKind::Image => {
+                let img = image::open(path).with_context(|| {
+                    format!("Unable to read \"{}\"", path.display())
+                })?;
+                builtins::image(args, &img)
+            },
+            DatasetKind::Audio => {
+                let clip = AudioClip::from_file(path)?;
+                builtins::sound(args, &clip)
+            },
+            DatasetKind::Table => {
+                let table = Table::from_file(path)?;
+                builtins::tabular(args, &table)
+            },
+        }
+    }
+}
+
+/// The kinds of file a [`Dataset`] can contain.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DatasetKind {
+    /// An image, for the `IMAGE` capability.
+    Image,
+    /// An audio clip, for the `SOUND` capability.
+    Audio,
+    /// A CSV file, for the `TABULAR` capability.
+    Table,
+}
+
+impl DatasetKind {
+    pub fn from_path(path: impl AsRef<Path>) -> Option<Self> {
+        let extension = path.as_ref().extension()?.to_str()?.to_lowercase();
+
+        match extension.as_str() {
+            "png" | "jpg" | "jpeg" | "bmp" | "gif" => Some(DatasetKind::Image),
+            "wav" | "mp3" | "ogg" | "flac" => Some(DatasetKind::Audio),
+            "csv" => Some(DatasetKind::Table),
+            _ => None,
+        }
+    }
+
+    /// The name of the capability this kind of item should be used for.
+    pub fn capability(self) -> &'static str {
+        match self {
+            DatasetKind::Image => "IMAGE",
+            DatasetKind::Audio => "SOUND",
+            DatasetKind::Table => "TABULAR",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn walk_a_directory_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let cats = dir.path().join("cat");
+        std::fs::create_dir(&cats).unwrap();
+        for path in [
+            cats.join("2.png"),
+            cats.join("1.JPG"),
+            dir.path().join("labels.csv"),
+            dir.path().join("README.md"),
+        ] {
+            std::fs::write(path, b"").unwrap();
+        }
+
+        let mut dataset = Dataset::open(dir.path()).unwrap();
+
+        assert_eq!(dataset.len(), 3);
+        let first = dataset.next().unwrap();
+        assert_eq!(first.path, cats.join("1.JPG"));
+        assert_eq!(first.kind, DatasetKind::Image);
+        assert_eq!(dataset.next().unwrap().path, cats.join("2.png"));
+        assert_eq!(dataset.next().unwrap().kind, DatasetKind::Table);
+        assert!(dataset.is_finished());
+        assert_eq!(dataset.next(), None);
+    }
+
+    #[test]
+    fn empty_directories_are_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+
+        assert!(Dataset::open(dir.path()).is_err());
+    }
+}
@@ -0,0 +1,463 @@
+//! Feeding several sources into a single capability.
+//!
+//! A capability's `"source"` argument may be a comma-separated list of
+//! indices (e.g. `source: 0,1` for a stereo pair of microphones), in which
+//! case the input from each source is merged into one tensor using the
+//! policy given by the `"combine"` argument.
+//!
+//! The Rune declares the shape of the *combined* tensor, so each source needs
+//! to provide its share of it. For example, interleaving a stereo pair of
+//! microphones into an `i16[1, 32000]` tensor means recording 16000 samples
+//! from each microphone.
+
+use std::{num::NonZeroUsize, str::FromStr};
+
+use anyhow::{Context, Error};
+use hotg_rune_core::Shape;
+
+use crate::{builtins::Arguments, ElementType, Tensor};
+
+/// Use the `"source"` argument to figure out which inputs to read.
+///
+/// This accepts either a single index or a comma-separated list of indices,
+/// defaulting to the first source. When loading one item in a
+/// [`crate::builtins::batch()`], the `"batch_index"` is added to each index,
+/// so there need to be enough sources for the whole batch.
+pub fn sources<'src, T>(
+    sources: &'src [T],
+    args: &Arguments,
+) -> Result<Vec<&'src T>, Error> {
+    let batch_index: usize = args.parse_or_default("batch_index", 0)?;
+
+    let indices = match args.0.get("source") {
+        Some(value) => value
+            .split(',')
+            .map(|index| {
+                index.trim().parse::<usize>().with_context(|| {
+                    format!("Unable to parse {:?} as a source index", index)
+                })
+            })
+            .collect::<Result<Vec<_>, Error>>()?,
+        None => vec![0],
+    };
+
+    indices
+        .into_iter()
+        .map(|index| {
+            let source = sources.get(index + batch_index);
+
+            match batch_index {
+                0 => source.with_context(|| {
+                    format!(
+                        "The user asked for source {}, but there are only {} \
+                         sources available",
+                        index,
+                        sources.len(),
+                    )
+                }),
+                _ => source.with_context(|| {
+                    format!(
+                        "Batch item {} needs source {}, but there are only {} \
+                         sources available",
+                        batch_index,
+                        index + batch_index,
+                        sources.len(),
+                    )
+                }),
+            }
+        })
+        .collect()
+}
+
+/// Load every source selected by the `"source"` argument and merge them into
+/// a single tensor using the [`CombinePolicy`] from the `"combine"`
+/// argument.
+pub fn load_sources<T>(
+    all_sources: &[T],
+    args: &Arguments,
+    load: impl Fn(&T) -> Result<Tensor, Error>,
+) -> Result<Tensor, Error> {
+    let tensors = sources(all_sources, args)?
+        .into_iter()
+        .map(load)
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    let count = tensors.len();
+    let policy = args.parse_or_default("combine", CombinePolicy::default())?;
+    let combined = combine(policy, tensors)?;
+
+    // Items in a batch are only part of the declared shape, so they get
+    // checked once they have been stacked
+    if !args.0.contains_key("batch_index") {
+        if let Some(declared) = declared_shape(args)? {
+            check_combined_shape(policy, count, &combined, &declared, args)?;
+        }
+    }
+
+    Ok(combined)
+}
+
+/// The shape of the tensor the Rune expects a capability to provide.
+///
+/// This is sent by the Rune as the `"shape"` argument, so it will be `None`
+/// for Runes compiled before the argument was added.
+pub(crate) fn declared_shape(
+    args: &Arguments,
+) -> Result<Option<Shape<'static>>, Error> {
+    match args.0.get("shape") {
+        Some(value) => value.parse().map(Some).map_err(|e| {
+            anyhow::anyhow!("Unable to parse {:?} as a shape: {}", value, e)
+        }),
+        None => Ok(None),
+    }
+}
+
+/// Make sure a [`CombinePolicy`] can split the declared shape evenly between
+/// `source_count` sources, so mistakes are caught when the Rune is loaded.
+pub(crate) fn check_policy(
+    policy: CombinePolicy,
+    source_count: usize,
+    declared: &Shape<'_>,
+) -> Result<(), String> {
+    let dimensions = declared.dimensions();
+    let (axis, dimension) = match policy {
+        CombinePolicy::Mean => return Ok(()),
+        _ if source_count <= 1 => return Ok(()),
+        CombinePolicy::Interleave => ("last", dimensions.last()),
+        CombinePolicy::Concatenate => ("first", dimensions.first()),
+    };
+
+    match dimension.and_then(|d| d.value()) {
+        Some(length) if length % source_count == 0 => Ok(()),
+        _ => Err(format!(
+            "{} {} sources into {} needs the {} dimension to be a multiple of \
+             {}",
+            policy.verb(),
+            source_count,
+            declared,
+            axis,
+            source_count,
+        )),
+    }
+}
+
+/// Compare a combined tensor with the shape the Rune declared, taking any
+/// `"convert_to"` conversion into account, and explain what each source
+/// should have provided when they don't match.
+fn check_combined_shape(
+    policy: CombinePolicy,
+    source_count: usize,
+    combined: &Tensor,
+    declared: &Shape<'_>,
+    args: &Arguments,
+) -> Result<(), Error> {
+    if source_count <= 1 {
+        // The normal "wrong input size" error will be more useful here
+        return Ok(());
+    }
+
+    let element_count =
+        combined.buffer().len() / combined.element_type().byte_size();
+    let actual_bytes = match args.0.get("convert_to") {
+        Some(ty) => {
+            element_count * ty.trim().parse::<ElementType>()?.byte_size()
+        },
+        None => combined.buffer().len(),
+    };
+    match declared.size() {
+        Some(expected) if expected != actual_bytes => {},
+        _ => return Ok(()),
+    }
+
+    let mut msg = format!(
+        "{} {} sources gave {}, but the Rune expects {}",
+        policy.verb(),
+        source_count,
+        combined.shape(),
+        declared,
+    );
+
+    if let Some(dimensions) = declared.fixed_dimensions() {
+        let mut per_source = dimensions;
+        let axis = match policy {
+            CombinePolicy::Interleave => per_source.len().checked_sub(1),
+            CombinePolicy::Concatenate => Some(0),
+            CombinePolicy::Mean => None,
+        };
+        if let Some(axis) = axis {
+            per_source[axis] /= source_count;
+        }
+
+        msg.push_str(&format!(
+            " (each source should provide {:?})",
+            per_source
+        ));
+    }
+
+    Err(Error::msg(msg))
+}
+
+/// How the inputs from multiple sources are merged into one tensor.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CombinePolicy {
+    /// Alternate between elements from each source (e.g. `[l0, r0, l1, r1,
+    /// ...]` for a stereo pair of microphones), multiplying the last
+    /// dimension by the number of sources.
+    Interleave,
+    /// Append each source's elements one after another, multiplying the
+    /// first dimension by the number of sources.
+    Concatenate,
+    /// Take the element-wise average of every source (e.g. to mix several
+    /// microphones down to mono).
+    Mean,
+}
+
+impl CombinePolicy {
+    fn verb(self) -> &'static str {
+        match self {
+            CombinePolicy::Interleave => "Interleaving",
+            CombinePolicy::Concatenate => "Concatenating",
+            CombinePolicy::Mean => "Averaging",
+        }
+    }
+}
+
+impl Default for CombinePolicy {
+    fn default() -> Self { CombinePolicy::Interleave }
+}
+
+impl FromStr for CombinePolicy {
+    type Err = UnknownCombinePolicy;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "interleave" => Ok(CombinePolicy::Interleave),
+            "concat" | "concatenate" => Ok(CombinePolicy::Concatenate),
+            "mean" | "average" => Ok(CombinePolicy::Mean),
+            _ => Err(UnknownCombinePolicy),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, thiserror::Error)]
+#[error("Expected one of \"interleave\", \"concat\", or \"mean\"")]
+pub struct UnknownCombinePolicy;
+
+/// Merge several tensors with the same shape into one.
+pub fn combine(
+    policy: CombinePolicy,
+    mut tensors: Vec<Tensor>,
+) -> Result<Tensor, Error> {
+    let first = match tensors.len() {
+        0 => anyhow::bail!("No sources were provided"),
+        1 => return Ok(tensors.remove(0)),
+        _ => &tensors[0],
+    };
+
+    let element_type = first.element_type();
+    let dimensions = first.dimensions().to_vec();
+
+    for (i, tensor) in tensors.iter().enumerate().skip(1) {
+        anyhow::ensure!(
+            tensor.element_type() == element_type
+                && tensor.dimensions() == dimensions.as_slice(),
+            "Unable to combine source {} ({}) with source 0 ({}) because \
+             their shapes are different",
+            i,
+            tensor.shape(),
+            first.shape(),
+        );
+    }
+
+    let count = tensors.len();
+
+    let tensor = match policy {
+        CombinePolicy::Interleave => {
+            let element_size = element_type.byte_size();
+            let mut buffer = Vec::with_capacity(first.buffer().len() * count);
+            let num_elements = first.buffer().len() / element_size;
+
+            for i in 0..num_elements {
+                for tensor in &tensors {
+                    let start = i * element_size;
+                    buffer.extend_from_slice(
+                        &tensor.buffer()[start..start + element_size],
+                    );
+                }
+            }
+
+            let dimensions = scale_dimension(dimensions, true, count);
+            Tensor::new_raw(element_type, dimensions, buffer)
+        },
+        CombinePolicy::Concatenate => {
+            let buffer: Vec<u8> = tensors
+                .iter()
+                .flat_map(|t| t.buffer().iter().copied())
+                .collect();
+            let dimensions = scale_dimension(dimensions, false, count);
+            Tensor::new_raw(element_type, dimensions, buffer)
+        },
+        CombinePolicy::Mean => mean(element_type, dimensions, &tensors),
+    };
+
+    Ok(tensor)
+}
+
+/// Multiply the first or last dimension by `factor`, treating scalars as a
+/// tensor with a single element.
+fn scale_dimension(
+    mut dimensions: Vec<NonZeroUsize>,
+    last: bool,
+    factor: usize,
+) -> Vec<NonZeroUsize> {
+    if dimensions.is_empty() {
+        dimensions.push(NonZeroUsize::new(1).unwrap());
+    }
+
+    let index = if last { dimensions.len() - 1 } else { 0 };
+    let scaled = dimensions[index].get() * factor;
+    dimensions[index] = NonZeroUsize::new(scaled).unwrap();
+
+    dimensions
+}
+
+fn mean(
+    element_type: ElementType,
+    dimensions: Vec<NonZeroUsize>,
+    tensors: &[Tensor],
+) -> Tensor {
+    macro_rules! mean_of {
+        ($($variant:ident => $type:ty),* $(,)?) => {
+            match element_type {
+                $(
+                    ElementType::$variant => {
+                        let inputs: Vec<&[$type]> = tensors
+                            .iter()
+                            .map(|t| t.elements::<$type>().unwrap())
+                            .collect();
+                        let averaged: Vec<$type> = (0..inputs[0].len())
+                            .map(|i| {
+                                let sum: f64 =
+                                    inputs.iter().map(|e| e[i] as f64).sum();
+                                (sum / inputs.len() as f64) as $type
+                            })
+                            .collect();
+                        let dimensions: Vec<usize> =
+                            dimensions.iter().map(|d| d.get()).collect();
+
+                        Tensor::new(&averaged, &dimensions)
+                    },
+                )*
+            }
+        };
+    }
+
+    mean_of! {
+        U8 => u8,
+        I8 => i8,
+        U16 => u16,
+        I16 => i16,
+        U32 => u32,
+        I32 => i32,
+        F32 => f32,
+        U64 => u64,
+        I64 => i64,
+        F64 => f64,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_a_list_of_sources() {
+        let all = ["first", "second", "third"];
+
+        let got =
+            sources(&all, &Arguments::from([("source", "2, 0")])).unwrap();
+
+        assert_eq!(got, vec![&"third", &"first"]);
+        assert!(sources(&all, &Arguments::from([("source", "0,3")])).is_err());
+        assert_eq!(
+            sources(&all, &Arguments::from([])).unwrap(),
+            vec![&"first"]
+        );
+    }
+
+    #[test]
+    fn interleave_two_microphones() {
+        let left = Tensor::new(&[1_i16, 2, 3], &[1, 3]);
+        let right = Tensor::new(&[-1_i16, -2, -3], &[1, 3]);
+
+        let got =
+            combine(CombinePolicy::Interleave, vec![left, right]).unwrap();
+
+        assert_eq!(got, Tensor::new(&[1_i16, -1, 2, -2, 3, -3], &[1, 6]));
+    }
+
+    #[test]
+    fn concatenate_sources() {
+        let first = Tensor::new(&[1_u8, 2], &[1, 2]);
+        let second = Tensor::new(&[3_u8, 4], &[1, 2]);
+
+        let got =
+            combine(CombinePolicy::Concatenate, vec![first, second]).unwrap();
+
+        assert_eq!(got, Tensor::new(&[1_u8, 2, 3, 4], &[2, 2]));
+    }
+
+    #[test]
+    fn average_sources() {
+        let first = Tensor::new(&[0.0_f32, 2.0], &[2]);
+        let second = Tensor::new(&[1.0_f32, 4.0], &[2]);
+
+        let got = combine(CombinePolicy::Mean, vec![first, second]).unwrap();
+
+        assert_eq!(got, Tensor::new(&[0.5_f32, 3.0], &[2]));
+    }
+
+    #[test]
+    fn sources_must_have_the_same_shape() {
+        let first = Tensor::new(&[0_u8; 4], &[4]);
+        let second = Tensor::new(&[0_u8; 2], &[2]);
+
+        assert!(combine(CombinePolicy::Mean, vec![first, second]).is_err());
+    }
+
+    #[test]
+    fn combined_sources_must_match_the_declared_shape() {
+        let all = [[1_i16, 2], [3, 4]];
+        let load = |samples: &[i16; 2]| Ok(Tensor::new(samples, &[1, 2]));
+        let stereo =
+            Arguments::from([("source", "0,1"), ("shape", "i16[1, 4]")]);
+        let too_small =
+            Arguments::from([("source", "0,1"), ("shape", "i16[1, 2]")]);
+
+        let got = load_sources(&all, &stereo, load).unwrap();
+        let err = load_sources(&all, &too_small, load).unwrap_err();
+
+        assert_eq!(got, Tensor::new(&[1_i16, 3, 2, 4], &[1, 4]));
+        assert_eq!(
+            err.to_string(),
+            "Interleaving 2 sources gave i16[1, 4], but the Rune expects \
+             i16[1, 2] (each source should provide [1, 1])"
+        );
+    }
+
+    #[test]
+    fn conversions_are_taken_into_account() {
+        let all = [[1_u8, 2], [3, 4]];
+        let load = |pixels: &[u8; 2]| Ok(Tensor::new(pixels, &[2, 1]));
+        let args = Arguments::from([
+            ("source", "0,1"),
+            ("combine", "concat"),
+            ("convert_to", "f32"),
+            ("shape", "f32[4, 1]"),
+        ]);
+
+        let got = load_sources(&all, &args, load).unwrap();
+
+        assert_eq!(got, Tensor::new(&[1_u8, 2, 3, 4], &[4, 1]));
+    }
+}
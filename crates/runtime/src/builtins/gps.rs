@@ -0,0 +1,303 @@
+use std::{ops::Deref, path::Path};
+
+use anyhow::{Context, Error};
+
+use crate::{builtins::Arguments, Tensor};
+
+/// A rough estimate of a receiver's error (in metres) for each unit of
+/// horizontal dilution of precision.
+const METRES_PER_HDOP: f64 = 5.0;
+
+/// Load an input tensor from a GPS track.
+///
+/// The tensor is a `f64[samples, 4]` where each row contains the latitude
+/// and longitude (in degrees), altitude (in metres), and estimated accuracy
+/// (in metres, or `0` when unknown).
+pub fn gps(args: &Arguments, track: &GpsTrack) -> Result<Tensor, Error> {
+    let requested_samples: usize = args.parse_or_default("samples", 1)?;
+
+    if requested_samples > track.len() {
+        anyhow::bail!(
+            "{} GPS fixes were requested but only {} are available",
+            requested_samples,
+            track.len(),
+        );
+    }
+
+    let mut buffer = Vec::with_capacity(requested_samples * 4);
+
+    for fix in &track[..requested_samples] {
+        let GpsFix {
+            latitude,
+            longitude,
+            altitude,
+            accuracy,
+        } = *fix;
+        buffer.extend([latitude, longitude, altitude, accuracy]);
+    }
+
+    Ok(Tensor::new(&buffer, &[requested_samples, 4]))
+}
+
+/// A single position reported by a GPS receiver.
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct GpsFix {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub altitude: f64,
+    pub accuracy: f64,
+}
+
+/// A recorded sequence of [`GpsFix`]es which can be played back to a Rune.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct GpsTrack(pub Vec<GpsFix>);
+
+impl GpsTrack {
+    /// Load a track from disk, treating `*.gpx` files as GPX and everything
+    /// else as NMEA 0183 sentences.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path).with_context(|| {
+            format!("Unable to read \"{}\"", path.display())
+        })?;
+
+        let is_gpx = path
+            .extension()
+            .map(|ext| ext.eq_ignore_ascii_case("gpx"))
+            .unwrap_or(false);
+
+        if is_gpx {
+            GpsTrack::from_gpx(&text)
+        } else {
+            GpsTrack::from_nmea(&text)
+        }
+    }
+
+    /// Parse the track points (`<trkpt>`), route points (`<rtept>`), and
+    /// waypoints (`<wpt>`) from a GPX document.
+    pub fn from_gpx(text: &str) -> Result<Self, Error> {
+        let doc = roxmltree::Document::parse(text)
+            .context("Unable to parse the GPX document")?;
+
+        let mut fixes = Vec::new();
+
+        let points = doc.descendants().filter(|node| {
+            matches!(node.tag_name().name(), "trkpt" | "rtept" | "wpt")
+        });
+
+        for point in points {
+            let attribute = |name: &str| -> Result<f64, Error> {
+                let value = point.attribute(name).with_context(|| {
+                    format!("A GPX point is missing its \"{}\" attribute", name)
+                })?;
+                value.parse().with_context(|| {
+                    format!("Unable to parse \"{}\" as a {}", value, name)
+                })
+            };
+            let child = |name: &str| -> Result<Option<f64>, Error> {
+                let text = point
+                    .children()
+                    .find(|child| child.tag_name().name() == name)
+                    .and_then(|child| child.text());
+
+                match text {
+                    Some(text) => text
+                        .trim()
+                        .parse()
+                        .map(Some)
+                        .with_context(|| {
+                            format!("Unable to parse \"{}\" as {}", text, name)
+                        }),
+                    None => Ok(None),
+                }
+            };
+
+            fixes.push(GpsFix {
+                latitude: attribute("lat")?,
+                longitude: attribute("lon")?,
+                altitude: child("ele")?.unwrap_or_default(),
+                accuracy: child("hdop")?
+                    .map(|hdop| hdop * METRES_PER_HDOP)
+                    .unwrap_or_default(),
+            });
+        }
+
+        Ok(GpsTrack(fixes))
+    }
+
+    /// Parse the `GGA` sentences from a NMEA 0183 log, ignoring any other
+    /// sentences and fixes that aren't valid.
+    pub fn from_nmea(text: &str) -> Result<Self, Error> {
+        let mut fixes = Vec::new();
+
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+
+            if let Some(fix) = parse_gga(line)
+                .with_context(|| format!("Unable to parse line {}", i + 1))?
+            {
+                fixes.push(fix);
+            }
+        }
+
+        Ok(GpsTrack(fixes))
+    }
+}
+
+impl Deref for GpsTrack {
+    type Target = [GpsFix];
+
+    fn deref(&self) -> &Self::Target { &self.0 }
+}
+
+/// Parse a `$--GGA` sentence (e.g. `$GPGGA` or `$GNGGA`), returning `None`
+/// for other sentences or when the receiver didn't have a fix.
+fn parse_gga(sentence: &str) -> Result<Option<GpsFix>, Error> {
+    // Strip the checksum, if there is one
+    let sentence = sentence.split('*').next().unwrap_or_default();
+    let fields: Vec<&str> = sentence.split(',').collect();
+
+    let is_gga = fields[0].len() == 6
+        && fields[0].starts_with('$')
+        && fields[0].ends_with("GGA");
+
+    if !is_gga {
+        return Ok(None);
+    }
+
+    anyhow::ensure!(
+        fields.len() >= 10,
+        "A GGA sentence should have at least 10 fields, found {}",
+        fields.len()
+    );
+
+    let fix_quality = fields[6];
+    if fix_quality.is_empty() || fix_quality == "0" {
+        return Ok(None);
+    }
+
+    let latitude = parse_coordinate(fields[2], fields[3], 2)?;
+    let longitude = parse_coordinate(fields[4], fields[5], 3)?;
+    let hdop: f64 = parse_number(fields[8])?;
+    let altitude: f64 = parse_number(fields[9])?;
+
+    Ok(Some(GpsFix {
+        latitude,
+        longitude,
+        altitude,
+        accuracy: hdop * METRES_PER_HDOP,
+    }))
+}
+
+/// Convert a NMEA coordinate (`DDMM.MMMM` or `DDDMM.MMMM`) and hemisphere
+/// into decimal degrees.
+fn parse_coordinate(
+    value: &str,
+    hemisphere: &str,
+    degree_digits: usize,
+) -> Result<f64, Error> {
+    anyhow::ensure!(
+        value.len() > degree_digits && value.is_char_boundary(degree_digits),
+        "\"{}\" isn't a valid coordinate",
+        value
+    );
+
+    let (degrees, minutes) = value.split_at(degree_digits);
+    let degrees: f64 = parse_number(degrees)?;
+    let minutes: f64 = parse_number(minutes)?;
+    let decimal = degrees + minutes / 60.0;
+
+    match hemisphere {
+        "N" | "E" => Ok(decimal),
+        "S" | "W" => Ok(-decimal),
+        other => anyhow::bail!("\"{}\" isn't a valid hemisphere", other),
+    }
+}
+
+fn parse_number(value: &str) -> Result<f64, Error> {
+    if value.is_empty() {
+        return Ok(0.0);
+    }
+
+    value
+        .parse()
+        .with_context(|| format!("Unable to parse \"{}\" as a number", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_nmea_sentences() {
+        let log = [
+            "$GPRMC,123519,A,4807.038,N,01131.000,E,022.4,084.4,230394,,W*6A",
+            "$GPGGA,123519,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*47",
+            "$GPGGA,123520,,,,,0,00,,,M,,M,,*66",
+        ]
+        .join("\n");
+
+        let track = GpsTrack::from_nmea(&log).unwrap();
+
+        assert_eq!(track.len(), 1);
+        let fix = track[0];
+        assert!((fix.latitude - 48.1173).abs() < 1e-4);
+        assert!((fix.longitude - 11.516_666).abs() < 1e-4);
+        assert_eq!(fix.altitude, 545.4);
+        assert_eq!(fix.accuracy, 0.9 * METRES_PER_HDOP);
+    }
+
+    #[test]
+    fn southern_and_western_hemispheres_are_negative() {
+        let got = parse_coordinate("3351.000", "S", 2).unwrap();
+        assert_eq!(got, -33.85);
+
+        let got = parse_coordinate("15112.000", "W", 3).unwrap();
+        assert_eq!(got, -151.2);
+    }
+
+    #[test]
+    fn parse_a_gpx_track() {
+        let gpx = r#"<?xml version="1.0"?>
+            <gpx version="1.1" xmlns="http://www.topografix.com/GPX/1/1">
+              <trk><trkseg>
+                <trkpt lat="-33.85" lon="151.2"><ele>12.5</ele></trkpt>
+                <trkpt lat="-33.86" lon="151.21">
+                  <ele>13</ele>
+                  <hdop>2</hdop>
+                </trkpt>
+              </trkseg></trk>
+            </gpx>"#;
+
+        let track = GpsTrack::from_gpx(gpx).unwrap();
+
+        assert_eq!(
+            track.0,
+            vec![
+                GpsFix {
+                    latitude: -33.85,
+                    longitude: 151.2,
+                    altitude: 12.5,
+                    accuracy: 0.0,
+                },
+                GpsFix {
+                    latitude: -33.86,
+                    longitude: 151.21,
+                    altitude: 13.0,
+                    accuracy: 2.0 * METRES_PER_HDOP,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn load_several_fixes() {
+        let track = GpsTrack(vec![GpsFix::default(); 3]);
+        let args = Arguments::from([("samples", "2")]);
+
+        let got = gps(&args, &track).unwrap();
+
+        assert_eq!(got, Tensor::new(&[0.0_f64; 8], &[2, 4]));
+        assert!(gps(&Arguments::from([]), &GpsTrack::default()).is_err());
+    }
+}
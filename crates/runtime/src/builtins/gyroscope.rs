@@ -0,0 +1,144 @@
+use std::{io::Read, ops::Deref, path::Path, str::FromStr};
+
+use anyhow::Error;
+
+use crate::{
+    builtins::{AccelerometerParseError, AccelerometerSamples, Arguments},
+    Tensor,
+};
+
+/// Gyroscope recordings use the same `x,y,z` CSV format as the
+/// accelerometer.
+pub type GyroscopeParseError = AccelerometerParseError;
+
+/// Load an input tensor from a set of gyroscope samples.
+///
+/// The `"samples"` argument sets how many readings are provided (defaulting
+/// to all of them) and `"rate"` is the sampling rate in Hz, which device
+/// readers use when polling the sensor. The result is a `f32[samples, 3]`
+/// tensor of angular velocities around the X, Y, and Z axes.
+pub fn gyroscope(
+    args: &Arguments,
+    samples: &GyroscopeSamples,
+) -> Result<Tensor, Error> {
+    let requested_samples: usize =
+        args.parse_or_default("samples", samples.len())?;
+    let rate: Option<f32> = args
+        .0
+        .get("rate")
+        .map(|_| args.parse("rate"))
+        .transpose()?;
+
+    if let Some(rate) = rate {
+        anyhow::ensure!(
+            rate > 0.0,
+            "The sampling rate must be positive, not {}",
+            rate
+        );
+    }
+
+    if requested_samples > samples.len() {
+        anyhow::bail!(
+            "{} samples were requested but only {} are available",
+            requested_samples,
+            samples.len(),
+        );
+    }
+
+    let mut buffer = Vec::with_capacity(requested_samples * 3);
+
+    for sample in &samples[..requested_samples] {
+        let GyroscopeSample { x, y, z } = *sample;
+        buffer.extend([x, y, z]);
+    }
+
+    Ok(Tensor::new(&buffer, &[requested_samples, 3]))
+}
+
+/// The angular velocity around each axis, in radians per second.
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct GyroscopeSample {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct GyroscopeSamples(pub Vec<GyroscopeSample>);
+
+impl GyroscopeSamples {
+    pub fn from_file(
+        path: impl AsRef<Path>,
+    ) -> Result<Self, GyroscopeParseError> {
+        AccelerometerSamples::from_file(path).map(GyroscopeSamples::from)
+    }
+
+    pub fn from_reader(
+        reader: impl Read,
+    ) -> Result<Self, GyroscopeParseError> {
+        AccelerometerSamples::from_reader(reader).map(GyroscopeSamples::from)
+    }
+}
+
+impl From<AccelerometerSamples> for GyroscopeSamples {
+    fn from(samples: AccelerometerSamples) -> Self {
+        GyroscopeSamples(
+            samples
+                .iter()
+                .map(|s| GyroscopeSample {
+                    x: s.x,
+                    y: s.y,
+                    z: s.z,
+                })
+                .collect(),
+        )
+    }
+}
+
+impl FromStr for GyroscopeSamples {
+    type Err = GyroscopeParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        GyroscopeSamples::from_reader(s.as_bytes())
+    }
+}
+
+impl Deref for GyroscopeSamples {
+    type Target = [GyroscopeSample];
+
+    fn deref(&self) -> &Self::Target { &self.0 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn load_the_requested_number_of_samples() {
+        let samples: GyroscopeSamples =
+            "0.1,0.2,0.3\n0.4,0.5,0.6\n0.7,0.8,0.9\n".parse().unwrap();
+
+        let got =
+            gyroscope(&Arguments::from([("samples", "2")]), &samples).unwrap();
+
+        assert_eq!(
+            got,
+            Tensor::new(&[0.1_f32, 0.2, 0.3, 0.4, 0.5, 0.6], &[2, 3])
+        );
+    }
+
+    #[test]
+    fn invalid_arguments_are_rejected() {
+        let samples: GyroscopeSamples = "0,0,0\n".parse().unwrap();
+
+        assert!(
+            gyroscope(&Arguments::from([("samples", "2")]), &samples).is_err()
+        );
+        assert!(
+            gyroscope(&Arguments::from([("rate", "-1")]), &samples).is_err()
+        );
+        assert!(
+            gyroscope(&Arguments::from([("rate", "100")]), &samples).is_ok()
+        );
+    }
+}
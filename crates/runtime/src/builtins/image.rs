@@ -1,19 +1,87 @@
-use std::{num::NonZeroUsize, str::FromStr};
+use std::{borrow::Cow, num::NonZeroUsize, str::FromStr};
 
 use anyhow::Error;
-use image::{imageops::FilterType, DynamicImage};
+use image::{
+    imageops::FilterType, DynamicImage, GenericImageView, RgbImage,
+};
 
 use crate::{builtins::Arguments, ElementType, Tensor};
 
 /// Load an input tensor from an image, applying any transformations requested
 /// by the Rune.
+///
+/// The image is converted to the [`PixelFormat`] from the `"pixel_format"`
+/// argument (defaulting to [`PixelFormat::RGB8`]). Images which aren't
+/// already `"width"` by `"height"` are resized as described by [`Resize`].
+///
+/// If a per-channel `"mean"` or `"std"` (e.g. `mean=[0.485,0.456,0.406]`) is
+/// provided, pixels are scaled to `[0, 1]` and normalized, giving a `f32`
+/// tensor.
 pub fn image(args: &Arguments, img: &DynamicImage) -> Result<Tensor, Error> {
     let width: u32 = args.parse("width")?;
     let height: u32 = args.parse("height")?;
     let pixel_format: PixelFormat =
         args.parse_or_default("pixel_format", PixelFormat::RGB8)?;
+    pixel_format.check_dimensions(width, height)?;
+    let resize = Resize::from_args(args)?;
 
-    Ok(transform(img, width, height, pixel_format))
+    let tensor = transform(img, width, height, pixel_format, resize);
+
+    if args.0.contains_key("mean") || args.0.contains_key("std") {
+        normalize(args, pixel_format, &tensor)
+    } else {
+        Ok(tensor)
+    }
+}
+
+/// Apply `(pixel / 255 - mean[channel]) / std[channel]` to each pixel.
+fn normalize(
+    args: &Arguments,
+    pixel_format: PixelFormat,
+    tensor: &Tensor,
+) -> Result<Tensor, Error> {
+    anyhow::ensure!(
+        pixel_format.element_type() == ElementType::U8
+            && pixel_format != PixelFormat::YUV420,
+        "Normalization isn't supported for {:?} images",
+        pixel_format,
+    );
+
+    let channels = pixel_format.channels();
+    let mean: Vec<f32> = args.parse_list_or_default("mean", vec![0.0])?;
+    let std: Vec<f32> = args.parse_list_or_default("std", vec![1.0])?;
+
+    for (name, values) in [("mean", &mean), ("std", &std)] {
+        anyhow::ensure!(
+            values.len() == 1 || values.len() == channels,
+            "The \"{}\" argument should have 1 or {} values, but it has {}",
+            name,
+            channels,
+            values.len(),
+        );
+    }
+    anyhow::ensure!(
+        std.iter().all(|&s| s != 0.0),
+        "The \"std\" argument can't contain zeroes"
+    );
+
+    let per_channel = |values: &[f32], channel: usize| {
+        values.get(channel).copied().unwrap_or(values[0])
+    };
+    let normalized: Vec<f32> = tensor
+        .buffer()
+        .iter()
+        .enumerate()
+        .map(|(i, &pixel)| {
+            let channel = i % channels;
+            (pixel as f32 / 255.0 - per_channel(&mean, channel))
+                / per_channel(&std, channel)
+        })
+        .collect();
+    let dimensions: Vec<usize> =
+        tensor.dimensions().iter().map(|d| d.get()).collect();
+
+    Ok(Tensor::new(&normalized, &dimensions))
 }
 
 fn transform(
@@ -21,51 +89,283 @@ fn transform(
     width: u32,
     height: u32,
     pixel_format: PixelFormat,
+    resize: Resize,
 ) -> Tensor {
-    let resized = img.resize_exact(width, height, FilterType::CatmullRom);
-
-    let image = match pixel_format {
-        PixelFormat::RGB8 => DynamicImage::ImageRgb8(resized.to_rgb8()),
-        PixelFormat::BGR8 => DynamicImage::ImageBgr8(resized.to_bgr8()),
-        PixelFormat::GrayScale => DynamicImage::ImageLuma8(resized.to_luma8()),
-    };
-
     let dimensions = [
         NonZeroUsize::new(1).unwrap(),
         NonZeroUsize::new(width as usize).unwrap(),
-        NonZeroUsize::new(height as usize).unwrap(),
+        NonZeroUsize::new(pixel_format.rows(height) as usize).unwrap(),
         NonZeroUsize::new(pixel_format.channels()).unwrap(),
     ];
-    let buffer = image.as_bytes().to_vec();
+    let buffer = pixels(img, width, height, pixel_format, resize);
 
     Tensor::new_raw(pixel_format.element_type(), dimensions.to_vec(), buffer)
 }
 
+/// Resize an image and convert it to the desired [`PixelFormat`], returning
+/// the raw pixel data.
+pub(crate) fn pixels(
+    img: &DynamicImage,
+    width: u32,
+    height: u32,
+    pixel_format: PixelFormat,
+    resize: Resize,
+) -> Vec<u8> {
+    let resized = resize.apply(img, width, height);
+
+    match pixel_format {
+        PixelFormat::RGB8 => resized.to_rgb8().into_raw(),
+        PixelFormat::BGR8 => resized.to_bgr8().into_raw(),
+        PixelFormat::RGBA8 => resized.to_rgba8().into_raw(),
+        PixelFormat::GrayScale => resized.to_luma8().into_raw(),
+        PixelFormat::GrayScale16 => luma16(&resized)
+            .into_iter()
+            .flat_map(u16::to_ne_bytes)
+            .collect(),
+        PixelFormat::YUV420 => yuv420(&resized.to_rgb8()),
+    }
+}
+
+/// Convert an image to 16-bit grayscale.
+///
+/// The `image` crate widens 8-bit channels by shifting them, which would turn
+/// white (`0xff`) into `0xff00` instead of `0xffff`, so they are scaled here
+/// instead.
+fn luma16(img: &DynamicImage) -> Vec<u16> {
+    match img {
+        DynamicImage::ImageLuma16(_)
+        | DynamicImage::ImageLumaA16(_)
+        | DynamicImage::ImageRgb16(_)
+        | DynamicImage::ImageRgba16(_) => img.to_luma16().into_raw(),
+        _ => img
+            .to_luma8()
+            .into_raw()
+            .into_iter()
+            .map(|p| u16::from(p) * 257)
+            .collect(),
+    }
+}
+
+/// How an image is resized when it doesn't match the dimensions a capability
+/// asked for.
+///
+/// The `"resize"` argument chooses the [`ResizeFilter`] and, if the
+/// `"center_crop"` argument is `true`, the image is first cropped to the
+/// requested aspect ratio so it isn't stretched.
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct Resize {
+    pub filter: ResizeFilter,
+    pub center_crop: bool,
+}
+
+impl Resize {
+    pub(crate) fn from_args(args: &Arguments) -> Result<Self, Error> {
+        Ok(Resize {
+            filter: args.parse_or_default("resize", ResizeFilter::default())?,
+            center_crop: args.parse_or_default("center_crop", false)?,
+        })
+    }
+
+    pub fn apply<'img>(
+        self,
+        img: &'img DynamicImage,
+        width: u32,
+        height: u32,
+    ) -> Cow<'img, DynamicImage> {
+        let (original_width, original_height) = img.dimensions();
+
+        if (original_width, original_height) == (width, height) {
+            return Cow::Borrowed(img);
+        }
+
+        log::debug!(
+            "Resizing a {}x{} image to {}x{} ({:?})",
+            original_width,
+            original_height,
+            width,
+            height,
+            self
+        );
+
+        let img = if self.center_crop {
+            Cow::Owned(center_crop(img, width, height))
+        } else {
+            Cow::Borrowed(img)
+        };
+
+        Cow::Owned(img.resize_exact(width, height, self.filter.into()))
+    }
+}
+
+/// Crop the largest region from the middle of an image which has the same
+/// aspect ratio as `width` by `height`.
+fn center_crop(img: &DynamicImage, width: u32, height: u32) -> DynamicImage {
+    let (w, h) = img.dimensions();
+    let (w, h, width, height) =
+        (w as u64, h as u64, width as u64, height as u64);
+
+    let (crop_width, crop_height) = if w * height > h * width {
+        // Too wide, so trim the sides
+        (h * width / height, h)
+    } else {
+        // Too tall, so trim the top and bottom
+        (w, w * height / width)
+    };
+
+    img.crop_imm(
+        ((w - crop_width) / 2) as u32,
+        ((h - crop_height) / 2) as u32,
+        crop_width as u32,
+        crop_height as u32,
+    )
+}
+
+/// The interpolation method used when resizing.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ResizeFilter {
+    Nearest,
+    Bilinear,
+    /// Bicubic interpolation using a Catmull-Rom spline (the default).
+    Bicubic,
+    Lanczos,
+}
+
+impl Default for ResizeFilter {
+    fn default() -> Self { ResizeFilter::Bicubic }
+}
+
+impl From<ResizeFilter> for FilterType {
+    fn from(filter: ResizeFilter) -> FilterType {
+        match filter {
+            ResizeFilter::Nearest => FilterType::Nearest,
+            ResizeFilter::Bilinear => FilterType::Triangle,
+            ResizeFilter::Bicubic => FilterType::CatmullRom,
+            ResizeFilter::Lanczos => FilterType::Lanczos3,
+        }
+    }
+}
+
+impl FromStr for ResizeFilter {
+    type Err = UnknownResizeFilter;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "nearest" => Ok(ResizeFilter::Nearest),
+            "bilinear" | "linear" => Ok(ResizeFilter::Bilinear),
+            "bicubic" | "cubic" => Ok(ResizeFilter::Bicubic),
+            "lanczos" => Ok(ResizeFilter::Lanczos),
+            _ => Err(UnknownResizeFilter),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, thiserror::Error)]
+#[error(
+    "Expected one of \"nearest\", \"bilinear\", \"bicubic\", or \
+     \"lanczos\""
+)]
+pub struct UnknownResizeFilter;
+
+/// Convert an image to planar YUV 4:2:0 (I420) using the BT.601 coefficients,
+/// where the `U` and `V` values are the average of each 2x2 block of pixels.
+fn yuv420(img: &RgbImage) -> Vec<u8> {
+    let (width, height) = img.dimensions();
+    let mut y_plane = Vec::with_capacity((width * height) as usize);
+    let mut u_plane = Vec::with_capacity((width * height / 4) as usize);
+    let mut v_plane = Vec::with_capacity((width * height / 4) as usize);
+
+    for pixel in img.pixels() {
+        let [r, g, b] = pixel.0.map(i32::from);
+        y_plane.push((((66 * r + 129 * g + 25 * b + 128) >> 8) + 16) as u8);
+    }
+
+    for row in (0..height).step_by(2) {
+        for column in (0..width).step_by(2) {
+            let mut sum = [0_i32; 3];
+
+            for (x, y) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                let pixel = img.get_pixel(column + x, row + y);
+                for (total, channel) in sum.iter_mut().zip(pixel.0) {
+                    *total += i32::from(channel);
+                }
+            }
+
+            let [r, g, b] = sum.map(|total| total / 4);
+            u_plane.push(
+                (((-38 * r - 74 * g + 112 * b + 128) >> 8) + 128) as u8,
+            );
+            v_plane.push(
+                (((112 * r - 94 * g - 18 * b + 128) >> 8) + 128) as u8,
+            );
+        }
+    }
+
+    y_plane.extend(u_plane);
+    y_plane.extend(v_plane);
+    y_plane
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum PixelFormat {
     /// Red-Green-Blue pixels stored as `u8`.
     RGB8,
     /// Blue-Green-Red pixels stored as `u8`.
     BGR8,
+    /// Red-Green-Blue-Alpha pixels stored as `u8`.
+    RGBA8,
     /// Grayscale pixels stored as `u8`.
     GrayScale,
+    /// Grayscale pixels stored as `u16`.
+    GrayScale16,
+    /// Planar YUV 4:2:0, where a full-resolution `Y` plane is followed by
+    /// quarter-resolution `U` and `V` planes. This is stored as a single
+    /// channel of `u8` with `height * 3 / 2` rows, and both the width and
+    /// height must be even.
+    YUV420,
 }
 
 impl PixelFormat {
     pub fn channels(self) -> usize {
         match self {
             PixelFormat::RGB8 | PixelFormat::BGR8 => 3,
-            PixelFormat::GrayScale => 1,
+            PixelFormat::RGBA8 => 4,
+            PixelFormat::GrayScale
+            | PixelFormat::GrayScale16
+            | PixelFormat::YUV420 => 1,
         }
     }
 
-    fn element_type(&self) -> ElementType {
+    /// The number of rows needed to store an image with this `height`.
+    pub fn rows(self, height: u32) -> u32 {
         match self {
-            PixelFormat::RGB8 | PixelFormat::BGR8 | PixelFormat::GrayScale => {
-                ElementType::U8
-            },
+            PixelFormat::YUV420 => height * 3 / 2,
+            _ => height,
         }
     }
+
+    pub(crate) fn element_type(&self) -> ElementType {
+        match self {
+            PixelFormat::GrayScale16 => ElementType::U16,
+            _ => ElementType::U8,
+        }
+    }
+
+    pub(crate) fn check_dimensions(
+        self,
+        width: u32,
+        height: u32,
+    ) -> Result<(), Error> {
+        if self == PixelFormat::YUV420 {
+            anyhow::ensure!(
+                width % 2 == 0 && height % 2 == 0,
+                "YUV420 images must have an even width and height, not {}x{}",
+                width,
+                height
+            );
+        }
+
+        Ok(())
+    }
 }
 
 impl FromStr for PixelFormat {
@@ -74,15 +374,162 @@ impl FromStr for PixelFormat {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
             "RGB8" => Ok(PixelFormat::RGB8),
+            "BGR8" => Ok(PixelFormat::BGR8),
+            "RGBA8" => Ok(PixelFormat::RGBA8),
+            "GrayScale" | "Gray8" => Ok(PixelFormat::GrayScale),
+            "GrayScale16" | "Gray16" => Ok(PixelFormat::GrayScale16),
+            "YUV420" | "I420" => Ok(PixelFormat::YUV420),
             // Legacy
             "@PixelFormat::RGB" | "0" => Ok(PixelFormat::RGB8),
             "@PixelFormat::BGR" | "1" => Ok(PixelFormat::BGR8),
             "@PixelFormat::GrayScale" | "2" => Ok(PixelFormat::GrayScale),
+            "@PixelFormat::RGBA" | "3" => Ok(PixelFormat::RGBA8),
+            "@PixelFormat::GrayScale16" | "4" => Ok(PixelFormat::GrayScale16),
+            "@PixelFormat::YUV420" | "5" => Ok(PixelFormat::YUV420),
             _ => Err(UnknownPixelFormat),
         }
     }
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, thiserror::Error)]
-#[error("Unknown pixel format")]
+#[error(
+    "Unknown pixel format, expected one of \"RGB8\", \"BGR8\", \"RGBA8\", \
+     \"GrayScale\", \"GrayScale16\", or \"YUV420\""
+)]
 pub struct UnknownPixelFormat;
+
+#[cfg(test)]
+mod tests {
+    use image::{GrayImage, ImageBuffer, Luma, Rgb};
+
+    use super::*;
+
+    #[test]
+    fn rgba_adds_an_opaque_alpha_channel() {
+        let img = DynamicImage::ImageRgb8(RgbImage::from_pixel(
+            1,
+            1,
+            Rgb([1, 2, 3]),
+        ));
+
+        let got = pixels(&img, 1, 1, PixelFormat::RGBA8, Resize::default());
+
+        assert_eq!(got, vec![1, 2, 3, 255]);
+    }
+
+    #[test]
+    fn grayscale16_uses_two_bytes_per_pixel() {
+        let img =
+            DynamicImage::ImageLuma8(GrayImage::from_pixel(2, 1, Luma([255])));
+
+        let got =
+            transform(&img, 2, 1, PixelFormat::GrayScale16, Resize::default());
+
+        assert_eq!(got.element_type(), ElementType::U16);
+        let should_be: Vec<u8> =
+            [u16::MAX; 2].iter().flat_map(|p| p.to_ne_bytes()).collect();
+        assert_eq!(got.buffer(), should_be.as_slice());
+    }
+
+    #[test]
+    fn grayscale16_keeps_the_precision_of_16_bit_images() {
+        let img = DynamicImage::ImageLuma16(ImageBuffer::from_pixel(
+            2,
+            1,
+            Luma([1000_u16]),
+        ));
+
+        let got =
+            transform(&img, 2, 1, PixelFormat::GrayScale16, Resize::default());
+
+        let should_be: Vec<u8> =
+            [1000_u16; 2].iter().flat_map(|p| p.to_ne_bytes()).collect();
+        assert_eq!(got.buffer(), should_be.as_slice());
+    }
+
+    #[test]
+    fn normalize_with_a_per_channel_mean_and_std() {
+        let img = DynamicImage::ImageRgb8(RgbImage::from_pixel(
+            1,
+            1,
+            Rgb([255, 0, 0]),
+        ));
+        let args = Arguments::from([
+            ("width", "1"),
+            ("height", "1"),
+            ("mean", "[0.5, 0.0, 0.1]"),
+            ("std", "0.5"),
+        ]);
+
+        let got = image(&args, &img).unwrap();
+
+        assert_eq!(got.element_type(), ElementType::F32);
+        assert_eq!(got.elements::<f32>().unwrap(), &[1.0, 0.0, -0.2]);
+    }
+
+    #[test]
+    fn yuv420_planes() {
+        let white = RgbImage::from_pixel(4, 2, Rgb([255, 255, 255]));
+
+        let got = yuv420(&white);
+
+        // 8 luma values, followed by 2 U and 2 V values
+        assert_eq!(got.len(), 12);
+        assert!(got[..8].iter().all(|&y| y == 235));
+        assert!(got[8..].iter().all(|&c| c == 128));
+    }
+
+    #[test]
+    fn yuv420_needs_even_dimensions() {
+        assert!(PixelFormat::YUV420.check_dimensions(3, 2).is_err());
+        assert!(PixelFormat::YUV420.check_dimensions(4, 2).is_ok());
+        assert!(PixelFormat::RGB8.check_dimensions(3, 3).is_ok());
+    }
+
+    #[test]
+    fn center_crop_keeps_the_middle() {
+        // A 4x2 image where the outer columns are black and the middle ones
+        // are white
+        let img = GrayImage::from_fn(4, 2, |x, _| match x {
+            1 | 2 => Luma([255]),
+            _ => Luma([0]),
+        });
+        let img = DynamicImage::ImageLuma8(img);
+        let resize = Resize {
+            filter: ResizeFilter::Nearest,
+            center_crop: true,
+        };
+
+        let got = pixels(&img, 1, 1, PixelFormat::GrayScale, resize);
+
+        assert_eq!(got, vec![255]);
+    }
+
+    #[test]
+    fn resize_without_cropping() {
+        let img = DynamicImage::ImageLuma8(GrayImage::new(4, 2));
+        let resize = Resize {
+            filter: ResizeFilter::Bilinear,
+            center_crop: false,
+        };
+
+        let got = resize.apply(&img, 2, 2);
+
+        assert_eq!(got.dimensions(), (2, 2));
+    }
+
+    #[test]
+    fn parse_pixel_formats() {
+        let inputs = vec![
+            ("RGBA8", PixelFormat::RGBA8),
+            ("@PixelFormat::GrayScale16", PixelFormat::GrayScale16),
+            ("5", PixelFormat::YUV420),
+            ("BGR8", PixelFormat::BGR8),
+        ];
+
+        for (src, should_be) in inputs {
+            let got: PixelFormat = src.parse().unwrap();
+            assert_eq!(got, should_be);
+        }
+    }
+}
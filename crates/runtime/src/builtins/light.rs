@@ -0,0 +1,41 @@
+use anyhow::Error;
+
+use crate::{
+    builtins::{readings::readings_tensor, Arguments, SensorReadings},
+    Tensor,
+};
+
+/// Load an input tensor from a log of ambient light readings (in lux).
+///
+/// The `"samples"` argument sets how many readings are provided (defaulting
+/// to a single reading) and the result is a `f32[samples, 1]` tensor.
+/// Negative readings are treated as complete darkness.
+pub fn light(
+    args: &Arguments,
+    readings: &SensorReadings,
+) -> Result<Tensor, Error> {
+    let samples: usize = args.parse_or_default("samples", 1)?;
+
+    let values = readings
+        .take(samples, 1)?
+        .iter()
+        .map(|reading| reading[0].max(0.0))
+        .collect();
+
+    Ok(readings_tensor(values, samples, 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lux_readings() {
+        let readings: SensorReadings = "320\n-1\n10000\n".parse().unwrap();
+        let args = Arguments::from([("samples", "3")]);
+
+        let got = light(&args, &readings).unwrap();
+
+        assert_eq!(got, Tensor::new(&[320.0_f32, 0.0, 10000.0], &[3, 1]));
+    }
+}
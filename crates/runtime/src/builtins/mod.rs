@@ -2,23 +2,70 @@
 
 mod accelerometer;
 mod arguments;
+mod barometer;
+mod batch;
+mod battery;
+mod dataset;
+mod fan_in;
+mod gps;
+mod gyroscope;
 mod image;
+mod light;
+mod numpy;
+mod proximity;
 mod random;
 mod raw;
+mod readings;
+mod schema;
+mod signal;
 mod sound;
+mod tabular;
+mod thermometer;
+mod video;
 
 use anyhow::Error;
 
+#[cfg(feature = "microphone")]
+pub(crate) use self::sound::resample;
+
 pub use self::{
     accelerometer::{
         accelerometer, AccelerometerParseError, AccelerometerSample,
-        AccelerometerSamples,
+        AccelerometerSamples, Interpolation, UnknownInterpolation,
+    },
+    arguments::{ArgumentValue, ArgumentValueError, Arguments},
+    barometer::{barometer, pressure_to_altitude, STANDARD_SEA_LEVEL_PRESSURE},
+    batch::{batch, stack},
+    battery::{battery, BatteryReading, SimulatedBattery},
+    dataset::{Dataset, DatasetItem, DatasetKind},
+    fan_in::{
+        combine, load_sources, sources, CombinePolicy, UnknownCombinePolicy,
+    },
+    gps::{gps, GpsFix, GpsTrack},
+    gyroscope::{
+        gyroscope, GyroscopeParseError, GyroscopeSample, GyroscopeSamples,
+    },
+    image::{
+        image, PixelFormat, Resize, ResizeFilter, UnknownPixelFormat,
+        UnknownResizeFilter,
+    },
+    light::light,
+    numpy::{read_npy, tensor, NumpyArrays},
+    proximity::proximity,
+    random::{
+        random, seeded_random, RandomDistribution, UnknownRandomDistribution,
+    },
+    raw::{raw, RawStream},
+    readings::SensorReadings,
+    schema::{
+        capability_schema, validate_arguments, ArgumentError, ArgumentKind,
+        ArgumentSpec,
     },
-    arguments::Arguments,
-    image::{image, UnknownPixelFormat},
-    random::{random, seeded_random},
-    raw::raw,
+    signal::{signal, UnknownWaveform, Waveform},
     sound::{sound, AudioClip},
+    tabular::{tabular, Normalization, Table, UnknownNormalization},
+    thermometer::{thermometer, TemperatureUnit, UnknownTemperatureUnit},
+    video::{video, VideoClip},
 };
 
 /// Use the `"source"` argument to figure out which input to read.
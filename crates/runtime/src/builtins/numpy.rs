@@ -0,0 +1,341 @@
+use std::{
+    fs::File,
+    io::{BufReader, Read, Seek},
+    num::NonZeroUsize,
+    path::Path,
+};
+
+use anyhow::{Context, Error};
+
+use crate::{builtins::Arguments, ElementType, Tensor};
+
+const MAGIC: &[u8] = b"\x93NUMPY";
+
+/// Load an input tensor from a NumPy array.
+///
+/// The `"array"` argument picks an array from a `.npz` archive by name
+/// (defaulting to the first one). If the `"element_type"` argument is
+/// provided it must match the array's element type, and the array will be
+/// reshaped to the comma-separated `"dimensions"` argument (e.g.
+/// `1,28,28`) as long as the number of elements is the same.
+pub fn tensor(args: &Arguments, arrays: &NumpyArrays) -> Result<Tensor, Error> {
+    let array = match args.0.get("array") {
+        Some(name) => arrays.get(name).with_context(|| {
+            format!("There is no array called \"{}\"", name)
+        })?,
+        None => arrays.first().context("No arrays were provided")?,
+    };
+
+    if args.0.contains_key("element_type") {
+        let element_type: ElementType = args.parse("element_type")?;
+        anyhow::ensure!(
+            element_type == array.element_type(),
+            "Expected a {} array but found {}",
+            element_type,
+            array.shape(),
+        );
+    }
+
+    match args.0.get("dimensions") {
+        Some(dimensions) => reshape(array, dimensions),
+        None => Ok(array.clone()),
+    }
+}
+
+fn reshape(array: &Tensor, dimensions: &str) -> Result<Tensor, Error> {
+    let dimensions = dimensions
+        .split(',')
+        .map(|d| {
+            d.trim()
+                .parse::<NonZeroUsize>()
+                .with_context(|| format!("\"{}\" isn't a valid dimension", d))
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    let expected: usize = dimensions.iter().map(|d| d.get()).product();
+    let actual: usize = array.dimensions().iter().map(|d| d.get()).product();
+    anyhow::ensure!(
+        expected == actual,
+        "Unable to reshape a {} array to {:?} because it has {} elements \
+         instead of {}",
+        array.shape(),
+        dimensions,
+        actual,
+        expected,
+    );
+
+    Ok(Tensor::new_raw(
+        array.element_type(),
+        dimensions,
+        array.buffer().to_vec(),
+    ))
+}
+
+/// The named arrays loaded from a `.npy` or `.npz` file.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct NumpyArrays(pub Vec<(String, Tensor)>);
+
+impl NumpyArrays {
+    /// Load a `.npz` archive or a single `.npy` array, using the file's
+    /// extension to tell them apart.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let f = File::open(path).with_context(|| {
+            format!("Unable to open \"{}\"", path.display())
+        })?;
+        let reader = BufReader::new(f);
+
+        let is_npz = path
+            .extension()
+            .map(|ext| ext.eq_ignore_ascii_case("npz"))
+            .unwrap_or(false);
+
+        if is_npz {
+            NumpyArrays::from_npz(reader)
+        } else {
+            let name = path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let array = read_npy(reader)?;
+            Ok(NumpyArrays(vec![(name, array)]))
+        }
+    }
+
+    /// Read every array from a `.npz` archive (a zip file containing `.npy`
+    /// files), keeping them in the order they were saved.
+    pub fn from_npz(reader: impl Read + Seek) -> Result<Self, Error> {
+        let mut archive =
+            zip::ZipArchive::new(reader).context("Unable to read the archive")?;
+        let mut arrays = Vec::new();
+
+        for i in 0..archive.len() {
+            let entry = archive.by_index(i)?;
+            let name = entry.name();
+            let name = name.strip_suffix(".npy").unwrap_or(name).to_string();
+
+            let array = read_npy(entry)
+                .with_context(|| format!("Unable to read \"{}\"", name))?;
+            arrays.push((name, array));
+        }
+
+        Ok(NumpyArrays(arrays))
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Tensor> {
+        self.0
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, array)| array)
+    }
+
+    pub fn first(&self) -> Option<&Tensor> {
+        self.0.first().map(|(_, array)| array)
+    }
+}
+
+/// Parse a single array in the [`.npy` format][npy].
+///
+/// [npy]: https://numpy.org/doc/stable/reference/generated/numpy.lib.format.html
+pub fn read_npy(mut reader: impl Read) -> Result<Tensor, Error> {
+    let mut magic = [0; 8];
+    reader.read_exact(&mut magic)?;
+    anyhow::ensure!(magic.starts_with(MAGIC), "Not a NumPy array");
+
+    let major_version = magic[6];
+    let header_length = match major_version {
+        1 => {
+            let mut length = [0; 2];
+            reader.read_exact(&mut length)?;
+            u16::from_le_bytes(length) as usize
+        },
+        2 | 3 => {
+            let mut length = [0; 4];
+            reader.read_exact(&mut length)?;
+            u32::from_le_bytes(length) as usize
+        },
+        other => anyhow::bail!("Unsupported .npy version, {}", other),
+    };
+
+    let mut header = vec![0; header_length];
+    reader.read_exact(&mut header)?;
+    let header = String::from_utf8(header)
+        .context("The header should be a valid string")?;
+    let header = Header::parse(&header)?;
+
+    let element_count: usize =
+        header.dimensions.iter().map(|d| d.get()).product();
+    let mut buffer = vec![0; element_count * header.element_type.byte_size()];
+    reader
+        .read_exact(&mut buffer)
+        .context("The array's data was truncated")?;
+
+    if header.swap_bytes {
+        for element in buffer.chunks_mut(header.element_type.byte_size()) {
+            element.reverse();
+        }
+    }
+
+    Ok(Tensor::new_raw(header.element_type, header.dimensions, buffer))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Header {
+    element_type: ElementType,
+    swap_bytes: bool,
+    dimensions: Vec<NonZeroUsize>,
+}
+
+impl Header {
+    /// Parse the Python dictionary literal at the start of a `.npy` file,
+    /// e.g. `{'descr': '<f4', 'fortran_order': False, 'shape': (2, 3), }`.
+    fn parse(header: &str) -> Result<Self, Error> {
+        let descr = value_after(header, "'descr'")
+            .and_then(|v| v.split('\'').nth(1))
+            .context("The header doesn't have a \"descr\"")?;
+        let fortran_order = value_after(header, "'fortran_order'")
+            .context("The header doesn't have a \"fortran_order\"")?;
+        let shape = value_after(header, "'shape'")
+            .and_then(|v| v.strip_prefix('('))
+            .and_then(|v| v.split(')').next())
+            .context("The header doesn't have a \"shape\"")?;
+
+        anyhow::ensure!(
+            !fortran_order.starts_with("True"),
+            "Fortran-ordered arrays aren't supported"
+        );
+
+        let (element_type, swap_bytes) = parse_descr(descr)?;
+
+        let mut dimensions = shape
+            .split(',')
+            .map(str::trim)
+            .filter(|d| !d.is_empty())
+            .map(|d| {
+                d.parse::<NonZeroUsize>().with_context(|| {
+                    format!("\"{}\" isn't a valid dimension", d)
+                })
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        if dimensions.is_empty() {
+            // Scalars are stored with a shape of ()
+            dimensions.push(NonZeroUsize::new(1).unwrap());
+        }
+
+        Ok(Header {
+            element_type,
+            swap_bytes,
+            dimensions,
+        })
+    }
+}
+
+fn value_after<'a>(header: &'a str, key: &str) -> Option<&'a str> {
+    let (_, rest) = header.split_once(key)?;
+    let (_, value) = rest.split_once(':')?;
+    Some(value.trim_start())
+}
+
+/// Parse a dtype string like `<f4`, returning the element type and whether
+/// its bytes need to be swapped to match the host's endianness.
+fn parse_descr(descr: &str) -> Result<(ElementType, bool), Error> {
+    let (endianness, kind) = match descr.chars().next() {
+        Some(c @ ('<' | '>' | '|' | '=')) => (c, &descr[1..]),
+        _ => ('=', descr),
+    };
+
+    let element_type = match kind {
+        "u1" | "b1" => ElementType::U8,
+        "i1" => ElementType::I8,
+        "u2" => ElementType::U16,
+        "i2" => ElementType::I16,
+        "u4" => ElementType::U32,
+        "i4" => ElementType::I32,
+        "f4" => ElementType::F32,
+        "u8" => ElementType::U64,
+        "i8" => ElementType::I64,
+        "f8" => ElementType::F64,
+        _ => anyhow::bail!("Unsupported NumPy dtype, \"{}\"", descr),
+    };
+
+    let little_endian_file = match endianness {
+        '<' => true,
+        '>' => false,
+        _ => cfg!(target_endian = "little"),
+    };
+    let swap_bytes = little_endian_file != cfg!(target_endian = "little");
+
+    Ok((element_type, swap_bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn npy(descr: &str, shape: &str, data: &[u8]) -> Vec<u8> {
+        let header = format!(
+            "{{'descr': '{}', 'fortran_order': False, 'shape': {}, }}\n",
+            descr, shape
+        );
+        let mut buffer = MAGIC.to_vec();
+        buffer.extend([1, 0]);
+        buffer.extend((header.len() as u16).to_le_bytes());
+        buffer.extend(header.as_bytes());
+        buffer.extend(data);
+        buffer
+    }
+
+    #[test]
+    fn read_a_little_endian_array() {
+        let data: Vec<u8> = [1.0_f32, 2.0, 3.0, 4.0, 5.0, 6.0]
+            .iter()
+            .flat_map(|f| f.to_le_bytes())
+            .collect();
+        let src = npy("<f4", "(2, 3)", &data);
+
+        let got = read_npy(src.as_slice()).unwrap();
+
+        assert_eq!(
+            got,
+            Tensor::new(&[1.0_f32, 2.0, 3.0, 4.0, 5.0, 6.0], &[2, 3])
+        );
+    }
+
+    #[test]
+    fn big_endian_arrays_are_swapped() {
+        let src = npy(">i2", "(2,)", &[0x01, 0x02, 0x00, 0x03]);
+
+        let got = read_npy(src.as_slice()).unwrap();
+
+        assert_eq!(got, Tensor::new(&[0x0102_i16, 0x0003], &[2]));
+    }
+
+    #[test]
+    fn fortran_order_is_rejected() {
+        let header = Header::parse(
+            "{'descr': '<f4', 'fortran_order': True, 'shape': (2, 2), }",
+        );
+
+        assert!(header.is_err());
+    }
+
+    #[test]
+    fn reshape_and_check_the_element_type() {
+        let arrays = NumpyArrays(vec![(
+            String::from("x"),
+            Tensor::new(&[1_u8, 2, 3, 4], &[4]),
+        )]);
+        let mut args = Arguments::from([
+            ("element_type", "u8"),
+            ("dimensions", "1, 2, 2"),
+        ]);
+
+        let got = tensor(&args, &arrays).unwrap();
+
+        assert_eq!(got, Tensor::new(&[1_u8, 2, 3, 4], &[1, 2, 2]));
+
+        args.0.insert(String::from("element_type"), String::from("f32"));
+        assert!(tensor(&args, &arrays).is_err());
+    }
+}
@@ -0,0 +1,59 @@
+use anyhow::Error;
+
+use crate::{
+    builtins::{readings::readings_tensor, Arguments, SensorReadings},
+    Tensor,
+};
+
+/// Load an input tensor from a log of proximity sensor readings (the
+/// distance to the nearest object, in centimetres).
+///
+/// The `"samples"` argument sets how many readings are provided, either a
+/// single value (the default) or a short window, and the result is a
+/// `f32[samples, 1]` tensor. If `"max_range"` is set, readings are clamped to
+/// the sensor's range so playback matches what real hardware would report.
+pub fn proximity(
+    args: &Arguments,
+    readings: &SensorReadings,
+) -> Result<Tensor, Error> {
+    let samples: usize = args.parse_or_default("samples", 1)?;
+    let max_range: f32 = args.parse_or_default("max_range", f32::INFINITY)?;
+
+    anyhow::ensure!(
+        max_range > 0.0,
+        "The maximum range must be positive, not {}",
+        max_range
+    );
+
+    let values = readings
+        .take(samples, 1)?
+        .iter()
+        .map(|reading| reading[0].clamp(0.0, max_range))
+        .collect();
+
+    Ok(readings_tensor(values, samples, 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_single_reading() {
+        let readings: SensorReadings = "4.5\n8\n".parse().unwrap();
+
+        let got = proximity(&Arguments::from([]), &readings).unwrap();
+
+        assert_eq!(got, Tensor::new(&[4.5_f32], &[1, 1]));
+    }
+
+    #[test]
+    fn windows_are_clamped_to_the_sensor_range() {
+        let readings: SensorReadings = "-1\n3\n12\n".parse().unwrap();
+        let args = Arguments::from([("samples", "3"), ("max_range", "5")]);
+
+        let got = proximity(&args, &readings).unwrap();
+
+        assert_eq!(got, Tensor::new(&[0.0_f32, 3.0, 5.0], &[3, 1]));
+    }
+}
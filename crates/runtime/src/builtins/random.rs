@@ -1,26 +1,189 @@
-use anyhow::Error;
-use rand::{Rng, SeedableRng};
+use std::str::FromStr;
+
+use anyhow::{Context, Error};
+use rand::{
+    distributions::{Standard, Uniform},
+    Rng, SeedableRng,
+};
+use rand_distr::Normal;
 
 use crate::{builtins::Arguments, Tensor};
 
+/// Generate a `[1, amount]` tensor of random numbers, where the `"amount"`
+/// argument defaults to `1` and the `"distribution"` argument chooses a
+/// [`RandomDistribution`].
 pub fn random(args: &Arguments) -> Result<Tensor, Error> {
-    let count: usize = args.parse_or_default("amount", 1)?;
-
     let rng = rand::thread_rng();
-    random_tensor(count, rng)
+    random_tensor(args, rng)
 }
 
+/// Like [`random()`], but the numbers are generated deterministically from a
+/// seed.
 pub fn seeded_random(args: &Arguments, seed: u64) -> Result<Tensor, Error> {
+    let rng = rand::rngs::SmallRng::seed_from_u64(seed);
+    random_tensor(args, rng)
+}
+
+fn random_tensor(args: &Arguments, rng: impl Rng) -> Result<Tensor, Error> {
     let count: usize = args.parse_or_default("amount", 1)?;
+    let distribution: RandomDistribution =
+        args.parse_or_default("distribution", RandomDistribution::default())?;
+    let dimensions = [1, count];
 
-    let rng = rand::rngs::SmallRng::seed_from_u64(seed);
-    random_tensor(count, rng)
+    match distribution {
+        RandomDistribution::Bits => {
+            let numbers: Vec<u32> =
+                rng.sample_iter(Standard).take(count).collect();
+            Ok(Tensor::new(&numbers, &dimensions))
+        },
+        RandomDistribution::Uniform => {
+            let min: f32 = args.parse_or_default("min", 0.0)?;
+            let max: f32 = args.parse_or_default("max", 1.0)?;
+            anyhow::ensure!(
+                min < max,
+                "The minimum ({}) must be less than the maximum ({})",
+                min,
+                max
+            );
+
+            let numbers: Vec<f32> = rng
+                .sample_iter(Uniform::new(min, max))
+                .take(count)
+                .collect();
+            Ok(Tensor::new(&numbers, &dimensions))
+        },
+        RandomDistribution::Gaussian => {
+            let mean: f32 = args.parse_or_default("mean", 0.0)?;
+            let std_dev: f32 = args.parse_or_default("std_dev", 1.0)?;
+            // Note: rand_distr accepts a negative standard deviation and
+            // mirrors the distribution, which is almost certainly a mistake
+            anyhow::ensure!(
+                std_dev >= 0.0,
+                "The standard deviation must be finite and positive"
+            );
+            let normal = Normal::new(mean, std_dev)
+                .context("The standard deviation must be finite and positive")?;
+
+            let numbers: Vec<f32> =
+                rng.sample_iter(normal).take(count).collect();
+            Ok(Tensor::new(&numbers, &dimensions))
+        },
+        RandomDistribution::Integer => {
+            let min: i32 = args.parse_or_default("min", 0)?;
+            let max: i32 = args.parse("max")?;
+            anyhow::ensure!(
+                min <= max,
+                "The minimum ({}) can't be more than the maximum ({})",
+                min,
+                max
+            );
+
+            let numbers: Vec<i32> = rng
+                .sample_iter(Uniform::new_inclusive(min, max))
+                .take(count)
+                .collect();
+            Ok(Tensor::new(&numbers, &dimensions))
+        },
+    }
+}
+
+/// The kind of random numbers to generate.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RandomDistribution {
+    /// Random `u32`s where every bit is equally likely to be set (the
+    /// default).
+    Bits,
+    /// `f32`s spread evenly between the `"min"` (inclusive, defaults to `0`)
+    /// and `"max"` (exclusive, defaults to `1`) arguments.
+    Uniform,
+    /// `f32`s from a normal distribution with the given `"mean"` (defaults
+    /// to `0`) and `"std_dev"` (defaults to `1`).
+    Gaussian,
+    /// `i32`s spread evenly between the `"min"` (defaults to `0`) and `"max"`
+    /// arguments, inclusive.
+    Integer,
+}
+
+impl Default for RandomDistribution {
+    fn default() -> Self { RandomDistribution::Bits }
+}
+
+impl FromStr for RandomDistribution {
+    type Err = UnknownRandomDistribution;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bits" | "raw" => Ok(RandomDistribution::Bits),
+            "uniform" => Ok(RandomDistribution::Uniform),
+            "gaussian" | "normal" => Ok(RandomDistribution::Gaussian),
+            "integer" | "int" => Ok(RandomDistribution::Integer),
+            _ => Err(UnknownRandomDistribution),
+        }
+    }
 }
 
-fn random_tensor(count: usize, mut rng: impl Rng) -> Result<Tensor, Error> {
-    let mut numbers: Vec<u32> = Vec::new();
-    for _ in 0..count {
-        numbers.push(rng.gen());
+#[derive(Debug, Copy, Clone, PartialEq, thiserror::Error)]
+#[error(
+    "Expected one of \"bits\", \"uniform\", \"gaussian\", or \"integer\""
+)]
+pub struct UnknownRandomDistribution;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uniform_floats_stay_in_range() {
+        let args = Arguments::from([
+            ("amount", "100"),
+            ("distribution", "uniform"),
+            ("min", "-1"),
+            ("max", "1"),
+        ]);
+
+        let got = seeded_random(&args, 42).unwrap();
+
+        let numbers: Vec<f32> = got
+            .buffer()
+            .chunks(4)
+            .map(|b| f32::from_ne_bytes([b[0], b[1], b[2], b[3]]))
+            .collect();
+        assert_eq!(numbers.len(), 100);
+        assert!(numbers.iter().all(|&n| (-1.0..1.0).contains(&n)));
+    }
+
+    #[test]
+    fn integers_are_inclusive() {
+        let args = Arguments::from([
+            ("amount", "100"),
+            ("distribution", "integer"),
+            ("min", "3"),
+            ("max", "3"),
+        ]);
+
+        let got = seeded_random(&args, 42).unwrap();
+
+        assert_eq!(got, Tensor::new(&[3_i32; 100], &[1, 100]));
+    }
+
+    #[test]
+    fn seeded_numbers_are_reproducible() {
+        let args =
+            Arguments::from([("amount", "10"), ("distribution", "gaussian")]);
+
+        let first = seeded_random(&args, 1).unwrap();
+        let second = seeded_random(&args, 1).unwrap();
+
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn invalid_parameters_are_rejected() {
+        let gaussian =
+            Arguments::from([("distribution", "gaussian"), ("std_dev", "-1")]);
+        let integer = Arguments::from([("distribution", "integer")]);
+
+        assert!(random(&gaussian).is_err());
+        assert!(random(&integer).is_err());
     }
-    Ok(Tensor::new(&numbers, &[1, count]))
 }
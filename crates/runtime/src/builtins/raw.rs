@@ -1,4 +1,11 @@
-use anyhow::Error;
+use std::{
+    fmt::{self, Debug, Formatter},
+    fs::File,
+    io::{ErrorKind, Read},
+    path::Path,
+};
+
+use anyhow::{Context, Error};
 
 use crate::{builtins::Arguments, Tensor};
 
@@ -15,3 +22,100 @@ pub fn raw(args: &Arguments, bytes: &[u8]) -> Result<Tensor, Error> {
 
     Ok(Tensor::new(&bytes[..length], &[1, length]))
 }
+
+/// A continuous source of bytes for the `RAW` capability, such as stdin or
+/// a named pipe, which is read one chunk at a time.
+pub struct RawStream {
+    name: String,
+    reader: Box<dyn Read + Send>,
+}
+
+impl RawStream {
+    pub fn new(
+        name: impl Into<String>,
+        reader: impl Read + Send + 'static,
+    ) -> Self {
+        RawStream {
+            name: name.into(),
+            reader: Box::new(reader),
+        }
+    }
+
+    /// Open a stream, where `-` means stdin.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+
+        if path == Path::new("-") {
+            return Ok(RawStream::new("stdin", std::io::stdin()));
+        }
+
+        let f = File::open(path).with_context(|| {
+            format!("Unable to open \"{}\"", path.display())
+        })?;
+
+        Ok(RawStream::new(path.display().to_string(), f))
+    }
+
+    /// Should this path be read as a [`RawStream`] (i.e. is it `-` or a
+    /// named pipe) rather than all at once?
+    pub fn is_stream(path: impl AsRef<Path>) -> bool {
+        let path = path.as_ref();
+
+        if path == Path::new("-") {
+            return true;
+        }
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::FileTypeExt;
+
+            if let Ok(meta) = std::fs::metadata(path) {
+                return meta.file_type().is_fifo();
+            }
+        }
+
+        false
+    }
+
+    /// Read the next `length` bytes, blocking until they are available.
+    pub fn read_chunk(&mut self, length: usize) -> Result<Vec<u8>, Error> {
+        let mut buffer = vec![0; length];
+
+        match self.reader.read_exact(&mut buffer) {
+            Ok(_) => Ok(buffer),
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => {
+                anyhow::bail!("Reached the end of {}", self.name)
+            },
+            Err(e) => Err(Error::from(e)
+                .context(format!("Unable to read from {}", self.name))),
+        }
+    }
+}
+
+impl Debug for RawStream {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RawStream")
+            .field("name", &self.name)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_a_stream_in_chunks() {
+        let mut stream = RawStream::new("test", &b"abcdefg"[..]);
+
+        assert_eq!(stream.read_chunk(3).unwrap(), b"abc");
+        assert_eq!(stream.read_chunk(3).unwrap(), b"def");
+        assert!(stream.read_chunk(3).is_err());
+    }
+
+    #[test]
+    fn stdin_is_a_stream() {
+        assert!(RawStream::is_stream("-"));
+        assert!(!RawStream::is_stream("Cargo.toml"));
+    }
+}
@@ -0,0 +1,145 @@
+use std::{fs::File, io::Read, ops::Deref, path::Path, str::FromStr};
+
+use anyhow::{Context, Error};
+use csv::StringRecord;
+
+use crate::Tensor;
+
+/// A log of readings from a simple sensor (e.g. a thermometer or light
+/// sensor), where each row of a CSV file is one reading and every row has
+/// the same number of numeric columns.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SensorReadings(pub Vec<Vec<f32>>);
+
+impl SensorReadings {
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let f = File::open(path).with_context(|| {
+            format!("Unable to open \"{}\"", path.display())
+        })?;
+
+        SensorReadings::from_reader(f)
+    }
+
+    pub fn from_reader(reader: impl Read) -> Result<Self, Error> {
+        let mut reader = csv::ReaderBuilder::default()
+            .has_headers(false)
+            .comment(Some(b'#'))
+            .from_reader(reader);
+        let mut record = StringRecord::new();
+        let mut readings: Vec<Vec<f32>> = Vec::new();
+
+        while reader.read_record(&mut record)? {
+            let line = reader.position().line();
+
+            let reading = record
+                .iter()
+                .map(|field| {
+                    field.trim().parse().with_context(|| {
+                        format!(
+                            "Unable to parse \"{}\" on line {}",
+                            field, line
+                        )
+                    })
+                })
+                .collect::<Result<Vec<f32>, Error>>()?;
+
+            if let Some(first) = readings.first() {
+                anyhow::ensure!(
+                    first.len() == reading.len(),
+                    "Line {} should have {} fields but it actually had {}",
+                    line,
+                    first.len(),
+                    reading.len(),
+                );
+            }
+
+            readings.push(reading);
+        }
+
+        Ok(SensorReadings(readings))
+    }
+
+    /// The number of values in each reading.
+    pub fn columns(&self) -> usize {
+        self.0.first().map(|r| r.len()).unwrap_or_default()
+    }
+
+    /// Get the first `samples` readings, making sure there are enough of
+    /// them and that each one has at least `columns` values.
+    pub(crate) fn take(
+        &self,
+        samples: usize,
+        columns: usize,
+    ) -> Result<&[Vec<f32>], Error> {
+        anyhow::ensure!(
+            samples <= self.len(),
+            "{} readings were requested but only {} are available",
+            samples,
+            self.len(),
+        );
+        anyhow::ensure!(
+            self.is_empty() || self.columns() >= columns,
+            "Each reading should have at least {} values, but only {} were \
+             provided",
+            columns,
+            self.columns(),
+        );
+
+        Ok(&self[..samples])
+    }
+}
+
+impl FromStr for SensorReadings {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        SensorReadings::from_reader(s.as_bytes())
+    }
+}
+
+impl Deref for SensorReadings {
+    type Target = [Vec<f32>];
+
+    fn deref(&self) -> &Self::Target { &self.0 }
+}
+
+/// Create a `f32[samples, columns]` tensor from a set of values.
+pub(crate) fn readings_tensor(
+    values: Vec<f32>,
+    samples: usize,
+    columns: usize,
+) -> Tensor {
+    debug_assert_eq!(values.len(), samples * columns);
+    Tensor::new(&values, &[samples, columns])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_readings() {
+        let src = "# temperature, humidity\n21.5, 40\n22,41.5\n";
+
+        let got: SensorReadings = src.parse().unwrap();
+
+        assert_eq!(got.0, vec![vec![21.5, 40.0], vec![22.0, 41.5]]);
+        assert_eq!(got.columns(), 2);
+    }
+
+    #[test]
+    fn rows_must_be_the_same_length() {
+        assert!("1,2\n3\n".parse::<SensorReadings>().is_err());
+        assert!("1,two\n".parse::<SensorReadings>().is_err());
+    }
+
+    #[test]
+    fn take_checks_the_shape() {
+        let readings: SensorReadings = "1\n2\n3\n".parse().unwrap();
+
+        assert_eq!(readings.take(2, 1).unwrap().len(), 2);
+        assert!(readings.take(4, 1).is_err());
+        assert!(readings.take(1, 2).is_err());
+    }
+}
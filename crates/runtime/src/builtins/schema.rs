@@ -0,0 +1,521 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
+
+use hotg_rune_core::Shape;
+
+use crate::{
+    builtins::{
+        fan_in, ArgumentValue, Arguments, CombinePolicy, Interpolation,
+        Normalization, PixelFormat, RandomDistribution, ResizeFilter,
+        TemperatureUnit, Waveform,
+    },
+    ElementType,
+};
+
+/// The values an argument accepts.
+#[derive(Copy, Clone)]
+pub enum ArgumentKind {
+    /// A whole number greater than zero.
+    PositiveInteger,
+    /// A whole number greater than or equal to zero.
+    NonNegativeInteger,
+    /// Any finite number.
+    Number,
+    /// A finite number greater than zero.
+    PositiveNumber,
+    /// `true` or `false`.
+    Boolean,
+    /// Anything.
+    Text,
+    /// Something only accepted by `is_valid` (e.g. the name of an enum
+    /// variant).
+    Custom {
+        description: &'static str,
+        is_valid: fn(&str) -> bool,
+    },
+}
+
+impl ArgumentKind {
+    pub fn is_valid(self, value: &str) -> bool {
+        let value = value.trim();
+
+        match self {
+            ArgumentKind::PositiveInteger => {
+                matches!(value.parse::<u64>(), Ok(n) if n > 0)
+            },
+            ArgumentKind::NonNegativeInteger => value.parse::<u64>().is_ok(),
+            ArgumentKind::Number => {
+                matches!(value.parse::<f64>(), Ok(n) if n.is_finite())
+            },
+            ArgumentKind::PositiveNumber => matches!(
+                value.parse::<f64>(),
+                Ok(n) if n.is_finite() && n > 0.0
+            ),
+            ArgumentKind::Boolean => value.parse::<bool>().is_ok(),
+            ArgumentKind::Text => true,
+            ArgumentKind::Custom { is_valid, .. } => is_valid(value),
+        }
+    }
+}
+
+impl Display for ArgumentKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ArgumentKind::PositiveInteger => write!(f, "a positive integer"),
+            ArgumentKind::NonNegativeInteger => {
+                write!(f, "a non-negative integer")
+            },
+            ArgumentKind::Number => write!(f, "a number"),
+            ArgumentKind::PositiveNumber => write!(f, "a positive number"),
+            ArgumentKind::Boolean => write!(f, "either true or false"),
+            ArgumentKind::Text => write!(f, "text"),
+            ArgumentKind::Custom { description, .. } => {
+                write!(f, "{}", description)
+            },
+        }
+    }
+}
+
+impl fmt::Debug for ArgumentKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "ArgumentKind({})", self)
+    }
+}
+
+/// A single argument accepted by a capability.
+#[derive(Debug, Copy, Clone)]
+pub struct ArgumentSpec {
+    pub name: &'static str,
+    pub kind: ArgumentKind,
+    pub required: bool,
+}
+
+const fn required(name: &'static str, kind: ArgumentKind) -> ArgumentSpec {
+    ArgumentSpec {
+        name,
+        kind,
+        required: true,
+    }
+}
+
+const fn optional(name: &'static str, kind: ArgumentKind) -> ArgumentSpec {
+    ArgumentSpec {
+        name,
+        kind,
+        required: false,
+    }
+}
+
+fn parses<T: FromStr>(value: &str) -> bool { value.parse::<T>().is_ok() }
+
+fn is_index_list(value: &str) -> bool {
+    value.split(',').all(|index| index.trim().parse::<usize>().is_ok())
+}
+
+fn is_conversion(value: &str) -> bool {
+    matches!(value, "saturate" | "normalize" | "quantize")
+}
+
+fn is_number_list(value: &str) -> bool {
+    value
+        .parse::<ArgumentValue>()
+        .map_or(false, |v| v.parse_list::<f32>().is_ok())
+}
+
+const NUMBERS: ArgumentKind = ArgumentKind::Custom {
+    description: "a number or list of numbers (e.g. [0.5, 0.5, 0.5])",
+    is_valid: is_number_list,
+};
+const PIXEL_FORMAT: ArgumentKind = ArgumentKind::Custom {
+    description: "a pixel format (e.g. \"RGB8\" or \"GrayScale\")",
+    is_valid: parses::<PixelFormat>,
+};
+const ELEMENT_TYPE: ArgumentKind = ArgumentKind::Custom {
+    description: "an element type (e.g. \"u8\" or \"f32\")",
+    is_valid: parses::<ElementType>,
+};
+
+/// Arguments that are understood by every builtin capability.
+const COMMON: &[ArgumentSpec] = &[
+    optional(
+        "source",
+        ArgumentKind::Custom {
+            description: "a comma-separated list of source indices",
+            is_valid: is_index_list,
+        },
+    ),
+    optional(
+        "combine",
+        ArgumentKind::Custom {
+            description: "one of \"interleave\", \"concat\", or \"mean\"",
+            is_valid: parses::<CombinePolicy>,
+        },
+    ),
+    optional("batch_size", ArgumentKind::PositiveInteger),
+    // Note: this is set by the Rune itself
+    optional(
+        "shape",
+        ArgumentKind::Custom {
+            description: "a tensor shape (e.g. \"f32[1, 28, 28]\")",
+            is_valid: parses::<Shape<'static>>,
+        },
+    ),
+    optional("batch_index", ArgumentKind::NonNegativeInteger),
+    optional("convert_to", ELEMENT_TYPE),
+    optional(
+        "conversion",
+        ArgumentKind::Custom {
+            description: "one of \"saturate\", \"normalize\", or \
+                          \"quantize\"",
+            is_valid: is_conversion,
+        },
+    ),
+    optional("scale", ArgumentKind::PositiveNumber),
+    optional(
+        "zero_point",
+        ArgumentKind::Custom {
+            description: "a whole number",
+            is_valid: parses::<i32>,
+        },
+    ),
+];
+
+const IMAGE: &[ArgumentSpec] = &[
+    required("width", ArgumentKind::PositiveInteger),
+    required("height", ArgumentKind::PositiveInteger),
+    optional("pixel_format", PIXEL_FORMAT),
+    optional(
+        "resize",
+        ArgumentKind::Custom {
+            description: "one of \"nearest\", \"bilinear\", \"bicubic\", or \
+                          \"lanczos\"",
+            is_valid: parses::<ResizeFilter>,
+        },
+    ),
+    optional("center_crop", ArgumentKind::Boolean),
+    optional("mean", NUMBERS),
+    optional("std", NUMBERS),
+];
+
+const VIDEO: &[ArgumentSpec] = &[
+    required("width", ArgumentKind::PositiveInteger),
+    required("height", ArgumentKind::PositiveInteger),
+    optional("pixel_format", PIXEL_FORMAT),
+    optional("frames", ArgumentKind::PositiveInteger),
+    optional("fps", ArgumentKind::PositiveNumber),
+];
+
+const SOUND: &[ArgumentSpec] = &[
+    required("hz", ArgumentKind::PositiveInteger),
+    required("sample_duration_ms", ArgumentKind::PositiveInteger),
+];
+
+const ACCEL: &[ArgumentSpec] = &[
+    optional("samples", ArgumentKind::PositiveInteger),
+    optional("rate", ArgumentKind::PositiveNumber),
+    optional(
+        "interpolation",
+        ArgumentKind::Custom {
+            description: "one of \"linear\", \"nearest\", or \"previous\"",
+            is_valid: parses::<Interpolation>,
+        },
+    ),
+];
+
+const GYRO: &[ArgumentSpec] = &[
+    optional("samples", ArgumentKind::PositiveInteger),
+    optional("rate", ArgumentKind::PositiveNumber),
+];
+
+const SAMPLES: &[ArgumentSpec] =
+    &[optional("samples", ArgumentKind::PositiveInteger)];
+
+const PROXIMITY: &[ArgumentSpec] = &[
+    optional("samples", ArgumentKind::PositiveInteger),
+    optional("max_range", ArgumentKind::PositiveNumber),
+];
+
+const THERMOMETER: &[ArgumentSpec] = &[
+    optional("samples", ArgumentKind::PositiveInteger),
+    optional(
+        "unit",
+        ArgumentKind::Custom {
+            description: "one of \"celsius\", \"fahrenheit\", or \"kelvin\"",
+            is_valid: parses::<TemperatureUnit>,
+        },
+    ),
+    optional("humidity", ArgumentKind::Boolean),
+];
+
+const BAROMETER: &[ArgumentSpec] = &[
+    optional("samples", ArgumentKind::PositiveInteger),
+    optional("altitude", ArgumentKind::Boolean),
+    optional("sea_level", ArgumentKind::PositiveNumber),
+];
+
+const RAND: &[ArgumentSpec] = &[
+    optional("amount", ArgumentKind::PositiveInteger),
+    optional(
+        "distribution",
+        ArgumentKind::Custom {
+            description: "one of \"bits\", \"uniform\", \"gaussian\", or \
+                          \"integer\"",
+            is_valid: parses::<RandomDistribution>,
+        },
+    ),
+    optional("min", ArgumentKind::Number),
+    optional("max", ArgumentKind::Number),
+    optional("mean", ArgumentKind::Number),
+    optional("std_dev", ArgumentKind::PositiveNumber),
+];
+
+const RAW: &[ArgumentSpec] =
+    &[optional("length", ArgumentKind::PositiveInteger)];
+
+const SIGNAL: &[ArgumentSpec] = &[
+    optional(
+        "waveform",
+        ArgumentKind::Custom {
+            description: "one of \"sine\", \"square\", \"chirp\", \"noise\", \
+                          or \"step\"",
+            is_valid: parses::<Waveform>,
+        },
+    ),
+    optional("samples", ArgumentKind::PositiveInteger),
+    optional("rate", ArgumentKind::PositiveNumber),
+    optional("amplitude", ArgumentKind::Number),
+    optional("offset", ArgumentKind::Number),
+    optional("frequency", ArgumentKind::Number),
+    optional("end_frequency", ArgumentKind::Number),
+    optional("phase", ArgumentKind::Number),
+    optional("duty_cycle", ArgumentKind::Number),
+    optional("step_at", ArgumentKind::NonNegativeInteger),
+    optional("seed", ArgumentKind::NonNegativeInteger),
+];
+
+const TENSOR: &[ArgumentSpec] = &[
+    optional("array", ArgumentKind::Text),
+    optional("element_type", ELEMENT_TYPE),
+    optional(
+        "dimensions",
+        ArgumentKind::Custom {
+            description: "a comma-separated list of dimensions",
+            is_valid: is_index_list,
+        },
+    ),
+];
+
+const TABULAR: &[ArgumentSpec] = &[
+    optional("columns", ArgumentKind::Text),
+    optional("element_type", ELEMENT_TYPE),
+    optional("offset", ArgumentKind::NonNegativeInteger),
+    optional("window", ArgumentKind::PositiveInteger),
+    optional("header", ArgumentKind::Boolean),
+    optional(
+        "normalize",
+        ArgumentKind::Custom {
+            description: "one of \"none\", \"minmax\", or \"zscore\"",
+            is_valid: parses::<Normalization>,
+        },
+    ),
+];
+
+/// The arguments a builtin capability accepts (on top of the common
+/// `source`, `combine`, and `batch_size` arguments), or `None` if the
+/// capability isn't one of the builtins.
+pub fn capability_schema(kind: &str) -> Option<&'static [ArgumentSpec]> {
+    let schema = match kind {
+        "IMAGE" => IMAGE,
+        "VIDEO" => VIDEO,
+        "SOUND" => SOUND,
+        "ACCEL" => ACCEL,
+        "GYRO" => GYRO,
+        "GPS" | "LIGHT" => SAMPLES,
+        "PROXIMITY" => PROXIMITY,
+        "THERMOMETER" => THERMOMETER,
+        "BAROMETER" => BAROMETER,
+        "BATTERY" => &[],
+        "RAND" => RAND,
+        "RAW" => RAW,
+        "SIGNAL" => SIGNAL,
+        "TENSOR" => TENSOR,
+        "TABULAR" => TABULAR,
+        _ => return None,
+    };
+
+    Some(schema)
+}
+
+/// Check a capability's arguments against its schema, so mistakes are
+/// reported when the Rune is loaded rather than when (or if) the argument is
+/// first used.
+///
+/// Arguments the schema doesn't know about are passed through (they may be
+/// used by the Rune itself) with a warning.
+pub fn validate_arguments(
+    kind: &str,
+    args: &Arguments,
+) -> Result<(), ArgumentError> {
+    let schema = match capability_schema(kind) {
+        Some(schema) => schema,
+        None => return Ok(()),
+    };
+    let capability = kind.to_lowercase();
+
+    for spec in COMMON.iter().chain(schema) {
+        match args.0.get(spec.name) {
+            Some(value) if !spec.kind.is_valid(value) => {
+                return Err(ArgumentError::Invalid {
+                    capability,
+                    name: spec.name,
+                    expected: spec.kind,
+                    value: value.clone(),
+                });
+            },
+            None if spec.required => {
+                return Err(ArgumentError::Missing {
+                    capability,
+                    name: spec.name,
+                    expected: spec.kind,
+                });
+            },
+            _ => {},
+        }
+    }
+
+    check_fan_in(&capability, args)?;
+
+    for name in args.0.keys() {
+        if !COMMON.iter().chain(schema).any(|spec| spec.name == name) {
+            log::warn!(
+                "capability `{}`: the builtin doesn't use the `{}` argument",
+                capability,
+                name
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Make sure the sources selected by `"source"` can be combined into the
+/// shape the Rune declared.
+fn check_fan_in(
+    capability: &str,
+    args: &Arguments,
+) -> Result<(), ArgumentError> {
+    let declared = match fan_in::declared_shape(args) {
+        Ok(Some(shape)) => shape,
+        _ => return Ok(()),
+    };
+    let source_count = args
+        .0
+        .get("source")
+        .map_or(1, |value| value.split(',').count());
+    let policy = args
+        .0
+        .get("combine")
+        .and_then(|value| value.parse().ok())
+        .unwrap_or_default();
+
+    fan_in::check_policy(policy, source_count, &declared).map_err(|reason| {
+        ArgumentError::IncompatibleShape {
+            capability: capability.to_string(),
+            reason,
+        }
+    })
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ArgumentError {
+    #[error(
+        "capability `{capability}`: `{name}` must be {expected} (got \
+         {value:?})"
+    )]
+    Invalid {
+        capability: String,
+        name: &'static str,
+        expected: ArgumentKind,
+        value: String,
+    },
+    #[error(
+        "capability `{capability}`: `{name}` is required and must be \
+         {expected}"
+    )]
+    Missing {
+        capability: String,
+        name: &'static str,
+        expected: ArgumentKind,
+    },
+    #[error("capability `{capability}`: {reason}")]
+    IncompatibleShape { capability: String, reason: String },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalid_values_are_reported() {
+        let args =
+            Arguments::from([("hz", "abc"), ("sample_duration_ms", "1000")]);
+
+        let err = validate_arguments("SOUND", &args).unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "capability `sound`: `hz` must be a positive integer (got \"abc\")"
+        );
+    }
+
+    #[test]
+    fn missing_arguments_are_reported() {
+        let args = Arguments::from([("width", "224")]);
+
+        let err = validate_arguments("IMAGE", &args).unwrap_err();
+
+        assert!(matches!(err, ArgumentError::Missing { name: "height", .. }));
+    }
+
+    #[test]
+    fn negative_numbers_arent_positive() {
+        let args = Arguments::from([("samples", "-1")]);
+
+        assert!(validate_arguments("LIGHT", &args).is_err());
+    }
+
+    #[test]
+    fn valid_arguments_and_unknown_capabilities_are_accepted() {
+        let image = Arguments::from([
+            ("width", "224"),
+            ("height", "224"),
+            ("pixel_format", "RGB8"),
+            ("source", "0, 1"),
+            ("combine", "mean"),
+        ]);
+
+        assert!(validate_arguments("IMAGE", &image).is_ok());
+        assert!(validate_arguments("CUSTOM", &Arguments::from([("x", "?")]))
+            .is_ok());
+    }
+
+    #[test]
+    fn sources_must_split_the_declared_shape_evenly() {
+        let stereo = Arguments::from([
+            ("hz", "16000"),
+            ("sample_duration_ms", "1000"),
+            ("source", "0,1,2"),
+            ("shape", "i16[1, 16000]"),
+        ]);
+
+        let err = validate_arguments("SOUND", &stereo).unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "capability `sound`: Interleaving 3 sources into i16[1, 16000] \
+             needs the last dimension to be a multiple of 3"
+        );
+    }
+}
@@ -0,0 +1,213 @@
+use std::{f64::consts::PI, str::FromStr};
+
+use anyhow::{Context, Error};
+use rand::{Rng, SeedableRng};
+use rand_distr::Normal;
+
+use crate::{builtins::Arguments, Tensor};
+
+/// Generate a `f32[1, samples]` tensor containing a synthetic [`Waveform`],
+/// so DSP proc blocks and models can be tested without recorded data.
+///
+/// The `"samples"` argument (defaults to `1024`) are taken at `"rate"` Hz
+/// (defaults to `1000`). Every waveform is scaled by `"amplitude"` (defaults
+/// to `1`) and shifted by `"offset"` (defaults to `0`).
+pub fn signal(args: &Arguments) -> Result<Tensor, Error> {
+    let waveform: Waveform =
+        args.parse_or_default("waveform", Waveform::default())?;
+    let samples: usize = args.parse_or_default("samples", 1024)?;
+    let rate: f64 = args.parse_or_default("rate", 1000.0)?;
+    let amplitude: f64 = args.parse_or_default("amplitude", 1.0)?;
+    let offset: f64 = args.parse_or_default("offset", 0.0)?;
+    anyhow::ensure!(samples > 0, "At least one sample must be requested");
+    anyhow::ensure!(rate > 0.0, "The sample rate must be positive");
+
+    let values = match waveform {
+        Waveform::Sine => {
+            let frequency: f64 = args.parse_or_default("frequency", 1.0)?;
+            let phase: f64 = args.parse_or_default("phase", 0.0)?;
+
+            generate(samples, rate, |t| {
+                (2.0 * PI * frequency * t + phase).sin()
+            })
+        },
+        Waveform::Square => {
+            let frequency: f64 = args.parse_or_default("frequency", 1.0)?;
+            let duty_cycle: f64 = args.parse_or_default("duty_cycle", 0.5)?;
+            anyhow::ensure!(
+                (0.0..=1.0).contains(&duty_cycle),
+                "The duty cycle must be between 0 and 1, not {}",
+                duty_cycle
+            );
+
+            generate(samples, rate, |t| {
+                if (frequency * t).fract() < duty_cycle {
+                    1.0
+                } else {
+                    -1.0
+                }
+            })
+        },
+        Waveform::Chirp => {
+            let start: f64 = args.parse_or_default("frequency", 1.0)?;
+            let duration = samples as f64 / rate;
+            let end: f64 =
+                args.parse_or_default("end_frequency", rate / 2.0)?;
+            let sweep = (end - start) / duration;
+
+            // A linear chirp, where the instantaneous frequency goes from
+            // start to end over the whole signal
+            generate(samples, rate, |t| {
+                (2.0 * PI * (start * t + sweep * t * t / 2.0)).sin()
+            })
+        },
+        Waveform::Noise => {
+            let normal = Normal::new(0.0, 1.0).context("Invalid distribution")?;
+
+            match args.0.get("seed") {
+                Some(_) => {
+                    let seed: u64 = args.parse("seed")?;
+                    rand::rngs::SmallRng::seed_from_u64(seed)
+                        .sample_iter(normal)
+                        .take(samples)
+                        .collect()
+                },
+                None => rand::thread_rng()
+                    .sample_iter(normal)
+                    .take(samples)
+                    .collect(),
+            }
+        },
+        Waveform::Step => {
+            let step_at: usize = args.parse_or_default("step_at", samples / 2)?;
+
+            (0..samples)
+                .map(|i| if i < step_at { 0.0 } else { 1.0 })
+                .collect()
+        },
+    };
+
+    let values: Vec<f32> = values
+        .into_iter()
+        .map(|v: f64| (v * amplitude + offset) as f32)
+        .collect();
+
+    Ok(Tensor::new(&values, &[1, samples]))
+}
+
+fn generate(samples: usize, rate: f64, f: impl Fn(f64) -> f64) -> Vec<f64> {
+    (0..samples).map(|i| f(i as f64 / rate)).collect()
+}
+
+/// The shape of the signal generated by [`signal()`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Waveform {
+    /// A sine wave with the given `"frequency"` (Hz, defaults to `1`) and
+    /// `"phase"` (radians, defaults to `0`).
+    Sine,
+    /// A square wave alternating between `1` and `-1` at the given
+    /// `"frequency"`, which is high for `"duty_cycle"` (defaults to `0.5`) of
+    /// each period.
+    Square,
+    /// A sine wave whose frequency sweeps linearly from `"frequency"` to
+    /// `"end_frequency"` (defaults to the Nyquist frequency).
+    Chirp,
+    /// Gaussian white noise, optionally generated deterministically from a
+    /// `"seed"`.
+    Noise,
+    /// `0` until the `"step_at"` sample (defaults to halfway), then `1`.
+    Step,
+}
+
+impl Default for Waveform {
+    fn default() -> Self { Waveform::Sine }
+}
+
+impl FromStr for Waveform {
+    type Err = UnknownWaveform;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sine" | "sin" => Ok(Waveform::Sine),
+            "square" => Ok(Waveform::Square),
+            "chirp" | "sweep" => Ok(Waveform::Chirp),
+            "noise" | "gaussian" => Ok(Waveform::Noise),
+            "step" => Ok(Waveform::Step),
+            _ => Err(UnknownWaveform),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, thiserror::Error)]
+#[error(
+    "Expected one of \"sine\", \"square\", \"chirp\", \"noise\", or \"step\""
+)]
+pub struct UnknownWaveform;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn elements(tensor: &Tensor) -> Vec<f32> {
+        tensor.elements::<f32>().unwrap().to_vec()
+    }
+
+    #[test]
+    fn sine_wave() {
+        let args = Arguments::from([
+            ("rate", "4"),
+            ("samples", "4"),
+            ("amplitude", "2"),
+        ]);
+
+        let got = signal(&args).unwrap();
+
+        assert_eq!(got.dimensions()[1].get(), 4);
+        let should_be = [0.0, 2.0, 0.0, -2.0];
+        for (got, expected) in elements(&got).iter().zip(should_be) {
+            assert!((got - expected).abs() < 1e-5, "{} != {}", got, expected);
+        }
+    }
+
+    #[test]
+    fn square_wave_with_a_duty_cycle() {
+        let args = Arguments::from([
+            ("waveform", "square"),
+            ("rate", "4"),
+            ("samples", "8"),
+            ("duty_cycle", "0.25"),
+        ]);
+
+        let got = signal(&args).unwrap();
+
+        assert_eq!(
+            elements(&got),
+            vec![1.0, -1.0, -1.0, -1.0, 1.0, -1.0, -1.0, -1.0]
+        );
+    }
+
+    #[test]
+    fn step_with_an_offset() {
+        let args = Arguments::from([
+            ("waveform", "step"),
+            ("samples", "4"),
+            ("step_at", "1"),
+            ("offset", "0.5"),
+        ]);
+
+        let got = signal(&args).unwrap();
+
+        assert_eq!(elements(&got), vec![0.5, 1.5, 1.5, 1.5]);
+    }
+
+    #[test]
+    fn seeded_noise_is_deterministic() {
+        let args = Arguments::from([
+            ("waveform", "noise"),
+            ("samples", "16"),
+            ("seed", "42"),
+        ]);
+
+        assert_eq!(signal(&args).unwrap(), signal(&args).unwrap());
+    }
+}
@@ -14,6 +14,12 @@ use crate::{builtins::Arguments, Tensor};
 
 /// Load an input from a sound clip, applying any transformations requested by
 /// the Rune.
+///
+/// Clips with several channels are mixed down to mono by averaging each
+/// frame's channels. If the clip's sample rate doesn't match the `"hz"`
+/// argument, enough audio to cover `"sample_duration_ms"` is taken from the
+/// start of the clip and resampled using linear interpolation between
+/// neighbouring samples.
 pub fn sound(args: &Arguments, clip: &AudioClip) -> Result<Tensor, Error> {
     let sample_rate: u32 = args.parse("hz")?;
     let sample_duration_ms = args.parse("sample_duration_ms")?;
@@ -26,28 +32,92 @@ pub fn sound(args: &Arguments, clip: &AudioClip) -> Result<Tensor, Error> {
 fn transform_samples(
     sample_rate: u32,
     duration: Duration,
-    _spec: &WavSpec,
+    spec: &WavSpec,
     samples: &[i16],
 ) -> Result<Tensor, Error> {
-    // TODO: actually resample the audio so it will have the correct sample rate
-    // instead of blindly copying across the requested number of samples.
+    let required_samples = samples_in(sample_rate, duration)?;
 
-    let required_samples = usize::try_from(
-        (sample_rate as u128) * duration.as_micros() / 1_000_000,
-    )?;
+    let mono = if spec.channels > 1 {
+        log::debug!("Mixing {} channels down to mono", spec.channels);
+        downmix(samples, usize::from(spec.channels))
+    } else {
+        samples.to_vec()
+    };
 
-    if samples.len() < required_samples {
+    let available = samples_in(spec.sample_rate, duration)?;
+
+    if mono.len() < available {
         anyhow::bail!(
             "At least {} samples are required to generate this input, but \
              only {} were provided",
+            available,
+            mono.len(),
+        );
+    }
+
+    let samples = if spec.sample_rate == sample_rate {
+        mono[..required_samples].to_vec()
+    } else {
+        log::debug!(
+            "Resampling {} samples at {} Hz to {} samples at {} Hz",
+            available,
+            spec.sample_rate,
             required_samples,
-            samples.len(),
+            sample_rate
         );
+        let original: Vec<f32> =
+            mono[..available].iter().map(|&s| f32::from(s)).collect();
+        resample(&original, required_samples)
+            .into_iter()
+            .map(|s| s.round() as i16)
+            .collect()
+    };
+
+    Ok(Tensor::new(&samples, &[1, samples.len()]))
+}
+
+/// How many samples are needed to cover a duration at a given sample rate.
+fn samples_in(sample_rate: u32, duration: Duration) -> Result<usize, Error> {
+    let samples = (sample_rate as u128) * duration.as_micros() / 1_000_000;
+    usize::try_from(samples).map_err(Error::from)
+}
+
+/// Average each frame of interleaved samples.
+fn downmix(samples: &[i16], channels: usize) -> Vec<i16> {
+    samples
+        .chunks_exact(channels)
+        .map(|frame| {
+            let sum: i32 = frame.iter().map(|&s| i32::from(s)).sum();
+            (sum / channels as i32) as i16
+        })
+        .collect()
+}
+
+/// Stretch or squash the samples to the desired length using linear
+/// interpolation.
+pub(crate) fn resample(samples: &[f32], output_len: usize) -> Vec<f32> {
+    match (samples.len(), output_len) {
+        (_, 0) | (0, _) => return vec![0.0; output_len],
+        (1, _) | (_, 1) => return vec![samples[0]; output_len],
+        _ => {},
     }
 
-    let samples = &samples[..required_samples];
+    let step = (samples.len() - 1) as f32 / (output_len - 1) as f32;
+
+    (0..output_len)
+        .map(|i| {
+            let position = i as f32 * step;
+            let index = position.floor() as usize;
+            let fraction = position - index as f32;
 
-    Ok(Tensor::new(samples, &[1, samples.len()]))
+            match samples.get(index + 1) {
+                Some(next) => {
+                    samples[index] + (next - samples[index]) * fraction
+                },
+                None => samples[index],
+            }
+        })
+        .collect()
 }
 
 #[derive(Clone, PartialEq)]
@@ -57,6 +127,136 @@ pub struct AudioClip {
 }
 
 impl AudioClip {
+    /// Create a clip from single-channel 16-bit audio.
+    pub fn mono(sample_rate: u32, samples: Vec<i16>) -> Self {
+        let spec = WavSpec {
+            channels: 1,
+            sample_rate,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+
+        AudioClip { spec, samples }
+    }
+
+    /// Load an audio file, using its extension to figure out the format.
+    ///
+    /// WAV files are always supported, while MP3, OGG Vorbis, and FLAC files
+    /// need the `compressed-audio` feature.
+    pub fn from_file(filename: impl AsRef<Path>) -> Result<Self, Error> {
+        let filename = filename.as_ref();
+        let is_wav = filename
+            .extension()
+            .map(|ext| ext.eq_ignore_ascii_case("wav"))
+            .unwrap_or(false);
+
+        if is_wav {
+            AudioClip::from_wav_file(filename)
+        } else {
+            AudioClip::from_compressed_file(filename)
+        }
+    }
+
+    #[cfg(feature = "compressed-audio")]
+    fn from_compressed_file(filename: &Path) -> Result<Self, Error> {
+        let f = File::open(filename).with_context(|| {
+            format!("Unable to open \"{}\" for reading", filename.display())
+        })?;
+        let extension = filename.extension().and_then(|ext| ext.to_str());
+
+        AudioClip::decode(f, extension).with_context(|| {
+            format!("Unable to decode \"{}\"", filename.display())
+        })
+    }
+
+    #[cfg(not(feature = "compressed-audio"))]
+    fn from_compressed_file(filename: &Path) -> Result<Self, Error> {
+        anyhow::bail!(
+            "Unable to load \"{}\" because only WAV files are supported. \
+             Enable the \"compressed-audio\" feature for MP3, OGG, and FLAC",
+            filename.display()
+        )
+    }
+
+    /// Decode a compressed audio stream (MP3, OGG Vorbis, or FLAC) using
+    /// [symphonia](https://crates.io/crates/symphonia).
+    ///
+    /// The `extension` is used as a hint when detecting the format.
+    #[cfg(feature = "compressed-audio")]
+    pub fn decode(
+        source: impl symphonia::core::io::MediaSource + 'static,
+        extension: Option<&str>,
+    ) -> Result<Self, Error> {
+        use symphonia::core::{
+            audio::SampleBuffer, codecs::DecoderOptions,
+            errors::Error as DecodeError, formats::FormatOptions,
+            io::MediaSourceStream, meta::MetadataOptions, probe::Hint,
+        };
+
+        let stream =
+            MediaSourceStream::new(Box::new(source), Default::default());
+        let mut hint = Hint::new();
+        if let Some(extension) = extension {
+            hint.with_extension(extension);
+        }
+
+        let probed = symphonia::default::get_probe()
+            .format(
+                &hint,
+                stream,
+                &FormatOptions::default(),
+                &MetadataOptions::default(),
+            )
+            .context("Unrecognised audio format")?;
+        let mut format = probed.format;
+
+        let track = format
+            .default_track()
+            .context("The file doesn't contain any audio")?;
+        let track_id = track.id;
+        let mut decoder = symphonia::default::get_codecs()
+            .make(&track.codec_params, &DecoderOptions::default())
+            .context("Unsupported codec")?;
+
+        let mut spec = None;
+        let mut samples = Vec::new();
+
+        loop {
+            let packet = match format.next_packet() {
+                Ok(packet) => packet,
+                Err(DecodeError::IoError(e))
+                    if e.kind() == std::io::ErrorKind::UnexpectedEof =>
+                {
+                    break
+                },
+                Err(e) => return Err(e.into()),
+            };
+
+            if packet.track_id() != track_id {
+                continue;
+            }
+
+            let decoded = decoder.decode(&packet)?;
+            let signal = *decoded.spec();
+            let mut buffer =
+                SampleBuffer::<i16>::new(decoded.capacity() as u64, signal);
+            buffer.copy_interleaved_ref(decoded);
+            samples.extend_from_slice(buffer.samples());
+
+            spec.get_or_insert(WavSpec {
+                channels: signal.channels.count() as u16,
+                sample_rate: signal.rate,
+                bits_per_sample: 16,
+                sample_format: hound::SampleFormat::Int,
+            });
+        }
+
+        let spec = spec.context("The file doesn't contain any audio")?;
+        log::debug!("Decoded {} samples with {:?}", samples.len(), spec);
+
+        Ok(AudioClip { spec, samples })
+    }
+
     pub fn from_wav_file(filename: impl AsRef<Path>) -> Result<Self, Error> {
         let filename = filename.as_ref();
         let f = File::open(filename).with_context(|| {
@@ -86,3 +286,116 @@ impl Debug for AudioClip {
             .finish()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec(channels: u16, sample_rate: u32) -> WavSpec {
+        WavSpec {
+            channels,
+            sample_rate,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        }
+    }
+
+    #[test]
+    fn upsample_with_interpolation() {
+        let got = resample(&[0.0, 1.0, 0.0], 5);
+
+        assert_eq!(got, vec![0.0, 0.5, 1.0, 0.5, 0.0]);
+    }
+
+    #[test]
+    fn downsample_keeps_the_endpoints() {
+        let got = resample(&[0.0, 1.0, 2.0, 3.0, 4.0], 3);
+
+        assert_eq!(got, vec![0.0, 2.0, 4.0]);
+    }
+
+    #[test]
+    fn stereo_is_mixed_down_to_mono() {
+        let samples = [100, 200, -10, 10, 7, 7];
+
+        let got = transform_samples(
+            1000,
+            Duration::from_millis(3),
+            &spec(2, 1000),
+            &samples,
+        )
+        .unwrap();
+
+        assert_eq!(got, Tensor::new(&[150_i16, 0, 7], &[1, 3]));
+    }
+
+    #[test]
+    fn resample_to_the_requested_rate() {
+        let samples: Vec<i16> = (0..8).map(|i| i * 10).collect();
+
+        let got = transform_samples(
+            2000,
+            Duration::from_millis(2),
+            &spec(1, 4000),
+            &samples,
+        )
+        .unwrap();
+
+        // 8 samples at 4kHz become 4 samples at 2kHz
+        assert_eq!(got, Tensor::new(&[0_i16, 23, 47, 70], &[1, 4]));
+    }
+
+    /// Load one of the small clips in `tests/fixtures/`.
+    #[cfg(feature = "compressed-audio")]
+    fn fixture(name: &str) -> AudioClip {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests")
+            .join("fixtures")
+            .join(name);
+
+        AudioClip::from_file(path).unwrap()
+    }
+
+    #[test]
+    #[cfg(feature = "compressed-audio")]
+    fn decode_flac() {
+        // 400 frames of a 440 Hz tone at 8 kHz, with the right channel
+        // inverted
+        let AudioClip { spec, samples } = fixture("tone.flac");
+
+        assert_eq!(spec, self::spec(2, 8000));
+        assert_eq!(samples.len(), 2 * 400);
+        // FLAC is lossless, so we should get back exactly what was encoded
+        assert_eq!(&samples[..6], &[0, 0, 2709, -2709, 5099, -5099]);
+    }
+
+    #[test]
+    #[cfg(feature = "compressed-audio")]
+    fn decode_mp3() {
+        // 4 frames of silence at 32 kHz
+        let AudioClip { spec, samples } = fixture("silence.mp3");
+
+        assert_eq!(spec, self::spec(1, 32_000));
+        assert_eq!(samples.len(), 4 * 1152);
+        assert!(samples.iter().all(|&s| s == 0));
+    }
+
+    #[test]
+    #[cfg(feature = "compressed-audio")]
+    fn decode_ogg_vorbis() {
+        // 1024 samples of silence at 22.05 kHz
+        let AudioClip { spec, samples } = fixture("silence.ogg");
+
+        assert_eq!(spec, self::spec(2, 22_050));
+        assert_eq!(samples.len(), 2 * 1024);
+        assert!(samples.iter().all(|&s| s == 0));
+    }
+
+    #[test]
+    #[cfg(not(feature = "compressed-audio"))]
+    fn compressed_audio_needs_a_feature_flag() {
+        let err = AudioClip::from_file("clip.flac").unwrap_err();
+
+        assert!(err.to_string().contains("compressed-audio"));
+    }
+}
@@ -0,0 +1,290 @@
+use std::{fs::File, io::Read, path::Path, str::FromStr};
+
+use anyhow::{Context, Error};
+use csv::StringRecord;
+
+use crate::{builtins::Arguments, ElementType, Tensor};
+
+/// Load an input tensor from a table of data (e.g. a CSV file).
+///
+/// The following arguments are supported:
+///
+/// - `"columns"` - a comma-separated list of column names or indices to
+///   use (defaults to every column)
+/// - `"element_type"` - either `f32` (the default) or `i32`
+/// - `"offset"` - the first row to read (defaults to `0`)
+/// - `"window"` - how many rows to read (defaults to the rest of the table)
+/// - `"header"` - whether the first row is a header, which is detected
+///   automatically if not provided
+/// - `"normalize"` - a [`Normalization`] applied to each column
+///
+/// The result is a `[window, columns]` tensor.
+pub fn tabular(args: &Arguments, table: &Table) -> Result<Tensor, Error> {
+    let element_type: ElementType =
+        args.parse_or_default("element_type", ElementType::F32)?;
+    let has_header: bool =
+        args.parse_or_default("header", table.looks_like_it_has_a_header())?;
+    let normalization: Normalization =
+        args.parse_or_default("normalize", Normalization::None)?;
+
+    let (header, rows) = match table.0.split_first() {
+        Some((header, rows)) if has_header => (Some(header), rows),
+        _ => (None, table.0.as_slice()),
+    };
+
+    let columns = match args.0.get("columns") {
+        Some(columns) => columns
+            .split(',')
+            .map(|c| column_index(c.trim(), header))
+            .collect::<Result<Vec<_>, Error>>()?,
+        None => (0..rows.first().map(|r| r.len()).unwrap_or(0)).collect(),
+    };
+    anyhow::ensure!(!columns.is_empty(), "No columns were selected");
+
+    let offset: usize = args.parse_or_default("offset", 0)?;
+    let available = rows.len().saturating_sub(offset);
+    let window: usize = args.parse_or_default("window", available)?;
+    anyhow::ensure!(
+        window > 0 && window <= available,
+        "{} rows were requested starting from row {}, but the table only has \
+         {}",
+        window,
+        offset,
+        rows.len(),
+    );
+
+    let values = columns
+        .iter()
+        .map(|&column| {
+            let all = parse_column(rows, column, has_header)?;
+            let selected = &all[offset..offset + window];
+            Ok(normalization.apply(selected, &all))
+        })
+        .collect::<Result<Vec<Vec<f64>>, Error>>()?;
+
+    // Transpose from column-major to row-major
+    let row_major =
+        (0..window).flat_map(|row| values.iter().map(move |c| c[row]));
+    let dimensions = [window, columns.len()];
+
+    match element_type {
+        ElementType::F32 => {
+            let elements: Vec<f32> = row_major.map(|v| v as f32).collect();
+            Ok(Tensor::new(&elements, &dimensions))
+        },
+        ElementType::I32 => {
+            anyhow::ensure!(
+                normalization == Normalization::None,
+                "Normalized values can't be stored as i32"
+            );
+            let elements: Vec<i32> =
+                row_major.map(|v| v.round() as i32).collect();
+            Ok(Tensor::new(&elements, &dimensions))
+        },
+        other => anyhow::bail!(
+            "Tables can only be loaded as f32 or i32, not {}",
+            other
+        ),
+    }
+}
+
+fn column_index(
+    column: &str,
+    header: Option<&StringRecord>,
+) -> Result<usize, Error> {
+    if let Some(index) = header.and_then(|h| h.iter().position(|n| n == column))
+    {
+        return Ok(index);
+    }
+
+    column.parse().with_context(|| {
+        format!("There is no column called \"{}\"", column)
+    })
+}
+
+fn parse_column(
+    rows: &[StringRecord],
+    column: usize,
+    has_header: bool,
+) -> Result<Vec<f64>, Error> {
+    rows.iter()
+        .enumerate()
+        .map(|(i, row)| {
+            let line = i + 1 + usize::from(has_header);
+            let field = row.get(column).with_context(|| {
+                format!("Line {} doesn't have a column {}", line, column)
+            })?;
+            field.parse().with_context(|| {
+                format!("Unable to parse \"{}\" on line {}", field, line)
+            })
+        })
+        .collect()
+}
+
+/// How each column's values are rescaled.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Normalization {
+    /// Leave the values as they are.
+    None,
+    /// Rescale the values to the range `[0, 1]` using the column's minimum
+    /// and maximum.
+    MinMax,
+    /// Subtract the column's mean and divide by its standard deviation.
+    ZScore,
+}
+
+impl Normalization {
+    /// Normalize some values using statistics calculated from the whole
+    /// column.
+    fn apply(self, values: &[f64], column: &[f64]) -> Vec<f64> {
+        match self {
+            Normalization::None => values.to_vec(),
+            Normalization::MinMax => {
+                let min = column.iter().copied().fold(f64::INFINITY, f64::min);
+                let max =
+                    column.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+                let range = max - min;
+
+                values
+                    .iter()
+                    .map(|v| if range > 0.0 { (v - min) / range } else { 0.0 })
+                    .collect()
+            },
+            Normalization::ZScore => {
+                let n = column.len() as f64;
+                let mean = column.iter().sum::<f64>() / n;
+                let variance =
+                    column.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n;
+                let std_dev = variance.sqrt();
+
+                values
+                    .iter()
+                    .map(|v| {
+                        if std_dev > 0.0 {
+                            (v - mean) / std_dev
+                        } else {
+                            0.0
+                        }
+                    })
+                    .collect()
+            },
+        }
+    }
+}
+
+impl FromStr for Normalization {
+    type Err = UnknownNormalization;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Normalization::None),
+            "minmax" | "min-max" => Ok(Normalization::MinMax),
+            "zscore" | "z-score" | "standard" => Ok(Normalization::ZScore),
+            _ => Err(UnknownNormalization),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, thiserror::Error)]
+#[error("Expected one of \"none\", \"minmax\", or \"zscore\"")]
+pub struct UnknownNormalization;
+
+/// The rows from a CSV file, stored as-is until we know which columns a
+/// capability wants.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Table(pub Vec<StringRecord>);
+
+impl Table {
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let f = File::open(path).with_context(|| {
+            format!("Unable to open \"{}\"", path.display())
+        })?;
+
+        Table::from_reader(f)
+    }
+
+    pub fn from_reader(reader: impl Read) -> Result<Self, Error> {
+        let rows = csv::ReaderBuilder::default()
+            .has_headers(false)
+            .trim(csv::Trim::All)
+            .comment(Some(b'#'))
+            .from_reader(reader)
+            .into_records()
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Table(rows))
+    }
+
+    /// Guess whether the first row is a header by checking if it contains
+    /// anything that isn't a number.
+    fn looks_like_it_has_a_header(&self) -> bool {
+        self.0
+            .first()
+            .map(|row| row.iter().any(|field| field.parse::<f64>().is_err()))
+            .unwrap_or(false)
+    }
+}
+
+impl FromStr for Table {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Table::from_reader(s.as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TABLE: &str = "time,temp,humidity\n0,20,50\n1,22,40\n2,24,30\n";
+
+    #[test]
+    fn select_columns_by_name_and_index() {
+        let table: Table = TABLE.parse().unwrap();
+        let args = Arguments::from([("columns", "humidity, 1")]);
+
+        let got = tabular(&args, &table).unwrap();
+
+        assert_eq!(
+            got,
+            Tensor::new(&[50.0_f32, 20.0, 40.0, 22.0, 30.0, 24.0], &[3, 2])
+        );
+    }
+
+    #[test]
+    fn windows_of_integers() {
+        let table: Table = TABLE.parse().unwrap();
+        let args = Arguments::from([
+            ("columns", "temp"),
+            ("element_type", "i32"),
+            ("offset", "1"),
+            ("window", "2"),
+        ]);
+
+        let got = tabular(&args, &table).unwrap();
+
+        assert_eq!(got, Tensor::new(&[22_i32, 24], &[2, 1]));
+    }
+
+    #[test]
+    fn normalize_each_column() {
+        let table: Table = TABLE.parse().unwrap();
+        let args =
+            Arguments::from([("columns", "temp"), ("normalize", "minmax")]);
+
+        let got = tabular(&args, &table).unwrap();
+
+        assert_eq!(got, Tensor::new(&[0.0_f32, 0.5, 1.0], &[3, 1]));
+    }
+
+    #[test]
+    fn tables_without_a_header() {
+        let table: Table = "1,2\n3,4\n".parse().unwrap();
+
+        let got = tabular(&Arguments::from([]), &table).unwrap();
+
+        assert_eq!(got, Tensor::new(&[1.0_f32, 2.0, 3.0, 4.0], &[2, 2]));
+    }
+}
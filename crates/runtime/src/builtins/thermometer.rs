@@ -0,0 +1,120 @@
+use std::str::FromStr;
+
+use anyhow::Error;
+
+use crate::{
+    builtins::{
+        readings::{readings_tensor, SensorReadings},
+        Arguments,
+    },
+    Tensor,
+};
+
+/// Load an input tensor from a log of temperature (in degrees Celsius) and,
+/// optionally, relative humidity (as a percentage) readings.
+///
+/// The `"samples"` argument sets how many readings are provided (defaulting
+/// to a single reading) and `"unit"` is the [`TemperatureUnit`] the Rune
+/// wants temperatures in. If `"humidity"` is `true`, the result is a
+/// `f32[samples, 2]` tensor of `[temperature, humidity]` pairs, otherwise it
+/// is a `f32[samples, 1]` tensor of temperatures.
+pub fn thermometer(
+    args: &Arguments,
+    readings: &SensorReadings,
+) -> Result<Tensor, Error> {
+    let samples: usize = args.parse_or_default("samples", 1)?;
+    let unit: TemperatureUnit =
+        args.parse_or_default("unit", TemperatureUnit::default())?;
+    let humidity: bool = args.parse_or_default("humidity", false)?;
+
+    let columns = if humidity { 2 } else { 1 };
+    let readings = readings.take(samples, columns)?;
+
+    let mut values = Vec::with_capacity(samples * columns);
+
+    for reading in readings {
+        values.push(unit.convert(reading[0]));
+
+        if humidity {
+            values.push(reading[1]);
+        }
+    }
+
+    Ok(readings_tensor(values, samples, columns))
+}
+
+/// The unit temperatures are reported in.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TemperatureUnit {
+    Celsius,
+    Fahrenheit,
+    Kelvin,
+}
+
+impl TemperatureUnit {
+    /// Convert a temperature in degrees Celsius to this unit.
+    pub fn convert(self, celsius: f32) -> f32 {
+        match self {
+            TemperatureUnit::Celsius => celsius,
+            TemperatureUnit::Fahrenheit => celsius * 9.0 / 5.0 + 32.0,
+            TemperatureUnit::Kelvin => celsius + 273.15,
+        }
+    }
+}
+
+impl Default for TemperatureUnit {
+    fn default() -> Self { TemperatureUnit::Celsius }
+}
+
+impl FromStr for TemperatureUnit {
+    type Err = UnknownTemperatureUnit;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "c" | "C" | "celsius" => Ok(TemperatureUnit::Celsius),
+            "f" | "F" | "fahrenheit" => Ok(TemperatureUnit::Fahrenheit),
+            "k" | "K" | "kelvin" => Ok(TemperatureUnit::Kelvin),
+            _ => Err(UnknownTemperatureUnit),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, thiserror::Error)]
+#[error("Expected one of \"celsius\", \"fahrenheit\", or \"kelvin\"")]
+pub struct UnknownTemperatureUnit;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_single_reading_in_fahrenheit() {
+        let readings: SensorReadings = "100,50\n0,10\n".parse().unwrap();
+
+        let got =
+            thermometer(&Arguments::from([("unit", "fahrenheit")]), &readings)
+                .unwrap();
+
+        assert_eq!(got, Tensor::new(&[212.0_f32], &[1, 1]));
+    }
+
+    #[test]
+    fn a_time_series_with_humidity() {
+        let readings: SensorReadings = "20,50\n21,55\n".parse().unwrap();
+        let args = Arguments::from([("samples", "2"), ("humidity", "true")]);
+
+        let got = thermometer(&args, &readings).unwrap();
+
+        assert_eq!(got, Tensor::new(&[20.0_f32, 50.0, 21.0, 55.0], &[2, 2]));
+    }
+
+    #[test]
+    fn humidity_needs_a_second_column() {
+        let readings: SensorReadings = "20\n".parse().unwrap();
+
+        let got =
+            thermometer(&Arguments::from([("humidity", "true")]), &readings);
+
+        assert!(got.is_err());
+    }
+}
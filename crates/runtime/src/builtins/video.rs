@@ -0,0 +1,269 @@
+use std::{
+    fmt::{self, Debug, Formatter},
+    fs::File,
+    io::{BufRead, BufReader, Read},
+    num::NonZeroUsize,
+    ops::Deref,
+    path::Path,
+    process::{Command, Stdio},
+};
+
+use anyhow::{Context, Error};
+use image::{
+    codecs::gif::GifDecoder, AnimationDecoder, DynamicImage, RgbImage,
+};
+
+use crate::{
+    builtins::{
+        image::{pixels, PixelFormat, Resize},
+        Arguments,
+    },
+    Tensor,
+};
+
+/// Load an input tensor from a sequence of video frames.
+///
+/// Each frame is resized to the `"width"` and `"height"` arguments and
+/// converted to the `"pixel_format"` the same way as
+/// [`crate::builtins::image()`], then the first `"frames"` frames
+/// (defaulting to all of them) are stacked into a
+/// `[frames, height, width, channels]` tensor.
+pub fn video(args: &Arguments, clip: &VideoClip) -> Result<Tensor, Error> {
+    let width: u32 = args.parse("width")?;
+    let height: u32 = args.parse("height")?;
+    let pixel_format: PixelFormat =
+        args.parse_or_default("pixel_format", PixelFormat::RGB8)?;
+    let frames: usize = args.parse_or_default("frames", clip.len())?;
+    pixel_format.check_dimensions(width, height)?;
+    let resize = Resize::from_args(args)?;
+
+    anyhow::ensure!(frames > 0, "At least one frame must be requested");
+    anyhow::ensure!(
+        frames <= clip.len(),
+        "{} frames were requested but the video only has {}",
+        frames,
+        clip.len()
+    );
+
+    let mut buffer = Vec::new();
+
+    for frame in &clip[..frames] {
+        buffer.extend(pixels(frame, width, height, pixel_format, resize));
+    }
+
+    let dimensions = [
+        frames,
+        pixel_format.rows(height) as usize,
+        width as usize,
+        pixel_format.channels(),
+    ]
+    .iter()
+    .map(|&d| NonZeroUsize::new(d).context("Dimensions must be non-zero"))
+    .collect::<Result<Vec<_>, Error>>()?;
+
+    Ok(Tensor::new_raw(pixel_format.element_type(), dimensions, buffer))
+}
+
+/// The decoded frames from a video.
+#[derive(Default, Clone)]
+pub struct VideoClip(pub Vec<DynamicImage>);
+
+impl VideoClip {
+    /// Decode up to `max_frames` frames from a video file.
+    ///
+    /// Animated GIFs are decoded natively, while every other format is
+    /// decoded by running the `ffmpeg` executable, which must be on the
+    /// `$PATH`.
+    pub fn from_file(
+        path: impl AsRef<Path>,
+        max_frames: Option<usize>,
+    ) -> Result<Self, Error> {
+        let path = path.as_ref();
+
+        let is_gif = path
+            .extension()
+            .map(|ext| ext.eq_ignore_ascii_case("gif"))
+            .unwrap_or(false);
+
+        if is_gif {
+            let f = File::open(path).with_context(|| {
+                format!("Unable to open \"{}\"", path.display())
+            })?;
+            VideoClip::from_gif(BufReader::new(f), max_frames)
+        } else {
+            VideoClip::from_ffmpeg(path, max_frames)
+        }
+    }
+
+    /// Decode the frames from an animated GIF.
+    pub fn from_gif(
+        reader: impl Read,
+        max_frames: Option<usize>,
+    ) -> Result<Self, Error> {
+        let decoder =
+            GifDecoder::new(reader).context("Unable to read the GIF")?;
+
+        let frames = decoder
+            .into_frames()
+            .take(max_frames.unwrap_or(usize::MAX))
+            .map(|frame| {
+                frame.map(|f| DynamicImage::ImageRgba8(f.into_buffer()))
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .context("Unable to decode the GIF's frames")?;
+
+        Ok(VideoClip(frames))
+    }
+
+    /// Use `ffmpeg` to decode a video, streaming the frames back as binary
+    /// PPM images.
+    pub fn from_ffmpeg(
+        path: impl AsRef<Path>,
+        max_frames: Option<usize>,
+    ) -> Result<Self, Error> {
+        let path = path.as_ref();
+
+        let mut cmd = Command::new("ffmpeg");
+        cmd.arg("-loglevel").arg("error").arg("-i").arg(path);
+        if let Some(max_frames) = max_frames {
+            cmd.arg("-frames:v").arg(max_frames.to_string());
+        }
+        cmd.args(&["-f", "image2pipe", "-vcodec", "ppm", "-"])
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped());
+
+        log::debug!("Decoding \"{}\" with {:?}", path.display(), cmd);
+
+        let mut child = cmd
+            .spawn()
+            .context("Unable to start ffmpeg. Is it installed?")?;
+        let stdout = child.stdout.take().expect("stdout is piped");
+        let clip = VideoClip::from_ppm_stream(BufReader::new(stdout));
+
+        let status = child.wait().context("Unable to wait for ffmpeg")?;
+        anyhow::ensure!(
+            status.success(),
+            "ffmpeg was unable to decode \"{}\" ({})",
+            path.display(),
+            status
+        );
+
+        clip
+    }
+
+    /// Parse a sequence of back-to-back binary PPM (`P6`) images.
+    pub fn from_ppm_stream(mut reader: impl BufRead) -> Result<Self, Error> {
+        let mut frames = Vec::new();
+
+        while !reader.fill_buf()?.is_empty() {
+            let frame = read_ppm(&mut reader).with_context(|| {
+                format!("Unable to read frame {}", frames.len())
+            })?;
+            frames.push(DynamicImage::ImageRgb8(frame));
+        }
+
+        Ok(VideoClip(frames))
+    }
+}
+
+impl Debug for VideoClip {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("VideoClip")
+            .field("frames", &self.len())
+            .finish()
+    }
+}
+
+impl Deref for VideoClip {
+    type Target = [DynamicImage];
+
+    fn deref(&self) -> &Self::Target { &self.0 }
+}
+
+fn read_ppm(reader: &mut impl BufRead) -> Result<RgbImage, Error> {
+    let magic = read_token(reader)?;
+    anyhow::ensure!(magic == "P6", "Expected a binary PPM, found {:?}", magic);
+
+    let width: u32 = read_token(reader)?.parse()?;
+    let height: u32 = read_token(reader)?.parse()?;
+    let max_value: u32 = read_token(reader)?.parse()?;
+    anyhow::ensure!(
+        max_value == 255,
+        "Only 8-bit PPM images are supported, not {}",
+        max_value
+    );
+
+    let mut pixels = vec![0; width as usize * height as usize * 3];
+    reader.read_exact(&mut pixels)?;
+
+    RgbImage::from_raw(width, height, pixels)
+        .context("The frame's buffer is the wrong size")
+}
+
+/// Read a whitespace-delimited token from a PPM header, consuming exactly
+/// one whitespace character after it.
+fn read_token(reader: &mut impl BufRead) -> Result<String, Error> {
+    let mut token = String::new();
+    let mut byte = [0_u8];
+
+    loop {
+        reader.read_exact(&mut byte)?;
+
+        match byte[0] {
+            b'#' if token.is_empty() => {
+                let mut comment = Vec::new();
+                reader.read_until(b'\n', &mut comment)?;
+            },
+            b if b.is_ascii_whitespace() => {
+                if !token.is_empty() {
+                    return Ok(token);
+                }
+            },
+            b => token.push(b as char),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ppm(width: u32, height: u32, value: u8) -> Vec<u8> {
+        let header = format!("P6\n{} {}\n255\n", width, height);
+        let mut buffer = header.into_bytes();
+        buffer.extend(vec![value; (width * height * 3) as usize]);
+        buffer
+    }
+
+    #[test]
+    fn parse_a_ppm_stream() {
+        let mut stream = ppm(2, 1, 10);
+        stream.extend(ppm(2, 1, 20));
+
+        let clip = VideoClip::from_ppm_stream(stream.as_slice()).unwrap();
+
+        assert_eq!(clip.len(), 2);
+        assert_eq!(clip[0].as_bytes(), &[10; 6]);
+        assert_eq!(clip[1].as_bytes(), &[20; 6]);
+    }
+
+    #[test]
+    fn stack_frames_into_a_tensor() {
+        let stream = [ppm(4, 4, 1), ppm(4, 4, 2), ppm(4, 4, 3)].concat();
+        let clip = VideoClip::from_ppm_stream(stream.as_slice()).unwrap();
+        let args = Arguments::from([
+            ("width", "4"),
+            ("height", "4"),
+            ("frames", "2"),
+            ("pixel_format", "@PixelFormat::GrayScale"),
+        ]);
+
+        let got = video(&args, &clip).unwrap();
+
+        let dimensions: Vec<usize> =
+            got.dimensions().iter().map(|d| d.get()).collect();
+        assert_eq!(dimensions, vec![2, 4, 4, 1]);
+        assert_eq!(&got.buffer()[..16], &[1; 16]);
+        assert_eq!(&got.buffer()[16..], &[2; 16]);
+    }
+}
@@ -1,9 +1,23 @@
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::Read,
+    time::{Duration, SystemTime},
+};
 
 use anyhow::Error;
 use hotg_rune_core::Shape;
 use log::Record;
 
+use crate::{files::FileMode, metrics::Stage};
+
+/// A stream of data which a Rune can pull from a capability incrementally.
+///
+/// Reads may return fewer bytes than requested (e.g. when only a handful of
+/// audio samples are available) and returning `0` means the stream has
+/// finished.
+pub type CapabilityStream = Box<dyn Read + Send + Sync>;
+
 pub(crate) trait Callbacks: Send + Sync + 'static {
     /// A callback fired after a Rune is loaded.
     fn loaded(&self, _rune: &RuneGraph<'_>) -> Result<(), Error>;
@@ -15,6 +29,31 @@ pub(crate) trait Callbacks: Send + Sync + 'static {
         buffer: &mut [u8],
     ) -> Result<usize, Error>;
 
+    /// Open a stream the Rune can pull a capability's data from
+    /// incrementally.
+    fn open_capability_stream(
+        &self,
+        _id: u32,
+        meta: &NodeMetadata,
+    ) -> Result<CapabilityStream, Error> {
+        anyhow::bail!(
+            "The \"{}\" capability doesn't support streaming",
+            meta.kind
+        )
+    }
+
+    /// Read the next chunk of data from a stream opened with
+    /// [`Callbacks::open_capability_stream()`].
+    fn read_capability_stream(
+        &self,
+        _id: u32,
+        _meta: &NodeMetadata,
+        stream: &mut CapabilityStream,
+        buffer: &mut [u8],
+    ) -> Result<usize, Error> {
+        stream.read(buffer).map_err(Error::from)
+    }
+
     fn write_output(
         &self,
         id: u32,
@@ -36,7 +75,64 @@ pub(crate) trait Callbacks: Send + Sync + 'static {
     /// Get the value of a global resource.
     fn get_resource(&self, name: &str) -> Option<&[u8]>;
 
+    /// Get the file for a model which isn't embedded in the Rune and should
+    /// be provided by the host.
+    fn get_model(&self, _name: &str) -> Option<&[u8]> { None }
+
+    /// Get the WebAssembly module for a proc block which isn't compiled into
+    /// the Rune and should be provided by the host.
+    fn get_proc_block(&self, _name: &str) -> Option<&[u8]> { None }
+
+    /// Instantiate a standalone proc block.
+    ///
+    /// The default implementation uses
+    /// [`crate::proc_blocks::default_proc_block_handler()`], which needs the
+    /// `wasmer` feature.
+    fn load_proc_block(
+        &self,
+        id: u32,
+        name: &str,
+        wasm: &[u8],
+    ) -> Result<Box<dyn ProcBlock>, Error> {
+        crate::proc_blocks::default_proc_block_handler(id, name, wasm)
+    }
+
+    /// Decrypt a model which was embedded in the Rune in encrypted form.
+    fn decrypt_model(
+        &self,
+        name: &str,
+        _encrypted: &[u8],
+    ) -> Result<Vec<u8>, Error> {
+        anyhow::bail!("The host doesn't know how to decrypt \"{}\"", name)
+    }
+
+    /// Open a file on the host's filesystem.
+    ///
+    /// Implementations must make sure the Rune has been given permission to
+    /// access this file.
+    fn open_file(&self, path: &str, _mode: FileMode) -> Result<File, Error> {
+        anyhow::bail!("The Rune isn't allowed to access \"{}\"", path)
+    }
+
     fn log(&self, _record: &Record<'_>);
+
+    /// The current wall-clock time.
+    fn now(&self) -> SystemTime { SystemTime::now() }
+
+    /// The number of nanoseconds elapsed since some fixed point in time
+    /// (typically when the Rune was loaded).
+    ///
+    /// Unlike [`Callbacks::now()`], this is guaranteed to never go backwards.
+    fn monotonic_ns(&self) -> u64;
+
+    /// Has the host asked for the current call to be cancelled?
+    fn is_cancelled(&self) -> bool { false }
+
+    /// Record how long a host call took.
+    fn record_timing(&self, _stage: Stage, _id: u32, _elapsed: Duration) {}
+
+    /// Record how long one of the pipeline's stages took to run.
+    fn record_stage_timing(&self, _stage: &str, _elapsed: Duration) {}
 }
 
 /// Metadata for a node in the ML pipeline, typically an input or output.
@@ -84,4 +180,34 @@ pub trait Model: Send + Sync + 'static {
 
     fn input_shapes(&self) -> &[Shape<'_>];
     fn output_shapes(&self) -> &[Shape<'_>];
+
+    /// Prepare the model for inputs with these shapes, for models whose input
+    /// dimensions are only known at runtime (e.g. variable-length audio).
+    ///
+    /// By default, only the shapes the model was loaded with are accepted.
+    fn resize_inputs(&mut self, shapes: &[Shape<'_>]) -> Result<(), Error> {
+        anyhow::ensure!(
+            shapes == self.input_shapes(),
+            "The model expected inputs with shapes {:?}, but found {:?}, and \
+             it doesn't support dynamic shapes",
+            self.input_shapes(),
+            shapes,
+        );
+
+        Ok(())
+    }
+}
+
+/// A proc block which was compiled to a standalone WebAssembly module and is
+/// run by the host.
+pub trait ProcBlock: Send + Sync + 'static {
+    /// Set one of the proc block's parameters.
+    fn set_parameter(&mut self, key: &str, value: &str) -> Result<(), Error>;
+
+    /// Transform some tensors, where both the inputs and outputs are encoded
+    /// using [`hotg_rune_core::encoding`].
+    fn transform(&mut self, inputs: &[u8]) -> Result<Vec<u8>, Error>;
+
+    /// Clear any state accumulated by previous transforms.
+    fn reset(&mut self) -> Result<(), Error> { Ok(()) }
 }
@@ -0,0 +1,53 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+/// A handle which can be used to cancel a [`crate::Runtime::predict()`] call
+/// from another thread (e.g. when the user navigates away from a screen).
+///
+/// The Rune is stopped the next time it calls into the host (starting a
+/// pipeline stage, reading from a capability, running a model, writing to an
+/// output, etc.). None of the WebAssembly engines we use support epoch
+/// interruption, so a proc-block stuck in a tight loop won't be interrupted.
+/// The call will fail with a [`Cancelled`] error and
+/// [`crate::Runtime::metrics()`] will contain timings for everything that ran
+/// before the Rune was cancelled.
+///
+/// Cancelling when no call is in progress stops the next
+/// [`crate::Runtime::predict()`] call instead.
+#[derive(Debug, Default, Clone)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self { CancellationToken::default() }
+
+    /// Ask the in-flight call to stop.
+    pub fn cancel(&self) { self.0.store(true, Ordering::SeqCst); }
+
+    pub fn is_cancelled(&self) -> bool { self.0.load(Ordering::SeqCst) }
+
+    pub(crate) fn reset(&self) { self.0.store(false, Ordering::SeqCst); }
+}
+
+/// The error returned when a Rune is stopped using a [`CancellationToken`].
+#[derive(Debug, Copy, Clone, PartialEq, thiserror::Error)]
+#[error("The Rune was cancelled")]
+pub struct Cancelled;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clones_share_the_same_flag() {
+        let token = CancellationToken::new();
+        let clone = token.clone();
+
+        clone.cancel();
+        assert!(token.is_cancelled());
+
+        token.reset();
+        assert!(!clone.is_cancelled());
+    }
+}
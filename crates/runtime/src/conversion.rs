@@ -0,0 +1,422 @@
+//! Converting a [`Tensor`] from one [`ElementType`] to another.
+//!
+//! Capabilities produce whatever is most natural for their data (e.g. `u8`
+//! pixels or `i16` audio samples), which isn't always what a Rune's model was
+//! trained on. These conversions let the runtime bridge that gap instead of
+//! every Rune needing a proc block to do it.
+
+use alloc::vec::Vec;
+use core::{
+    convert::TryInto,
+    fmt::{self, Display, Formatter},
+};
+
+use hotg_rune_core::Quantization;
+
+use crate::{BufferPool, ElementType, Tensor, TensorViewMut, ViewError};
+
+/// How values are mapped from one [`ElementType`] to another.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Conversion {
+    /// Cast each value, clamping anything that doesn't fit into the new
+    /// type's range (`NaN` becomes `0`).
+    Saturate,
+    /// Map an integer type's full range onto `[0, 1]` (unsigned) or
+    /// `[-1, 1]` (signed) floats, or scale floats in that range back up to
+    /// an integer type.
+    Normalize,
+    /// Quantize floats using `q = round(x / scale) + zero_point`, or
+    /// dequantize integers using `x = scale * (q - zero_point)`.
+    Quantize { scale: f32, zero_point: i32 },
+}
+
+impl Conversion {
+    fn check(
+        self,
+        from: ElementType,
+        to: ElementType,
+    ) -> Result<(), ConversionError> {
+        let supported = match self {
+            Conversion::Saturate => true,
+            Conversion::Normalize | Conversion::Quantize { .. } => {
+                is_float(from) != is_float(to)
+            },
+        };
+
+        if !supported {
+            return Err(ConversionError::Unsupported {
+                from,
+                to,
+                conversion: self,
+            });
+        }
+
+        match self {
+            Conversion::Quantize { scale, .. }
+                if !(scale.is_finite() && scale > 0.0) =>
+            {
+                Err(ConversionError::InvalidScale { scale })
+            },
+            _ => Ok(()),
+        }
+    }
+
+    fn apply(
+        self,
+        value: Value,
+        from: ElementType,
+        to: ElementType,
+    ) -> Value {
+        // Note: f64::round() needs std, so rounding is done with libm
+        match (self, value) {
+            (Conversion::Saturate, value) => value,
+            (Conversion::Normalize, Value::Integer(i)) => {
+                Value::Float((i as f64 / max(from)).max(-1.0))
+            },
+            (Conversion::Normalize, Value::Float(f)) => {
+                Value::Float(libm::round(f * max(to)))
+            },
+            (Conversion::Quantize { scale, zero_point }, Value::Integer(q)) => {
+                let q = (q - i128::from(zero_point)) as f64;
+                Value::Float(f64::from(scale) * q)
+            },
+            (Conversion::Quantize { scale, zero_point }, Value::Float(x)) => {
+                let q = libm::round(x / f64::from(scale));
+                Value::Float(q + f64::from(zero_point))
+            },
+        }
+    }
+}
+
+impl Default for Conversion {
+    fn default() -> Self { Conversion::Saturate }
+}
+
+impl Display for Conversion {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Conversion::Saturate => write!(f, "saturate"),
+            Conversion::Normalize => write!(f, "normalize"),
+            Conversion::Quantize { .. } => write!(f, "quantize"),
+        }
+    }
+}
+
+/// Convert a tensor's elements to another [`ElementType`].
+///
+/// Quantizing attaches the [`Quantization`] parameters to the result so
+/// whoever reads it can recover the original values.
+pub fn convert(
+    tensor: &Tensor,
+    to: ElementType,
+    conversion: Conversion,
+) -> Result<Tensor, ConversionError> {
+    let count = tensor.buffer().len() / tensor.element_type().byte_size();
+    let buffer = Vec::with_capacity(count * to.byte_size());
+
+    convert_into(tensor, to, conversion, buffer)
+}
+
+/// Like [`convert()`], except the result reuses a buffer from the
+/// [`BufferPool`].
+pub fn convert_pooled(
+    tensor: &Tensor,
+    to: ElementType,
+    conversion: Conversion,
+    pool: &mut BufferPool,
+) -> Result<Tensor, ConversionError> {
+    let from = tensor.element_type();
+    conversion.check(from, to)?;
+
+    let count = tensor.buffer().len() / from.byte_size();
+    let buffer = pool.acquire(count * to.byte_size());
+
+    convert_into(tensor, to, conversion, buffer)
+}
+
+fn convert_into(
+    tensor: &Tensor,
+    to: ElementType,
+    conversion: Conversion,
+    mut buffer: Vec<u8>,
+) -> Result<Tensor, ConversionError> {
+    let from = tensor.element_type();
+    conversion.check(from, to)?;
+    buffer.clear();
+
+    for bytes in tensor.buffer().chunks_exact(from.byte_size()) {
+        let value = conversion.apply(read(from, bytes), from, to);
+        write(to, value, &mut buffer);
+    }
+
+    let converted = Tensor::new_raw(to, tensor.dimensions().to_vec(), buffer);
+
+    match conversion {
+        Conversion::Quantize { scale, zero_point } if !is_float(to) => Ok(
+            converted
+                .with_quantization(Quantization::per_tensor(scale, zero_point)),
+        ),
+        _ => Ok(converted),
+    }
+}
+
+/// The reasons a [`convert()`] may fail.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ConversionError {
+    /// The conversion doesn't make sense for these element types (e.g.
+    /// normalizing one float type into another).
+    Unsupported {
+        from: ElementType,
+        to: ElementType,
+        conversion: Conversion,
+    },
+    /// The quantization scale must be a positive, finite number.
+    InvalidScale { scale: f32 },
+    /// The converted elements couldn't be written to the destination buffer.
+    View(ViewError),
+}
+
+impl Display for ConversionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ConversionError::Unsupported {
+                from,
+                to,
+                conversion,
+            } => write!(
+                f,
+                "Unable to {} {} elements into {}, one type must be an \
+                 integer and the other a float",
+                conversion, from, to
+            ),
+            ConversionError::InvalidScale { scale } => write!(
+                f,
+                "The quantization scale must be a positive number, not {}",
+                scale
+            ),
+            ConversionError::View(e) => Display::fmt(e, f),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ConversionError {}
+
+/// An element, widened so any element type can be represented without
+/// losing information.
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Value {
+    Integer(i128),
+    Float(f64),
+}
+
+macro_rules! for_each_type {
+    ($element_type:expr, |$ty:ident| $body:expr) => {
+        match $element_type {
+            ElementType::U8 => { type $ty = u8; $body },
+            ElementType::I8 => { type $ty = i8; $body },
+            ElementType::U16 => { type $ty = u16; $body },
+            ElementType::I16 => { type $ty = i16; $body },
+            ElementType::U32 => { type $ty = u32; $body },
+            ElementType::I32 => { type $ty = i32; $body },
+            ElementType::F32 => { type $ty = f32; $body },
+            ElementType::U64 => { type $ty = u64; $body },
+            ElementType::I64 => { type $ty = i64; $body },
+            ElementType::F64 => { type $ty = f64; $body },
+        }
+    };
+}
+
+/// Cast a [`Value`] to `$ty`, which is the Rust type for `$element_type`.
+macro_rules! cast {
+    ($ty:ty, $element_type:expr, $value:expr) => {
+        // Note: float-to-int casts saturate (with NaN becoming 0), so only
+        // integers need to be clamped by hand.
+        match $value {
+            Value::Float(f) => f as $ty,
+            Value::Integer(i) if is_float($element_type) => i as $ty,
+            Value::Integer(i) => {
+                i.clamp(<$ty>::MIN as i128, <$ty>::MAX as i128) as $ty
+            },
+        }
+    };
+}
+
+fn is_float(element_type: ElementType) -> bool {
+    matches!(element_type, ElementType::F32 | ElementType::F64)
+}
+
+/// The largest value an element type can hold.
+fn max(element_type: ElementType) -> f64 {
+    for_each_type!(element_type, |T| T::MAX as f64)
+}
+
+fn read(element_type: ElementType, bytes: &[u8]) -> Value {
+    for_each_type!(element_type, |T| {
+        let value = T::from_ne_bytes(bytes.try_into().unwrap());
+
+        if is_float(element_type) {
+            Value::Float(value as f64)
+        } else {
+            Value::Integer(value as i128)
+        }
+    })
+}
+
+fn write(element_type: ElementType, value: Value, buffer: &mut Vec<u8>) {
+    for_each_type!(element_type, |T| {
+        let value = cast!(T, element_type, value);
+        buffer.extend_from_slice(&value.to_ne_bytes());
+    })
+}
+
+/// Like [`convert()`], except the elements are written directly into
+/// `destination` (e.g. the buffer a Rune provided for a capability) through a
+/// [`TensorViewMut`] instead of allocating a new [`Tensor`].
+///
+/// This fails with [`ConversionError::View`] if `destination` isn't aligned
+/// for `to` or is the wrong size.
+pub fn convert_into_buffer(
+    tensor: &Tensor,
+    to: ElementType,
+    conversion: Conversion,
+    destination: &mut [u8],
+) -> Result<(), ConversionError> {
+    let from = tensor.element_type();
+    conversion.check(from, to)?;
+
+    let dimensions: Vec<usize> =
+        tensor.dimensions().iter().map(|d| d.get()).collect();
+
+    for_each_type!(to, |T| {
+        let mut view = TensorViewMut::<T>::new(destination, &dimensions)
+            .map_err(ConversionError::View)?;
+        let elements = tensor.buffer().chunks_exact(from.byte_size());
+
+        for (dest, bytes) in view.elements_mut().iter_mut().zip(elements) {
+            let value = conversion.apply(read(from, bytes), from, to);
+            *dest = cast!(T, to, value);
+        }
+    });
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn convert_into_an_existing_buffer() {
+        let pixels = Tensor::new(&[0_u8, 51, 255], &[1, 3]);
+        let mut floats = [0.0_f32; 3];
+        let destination = unsafe {
+            core::slice::from_raw_parts_mut(floats.as_mut_ptr().cast(), 12)
+        };
+
+        convert_into_buffer(
+            &pixels,
+            ElementType::F32,
+            Conversion::Normalize,
+            destination,
+        )
+        .unwrap();
+
+        assert_eq!(floats, [0.0, 0.2, 1.0]);
+        assert_eq!(
+            convert_into_buffer(
+                &pixels,
+                ElementType::F32,
+                Conversion::Normalize,
+                &mut [0; 8],
+            ),
+            Err(ConversionError::View(ViewError::LengthMismatch {
+                expected: 12,
+                found: 8,
+            }))
+        );
+    }
+
+    #[test]
+    fn normalize_pixels() {
+        let pixels = Tensor::new(&[0_u8, 51, 255], &[1, 3]);
+
+        let got = convert(&pixels, ElementType::F32, Conversion::Normalize)
+            .unwrap();
+
+        assert_eq!(got, Tensor::new(&[0.0_f32, 0.2, 1.0], &[1, 3]));
+    }
+
+    #[test]
+    fn normalize_signed_samples() {
+        let samples = Tensor::new(&[i16::MIN, 0, i16::MAX], &[3]);
+
+        let got =
+            convert(&samples, ElementType::F64, Conversion::Normalize).unwrap();
+
+        assert_eq!(got, Tensor::new(&[-1.0_f64, 0.0, 1.0], &[3]));
+
+        let round_trip =
+            convert(&got, ElementType::I16, Conversion::Normalize).unwrap();
+        assert_eq!(round_trip, Tensor::new(&[-i16::MAX, 0, i16::MAX], &[3]));
+    }
+
+    #[test]
+    fn quantize_floats() {
+        let conversion = Conversion::Quantize {
+            scale: 0.5,
+            zero_point: -10,
+        };
+        let tensor = Tensor::new(&[0.0_f32, 1.0, -100.0, 1000.0], &[4]);
+
+        let got = convert(&tensor, ElementType::I8, conversion).unwrap();
+
+        let should_be = Tensor::new(&[-10_i8, -8, -128, 127], &[4])
+            .with_quantization(Quantization::per_tensor(0.5, -10));
+        assert_eq!(got, should_be);
+
+        let dequantized = convert(&got, ElementType::F32, conversion).unwrap();
+        assert_eq!(
+            dequantized,
+            Tensor::new(&[0.0_f32, 1.0, -59.0, 68.5], &[4])
+        );
+    }
+
+    #[test]
+    fn saturating_casts() {
+        let tensor = Tensor::new(&[-1.5_f32, 3.7, 300.0, f32::NAN], &[4]);
+
+        let got =
+            convert(&tensor, ElementType::U8, Conversion::Saturate).unwrap();
+        assert_eq!(got, Tensor::new(&[0_u8, 3, 255, 0], &[4]));
+
+        let tensor = Tensor::new(&[-70_000_i32, 5, u16::MAX as i32 + 1], &[3]);
+        let got =
+            convert(&tensor, ElementType::U16, Conversion::Saturate).unwrap();
+        assert_eq!(got, Tensor::new(&[0_u16, 5, u16::MAX], &[3]));
+    }
+
+    #[test]
+    fn reject_conversions_that_dont_make_sense() {
+        let tensor = Tensor::new(&[1_u8, 2, 3], &[3]);
+
+        assert_eq!(
+            convert(&tensor, ElementType::I32, Conversion::Normalize)
+                .unwrap_err(),
+            ConversionError::Unsupported {
+                from: ElementType::U8,
+                to: ElementType::I32,
+                conversion: Conversion::Normalize,
+            }
+        );
+
+        let conversion = Conversion::Quantize {
+            scale: 0.0,
+            zero_point: 0,
+        };
+        assert_eq!(
+            convert(&tensor, ElementType::F32, conversion).unwrap_err(),
+            ConversionError::InvalidScale { scale: 0.0 }
+        );
+    }
+}
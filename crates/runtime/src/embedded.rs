@@ -0,0 +1,1007 @@
+//! A `#![no_std]` engine for running Runes on microcontrollers, built on
+//! the [wasmi](https://crates.io/crates/wasmi) interpreter.
+//!
+//! Unlike [`Runtime`][crate::Runtime], which knows how to read capabilities,
+//! run models, and send outputs itself, everything hardware-specific is
+//! delegated to a [`Host`]. Only the host functions used by a Rune's pipeline
+//! are provided (capabilities, models, outputs, and logging), so Runes that
+//! use files, resources, capability streams, or standalone proc blocks will
+//! fail to load.
+//!
+//! Tensors are copied between the Rune's linear memory and buffers from a
+//! [`BufferPool`], so creating the engine with
+//! [`BufferPool::with_static_buffers()`] means no tensors are allocated while
+//! the Rune is running.
+
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{
+    convert::TryFrom,
+    fmt::{self, Display, Formatter},
+};
+
+use hotg_rune_core::{Shape, Type, Value};
+use log::Level;
+use wasmi::{
+    Externals, FuncInstance, FuncRef, ImportsBuilder, MemoryRef, Module,
+    ModuleImportResolver, ModuleInstance, ModuleRef, RuntimeArgs, RuntimeValue,
+    Signature, Trap, ValueType,
+};
+
+use crate::BufferPool;
+
+/// Everything hardware-specific an [`EmbeddedRuntime`] needs.
+pub trait Host {
+    /// Fill `buffer` with the next input from a capability, returning the
+    /// number of bytes written.
+    fn read_capability(
+        &mut self,
+        capability: &Capability,
+        buffer: &mut [u8],
+    ) -> Result<usize, Error>;
+
+    /// Load a model so it can be used by [`Host::infer()`] later on.
+    fn load_model(
+        &mut self,
+        model: &ModelInfo,
+        data: &[u8],
+    ) -> Result<(), Error>;
+
+    /// Prepare a model for inputs with different dimensions to the ones it
+    /// was loaded with (e.g. a bigger batch), returning the new output
+    /// shapes.
+    fn resize_inputs(
+        &mut self,
+        model: &ModelInfo,
+        _shapes: &[Shape<'static>],
+    ) -> Result<Vec<Shape<'static>>, Error> {
+        Err(Error::Host(format!(
+            "Model {} doesn't support resizing its inputs",
+            model.id
+        )))
+    }
+
+    /// Run inference on a model that was previously loaded.
+    fn infer(
+        &mut self,
+        model: &ModelInfo,
+        inputs: &[&[u8]],
+        outputs: &mut [&mut [u8]],
+    ) -> Result<(), Error>;
+
+    /// Send a serialized result to an output.
+    fn write_output(
+        &mut self,
+        output: &Output,
+        data: &[u8],
+    ) -> Result<(), Error>;
+}
+
+/// A capability requested by the Rune.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Capability {
+    pub id: u32,
+    /// The capability's name (e.g. `"SOUND"`).
+    pub kind: &'static str,
+    /// Parameters set in the Runefile, stringified.
+    pub parameters: Vec<(String, String)>,
+}
+
+impl Capability {
+    pub fn parameter(&self, key: &str) -> Option<&str> {
+        self.parameters
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// The shape of the tensor the Rune expects this capability to produce.
+    pub fn shape(&self) -> Option<Shape<'static>> {
+        self.parameter("shape")?.parse().ok()
+    }
+}
+
+/// A model loaded by the Rune.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelInfo {
+    pub id: u32,
+    pub mimetype: String,
+    pub inputs: Vec<Shape<'static>>,
+    pub outputs: Vec<Shape<'static>>,
+}
+
+/// An output requested by the Rune.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Output {
+    pub id: u32,
+    /// The output's name (e.g. `"SERIAL"`).
+    pub kind: &'static str,
+}
+
+/// A Rune, running inside the wasmi interpreter.
+pub struct EmbeddedRuntime<H> {
+    instance: ModuleRef,
+    state: State<H>,
+}
+
+impl<H: Host> EmbeddedRuntime<H> {
+    /// Load a Rune using a [`BufferPool`] with the default settings.
+    pub fn load(wasm: &[u8], host: H) -> Result<Self, Error> {
+        EmbeddedRuntime::with_buffer_pool(wasm, host, BufferPool::new())
+    }
+
+    /// Load a Rune and initialize its pipeline.
+    pub fn with_buffer_pool(
+        wasm: &[u8],
+        host: H,
+        pool: BufferPool,
+    ) -> Result<Self, Error> {
+        let module = Module::from_buffer(wasm)?;
+        let imports = ImportsBuilder::new().with_resolver("env", &Resolver);
+        let not_started = ModuleInstance::new(&module, &imports)?;
+
+        let memory = not_started
+            .not_started_instance()
+            .export_by_name("memory")
+            .and_then(|m| m.as_memory().cloned())
+            .ok_or(Error::MissingMemory)?;
+
+        let mut state = State {
+            host,
+            memory: Memory(memory),
+            pool,
+            capabilities: Vec::new(),
+            models: Vec::new(),
+            outputs: Vec::new(),
+        };
+
+        let instance = not_started
+            .run_start(&mut state)
+            .map_err(Error::from_trap)?;
+
+        let mut runtime = EmbeddedRuntime { instance, state };
+        runtime.call("_manifest", &[])?;
+
+        Ok(runtime)
+    }
+
+    /// Run the Rune's pipeline once.
+    pub fn predict(&mut self) -> Result<(), Error> {
+        let args = [RuntimeValue::I32(0); 3];
+        self.call("_call", &args)?;
+        Ok(())
+    }
+
+    /// Clear any state held by the Rune's proc blocks.
+    pub fn reset(&mut self) -> Result<(), Error> {
+        self.call("_reset", &[])?;
+        Ok(())
+    }
+
+    pub fn capabilities(&self) -> &[Capability] { &self.state.capabilities }
+
+    pub fn models(&self) -> &[ModelInfo] { &self.state.models }
+
+    pub fn outputs(&self) -> &[Output] { &self.state.outputs }
+
+    pub fn host(&self) -> &H { &self.state.host }
+
+    pub fn host_mut(&mut self) -> &mut H { &mut self.state.host }
+
+    pub fn buffer_pool(&self) -> &BufferPool { &self.state.pool }
+
+    fn call(
+        &mut self,
+        name: &str,
+        args: &[RuntimeValue],
+    ) -> Result<Option<RuntimeValue>, Error> {
+        self.instance
+            .invoke_export(name, args, &mut self.state)
+            .map_err(Error::from_wasmi)
+    }
+}
+
+/// Things that can go wrong while running a Rune with an
+/// [`EmbeddedRuntime`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// The Rune couldn't be loaded, or called a function incorrectly.
+    Wasm(wasmi::Error),
+    /// The Rune trapped (e.g. because it panicked).
+    Trap(Trap),
+    /// The Rune doesn't export its linear memory.
+    MissingMemory,
+    /// The Rune imports a host function this engine doesn't provide.
+    UnsupportedFunction(String),
+    /// The Rune passed a pointer that is outside its linear memory.
+    OutOfBounds { address: u32, len: u32 },
+    /// The Rune referred to a capability, model, or output that doesn't
+    /// exist.
+    UnknownId { kind: &'static str, id: u32 },
+    /// The Rune passed invalid data to a host function.
+    Invalid(String),
+    /// The Rune reported an error.
+    Rune { stage: String, message: String },
+    /// The [`Host`] failed.
+    Host(String),
+}
+
+impl Error {
+    /// Create an error for a failure in the [`Host`].
+    pub fn host(message: impl Display) -> Self {
+        Error::Host(message.to_string())
+    }
+
+    fn from_wasmi(e: wasmi::Error) -> Self {
+        match e {
+            wasmi::Error::Trap(trap) => Error::from_trap(trap),
+            wasmi::Error::Host(host) => match host.downcast::<Error>() {
+                Ok(e) => *e,
+                Err(host) => Error::Wasm(wasmi::Error::Host(host)),
+            },
+            other => Error::Wasm(other),
+        }
+    }
+
+    fn from_trap(trap: Trap) -> Self {
+        match trap {
+            Trap::Host(host) => match host.downcast::<Error>() {
+                Ok(e) => *e,
+                Err(host) => Error::Trap(Trap::Host(host)),
+            },
+            other => Error::Trap(other),
+        }
+    }
+}
+
+impl From<wasmi::Error> for Error {
+    fn from(e: wasmi::Error) -> Self { Error::from_wasmi(e) }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Wasm(e) => Display::fmt(e, f),
+            Error::Trap(trap) => write!(f, "The Rune trapped: {}", trap),
+            Error::MissingMemory => {
+                write!(f, "The Rune doesn't export its linear memory")
+            },
+            Error::UnsupportedFunction(name) => write!(
+                f,
+                "The \"{}\" host function isn't supported on this engine",
+                name
+            ),
+            Error::OutOfBounds { address, len } => write!(
+                f,
+                "The {} byte buffer at {:#x} is outside the Rune's memory",
+                len, address
+            ),
+            Error::UnknownId { kind, id } => {
+                write!(f, "There is no {} with ID {}", kind, id)
+            },
+            Error::Invalid(message) => f.write_str(message),
+            Error::Rune { stage, message } if stage.is_empty() => {
+                write!(f, "The Rune failed: {}", message)
+            },
+            Error::Rune { stage, message } => {
+                write!(
+                    f,
+                    "The Rune failed while running {}: {}",
+                    stage, message
+                )
+            },
+            Error::Host(message) => f.write_str(message),
+        }
+    }
+}
+
+impl wasmi::HostError for Error {}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
+
+struct State<H> {
+    host: H,
+    memory: Memory,
+    pool: BufferPool,
+    capabilities: Vec<Capability>,
+    models: Vec<ModelInfo>,
+    outputs: Vec<Output>,
+}
+
+/// A Rune's linear memory.
+struct Memory(MemoryRef);
+
+impl Memory {
+    fn read(&self, address: u32, buffer: &mut [u8]) -> Result<(), Error> {
+        self.0
+            .get_into(address, buffer)
+            .map_err(|_| Error::OutOfBounds {
+                address,
+                len: buffer.len() as u32,
+            })
+    }
+
+    fn write(&self, address: u32, data: &[u8]) -> Result<(), Error> {
+        self.0.set(address, data).map_err(|_| Error::OutOfBounds {
+            address,
+            len: data.len() as u32,
+        })
+    }
+
+    fn read_u32s(&self, address: u32, count: u32) -> Result<Vec<u32>, Error> {
+        let mut buffer = alloc::vec![0; count as usize * 4];
+        self.read(address, &mut buffer)?;
+
+        Ok(buffer
+            .chunks_exact(4)
+            .map(|w| u32::from_le_bytes([w[0], w[1], w[2], w[3]]))
+            .collect())
+    }
+
+    fn read_string(&self, address: u32, len: u32) -> Result<String, Error> {
+        let mut buffer = alloc::vec![0; len as usize];
+        self.read(address, &mut buffer)?;
+
+        String::from_utf8(buffer)
+            .map_err(|e| Error::Invalid(format!("Invalid string: {}", e)))
+    }
+
+    /// Read the `(data, len)` pair from a `StringRef`.
+    fn read_string_ref(&self, address: u32) -> Result<String, Error> {
+        match *self.read_u32s(address, 2)? {
+            [data, len] => self.read_string(data, len),
+            _ => unreachable!(),
+        }
+    }
+
+    fn read_shapes(
+        &self,
+        descriptors: u32,
+        count: u32,
+    ) -> Result<Vec<Shape<'static>>, Error> {
+        (0..count)
+            .map(|i| {
+                let descriptor =
+                    self.read_string_ref(descriptors + i * STRING_REF_SIZE)?;
+                descriptor.parse().map_err(|e| {
+                    Error::Invalid(format!(
+                        "Unable to parse \"{}\" as a shape: {}",
+                        descriptor, e
+                    ))
+                })
+            })
+            .collect()
+    }
+}
+
+impl<H: Host> State<H> {
+    fn capability_mut(&mut self, id: u32) -> Result<&mut Capability, Error> {
+        self.capabilities
+            .get_mut(id as usize)
+            .ok_or(Error::UnknownId {
+                kind: "capability",
+                id,
+            })
+    }
+
+    fn debug(&mut self, msg: u32, len: u32) -> Result<u32, Error> {
+        let message = self.memory.read_string(msg, len)?;
+        log::debug!("{}", message.trim_end());
+        Ok(0)
+    }
+
+    fn rune_log(&mut self, record: u32) -> Result<u32, Error> {
+        // See the LogRecord struct in runicos-base
+        let level = self.memory.read_u32s(record, 1)?[0];
+        let target = self.memory.read_string_ref(record + 4)?;
+        let message = self.memory.read_string_ref(record + 32)?;
+
+        let level = match level {
+            1 => Level::Error,
+            2 => Level::Warn,
+            3 => Level::Info,
+            4 => Level::Debug,
+            5 => Level::Trace,
+            other => {
+                return Err(Error::Invalid(format!(
+                    "Invalid log level: {}",
+                    other
+                )))
+            },
+        };
+        log::log!(target: &target, level, "{}", message);
+
+        Ok(0)
+    }
+
+    fn rune_error(&mut self, record: u32) -> Result<u32, Error> {
+        // See the ErrorRecord struct in runicos-base
+        let stage = self.memory.read_string_ref(record + 4)?;
+        let message = self.memory.read_string_ref(record + 12)?;
+
+        Err(Error::Rune { stage, message })
+    }
+
+    fn request_capability(&mut self, kind: u32) -> Result<u32, Error> {
+        let kind =
+            hotg_rune_core::capabilities::name(kind).ok_or_else(|| {
+                Error::Invalid(format!("Unknown capability type: {}", kind))
+            })?;
+        let id = self.capabilities.len() as u32;
+        self.capabilities.push(Capability {
+            id,
+            kind,
+            parameters: Vec::new(),
+        });
+
+        Ok(id)
+    }
+
+    fn set_capability_param(
+        &mut self,
+        id: u32,
+        key: String,
+        value: String,
+    ) -> Result<u32, Error> {
+        let capability = self.capability_mut(id)?;
+        capability.parameters.retain(|(k, _)| *k != key);
+        capability.parameters.push((key, value));
+
+        Ok(0)
+    }
+
+    fn rune_capability_set_string_param(
+        &mut self,
+        id: u32,
+        (key, key_len): (u32, u32),
+        (value, value_len): (u32, u32),
+    ) -> Result<u32, Error> {
+        let key = self.memory.read_string(key, key_len)?;
+        let value = self.memory.read_string(value, value_len)?;
+
+        self.set_capability_param(id, key, value)
+    }
+
+    fn request_capability_set_param(
+        &mut self,
+        id: u32,
+        (key, key_len): (u32, u32),
+        (value, value_len): (u32, u32),
+        value_type: u32,
+    ) -> Result<u32, Error> {
+        let key = self.memory.read_string(key, key_len)?;
+        let mut bytes = alloc::vec![0; value_len as usize];
+        self.memory.read(value, &mut bytes)?;
+
+        let value = Type::try_from(value_type)
+            .ok()
+            .and_then(|ty| Value::from_le_bytes(ty, &bytes))
+            .ok_or_else(|| {
+                Error::Invalid(format!("Invalid value for \"{}\"", key))
+            })?;
+        let value = match value {
+            Value::Byte(b) => b.to_string(),
+            Value::Short(s) => s.to_string(),
+            Value::Integer(i) => i.to_string(),
+            Value::Float(f) => f.to_string(),
+            Value::SignedByte(s) => s.to_string(),
+            other => {
+                return Err(Error::Invalid(format!(
+                    "Unknown value type: {}",
+                    other
+                )))
+            },
+        };
+
+        self.set_capability_param(id, key, value)
+    }
+
+    fn request_provider_response(
+        &mut self,
+        buffer: u32,
+        len: u32,
+        id: u32,
+    ) -> Result<u32, Error> {
+        let State {
+            host,
+            memory,
+            pool,
+            capabilities,
+            ..
+        } = self;
+
+        let capability =
+            capabilities.get(id as usize).ok_or(Error::UnknownId {
+                kind: "capability",
+                id,
+            })?;
+
+        let mut scratch = pool.acquire(len as usize);
+        let result = host.read_capability(capability, &mut scratch).and_then(
+            |bytes_written| {
+                let bytes_written = bytes_written.min(scratch.len());
+                memory.write(buffer, &scratch[..bytes_written])?;
+                Ok(bytes_written as u32)
+            },
+        );
+        pool.release(scratch);
+
+        result
+    }
+
+    fn rune_model_load(
+        &mut self,
+        (mimetype, mimetype_len): (u32, u32),
+        (model, model_len): (u32, u32),
+        (inputs, input_len): (u32, u32),
+        (outputs, output_len): (u32, u32),
+    ) -> Result<u32, Error> {
+        let info = ModelInfo {
+            id: self.models.len() as u32,
+            mimetype: self.memory.read_string(mimetype, mimetype_len)?,
+            inputs: self.memory.read_shapes(inputs, input_len)?,
+            outputs: self.memory.read_shapes(outputs, output_len)?,
+        };
+
+        let State {
+            host, memory, pool, ..
+        } = self;
+        let mut data = pool.acquire(model_len as usize);
+        let result = memory
+            .read(model, &mut data)
+            .and_then(|_| host.load_model(&info, &data));
+        pool.release(data);
+        result?;
+
+        let id = info.id;
+        self.models.push(info);
+
+        Ok(id)
+    }
+
+    fn rune_model_infer(
+        &mut self,
+        id: u32,
+        inputs: u32,
+        outputs: u32,
+    ) -> Result<u32, Error> {
+        let State {
+            host,
+            memory,
+            pool,
+            models,
+            ..
+        } = self;
+
+        let model = models
+            .get(id as usize)
+            .ok_or(Error::UnknownId { kind: "model", id })?;
+        let input_pointers =
+            memory.read_u32s(inputs, model.inputs.len() as u32)?;
+        let output_pointers =
+            memory.read_u32s(outputs, model.outputs.len() as u32)?;
+
+        // Note: the tensors are copied out of linear memory because the host
+        // needs the inputs and outputs at the same time. Using the pool means
+        // this doesn't need to allocate.
+        let mut input_buffers = Vec::with_capacity(input_pointers.len());
+        let mut output_buffers = Vec::with_capacity(output_pointers.len());
+
+        let mut infer = || {
+            for (&ptr, shape) in input_pointers.iter().zip(&model.inputs) {
+                let mut buffer = pool.acquire(tensor_size(shape)?);
+                let read = memory.read(ptr, &mut buffer);
+                input_buffers.push(buffer);
+                read?;
+            }
+            for shape in &model.outputs {
+                output_buffers.push(pool.acquire(tensor_size(shape)?));
+            }
+
+            let inputs: Vec<&[u8]> =
+                input_buffers.iter().map(|b| b.as_slice()).collect();
+            let mut outputs: Vec<&mut [u8]> = output_buffers
+                .iter_mut()
+                .map(|b| b.as_mut_slice())
+                .collect();
+            host.infer(model, &inputs, &mut outputs)?;
+
+            for (&ptr, buffer) in output_pointers.iter().zip(&output_buffers) {
+                memory.write(ptr, buffer)?;
+            }
+
+            Ok(0)
+        };
+        let result = infer();
+
+        for buffer in input_buffers.into_iter().chain(output_buffers) {
+            pool.release(buffer);
+        }
+
+        result
+    }
+
+    fn rune_model_resize_inputs(
+        &mut self,
+        id: u32,
+        descriptors: u32,
+        len: u32,
+    ) -> Result<u32, Error> {
+        let shapes = self.memory.read_shapes(descriptors, len)?;
+        let model = self
+            .models
+            .get_mut(id as usize)
+            .ok_or(Error::UnknownId { kind: "model", id })?;
+
+        if model.inputs != shapes {
+            model.outputs = self.host.resize_inputs(model, &shapes)?;
+            model.inputs = shapes;
+        }
+
+        Ok(0)
+    }
+
+    fn rune_model_output_shape(
+        &mut self,
+        id: u32,
+        index: u32,
+        buffer: u32,
+        len: u32,
+    ) -> Result<u32, Error> {
+        let model = self
+            .models
+            .get(id as usize)
+            .ok_or(Error::UnknownId { kind: "model", id })?;
+        let shape = model.outputs.get(index as usize).ok_or_else(|| {
+            Error::Invalid(format!("Model {} has no output {}", id, index))
+        })?;
+
+        let descriptor = shape.to_string();
+        if descriptor.len() <= len as usize {
+            self.memory.write(buffer, descriptor.as_bytes())?;
+        }
+
+        Ok(descriptor.len() as u32)
+    }
+
+    fn request_output(&mut self, kind: u32) -> Result<u32, Error> {
+        let kind = hotg_rune_core::outputs::name(kind).ok_or_else(|| {
+            Error::Invalid(format!("Unknown output type: {}", kind))
+        })?;
+        let id = self.outputs.len() as u32;
+        self.outputs.push(Output { id, kind });
+
+        Ok(id)
+    }
+
+    fn consume_output(
+        &mut self,
+        id: u32,
+        buffer: u32,
+        len: u32,
+    ) -> Result<u32, Error> {
+        let State {
+            host,
+            memory,
+            pool,
+            outputs,
+            ..
+        } = self;
+
+        let output = outputs
+            .get(id as usize)
+            .ok_or(Error::UnknownId { kind: "output", id })?;
+
+        let mut data = pool.acquire(len as usize);
+        let result = memory
+            .read(buffer, &mut data)
+            .and_then(|_| host.write_output(output, &data));
+        pool.release(data);
+        result?;
+
+        Ok(len)
+    }
+}
+
+/// The size of a `StringRef` (a `(data, len)` pair of `u32`s) in the Rune's
+/// memory.
+const STRING_REF_SIZE: u32 = 8;
+
+fn tensor_size(shape: &Shape<'_>) -> Result<usize, Error> {
+    shape.size().ok_or_else(|| {
+        Error::Invalid(format!("{} tensors don't have a fixed size", shape))
+    })
+}
+
+macro_rules! host_functions {
+    ($( $index:literal => $name:ident($arity:literal) ),* $(,)?) => {
+        fn host_function(name: &str) -> Option<(usize, usize)> {
+            match name {
+                $( stringify!($name) => Some(($index, $arity)), )*
+                _ => None,
+            }
+        }
+    };
+}
+
+host_functions! {
+    0 => _debug(2),
+    1 => rune_log(1),
+    2 => rune_error(1),
+    3 => request_capability(1),
+    4 => request_capability_set_param(6),
+    5 => rune_capability_set_string_param(5),
+    6 => request_provider_response(3),
+    7 => rune_model_load(8),
+    8 => rune_model_infer(3),
+    9 => request_output(1),
+    10 => consume_output(3),
+    11 => rune_model_resize_inputs(3),
+    12 => rune_model_output_shape(4),
+    13 => rune_stage_started(2),
+    14 => rune_stage_finished(2),
+}
+
+struct Resolver;
+
+impl ModuleImportResolver for Resolver {
+    fn resolve_func(
+        &self,
+        field_name: &str,
+        signature: &Signature,
+    ) -> Result<FuncRef, wasmi::Error> {
+        let (index, arity) = host_function(field_name).ok_or_else(|| {
+            wasmi::Error::host(Error::UnsupportedFunction(
+                field_name.to_string(),
+            ))
+        })?;
+
+        let params = alloc::vec![ValueType::I32; arity];
+        let expected = Signature::new(params, Some(ValueType::I32));
+
+        if *signature != expected {
+            return Err(wasmi::Error::Instantiation(format!(
+                "Expected \"{}\" to have the signature {:?}, found {:?}",
+                field_name, expected, signature
+            )));
+        }
+
+        Ok(FuncInstance::alloc_host(expected, index))
+    }
+}
+
+impl<H: Host> Externals for State<H> {
+    fn invoke_index(
+        &mut self,
+        index: usize,
+        args: RuntimeArgs<'_>,
+    ) -> Result<Option<RuntimeValue>, Trap> {
+        let arg = |i: usize| -> Result<u32, Trap> { args.nth_checked(i) };
+
+        let ret = match index {
+            0 => self.debug(arg(0)?, arg(1)?),
+            1 => self.rune_log(arg(0)?),
+            2 => self.rune_error(arg(0)?),
+            3 => self.request_capability(arg(0)?),
+            4 => self.request_capability_set_param(
+                arg(0)?,
+                (arg(1)?, arg(2)?),
+                (arg(3)?, arg(4)?),
+                arg(5)?,
+            ),
+            5 => self.rune_capability_set_string_param(
+                arg(0)?,
+                (arg(1)?, arg(2)?),
+                (arg(3)?, arg(4)?),
+            ),
+            6 => self.request_provider_response(arg(0)?, arg(1)?, arg(2)?),
+            7 => self.rune_model_load(
+                (arg(0)?, arg(1)?),
+                (arg(2)?, arg(3)?),
+                (arg(4)?, arg(5)?),
+                (arg(6)?, arg(7)?),
+            ),
+            8 => self.rune_model_infer(arg(0)?, arg(1)?, arg(2)?),
+            9 => self.request_output(arg(0)?),
+            10 => self.consume_output(arg(0)?, arg(1)?, arg(2)?),
+            11 => self.rune_model_resize_inputs(arg(0)?, arg(1)?, arg(2)?),
+            12 => {
+                self.rune_model_output_shape(arg(0)?, arg(1)?, arg(2)?, arg(3)?)
+            },
+            // Stage timings aren't recorded on microcontrollers
+            13 | 14 => Ok(0),
+            _ => unreachable!("Unknown host function: {}", index),
+        };
+
+        match ret {
+            Ok(value) => Ok(Some(RuntimeValue::I32(value as i32))),
+            Err(e) => Err(Trap::host(e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{vec, vec::Vec};
+
+    use super::*;
+
+    /// A Rune which reads 4 bytes from a `RAW` capability, passes them
+    /// through a model, and sends the result to a `SERIAL` output.
+    const RUNE: &str = r#"
+        (module
+            (import "env" "request_capability"
+                (func $request_capability (param i32) (result i32)))
+            (import "env" "rune_capability_set_string_param"
+                (func $set_param (param i32 i32 i32 i32 i32) (result i32)))
+            (import "env" "request_provider_response"
+                (func $read_capability (param i32 i32 i32) (result i32)))
+            (import "env" "rune_model_load"
+                (func $load_model
+                    (param i32 i32 i32 i32 i32 i32 i32 i32) (result i32)))
+            (import "env" "rune_model_infer"
+                (func $infer (param i32 i32 i32) (result i32)))
+            (import "env" "request_output"
+                (func $request_output (param i32) (result i32)))
+            (import "env" "consume_output"
+                (func $consume_output (param i32 i32 i32) (result i32)))
+
+            (memory (export "memory") 1)
+            (data (i32.const 0) "shape")
+            (data (i32.const 8) "u8[4]")
+            (data (i32.const 16) "application/x-double")
+            ;; a StringRef pointing at "u8[4]"
+            (data (i32.const 48) "\08\00\00\00\05\00\00\00")
+            ;; pointers to the model's input (128) and output (136)
+            (data (i32.const 56) "\80\00\00\00\88\00\00\00")
+
+            (global $capability (mut i32) (i32.const 0))
+            (global $model (mut i32) (i32.const 0))
+            (global $output (mut i32) (i32.const 0))
+
+            (func (export "_manifest") (result i32)
+                (global.set $capability
+                    (call $request_capability (i32.const 5)))
+                (drop (call $set_param (global.get $capability)
+                    (i32.const 0) (i32.const 5) (i32.const 8) (i32.const 5)))
+                (global.set $model (call $load_model
+                    (i32.const 16) (i32.const 20)
+                    (i32.const 0) (i32.const 0)
+                    (i32.const 48) (i32.const 1)
+                    (i32.const 48) (i32.const 1)))
+                (global.set $output (call $request_output (i32.const 1)))
+                (i32.const 0))
+
+            (func (export "_call") (param i32 i32 i32) (result i32)
+                (drop (call $read_capability
+                    (i32.const 128) (i32.const 4) (global.get $capability)))
+                (drop (call $infer
+                    (global.get $model) (i32.const 56) (i32.const 60)))
+                (drop (call $consume_output
+                    (global.get $output) (i32.const 136) (i32.const 4)))
+                (i32.const 0)))
+    "#;
+
+    #[derive(Default)]
+    struct MockHost {
+        models: Vec<String>,
+        outputs: Vec<Vec<u8>>,
+    }
+
+    impl Host for MockHost {
+        fn read_capability(
+            &mut self,
+            capability: &Capability,
+            buffer: &mut [u8],
+        ) -> Result<usize, Error> {
+            assert_eq!(capability.kind, "RAW");
+            buffer.copy_from_slice(&[1, 2, 3, 4]);
+            Ok(buffer.len())
+        }
+
+        fn load_model(
+            &mut self,
+            model: &ModelInfo,
+            _data: &[u8],
+        ) -> Result<(), Error> {
+            self.models.push(model.mimetype.clone());
+            Ok(())
+        }
+
+        fn infer(
+            &mut self,
+            _model: &ModelInfo,
+            inputs: &[&[u8]],
+            outputs: &mut [&mut [u8]],
+        ) -> Result<(), Error> {
+            for (dest, src) in outputs[0].iter_mut().zip(inputs[0]) {
+                *dest = src * 2;
+            }
+            Ok(())
+        }
+
+        fn write_output(
+            &mut self,
+            output: &Output,
+            data: &[u8],
+        ) -> Result<(), Error> {
+            assert_eq!(output.kind, "SERIAL");
+            self.outputs.push(data.to_vec());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn run_a_rune() {
+        let wasm = wat::parse_str(RUNE).unwrap();
+        let pool = BufferPool::with_static_buffers(&[4, 4]);
+
+        let mut runtime =
+            EmbeddedRuntime::with_buffer_pool(&wasm, MockHost::default(), pool)
+                .unwrap();
+        runtime.predict().unwrap();
+        runtime.predict().unwrap();
+
+        assert_eq!(
+            runtime.capabilities()[0].shape().unwrap().to_string(),
+            "u8[4]"
+        );
+        assert_eq!(runtime.host().models, vec!["application/x-double"]);
+        assert_eq!(runtime.host().outputs, vec![vec![2, 4, 6, 8]; 2]);
+        assert_eq!(runtime.buffer_pool().stats().misses, 0);
+    }
+
+    #[test]
+    fn unsupported_host_functions_are_rejected() {
+        let wasm = wat::parse_str(
+            r#"(module
+                (import "env" "rune_file_open"
+                    (func (param i32 i32 i32) (result i32)))
+                (memory (export "memory") 1))"#,
+        )
+        .unwrap();
+
+        let err = EmbeddedRuntime::load(&wasm, MockHost::default())
+            .err()
+            .unwrap();
+
+        match err {
+            Error::UnsupportedFunction(name) => {
+                assert_eq!(name, "rune_file_open")
+            },
+            other => panic!("Unexpected error: {}", other),
+        }
+    }
+
+    #[test]
+    fn errors_from_the_rune_are_returned() {
+        let wasm = wat::parse_str(
+            r#"(module
+                (import "env" "rune_error"
+                    (func $rune_error (param i32) (result i32)))
+                (memory (export "memory") 1)
+                (data (i32.const 0) "model")
+                (data (i32.const 8) "boom")
+                ;; ErrorRecord { kind, stage, message }
+                (data (i32.const 16)
+                    "\00\00\00\00\00\00\00\00\05\00\00\00\08\00\00\00\04\00\00\00")
+                (func (export "_manifest") (result i32)
+                    (call $rune_error (i32.const 16))))"#,
+        )
+        .unwrap();
+
+        let err = EmbeddedRuntime::load(&wasm, MockHost::default())
+            .err()
+            .unwrap();
+
+        assert_eq!(
+            err.to_string(),
+            "The Rune failed while running model: boom"
+        );
+    }
+}
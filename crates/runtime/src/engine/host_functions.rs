@@ -1,16 +1,27 @@
 #![allow(dead_code)] // triggered when you don't compile with an engine feature
 
 use std::{
+    borrow::Cow,
     collections::HashMap,
-    io::{Cursor, Read},
+    fs::File,
+    io::{Cursor, Read, Write},
     sync::Arc,
+    time::{Instant, UNIX_EPOCH},
 };
 
 use anyhow::{Context, Error};
 use hotg_rune_core::{SerializableRecord, Shape};
+use log::Level;
 
-use crate::callbacks::{
-    Callbacks, Model, ModelMetadata, NodeMetadata, RuneGraph,
+use crate::{
+    callbacks::{
+        Callbacks, CapabilityStream, Model, ModelMetadata, NodeMetadata,
+        ProcBlock, RuneGraph,
+    },
+    engine::wasi,
+    files::FileMode,
+    metrics::{memory, ModelBenchmark, Stage},
+    Cancelled, GuestError, GuestErrorKind,
 };
 
 /// An adapter that exposes functionality from [`Callbacks`] via functions that
@@ -23,7 +34,41 @@ pub(crate) struct HostFunctions {
     capabilities: HashMap<u32, NodeMetadata>,
     outputs: HashMap<u32, NodeMetadata>,
     resources: HashMap<u32, Box<dyn Read + Send + Sync>>,
+    /// Open capability streams, plus the ID of the capability each one is
+    /// reading from.
+    capability_streams: HashMap<u32, (u32, CapabilityStream)>,
+    files: HashMap<u32, (FileMode, File)>,
     models: HashMap<u32, Box<dyn Model>>,
+    /// Models which are only loaded the first time they are used, giving the
+    /// host a chance to provide (or decrypt) them after the Rune is loaded.
+    deferred_models: HashMap<u32, DeferredModel>,
+    proc_blocks: HashMap<u32, Box<dyn ProcBlock>>,
+    /// Standalone proc blocks which haven't been used yet, so the host can
+    /// provide them after the Rune is loaded.
+    deferred_proc_blocks: HashMap<u32, DeferredProcBlock>,
+    /// The encoded outputs from each proc block's most recent transform,
+    /// waiting for the Rune to read them.
+    proc_block_outputs: HashMap<u32, Vec<u8>>,
+    /// The pipeline stage which is currently running and when it started.
+    current_stage: Option<(String, Instant)>,
+}
+
+/// A model which needs help from the host before it can be loaded.
+struct DeferredModel {
+    name: String,
+    mimetype: String,
+    inputs: Vec<Shape<'static>>,
+    outputs: Vec<Shape<'static>>,
+    /// The encrypted model embedded in the Rune, or `None` if the host is
+    /// expected to provide the model file.
+    encrypted: Option<Vec<u8>>,
+}
+
+/// A standalone proc block which will be provided by the host.
+struct DeferredProcBlock {
+    name: String,
+    /// Parameters which were set before the proc block was loaded.
+    parameters: Vec<(String, String)>,
 }
 
 impl HostFunctions {
@@ -34,7 +79,14 @@ impl HostFunctions {
             capabilities: HashMap::new(),
             outputs: HashMap::new(),
             resources: HashMap::new(),
+            capability_streams: HashMap::new(),
+            files: HashMap::new(),
             models: HashMap::new(),
+            deferred_models: HashMap::new(),
+            proc_blocks: HashMap::new(),
+            deferred_proc_blocks: HashMap::new(),
+            proc_block_outputs: HashMap::new(),
+            current_stage: None,
         }
     }
 
@@ -45,8 +97,119 @@ impl HostFunctions {
         }
     }
 
-    pub(crate) fn model_by_id(&mut self, id: u32) -> Option<&mut dyn Model> {
-        self.models.get_mut(&id).map(|m| &mut **m)
+    /// Get a model, loading it first if it was deferred.
+    pub(crate) fn model_by_id(
+        &mut self,
+        id: u32,
+    ) -> Result<&mut dyn Model, Error> {
+        if let Some(deferred) = self.deferred_models.get(&id) {
+            let model = self.load_deferred_model(id, deferred)?;
+            self.deferred_models.remove(&id);
+            self.models.insert(id, model);
+        }
+
+        self.models
+            .get_mut(&id)
+            .map(|m| &mut **m)
+            .with_context(|| {
+                format!("Tried to access non-existent model with ID {}", id)
+            })
+    }
+
+    fn load_deferred_model(
+        &self,
+        id: u32,
+        deferred: &DeferredModel,
+    ) -> Result<Box<dyn Model>, Error> {
+        let DeferredModel {
+            name,
+            mimetype,
+            inputs,
+            outputs,
+            encrypted,
+        } = deferred;
+
+        let decrypted;
+        let model = match encrypted {
+            Some(encrypted) => {
+                decrypted = self
+                    .callbacks
+                    .decrypt_model(name, encrypted)
+                    .with_context(|| {
+                        format!("Unable to decrypt the \"{}\" model", name)
+                    })?;
+                &decrypted[..]
+            },
+            None => self.callbacks.get_model(name).with_context(|| {
+                format!("The host didn't provide the \"{}\" model", name)
+            })?,
+        };
+
+        let meta = ModelMetadata {
+            mimetype,
+            inputs,
+            outputs,
+        };
+
+        self.callbacks
+            .load_model(id, &meta, model)
+            .with_context(|| format!("Unable to load the \"{}\" model", name))
+    }
+
+    /// Get a standalone proc block, loading it first if it was deferred.
+    fn proc_block_by_id(
+        &mut self,
+        id: u32,
+    ) -> Result<&mut dyn ProcBlock, Error> {
+        if let Some(deferred) = self.deferred_proc_blocks.remove(&id) {
+            let proc_block = self.load_deferred_proc_block(id, deferred)?;
+            self.proc_blocks.insert(id, proc_block);
+        }
+
+        self.proc_blocks
+            .get_mut(&id)
+            .map(|p| &mut **p)
+            .with_context(|| {
+                format!(
+                    "Tried to access non-existent proc block with ID {}",
+                    id
+                )
+            })
+    }
+
+    fn load_deferred_proc_block(
+        &self,
+        id: u32,
+        deferred: DeferredProcBlock,
+    ) -> Result<Box<dyn ProcBlock>, Error> {
+        let DeferredProcBlock { name, parameters } = deferred;
+
+        let wasm = self.callbacks.get_proc_block(&name).with_context(|| {
+            format!("The host didn't provide the \"{}\" proc block", name)
+        })?;
+
+        let mut proc_block = self.callbacks.load_proc_block(id, &name, wasm)?;
+
+        for (key, value) in &parameters {
+            proc_block.set_parameter(key, value).with_context(|| {
+                format!(
+                    "Unable to set the \"{}\" proc block's \"{}\" parameter \
+                     to \"{}\"",
+                    name, key, value
+                )
+            })?;
+        }
+
+        Ok(proc_block)
+    }
+
+    /// Stop the Rune if the host has cancelled the current call.
+    fn check_cancelled(&self) -> Result<(), Error> {
+        if self.callbacks.is_cancelled() {
+            Err(Cancelled.into())
+        } else {
+            Ok(())
+        }
     }
 
     fn next_id(&mut self) -> u32 {
@@ -74,6 +237,55 @@ impl HostFunctions {
         Ok(())
     }
 
+    /// Forward a structured log record from the Rune to the [`Callbacks`].
+    ///
+    /// Empty `module_path` and `file` strings and a `line` of `0` are
+    /// treated as missing.
+    pub fn log(
+        &self,
+        level: u32,
+        target: &str,
+        module_path: &str,
+        file: &str,
+        line: u32,
+        message: &str,
+    ) -> Result<(), Error> {
+        let level = log_level(level)?;
+
+        let record = SerializableRecord {
+            level,
+            message: Cow::Borrowed(message),
+            target: Cow::Borrowed(target),
+            module_path: non_empty(module_path),
+            file: non_empty(file),
+            line: if line == 0 { None } else { Some(line) },
+        };
+
+        record.with_record(|r| self.callbacks.log(r));
+
+        Ok(())
+    }
+
+    /// Handle an error reported by the Rune, returning it so the call that
+    /// is currently in progress is aborted.
+    ///
+    /// An empty `stage` means the Rune doesn't know which pipeline stage was
+    /// running.
+    pub fn rune_error(
+        &self,
+        kind: u32,
+        stage: &str,
+        message: &str,
+    ) -> Result<u32, Error> {
+        let error = GuestError {
+            stage: non_empty(stage).map(Cow::into_owned),
+            kind: GuestErrorKind::from_u32(kind),
+            message: message.to_string(),
+        };
+
+        Err(error.into())
+    }
+
     pub fn request_capability(
         &mut self,
         capability_type: u32,
@@ -118,6 +330,8 @@ impl HostFunctions {
         capability_id: u32,
         buffer: &mut [u8],
     ) -> Result<u32, Error> {
+        self.check_cancelled()?;
+
         let meta =
             self.capabilities.get(&capability_id).with_context(|| {
                 format!(
@@ -126,14 +340,110 @@ impl HostFunctions {
                 )
             })?;
 
+        let start = Instant::now();
         let bytes_written = self
             .callbacks
             .read_capability(capability_id, meta, buffer)
             .context("Unable to read the input")?;
+        self.callbacks.record_timing(
+            Stage::Capability,
+            capability_id,
+            start.elapsed(),
+        );
 
         Ok(bytes_written as u32)
     }
 
+    /// Open a stream the Rune can use to pull data from a capability
+    /// incrementally, rather than receiving one fixed-size snapshot.
+    pub fn rune_capability_stream_open(
+        &mut self,
+        capability_id: u32,
+    ) -> Result<u32, Error> {
+        let meta =
+            self.capabilities.get(&capability_id).with_context(|| {
+                format!(
+                    "Tried to open a stream for non-existent capability with \
+                     ID {}",
+                    capability_id
+                )
+            })?;
+
+        let stream = self
+            .callbacks
+            .open_capability_stream(capability_id, meta)
+            .with_context(|| {
+                format!(
+                    "Unable to open a stream for the \"{}\" capability with \
+                     ID {}",
+                    meta.kind, capability_id
+                )
+            })?;
+
+        let id = self.next_id();
+        self.capability_streams.insert(id, (capability_id, stream));
+
+        Ok(id)
+    }
+
+    /// Read the next chunk of data from a capability stream, returning the
+    /// number of bytes read (`0` means the stream is finished).
+    ///
+    /// Streams may return fewer bytes than requested when there isn't much
+    /// data available, so the Rune only ever receives as much as it asks for.
+    pub fn rune_capability_stream_read(
+        &mut self,
+        stream_id: u32,
+        buffer: &mut [u8],
+    ) -> Result<u32, Error> {
+        self.check_cancelled()?;
+
+        let (capability_id, stream) =
+            self.capability_streams.get_mut(&stream_id).with_context(|| {
+                format!(
+                    "Tried to read from non-existent capability stream with \
+                     ID {}",
+                    stream_id
+                )
+            })?;
+
+        let meta = self.capabilities.get(capability_id).with_context(|| {
+            format!(
+                "Tried to read from a stream for non-existent capability with \
+                 ID {}",
+                capability_id
+            )
+        })?;
+
+        let start = Instant::now();
+        let bytes_read = self
+            .callbacks
+            .read_capability_stream(*capability_id, meta, stream, buffer)
+            .context("Unable to read from the capability stream")?;
+        self.callbacks.record_timing(
+            Stage::Capability,
+            *capability_id,
+            start.elapsed(),
+        );
+
+        Ok(bytes_read as u32)
+    }
+
+    pub fn rune_capability_stream_close(
+        &mut self,
+        stream_id: u32,
+    ) -> Result<(), Error> {
+        let _ =
+            self.capability_streams.remove(&stream_id).with_context(|| {
+                format!(
+                    "Tried to close non-existent capability stream with ID {}",
+                    stream_id
+                )
+            })?;
+
+        Ok(())
+    }
+
     pub fn tfm_model_invoke(&self) -> Result<(), Error> {
         anyhow::bail!("This feature has been removed")
     }
@@ -173,21 +483,346 @@ impl HostFunctions {
         Ok(id)
     }
 
+    /// Register a model which the host will provide, instead of one whose
+    /// weights are embedded in the Rune.
+    ///
+    /// The model is only loaded the first time it is used, so hosts can
+    /// provide it via [`Callbacks::get_model()`] any time before then.
+    pub fn rune_model_load_external(
+        &mut self,
+        name: &str,
+        mimetype: &str,
+        inputs: &[Shape<'_>],
+        outputs: &[Shape<'_>],
+    ) -> Result<u32, Error> {
+        let id = self.next_id();
+
+        let deferred = DeferredModel {
+            name: name.to_string(),
+            mimetype: mimetype.to_string(),
+            inputs: inputs.iter().map(|s| s.to_owned()).collect(),
+            outputs: outputs.iter().map(|s| s.to_owned()).collect(),
+            encrypted: None,
+        };
+        self.deferred_models.insert(id, deferred);
+
+        Ok(id)
+    }
+
+    /// Register a model whose weights are embedded in the Rune in encrypted
+    /// form.
+    ///
+    /// The model is decrypted with [`Callbacks::decrypt_model()`] and loaded
+    /// the first time it is used, so the plaintext weights never pass
+    /// through the Rune's memory.
+    pub fn rune_model_load_encrypted(
+        &mut self,
+        name: &str,
+        mimetype: &str,
+        encrypted: &[u8],
+        inputs: &[Shape<'_>],
+        outputs: &[Shape<'_>],
+    ) -> Result<u32, Error> {
+        let id = self.next_id();
+
+        let deferred = DeferredModel {
+            name: name.to_string(),
+            mimetype: mimetype.to_string(),
+            inputs: inputs.iter().map(|s| s.to_owned()).collect(),
+            outputs: outputs.iter().map(|s| s.to_owned()).collect(),
+            encrypted: Some(encrypted.to_vec()),
+        };
+        self.deferred_models.insert(id, deferred);
+
+        Ok(id)
+    }
+
+    /// Figure out which of a model's variants the host chose (see
+    /// [`crate::models::MODEL_VARIANTS_ENV`]).
+    ///
+    /// Returns `0` for the default model, or `i + 1` for the `i`'th item in
+    /// `variants`.
+    pub fn rune_model_variant(
+        &mut self,
+        name: &str,
+        variants: &[&str],
+    ) -> Result<u32, Error> {
+        let selected = match crate::models::selected_variant(name)? {
+            Some(selected) => selected,
+            None => return Ok(0),
+        };
+
+        match variants.iter().position(|v| *v == selected) {
+            Some(index) => {
+                log::debug!("Using the \"{}\" variant of {}", selected, name);
+                Ok(index as u32 + 1)
+            },
+            None => anyhow::bail!(
+                "\"{}\" doesn't have a \"{}\" variant (expected one of {:?})",
+                name,
+                selected,
+                variants,
+            ),
+        }
+    }
+
+    /// Get a model ready for inputs with the provided shapes, for models
+    /// whose input dimensions are only known at runtime.
+    pub fn rune_model_resize_inputs(
+        &mut self,
+        model_id: u32,
+        shapes: &[Shape<'_>],
+    ) -> Result<&mut dyn Model, Error> {
+        if let Some(shape) = shapes.iter().find(|s| !s.is_fixed()) {
+            anyhow::bail!(
+                "Unable to resize model {}'s inputs because the dimensions in \
+                 {} aren't all known",
+                model_id,
+                shape
+            );
+        }
+
+        let model = self.model_by_id(model_id)?;
+
+        if model.input_shapes() != shapes {
+            log::debug!("Resizing model {}'s inputs to {:?}", model_id, shapes);
+            model.resize_inputs(shapes).with_context(|| {
+                format!("Unable to resize model {}'s inputs", model_id)
+            })?;
+        }
+
+        Ok(model)
+    }
+
+    /// Write one of a model's output shapes (e.g. `"f32[4, 10]"`) to
+    /// `buffer`, returning the descriptor's length.
+    ///
+    /// Resizing a model's inputs may change its outputs, so this is how the
+    /// Rune finds out how big the output tensors need to be. Nothing is
+    /// written if `buffer` is too small, letting the Rune try again with a
+    /// bigger buffer.
+    pub fn rune_model_output_shape(
+        &mut self,
+        model_id: u32,
+        index: u32,
+        buffer: &mut [u8],
+    ) -> Result<u32, Error> {
+        let model = self.model_by_id(model_id)?;
+        let shape =
+            model.output_shapes().get(index as usize).with_context(|| {
+                format!("Model {} has no output {}", model_id, index)
+            })?;
+
+        let descriptor = shape.to_string();
+        if let Some(dest) = buffer.get_mut(..descriptor.len()) {
+            dest.copy_from_slice(descriptor.as_bytes());
+        }
+
+        Ok(descriptor.len() as u32)
+    }
+
     pub fn rune_model_infer(
         &mut self,
         model_id: u32,
         inputs: &[&[u8]],
         outputs: &mut [&mut [u8]],
     ) -> Result<(), Error> {
-        let model = self.models.get_mut(&model_id).with_context(|| {
-            format!("Tried to access non-existent model with ID {}", model_id)
-        })?;
+        self.check_cancelled()?;
+
+        let model = self.model_by_id(model_id)?;
 
+        let start = Instant::now();
         model.infer(inputs, outputs)?;
+        self.callbacks
+            .record_timing(Stage::Model, model_id, start.elapsed());
+
+        Ok(())
+    }
+
+    /// Run each model once with zeroed inputs.
+    ///
+    /// Models are kept alive for as long as the Rune is, but many backends
+    /// do their expensive setup (allocating tensors, compiling kernels,
+    /// uploading weights to an accelerator, etc.) on the first inference.
+    /// Doing that up front means the first real inference is as fast as the
+    /// rest.
+    pub(crate) fn warm_up_models(&mut self) -> Result<(), Error> {
+        self.load_deferred_models()?;
+
+        for (&id, model) in &mut self.models {
+            let mut tensors = match SyntheticTensors::for_model(&**model) {
+                Some(t) => t,
+                None => {
+                    log::debug!(
+                        "Skipping warm-up for model {} because its tensors \
+                         don't have a fixed size",
+                        id
+                    );
+                    continue;
+                },
+            };
+
+            let start = Instant::now();
+            tensors.infer(&mut **model).with_context(|| {
+                format!("Unable to warm up model {}", id)
+            })?;
+            log::debug!("Warmed up model {} in {:?}", id, start.elapsed());
+        }
+
+        Ok(())
+    }
+
+    /// Run each model `iterations` times with zeroed inputs, recording how
+    /// long each inference took and the peak memory usage.
+    pub(crate) fn benchmark_models(
+        &mut self,
+        iterations: usize,
+    ) -> Result<Vec<ModelBenchmark>, Error> {
+        anyhow::ensure!(iterations > 0, "At least one iteration is required");
+
+        self.load_deferred_models()?;
+
+        let mut ids: Vec<u32> = self.models.keys().copied().collect();
+        ids.sort_unstable();
+
+        let mut benchmarks = Vec::new();
+
+        for id in ids {
+            self.check_cancelled()?;
+
+            let model = self.model_by_id(id)?;
+            let mut tensors = match SyntheticTensors::for_model(model) {
+                Some(t) => t,
+                None => {
+                    log::warn!(
+                        "Skipping model {} because its tensors don't have a \
+                         fixed size",
+                        id
+                    );
+                    continue;
+                },
+            };
+
+            memory::reset_peak_memory();
+            let mut latencies = Vec::with_capacity(iterations);
+
+            for _ in 0..iterations {
+                let start = Instant::now();
+                tensors.infer(model).with_context(|| {
+                    format!("Unable to benchmark model {}", id)
+                })?;
+                latencies.push(start.elapsed());
+            }
+
+            let benchmark = ModelBenchmark::from_latencies(
+                id,
+                latencies,
+                memory::peak_memory(),
+            );
+            log::debug!("Benchmarked model {}: {}", id, benchmark);
+            benchmarks.push(benchmark);
+        }
+
+        Ok(benchmarks)
+    }
+
+    fn load_deferred_models(&mut self) -> Result<(), Error> {
+        let deferred: Vec<u32> =
+            self.deferred_models.keys().copied().collect();
+        for id in deferred {
+            self.model_by_id(id)?;
+        }
 
         Ok(())
     }
 
+    /// Register a proc block which was compiled to a standalone WebAssembly
+    /// module that the host will provide.
+    ///
+    /// Like host-side models, the module is only loaded the first time the
+    /// proc block is used, so hosts can provide it via
+    /// [`Callbacks::get_proc_block()`] any time before then.
+    pub fn rune_proc_block_load(&mut self, name: &str) -> Result<u32, Error> {
+        let id = self.next_id();
+
+        let deferred = DeferredProcBlock {
+            name: name.to_string(),
+            parameters: Vec::new(),
+        };
+        self.deferred_proc_blocks.insert(id, deferred);
+
+        Ok(id)
+    }
+
+    pub fn rune_proc_block_set_parameter(
+        &mut self,
+        id: u32,
+        key: &str,
+        value: &str,
+    ) -> Result<(), Error> {
+        if let Some(deferred) = self.deferred_proc_blocks.get_mut(&id) {
+            deferred
+                .parameters
+                .push((key.to_string(), value.to_string()));
+            return Ok(());
+        }
+
+        self.proc_block_by_id(id)?.set_parameter(key, value)
+    }
+
+    /// Run a standalone proc block, returning the length of its encoded
+    /// outputs so the Rune can allocate a buffer for
+    /// [`HostFunctions::rune_proc_block_read_output()`].
+    pub fn rune_proc_block_transform(
+        &mut self,
+        id: u32,
+        inputs: &[u8],
+    ) -> Result<u32, Error> {
+        self.check_cancelled()?;
+
+        let outputs = self.proc_block_by_id(id)?.transform(inputs)?;
+        let len = outputs.len() as u32;
+        self.proc_block_outputs.insert(id, outputs);
+
+        Ok(len)
+    }
+
+    /// Clear a standalone proc block's state.
+    ///
+    /// Proc blocks which haven't been loaded yet don't have any state, so
+    /// this won't force them to be loaded.
+    pub fn rune_proc_block_reset(&mut self, id: u32) -> Result<(), Error> {
+        if self.deferred_proc_blocks.contains_key(&id) {
+            return Ok(());
+        }
+
+        self.proc_block_outputs.remove(&id);
+        self.proc_block_by_id(id)?.reset()
+    }
+
+    pub fn rune_proc_block_read_output(
+        &mut self,
+        id: u32,
+        buffer: &mut [u8],
+    ) -> Result<u32, Error> {
+        let outputs =
+            self.proc_block_outputs.remove(&id).with_context(|| {
+                format!("Proc block {} doesn't have any outputs to read", id)
+            })?;
+
+        anyhow::ensure!(
+            outputs.len() == buffer.len(),
+            "The Rune provided a {} byte buffer, but proc block {}'s outputs \
+             are {} bytes",
+            buffer.len(),
+            id,
+            outputs.len(),
+        );
+        buffer.copy_from_slice(&outputs);
+
+        Ok(outputs.len() as u32)
+    }
+
     pub fn request_output(&mut self, output_type: u32) -> Result<u32, Error> {
         let id = self.next_id();
 
@@ -208,6 +843,8 @@ impl HostFunctions {
         output_id: u32,
         data: &[u8],
     ) -> Result<(), Error> {
+        self.check_cancelled()?;
+
         let metadata = self.outputs.get(&output_id).with_context(|| {
             format!(
                 "Tried to write to non-existent output with ID {}",
@@ -215,9 +852,12 @@ impl HostFunctions {
             )
         })?;
 
+        let start = Instant::now();
         self.callbacks
             .write_output(output_id, metadata, data)
             .context("Writing output failed")?;
+        self.callbacks
+            .record_timing(Stage::Output, output_id, start.elapsed());
 
         Ok(())
     }
@@ -269,4 +909,677 @@ impl HostFunctions {
 
         Ok(())
     }
+
+    pub fn rune_file_open(
+        &mut self,
+        path: &str,
+        mode: u32,
+    ) -> Result<u32, Error> {
+        let mode = FileMode::from_u32(mode)?;
+        let file = self.callbacks.open_file(path, mode)?;
+        let id = self.next_id();
+
+        self.files.insert(id, (mode, file));
+
+        Ok(id)
+    }
+
+    pub fn rune_file_read(
+        &mut self,
+        file_id: u32,
+        buffer: &mut [u8],
+    ) -> Result<u32, Error> {
+        let file = self.file(file_id, FileMode::Read)?;
+        let bytes_read =
+            file.read(buffer).context("Unable to read from the file")?;
+
+        Ok(bytes_read as u32)
+    }
+
+    pub fn rune_file_write(
+        &mut self,
+        file_id: u32,
+        data: &[u8],
+    ) -> Result<u32, Error> {
+        let file = self.file(file_id, FileMode::Append)?;
+        file.write_all(data).context("Unable to write to the file")?;
+
+        Ok(data.len() as u32)
+    }
+
+    pub fn rune_file_close(&mut self, file_id: u32) -> Result<(), Error> {
+        let _ = self.files.remove(&file_id).with_context(|| {
+            format!("Tried to close non-existent file with ID {}", file_id)
+        })?;
+
+        Ok(())
+    }
+
+    fn file(
+        &mut self,
+        file_id: u32,
+        mode: FileMode,
+    ) -> Result<&mut File, Error> {
+        let (opened_for, file) =
+            self.files.get_mut(&file_id).with_context(|| {
+                format!("Tried to use non-existent file with ID {}", file_id)
+            })?;
+
+        anyhow::ensure!(
+            *opened_for == mode,
+            "File {} was opened for {}, not {}",
+            file_id,
+            opened_for,
+            mode
+        );
+
+        Ok(file)
+    }
+
+    /// The current wall-clock time, in nanoseconds since the Unix epoch.
+    pub fn rune_clock_realtime(&self) -> Result<u64, Error> {
+        let since_epoch = self
+            .callbacks
+            .now()
+            .duration_since(UNIX_EPOCH)
+            .context("The system clock is set to before the Unix epoch")?;
+
+        Ok(since_epoch.as_nanos() as u64)
+    }
+
+    /// Nanoseconds elapsed since the Rune was loaded.
+    pub fn rune_clock_monotonic(&self) -> u64 { self.callbacks.monotonic_ns() }
+
+    /// The generated pipeline is about to run a stage.
+    ///
+    /// This is also a chance to stop the Rune if the call was cancelled.
+    pub fn rune_stage_started(&mut self, name: &str) -> Result<(), Error> {
+        self.check_cancelled()?;
+        self.current_stage = Some((name.to_string(), Instant::now()));
+
+        Ok(())
+    }
+
+    /// The generated pipeline has finished running a stage, so we can record
+    /// how long it took.
+    pub fn rune_stage_finished(&mut self, name: &str) {
+        match self.current_stage.take() {
+            Some((stage, started)) if stage == name => {
+                self.callbacks.record_stage_timing(name, started.elapsed());
+            },
+            _ => log::warn!(
+                "The \"{}\" stage finished without being started",
+                name
+            ),
+        }
+    }
+
+    /// Read a WASI clock, returning the time in nanoseconds or `None` if the
+    /// clock isn't supported.
+    pub fn wasi_clock_time_get(&self, clock_id: u32) -> Option<u64> {
+        match clock_id {
+            wasi::CLOCK_REALTIME => self.rune_clock_realtime().ok(),
+            wasi::CLOCK_MONOTONIC => Some(self.rune_clock_monotonic()),
+            _ => None,
+        }
+    }
+
+    /// Handle a WASI `random_get()` call, returning the corresponding errno.
+    pub fn wasi_random_get(&self, buffer: &mut [u8]) -> u32 {
+        wasi::random_get(buffer)
+    }
+
+    /// Handle a WASI `fd_write()` call, returning the corresponding errno.
+    ///
+    /// Anything written to stdout or stderr is sent to the logger.
+    pub fn wasi_fd_write(&self, fd: u32, data: &[u8]) -> u32 {
+        let (level, target) = match fd {
+            wasi::STDOUT => (Level::Info, "stdout"),
+            wasi::STDERR => (Level::Warn, "stderr"),
+            _ => return wasi::ERRNO_BADF,
+        };
+
+        let message = String::from_utf8_lossy(data);
+        let record = SerializableRecord {
+            level,
+            message: Cow::Borrowed(message.trim_end()),
+            target: Cow::Borrowed(target),
+            ..Default::default()
+        };
+        record.with_record(|r| self.callbacks.log(r));
+
+        wasi::ERRNO_SUCCESS
+    }
+
+    pub fn wasi_proc_exit(&self, exit_code: u32) -> Result<(), Error> {
+        anyhow::bail!("The Rune exited with code {}", exit_code)
+    }
+}
+
+fn log_level(level: u32) -> Result<Level, Error> {
+    match level {
+        1 => Ok(Level::Error),
+        2 => Ok(Level::Warn),
+        3 => Ok(Level::Info),
+        4 => Ok(Level::Debug),
+        5 => Ok(Level::Trace),
+        other => anyhow::bail!("Invalid log level: {}", other),
+    }
+}
+
+fn non_empty(s: &str) -> Option<Cow<'_, str>> {
+    if s.is_empty() {
+        None
+    } else {
+        Some(Cow::Borrowed(s))
+    }
+}
+
+/// Zeroed input and output buffers for running a model without any real data.
+struct SyntheticTensors {
+    inputs: Vec<Vec<u8>>,
+    outputs: Vec<Vec<u8>>,
+}
+
+impl SyntheticTensors {
+    /// Allocate buffers for the model's tensors, returning `None` if they
+    /// don't have a fixed size.
+    fn for_model(model: &dyn Model) -> Option<Self> {
+        let zeroed = |shapes: &[Shape<'_>]| -> Option<Vec<Vec<u8>>> {
+            shapes.iter().map(|s| s.size().map(|len| vec![0; len])).collect()
+        };
+
+        Some(SyntheticTensors {
+            inputs: zeroed(model.input_shapes())?,
+            outputs: zeroed(model.output_shapes())?,
+        })
+    }
+
+    fn infer(&mut self, model: &mut dyn Model) -> Result<(), Error> {
+        let inputs: Vec<&[u8]> = self.inputs.iter().map(|i| &i[..]).collect();
+        let mut outputs: Vec<&mut [u8]> =
+            self.outputs.iter_mut().map(|o| &mut o[..]).collect();
+
+        model.infer(&inputs, &mut outputs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Mutex, time::Duration};
+
+    use hotg_rune_core::ElementType;
+    use log::Record;
+
+    use super::*;
+    use crate::models::batching;
+
+    /// A model which doubles its input and, like TensorFlow Lite, only
+    /// accepts a fixed batch size.
+    struct Doubler {
+        model_inputs: Vec<Shape<'static>>,
+        model_outputs: Vec<Shape<'static>>,
+        batches: usize,
+        inputs: Vec<Shape<'static>>,
+        outputs: Vec<Shape<'static>>,
+    }
+
+    impl Model for Doubler {
+        fn infer(
+            &mut self,
+            inputs: &[&[u8]],
+            outputs: &mut [&mut [u8]],
+        ) -> Result<(), Error> {
+            batching::infer_in_batches(
+                self.batches,
+                inputs,
+                outputs,
+                |inputs, outputs| {
+                    assert_eq!(inputs[0].len(), 2);
+
+                    for (dest, src) in outputs[0].iter_mut().zip(inputs[0]) {
+                        *dest = src * 2;
+                    }
+
+                    Ok(())
+                },
+            )
+        }
+
+        fn input_shapes(&self) -> &[Shape<'_>] { &self.inputs }
+
+        fn output_shapes(&self) -> &[Shape<'_>] { &self.outputs }
+
+        fn resize_inputs(&mut self, shapes: &[Shape<'_>]) -> Result<(), Error> {
+            self.batches = batching::batch_count(&self.model_inputs, shapes)
+                .context("Only the batch size can change")?;
+            self.inputs = shapes.iter().map(|s| s.to_owned()).collect();
+            self.outputs = batching::rebatch(&self.model_outputs, self.batches);
+
+            Ok(())
+        }
+    }
+
+    struct Dummy;
+
+    impl Callbacks for Dummy {
+        fn loaded(&self, _rune: &RuneGraph<'_>) -> Result<(), Error> { Ok(()) }
+
+        fn read_capability(
+            &self,
+            _id: u32,
+            _meta: &NodeMetadata,
+            _buffer: &mut [u8],
+        ) -> Result<usize, Error> {
+            unimplemented!()
+        }
+
+        fn write_output(
+            &self,
+            _id: u32,
+            _meta: &NodeMetadata,
+            _data: &[u8],
+        ) -> Result<(), Error> {
+            unimplemented!()
+        }
+
+        fn load_model(
+            &self,
+            _id: u32,
+            meta: &ModelMetadata<'_>,
+            _model: &[u8],
+        ) -> Result<Box<dyn Model>, Error> {
+            let inputs: Vec<_> =
+                meta.inputs.iter().map(|s| s.to_owned()).collect();
+            let outputs: Vec<_> =
+                meta.outputs.iter().map(|s| s.to_owned()).collect();
+
+            Ok(Box::new(Doubler {
+                model_inputs: inputs.clone(),
+                model_outputs: outputs.clone(),
+                batches: 1,
+                inputs,
+                outputs,
+            }))
+        }
+
+        fn get_resource(&self, _name: &str) -> Option<&[u8]> { None }
+
+        fn log(&self, _record: &Record<'_>) {}
+
+        fn monotonic_ns(&self) -> u64 { 42 }
+    }
+
+    /// The inputs and output lengths a [`Spy`] model was called with.
+    type Inferences = Arc<Mutex<Vec<(u32, Vec<Vec<u8>>, Vec<usize>)>>>;
+
+    /// A model which records every call to [`Model::infer()`], failing if it
+    /// was loaded with the `"application/x-broken"` mimetype.
+    struct Spy {
+        id: u32,
+        broken: bool,
+        inputs: Vec<Shape<'static>>,
+        outputs: Vec<Shape<'static>>,
+        inferences: Inferences,
+    }
+
+    impl Model for Spy {
+        fn infer(
+            &mut self,
+            inputs: &[&[u8]],
+            outputs: &mut [&mut [u8]],
+        ) -> Result<(), Error> {
+            anyhow::ensure!(!self.broken, "Out of memory");
+
+            self.inferences.lock().unwrap().push((
+                self.id,
+                inputs.iter().map(|i| i.to_vec()).collect(),
+                outputs.iter().map(|o| o.len()).collect(),
+            ));
+
+            Ok(())
+        }
+
+        fn input_shapes(&self) -> &[Shape<'_>] { &self.inputs }
+
+        fn output_shapes(&self) -> &[Shape<'_>] { &self.outputs }
+    }
+
+    /// Loads [`Spy`] models, including an `"external"` model provided by the
+    /// host, and records which stages were timed.
+    #[derive(Default)]
+    struct Spies {
+        inferences: Inferences,
+        stages: Mutex<Vec<String>>,
+        cancelled: bool,
+    }
+
+    impl Callbacks for Spies {
+        fn loaded(&self, _rune: &RuneGraph<'_>) -> Result<(), Error> { Ok(()) }
+
+        fn read_capability(
+            &self,
+            _id: u32,
+            _meta: &NodeMetadata,
+            _buffer: &mut [u8],
+        ) -> Result<usize, Error> {
+            unimplemented!()
+        }
+
+        fn write_output(
+            &self,
+            _id: u32,
+            _meta: &NodeMetadata,
+            _data: &[u8],
+        ) -> Result<(), Error> {
+            unimplemented!()
+        }
+
+        fn load_model(
+            &self,
+            id: u32,
+            meta: &ModelMetadata<'_>,
+            _model: &[u8],
+        ) -> Result<Box<dyn Model>, Error> {
+            Ok(Box::new(Spy {
+                id,
+                broken: meta.mimetype == "application/x-broken",
+                inputs: meta.inputs.iter().map(|s| s.to_owned()).collect(),
+                outputs: meta.outputs.iter().map(|s| s.to_owned()).collect(),
+                inferences: Arc::clone(&self.inferences),
+            }))
+        }
+
+        fn get_resource(&self, _name: &str) -> Option<&[u8]> { None }
+
+        fn get_model(&self, name: &str) -> Option<&[u8]> {
+            if name == "external" {
+                Some(&[])
+            } else {
+                None
+            }
+        }
+
+        fn log(&self, _record: &Record<'_>) {}
+
+        fn monotonic_ns(&self) -> u64 { 0 }
+
+        fn is_cancelled(&self) -> bool { self.cancelled }
+
+        fn record_stage_timing(&self, stage: &str, _elapsed: Duration) {
+            self.stages.lock().unwrap().push(stage.to_string());
+        }
+    }
+
+    /// Provides the [`crate::proc_blocks::tests::ECHO`] proc block.
+    #[cfg(feature = "wasmer")]
+    struct Echo(Vec<u8>);
+
+    #[cfg(feature = "wasmer")]
+    impl Callbacks for Echo {
+        fn loaded(&self, _rune: &RuneGraph<'_>) -> Result<(), Error> { Ok(()) }
+
+        fn read_capability(
+            &self,
+            _id: u32,
+            _meta: &NodeMetadata,
+            _buffer: &mut [u8],
+        ) -> Result<usize, Error> {
+            unimplemented!()
+        }
+
+        fn write_output(
+            &self,
+            _id: u32,
+            _meta: &NodeMetadata,
+            _data: &[u8],
+        ) -> Result<(), Error> {
+            unimplemented!()
+        }
+
+        fn load_model(
+            &self,
+            _id: u32,
+            _meta: &ModelMetadata<'_>,
+            _model: &[u8],
+        ) -> Result<Box<dyn Model>, Error> {
+            unimplemented!()
+        }
+
+        fn get_resource(&self, _name: &str) -> Option<&[u8]> { None }
+
+        fn get_proc_block(&self, name: &str) -> Option<&[u8]> {
+            if name == "echo" {
+                Some(&self.0)
+            } else {
+                None
+            }
+        }
+
+        fn log(&self, _record: &Record<'_>) {}
+
+        fn monotonic_ns(&self) -> u64 { 0 }
+    }
+
+    fn output_shape(host: &mut HostFunctions, model_id: u32) -> Shape<'static> {
+        let mut buffer = [0; 64];
+        let len = host
+            .rune_model_output_shape(model_id, 0, &mut buffer)
+            .unwrap();
+
+        std::str::from_utf8(&buffer[..len as usize])
+            .unwrap()
+            .parse()
+            .unwrap()
+    }
+
+    #[test]
+    fn run_a_model_with_different_batch_sizes() {
+        let mut host = HostFunctions::new(Arc::new(Dummy));
+        let shape = Shape::new(ElementType::U8, [1, 2].as_ref());
+        let model_id = host
+            .rune_model_load(
+                "application/x-doubler",
+                &[],
+                &[shape.clone()],
+                &[shape],
+            )
+            .unwrap();
+
+        for batch_size in [1, 4, 1] {
+            let input_shape = Shape::new(ElementType::U8, vec![batch_size, 2]);
+            host.rune_model_resize_inputs(model_id, &[input_shape.clone()])
+                .unwrap();
+
+            let output_shape = output_shape(&mut host, model_id);
+            assert_eq!(output_shape, input_shape);
+
+            let input: Vec<u8> = (0..batch_size as u8 * 2).collect();
+            let mut output = vec![0; output_shape.size().unwrap()];
+            host.rune_model_infer(model_id, &[&input], &mut [&mut output])
+                .unwrap();
+
+            let doubled: Vec<u8> = input.iter().map(|x| x * 2).collect();
+            assert_eq!(output, doubled);
+        }
+    }
+
+    #[test]
+    fn output_shapes_arent_written_to_small_buffers() {
+        let mut host = HostFunctions::new(Arc::new(Dummy));
+        let shape = Shape::new(ElementType::F32, [1, 1024].as_ref());
+        let model_id = host
+            .rune_model_load(
+                "application/x-doubler",
+                &[],
+                &[shape],
+                &["f32[1, 1024]".parse().unwrap()],
+            )
+            .unwrap();
+        let mut buffer = [0; 4];
+
+        let len = host
+            .rune_model_output_shape(model_id, 0, &mut buffer)
+            .unwrap();
+
+        assert_eq!(len as usize, "f32[1, 1024]".len());
+        assert_eq!(buffer, [0; 4]);
+    }
+
+    #[test]
+    fn warm_up_runs_each_model_once_with_zeroed_tensors() {
+        let spies = Arc::new(Spies::default());
+        let mut host = HostFunctions::new(spies.clone());
+        let shape = |s: &str| -> Shape<'static> { s.parse().unwrap() };
+        let embedded = host
+            .rune_model_load(
+                "application/x-spy",
+                &[],
+                &[shape("f32[1, 2]"), shape("u8[3]")],
+                &[shape("i16[5]")],
+            )
+            .unwrap();
+        let dynamic = host
+            .rune_model_load(
+                "application/x-spy",
+                &[],
+                &[shape("f32[?, 2]")],
+                &[shape("f32[?, 2]")],
+            )
+            .unwrap();
+        let external = host
+            .rune_model_load_external(
+                "external",
+                "application/x-spy",
+                &[shape("u8[4]")],
+                &[shape("u8[1]")],
+            )
+            .unwrap();
+
+        host.warm_up_models().unwrap();
+
+        let mut inferences = spies.inferences.lock().unwrap().clone();
+        inferences.sort();
+        assert_eq!(
+            inferences,
+            vec![
+                (embedded, vec![vec![0; 8], vec![0; 3]], vec![10]),
+                (external, vec![vec![0; 4]], vec![1]),
+            ]
+        );
+        // Models with dynamic shapes were skipped
+        assert!(inferences.iter().all(|(id, _, _)| *id != dynamic));
+    }
+
+    #[test]
+    fn warm_up_errors_say_which_model_failed() {
+        let mut host = HostFunctions::new(Arc::new(Spies::default()));
+        let shape: Shape<'static> = "u8[1]".parse().unwrap();
+        let broken = host
+            .rune_model_load(
+                "application/x-broken",
+                &[],
+                &[shape.clone()],
+                &[shape],
+            )
+            .unwrap();
+
+        let err = host.warm_up_models().unwrap_err();
+
+        assert_eq!(
+            format!("{:#}", err),
+            format!("Unable to warm up model {}: Out of memory", broken)
+        );
+    }
+
+    #[test]
+    fn stages_are_timed_when_they_finish() {
+        let spies = Arc::new(Spies::default());
+        let mut host = HostFunctions::new(spies.clone());
+
+        host.rune_stage_started("audio").unwrap();
+        host.rune_stage_finished("audio");
+        // Stages which were never started can't be timed
+        host.rune_stage_finished("fft");
+
+        assert_eq!(*spies.stages.lock().unwrap(), ["audio"]);
+    }
+
+    #[test]
+    fn cancelled_calls_stop_before_the_next_stage() {
+        let spies = Spies {
+            cancelled: true,
+            ..Default::default()
+        };
+        let mut host = HostFunctions::new(Arc::new(spies));
+
+        let err = host.rune_stage_started("model").unwrap_err();
+
+        assert!(err.is::<Cancelled>());
+    }
+
+    #[test]
+    fn wasi_clocks_come_from_the_host() {
+        let host = HostFunctions::new(Arc::new(Dummy));
+
+        assert_eq!(host.wasi_clock_time_get(wasi::CLOCK_MONOTONIC), Some(42));
+        assert!(host.wasi_clock_time_get(wasi::CLOCK_REALTIME).is_some());
+        assert_eq!(host.wasi_clock_time_get(42), None);
+    }
+
+    #[test]
+    #[cfg(feature = "wasmer")]
+    fn run_a_proc_block_provided_by_the_host() {
+        let wasm = wat::parse_str(crate::proc_blocks::tests::ECHO).unwrap();
+        let mut host = HostFunctions::new(Arc::new(Echo(wasm)));
+
+        let id = host.rune_proc_block_load("echo").unwrap();
+        // Parameters are set before the module is loaded
+        host.rune_proc_block_set_parameter(id, "suffix", "!")
+            .unwrap();
+        host.rune_proc_block_reset(id).unwrap();
+
+        let len = host.rune_proc_block_transform(id, b"hi").unwrap();
+        let mut outputs = vec![0; len as usize];
+        host.rune_proc_block_read_output(id, &mut outputs).unwrap();
+        assert_eq!(outputs, b"hi!\x01");
+
+        host.rune_proc_block_set_parameter(id, "suffix", "?")
+            .unwrap();
+        host.rune_proc_block_transform(id, b"hi").unwrap();
+        let mut too_small = [0; 2];
+        assert!(host
+            .rune_proc_block_read_output(id, &mut too_small)
+            .is_err());
+
+        host.rune_proc_block_reset(id).unwrap();
+        let len = host.rune_proc_block_transform(id, b"hi").unwrap();
+        let mut outputs = vec![0; len as usize];
+        host.rune_proc_block_read_output(id, &mut outputs).unwrap();
+        assert_eq!(outputs, b"hi?\x01");
+    }
+
+    #[test]
+    #[cfg(feature = "wasmer")]
+    fn errors_from_deferred_proc_blocks_are_reported_when_first_used() {
+        let wasm = wat::parse_str(crate::proc_blocks::tests::ECHO).unwrap();
+        let mut host = HostFunctions::new(Arc::new(Echo(wasm)));
+
+        let missing = host.rune_proc_block_load("missing").unwrap();
+        let err = host.rune_proc_block_transform(missing, b"").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "The host didn't provide the \"missing\" proc block"
+        );
+
+        let echo = host.rune_proc_block_load("echo").unwrap();
+        host.rune_proc_block_set_parameter(echo, "prefix", "?")
+            .unwrap();
+        let err = host.rune_proc_block_transform(echo, b"").unwrap_err();
+        assert_eq!(
+            format!("{:#}", err),
+            "Unable to set the \"echo\" proc block's \"prefix\" parameter to \
+             \"?\": Unknown parameter"
+        );
+    }
 }
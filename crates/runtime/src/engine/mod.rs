@@ -1,4 +1,5 @@
 mod host_functions;
+mod wasi;
 #[cfg(feature = "wasm3")]
 mod wasm3;
 #[cfg(feature = "wasmer")]
@@ -8,6 +9,7 @@ use std::sync::Arc;
 
 use anyhow::Error;
 
+use crate::metrics::ModelBenchmark;
 #[cfg(feature = "wasm3")]
 pub(crate) use self::wasm3::Wasm3Engine;
 #[cfg(feature = "wasmer")]
@@ -25,8 +27,23 @@ pub(crate) trait WebAssemblyEngine {
     /// Call the `_manifest()` function to initialize the Rune graph.
     fn init(&mut self) -> Result<(), Error>;
 
-    /// Call the `_call()` function to run the Rune.
-    fn predict(&mut self) -> Result<(), Error>;
+    /// Call the `_call()` function to run the Rune, returning its return
+    /// code.
+    fn predict(&mut self) -> Result<i32, Error>;
+
+    /// Call the `_reset()` function to clear any state held by the Rune's
+    /// proc blocks.
+    fn reset(&mut self) -> Result<(), Error>;
+
+    /// Run every model once with dummy inputs.
+    fn warm_up(&mut self) -> Result<(), Error>;
+
+    /// Run every model several times with dummy inputs and measure how it
+    /// performs.
+    fn benchmark_models(
+        &mut self,
+        iterations: usize,
+    ) -> Result<Vec<ModelBenchmark>, Error>;
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -40,4 +57,10 @@ pub enum LoadError {
     #[error(transparent)]
     #[cfg(feature = "wasmer")]
     WasmerCompile(#[from] ::wasmer::CompileError),
+    #[error(
+        "The Rune was compiled for version {rune} of the Rune ABI, but this \
+         runtime only supports versions {min} to {max}. Try rebuilding the \
+         Rune or using a compatible version of the runtime"
+    )]
+    UnsupportedAbiVersion { rune: u32, min: u32, max: u32 },
 }
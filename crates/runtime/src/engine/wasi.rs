@@ -0,0 +1,49 @@
+#![allow(dead_code)] // triggered when you don't compile with an engine feature
+
+//! A minimal, sandboxed implementation of the WASI functions a Rune (or a
+//! proc-block which accidentally pulled in `std`) is likely to import.
+//!
+//! The Rune doesn't get access to the host's filesystem, environment
+//! variables, or command-line arguments. Anything written to stdout or stderr
+//! is forwarded to the runtime's logger.
+
+/// The module WASI functions are imported from.
+pub(crate) const NAMESPACE: &str = "wasi_snapshot_preview1";
+
+pub(crate) const ERRNO_SUCCESS: u32 = 0;
+pub(crate) const ERRNO_BADF: u32 = 8;
+pub(crate) const ERRNO_INVAL: u32 = 28;
+pub(crate) const ERRNO_IO: u32 = 29;
+
+pub(crate) const CLOCK_REALTIME: u32 = 0;
+pub(crate) const CLOCK_MONOTONIC: u32 = 1;
+
+pub(crate) const STDOUT: u32 = 1;
+pub(crate) const STDERR: u32 = 2;
+
+/// Fill a buffer with random bytes from the operating system's
+/// cryptographically secure random number generator.
+pub(crate) fn random_get(buffer: &mut [u8]) -> u32 {
+    match getrandom::getrandom(buffer) {
+        Ok(_) => ERRNO_SUCCESS,
+        Err(e) => {
+            log::warn!("Unable to generate random bytes: {}", e);
+            ERRNO_IO
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn random_get_fills_the_whole_buffer() {
+        let mut buffer = [0_u8; 13];
+
+        let errno = random_get(&mut buffer);
+
+        assert_eq!(errno, ERRNO_SUCCESS);
+        assert_ne!(buffer, [0_u8; 13]);
+    }
+}
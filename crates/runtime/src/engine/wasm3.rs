@@ -13,7 +13,10 @@ use wasm3::{
 
 use crate::{
     callbacks::Callbacks,
-    engine::{host_functions::HostFunctions, LoadError, WebAssemblyEngine},
+    engine::{
+        host_functions::HostFunctions, wasi, LoadError, WebAssemblyEngine,
+    },
+    metrics::ModelBenchmark,
 };
 
 const STACK_SIZE: u32 = 1024 * 16;
@@ -88,18 +91,60 @@ impl WebAssemblyEngine for Wasm3Engine {
 
         Linker::new(instance, &last_error, &host_functions)
             .link("_debug", debug)?
+            .link("rune_log", rune_log)?
+            .link("rune_error", rune_error)?
             .link("request_capability", request_capability)?
             .link("request_capability_set_param", request_capability_set_param)?
+            .link(
+                "rune_capability_set_string_param",
+                rune_capability_set_string_param,
+            )?
             .link("request_provider_response", request_provider_response)?
             .link("tfm_model_invoke", tfm_model_invoke)?
             .link("tfm_preload_model", tfm_preload_model)?
             .link("rune_model_load", rune_model_load)?
+            .link("rune_model_load_external", rune_model_load_external)?
+            .link("rune_model_load_encrypted", rune_model_load_encrypted)?
+            .link("rune_model_variant", rune_model_variant)?
             .link("rune_model_infer", rune_model_infer)?
+            .link("rune_model_resize_inputs", rune_model_resize_inputs)?
+            .link("rune_model_output_shape", rune_model_output_shape)?
+            .link("rune_proc_block_load", rune_proc_block_load)?
+            .link(
+                "rune_proc_block_set_parameter",
+                rune_proc_block_set_parameter,
+            )?
+            .link("rune_proc_block_transform", rune_proc_block_transform)?
+            .link("rune_proc_block_read_output", rune_proc_block_read_output)?
+            .link("rune_proc_block_reset", rune_proc_block_reset)?
             .link("request_output", request_output)?
             .link("consume_output", consume_output)?
             .link("rune_resource_open", rune_resource_open)?
             .link("rune_resource_read", rune_resource_read)?
-            .link("rune_resource_close", rune_resource_close)?;
+            .link("rune_resource_close", rune_resource_close)?
+            .link("rune_capability_stream_open", rune_capability_stream_open)?
+            .link("rune_capability_stream_read", rune_capability_stream_read)?
+            .link("rune_capability_stream_close", rune_capability_stream_close)?
+            .link("rune_clock_realtime", rune_clock_realtime)?
+            .link("rune_clock_monotonic", rune_clock_monotonic)?
+            .link("rune_stage_started", rune_stage_started)?
+            .link("rune_stage_finished", rune_stage_finished)?
+            .link("rune_file_open", rune_file_open)?
+            .link("rune_file_read", rune_file_read)?
+            .link("rune_file_write", rune_file_write)?
+            .link("rune_file_close", rune_file_close)?
+            .link_wasi("args_get", wasi_args_get)?
+            .link_wasi("args_sizes_get", wasi_args_sizes_get)?
+            .link_wasi("environ_get", wasi_environ_get)?
+            .link_wasi("environ_sizes_get", wasi_environ_sizes_get)?
+            .link_wasi("clock_time_get", wasi_clock_time_get)?
+            .link_wasi("random_get", wasi_random_get)?
+            .link_wasi("fd_write", wasi_fd_write)?
+            .link_wasi("fd_close", wasi_fd_close)?
+            .link_wasi("fd_seek", wasi_fd_seek)?
+            .link_wasi("fd_prestat_get", wasi_fd_prestat_get)?
+            .link_wasi("fd_prestat_dir_name", wasi_fd_prestat_dir_name)?
+            .link_wasi("proc_exit", wasi_proc_exit)?;
 
         Ok(Wasm3Engine {
             runtime,
@@ -117,7 +162,7 @@ impl WebAssemblyEngine for Wasm3Engine {
         self.callbacks.loaded(&graph)
     }
 
-    fn predict(&mut self) -> Result<(), Error> {
+    fn predict(&mut self) -> Result<i32, Error> {
         // Note: these three parameters used to contain the ID for the RAND
         // capability plus the tensor type sent to the SERIAL output. They are
         // now redundant because the pipeline nodes are compiled directly into
@@ -125,13 +170,29 @@ impl WebAssemblyEngine for Wasm3Engine {
         //
         // We should be able to change the _call function's signature once
         // hotg-ai/rune#28 lands.
-        let _: i32 =
-            self.call("_call", (0_i32, 0_i32, 0_i32), |f, (a, b, c)| {
-                f.call(a, b, c)
-            })?;
+        self.call("_call", (0_i32, 0_i32, 0_i32), |f, (a, b, c)| {
+            f.call(a, b, c)
+        })
+    }
 
+    fn reset(&mut self) -> Result<(), Error> {
+        let _: i32 = self.call("_reset", (), |f, _| f.call())?;
         Ok(())
     }
+
+    fn warm_up(&mut self) -> Result<(), Error> {
+        self.host_functions.lock().unwrap().warm_up_models()
+    }
+
+    fn benchmark_models(
+        &mut self,
+        iterations: usize,
+    ) -> Result<Vec<ModelBenchmark>, Error> {
+        self.host_functions
+            .lock()
+            .unwrap()
+            .benchmark_models(iterations)
+    }
 }
 
 struct Linker<'rt> {
@@ -156,6 +217,43 @@ impl<'rt> Linker<'rt> {
     fn link<F, Ret, Args>(
         &mut self,
         name: &str,
+        func: F,
+    ) -> Result<&mut Self, Error>
+    where
+        Args: WasmArgs,
+        Ret: WasmType,
+        F: for<'cc> FnMut(
+                CallContext<'cc>,
+                &mut HostFunctions,
+                Args,
+            ) -> Result<Ret, Error>
+            + 'static,
+    {
+        self.link_in_namespace("env", name, func)
+    }
+
+    fn link_wasi<F, Ret, Args>(
+        &mut self,
+        name: &str,
+        func: F,
+    ) -> Result<&mut Self, Error>
+    where
+        Args: WasmArgs,
+        Ret: WasmType,
+        F: for<'cc> FnMut(
+                CallContext<'cc>,
+                &mut HostFunctions,
+                Args,
+            ) -> Result<Ret, Error>
+            + 'static,
+    {
+        self.link_in_namespace(wasi::NAMESPACE, name, func)
+    }
+
+    fn link_in_namespace<F, Ret, Args>(
+        &mut self,
+        namespace: &str,
+        name: &str,
         mut func: F,
     ) -> Result<&mut Self, Error>
     where
@@ -172,7 +270,7 @@ impl<'rt> Linker<'rt> {
         let error_location = Arc::clone(&self.last_error);
 
         let ret = self.instance.link_closure(
-            "env",
+            namespace,
             name,
             move |cc: CallContext<'_>, args: Args| {
                 let mut host_functions = host_functions
@@ -211,6 +309,53 @@ fn debug(
     Ok(0)
 }
 
+fn rune_log(
+    cc: CallContext<'_>,
+    host: &mut HostFunctions,
+    record: u32,
+) -> Result<u32, Error> {
+    let LogRecord {
+        level,
+        target,
+        module_path,
+        file,
+        line,
+        message,
+    } = unsafe {
+        cc.array::<LogRecord>(record, 1)
+            .context("Unable to read the log record")?[0]
+    };
+
+    let target = target.read(&cc).context("Invalid target")?;
+    let module_path = module_path.read(&cc).context("Invalid module path")?;
+    let file = file.read(&cc).context("Invalid file")?;
+    let message = message.read(&cc).context("Invalid message")?;
+
+    host.log(level, target, module_path, file, line, message)?;
+
+    Ok(0)
+}
+
+fn rune_error(
+    cc: CallContext<'_>,
+    host: &mut HostFunctions,
+    record: u32,
+) -> Result<u32, Error> {
+    let ErrorRecord {
+        kind,
+        stage,
+        message,
+    } = unsafe {
+        cc.array::<ErrorRecord>(record, 1)
+            .context("Unable to read the error record")?[0]
+    };
+
+    let stage = stage.read(&cc).context("Invalid stage")?;
+    let message = message.read(&cc).context("Invalid message")?;
+
+    host.rune_error(kind, stage, message)
+}
+
 fn request_capability(
     _cc: CallContext<'_>,
     host: &mut HostFunctions,
@@ -259,6 +404,29 @@ fn request_capability_set_param(
     Ok(0)
 }
 
+fn rune_capability_set_string_param(
+    cc: CallContext<'_>,
+    host: &mut HostFunctions,
+    (capability_id, key_ptr, key_len, value_ptr, value_len): (
+        u32,
+        u32,
+        u32,
+        u32,
+        u32,
+    ),
+) -> Result<u32, Error> {
+    let key = cc
+        .read_string(key_ptr, key_len)
+        .context("Unable to read the key")?;
+    let value = cc
+        .read_string(value_ptr, value_len)
+        .context("Unable to read the value")?;
+
+    host.request_capability_set_param(capability_id, key, value)?;
+
+    Ok(0)
+}
+
 fn request_provider_response(
     cc: CallContext<'_>,
     host: &mut HostFunctions,
@@ -299,7 +467,7 @@ fn rune_model_infer(
             .copied()
             .enumerate()
             .map(|(i, ptr): (usize, u32)| {
-                cc.array(ptr, input_shapes[i].size().unwrap() as u32)
+                cc.array(ptr, tensor_size(&input_shapes[i])? as u32)
             })
             .collect::<Result<Vec<_>, _>>()?
     };
@@ -311,7 +479,7 @@ fn rune_model_infer(
             .copied()
             .enumerate()
             .map(|(i, ptr): (usize, u32)| {
-                cc.array_mut(ptr, input_shapes[i].size().unwrap() as u32)
+                cc.array_mut(ptr, tensor_size(&output_shapes[i])? as u32)
             })
             .collect::<Result<Vec<_>, _>>()?
     };
@@ -321,6 +489,32 @@ fn rune_model_infer(
     Ok(0)
 }
 
+fn rune_model_resize_inputs(
+    cc: CallContext<'_>,
+    host: &mut HostFunctions,
+    (model_id, input_descriptors, input_len): (u32, u32, u32),
+) -> Result<u32, Error> {
+    let input_shapes = read_shapes(&cc, input_descriptors, input_len)?;
+    host.rune_model_resize_inputs(model_id, &input_shapes)?;
+
+    Ok(0)
+}
+
+fn rune_model_output_shape(
+    cc: CallContext<'_>,
+    host: &mut HostFunctions,
+    (model_id, index, buffer, len): (u32, u32, u32, u32),
+) -> Result<u32, Error> {
+    let buffer = unsafe { cc.array_mut(buffer, len)? };
+    host.rune_model_output_shape(model_id, index, buffer)
+}
+
+fn tensor_size(shape: &Shape<'_>) -> Result<usize, Error> {
+    shape
+        .size()
+        .with_context(|| format!("{} tensors don't have a fixed size", shape))
+}
+
 fn rune_model_load(
     cc: CallContext<'_>,
     host: &mut HostFunctions,
@@ -343,6 +537,68 @@ fn rune_model_load(
     host.rune_model_load(mimetype, model, &inputs, &outputs)
 }
 
+fn rune_model_load_external(
+    cc: CallContext<'_>,
+    host: &mut HostFunctions,
+    (
+        name,
+        name_len,
+        mimetype,
+        mimetype_len,
+        input_descriptors,
+        input_len,
+        output_descriptors,
+        output_len,
+    ): (u32, u32, u32, u32, u32, u32, u32, u32),
+) -> Result<u32, Error> {
+    let name = cc.read_string(name, name_len)?;
+    let mimetype = cc.read_string(mimetype, mimetype_len)?;
+    let inputs = read_shapes(&cc, input_descriptors, input_len)?;
+    let outputs = read_shapes(&cc, output_descriptors, output_len)?;
+
+    host.rune_model_load_external(name, mimetype, &inputs, &outputs)
+}
+
+fn rune_model_load_encrypted(
+    cc: CallContext<'_>,
+    host: &mut HostFunctions,
+    (
+        name,
+        name_len,
+        mimetype,
+        mimetype_len,
+        model,
+        model_len,
+        input_descriptors,
+        input_len,
+        output_descriptors,
+        output_len,
+    ): (u32, u32, u32, u32, u32, u32, u32, u32, u32, u32),
+) -> Result<u32, Error> {
+    let name = cc.read_string(name, name_len)?;
+    let mimetype = cc.read_string(mimetype, mimetype_len)?;
+    let model = unsafe { cc.array(model, model_len)? };
+    let inputs = read_shapes(&cc, input_descriptors, input_len)?;
+    let outputs = read_shapes(&cc, output_descriptors, output_len)?;
+
+    host.rune_model_load_encrypted(name, mimetype, model, &inputs, &outputs)
+}
+
+fn rune_model_variant(
+    cc: CallContext<'_>,
+    host: &mut HostFunctions,
+    (name, name_len, variants, variant_len): (u32, u32, u32, u32),
+) -> Result<u32, Error> {
+    let name = cc.read_string(name, name_len)?;
+    let variants: &[StringRef] = unsafe { cc.array(variants, variant_len)? };
+    let variants = variants
+        .iter()
+        .map(|v| v.read(&cc))
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    host.rune_model_variant(name, &variants)
+}
+
 fn read_shapes(
     cc: &CallContext<'_>,
     input_descriptors: u32,
@@ -370,6 +626,31 @@ struct StringRef {
     len: u32,
 }
 
+impl StringRef {
+    fn read<'cc>(&self, cc: &'cc CallContext<'_>) -> Result<&'cc str, Error> {
+        cc.read_string(self.data, self.len)
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+#[repr(C)]
+struct LogRecord {
+    level: u32,
+    target: StringRef,
+    module_path: StringRef,
+    file: StringRef,
+    line: u32,
+    message: StringRef,
+}
+
+#[derive(Debug, Copy, Clone)]
+#[repr(C)]
+struct ErrorRecord {
+    kind: u32,
+    stage: StringRef,
+    message: StringRef,
+}
+
 fn tfm_preload_model(
     _cc: CallContext<'_>,
     host: &mut HostFunctions,
@@ -398,6 +679,61 @@ fn consume_output(
     Ok(len)
 }
 
+fn rune_proc_block_load(
+    cc: CallContext<'_>,
+    host: &mut HostFunctions,
+    (name, len): (u32, u32),
+) -> Result<u32, Error> {
+    let name = cc
+        .read_string(name, len)
+        .context("Unable to read the proc block's name")?;
+    host.rune_proc_block_load(name)
+}
+
+fn rune_proc_block_set_parameter(
+    cc: CallContext<'_>,
+    host: &mut HostFunctions,
+    (id, key_ptr, key_len, value_ptr, value_len): (u32, u32, u32, u32, u32),
+) -> Result<u32, Error> {
+    let key = cc
+        .read_string(key_ptr, key_len)
+        .context("Unable to read the key")?;
+    let value = cc
+        .read_string(value_ptr, value_len)
+        .context("Unable to read the value")?;
+
+    host.rune_proc_block_set_parameter(id, key, value)?;
+
+    Ok(0)
+}
+
+fn rune_proc_block_transform(
+    cc: CallContext<'_>,
+    host: &mut HostFunctions,
+    (id, inputs, len): (u32, u32, u32),
+) -> Result<u32, Error> {
+    let inputs = unsafe { cc.array(inputs, len)? };
+    host.rune_proc_block_transform(id, inputs)
+}
+
+fn rune_proc_block_read_output(
+    cc: CallContext<'_>,
+    host: &mut HostFunctions,
+    (id, buffer, len): (u32, u32, u32),
+) -> Result<u32, Error> {
+    let buffer = unsafe { cc.array_mut(buffer, len)? };
+    host.rune_proc_block_read_output(id, buffer)
+}
+
+fn rune_proc_block_reset(
+    _cc: CallContext<'_>,
+    host: &mut HostFunctions,
+    id: u32,
+) -> Result<u32, Error> {
+    host.rune_proc_block_reset(id)?;
+    Ok(0)
+}
+
 fn rune_resource_open(
     cc: CallContext<'_>,
     host: &mut HostFunctions,
@@ -425,6 +761,234 @@ fn rune_resource_close(
     Ok(0)
 }
 
+fn rune_capability_stream_open(
+    _cc: CallContext<'_>,
+    host: &mut HostFunctions,
+    capability_id: u32,
+) -> Result<u32, Error> {
+    host.rune_capability_stream_open(capability_id)
+}
+
+fn rune_capability_stream_read(
+    cc: CallContext<'_>,
+    host: &mut HostFunctions,
+    (id, buffer, len): (u32, u32, u32),
+) -> Result<u32, Error> {
+    let buffer = unsafe { cc.array_mut(buffer, len)? };
+    host.rune_capability_stream_read(id, buffer)
+}
+
+fn rune_capability_stream_close(
+    _cc: CallContext<'_>,
+    host: &mut HostFunctions,
+    id: u32,
+) -> Result<u32, Error> {
+    host.rune_capability_stream_close(id)?;
+    Ok(0)
+}
+
+fn rune_clock_realtime(
+    _cc: CallContext<'_>,
+    host: &mut HostFunctions,
+    _: (),
+) -> Result<u64, Error> {
+    host.rune_clock_realtime()
+}
+
+fn rune_clock_monotonic(
+    _cc: CallContext<'_>,
+    host: &mut HostFunctions,
+    _: (),
+) -> Result<u64, Error> {
+    Ok(host.rune_clock_monotonic())
+}
+
+fn rune_stage_started(
+    cc: CallContext<'_>,
+    host: &mut HostFunctions,
+    (name, len): (u32, u32),
+) -> Result<u32, Error> {
+    let name = cc
+        .read_string(name, len)
+        .context("Unable to read the stage's name")?;
+    host.rune_stage_started(name)?;
+
+    Ok(0)
+}
+
+fn rune_stage_finished(
+    cc: CallContext<'_>,
+    host: &mut HostFunctions,
+    (name, len): (u32, u32),
+) -> Result<u32, Error> {
+    let name = cc
+        .read_string(name, len)
+        .context("Unable to read the stage's name")?;
+    host.rune_stage_finished(name);
+
+    Ok(0)
+}
+
+fn rune_file_open(
+    cc: CallContext<'_>,
+    host: &mut HostFunctions,
+    (path, len, mode): (u32, u32, u32),
+) -> Result<u32, Error> {
+    let path = cc.read_string(path, len)?;
+    host.rune_file_open(path, mode)
+}
+
+fn rune_file_read(
+    cc: CallContext<'_>,
+    host: &mut HostFunctions,
+    (id, buffer, len): (u32, u32, u32),
+) -> Result<u32, Error> {
+    let buffer = unsafe { cc.array_mut(buffer, len)? };
+    host.rune_file_read(id, buffer)
+}
+
+fn rune_file_write(
+    cc: CallContext<'_>,
+    host: &mut HostFunctions,
+    (id, data, len): (u32, u32, u32),
+) -> Result<u32, Error> {
+    let data = unsafe { cc.array(data, len)? };
+    host.rune_file_write(id, data)
+}
+
+fn rune_file_close(
+    _cc: CallContext<'_>,
+    host: &mut HostFunctions,
+    id: u32,
+) -> Result<u32, Error> {
+    host.rune_file_close(id)?;
+    Ok(0)
+}
+
+fn wasi_args_get(
+    _cc: CallContext<'_>,
+    _host: &mut HostFunctions,
+    (_argv, _argv_buf): (u32, u32),
+) -> Result<u32, Error> {
+    Ok(wasi::ERRNO_SUCCESS)
+}
+
+fn wasi_args_sizes_get(
+    cc: CallContext<'_>,
+    _host: &mut HostFunctions,
+    (argc, argv_buf_size): (u32, u32),
+) -> Result<u32, Error> {
+    cc.write_bytes(argc, &0_u32.to_le_bytes())?;
+    cc.write_bytes(argv_buf_size, &0_u32.to_le_bytes())?;
+
+    Ok(wasi::ERRNO_SUCCESS)
+}
+
+fn wasi_environ_get(
+    _cc: CallContext<'_>,
+    _host: &mut HostFunctions,
+    (_environ, _environ_buf): (u32, u32),
+) -> Result<u32, Error> {
+    Ok(wasi::ERRNO_SUCCESS)
+}
+
+fn wasi_environ_sizes_get(
+    cc: CallContext<'_>,
+    _host: &mut HostFunctions,
+    (environ_count, environ_buf_size): (u32, u32),
+) -> Result<u32, Error> {
+    cc.write_bytes(environ_count, &0_u32.to_le_bytes())?;
+    cc.write_bytes(environ_buf_size, &0_u32.to_le_bytes())?;
+
+    Ok(wasi::ERRNO_SUCCESS)
+}
+
+fn wasi_clock_time_get(
+    cc: CallContext<'_>,
+    host: &mut HostFunctions,
+    (clock_id, _precision, time): (u32, u64, u32),
+) -> Result<u32, Error> {
+    match host.wasi_clock_time_get(clock_id) {
+        Some(nanos) => {
+            cc.write_bytes(time, &nanos.to_le_bytes())?;
+            Ok(wasi::ERRNO_SUCCESS)
+        },
+        None => Ok(wasi::ERRNO_INVAL),
+    }
+}
+
+fn wasi_random_get(
+    cc: CallContext<'_>,
+    host: &mut HostFunctions,
+    (buffer, len): (u32, u32),
+) -> Result<u32, Error> {
+    let buffer = unsafe { cc.array_mut(buffer, len)? };
+    Ok(host.wasi_random_get(buffer))
+}
+
+fn wasi_fd_write(
+    cc: CallContext<'_>,
+    host: &mut HostFunctions,
+    (fd, iovs, iovs_len, nwritten): (u32, u32, u32, u32),
+) -> Result<u32, Error> {
+    let iovs: &[StringRef] = unsafe { cc.array(iovs, iovs_len)? };
+
+    let mut data = Vec::new();
+    for iov in iovs {
+        let StringRef { data: ptr, len } = *iov;
+        data.extend_from_slice(unsafe { cc.array(ptr, len)? });
+    }
+
+    let errno = host.wasi_fd_write(fd, &data);
+
+    if errno == wasi::ERRNO_SUCCESS {
+        cc.write_bytes(nwritten, &(data.len() as u32).to_le_bytes())?;
+    }
+
+    Ok(errno)
+}
+
+fn wasi_fd_close(
+    _cc: CallContext<'_>,
+    _host: &mut HostFunctions,
+    _fd: u32,
+) -> Result<u32, Error> {
+    Ok(wasi::ERRNO_BADF)
+}
+
+fn wasi_fd_seek(
+    _cc: CallContext<'_>,
+    _host: &mut HostFunctions,
+    (_fd, _offset, _whence, _new_offset): (u32, i64, u32, u32),
+) -> Result<u32, Error> {
+    Ok(wasi::ERRNO_BADF)
+}
+
+fn wasi_fd_prestat_get(
+    _cc: CallContext<'_>,
+    _host: &mut HostFunctions,
+    (_fd, _prestat): (u32, u32),
+) -> Result<u32, Error> {
+    // There are no pre-opened directories
+    Ok(wasi::ERRNO_BADF)
+}
+
+fn wasi_fd_prestat_dir_name(
+    _cc: CallContext<'_>,
+    _host: &mut HostFunctions,
+    (_fd, _path, _path_len): (u32, u32, u32),
+) -> Result<u32, Error> {
+    Ok(wasi::ERRNO_BADF)
+}
+
+fn wasi_proc_exit(
+    _cc: CallContext<'_>,
+    host: &mut HostFunctions,
+    exit_code: u32,
+) -> Result<(), Error> {
+    host.wasi_proc_exit(exit_code)
+}
+
 trait Wasm3ResultExt<T> {
     fn to_anyhow(self) -> Result<T, Error>;
 }
@@ -460,6 +1024,12 @@ trait CallContextExt<'a> {
         let bytes = unsafe { self.array(ptr, len)? };
         std::str::from_utf8(bytes).map_err(Error::from)
     }
+
+    fn write_bytes(&self, ptr: u32, bytes: &[u8]) -> Result<(), Error> {
+        let dest = unsafe { self.array_mut(ptr, bytes.len() as u32)? };
+        dest.copy_from_slice(bytes);
+        Ok(())
+    }
 }
 
 impl<'a> CallContextExt<'a> for CallContext<'a> {
@@ -538,6 +1108,8 @@ mod tests {
     }
 
     impl Callbacks for Spy {
+        fn monotonic_ns(&self) -> u64 { 0 }
+
         fn read_capability(
             &self,
             _id: u32,
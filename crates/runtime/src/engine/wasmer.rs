@@ -7,13 +7,16 @@ use std::{
 use anyhow::{Context, Error};
 use hotg_rune_core::Shape;
 use wasmer::{
-    Array, Function, Instance, LazyInit, Memory, Module, NativeFunc,
+    Array, Function, Instance, Item, LazyInit, Memory, Module, NativeFunc,
     RuntimeError, Store, ValueType, WasmPtr, WasmerEnv,
 };
 
 use crate::{
     callbacks::Callbacks,
-    engine::{host_functions::HostFunctions, LoadError, WebAssemblyEngine},
+    engine::{
+        host_functions::HostFunctions, wasi, LoadError, WebAssemblyEngine,
+    },
+    metrics::ModelBenchmark,
 };
 
 pub struct WasmerEngine {
@@ -43,18 +46,56 @@ impl WebAssemblyEngine for WasmerEngine {
         let imports = wasmer::imports! {
             "env" => {
                 "_debug" => Function::new_native_with_env(&store, env.clone(), debug),
+                "rune_log" => Function::new_native_with_env(&store, env.clone(), rune_log),
+                "rune_error" => Function::new_native_with_env(&store, env.clone(), rune_error),
                 "request_capability" => Function::new_native_with_env(&store, env.clone(), request_capability),
                 "request_capability_set_param" => Function::new_native_with_env(&store, env.clone(), request_capability_set_param),
+                "rune_capability_set_string_param" => Function::new_native_with_env(&store, env.clone(), rune_capability_set_string_param),
                 "request_provider_response" => Function::new_native_with_env(&store, env.clone(), request_provider_response),
                 "tfm_model_invoke" => Function::new_native_with_env(&store, env.clone(), tfm_model_invoke),
                 "tfm_preload_model" => Function::new_native_with_env(&store, env.clone(), tfm_preload_model),
                 "rune_model_load" => Function::new_native_with_env(&store, env.clone(), rune_model_load),
+                "rune_model_load_external" => Function::new_native_with_env(&store, env.clone(), rune_model_load_external),
+                "rune_model_load_encrypted" => Function::new_native_with_env(&store, env.clone(), rune_model_load_encrypted),
+                "rune_model_variant" => Function::new_native_with_env(&store, env.clone(), rune_model_variant),
                 "rune_model_infer" => Function::new_native_with_env(&store, env.clone(), rune_model_infer),
+                "rune_model_resize_inputs" => Function::new_native_with_env(&store, env.clone(), rune_model_resize_inputs),
+                "rune_model_output_shape" => Function::new_native_with_env(&store, env.clone(), rune_model_output_shape),
+                "rune_proc_block_load" => Function::new_native_with_env(&store, env.clone(), rune_proc_block_load),
+                "rune_proc_block_set_parameter" => Function::new_native_with_env(&store, env.clone(), rune_proc_block_set_parameter),
+                "rune_proc_block_transform" => Function::new_native_with_env(&store, env.clone(), rune_proc_block_transform),
+                "rune_proc_block_read_output" => Function::new_native_with_env(&store, env.clone(), rune_proc_block_read_output),
+                "rune_proc_block_reset" => Function::new_native_with_env(&store, env.clone(), rune_proc_block_reset),
                 "request_output" => Function::new_native_with_env(&store, env.clone(), request_output),
                 "consume_output" => Function::new_native_with_env(&store, env.clone(), consume_output),
                 "rune_resource_open" => Function::new_native_with_env(&store, env.clone(), rune_resource_open),
                 "rune_resource_read" => Function::new_native_with_env(&store, env.clone(), rune_resource_read),
                 "rune_resource_close" => Function::new_native_with_env(&store, env.clone(), rune_resource_close),
+                "rune_capability_stream_open" => Function::new_native_with_env(&store, env.clone(), rune_capability_stream_open),
+                "rune_capability_stream_read" => Function::new_native_with_env(&store, env.clone(), rune_capability_stream_read),
+                "rune_capability_stream_close" => Function::new_native_with_env(&store, env.clone(), rune_capability_stream_close),
+                "rune_clock_realtime" => Function::new_native_with_env(&store, env.clone(), rune_clock_realtime),
+                "rune_clock_monotonic" => Function::new_native_with_env(&store, env.clone(), rune_clock_monotonic),
+                "rune_stage_started" => Function::new_native_with_env(&store, env.clone(), rune_stage_started),
+                "rune_stage_finished" => Function::new_native_with_env(&store, env.clone(), rune_stage_finished),
+                "rune_file_open" => Function::new_native_with_env(&store, env.clone(), rune_file_open),
+                "rune_file_read" => Function::new_native_with_env(&store, env.clone(), rune_file_read),
+                "rune_file_write" => Function::new_native_with_env(&store, env.clone(), rune_file_write),
+                "rune_file_close" => Function::new_native_with_env(&store, env.clone(), rune_file_close),
+            },
+            wasi::NAMESPACE => {
+                "args_get" => Function::new_native_with_env(&store, env.clone(), wasi_args_get),
+                "args_sizes_get" => Function::new_native_with_env(&store, env.clone(), wasi_args_sizes_get),
+                "environ_get" => Function::new_native_with_env(&store, env.clone(), wasi_environ_get),
+                "environ_sizes_get" => Function::new_native_with_env(&store, env.clone(), wasi_environ_sizes_get),
+                "clock_time_get" => Function::new_native_with_env(&store, env.clone(), wasi_clock_time_get),
+                "random_get" => Function::new_native_with_env(&store, env.clone(), wasi_random_get),
+                "fd_write" => Function::new_native_with_env(&store, env.clone(), wasi_fd_write),
+                "fd_close" => Function::new_native_with_env(&store, env.clone(), wasi_fd_close),
+                "fd_seek" => Function::new_native_with_env(&store, env.clone(), wasi_fd_seek),
+                "fd_prestat_get" => Function::new_native_with_env(&store, env.clone(), wasi_fd_prestat_get),
+                "fd_prestat_dir_name" => Function::new_native_with_env(&store, env.clone(), wasi_fd_prestat_dir_name),
+                "proc_exit" => Function::new_native_with_env(&store, env.clone(), wasi_proc_exit),
             }
         };
 
@@ -81,17 +122,41 @@ impl WebAssemblyEngine for WasmerEngine {
         self.callbacks.loaded(&graph)
     }
 
-    fn predict(&mut self) -> Result<(), Error> {
+    fn predict(&mut self) -> Result<i32, Error> {
         let call: NativeFunc<(i32, i32, i32), i32> = self
             .instance
             .exports
             .get_native_function("_call")
             .context("Unable to get the \"_call\" function")?;
 
-        call.call(0, 0, 0).map_err(unwrap_anyhow_error)?;
+        call.call(0, 0, 0).map_err(unwrap_anyhow_error)
+    }
+
+    fn reset(&mut self) -> Result<(), Error> {
+        let reset: NativeFunc<(), i32> = self
+            .instance
+            .exports
+            .get_native_function("_reset")
+            .context("Unable to get the \"_reset\" function")?;
+
+        reset.call().map_err(unwrap_anyhow_error)?;
 
         Ok(())
     }
+
+    fn warm_up(&mut self) -> Result<(), Error> {
+        self.host_functions.lock().unwrap().warm_up_models()
+    }
+
+    fn benchmark_models(
+        &mut self,
+        iterations: usize,
+    ) -> Result<Vec<ModelBenchmark>, Error> {
+        self.host_functions
+            .lock()
+            .unwrap()
+            .benchmark_models(iterations)
+    }
 }
 
 #[derive(Debug)]
@@ -152,6 +217,220 @@ fn debug(
     }
 }
 
+fn rune_log(
+    env: &Env,
+    record: WasmPtr<LogRecord, Item>,
+) -> Result<u32, RuntimeError> {
+    let memory = env
+        .memory
+        .get_ref()
+        .context("The memory isn't initialized")
+        .map_err(runtime_error)?;
+
+    let LogRecord {
+        level,
+        target,
+        module_path,
+        file,
+        line,
+        message,
+    } = record
+        .deref(memory)
+        .context("Unable to read the log record")
+        .map_err(runtime_error)?
+        .get();
+
+    // Safety: this function isn't reentrant, so we don't need to worry about
+    // concurrent mutations.
+    unsafe {
+        let target = target
+            .read(memory)
+            .context("Invalid target")
+            .map_err(runtime_error)?;
+        let module_path = module_path
+            .read(memory)
+            .context("Invalid module path")
+            .map_err(runtime_error)?;
+        let file = file
+            .read(memory)
+            .context("Invalid file")
+            .map_err(runtime_error)?;
+        let message = message
+            .read(memory)
+            .context("Invalid message")
+            .map_err(runtime_error)?;
+
+        env.host_functions
+            .lock()
+            .unwrap()
+            .log(level, target, module_path, file, line, message)
+            .map_err(runtime_error)?;
+    }
+
+    Ok(0)
+}
+
+fn rune_error(
+    env: &Env,
+    record: WasmPtr<ErrorRecord, Item>,
+) -> Result<u32, RuntimeError> {
+    let memory = env
+        .memory
+        .get_ref()
+        .context("The memory isn't initialized")
+        .map_err(runtime_error)?;
+
+    let ErrorRecord {
+        kind,
+        stage,
+        message,
+    } = record
+        .deref(memory)
+        .context("Unable to read the error record")
+        .map_err(runtime_error)?
+        .get();
+
+    // Safety: this function isn't reentrant, so we don't need to worry about
+    // concurrent mutations.
+    unsafe {
+        let stage = stage
+            .read(memory)
+            .context("Invalid stage")
+            .map_err(runtime_error)?;
+        let message = message
+            .read(memory)
+            .context("Invalid message")
+            .map_err(runtime_error)?;
+
+        env.host_functions
+            .lock()
+            .unwrap()
+            .rune_error(kind, stage, message)
+            .map_err(runtime_error)
+    }
+}
+
+fn rune_proc_block_load(
+    env: &Env,
+    name: WasmPtr<u8, Array>,
+    len: u32,
+) -> Result<u32, RuntimeError> {
+    let memory = env
+        .memory
+        .get_ref()
+        .context("The memory isn't initialized")
+        .map_err(runtime_error)?;
+
+    // Safety: this function isn't reentrant, so we don't need to worry about
+    // concurrent mutations.
+    let name = unsafe {
+        name.get_utf8_str(memory, len)
+            .context("Unable to read the proc block's name")
+            .map_err(runtime_error)?
+    };
+
+    env.host_functions
+        .lock()
+        .unwrap()
+        .rune_proc_block_load(name)
+        .map_err(runtime_error)
+}
+
+fn rune_proc_block_set_parameter(
+    env: &Env,
+    id: u32,
+    key_ptr: WasmPtr<u8, Array>,
+    key_len: u32,
+    value_ptr: WasmPtr<u8, Array>,
+    value_len: u32,
+) -> Result<u32, RuntimeError> {
+    let memory = env
+        .memory
+        .get_ref()
+        .context("The memory isn't initialized")
+        .map_err(runtime_error)?;
+
+    // Safety: this function isn't reentrant, so we don't need to worry about
+    // concurrent mutations.
+    unsafe {
+        let key = key_ptr
+            .get_utf8_str(memory, key_len)
+            .context("Unable to read the key")
+            .map_err(runtime_error)?;
+        let value = value_ptr
+            .get_utf8_str(memory, value_len)
+            .context("Unable to read the value")
+            .map_err(runtime_error)?;
+
+        env.host_functions
+            .lock()
+            .unwrap()
+            .rune_proc_block_set_parameter(id, key, value)
+            .map_err(runtime_error)?;
+
+        Ok(0)
+    }
+}
+
+fn rune_proc_block_transform(
+    env: &Env,
+    id: u32,
+    inputs: WasmPtr<u8, Array>,
+    len: u32,
+) -> Result<u32, RuntimeError> {
+    let memory = env
+        .memory
+        .get_ref()
+        .context("The memory isn't initialized")
+        .map_err(runtime_error)?;
+
+    // Safety: This function isn't reentrant so there are no concurrent
+    // modifications.
+    let inputs = unsafe {
+        guest_slice(memory, inputs, len)
+            .context("Invalid input")
+            .map_err(runtime_error)?
+    };
+
+    env.host_functions
+        .lock()
+        .unwrap()
+        .rune_proc_block_transform(id, inputs)
+        .map_err(runtime_error)
+}
+
+fn rune_proc_block_read_output(
+    env: &Env,
+    id: u32,
+    dest: WasmPtr<u8, Array>,
+    len: u32,
+) -> Result<u32, RuntimeError> {
+    let memory = env
+        .memory
+        .get_ref()
+        .context("The memory isn't initialized")
+        .map_err(runtime_error)?;
+
+    // Safety: Function isn't re-entrant so we don't need to worry about
+    // concurrent mutations.
+    let buffer =
+        unsafe { guest_slice_mut(memory, dest, len).map_err(runtime_error)? };
+
+    env.host_functions
+        .lock()
+        .unwrap()
+        .rune_proc_block_read_output(id, buffer)
+        .map_err(runtime_error)
+}
+
+fn rune_proc_block_reset(env: &Env, id: u32) -> Result<(), RuntimeError> {
+    env.host_functions
+        .lock()
+        .unwrap()
+        .rune_proc_block_reset(id)
+        .map_err(runtime_error)
+}
+
 fn rune_resource_open(
     env: &Env,
     name: WasmPtr<u8, Array>,
@@ -190,38 +469,223 @@ fn rune_resource_read(
         .context("The memory isn't initialized")
         .map_err(runtime_error)?;
 
-    let mut buffer = vec![0_u8; len as usize];
+    // Safety: Function isn't re-entrant so we don't need to worry about
+    // concurrent mutations.
+    let buffer =
+        unsafe { guest_slice_mut(memory, dest, len).map_err(runtime_error)? };
 
-    let bytes_written = env
-        .host_functions
+    env.host_functions
+        .lock()
+        .unwrap()
+        .rune_resource_read(id, buffer)
+        .map_err(runtime_error)
+}
+
+fn rune_resource_close(env: &Env, id: u32) -> Result<(), RuntimeError> {
+    env.host_functions
         .lock()
         .unwrap()
-        .rune_resource_read(id, &mut buffer)
+        .rune_resource_close(id)
+        .map_err(runtime_error)
+}
+
+fn rune_capability_stream_open(
+    env: &Env,
+    capability_id: u32,
+) -> Result<u32, RuntimeError> {
+    env.host_functions
+        .lock()
+        .unwrap()
+        .rune_capability_stream_open(capability_id)
+        .map_err(runtime_error)
+}
+
+fn rune_capability_stream_read(
+    env: &Env,
+    stream_id: u32,
+    dest: WasmPtr<u8, Array>,
+    len: u32,
+) -> Result<u32, RuntimeError> {
+    let memory = env
+        .memory
+        .get_ref()
+        .context("The memory isn't initialized")
         .map_err(runtime_error)?;
 
-    if bytes_written == 0 {
-        return Ok(0);
-    }
+    // Safety: Function isn't re-entrant so we don't need to worry about
+    // concurrent mutations.
+    let buffer =
+        unsafe { guest_slice_mut(memory, dest, len).map_err(runtime_error)? };
+
+    env.host_functions
+        .lock()
+        .unwrap()
+        .rune_capability_stream_read(stream_id, buffer)
+        .map_err(runtime_error)
+}
+
+fn rune_capability_stream_close(
+    env: &Env,
+    stream_id: u32,
+) -> Result<u32, RuntimeError> {
+    env.host_functions
+        .lock()
+        .unwrap()
+        .rune_capability_stream_close(stream_id)
+        .map_err(runtime_error)?;
+
+    Ok(0)
+}
+
+fn rune_clock_realtime(env: &Env) -> Result<u64, RuntimeError> {
+    env.host_functions
+        .lock()
+        .unwrap()
+        .rune_clock_realtime()
+        .map_err(runtime_error)
+}
+
+fn rune_clock_monotonic(env: &Env) -> u64 {
+    env.host_functions.lock().unwrap().rune_clock_monotonic()
+}
+
+fn rune_stage_started(
+    env: &Env,
+    name: WasmPtr<u8, Array>,
+    len: u32,
+) -> Result<u32, RuntimeError> {
+    let memory = env
+        .memory
+        .get_ref()
+        .context("The memory isn't initialized")
+        .map_err(runtime_error)?;
+
+    // Safety: this function isn't reentrant, so we don't need to worry about
+    // concurrent mutations.
+    let name = unsafe {
+        name.get_utf8_str(memory, len)
+            .context("Unable to read the stage's name")
+            .map_err(runtime_error)?
+    };
+
+    env.host_functions
+        .lock()
+        .unwrap()
+        .rune_stage_started(name)
+        .map_err(runtime_error)?;
+
+    Ok(0)
+}
+
+fn rune_stage_finished(
+    env: &Env,
+    name: WasmPtr<u8, Array>,
+    len: u32,
+) -> Result<u32, RuntimeError> {
+    let memory = env
+        .memory
+        .get_ref()
+        .context("The memory isn't initialized")
+        .map_err(runtime_error)?;
+
+    // Safety: this function isn't reentrant, so we don't need to worry about
+    // concurrent mutations.
+    let name = unsafe {
+        name.get_utf8_str(memory, len)
+            .context("Unable to read the stage's name")
+            .map_err(runtime_error)?
+    };
+
+    env.host_functions.lock().unwrap().rune_stage_finished(name);
+
+    Ok(0)
+}
+
+fn rune_file_open(
+    env: &Env,
+    path: WasmPtr<u8, Array>,
+    len: u32,
+    mode: u32,
+) -> Result<u32, RuntimeError> {
+    let memory = env
+        .memory
+        .get_ref()
+        .context("The memory isn't initialized")
+        .map_err(runtime_error)?;
+
+    // Safety: this function isn't reentrant, so we don't need to worry about
+    // concurrent mutations.
+    let path = unsafe {
+        path.get_utf8_str(memory, len)
+            .context("Invalid buffer pointer")
+            .map_err(runtime_error)?
+    };
+
+    env.host_functions
+        .lock()
+        .unwrap()
+        .rune_file_open(path, mode)
+        .map_err(runtime_error)
+}
+
+fn rune_file_read(
+    env: &Env,
+    id: u32,
+    dest: WasmPtr<u8, Array>,
+    len: u32,
+) -> Result<u32, RuntimeError> {
+    let memory = env
+        .memory
+        .get_ref()
+        .context("The memory isn't initialized")
+        .map_err(runtime_error)?;
 
-    let view = memory.view::<u8>();
     // Safety: Function isn't re-entrant so we don't need to worry about
     // concurrent mutations.
-    unsafe {
-        view.subarray(dest.offset(), dest.offset() + bytes_written)
-            .copy_from(&buffer[..bytes_written as usize]);
-    }
+    let buffer =
+        unsafe { guest_slice_mut(memory, dest, len).map_err(runtime_error)? };
 
-    Ok(bytes_written)
+    env.host_functions
+        .lock()
+        .unwrap()
+        .rune_file_read(id, buffer)
+        .map_err(runtime_error)
 }
 
-fn rune_resource_close(env: &Env, id: u32) -> Result<(), RuntimeError> {
+fn rune_file_write(
+    env: &Env,
+    id: u32,
+    data: WasmPtr<u8, Array>,
+    len: u32,
+) -> Result<u32, RuntimeError> {
+    let memory = env
+        .memory
+        .get_ref()
+        .context("The memory isn't initialized")
+        .map_err(runtime_error)?;
+
+    // Safety: Function isn't re-entrant so we don't need to worry about
+    // concurrent mutations.
+    let data =
+        unsafe { guest_slice(memory, data, len).map_err(runtime_error)? };
+
     env.host_functions
         .lock()
         .unwrap()
-        .rune_resource_close(id)
+        .rune_file_write(id, data)
         .map_err(runtime_error)
 }
 
+fn rune_file_close(env: &Env, id: u32) -> Result<u32, RuntimeError> {
+    env.host_functions
+        .lock()
+        .unwrap()
+        .rune_file_close(id)
+        .map_err(runtime_error)?;
+
+    Ok(0)
+}
+
 fn request_capability(
     env: &Env,
     capability_type: u32,
@@ -261,13 +725,11 @@ fn request_capability_set_param(
             .map_err(|()| Error::msg("Invalid key type"))
             .map_err(runtime_error)?;
 
-        let value = value_ptr
-            .deref(memory, 0, value_len)
+        let value = guest_slice(memory, value_ptr, value_len)
             .context("Unable to read the value")
             .map_err(runtime_error)?;
 
-        let value: Vec<u8> = value.into_iter().map(|c| c.get()).collect();
-        let value = hotg_rune_core::Value::from_le_bytes(ty, &value)
+        let value = hotg_rune_core::Value::from_le_bytes(ty, value)
             .context("Unable to deserialize the value")
             .map_err(runtime_error)?;
 
@@ -285,6 +747,42 @@ fn request_capability_set_param(
     }
 }
 
+fn rune_capability_set_string_param(
+    env: &Env,
+    capability_id: u32,
+    key_ptr: WasmPtr<u8, Array>,
+    key_len: u32,
+    value_ptr: WasmPtr<u8, Array>,
+    value_len: u32,
+) -> Result<u32, RuntimeError> {
+    let memory = env
+        .memory
+        .get_ref()
+        .context("The memory isn't initialized")
+        .map_err(runtime_error)?;
+
+    // Safety: this function isn't reentrant, so we don't need to worry about
+    // concurrent mutations.
+    unsafe {
+        let key = key_ptr
+            .get_utf8_str(memory, key_len)
+            .context("Unable to read the key")
+            .map_err(runtime_error)?;
+        let value = value_ptr
+            .get_utf8_str(memory, value_len)
+            .context("Unable to read the value")
+            .map_err(runtime_error)?;
+
+        env.host_functions
+            .lock()
+            .unwrap()
+            .request_capability_set_param(capability_id, key, value)
+            .map_err(runtime_error)?;
+
+        Ok(0)
+    }
+}
+
 fn stringified(value: hotg_rune_core::Value) -> String {
     match value {
         hotg_rune_core::Value::Byte(b) => b.to_string(),
@@ -308,25 +806,16 @@ fn request_provider_response(
         .context("The memory isn't initialized")
         .map_err(runtime_error)?;
 
-    let mut buffer = vec![0_u8; len as usize];
+    // Safety: Function isn't re-entrant so we don't need to worry about
+    // concurrent mutations.
+    let buffer =
+        unsafe { guest_slice_mut(memory, dest, len).map_err(runtime_error)? };
 
-    let bytes_written = env
-        .host_functions
+    env.host_functions
         .lock()
         .unwrap()
-        .request_provider_response(capability_id, &mut buffer)
-        .map_err(runtime_error)?;
-
-    let dest = dest
-        .deref(memory, 0, len)
-        .context("Invalid buffer pointer")
-        .map_err(runtime_error)?;
-
-    for (i, cell) in dest.iter().enumerate() {
-        cell.set(buffer[i]);
-    }
-
-    Ok(bytes_written)
+        .request_provider_response(capability_id, buffer)
+        .map_err(runtime_error)
 }
 
 fn tfm_model_invoke(
@@ -374,11 +863,102 @@ fn rune_model_infer(
         )
         .map_err(runtime_error)?;
 
-        host.rune_model_infer(model_id, &inputs, &mut outputs)
-            .map_err(runtime_error)?;
-    }
+        host.rune_model_infer(model_id, &inputs, &mut outputs)
+            .map_err(runtime_error)?;
+    }
+
+    Ok(0)
+}
+
+fn rune_model_resize_inputs(
+    env: &Env,
+    model_id: u32,
+    input_descriptors: WasmPtr<StringRef, Array>,
+    input_len: u32,
+) -> Result<u32, RuntimeError> {
+    let memory = env
+        .memory
+        .get_ref()
+        .context("The memory isn't initialized")
+        .map_err(runtime_error)?;
+
+    let input_shapes = unsafe {
+        shape_from_descriptors(memory, input_descriptors, input_len)
+            .map_err(runtime_error)?
+    };
+
+    env.host_functions
+        .lock()
+        .unwrap()
+        .rune_model_resize_inputs(model_id, &input_shapes)
+        .map_err(runtime_error)?;
+
+    Ok(0)
+}
+
+fn rune_model_output_shape(
+    env: &Env,
+    model_id: u32,
+    index: u32,
+    buffer: WasmPtr<u8, Array>,
+    len: u32,
+) -> Result<u32, RuntimeError> {
+    let memory = env
+        .memory
+        .get_ref()
+        .context("The memory isn't initialized")
+        .map_err(runtime_error)?;
+
+    // Safety: Function isn't re-entrant so we don't need to worry about
+    // concurrent mutations.
+    let buffer =
+        unsafe { guest_slice_mut(memory, buffer, len).map_err(runtime_error)? };
+
+    env.host_functions
+        .lock()
+        .unwrap()
+        .rune_model_output_shape(model_id, index, buffer)
+        .map_err(runtime_error)
+}
+
+/// Get direct access to a buffer in linear memory, avoiding the need to copy
+/// it into a temporary buffer on the host.
+///
+/// # Safety
+///
+/// This assumes linear memory won't be touched again (e.g. by executing a
+/// WebAssembly function or another call to [`guest_slice_mut()`]) until the
+/// returned reference is dropped.
+unsafe fn guest_slice(
+    memory: &Memory,
+    ptr: WasmPtr<u8, Array>,
+    len: u32,
+) -> Result<&[u8], Error> {
+    guest_slice_mut(memory, ptr, len).map(|s| &*s)
+}
 
-    Ok(0)
+/// Get mutable access to a buffer in linear memory, letting the host write
+/// directly into memory allocated by the guest.
+///
+/// # Safety
+///
+/// See [`guest_slice()`].
+unsafe fn guest_slice_mut(
+    memory: &Memory,
+    ptr: WasmPtr<u8, Array>,
+    len: u32,
+) -> Result<&mut [u8], Error> {
+    let linear_memory = memory.data_unchecked_mut();
+    let start = ptr.offset() as usize;
+    let end = start + len as usize;
+    let memory_len = linear_memory.len();
+
+    linear_memory.get_mut(start..end).with_context(|| {
+        format!(
+            "Range {}..{} lies outside of linear memory ({} bytes)",
+            start, end, memory_len
+        )
+    })
 }
 
 /// Given WebAssembly pointers to tensors in linear memory, get access to their
@@ -462,6 +1042,42 @@ pub struct StringRef {
 // necessary bounds checks.
 unsafe impl ValueType for StringRef {}
 
+impl StringRef {
+    /// # Safety
+    ///
+    /// See [`WasmPtr::get_utf8_str()`].
+    unsafe fn read<'vm>(&self, memory: &'vm Memory) -> Option<&'vm str> {
+        self.data.get_utf8_str(memory, self.len)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[repr(C)]
+struct LogRecord {
+    level: u32,
+    target: StringRef,
+    module_path: StringRef,
+    file: StringRef,
+    line: u32,
+    message: StringRef,
+}
+
+// Safety: All bit patterns are valid and the wasmer memory will do any
+// necessary bounds checks.
+unsafe impl ValueType for LogRecord {}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[repr(C)]
+struct ErrorRecord {
+    kind: u32,
+    stage: StringRef,
+    message: StringRef,
+}
+
+// Safety: All bit patterns are valid and the wasmer memory will do any
+// necessary bounds checks.
+unsafe impl ValueType for ErrorRecord {}
+
 fn rune_model_load(
     env: &Env,
     mimetype: WasmPtr<u8, Array>,
@@ -487,13 +1103,10 @@ fn rune_model_load(
             .context("Invalid mimtype string")
             .map_err(runtime_error)?;
 
-        let model = model
-            .deref(memory, 0, model_len)
+        let model = guest_slice(memory, model, model_len)
             .context("Invalid model")
             .map_err(runtime_error)?;
 
-        let model: Vec<u8> = model.into_iter().map(|v| v.get()).collect();
-
         (mimetype, model)
     };
 
@@ -511,7 +1124,112 @@ fn rune_model_load(
     env.host_functions
         .lock()
         .unwrap()
-        .rune_model_load(mimetype, &model, &inputs, &outputs)
+        .rune_model_load(mimetype, model, &inputs, &outputs)
+        .map_err(runtime_error)
+}
+
+fn rune_model_load_external(
+    env: &Env,
+    name: WasmPtr<u8, Array>,
+    name_len: u32,
+    mimetype: WasmPtr<u8, Array>,
+    mimetype_len: u32,
+    input_descriptors: WasmPtr<StringRef, Array>,
+    input_len: u32,
+    output_descriptors: WasmPtr<StringRef, Array>,
+    output_len: u32,
+) -> Result<u32, RuntimeError> {
+    let memory = env
+        .memory
+        .get_ref()
+        .context("The memory isn't initialized")
+        .map_err(runtime_error)?;
+
+    // Safety: This function isn't reentrant so there are no concurrent
+    // modifications.
+    let (name, mimetype) = unsafe {
+        let name = name
+            .get_utf8_str(memory, name_len)
+            .context("Invalid model name")
+            .map_err(runtime_error)?;
+        let mimetype = mimetype
+            .get_utf8_str(memory, mimetype_len)
+            .context("Invalid mimtype string")
+            .map_err(runtime_error)?;
+
+        (name, mimetype)
+    };
+
+    let (inputs, outputs) = unsafe {
+        let inputs =
+            shape_from_descriptors(memory, input_descriptors, input_len)
+                .map_err(runtime_error)?;
+        let outputs =
+            shape_from_descriptors(memory, output_descriptors, output_len)
+                .map_err(runtime_error)?;
+
+        (inputs, outputs)
+    };
+
+    env.host_functions
+        .lock()
+        .unwrap()
+        .rune_model_load_external(name, mimetype, &inputs, &outputs)
+        .map_err(runtime_error)
+}
+
+fn rune_model_load_encrypted(
+    env: &Env,
+    name: WasmPtr<u8, Array>,
+    name_len: u32,
+    mimetype: WasmPtr<u8, Array>,
+    mimetype_len: u32,
+    model: WasmPtr<u8, Array>,
+    model_len: u32,
+    input_descriptors: WasmPtr<StringRef, Array>,
+    input_len: u32,
+    output_descriptors: WasmPtr<StringRef, Array>,
+    output_len: u32,
+) -> Result<u32, RuntimeError> {
+    let memory = env
+        .memory
+        .get_ref()
+        .context("The memory isn't initialized")
+        .map_err(runtime_error)?;
+
+    // Safety: This function isn't reentrant so there are no concurrent
+    // modifications.
+    let (name, mimetype, model) = unsafe {
+        let name = name
+            .get_utf8_str(memory, name_len)
+            .context("Invalid model name")
+            .map_err(runtime_error)?;
+        let mimetype = mimetype
+            .get_utf8_str(memory, mimetype_len)
+            .context("Invalid mimtype string")
+            .map_err(runtime_error)?;
+        let model = guest_slice(memory, model, model_len)
+            .context("Invalid model")
+            .map_err(runtime_error)?;
+
+        (name, mimetype, model)
+    };
+
+    let (inputs, outputs) = unsafe {
+        let inputs =
+            shape_from_descriptors(memory, input_descriptors, input_len)
+                .map_err(runtime_error)?;
+        let outputs =
+            shape_from_descriptors(memory, output_descriptors, output_len)
+                .map_err(runtime_error)?;
+
+        (inputs, outputs)
+    };
+
+    env.host_functions
+        .lock()
+        .unwrap()
+        .rune_model_load_encrypted(name, mimetype, model, &inputs, &outputs)
         .map_err(runtime_error)
 }
 
@@ -531,6 +1249,60 @@ fn tfm_preload_model(
     Ok(0)
 }
 
+fn rune_model_variant(
+    env: &Env,
+    name: WasmPtr<u8, Array>,
+    name_len: u32,
+    variants: WasmPtr<StringRef, Array>,
+    variant_len: u32,
+) -> Result<u32, RuntimeError> {
+    let memory = env
+        .memory
+        .get_ref()
+        .context("The memory isn't initialized")
+        .map_err(runtime_error)?;
+
+    // Safety: This function isn't reentrant so there are no concurrent
+    // modifications.
+    let (name, variants) = unsafe {
+        let name = name
+            .get_utf8_str(memory, name_len)
+            .context("Invalid model name")
+            .map_err(runtime_error)?;
+        let variants = strings_from_refs(memory, variants, variant_len)
+            .map_err(runtime_error)?;
+
+        (name, variants)
+    };
+
+    env.host_functions
+        .lock()
+        .unwrap()
+        .rune_model_variant(name, &variants)
+        .map_err(runtime_error)
+}
+
+unsafe fn strings_from_refs(
+    memory: &Memory,
+    strings: WasmPtr<StringRef, Array>,
+    len: u32,
+) -> Result<Vec<&str>, Error> {
+    let strings = strings
+        .deref(memory, 0, len)
+        .context("Invalid string pointer")?;
+
+    strings
+        .iter()
+        .enumerate()
+        .map(|(i, s)| {
+            let StringRef { data, len } = s.get();
+            data.get_utf8_str(memory, len).with_context(|| {
+                format!("The {}'th string pointer is invalid", i)
+            })
+        })
+        .collect()
+}
+
 unsafe fn shape_from_descriptors(
     memory: &Memory,
     descriptors: WasmPtr<StringRef, Array>,
@@ -576,20 +1348,206 @@ fn consume_output(
         .context("The memory isn't initialized")
         .map_err(runtime_error)?;
 
-    let buffer = buffer
-        .deref(memory, 0, len)
-        .context("Invalid input")
-        .map_err(runtime_error)?;
-
     // Safety: This function isn't reentrant so there are no concurrent
-    // modifications. That also means it's safe to transmute [Cell<T>] to [T].
-    let buffer: Vec<u8> = buffer.into_iter().map(|c| c.get()).collect();
+    // modifications.
+    let buffer = unsafe {
+        guest_slice(memory, buffer, len)
+            .context("Invalid input")
+            .map_err(runtime_error)?
+    };
 
     env.host_functions
         .lock()
         .unwrap()
-        .consume_output(output_id, &buffer)
+        .consume_output(output_id, buffer)
         .map_err(runtime_error)?;
 
     Ok(len)
 }
+
+fn write_value<T: ValueType>(
+    memory: &Memory,
+    ptr: WasmPtr<T, Item>,
+    value: T,
+) -> Result<(), RuntimeError> {
+    ptr.deref(memory)
+        .context("Invalid pointer")
+        .map_err(runtime_error)?
+        .set(value);
+
+    Ok(())
+}
+
+fn wasi_args_get(
+    _env: &Env,
+    _argv: WasmPtr<WasmPtr<u8, Array>, Array>,
+    _argv_buf: WasmPtr<u8, Array>,
+) -> u32 {
+    wasi::ERRNO_SUCCESS
+}
+
+fn wasi_args_sizes_get(
+    env: &Env,
+    argc: WasmPtr<u32, Item>,
+    argv_buf_size: WasmPtr<u32, Item>,
+) -> Result<u32, RuntimeError> {
+    let memory = env
+        .memory
+        .get_ref()
+        .context("The memory isn't initialized")
+        .map_err(runtime_error)?;
+
+    write_value(memory, argc, 0)?;
+    write_value(memory, argv_buf_size, 0)?;
+
+    Ok(wasi::ERRNO_SUCCESS)
+}
+
+fn wasi_environ_get(
+    _env: &Env,
+    _environ: WasmPtr<WasmPtr<u8, Array>, Array>,
+    _environ_buf: WasmPtr<u8, Array>,
+) -> u32 {
+    wasi::ERRNO_SUCCESS
+}
+
+fn wasi_environ_sizes_get(
+    env: &Env,
+    environ_count: WasmPtr<u32, Item>,
+    environ_buf_size: WasmPtr<u32, Item>,
+) -> Result<u32, RuntimeError> {
+    let memory = env
+        .memory
+        .get_ref()
+        .context("The memory isn't initialized")
+        .map_err(runtime_error)?;
+
+    write_value(memory, environ_count, 0)?;
+    write_value(memory, environ_buf_size, 0)?;
+
+    Ok(wasi::ERRNO_SUCCESS)
+}
+
+fn wasi_clock_time_get(
+    env: &Env,
+    clock_id: u32,
+    _precision: u64,
+    time: WasmPtr<u64, Item>,
+) -> Result<u32, RuntimeError> {
+    let memory = env
+        .memory
+        .get_ref()
+        .context("The memory isn't initialized")
+        .map_err(runtime_error)?;
+
+    let nanos = env
+        .host_functions
+        .lock()
+        .unwrap()
+        .wasi_clock_time_get(clock_id);
+
+    match nanos {
+        Some(nanos) => {
+            write_value(memory, time, nanos)?;
+            Ok(wasi::ERRNO_SUCCESS)
+        },
+        None => Ok(wasi::ERRNO_INVAL),
+    }
+}
+
+fn wasi_random_get(
+    env: &Env,
+    buffer: WasmPtr<u8, Array>,
+    len: u32,
+) -> Result<u32, RuntimeError> {
+    let memory = env
+        .memory
+        .get_ref()
+        .context("The memory isn't initialized")
+        .map_err(runtime_error)?;
+
+    // Safety: This function isn't reentrant so there are no concurrent
+    // modifications.
+    let buffer = unsafe {
+        guest_slice_mut(memory, buffer, len)
+            .context("Invalid buffer pointer")
+            .map_err(runtime_error)?
+    };
+
+    Ok(env.host_functions.lock().unwrap().wasi_random_get(buffer))
+}
+
+fn wasi_fd_write(
+    env: &Env,
+    fd: u32,
+    iovs: WasmPtr<StringRef, Array>,
+    iovs_len: u32,
+    nwritten: WasmPtr<u32, Item>,
+) -> Result<u32, RuntimeError> {
+    let memory = env
+        .memory
+        .get_ref()
+        .context("The memory isn't initialized")
+        .map_err(runtime_error)?;
+
+    let iovs = iovs
+        .deref(memory, 0, iovs_len)
+        .context("Invalid iovec pointer")
+        .map_err(runtime_error)?;
+
+    let mut data = Vec::new();
+
+    for iov in iovs {
+        let StringRef { data: ptr, len } = iov.get();
+        // Safety: This function isn't reentrant so there are no concurrent
+        // modifications.
+        let bytes = unsafe {
+            guest_slice(memory, ptr, len)
+                .context("Invalid buffer pointer")
+                .map_err(runtime_error)?
+        };
+        data.extend_from_slice(bytes);
+    }
+
+    let errno = env.host_functions.lock().unwrap().wasi_fd_write(fd, &data);
+
+    if errno == wasi::ERRNO_SUCCESS {
+        write_value(memory, nwritten, data.len() as u32)?;
+    }
+
+    Ok(errno)
+}
+
+fn wasi_fd_close(_env: &Env, _fd: u32) -> u32 { wasi::ERRNO_BADF }
+
+fn wasi_fd_seek(
+    _env: &Env,
+    _fd: u32,
+    _offset: i64,
+    _whence: u32,
+    _new_offset: WasmPtr<u64, Item>,
+) -> u32 {
+    wasi::ERRNO_BADF
+}
+
+fn wasi_fd_prestat_get(_env: &Env, _fd: u32, _prestat: u32) -> u32 {
+    // There are no pre-opened directories
+    wasi::ERRNO_BADF
+}
+
+fn wasi_fd_prestat_dir_name(
+    _env: &Env,
+    _fd: u32,
+    _path: WasmPtr<u8, Array>,
+    _path_len: u32,
+) -> u32 {
+    wasi::ERRNO_BADF
+}
+
+fn wasi_proc_exit(env: &Env, exit_code: u32) -> Result<(), RuntimeError> {
+    env.host_functions
+        .lock()
+        .unwrap()
+        .wasi_proc_exit(exit_code)
+        .map_err(runtime_error)
+}
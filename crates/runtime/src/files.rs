@@ -0,0 +1,89 @@
+//! Sandboxed access to files on the host.
+//!
+//! Runes can only open files the host has explicitly allowed with
+//! [`crate::Runtime::allow_file()`], and only in the mode it was allowed for.
+
+use std::{
+    fmt::{self, Display, Formatter},
+    fs::{File, OpenOptions},
+    path::Path,
+};
+
+use anyhow::{Context, Error};
+
+/// How a Rune may access a file.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[repr(u32)]
+pub enum FileMode {
+    /// Read the file's contents (e.g. a small config file).
+    Read = 0,
+    /// Append to the end of the file, creating it if necessary (e.g. a log of
+    /// results).
+    Append = 1,
+}
+
+impl FileMode {
+    pub(crate) fn from_u32(mode: u32) -> Result<Self, Error> {
+        match mode {
+            0 => Ok(FileMode::Read),
+            1 => Ok(FileMode::Append),
+            other => anyhow::bail!("Unknown file mode, {}", other),
+        }
+    }
+
+    pub(crate) fn open(self, path: &Path) -> Result<File, Error> {
+        let result = match self {
+            FileMode::Read => File::open(path),
+            FileMode::Append => {
+                OpenOptions::new().append(true).create(true).open(path)
+            },
+        };
+
+        result.with_context(|| {
+            format!("Unable to open \"{}\" for {}", path.display(), self)
+        })
+    }
+}
+
+impl Display for FileMode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            FileMode::Read => write!(f, "reading"),
+            FileMode::Append => write!(f, "appending"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+
+    use super::*;
+
+    #[test]
+    fn append_creates_the_file() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("results.log");
+
+        let mut file = FileMode::Append.open(&path).unwrap();
+        file.write_all(b"first\n").unwrap();
+        drop(file);
+        let mut file = FileMode::Append.open(&path).unwrap();
+        file.write_all(b"second\n").unwrap();
+        drop(file);
+
+        let mut contents = String::new();
+        FileMode::Read
+            .open(&path)
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap();
+        assert_eq!(contents, "first\nsecond\n");
+    }
+
+    #[test]
+    fn unknown_modes_are_rejected() {
+        assert_eq!(FileMode::from_u32(1).unwrap(), FileMode::Append);
+        assert!(FileMode::from_u32(42).is_err());
+    }
+}
@@ -0,0 +1,83 @@
+use std::fmt::{self, Display, Formatter};
+
+/// An error reported by the Rune itself (e.g. because a proc-block panicked)
+/// via the `rune_error()` intrinsic.
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub struct GuestError {
+    /// The name of the pipeline stage that was running when the error
+    /// occurred, if known.
+    pub stage: Option<String>,
+    pub kind: GuestErrorKind,
+    pub message: String,
+}
+
+impl Display for GuestError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match &self.stage {
+            Some(stage) => {
+                write!(f, "stage `{}` failed: {}", stage, self.message)
+            },
+            None => write!(f, "the Rune failed: {}", self.message),
+        }
+    }
+}
+
+/// The broad category a [`GuestError`] falls into.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, serde::Serialize)]
+#[repr(u32)]
+pub enum GuestErrorKind {
+    Other = 0,
+    /// Something panicked.
+    Panic = 1,
+    /// A stage was given an argument or input it can't handle.
+    InvalidInput = 2,
+    /// Running a model failed.
+    Model = 3,
+}
+
+impl GuestErrorKind {
+    /// Parse the integer representation used by the `rune_error()`
+    /// intrinsic, treating unknown values as [`GuestErrorKind::Other`] so
+    /// newer Runes still produce a useful error.
+    pub fn from_u32(value: u32) -> Self {
+        match value {
+            1 => GuestErrorKind::Panic,
+            2 => GuestErrorKind::InvalidInput,
+            3 => GuestErrorKind::Model,
+            _ => GuestErrorKind::Other,
+        }
+    }
+}
+
+impl Display for GuestErrorKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            GuestErrorKind::Other => write!(f, "other"),
+            GuestErrorKind::Panic => write!(f, "panic"),
+            GuestErrorKind::InvalidInput => write!(f, "invalid input"),
+            GuestErrorKind::Model => write!(f, "model"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mention_the_failing_stage() {
+        let error = GuestError {
+            stage: Some(String::from("fft")),
+            kind: GuestErrorKind::InvalidInput,
+            message: String::from("window size 0"),
+        };
+
+        assert_eq!(error.to_string(), "stage `fft` failed: window size 0");
+    }
+
+    #[test]
+    fn unknown_kinds_are_other() {
+        assert_eq!(GuestErrorKind::from_u32(1), GuestErrorKind::Panic);
+        assert_eq!(GuestErrorKind::from_u32(42), GuestErrorKind::Other);
+    }
+}
@@ -7,35 +7,125 @@
 //!
 //! The following cargo features are available:
 //!
+//! - `std` - (default) everything that requires the standard library. Without
+//!   it the crate is `#![no_std]` and only the tensor types, their
+//!   conversions, [`BufferPool`], and (with the `wasmi` feature) the
+//!   `embedded` engine are available
+#![cfg_attr(not(feature = "std"), doc = "(disabled)")]
+//! - `ble` - send results to a BLE device using
+//!   [btleplug](https://crates.io/crates/btleplug)
+#![cfg_attr(not(feature = "ble"), doc = "(disabled)")]
 //! - `builtins` - (default) enable various builtin outputs and capabilities
 #![cfg_attr(not(feature = "builtins"), doc = "(disabled)")]
+//! - `camera` - capture images from a webcam using
+//!   [nokhwa](https://crates.io/crates/nokhwa)
+#![cfg_attr(not(feature = "camera"), doc = "(disabled)")]
 //! - `tflite` - (default) enable support for TensorFlow Lite models
 #![cfg_attr(not(feature = "tflite"), doc = "(disabled)")]
+//! - `torch` - enable support for TorchScript models using
+//!   [tch](https://crates.io/crates/tch) (requires libtorch)
+#![cfg_attr(not(feature = "torch"), doc = "(disabled)")]
+//! - `tract` - run TensorFlow Lite models with the pure-Rust
+//!   [tract](https://crates.io/crates/tract-tflite) engine, which is handy
+//!   when cross-compiling (build with `--no-default-features` to drop the
+//!   C++ TensorFlow Lite library entirely)
+#![cfg_attr(not(feature = "tract"), doc = "(disabled)")]
+//! - `compressed-audio` - decode MP3, OGG Vorbis, and FLAC files using
+//!   [symphonia](https://crates.io/crates/symphonia)
+#![cfg_attr(not(feature = "compressed-audio"), doc = "(disabled)")]
+//! - `microphone` - record audio from a microphone using
+//!   [cpal](https://crates.io/crates/cpal)
+#![cfg_attr(not(feature = "microphone"), doc = "(disabled)")]
+//! - `mqtt` - enable the MQTT output sink
+#![cfg_attr(not(feature = "mqtt"), doc = "(disabled)")]
 //! - `wasm3` - enable the [WASM3](https://github.com/wasm3/wasm3) engine
 #![cfg_attr(not(feature = "wasm3"), doc = "(disabled)")]
-//! - `wasmer` - enable the [wasmer](https://wasmer.io/) engine
+//! - `wasmer` - enable the [wasmer](https://wasmer.io/) engine, which is also
+//!   used for running standalone proc blocks
 #![cfg_attr(not(feature = "wasmer"), doc = "(disabled)")]
+//! - `wasmi` - enable the [`embedded`] engine, which uses the
+//!   [wasmi](https://crates.io/crates/wasmi) interpreter and doesn't need
+//!   `std`, so Runes can be run on microcontrollers
+#![cfg_attr(not(feature = "wasmi"), doc = "(disabled)")]
+//! - `websocket` - enable the WebSocket output sink
+#![cfg_attr(not(feature = "websocket"), doc = "(disabled)")]
 #![cfg_attr(feature = "unstable_doc_cfg", feature(doc_cfg))]
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(all(test, not(feature = "std")))]
+#[macro_use]
+extern crate std;
+
+extern crate alloc;
+
+#[cfg(all(not(feature = "std"), any(feature = "wasm3", feature = "wasmer")))]
+compile_error!("The WebAssembly engines require the \"std\" feature");
 
 #[cfg(feature = "wasm3")]
 pub extern crate wasm3;
 #[cfg(feature = "wasmer")]
 pub extern crate wasmer;
 
+#[cfg(feature = "std")]
+mod abi;
+mod buffer_pool;
+#[cfg(feature = "std")]
 mod callbacks;
+#[cfg(feature = "std")]
+mod cancellation;
+mod conversion;
+#[cfg(feature = "std")]
 mod engine;
+#[cfg(feature = "std")]
+mod files;
+#[cfg(feature = "std")]
+mod guest_error;
+#[cfg(feature = "std")]
+mod metrics;
+#[cfg(feature = "std")]
 pub mod models;
+#[cfg(feature = "std")]
+mod pacing;
+#[cfg(feature = "std")]
+pub mod proc_blocks;
+#[cfg(feature = "std")]
 mod runtime;
+#[cfg(feature = "std")]
+pub mod sinks;
+#[cfg(feature = "std")]
+pub mod sources;
 mod tensor;
+#[cfg(feature = "std")]
+mod trace;
 
 #[cfg(feature = "builtins")]
 pub mod builtins;
+#[cfg(feature = "wasmi")]
+pub mod embedded;
+#[cfg(feature = "std")]
 mod outputs;
 
 pub use crate::{
-    callbacks::{Model, ModelMetadata, NodeMetadata},
+    buffer_pool::{BufferPool, PoolStats},
+    conversion::{
+        convert, convert_into_buffer, convert_pooled, Conversion,
+        ConversionError,
+    },
+    tensor::{
+        ElementType, Tensor, TensorElement, TensorView, TensorViewMut,
+        UnknownElementType, ViewError,
+    },
+};
+#[cfg(feature = "std")]
+pub use crate::{
+    callbacks::{
+        CapabilityStream, Model, ModelMetadata, NodeMetadata, ProcBlock,
+    },
+    cancellation::{CancellationToken, Cancelled},
     engine::LoadError,
-    outputs::OutputTensor,
+    files::FileMode,
+    guest_error::{GuestError, GuestErrorKind},
+    metrics::{Metrics, ModelBenchmark},
+    outputs::{FieldSchema, OutputTensor, Record},
     runtime::Runtime,
-    tensor::{ElementType, Tensor, TensorElement},
 };
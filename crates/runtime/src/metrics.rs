@@ -0,0 +1,271 @@
+use std::{
+    collections::HashMap,
+    fmt::{self, Display, Formatter},
+    time::Duration,
+};
+
+/// Timing information gathered while running a Rune's pipeline.
+///
+/// The metrics are reset at the start of each [`crate::Runtime::predict()`]
+/// call.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Metrics {
+    /// How long the entire pipeline took to run.
+    pub total: Duration,
+    /// The time spent reading from each capability, keyed by capability ID.
+    pub capabilities: HashMap<u32, Duration>,
+    /// The time spent doing inference with each model, keyed by model ID.
+    pub models: HashMap<u32, Duration>,
+    /// The time spent writing to each output, keyed by output ID.
+    pub outputs: HashMap<u32, Duration>,
+    /// The time spent running each stage of the pipeline (including any host
+    /// calls it makes), in the order the stages were run.
+    ///
+    /// Proc blocks which were fused into a single loop are timed together,
+    /// using their names joined by a `+` (e.g. `"normalize+clip"`).
+    pub stages: Vec<(String, Duration)>,
+}
+
+impl Metrics {
+    pub(crate) fn record(&mut self, stage: Stage, id: u32, elapsed: Duration) {
+        let durations = match stage {
+            Stage::Capability => &mut self.capabilities,
+            Stage::Model => &mut self.models,
+            Stage::Output => &mut self.outputs,
+        };
+
+        *durations.entry(id).or_default() += elapsed;
+    }
+
+    pub(crate) fn record_stage(&mut self, name: &str, elapsed: Duration) {
+        match self.stages.iter_mut().find(|(stage, _)| stage == name) {
+            Some((_, total)) => *total += elapsed,
+            None => self.stages.push((name.to_string(), elapsed)),
+        }
+    }
+
+    /// The time spent inside the Rune itself (i.e. running proc-blocks and
+    /// shuffling data between stages) rather than calling into the host.
+    pub fn proc_blocks(&self) -> Duration {
+        let host_calls: Duration = self
+            .capabilities
+            .values()
+            .chain(self.models.values())
+            .chain(self.outputs.values())
+            .sum();
+
+        self.total.saturating_sub(host_calls)
+    }
+}
+
+impl Display for Metrics {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Total: {:?}", self.total)?;
+
+        for (name, elapsed) in &self.stages {
+            writeln!(f, "Stage \"{}\": {:?}", name, elapsed)?;
+        }
+
+        write_durations(f, "Capability", &self.capabilities)?;
+        write_durations(f, "Model", &self.models)?;
+        write_durations(f, "Output", &self.outputs)?;
+        write!(f, "Proc blocks: {:?}", self.proc_blocks())
+    }
+}
+
+fn write_durations(
+    f: &mut Formatter<'_>,
+    kind: &str,
+    durations: &HashMap<u32, Duration>,
+) -> fmt::Result {
+    let mut durations: Vec<_> = durations.iter().collect();
+    durations.sort_by_key(|(id, _)| **id);
+
+    for (id, elapsed) in durations {
+        writeln!(f, "{} {}: {:?}", kind, id, elapsed)?;
+    }
+
+    Ok(())
+}
+
+/// The results from running a model repeatedly with synthetic inputs (see
+/// [`crate::Runtime::benchmark_models()`]).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelBenchmark {
+    /// The model's ID.
+    pub model_id: u32,
+    /// How many times the model was run.
+    pub iterations: usize,
+    /// The median latency.
+    pub p50: Duration,
+    /// The 95th percentile latency.
+    pub p95: Duration,
+    /// The slowest inference.
+    pub max: Duration,
+    /// The process's peak resident memory (in bytes) while running the
+    /// model, if it could be measured on this platform.
+    pub peak_memory: Option<u64>,
+}
+
+impl ModelBenchmark {
+    pub(crate) fn from_latencies(
+        model_id: u32,
+        mut latencies: Vec<Duration>,
+        peak_memory: Option<u64>,
+    ) -> Self {
+        latencies.sort_unstable();
+
+        ModelBenchmark {
+            model_id,
+            iterations: latencies.len(),
+            p50: percentile(&latencies, 50),
+            p95: percentile(&latencies, 95),
+            max: latencies.last().copied().unwrap_or_default(),
+            peak_memory,
+        }
+    }
+}
+
+impl Display for ModelBenchmark {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Model {}: p50 {:?}, p95 {:?}, max {:?} ({} iterations)",
+            self.model_id, self.p50, self.p95, self.max, self.iterations
+        )?;
+
+        if let Some(bytes) = self.peak_memory {
+            write!(f, ", peak memory {} KiB", bytes / 1024)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Get the `p`'th percentile from a sorted list of durations, using the
+/// nearest-rank method.
+fn percentile(sorted: &[Duration], p: usize) -> Duration {
+    if sorted.is_empty() {
+        return Duration::default();
+    }
+
+    let rank = (p * sorted.len() + 99) / 100;
+    sorted[rank.saturating_sub(1).min(sorted.len() - 1)]
+}
+
+/// Measuring the process's memory usage.
+pub(crate) mod memory {
+    /// Reset the peak memory usage so [`peak_memory()`] only reflects what
+    /// happens from now on.
+    ///
+    /// This is best-effort and does nothing on platforms which don't support
+    /// it.
+    pub(crate) fn reset_peak_memory() {
+        #[cfg(target_os = "linux")]
+        {
+            // Writing "5" resets the process's peak resident set size
+            let _ = std::fs::write("/proc/self/clear_refs", "5");
+        }
+    }
+
+    /// The process's peak resident set size, in bytes.
+    pub(crate) fn peak_memory() -> Option<u64> {
+        #[cfg(target_os = "linux")]
+        {
+            let status = std::fs::read_to_string("/proc/self/status").ok()?;
+            parse_peak_memory(&status)
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            None
+        }
+    }
+
+    #[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+    fn parse_peak_memory(status: &str) -> Option<u64> {
+        let line = status.lines().find(|l| l.starts_with("VmHWM:"))?;
+        let kilobytes = line
+            .trim_start_matches("VmHWM:")
+            .trim()
+            .trim_end_matches("kB")
+            .trim()
+            .parse::<u64>()
+            .ok()?;
+
+        Some(kilobytes * 1024)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn read_the_high_water_mark() {
+            let status = "Name:\trune\nVmPeak:\t  20000 kB\nVmHWM:\t    1234 \
+                          kB\nVmRSS:\t    1000 kB\n";
+
+            assert_eq!(parse_peak_memory(status), Some(1234 * 1024));
+            assert_eq!(parse_peak_memory("Name:\trune\n"), None);
+        }
+    }
+}
+
+/// The kind of host call being timed.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum Stage {
+    Capability,
+    Model,
+    Output,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn proc_blocks_take_whatever_time_is_left_over() {
+        let mut metrics = Metrics {
+            total: Duration::from_millis(100),
+            ..Default::default()
+        };
+
+        metrics.record(Stage::Capability, 1, Duration::from_millis(10));
+        metrics.record(Stage::Model, 2, Duration::from_millis(30));
+        metrics.record(Stage::Model, 2, Duration::from_millis(20));
+        metrics.record(Stage::Output, 3, Duration::from_millis(5));
+
+        assert_eq!(metrics.models[&2], Duration::from_millis(50));
+        assert_eq!(metrics.proc_blocks(), Duration::from_millis(35));
+    }
+
+    #[test]
+    fn stages_are_kept_in_the_order_they_ran() {
+        let mut metrics = Metrics::default();
+
+        metrics.record_stage("audio", Duration::from_millis(10));
+        metrics.record_stage("fft+normalize", Duration::from_millis(5));
+        metrics.record_stage("model", Duration::from_millis(30));
+        metrics.record_stage("fft+normalize", Duration::from_millis(2));
+
+        assert_eq!(
+            metrics.stages,
+            vec![
+                ("audio".to_string(), Duration::from_millis(10)),
+                ("fft+normalize".to_string(), Duration::from_millis(7)),
+                ("model".to_string(), Duration::from_millis(30)),
+            ]
+        );
+    }
+
+    #[test]
+    fn benchmark_percentiles() {
+        let latencies = (1..=20).map(Duration::from_millis).collect();
+
+        let benchmark = ModelBenchmark::from_latencies(1, latencies, None);
+
+        assert_eq!(benchmark.iterations, 20);
+        assert_eq!(benchmark.p50, Duration::from_millis(10));
+        assert_eq!(benchmark.p95, Duration::from_millis(19));
+        assert_eq!(benchmark.max, Duration::from_millis(20));
+    }
+}
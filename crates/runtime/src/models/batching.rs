@@ -0,0 +1,195 @@
+//! Support for running models with a variable batch size.
+//!
+//! Backends like TensorFlow Lite are compiled for one particular batch size,
+//! so a batch of `n` times that size is run as `n` separate inferences and
+//! the results are stacked along the leading dimension.
+
+use anyhow::Error;
+use hotg_rune_core::{Dim, Shape};
+
+/// Work out how many of the `original` batches fit in the `resized` inputs,
+/// returning `None` if anything other than the leading (batch) dimension
+/// changed.
+pub(crate) fn batch_count(
+    original: &[Shape<'_>],
+    resized: &[Shape<'_>],
+) -> Option<usize> {
+    if original.len() != resized.len() {
+        return None;
+    }
+
+    let mut batches = None;
+
+    for (original, resized) in original.iter().zip(resized) {
+        let original_dims = original.fixed_dimensions()?;
+        let resized_dims = resized.fixed_dimensions()?;
+
+        let (&original_batch, original_rest) = original_dims.split_first()?;
+        let (&resized_batch, resized_rest) = resized_dims.split_first()?;
+
+        if original.element_type() != resized.element_type()
+            || original_rest != resized_rest
+            || original_batch == 0
+            || resized_batch % original_batch != 0
+        {
+            return None;
+        }
+
+        let n = resized_batch / original_batch;
+
+        match batches {
+            Some(previous) if previous != n => return None,
+            _ => batches = Some(n),
+        }
+    }
+
+    batches.filter(|&n| n > 0)
+}
+
+/// Multiply the leading dimension of each shape by `batches`.
+pub(crate) fn rebatch(
+    shapes: &[Shape<'_>],
+    batches: usize,
+) -> Vec<Shape<'static>> {
+    shapes
+        .iter()
+        .map(|shape| {
+            let mut dimensions = shape.dimensions().to_vec();
+
+            if let Some(Dim::Fixed(first)) = dimensions.first_mut() {
+                *first *= batches;
+            }
+
+            Shape::with_dims(shape.element_type(), dimensions)
+        })
+        .collect()
+}
+
+/// Run a model which only accepts one batch at a time over `batches`
+/// batches, where each input and output buffer contains `batches` equally
+/// sized chunks.
+pub(crate) fn infer_in_batches(
+    batches: usize,
+    inputs: &[&[u8]],
+    outputs: &mut [&mut [u8]],
+    mut infer: impl FnMut(&[&[u8]], &mut [&mut [u8]]) -> Result<(), Error>,
+) -> Result<(), Error> {
+    if batches == 1 {
+        return infer(inputs, outputs);
+    }
+
+    for buffer in inputs
+        .iter()
+        .map(|b| b.len())
+        .chain(outputs.iter().map(|b| b.len()))
+    {
+        anyhow::ensure!(
+            buffer % batches == 0,
+            "A {} byte tensor can't be split into {} batches",
+            buffer,
+            batches
+        );
+    }
+
+    for batch in 0..batches {
+        let batch_inputs: Vec<&[u8]> = inputs
+            .iter()
+            .map(|buffer| {
+                let len = buffer.len() / batches;
+                &buffer[batch * len..(batch + 1) * len]
+            })
+            .collect();
+        let mut batch_outputs: Vec<&mut [u8]> = outputs
+            .iter_mut()
+            .map(|buffer| {
+                let len = buffer.len() / batches;
+                &mut buffer[batch * len..(batch + 1) * len]
+            })
+            .collect();
+
+        infer(&batch_inputs, &mut batch_outputs)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shapes(descriptors: &[&str]) -> Vec<Shape<'static>> {
+        descriptors.iter().map(|s| s.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn only_changing_the_batch_size_is_supported() {
+        let original = shapes(&["f32[1, 4]", "u8[2, 3]"]);
+
+        let inputs = [
+            (vec!["f32[1, 4]", "u8[2, 3]"], Some(1)),
+            (vec!["f32[5, 4]", "u8[10, 3]"], Some(5)),
+            // every input needs the same number of batches
+            (vec!["f32[2, 4]", "u8[2, 3]"], None),
+            // the batch size must be a multiple of the original
+            (vec!["f32[3, 4]", "u8[3, 3]"], None),
+            (vec!["f32[1, 5]", "u8[2, 3]"], None),
+            (vec!["i32[1, 4]", "u8[2, 3]"], None),
+            (vec!["f32[1, 4]"], None),
+        ];
+
+        for (resized, should_be) in inputs {
+            let got = batch_count(&original, &shapes(&resized));
+            assert_eq!(got, should_be, "{:?}", resized);
+        }
+    }
+
+    #[test]
+    fn rebatch_the_leading_dimension() {
+        let outputs = shapes(&["f32[1, 10]", "u8[2]"]);
+
+        let got = rebatch(&outputs, 3);
+
+        assert_eq!(got, shapes(&["f32[3, 10]", "u8[6]"]));
+    }
+
+    #[test]
+    fn run_each_batch_separately() {
+        let input = [1_u8, 2, 3, 4, 5, 6];
+        let mut output = [0_u8; 3];
+        let mut calls = 0;
+
+        infer_in_batches(
+            3,
+            &[&input],
+            &mut [&mut output],
+            |inputs, outputs| {
+                calls += 1;
+                outputs[0][0] = inputs[0].iter().sum();
+                Ok(())
+            },
+        )
+        .unwrap();
+
+        assert_eq!(calls, 3);
+        assert_eq!(output, [3, 7, 11]);
+    }
+
+    #[test]
+    fn a_single_batch_is_passed_through() {
+        let input = [1_u8, 2];
+        let mut output = [0_u8; 2];
+
+        infer_in_batches(
+            1,
+            &[&input],
+            &mut [&mut output],
+            |inputs, outputs| {
+                outputs[0].copy_from_slice(inputs[0]);
+                Ok(())
+            },
+        )
+        .unwrap();
+
+        assert_eq!(output, [1, 2]);
+    }
+}
@@ -0,0 +1,90 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
+
+use anyhow::Error;
+
+/// The environment variable used to choose which [`Delegate`]s TensorFlow
+/// Lite models should try to use, as a comma-separated list (e.g.
+/// `RUNE_TFLITE_DELEGATES=edgetpu,gpu`).
+pub const TFLITE_DELEGATES_ENV: &str = "RUNE_TFLITE_DELEGATES";
+
+/// A hardware accelerator that TensorFlow Lite can offload inference to,
+/// instead of running everything on the CPU's reference kernels.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Delegate {
+    /// A mobile or desktop GPU.
+    Gpu,
+    /// Android's Neural Networks API.
+    Nnapi,
+    /// A Coral EdgeTPU (e.g. the USB Accelerator).
+    EdgeTpu,
+}
+
+impl Delegate {
+    /// Parse a comma-separated list of delegates.
+    pub fn parse_list(s: &str) -> Result<Vec<Delegate>, UnknownDelegate> {
+        s.split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(str::parse)
+            .collect()
+    }
+
+    /// Get the delegates named by [`TFLITE_DELEGATES_ENV`], if it is set.
+    pub fn from_env() -> Result<Vec<Delegate>, Error> {
+        match std::env::var(TFLITE_DELEGATES_ENV) {
+            Ok(value) => Delegate::parse_list(&value).map_err(Error::from),
+            Err(_) => Ok(Vec::new()),
+        }
+    }
+}
+
+impl FromStr for Delegate {
+    type Err = UnknownDelegate;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "gpu" => Ok(Delegate::Gpu),
+            "nnapi" => Ok(Delegate::Nnapi),
+            "edgetpu" | "edge-tpu" | "coral" => Ok(Delegate::EdgeTpu),
+            _ => Err(UnknownDelegate {
+                name: s.to_string(),
+            }),
+        }
+    }
+}
+
+impl Display for Delegate {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Delegate::Gpu => write!(f, "gpu"),
+            Delegate::Nnapi => write!(f, "nnapi"),
+            Delegate::EdgeTpu => write!(f, "edgetpu"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+#[error(
+    "Unknown delegate, \"{}\" (expected \"gpu\", \"nnapi\", or \"edgetpu\")",
+    name
+)]
+pub struct UnknownDelegate {
+    pub name: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_a_list_of_delegates() {
+        let got = Delegate::parse_list("EdgeTPU, gpu,").unwrap();
+
+        assert_eq!(got, vec![Delegate::EdgeTpu, Delegate::Gpu]);
+        assert!(Delegate::parse_list("gpu,tpu").is_err());
+        assert!(Delegate::parse_list("").unwrap().is_empty());
+    }
+}
@@ -1,21 +1,51 @@
 //! Functions for handling various "well-known" model formats.
 
+#[cfg(any(feature = "tflite", feature = "torch", test))]
+pub(crate) mod batching;
+mod delegate;
+mod quantization;
 #[cfg(feature = "tflite")]
 mod tflite;
+#[cfg(feature = "torch")]
+mod torch;
+#[cfg(feature = "tract")]
+mod tract;
+mod variant;
+
+use std::{
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
 
 use anyhow::Error;
-pub use hotg_rune_core::{TFJS_MIMETYPE, TFLITE_MIMETYPE, TF_MIMETYPE};
+pub use hotg_rune_core::{
+    TFJS_MIMETYPE, TFLITE_MIMETYPE, TF_MIMETYPE, TORCHSCRIPT_MIMETYPE,
+};
 
+pub use self::{
+    delegate::{Delegate, UnknownDelegate, TFLITE_DELEGATES_ENV},
+    quantization::Quantization,
+    variant::{parse_variants, selected_variant, MODEL_VARIANTS_ENV},
+};
 #[cfg(feature = "tflite")]
-pub use self::tflite::load_tflite;
+pub use self::tflite::{load_tflite, load_tflite_with_delegates};
+#[cfg(feature = "torch")]
+pub use self::torch::load_torchscript;
+#[cfg(feature = "tract")]
+pub use self::tract::load_tflite_with_tract;
 use crate::callbacks::{Model, ModelMetadata};
 
 /// A model handler which will try to load a model based on the feature flags
 /// that have been set.
 ///
 /// Supported formats are:
-/// - TensorFlow Lite
-#[cfg_attr(not(feature = "tflite"), doc("(not supported)"))]
+/// - TensorFlow Lite (see [`TfliteBackend`] for choosing how they are run)
+#[cfg_attr(
+    not(any(feature = "tflite", feature = "tract")),
+    doc("(not supported)")
+)]
+/// - TorchScript
+#[cfg_attr(not(feature = "torch"), doc("(not supported)"))]
 pub fn default_model_handler(
     _id: u32,
     meta: &ModelMetadata<'_>,
@@ -29,12 +59,129 @@ pub fn default_model_handler(
     } = *meta;
 
     match mimetype {
-        #[cfg(feature = "tflite")]
-        TFLITE_MIMETYPE => load_tflite(model, inputs, outputs),
+        #[cfg(any(feature = "tflite", feature = "tract"))]
+        TFLITE_MIMETYPE => {
+            let backend = TfliteBackend::from_env()?;
+            let delegates = Delegate::from_env()?;
+            load_tflite_with_backend(
+                backend, &delegates, model, inputs, outputs,
+            )
+        },
+        #[cfg(feature = "torch")]
+        TORCHSCRIPT_MIMETYPE => load_torchscript(model, inputs, outputs),
         _ => Err(UnsupportedModelFormat::new(mimetype).into()),
     }
 }
 
+/// The environment variable used to choose a [`TfliteBackend`] (e.g.
+/// `RUNE_TFLITE_BACKEND=tract`).
+pub const TFLITE_BACKEND_ENV: &str = "RUNE_TFLITE_BACKEND";
+
+/// The library used to run TensorFlow Lite models.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TfliteBackend {
+    /// The TensorFlow Lite C++ library, via [`hotg_runecoral`] (requires the
+    /// `tflite` feature).
+    RuneCoral,
+    /// The pure-Rust [`tract`](https://github.com/sonos/tract) inference
+    /// engine (requires the `tract` feature).
+    Tract,
+}
+
+impl TfliteBackend {
+    /// Get the backend named by [`TFLITE_BACKEND_ENV`], falling back to
+    /// [`TfliteBackend::default()`] when it isn't set.
+    pub fn from_env() -> Result<Self, Error> {
+        match std::env::var(TFLITE_BACKEND_ENV) {
+            Ok(name) => name.parse().map_err(Error::from),
+            Err(_) => Ok(TfliteBackend::default()),
+        }
+    }
+}
+
+impl Default for TfliteBackend {
+    /// Prefer the TensorFlow Lite library when it is available because it
+    /// supports the most operators.
+    fn default() -> Self {
+        if cfg!(feature = "tflite") {
+            TfliteBackend::RuneCoral
+        } else {
+            TfliteBackend::Tract
+        }
+    }
+}
+
+impl FromStr for TfliteBackend {
+    type Err = UnknownTfliteBackend;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "runecoral" | "tflite" => Ok(TfliteBackend::RuneCoral),
+            "tract" => Ok(TfliteBackend::Tract),
+            _ => Err(UnknownTfliteBackend {
+                name: s.to_string(),
+            }),
+        }
+    }
+}
+
+impl Display for TfliteBackend {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            TfliteBackend::RuneCoral => write!(f, "runecoral"),
+            TfliteBackend::Tract => write!(f, "tract"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+#[error(
+    "Unknown TensorFlow Lite backend, \"{}\" (expected \"runecoral\" or \
+     \"tract\")",
+    name
+)]
+pub struct UnknownTfliteBackend {
+    pub name: String,
+}
+
+/// Load a TensorFlow Lite model using a particular [`TfliteBackend`] and
+/// set of [`Delegate`]s.
+///
+/// Hosts which want to pick a backend or delegates for each model can call
+/// this from their own model handler.
+#[cfg(any(feature = "tflite", feature = "tract"))]
+pub fn load_tflite_with_backend(
+    backend: TfliteBackend,
+    delegates: &[Delegate],
+    model: &[u8],
+    inputs: &[hotg_rune_core::Shape<'_>],
+    outputs: &[hotg_rune_core::Shape<'_>],
+) -> Result<Box<dyn Model>, Error> {
+    match backend {
+        #[cfg(feature = "tflite")]
+        TfliteBackend::RuneCoral => {
+            load_tflite_with_delegates(model, inputs, outputs, delegates)
+        },
+        #[cfg(feature = "tract")]
+        TfliteBackend::Tract => {
+            if !delegates.is_empty() {
+                log::warn!(
+                    "The tract backend doesn't support delegates, so {:?} \
+                     will be ignored",
+                    delegates
+                );
+            }
+            load_tflite_with_tract(model, inputs, outputs)
+        },
+        #[allow(unreachable_patterns)]
+        other => anyhow::bail!(
+            "The runtime was compiled without support for the \"{}\" \
+             backend",
+            other
+        ),
+    }
+}
+
 /// The error returned when the model handler can't handle a particular model
 /// format.
 #[derive(Debug, Clone, PartialEq, thiserror::Error)]
@@ -51,3 +198,41 @@ impl UnsupportedModelFormat {
         }
     }
 }
+
+/// Make sure resizing a model's inputs doesn't change their element types.
+#[cfg(any(feature = "torch", feature = "tract"))]
+pub(crate) fn ensure_same_element_types(
+    original: &[hotg_rune_core::Shape<'_>],
+    resized: &[hotg_rune_core::Shape<'_>],
+) -> Result<(), Error> {
+    let same_types = original.len() == resized.len()
+        && original
+            .iter()
+            .zip(resized)
+            .all(|(a, b)| a.element_type() == b.element_type());
+
+    anyhow::ensure!(
+        same_types,
+        "Inputs can only be resized, not changed from {:?} to {:?}",
+        original,
+        resized,
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_tflite_backends() {
+        for backend in [TfliteBackend::RuneCoral, TfliteBackend::Tract] {
+            let round_tripped: TfliteBackend =
+                backend.to_string().parse().unwrap();
+            assert_eq!(round_tripped, backend);
+        }
+
+        assert!("coreml".parse::<TfliteBackend>().is_err());
+    }
+}
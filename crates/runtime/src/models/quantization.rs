@@ -0,0 +1,127 @@
+use std::convert::TryInto;
+
+use anyhow::Error;
+use hotg_rune_core::ElementType;
+
+/// The parameters used to map between a quantized model's `i8`/`u8` tensors
+/// and the `f32` values they represent, where
+/// `real_value = scale * (quantized_value - zero_point)`.
+///
+/// This lets a Rune feed `f32` tensors to a quantized model and get `f32`
+/// results back, instead of hand-writing scaling proc blocks.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Quantization {
+    pub scale: f32,
+    pub zero_point: i32,
+}
+
+impl Quantization {
+    pub const fn new(scale: f32, zero_point: i32) -> Self {
+        Quantization { scale, zero_point }
+    }
+
+    /// Quantize little-endian `f32`s into `i8` or `u8` values, saturating
+    /// anything that is out of range.
+    pub fn quantize(
+        &self,
+        values: &[u8],
+        element_type: ElementType,
+    ) -> Result<Vec<u8>, Error> {
+        let (min, max) = range(element_type)?;
+
+        Ok(floats(values)?
+            .map(|value| {
+                let q = (value / self.scale).round() + self.zero_point as f32;
+                q.max(min).min(max) as i32 as u8
+            })
+            .collect())
+    }
+
+    /// Convert quantized `i8` or `u8` values back into little-endian `f32`s,
+    /// writing them to `output`.
+    pub fn dequantize(
+        &self,
+        quantized: &[u8],
+        element_type: ElementType,
+        output: &mut [u8],
+    ) -> Result<(), Error> {
+        range(element_type)?;
+        anyhow::ensure!(
+            quantized.len() * std::mem::size_of::<f32>() == output.len(),
+            "Unable to dequantize {} values into a {} byte buffer",
+            quantized.len(),
+            output.len()
+        );
+
+        for (&q, dest) in quantized.iter().zip(output.chunks_exact_mut(4)) {
+            let q = match element_type {
+                ElementType::I8 => q as i8 as i32,
+                _ => q as i32,
+            };
+            let value = self.scale * (q - self.zero_point) as f32;
+            dest.copy_from_slice(&value.to_le_bytes());
+        }
+
+        Ok(())
+    }
+}
+
+fn range(element_type: ElementType) -> Result<(f32, f32), Error> {
+    match element_type {
+        ElementType::I8 => Ok((i8::MIN as f32, i8::MAX as f32)),
+        ElementType::U8 => Ok((u8::MIN as f32, u8::MAX as f32)),
+        _ => anyhow::bail!(
+            "Only i8 and u8 tensors can be quantized, not {:?}",
+            element_type
+        ),
+    }
+}
+
+fn floats(bytes: &[u8]) -> Result<impl Iterator<Item = f32> + '_, Error> {
+    anyhow::ensure!(
+        bytes.len() % 4 == 0,
+        "A buffer of {} bytes doesn't contain a whole number of f32s",
+        bytes.len()
+    );
+
+    Ok(bytes
+        .chunks_exact(4)
+        .map(|chunk| f32::from_le_bytes(chunk.try_into().unwrap())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_bytes(values: &[f32]) -> Vec<u8> {
+        values.iter().flat_map(|v| v.to_le_bytes()).collect()
+    }
+
+    #[test]
+    fn quantize_to_i8() {
+        let quantization = Quantization::new(0.5, -10);
+        let values = to_bytes(&[0.0, 1.0, -1.25, 100.0, -100.0]);
+
+        let got = quantization.quantize(&values, ElementType::I8).unwrap();
+
+        let got: Vec<i8> = got.into_iter().map(|q| q as i8).collect();
+        assert_eq!(got, vec![-10, -8, -13, 127, -128]);
+    }
+
+    #[test]
+    fn round_trip_through_u8() {
+        let quantization = Quantization::new(0.25, 128);
+        let values = [0.0, 0.5, -2.0, 31.75];
+
+        let quantized = quantization
+            .quantize(&to_bytes(&values), ElementType::U8)
+            .unwrap();
+        assert_eq!(quantized, vec![128, 130, 120, 255]);
+        let mut output = vec![0; values.len() * 4];
+        quantization
+            .dequantize(&quantized, ElementType::U8, &mut output)
+            .unwrap();
+
+        assert_eq!(output, to_bytes(&values));
+    }
+}
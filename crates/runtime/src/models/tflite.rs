@@ -7,13 +7,30 @@ use hotg_runecoral::{
     TensorDescriptor, TensorMut,
 };
 
-use crate::callbacks::Model;
+use crate::{
+    callbacks::Model,
+    models::{batching, Delegate},
+};
 
 /// Create a new [`Model`] backed by [`hotg_runecoral`].
 pub fn load_tflite(
     model: &[u8],
     inputs: &[Shape<'_>],
     outputs: &[Shape<'_>],
+) -> Result<Box<dyn Model>, Error> {
+    load_tflite_with_delegates(model, inputs, outputs, &[])
+}
+
+/// Create a new [`Model`] backed by [`hotg_runecoral`] which offloads
+/// inference to the first of the requested [`Delegate`]s that is available.
+///
+/// If none of the delegates can be used (e.g. because there is no EdgeTPU
+/// plugged in) the model falls back to running on the CPU.
+pub fn load_tflite_with_delegates(
+    model: &[u8],
+    inputs: &[Shape<'_>],
+    outputs: &[Shape<'_>],
+    delegates: &[Delegate],
 ) -> Result<Box<dyn Model>, Error> {
     let input_descriptors = inputs
         .iter()
@@ -26,32 +43,85 @@ pub fn load_tflite(
         .collect::<Result<Vec<_>, Error>>()
         .context("Invalid output")?;
 
-    let ctx = InferenceContext::create_context(
-        TFLITE_MIMETYPE,
-        model,
-        AccelerationBackend::NONE,
-    )
-    .context("Unable to create the inference context")?;
+    let ctx = create_context(model, delegates)?;
 
     let model_input_descriptors: Vec<_> = ctx.inputs().collect();
     ensure_shapes_equal(&input_descriptors, &model_input_descriptors)?;
     let model_output_descriptors: Vec<_> = ctx.outputs().collect();
     ensure_shapes_equal(&output_descriptors, &model_output_descriptors)?;
 
+    let inputs: Vec<_> = inputs.iter().map(|s| s.to_owned()).collect();
+    let outputs: Vec<_> = outputs.iter().map(|s| s.to_owned()).collect();
+
     Ok(Box::new(RuneCoralModel {
         ctx: Mutex::new(ctx),
-        inputs: inputs.iter().map(|s| s.to_owned()).collect(),
+        model_inputs: inputs.clone(),
         input_descriptors,
-        outputs: outputs.iter().map(|s| s.to_owned()).collect(),
+        model_outputs: outputs.clone(),
         output_descriptors,
+        batches: 1,
+        inputs,
+        outputs,
     }))
 }
 
+fn create_context(
+    model: &[u8],
+    delegates: &[Delegate],
+) -> Result<InferenceContext, Error> {
+    for &delegate in delegates {
+        let backend = match acceleration_backend(delegate) {
+            Some(backend) => backend,
+            None => {
+                log::warn!(
+                    "librunecoral doesn't support the {} delegate",
+                    delegate
+                );
+                continue;
+            },
+        };
+
+        match InferenceContext::create_context(TFLITE_MIMETYPE, model, backend)
+        {
+            Ok(ctx) => {
+                log::debug!("Running the model with the {} delegate", delegate);
+                return Ok(ctx);
+            },
+            Err(e) => {
+                log::warn!(
+                    "Unable to use the {} delegate ({}), trying the next one",
+                    delegate,
+                    e
+                );
+            },
+        }
+    }
+
+    if !delegates.is_empty() {
+        log::warn!("No delegates were available, falling back to the CPU");
+    }
+
+    InferenceContext::create_context(
+        TFLITE_MIMETYPE,
+        model,
+        AccelerationBackend::NONE,
+    )
+    .context("Unable to create the inference context")
+}
+
+fn acceleration_backend(delegate: Delegate) -> Option<AccelerationBackend> {
+    match delegate {
+        Delegate::Gpu => Some(AccelerationBackend::GPU),
+        Delegate::EdgeTpu => Some(AccelerationBackend::EDGETPU),
+        Delegate::Nnapi => None,
+    }
+}
+
 fn descriptor(s: &Shape) -> Result<TensorDescriptor<'static>, Error> {
     let dimensions: Vec<i32> = s
-        .dimensions()
-        .iter()
-        .copied()
+        .fixed_dimensions()
+        .with_context(|| format!("The dimensions in {} must all be known", s))?
+        .into_iter()
         .map(|d| d.try_into().unwrap())
         .collect();
 
@@ -64,10 +134,17 @@ fn descriptor(s: &Shape) -> Result<TensorDescriptor<'static>, Error> {
 
 struct RuneCoralModel {
     ctx: Mutex<InferenceContext>,
-    inputs: Vec<Shape<'static>>,
+    /// The input shapes the model was compiled for.
+    model_inputs: Vec<Shape<'static>>,
     input_descriptors: Vec<TensorDescriptor<'static>>,
-    outputs: Vec<Shape<'static>>,
+    /// The output shapes the model was compiled for.
+    model_outputs: Vec<Shape<'static>>,
     output_descriptors: Vec<TensorDescriptor<'static>>,
+    /// How many times the model is run per inference, for Runes which use a
+    /// bigger batch size than the model was compiled for.
+    batches: usize,
+    inputs: Vec<Shape<'static>>,
+    outputs: Vec<Shape<'static>>,
 }
 
 impl Model for RuneCoralModel {
@@ -76,39 +153,70 @@ impl Model for RuneCoralModel {
         inputs: &[&[u8]],
         outputs: &mut [&mut [u8]],
     ) -> Result<(), Error> {
-        let mut ctx = self.ctx.lock().expect("Lock was poisoned");
-
-        let inputs: Vec<Tensor<'_>> = self
-            .input_descriptors
-            .iter()
-            .zip(inputs)
-            .map(|(desc, data)| Tensor {
-                element_type: desc.element_type,
-                shape: Cow::Borrowed(&desc.shape),
-                buffer: *data,
-            })
-            .collect();
-
-        let mut outputs: Vec<TensorMut<'_>> = self
-            .output_descriptors
-            .iter()
-            .zip(outputs)
-            .map(|(desc, data)| TensorMut {
-                element_type: desc.element_type,
-                shape: Cow::Borrowed(&desc.shape),
-                buffer: *data,
-            })
-            .collect();
-
-        ctx.infer(&inputs, &mut outputs)
-            .context("Inference failed")?;
+        let batches = self.batches;
+        let RuneCoralModel {
+            ctx,
+            input_descriptors,
+            output_descriptors,
+            ..
+        } = self;
+        let mut ctx = ctx.lock().expect("Lock was poisoned");
 
-        Ok(())
+        batching::infer_in_batches(
+            batches,
+            inputs,
+            outputs,
+            |inputs, outputs| {
+                let inputs: Vec<Tensor<'_>> = input_descriptors
+                    .iter()
+                    .zip(inputs)
+                    .map(|(desc, data)| Tensor {
+                        element_type: desc.element_type,
+                        shape: Cow::Borrowed(&desc.shape),
+                        buffer: *data,
+                    })
+                    .collect();
+
+                let mut outputs: Vec<TensorMut<'_>> = output_descriptors
+                    .iter()
+                    .zip(outputs.iter_mut())
+                    .map(|(desc, data)| TensorMut {
+                        element_type: desc.element_type,
+                        shape: Cow::Borrowed(&desc.shape),
+                        buffer: &mut **data,
+                    })
+                    .collect();
+
+                ctx.infer(&inputs, &mut outputs)
+                    .context("Inference failed")?;
+
+                Ok(())
+            },
+        )
     }
 
     fn input_shapes(&self) -> &[Shape<'_>] { &self.inputs }
 
     fn output_shapes(&self) -> &[Shape<'_>] { &self.outputs }
+
+    fn resize_inputs(&mut self, shapes: &[Shape<'_>]) -> Result<(), Error> {
+        // TensorFlow Lite models are compiled for a particular batch size, so
+        // larger batches are split up and run one at a time
+        let batches = batching::batch_count(&self.model_inputs, shapes)
+            .with_context(|| {
+                format!(
+                    "TensorFlow Lite models can only change their batch size, \
+                     so the inputs can't be resized from {:?} to {:?}",
+                    self.model_inputs, shapes
+                )
+            })?;
+
+        self.batches = batches;
+        self.inputs = shapes.iter().map(|s| s.to_owned()).collect();
+        self.outputs = batching::rebatch(&self.model_outputs, batches);
+
+        Ok(())
+    }
 }
 
 fn element_type(rune_type: RuneElementType) -> Result<ElementType, Error> {
@@ -121,6 +229,7 @@ fn element_type(rune_type: RuneElementType) -> Result<ElementType, Error> {
         RuneElementType::F32 => ElementType::Float32,
         RuneElementType::F64 => ElementType::Float64,
         RuneElementType::String => ElementType::String,
+        RuneElementType::Bool => ElementType::Bool,
         _ => {
             anyhow::bail!(
                 "librunecoral doesn't support {:?} tensors",
@@ -153,10 +262,21 @@ fn ensure_shapes_equal(
         )
     }
 
+    let is_quantized = from_rune.iter().zip(from_model).any(|(x, y)| {
+        x.element_type == ElementType::Float32
+            && matches!(y.element_type, ElementType::Int8 | ElementType::UInt8)
+    });
+    let hint = if is_quantized {
+        " (use the tract backend to quantize f32 tensors automatically)"
+    } else {
+        ""
+    };
+
     anyhow::bail!(
         "The Rune said tensors would be {}, but the model said they would be \
-         {}",
+         {}{}",
         pretty_shapes(from_rune),
         pretty_shapes(from_model),
+        hint,
     );
 }
@@ -0,0 +1,167 @@
+use std::{convert::TryInto, io::Cursor, sync::Mutex};
+
+use anyhow::{Context, Error};
+use hotg_rune_core::{ElementType, Shape};
+use tch::{CModule, IValue, Kind, Tensor};
+
+use crate::{callbacks::Model, models::batching};
+
+/// Create a new [`Model`] from a TorchScript module (e.g. the `.pt` file
+/// saved by `torch.jit.save()`), backed by [`tch`].
+pub fn load_torchscript(
+    model: &[u8],
+    inputs: &[Shape<'_>],
+    outputs: &[Shape<'_>],
+) -> Result<Box<dyn Model>, Error> {
+    for shape in inputs.iter().chain(outputs) {
+        kind(shape.element_type())?;
+    }
+
+    let module = CModule::load_data(&mut Cursor::new(model))
+        .context("Unable to load the TorchScript module")?;
+
+    let inputs: Vec<_> = inputs.iter().map(|s| s.to_owned()).collect();
+    let outputs: Vec<_> = outputs.iter().map(|s| s.to_owned()).collect();
+
+    Ok(Box::new(TorchModel {
+        module: Mutex::new(module),
+        model_inputs: inputs.clone(),
+        model_outputs: outputs.clone(),
+        inputs,
+        outputs,
+    }))
+}
+
+struct TorchModel {
+    module: Mutex<CModule>,
+    /// The input shapes from the Runefile.
+    model_inputs: Vec<Shape<'static>>,
+    /// The output shapes from the Runefile.
+    model_outputs: Vec<Shape<'static>>,
+    inputs: Vec<Shape<'static>>,
+    outputs: Vec<Shape<'static>>,
+}
+
+impl Model for TorchModel {
+    fn infer(
+        &mut self,
+        inputs: &[&[u8]],
+        outputs: &mut [&mut [u8]],
+    ) -> Result<(), Error> {
+        let module = self.module.lock().expect("Lock was poisoned");
+
+        let inputs = self
+            .inputs
+            .iter()
+            .zip(inputs)
+            .map(|(shape, data)| {
+                let dimensions = dimensions(shape)?;
+                let kind = kind(shape.element_type())?;
+                Ok(IValue::Tensor(Tensor::of_data_size(
+                    data,
+                    &dimensions,
+                    kind,
+                )))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        let result =
+            module.forward_is(&inputs).context("Inference failed")?;
+
+        let results = match result {
+            IValue::Tensor(tensor) => vec![tensor],
+            IValue::Tuple(values) | IValue::GenericList(values) => values
+                .into_iter()
+                .map(|value| match value {
+                    IValue::Tensor(tensor) => Ok(tensor),
+                    other => Err(anyhow::anyhow!(
+                        "Expected the model to return tensors, found {:?}",
+                        other
+                    )),
+                })
+                .collect::<Result<Vec<_>, Error>>()?,
+            IValue::TensorList(tensors) => tensors,
+            other => anyhow::bail!(
+                "Expected the model to return tensors, found {:?}",
+                other
+            ),
+        };
+
+        anyhow::ensure!(
+            results.len() == outputs.len(),
+            "The Rune expected {} outputs, but the model returned {}",
+            outputs.len(),
+            results.len()
+        );
+
+        for ((result, shape), output) in
+            results.iter().zip(&self.outputs).zip(outputs.iter_mut())
+        {
+            let expected = dimensions(shape)?;
+            anyhow::ensure!(
+                result.size() == expected,
+                "The Rune said the output would be {}, but the model \
+                 returned {:?}",
+                shape,
+                result.size()
+            );
+
+            let result = result.to_kind(kind(shape.element_type())?);
+            result.contiguous().copy_data_u8(output, result.numel());
+        }
+
+        Ok(())
+    }
+
+    fn input_shapes(&self) -> &[Shape<'_>] { &self.inputs }
+
+    fn output_shapes(&self) -> &[Shape<'_>] { &self.outputs }
+
+    fn resize_inputs(&mut self, shapes: &[Shape<'_>]) -> Result<(), Error> {
+        // TorchScript modules accept whatever shapes they are given, so we
+        // just need to remember them
+        crate::models::ensure_same_element_types(&self.inputs, shapes)?;
+        self.inputs = shapes.iter().map(|s| s.to_owned()).collect();
+
+        // If only the batch size changed, the outputs will have the same
+        // number of batches. Otherwise (e.g. a longer audio clip) we assume
+        // the outputs stay the same, and inference will fail if they don't.
+        if let Some(batches) = batching::batch_count(&self.model_inputs, shapes)
+        {
+            self.outputs = batching::rebatch(&self.model_outputs, batches);
+        }
+
+        Ok(())
+    }
+}
+
+fn dimensions(shape: &Shape<'_>) -> Result<Vec<i64>, Error> {
+    shape
+        .fixed_dimensions()
+        .with_context(|| {
+            format!("The dimensions in {} must all be known", shape)
+        })?
+        .into_iter()
+        .map(|d| d.try_into().context("The dimension is too big"))
+        .collect()
+}
+
+fn kind(element_type: ElementType) -> Result<Kind, Error> {
+    Ok(match element_type {
+        ElementType::U8 => Kind::Uint8,
+        ElementType::I8 => Kind::Int8,
+        ElementType::I16 => Kind::Int16,
+        ElementType::I32 => Kind::Int,
+        ElementType::I64 => Kind::Int64,
+        ElementType::F32 => Kind::Float,
+        ElementType::F64 => Kind::Double,
+        ElementType::F16 => Kind::Half,
+        ElementType::BF16 => Kind::BFloat16,
+        ElementType::Bool => Kind::Bool,
+        ElementType::C64 => Kind::ComplexFloat,
+        ElementType::C128 => Kind::ComplexDouble,
+        _ => {
+            anyhow::bail!("PyTorch doesn't support {:?} tensors", element_type)
+        },
+    })
+}
@@ -0,0 +1,298 @@
+use std::io::Cursor;
+
+use anyhow::{Context, Error};
+use hotg_rune_core::{ElementType, Shape};
+use tract_tflite::prelude::{
+    tvec, DatumType, Framework, Tensor, TypedFact, TypedModel,
+    TypedRunnableModel,
+};
+
+use crate::{callbacks::Model, models::Quantization};
+
+/// Create a new [`Model`] from a TensorFlow Lite model using the pure-Rust
+/// [`tract_tflite`] crate.
+///
+/// This is slower than [`crate::models::load_tflite()`] for some models, but
+/// doesn't need a C++ toolchain and can be cross-compiled like any other Rust
+/// code.
+///
+/// When the Rune uses `f32` tensors for a quantized model's `i8`/`u8` inputs
+/// or outputs, they are automatically quantized and dequantized using the
+/// model's quantization parameters. Similarly, `f32` tensors are converted
+/// to and from `f16` for models which were quantized to half-precision.
+pub fn load_tflite_with_tract(
+    model: &[u8],
+    inputs: &[Shape<'_>],
+    outputs: &[Shape<'_>],
+) -> Result<Box<dyn Model>, Error> {
+    for shape in inputs.iter().chain(outputs) {
+        datum_type(shape.element_type())?;
+    }
+
+    let plan = tract_tflite::tflite()
+        .model_for_read(&mut Cursor::new(model))
+        .context("Unable to parse the TensorFlow Lite model")?;
+
+    anyhow::ensure!(
+        plan.inputs.len() == inputs.len()
+            && plan.outputs.len() == outputs.len(),
+        "The Rune said the model would have {} inputs and {} outputs, but it \
+         has {} inputs and {} outputs",
+        inputs.len(),
+        outputs.len(),
+        plan.inputs.len(),
+        plan.outputs.len(),
+    );
+
+    let input_conversions = inputs
+        .iter()
+        .enumerate()
+        .map(|(i, shape)| conversion(shape, plan.input_fact(i)?.datum_type))
+        .collect::<Result<Vec<_>, Error>>()?;
+    let output_conversions = outputs
+        .iter()
+        .enumerate()
+        .map(|(i, shape)| conversion(shape, plan.output_fact(i)?.datum_type))
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    let source = plan;
+    let plan = optimize(source.clone())?;
+
+    Ok(Box::new(TractModel {
+        source,
+        plan,
+        inputs: inputs.iter().map(|s| s.to_owned()).collect(),
+        input_conversions,
+        outputs: outputs.iter().map(|s| s.to_owned()).collect(),
+        output_conversions,
+    }))
+}
+
+/// How to convert a tensor from the Rune to the type the model expects (or
+/// vice versa).
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Conversion {
+    /// The Rune and model use the same type, so the data can be used as-is.
+    None,
+    /// The Rune uses `f32`, but the model uses half-precision floats.
+    Cast { model_type: DatumType },
+    /// The Rune uses `f32`, but the model uses quantized `i8`/`u8` values.
+    Quantized {
+        model_type: DatumType,
+        element_type: ElementType,
+        quantization: Quantization,
+    },
+}
+
+fn conversion(
+    shape: &Shape<'_>,
+    model_type: DatumType,
+) -> Result<Conversion, Error> {
+    if shape.element_type() != ElementType::F32 {
+        return Ok(Conversion::None);
+    }
+
+    if model_type == DatumType::F16 {
+        return Ok(Conversion::Cast { model_type });
+    }
+
+    if !model_type.is_quantized() {
+        return Ok(Conversion::None);
+    }
+
+    let element_type = match model_type.unquantized() {
+        DatumType::I8 => ElementType::I8,
+        DatumType::U8 => ElementType::U8,
+        other => anyhow::bail!("Unsupported quantized type, {:?}", other),
+    };
+    let (zero_point, scale) = model_type.zp_scale();
+
+    Ok(Conversion::Quantized {
+        model_type,
+        element_type,
+        quantization: Quantization::new(scale, zero_point),
+    })
+}
+
+fn optimize(
+    model: TypedModel,
+) -> Result<TypedRunnableModel<TypedModel>, Error> {
+    model
+        .into_optimized()
+        .and_then(TypedModel::into_runnable)
+        .context("Unable to optimize the model")
+}
+
+struct TractModel {
+    /// The model as it was loaded, before optimizing it for specific input
+    /// shapes.
+    source: TypedModel,
+    plan: TypedRunnableModel<TypedModel>,
+    inputs: Vec<Shape<'static>>,
+    input_conversions: Vec<Conversion>,
+    outputs: Vec<Shape<'static>>,
+    output_conversions: Vec<Conversion>,
+}
+
+impl Model for TractModel {
+    fn infer(
+        &mut self,
+        inputs: &[&[u8]],
+        outputs: &mut [&mut [u8]],
+    ) -> Result<(), Error> {
+        let mut tensors = tvec![];
+
+        for ((shape, conversion), data) in self
+            .inputs
+            .iter()
+            .zip(&self.input_conversions)
+            .zip(inputs)
+        {
+            let dimensions = fixed_dimensions(shape)?;
+            let dimensions = dimensions.as_slice();
+            // Safety: the datum type and dimensions both come from the
+            // shape (or the model), and tract checks that the buffer has the
+            // right length
+            let tensor = match *conversion {
+                Conversion::None => {
+                    let dt = datum_type(shape.element_type())?;
+                    unsafe { Tensor::from_raw_dt(dt, dimensions, data)? }
+                },
+                Conversion::Cast { model_type } => {
+                    let tensor = unsafe {
+                        Tensor::from_raw_dt(DatumType::F32, dimensions, data)?
+                    };
+                    tensor.cast_to_dt(model_type)?.into_owned()
+                },
+                Conversion::Quantized {
+                    model_type,
+                    element_type,
+                    quantization,
+                } => {
+                    let quantized = quantization.quantize(data, element_type)?;
+                    unsafe {
+                        Tensor::from_raw_dt(model_type, dimensions, &quantized)?
+                    }
+                },
+            };
+            tensors.push(tensor.into());
+        }
+
+        let results = self.plan.run(tensors).context("Inference failed")?;
+
+        anyhow::ensure!(
+            results.len() == outputs.len(),
+            "The Rune expected {} outputs, but the model returned {}",
+            outputs.len(),
+            results.len()
+        );
+
+        for (((result, shape), conversion), output) in results
+            .iter()
+            .zip(&self.outputs)
+            .zip(&self.output_conversions)
+            .zip(outputs.iter_mut())
+        {
+            let result_shape = Shape::new(shape.element_type(), result.shape());
+            anyhow::ensure!(
+                shape.accepts(&result_shape),
+                "The Rune said the output would be {}, but the model \
+                 returned {:?}",
+                shape,
+                result.shape()
+            );
+
+            if let Conversion::Quantized {
+                element_type,
+                quantization,
+                ..
+            } = *conversion
+            {
+                // Safety: quantized tensors are stored as plain i8s or u8s
+                let quantized = unsafe { result.as_bytes() };
+                quantization.dequantize(quantized, element_type, output)?;
+                continue;
+            }
+
+            let result = result.cast_to_dt(datum_type(shape.element_type())?)?;
+            // Safety: tensors are plain-old-data, and the datum type
+            // matches the element type the Rune is expecting
+            let bytes = unsafe { result.as_bytes() };
+            anyhow::ensure!(
+                bytes.len() == output.len(),
+                "Expected a {} byte output, found {} bytes",
+                output.len(),
+                bytes.len()
+            );
+            output.copy_from_slice(bytes);
+        }
+
+        Ok(())
+    }
+
+    fn input_shapes(&self) -> &[Shape<'_>] { &self.inputs }
+
+    fn output_shapes(&self) -> &[Shape<'_>] { &self.outputs }
+
+    fn resize_inputs(&mut self, shapes: &[Shape<'_>]) -> Result<(), Error> {
+        crate::models::ensure_same_element_types(&self.inputs, shapes)?;
+
+        // The optimized plan is specialised for the old input shapes, so we
+        // need to go back to the original model and re-optimize it
+        let mut model = self.source.clone();
+
+        for (i, shape) in shapes.iter().enumerate() {
+            let datum_type = model.input_fact(i)?.datum_type;
+            let dimensions = fixed_dimensions(shape)?;
+            let fact = TypedFact::dt_shape(datum_type, dimensions);
+            model.set_input_fact(i, fact)?;
+        }
+
+        let plan = optimize(model)?;
+
+        // The new input shapes may also change the output shapes, so ask the
+        // optimized model what they will be
+        let outputs = self
+            .outputs
+            .iter()
+            .enumerate()
+            .map(|(i, shape)| {
+                let fact = plan.model().output_fact(i)?;
+                let dimensions = fact.shape.as_concrete().with_context(|| {
+                    format!("Unable to determine output {}'s shape", i)
+                })?;
+                Ok(Shape::new(shape.element_type(), dimensions.to_vec()))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        self.plan = plan;
+        self.inputs = shapes.iter().map(|s| s.to_owned()).collect();
+        self.outputs = outputs;
+
+        Ok(())
+    }
+}
+
+fn fixed_dimensions(shape: &Shape<'_>) -> Result<Vec<usize>, Error> {
+    shape.fixed_dimensions().with_context(|| {
+        format!("The dimensions in {} must all be known", shape)
+    })
+}
+
+fn datum_type(element_type: ElementType) -> Result<DatumType, Error> {
+    Ok(match element_type {
+        ElementType::U8 => DatumType::U8,
+        ElementType::I8 => DatumType::I8,
+        ElementType::U16 => DatumType::U16,
+        ElementType::I16 => DatumType::I16,
+        ElementType::U32 => DatumType::U32,
+        ElementType::I32 => DatumType::I32,
+        ElementType::U64 => DatumType::U64,
+        ElementType::I64 => DatumType::I64,
+        ElementType::F32 => DatumType::F32,
+        ElementType::F64 => DatumType::F64,
+        ElementType::F16 => DatumType::F16,
+        ElementType::Bool => DatumType::Bool,
+        _ => anyhow::bail!("tract doesn't support {:?} tensors", element_type),
+    })
+}
@@ -0,0 +1,61 @@
+use anyhow::{Context, Error};
+
+/// The environment variable used to choose which variant of each model should
+/// be loaded, as a comma-separated list of `model=variant` pairs (e.g.
+/// `RUNE_MODEL_VARIANTS=classifier=small,detector=large`).
+///
+/// Models which aren't mentioned use their default variant.
+pub const MODEL_VARIANTS_ENV: &str = "RUNE_MODEL_VARIANTS";
+
+/// Parse a comma-separated list of `model=variant` pairs.
+pub fn parse_variants(s: &str) -> Result<Vec<(String, String)>, Error> {
+    s.split(',')
+        .map(str::trim)
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (model, variant) = pair.split_once('=').with_context(|| {
+                format!("Expected \"model=variant\", but found \"{}\"", pair)
+            })?;
+            Ok((model.trim().to_string(), variant.trim().to_string()))
+        })
+        .collect()
+}
+
+/// Get the variant [`MODEL_VARIANTS_ENV`] says should be used for a model,
+/// if any.
+pub fn selected_variant(model: &str) -> Result<Option<String>, Error> {
+    let value = match std::env::var(MODEL_VARIANTS_ENV) {
+        Ok(value) => value,
+        Err(_) => return Ok(None),
+    };
+
+    let variants = parse_variants(&value).with_context(|| {
+        format!("Unable to parse the {} variable", MODEL_VARIANTS_ENV)
+    })?;
+
+    Ok(variants
+        .into_iter()
+        .find(|(name, _)| name == model)
+        .map(|(_, variant)| variant))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_a_list_of_variants() {
+        let got =
+            parse_variants("classifier=small, detector = large,").unwrap();
+
+        assert_eq!(
+            got,
+            vec![
+                ("classifier".to_string(), "small".to_string()),
+                ("detector".to_string(), "large".to_string()),
+            ]
+        );
+        assert!(parse_variants("classifier").is_err());
+        assert!(parse_variants("").unwrap().is_empty());
+    }
+}
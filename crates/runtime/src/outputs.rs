@@ -1,25 +1,59 @@
+use std::{borrow::Cow, num::NonZeroUsize};
+
 use anyhow::{Context, Error};
+use hotg_rune_core::encoding::{self, EncodedTensor, Element};
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_json::{Map, Value};
 
-use crate::{NodeMetadata, Tensor, TensorElement};
+use crate::{BufferPool, ElementType, NodeMetadata, Tensor, TensorElement};
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum OutputTensor {
     Tensor(Tensor),
     StringTensor {
         dimensions: Vec<usize>,
         strings: Vec<String>,
     },
+    Record(Record),
+}
+
+/// A structured message with named fields, sent by serial outputs which were
+/// given a list of `fields`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Record {
+    /// The name, type, and dimensions of each field.
+    pub schema: Vec<FieldSchema>,
+    pub fields: Map<String, Value>,
+}
+
+impl Record {
+    pub fn get(&self, name: &str) -> Option<&Value> { self.fields.get(name) }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldSchema {
+    pub name: String,
+    /// The field's element type (e.g. `f32` or `utf8`).
+    #[serde(rename = "type")]
+    pub type_name: String,
+    pub dimensions: Vec<usize>,
 }
 
-pub(crate) fn parse_serial(data: &[u8]) -> Result<Vec<OutputTensor>, Error> {
+pub(crate) fn parse_serial(
+    data: &[u8],
+    pool: &mut BufferPool,
+) -> Result<Vec<OutputTensor>, Error> {
     if let Ok(s) = std::str::from_utf8(data) {
         log::trace!("Parsing serial output: {}", s);
     }
 
-    let deserialized: OneOrMany = serde_json::from_slice(data)
-        .context("Deserializing from JSON failed")?;
+    let deserialized: OneOrMany = if is_cbor(data) {
+        serde_cbor::from_slice(data)
+            .context("Deserializing from CBOR failed")?
+    } else {
+        serde_json::from_slice(data)
+            .context("Deserializing from JSON failed")?
+    };
 
     let values = match deserialized {
         OneOrMany::Many(many) => many,
@@ -29,28 +63,35 @@ pub(crate) fn parse_serial(data: &[u8]) -> Result<Vec<OutputTensor>, Error> {
     let mut outputs = Vec::new();
 
     for value in values {
-        let deserialized = deserialize_serial_tensor(value)?;
+        let deserialized = deserialize_serial_tensor(value, pool)?;
         outputs.push(deserialized);
     }
 
     Ok(outputs)
 }
 
+/// Serial messages are always a JSON object or array, so if the first byte is
+/// the start of a CBOR array or map we know it can't be JSON.
+fn is_cbor(data: &[u8]) -> bool { matches!(data.first(), Some(0x80..=0xbf)) }
+
 fn deserialize_serial_tensor(
     value: Map<String, Value>,
+    pool: &mut BufferPool,
 ) -> Result<OutputTensor, Error> {
     match value.get("type_name").and_then(|v| v.as_str()) {
         Some("utf8") => deserialize_strings(value),
-        Some("u8") => deserialize_numeric::<u8>(value),
-        Some("i8") => deserialize_numeric::<i8>(value),
-        Some("u16") => deserialize_numeric::<u16>(value),
-        Some("i16") => deserialize_numeric::<i16>(value),
-        Some("u32") => deserialize_numeric::<u32>(value),
-        Some("i32") => deserialize_numeric::<i32>(value),
-        Some("f32") => deserialize_numeric::<f32>(value),
-        Some("u64") => deserialize_numeric::<u64>(value),
-        Some("i64") => deserialize_numeric::<i64>(value),
-        Some("f64") => deserialize_numeric::<f64>(value),
+        Some("record") => deserialize_record(value),
+        Some("bool") => deserialize_bools(value, pool),
+        Some("u8") => deserialize_numeric::<u8>(value, pool),
+        Some("i8") => deserialize_numeric::<i8>(value, pool),
+        Some("u16") => deserialize_numeric::<u16>(value, pool),
+        Some("i16") => deserialize_numeric::<i16>(value, pool),
+        Some("u32") => deserialize_numeric::<u32>(value, pool),
+        Some("i32") => deserialize_numeric::<i32>(value, pool),
+        Some("f32") => deserialize_numeric::<f32>(value, pool),
+        Some("u64") => deserialize_numeric::<u64>(value, pool),
+        Some("i64") => deserialize_numeric::<i64>(value, pool),
+        Some("f64") => deserialize_numeric::<f64>(value, pool),
         Some(other) => anyhow::bail!("Unknown element type, {}", other),
         None => Err(Error::msg("The tensor didn't specify its element type")),
     }
@@ -77,8 +118,48 @@ fn deserialize_strings(
     })
 }
 
+/// A [`Tensor`] can only contain numbers, so booleans are passed to the host
+/// as `u8`s which are either `0` or `1`.
+fn deserialize_bools(
+    object: Map<String, Value>,
+    pool: &mut BufferPool,
+) -> Result<OutputTensor, Error> {
+    #[derive(Deserialize)]
+    struct BoolTensor {
+        dimensions: Vec<usize>,
+        elements: Vec<bool>,
+    }
+
+    let value = Value::Object(object);
+    let BoolTensor {
+        dimensions,
+        elements,
+    } = serde_json::from_value(value)?;
+    let elements: Vec<u8> = elements.into_iter().map(u8::from).collect();
+    let tensor = Tensor::new_pooled(&elements, &dimensions, pool);
+
+    Ok(tensor.into())
+}
+
+fn deserialize_record(
+    object: Map<String, Value>,
+) -> Result<OutputTensor, Error> {
+    let record: Record = serde_json::from_value(Value::Object(object))?;
+
+    for field in &record.schema {
+        anyhow::ensure!(
+            record.fields.contains_key(&field.name),
+            "The record doesn't contain a value for the \"{}\" field",
+            field.name
+        );
+    }
+
+    Ok(OutputTensor::Record(record))
+}
+
 fn deserialize_numeric<T>(
     object: Map<String, Value>,
+    pool: &mut BufferPool,
 ) -> Result<OutputTensor, Error>
 where
     T: TensorElement + DeserializeOwned,
@@ -94,7 +175,7 @@ where
         dimensions,
         elements,
     }: NumericTensor<T> = serde_json::from_value(value)?;
-    let tensor = Tensor::new(&elements, &dimensions);
+    let tensor = Tensor::new_pooled(&elements, &dimensions, pool);
 
     Ok(tensor.into())
 }
@@ -118,6 +199,13 @@ impl Serialize for OutputTensor {
             elements: &'a [String],
         }
 
+        #[derive(Serialize)]
+        struct SerializedRecord<'a> {
+            element_type: &'a str,
+            schema: &'a [FieldSchema],
+            fields: &'a Map<String, Value>,
+        }
+
         match self {
             OutputTensor::Tensor(t) => t.serializable().serialize(serializer),
             OutputTensor::StringTensor {
@@ -129,6 +217,14 @@ impl Serialize for OutputTensor {
                 elements: strings,
             }
             .serialize(serializer),
+            OutputTensor::Record(Record { schema, fields }) => {
+                SerializedRecord {
+                    element_type: "record",
+                    schema,
+                    fields,
+                }
+                .serialize(serializer)
+            },
         }
     }
 }
@@ -140,9 +236,214 @@ impl From<Tensor> for OutputTensor {
 pub(crate) fn parse_outputs(
     meta: &NodeMetadata,
     data: &[u8],
+    pool: &mut BufferPool,
 ) -> Result<Vec<OutputTensor>, Error> {
     match meta.kind.as_str() {
-        "SERIAL" => crate::outputs::parse_serial(data),
+        "SERIAL" | "MQTT" | "BLE" => {
+            crate::outputs::parse_serial(data, pool)
+        },
+        "TENSOR" => crate::outputs::parse_encoded(data, pool),
         _ => anyhow::bail!("Unknown output type"),
     }
 }
+
+/// Parse tensors written using the [`hotg_rune_core::encoding`] format.
+pub(crate) fn parse_encoded(
+    data: &[u8],
+    pool: &mut BufferPool,
+) -> Result<Vec<OutputTensor>, Error> {
+    let tensors = encoding::decode_all(data)
+        .context("Unable to decode the tensors")?;
+
+    tensors
+        .into_iter()
+        .map(|t| decode_tensor(t, pool))
+        .collect()
+}
+
+fn decode_tensor(
+    tensor: EncodedTensor<'_>,
+    pool: &mut BufferPool,
+) -> Result<OutputTensor, Error> {
+    let EncodedTensor { shape, data } = tensor;
+    let dimensions = shape
+        .fixed_dimensions()
+        .context("Decoded tensors always have known dimensions")?;
+
+    let element_type = match shape.element_type() {
+        hotg_rune_core::ElementType::String => {
+            let mut strings = Vec::new();
+            let mut rest = data;
+
+            while !rest.is_empty() {
+                let (s, r) = Cow::<'static, str>::read_le(rest)?;
+                strings.push(s.into_owned());
+                rest = r;
+            }
+
+            return Ok(OutputTensor::StringTensor {
+                dimensions,
+                strings,
+            });
+        },
+        // Booleans are already bytes that are either 0 or 1
+        hotg_rune_core::ElementType::Bool => ElementType::U8,
+        other => runtime_element_type(other).with_context(|| {
+            format!("The runtime doesn't support {} tensors", other)
+        })?,
+    };
+
+    let dimensions = dimensions
+        .into_iter()
+        .map(NonZeroUsize::new)
+        .collect::<Option<Vec<_>>>()
+        .context("All dimensions must be nonzero")?;
+
+    let mut buffer = pool.acquire(data.len());
+    buffer.copy_from_slice(data);
+    encoding::to_native_endian(shape.element_type(), &mut buffer);
+
+    let tensor = Tensor::new_raw(element_type, dimensions, buffer);
+    let tensor = match shape.quantization() {
+        Some(q) => tensor.with_quantization(q.clone()),
+        None => tensor,
+    };
+
+    Ok(tensor.into())
+}
+
+fn runtime_element_type(
+    element_type: hotg_rune_core::ElementType,
+) -> Option<ElementType> {
+    match element_type {
+        hotg_rune_core::ElementType::U8 => Some(ElementType::U8),
+        hotg_rune_core::ElementType::I8 => Some(ElementType::I8),
+        hotg_rune_core::ElementType::U16 => Some(ElementType::U16),
+        hotg_rune_core::ElementType::I16 => Some(ElementType::I16),
+        hotg_rune_core::ElementType::U32 => Some(ElementType::U32),
+        hotg_rune_core::ElementType::I32 => Some(ElementType::I32),
+        hotg_rune_core::ElementType::F32 => Some(ElementType::F32),
+        hotg_rune_core::ElementType::U64 => Some(ElementType::U64),
+        hotg_rune_core::ElementType::I64 => Some(ElementType::I64),
+        hotg_rune_core::ElementType::F64 => Some(ElementType::F64),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn record_message() -> Value {
+        json!({
+            "type_name": "record",
+            "channel": 1,
+            "schema": [
+                { "name": "label", "type": "utf8", "dimensions": [1] },
+                { "name": "confidence", "type": "f32", "dimensions": [1] },
+            ],
+            "fields": { "label": "cat", "confidence": 0.5 },
+        })
+    }
+
+    #[test]
+    fn parse_a_json_record() {
+        let data = serde_json::to_vec(&record_message()).unwrap();
+
+        let got = parse_serial(&data, &mut BufferPool::new()).unwrap();
+
+        match got.as_slice() {
+            [OutputTensor::Record(record)] => {
+                assert_eq!(record.schema[0].name, "label");
+                assert_eq!(record.schema[1].type_name, "f32");
+                assert_eq!(record.get("label"), Some(&json!("cat")));
+            },
+            other => panic!("Expected a record, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parse_a_cbor_record() {
+        let data = serde_cbor::to_vec(&record_message()).unwrap();
+        let json = serde_json::to_vec(&record_message()).unwrap();
+
+        let got = parse_serial(&data, &mut BufferPool::new()).unwrap();
+
+        assert_eq!(got, parse_serial(&json, &mut BufferPool::new()).unwrap());
+    }
+
+    #[test]
+    fn booleans_are_passed_along_as_bytes() {
+        let msg = json!({
+            "type_name": "bool",
+            "channel": 1,
+            "dimensions": [3],
+            "elements": [true, false, true],
+        });
+        let data = serde_json::to_vec(&msg).unwrap();
+
+        let got = parse_serial(&data, &mut BufferPool::new()).unwrap();
+
+        match got.as_slice() {
+            [OutputTensor::Tensor(t)] => {
+                assert_eq!(t.elements::<u8>().unwrap(), &[1, 0, 1]);
+            },
+            other => panic!("Expected a tensor, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn records_must_match_their_schema() {
+        let mut msg = record_message();
+        msg["fields"].as_object_mut().unwrap().remove("label");
+        let data = serde_json::to_vec(&msg).unwrap();
+
+        assert!(parse_serial(&data, &mut BufferPool::new()).is_err());
+    }
+
+    #[test]
+    fn parse_encoded_tensors() {
+        let floats = hotg_rune_core::Tensor::new_vector(vec![0.5_f32, -1.0]);
+        let labels: hotg_rune_core::Tensor<Cow<'static, str>> =
+            hotg_rune_core::Tensor::new_vector(vec!["cat".into()]);
+        let mut data = Vec::new();
+        encoding::encode_tensor(&floats, &mut data).unwrap();
+        encoding::encode_tensor(&labels, &mut data).unwrap();
+
+        let got = parse_encoded(&data, &mut BufferPool::new()).unwrap();
+
+        match got.as_slice() {
+            [OutputTensor::Tensor(t), OutputTensor::StringTensor {
+                dimensions,
+                strings,
+            }] => {
+                assert_eq!(t.elements::<f32>().unwrap(), &[0.5, -1.0]);
+                assert_eq!(dimensions, &[1]);
+                assert_eq!(strings, &["cat"]);
+            },
+            other => panic!("Expected two tensors, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn encoded_tensors_keep_their_quantization() {
+        let quantization = hotg_rune_core::Quantization::per_tensor(0.5, 128);
+        let tensor = hotg_rune_core::Tensor::new_vector(vec![128_u8, 130])
+            .with_quantization(quantization.clone())
+            .unwrap();
+        let mut data = Vec::new();
+        encoding::encode_tensor(&tensor, &mut data).unwrap();
+
+        let got = parse_encoded(&data, &mut BufferPool::new()).unwrap();
+
+        match got.as_slice() {
+            [OutputTensor::Tensor(t)] => {
+                assert_eq!(t.elements::<u8>().unwrap(), &[128, 130]);
+                assert_eq!(t.quantization(), Some(&quantization));
+            },
+            other => panic!("Expected a single tensor, found {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,149 @@
+//! Delivering capability data at the rate real hardware would produce it.
+
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+/// Figure out how long a sensor would take to capture the data requested by
+/// a capability, based on the arguments it was declared with.
+///
+/// This understands
+///
+/// - `sample_duration_ms` (e.g. the length of a `SOUND` clip)
+/// - `rate` or `hz` samples per second, with `samples` samples (defaulting to
+///   `1`)
+/// - `fps` frames per second, with `frames` frames (defaulting to `1`)
+///
+/// Capabilities without a sample rate (e.g. `IMAGE`) return `None`.
+pub(crate) fn capture_duration(
+    arguments: &HashMap<String, String>,
+) -> Option<Duration> {
+    let number = |name: &str| -> Option<f64> {
+        arguments
+            .get(name)
+            .and_then(|value| value.trim().parse().ok())
+            .filter(|&n: &f64| n.is_finite() && n > 0.0)
+    };
+
+    if let Some(ms) = number("sample_duration_ms") {
+        return Some(Duration::from_secs_f64(ms / 1000.0));
+    }
+
+    if let Some(rate) = number("rate").or_else(|| number("hz")) {
+        let samples = number("samples").unwrap_or(1.0);
+        return Some(Duration::from_secs_f64(samples / rate));
+    }
+
+    if let Some(fps) = number("fps") {
+        let frames = number("frames").unwrap_or(1.0);
+        return Some(Duration::from_secs_f64(frames / fps));
+    }
+
+    None
+}
+
+/// Keeps track of when each capability's next window of data would be ready.
+#[derive(Debug, Default)]
+pub(crate) struct Pacer {
+    deadlines: HashMap<u32, Instant>,
+}
+
+impl Pacer {
+    /// Block until the next window of data for a capability would be
+    /// available.
+    pub(crate) fn wait(&mut self, id: u32, period: Duration) {
+        let delay = self.delay(id, period, Instant::now());
+
+        if delay > Duration::ZERO {
+            log::trace!("Waiting {:?} for capability {}", delay, id);
+            std::thread::sleep(delay);
+        }
+    }
+
+    /// How long until the next window of data is ready, given the time
+    /// `now`.
+    ///
+    /// Windows are scheduled back-to-back, like a sensor that is constantly
+    /// sampling. If the Rune falls behind, the schedule restarts from `now`
+    /// rather than trying to catch up.
+    fn delay(&mut self, id: u32, period: Duration, now: Instant) -> Duration {
+        let deadline = match self.deadlines.get(&id) {
+            Some(&previous) => previous + period,
+            None => now + period,
+        };
+
+        if deadline < now {
+            log::debug!(
+                "Capability {} fell {:?} behind its sample rate",
+                id,
+                now - deadline
+            );
+            self.deadlines.insert(id, now);
+            return Duration::ZERO;
+        }
+
+        self.deadlines.insert(id, deadline);
+        deadline - now
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn durations_from_capability_arguments() {
+        let inputs = vec![
+            (args(&[("hz", "16000"), ("sample_duration_ms", "1000")]), 1.0),
+            (args(&[("rate", "100"), ("samples", "50")]), 0.5),
+            (args(&[("fps", "10"), ("frames", "5")]), 0.5),
+            (args(&[("hz", "4")]), 0.25),
+        ];
+
+        for (args, seconds) in inputs {
+            let got = capture_duration(&args).unwrap();
+            assert_eq!(got, Duration::from_secs_f64(seconds), "{:?}", args);
+        }
+    }
+
+    #[test]
+    fn capabilities_without_a_sample_rate_arent_paced() {
+        assert!(capture_duration(&args(&[("width", "224")])).is_none());
+        assert!(capture_duration(&args(&[("hz", "abc")])).is_none());
+    }
+
+    #[test]
+    fn windows_are_scheduled_back_to_back() {
+        let mut pacer = Pacer::default();
+        let period = Duration::from_millis(100);
+        let start = Instant::now();
+
+        assert_eq!(pacer.delay(1, period, start), period);
+        // The Rune took 30ms to process the first window
+        let now = start + period + Duration::from_millis(30);
+        assert_eq!(pacer.delay(1, period, now), Duration::from_millis(70));
+        // Other capabilities have their own schedule
+        assert_eq!(pacer.delay(2, period, now), period);
+    }
+
+    #[test]
+    fn falling_behind_restarts_the_schedule() {
+        let mut pacer = Pacer::default();
+        let period = Duration::from_millis(100);
+        let start = Instant::now();
+
+        pacer.delay(1, period, start);
+        let late = start + Duration::from_secs(1);
+
+        assert_eq!(pacer.delay(1, period, late), Duration::ZERO);
+        assert_eq!(pacer.delay(1, period, late), period);
+    }
+}
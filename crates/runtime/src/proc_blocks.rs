@@ -0,0 +1,338 @@
+//! Support for proc blocks which are compiled to standalone WebAssembly
+//! modules and provided by the host (i.e. `location: host` in the Runefile).
+//!
+//! See `hotg_rune_proc_blocks::standalone` for the functions these modules
+//! are expected to export.
+//!
+//! The modules are run using wasmer regardless of which engine is running
+//! the Rune, so a Rune with `location: host` proc blocks needs the runtime to
+//! be compiled with the `wasmer` feature, or a [`Callbacks`] implementation
+//! which overrides [`Callbacks::load_proc_block()`].
+//!
+//! [`Callbacks`]: crate::callbacks::Callbacks
+//! [`Callbacks::load_proc_block()`]: crate::callbacks::Callbacks::load_proc_block
+
+use anyhow::Error;
+
+#[cfg(feature = "wasmer")]
+pub use self::wasmer_proc_block::WasmerProcBlock;
+use crate::callbacks::ProcBlock;
+
+/// A proc block handler which will try to instantiate a standalone proc block
+/// using whichever WebAssembly engines are enabled.
+///
+/// Supported engines are:
+/// - wasmer
+#[cfg_attr(not(feature = "wasmer"), doc("(not supported)"))]
+pub fn default_proc_block_handler(
+    _id: u32,
+    name: &str,
+    wasm: &[u8],
+) -> Result<Box<dyn ProcBlock>, Error> {
+    #[cfg(feature = "wasmer")]
+    {
+        let proc_block = WasmerProcBlock::load(wasm).map_err(|e| {
+            e.context(format!("Unable to load the \"{}\" proc block", name))
+        })?;
+        Ok(Box::new(proc_block))
+    }
+
+    #[cfg(not(feature = "wasmer"))]
+    {
+        let _ = wasm;
+        anyhow::bail!(
+            "Unable to load the \"{}\" proc block because the runtime was \
+             compiled without the \"wasmer\" feature",
+            name
+        )
+    }
+}
+
+#[cfg(feature = "wasmer")]
+mod wasmer_proc_block {
+    use anyhow::{Context, Error};
+    use wasmer::{Instance, Memory, Module, NativeFunc, Store};
+
+    use crate::callbacks::ProcBlock;
+
+    /// A standalone proc block running inside its own wasmer instance.
+    pub struct WasmerProcBlock {
+        memory: Memory,
+        alloc: NativeFunc<u32, u32>,
+        free: NativeFunc<(u32, u32), ()>,
+        set_parameter: NativeFunc<(u32, u32, u32, u32), i32>,
+        transform: NativeFunc<(u32, u32), i32>,
+        /// Modules compiled before the reset hook was added won't export
+        /// this.
+        reset: Option<NativeFunc<(), ()>>,
+        result_ptr: NativeFunc<(), u32>,
+        result_len: NativeFunc<(), u32>,
+    }
+
+    impl WasmerProcBlock {
+        pub fn load(wasm: &[u8]) -> Result<Self, Error> {
+            let store = Store::default();
+            let module = Module::from_binary(&store, wasm)
+                .context("Unable to compile the WebAssembly module")?;
+            let instance = Instance::new(&module, &wasmer::imports! {})
+                .context("Unable to instantiate the WebAssembly module")?;
+
+            let exports = &instance.exports;
+            let missing =
+                |name: &str| format!("The module doesn't export \"{}\"", name);
+
+            Ok(WasmerProcBlock {
+                memory: exports
+                    .get_memory("memory")
+                    .with_context(|| missing("memory"))?
+                    .clone(),
+                alloc: exports
+                    .get_native_function("rune_proc_block_alloc")
+                    .with_context(|| missing("rune_proc_block_alloc"))?,
+                free: exports
+                    .get_native_function("rune_proc_block_free")
+                    .with_context(|| missing("rune_proc_block_free"))?,
+                set_parameter: exports
+                    .get_native_function("rune_proc_block_set_parameter")
+                    .with_context(|| {
+                        missing("rune_proc_block_set_parameter")
+                    })?,
+                transform: exports
+                    .get_native_function("rune_proc_block_transform")
+                    .with_context(|| missing("rune_proc_block_transform"))?,
+                reset: exports
+                    .get_native_function("rune_proc_block_reset")
+                    .ok(),
+                result_ptr: exports
+                    .get_native_function("rune_proc_block_result_ptr")
+                    .with_context(|| missing("rune_proc_block_result_ptr"))?,
+                result_len: exports
+                    .get_native_function("rune_proc_block_result_len")
+                    .with_context(|| missing("rune_proc_block_result_len"))?,
+            })
+        }
+
+        /// Copy some bytes into a buffer allocated by the guest.
+        fn write(&self, data: &[u8]) -> Result<(u32, u32), Error> {
+            let len = data.len() as u32;
+            let ptr = self.alloc.call(len)?;
+
+            // Safety: the slice is dropped before we execute any more
+            // WebAssembly.
+            unsafe {
+                self.memory_mut(ptr, len)?.copy_from_slice(data);
+            }
+
+            Ok((ptr, len))
+        }
+
+        /// Read the result from the last call, returning an error if it
+        /// failed.
+        fn result(&self, status: i32) -> Result<Vec<u8>, Error> {
+            let ptr = self.result_ptr.call()?;
+            let len = self.result_len.call()?;
+
+            // Safety: the slice is copied before we execute any more
+            // WebAssembly.
+            let result = unsafe { self.memory_mut(ptr, len)?.to_vec() };
+
+            if status == 0 {
+                Ok(result)
+            } else {
+                Err(Error::msg(String::from_utf8_lossy(&result).into_owned()))
+            }
+        }
+
+        /// # Safety
+        ///
+        /// The returned slice must be dropped before any WebAssembly code is
+        /// executed.
+        unsafe fn memory_mut(
+            &self,
+            ptr: u32,
+            len: u32,
+        ) -> Result<&mut [u8], Error> {
+            let linear_memory = self.memory.data_unchecked_mut();
+            let start = ptr as usize;
+            let end = start + len as usize;
+            let memory_len = linear_memory.len();
+
+            linear_memory.get_mut(start..end).with_context(|| {
+                format!(
+                    "Range {}..{} lies outside of linear memory ({} bytes)",
+                    start, end, memory_len
+                )
+            })
+        }
+    }
+
+    impl ProcBlock for WasmerProcBlock {
+        fn set_parameter(
+            &mut self,
+            key: &str,
+            value: &str,
+        ) -> Result<(), Error> {
+            let (key_ptr, key_len) = self.write(key.as_bytes())?;
+            let (value_ptr, value_len) = self.write(value.as_bytes())?;
+
+            let status = self
+                .set_parameter
+                .call(key_ptr, key_len, value_ptr, value_len)?;
+
+            self.free.call(key_ptr, key_len)?;
+            self.free.call(value_ptr, value_len)?;
+
+            self.result(status).map(|_| ())
+        }
+
+        fn transform(&mut self, inputs: &[u8]) -> Result<Vec<u8>, Error> {
+            let (ptr, len) = self.write(inputs)?;
+            let status = self.transform.call(ptr, len)?;
+            self.free.call(ptr, len)?;
+
+            self.result(status)
+        }
+
+        fn reset(&mut self) -> Result<(), Error> {
+            if let Some(reset) = &self.reset {
+                reset.call()?;
+            }
+
+            Ok(())
+        }
+    }
+}
+
+#[cfg(all(test, feature = "wasmer"))]
+pub(crate) mod tests {
+    use super::*;
+
+    /// A hand-written proc block which appends its `suffix` parameter and
+    /// the number of transforms since it was last reset to its inputs.
+    pub(crate) const ECHO: &str = r#"
+        (module
+            (memory (export "memory") 1)
+            (data (i32.const 0) "Unknown parameter")
+            (data (i32.const 32) "suffix")
+            ;; the suffix is stored at 64
+            (global $suffix_len (mut i32) (i32.const 0))
+            (global $calls (mut i32) (i32.const 0))
+            (global $heap (mut i32) (i32.const 1024))
+            (global $result_ptr (mut i32) (i32.const 0))
+            (global $result_len (mut i32) (i32.const 0))
+
+            (func $copy (param $dest i32) (param $src i32) (param $len i32)
+                (block $done
+                    (loop $next
+                        (br_if $done (i32.eqz (local.get $len)))
+                        (i32.store8 (local.get $dest)
+                            (i32.load8_u (local.get $src)))
+                        (local.set $dest
+                            (i32.add (local.get $dest) (i32.const 1)))
+                        (local.set $src
+                            (i32.add (local.get $src) (i32.const 1)))
+                        (local.set $len
+                            (i32.sub (local.get $len) (i32.const 1)))
+                        (br $next))))
+
+            (func $alloc (export "rune_proc_block_alloc")
+                (param $len i32) (result i32)
+                (local $ptr i32)
+                (local.set $ptr (global.get $heap))
+                (global.set $heap
+                    (i32.add (global.get $heap) (local.get $len)))
+                (local.get $ptr))
+
+            (func (export "rune_proc_block_free") (param i32 i32))
+
+            (func (export "rune_proc_block_set_parameter")
+                (param $key i32) (param $key_len i32)
+                (param $value i32) (param $value_len i32)
+                (result i32)
+                (if (i32.or
+                        (i32.ne (local.get $key_len) (i32.const 6))
+                        (i32.or
+                            (i32.ne (i32.load (local.get $key))
+                                (i32.load (i32.const 32)))
+                            (i32.ne (i32.load16_u offset=4 (local.get $key))
+                                (i32.load16_u (i32.const 36)))))
+                    (then
+                        (global.set $result_ptr (i32.const 0))
+                        (global.set $result_len (i32.const 17))
+                        (return (i32.const 1))))
+                (call $copy (i32.const 64) (local.get $value)
+                    (local.get $value_len))
+                (global.set $suffix_len (local.get $value_len))
+                (global.set $result_len (i32.const 0))
+                (i32.const 0))
+
+            (func (export "rune_proc_block_transform")
+                (param $inputs i32) (param $len i32) (result i32)
+                (local $out i32)
+                (global.set $calls (i32.add (global.get $calls) (i32.const 1)))
+                (global.set $result_len
+                    (i32.add
+                        (i32.add (local.get $len) (global.get $suffix_len))
+                        (i32.const 1)))
+                (local.set $out (call $alloc (global.get $result_len)))
+                (call $copy (local.get $out) (local.get $inputs)
+                    (local.get $len))
+                (call $copy (i32.add (local.get $out) (local.get $len))
+                    (i32.const 64) (global.get $suffix_len))
+                (i32.store8
+                    (i32.sub
+                        (i32.add (local.get $out) (global.get $result_len))
+                        (i32.const 1))
+                    (global.get $calls))
+                (global.set $result_ptr (local.get $out))
+                (i32.const 0))
+
+            (func (export "rune_proc_block_reset")
+                (global.set $calls (i32.const 0)))
+
+            (func (export "rune_proc_block_result_ptr") (result i32)
+                (global.get $result_ptr))
+
+            (func (export "rune_proc_block_result_len") (result i32)
+                (global.get $result_len)))
+    "#;
+
+    #[test]
+    fn run_a_standalone_proc_block() {
+        let wasm = wat::parse_str(ECHO).unwrap();
+        let mut proc_block =
+            default_proc_block_handler(0, "echo", &wasm).unwrap();
+
+        proc_block.set_parameter("suffix", "!").unwrap();
+        assert_eq!(proc_block.transform(b"hi").unwrap(), b"hi!\x01");
+        assert_eq!(proc_block.transform(b"").unwrap(), b"!\x02");
+
+        proc_block.reset().unwrap();
+        assert_eq!(proc_block.transform(b"hi").unwrap(), b"hi!\x01");
+    }
+
+    #[test]
+    fn errors_from_the_proc_block_are_returned() {
+        let wasm = wat::parse_str(ECHO).unwrap();
+        let mut proc_block =
+            default_proc_block_handler(0, "echo", &wasm).unwrap();
+
+        let err = proc_block.set_parameter("prefix", "?").unwrap_err();
+
+        assert_eq!(err.to_string(), "Unknown parameter");
+    }
+
+    #[test]
+    fn modules_must_export_the_proc_block_functions() {
+        let wasm =
+            wat::parse_str(r#"(module (memory (export "memory") 1))"#).unwrap();
+
+        let err = default_proc_block_handler(0, "empty", &wasm).err().unwrap();
+
+        assert_eq!(err.to_string(), "Unable to load the \"empty\" proc block");
+        assert_eq!(
+            err.chain().nth(1).unwrap().to_string(),
+            "The module doesn't export \"rune_proc_block_alloc\""
+        );
+    }
+}
@@ -38,23 +38,38 @@
 //! call a method on the [`Runtime`] which then asks the Rune for a reference to
 //! the tensor's buffer.
 
-use std::{cell::UnsafeCell, collections::HashMap, sync::Arc};
+use std::{
+    cell::UnsafeCell,
+    collections::HashMap,
+    fs::File,
+    io::{Cursor, Read, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use anyhow::{Context, Error};
-use log::Record;
+use log::{LevelFilter, Record};
 use wasmparser::{Parser, Payload};
 
 use crate::{
-    callbacks::{Callbacks, Model, ModelMetadata, RuneGraph},
+    abi,
+    callbacks::{Callbacks, CapabilityStream, Model, ModelMetadata, RuneGraph},
     engine::{LoadError, WebAssemblyEngine},
+    files::FileMode,
+    metrics::{Metrics, ModelBenchmark, Stage},
     outputs::{parse_outputs, OutputTensor},
-    NodeMetadata, Tensor,
+    pacing::{self, Pacer},
+    trace::{self, CapabilityTrace},
+    BufferPool, CancellationToken, Conversion, ElementType, NodeMetadata,
+    Tensor,
 };
 
 /// A loaded Rune.
 pub struct Runtime {
     state: Arc<State>,
     engine: Box<dyn WebAssemblyEngine>,
+    abi_version: u32,
 }
 
 impl Runtime {
@@ -74,6 +89,7 @@ impl Runtime {
     where
         E: WebAssemblyEngine + 'static,
     {
+        let abi_version = abi::negotiate(rune)?;
         let state = State::with_embedded_resources(rune);
         let state = Arc::new(state);
         let callbacks = Arc::clone(&state) as Arc<dyn Callbacks>;
@@ -84,15 +100,77 @@ impl Runtime {
         Ok(Runtime {
             state,
             engine: Box::new(engine),
+            abi_version,
         })
     }
 }
 
 impl Runtime {
     /// Run the Rune.
-    pub fn predict(&mut self) -> Result<(), Error> { self.engine.predict() }
+    ///
+    /// If the call is aborted using the [`Runtime::cancellation_token()`],
+    /// this will return a [`crate::Cancelled`] error and
+    /// [`Runtime::metrics()`] will contain everything recorded up to that
+    /// point.
+    pub fn predict(&mut self) -> Result<(), Error> {
+        unsafe {
+            *self.state.metrics() = Metrics::default();
+        }
+
+        let start = Instant::now();
+        let result = self.engine.predict();
+        // Note: the token is reset afterwards so cancelling just before the
+        // call starts still stops it
+        self.state.cancellation.reset();
+
+        unsafe {
+            self.state.metrics().total = start.elapsed();
+        }
+
+        let return_code = result?;
+
+        if self.abi_version != abi::LEGACY_ABI_VERSION {
+            anyhow::ensure!(
+                return_code == 0,
+                "The Rune failed with an error code of {}",
+                return_code
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Clear any state held by the Rune's proc blocks (e.g. rolling windows)
+    /// so the next [`Runtime::predict()`] behaves as if it were the first.
+    ///
+    /// This is useful when evaluating a dataset, where each item should be
+    /// processed independently.
+    pub fn reset(&mut self) -> Result<(), Error> {
+        self.engine.reset().context(
+            "Unable to reset the Rune. It may have been compiled by an older \
+             version of Rune, so try rebuilding it",
+        )
+    }
+
+    /// The version of the Rune ABI this Rune was compiled against.
+    pub fn abi_version(&self) -> u32 { self.abi_version }
+
+    /// Timing information from the most recent [`Runtime::predict()`] call.
+    pub fn metrics(&self) -> &Metrics { unsafe { self.state.metrics() } }
+
+    /// Get a token which can be used to cancel an in-flight
+    /// [`Runtime::predict()`] call from another thread.
+    ///
+    /// The token is reset at the start of every call.
+    pub fn cancellation_token(&self) -> CancellationToken {
+        self.state.cancellation.clone()
+    }
 
     /// Get all input tensors, keyed by capability ID.
+    ///
+    /// If a capability sets the `"convert_to"` argument, its tensor will be
+    /// converted to that element type (see [`Conversion`]) before being
+    /// given to the Rune.
     pub fn input_tensors(&mut self) -> &mut HashMap<u32, Tensor> {
         unsafe { self.state.input_tensors() }
     }
@@ -120,6 +198,62 @@ impl Runtime {
         unsafe { self.state.set_model_handler(load_model) }
     }
 
+    /// Use a callback to decrypt models which were embedded in the Rune in
+    /// encrypted form.
+    ///
+    /// The callback is given the model's name and its encrypted bytes.
+    /// Encrypted models are only decrypted when they are first used, so this
+    /// can be set any time before the first call to [`Runtime::predict()`]
+    /// or [`Runtime::warm_up()`].
+    pub fn set_model_decryptor<F>(&mut self, decrypt_model: F)
+    where
+        F: Fn(&str, &[u8]) -> Result<Vec<u8>, Error>,
+        F: Send + Sync + 'static,
+    {
+        unsafe { self.state.set_model_decryptor(decrypt_model) }
+    }
+
+    /// Use a callback to provide the data for each capability instead of
+    /// reading from [`Runtime::input_tensors()`].
+    ///
+    /// The buffer points directly into the Rune's memory, so wrapping it in a
+    /// [`crate::TensorViewMut`] lets the callback write elements in place.
+    pub fn set_capability_handler<F>(&mut self, read_capability: F)
+    where
+        F: Fn(u32, &NodeMetadata, &mut [u8]) -> Result<usize, Error>,
+        F: Send + Sync + 'static,
+    {
+        unsafe { self.state.set_capability_handler(read_capability) }
+    }
+
+    /// Use a callback to open the streams a Rune can pull capability data
+    /// from incrementally.
+    ///
+    /// Without a handler, a stream will read the capability's tensor from
+    /// [`Runtime::input_tensors()`] and then finish.
+    pub fn set_capability_stream_handler<F>(&mut self, open_stream: F)
+    where
+        F: Fn(u32, &NodeMetadata) -> Result<CapabilityStream, Error>,
+        F: Send + Sync + 'static,
+    {
+        unsafe { self.state.set_capability_stream_handler(open_stream) }
+    }
+
+    /// Use a callback to handle the data written to each output instead of
+    /// saving it to [`Runtime::output_tensors()`].
+    ///
+    /// The data is borrowed directly from the Rune's memory. The elements of
+    /// a `TENSOR` output can be read without copying by splitting it up with
+    /// [`hotg_rune_core::encoding::decode_all()`] and wrapping each tensor's
+    /// data in a [`crate::TensorView`].
+    pub fn set_output_handler<F>(&mut self, write_output: F)
+    where
+        F: Fn(u32, &NodeMetadata, &[u8]) -> Result<(), Error>,
+        F: Send + Sync + 'static,
+    {
+        unsafe { self.state.set_output_handler(write_output) }
+    }
+
     pub fn set_logger<L>(&mut self, log: L)
     where
         L: Fn(&Record<'_>),
@@ -128,11 +262,126 @@ impl Runtime {
         unsafe { self.state.set_logger(log) }
     }
 
+    /// Ignore any log records from the Rune which are more verbose than
+    /// `level`.
+    pub fn set_max_log_level(&mut self, level: LevelFilter) {
+        unsafe { self.state.set_max_log_level(level) }
+    }
+
     pub fn resources(&mut self) -> &mut HashMap<String, Vec<u8>> {
         unsafe { self.state.resources() }
     }
+
+    /// Model files for any models the Rune expects the host to provide,
+    /// keyed by the model's name.
+    ///
+    /// Host-side models are only loaded when they are first used, so these
+    /// can be provided any time before the first call to
+    /// [`Runtime::predict()`] or [`Runtime::warm_up()`].
+    pub fn models(&mut self) -> &mut HashMap<String, Vec<u8>> {
+        unsafe { self.state.models() }
+    }
+
+    /// WebAssembly modules for any proc blocks the Rune expects the host to
+    /// provide, keyed by the proc block's name.
+    ///
+    /// Like host-side models, these are only loaded when they are first used.
+    pub fn proc_blocks(&mut self) -> &mut HashMap<String, Vec<u8>> {
+        unsafe { self.state.proc_blocks() }
+    }
+
+    /// Let the Rune open a file on the host's filesystem.
+    ///
+    /// Runes may only open files which have been explicitly allowed and the
+    /// path they ask for must match `path` exactly.
+    pub fn allow_file(&mut self, path: impl Into<PathBuf>, mode: FileMode) {
+        unsafe {
+            self.state.allowed_files().insert(path.into(), mode);
+        }
+    }
+
+    /// The pool used for recycling tensor buffers.
+    ///
+    /// The runtime uses it for output tensors and for capability data which
+    /// needs converting to another element type. Callers can use
+    /// [`BufferPool::acquire()`] when creating new input tensors and give old
+    /// tensors back with [`BufferPool::release()`] to avoid allocating on
+    /// every call.
+    ///
+    /// Models never need a buffer because they read their inputs from and
+    /// write their outputs to the Rune's memory.
+    pub fn buffer_pool(&mut self) -> &mut BufferPool {
+        unsafe { self.state.buffer_pool() }
+    }
+
+    /// Record every byte delivered to the Rune by its capabilities, so the
+    /// run can be reproduced later with [`Runtime::replay_capabilities()`].
+    pub fn record_capabilities<W>(&mut self, trace: W)
+    where
+        W: Write + Send + 'static,
+    {
+        unsafe {
+            self.state
+                .set_capability_trace(CapabilityTrace::Record(Box::new(trace)))
+        }
+    }
+
+    /// Feed capability data from a trace created by
+    /// [`Runtime::record_capabilities()`] to the Rune instead of using
+    /// [`Runtime::input_tensors()`].
+    pub fn replay_capabilities<R>(&mut self, trace: R)
+    where
+        R: Read + Send + 'static,
+    {
+        unsafe {
+            self.state
+                .set_capability_trace(CapabilityTrace::Replay(Box::new(trace)))
+        }
+    }
+
+    /// Deliver capability data at the sample rate each capability was
+    /// declared with (e.g. a 1 second `SOUND` clip is only handed over once
+    /// per second), simulating real hardware instead of running as fast as
+    /// possible.
+    ///
+    /// This helps latency and windowing bugs show up on the desktop.
+    pub fn pace_capabilities(&mut self, enabled: bool) {
+        unsafe {
+            *self.state.pacer.get() =
+                if enabled { Some(Pacer::default()) } else { None };
+        }
+    }
+
+    /// Run each of the Rune's models once with zeroed inputs so their
+    /// one-off setup costs aren't paid by the first call to
+    /// [`Runtime::predict()`].
+    ///
+    /// Models are loaded once and reused for every prediction, so this only
+    /// needs to be called after the Rune is loaded.
+    pub fn warm_up(&mut self) -> Result<(), Error> { self.engine.warm_up() }
+
+    /// Run each of the Rune's models `iterations` times with zeroed inputs,
+    /// measuring their latency and peak memory usage.
+    ///
+    /// This calls the models directly and bypasses the rest of the pipeline.
+    pub fn benchmark_models(
+        &mut self,
+        iterations: usize,
+    ) -> Result<Vec<ModelBenchmark>, Error> {
+        self.engine.benchmark_models(iterations)
+    }
 }
 
+type CapabilityHandler = dyn Fn(u32, &NodeMetadata, &mut [u8]) -> Result<usize, Error>
+    + Send
+    + Sync;
+type CapabilityStreamHandler =
+    dyn Fn(u32, &NodeMetadata) -> Result<CapabilityStream, Error> + Send + Sync;
+type OutputHandler =
+    dyn Fn(u32, &NodeMetadata, &[u8]) -> Result<(), Error> + Send + Sync;
+type DecryptHandler =
+    dyn Fn(&str, &[u8]) -> Result<Vec<u8>, Error> + Send + Sync;
+
 /// State that is shared between the Runtime and the Rune.
 struct State {
     input_tensors: UnsafeCell<HashMap<u32, Tensor>>,
@@ -150,8 +399,24 @@ struct State {
                 + Send,
         >,
     >,
+    read_capability: UnsafeCell<Option<Box<CapabilityHandler>>>,
+    open_capability_stream: UnsafeCell<Option<Box<CapabilityStreamHandler>>>,
+    write_output: UnsafeCell<Option<Box<OutputHandler>>>,
+    decrypt_model: UnsafeCell<Option<Box<DecryptHandler>>>,
     log: UnsafeCell<Box<dyn Fn(&Record<'_>) + Send + Sync>>,
+    max_log_level: UnsafeCell<LevelFilter>,
     resources: UnsafeCell<HashMap<String, Vec<u8>>>,
+    models: UnsafeCell<HashMap<String, Vec<u8>>>,
+    proc_blocks: UnsafeCell<HashMap<String, Vec<u8>>>,
+    allowed_files: UnsafeCell<HashMap<PathBuf, FileMode>>,
+    metrics: UnsafeCell<Metrics>,
+    capability_trace: UnsafeCell<Option<CapabilityTrace>>,
+    buffer_pool: UnsafeCell<BufferPool>,
+    pacer: UnsafeCell<Option<Pacer>>,
+    cancellation: CancellationToken,
+    /// When the Rune was loaded, used as the reference point for the Rune's
+    /// monotonic clock.
+    started: Instant,
 }
 
 impl State {
@@ -179,6 +444,92 @@ impl State {
         s
     }
 
+    fn read_live_capability(
+        &self,
+        id: u32,
+        meta: &NodeMetadata,
+        buffer: &mut [u8],
+    ) -> Result<usize, Error> {
+        // Safety: see the safety comments on State
+        let read_capability = unsafe { &*self.read_capability.get() };
+
+        if let Some(read_capability) = read_capability {
+            return read_capability(id, meta, buffer);
+        }
+
+        // Safety: see the safety comments on State
+        let inputs = unsafe { &*self.input_tensors.get() };
+        let tensor = inputs.get(&id).with_context(|| {
+            format!(
+                "No input tensor provided for the \"{}\" capability with ID {}",
+                meta.kind, id
+            )
+        })?;
+
+        // Safety: see the safety comments on State
+        let pool = unsafe { &mut *self.buffer_pool.get() };
+
+        let converted = match requested_conversion(meta)? {
+            Some((element_type, conversion))
+                if element_type != tensor.element_type() =>
+            {
+                // Write the converted elements straight into the Rune's
+                // memory. If that isn't possible (e.g. the buffer is
+                // misaligned) we fall back to converting into a pooled buffer
+                // and copying it across, which also gives better errors.
+                if crate::convert_into_buffer(
+                    tensor,
+                    element_type,
+                    conversion,
+                    buffer,
+                )
+                .is_ok()
+                {
+                    return Ok(buffer.len());
+                }
+
+                let converted = crate::convert_pooled(
+                    tensor,
+                    element_type,
+                    conversion,
+                    pool,
+                )
+                .with_context(|| {
+                    format!(
+                        "Unable to convert the \"{}\" capability's {} tensor",
+                        meta.kind,
+                        tensor.shape()
+                    )
+                })?;
+                Some(converted)
+            },
+            _ => None,
+        };
+        let tensor = converted.as_ref().unwrap_or(tensor);
+
+        let src = tensor.buffer();
+        let result = if src.len() == buffer.len() {
+            buffer.copy_from_slice(src);
+            Ok(src.len())
+        } else {
+            Err(anyhow::anyhow!(
+                "The Rune provided a {} byte buffer, but the input tensor is \
+                 {} ({} bytes)",
+                buffer.len(),
+                tensor.shape(),
+                src.len(),
+            ))
+        };
+
+        // the converted tensor has been copied into the Rune, so its buffer
+        // can be used next time
+        if let Some(converted) = converted {
+            pool.release(converted.into_buffer());
+        }
+
+        result
+    }
+
     unsafe fn outputs(&self) -> &HashMap<u32, NodeMetadata> {
         &*self.outputs.get()
     }
@@ -199,6 +550,24 @@ impl State {
         &mut *self.resources.get()
     }
 
+    unsafe fn models(&self) -> &mut HashMap<String, Vec<u8>> {
+        &mut *self.models.get()
+    }
+
+    unsafe fn proc_blocks(&self) -> &mut HashMap<String, Vec<u8>> {
+        &mut *self.proc_blocks.get()
+    }
+
+    unsafe fn allowed_files(&self) -> &mut HashMap<PathBuf, FileMode> {
+        &mut *self.allowed_files.get()
+    }
+
+    unsafe fn metrics(&self) -> &mut Metrics { &mut *self.metrics.get() }
+
+    unsafe fn buffer_pool(&self) -> &mut BufferPool {
+        &mut *self.buffer_pool.get()
+    }
+
     unsafe fn set_logger<L>(&self, log: L)
     where
         L: Fn(&Record<'_>),
@@ -207,6 +576,46 @@ impl State {
         *self.log.get() = Box::new(log);
     }
 
+    unsafe fn set_max_log_level(&self, level: LevelFilter) {
+        *self.max_log_level.get() = level;
+    }
+
+    unsafe fn set_capability_handler<F>(&self, read_capability: F)
+    where
+        F: Fn(u32, &NodeMetadata, &mut [u8]) -> Result<usize, Error>,
+        F: Send + Sync + 'static,
+    {
+        *self.read_capability.get() = Some(Box::new(read_capability));
+    }
+
+    unsafe fn set_capability_stream_handler<F>(&self, open_stream: F)
+    where
+        F: Fn(u32, &NodeMetadata) -> Result<CapabilityStream, Error>,
+        F: Send + Sync + 'static,
+    {
+        *self.open_capability_stream.get() = Some(Box::new(open_stream));
+    }
+
+    unsafe fn set_output_handler<F>(&self, write_output: F)
+    where
+        F: Fn(u32, &NodeMetadata, &[u8]) -> Result<(), Error>,
+        F: Send + Sync + 'static,
+    {
+        *self.write_output.get() = Some(Box::new(write_output));
+    }
+
+    unsafe fn set_model_decryptor<F>(&self, decrypt_model: F)
+    where
+        F: Fn(&str, &[u8]) -> Result<Vec<u8>, Error>,
+        F: Send + Sync + 'static,
+    {
+        *self.decrypt_model.get() = Some(Box::new(decrypt_model));
+    }
+
+    unsafe fn set_capability_trace(&self, trace: CapabilityTrace) {
+        *self.capability_trace.get() = Some(trace);
+    }
+
     unsafe fn set_model_handler<F>(&self, load_model: F)
     where
         F: Fn(u32, &ModelMetadata<'_>, &[u8]) -> Result<Box<dyn Model>, Error>,
@@ -226,8 +635,22 @@ impl Default for State {
             load_model: UnsafeCell::new(Box::new(
                 crate::models::default_model_handler,
             )),
+            read_capability: UnsafeCell::new(None),
+            open_capability_stream: UnsafeCell::new(None),
+            write_output: UnsafeCell::new(None),
+            decrypt_model: UnsafeCell::new(None),
             log: UnsafeCell::new(Box::new(|_| {})),
+            max_log_level: UnsafeCell::new(LevelFilter::Trace),
             resources: UnsafeCell::default(),
+            models: UnsafeCell::default(),
+            proc_blocks: UnsafeCell::default(),
+            allowed_files: UnsafeCell::default(),
+            metrics: UnsafeCell::default(),
+            capability_trace: UnsafeCell::new(None),
+            buffer_pool: UnsafeCell::default(),
+            pacer: UnsafeCell::new(None),
+            cancellation: CancellationToken::new(),
+            started: Instant::now(),
         }
     }
 }
@@ -252,6 +675,52 @@ impl Callbacks for State {
         meta: &NodeMetadata,
         buffer: &mut [u8],
     ) -> Result<usize, Error> {
+        // Safety: see the safety comments on State
+        let pacer = unsafe { &mut *self.pacer.get() };
+
+        if let Some(pacer) = pacer {
+            if let Some(period) = pacing::capture_duration(&meta.arguments) {
+                pacer.wait(id, period);
+            }
+        }
+
+        // Safety: see the safety comments on State
+        let capability_trace = unsafe { &mut *self.capability_trace.get() };
+
+        match capability_trace {
+            Some(CapabilityTrace::Replay(reader)) => {
+                replay_capability(&mut **reader, id, meta, buffer)
+            },
+            Some(CapabilityTrace::Record(writer)) => {
+                let bytes_read = self.read_live_capability(id, meta, buffer)?;
+                trace::write_frame(&mut **writer, id, &buffer[..bytes_read])
+                    .context("Unable to record the capability's data")?;
+                Ok(bytes_read)
+            },
+            None => self.read_live_capability(id, meta, buffer),
+        }
+    }
+
+    fn open_capability_stream(
+        &self,
+        id: u32,
+        meta: &NodeMetadata,
+    ) -> Result<CapabilityStream, Error> {
+        // Safety: see the safety comments on State
+        let open_stream = unsafe { &*self.open_capability_stream.get() };
+
+        // Safety: see the safety comments on State
+        let capability_trace = unsafe { &*self.capability_trace.get() };
+
+        if let Some(CapabilityTrace::Replay(_)) = capability_trace {
+            // the data will come from the trace instead
+            return Ok(Box::new(std::io::empty()));
+        }
+
+        if let Some(open_stream) = open_stream {
+            return open_stream(id, meta);
+        }
+
         // Safety: see the safety comments on State
         let inputs = unsafe { &*self.input_tensors.get() };
         let tensor = inputs.get(&id).with_context(|| {
@@ -261,21 +730,31 @@ impl Callbacks for State {
             )
         })?;
 
-        let src = tensor.buffer();
-
-        if src.len() != buffer.len() {
-            anyhow::bail!(
-                "The Rune provided a {} byte buffer, but the input tensor is \
-                 {} ({} bytes)",
-                buffer.len(),
-                tensor.shape(),
-                src.len(),
-            );
-        }
+        Ok(Box::new(Cursor::new(tensor.buffer().to_vec())))
+    }
 
-        buffer.copy_from_slice(src);
+    fn read_capability_stream(
+        &self,
+        id: u32,
+        meta: &NodeMetadata,
+        stream: &mut CapabilityStream,
+        buffer: &mut [u8],
+    ) -> Result<usize, Error> {
+        // Safety: see the safety comments on State
+        let capability_trace = unsafe { &mut *self.capability_trace.get() };
 
-        Ok(src.len())
+        match capability_trace {
+            Some(CapabilityTrace::Replay(reader)) => {
+                replay_capability_stream(&mut **reader, id, meta, buffer)
+            },
+            Some(CapabilityTrace::Record(writer)) => {
+                let bytes_read = stream.read(buffer)?;
+                trace::write_frame(&mut **writer, id, &buffer[..bytes_read])
+                    .context("Unable to record the capability's data")?;
+                Ok(bytes_read)
+            },
+            None => stream.read(buffer).map_err(Error::from),
+        }
     }
 
     fn write_output(
@@ -284,17 +763,31 @@ impl Callbacks for State {
         meta: &NodeMetadata,
         data: &[u8],
     ) -> Result<(), Error> {
+        // Safety: see the safety comments on State
+        let write_output = unsafe { &*self.write_output.get() };
+
+        if let Some(write_output) = write_output {
+            return write_output(id, meta, data);
+        }
+
         // Safety: see the safety comments on State
         let outputs = unsafe { &mut *self.output_tensors.get() };
+        let pool = unsafe { &mut *self.buffer_pool.get() };
 
-        let parsed = parse_outputs(meta, data).with_context(|| {
+        let parsed = parse_outputs(meta, data, pool).with_context(|| {
             format!(
                 "Unable to parse the \"{}\" output with ID {}",
                 meta.kind, id
             )
         })?;
 
-        outputs.insert(id, parsed);
+        if let Some(previous) = outputs.insert(id, parsed) {
+            for tensor in previous {
+                if let OutputTensor::Tensor(t) = tensor {
+                    pool.release(t.into_buffer());
+                }
+            }
+        }
 
         Ok(())
     }
@@ -317,12 +810,337 @@ impl Callbacks for State {
         resources.get(name).map(|s| s.as_slice())
     }
 
+    fn get_model(&self, name: &str) -> Option<&[u8]> {
+        // Safety: see the safety comments on State
+        let models = unsafe { &*self.models.get() };
+
+        models.get(name).map(|s| s.as_slice())
+    }
+
+    fn get_proc_block(&self, name: &str) -> Option<&[u8]> {
+        // Safety: see the safety comments on State
+        let proc_blocks = unsafe { &*self.proc_blocks.get() };
+
+        proc_blocks.get(name).map(|s| s.as_slice())
+    }
+
+    fn decrypt_model(
+        &self,
+        name: &str,
+        encrypted: &[u8],
+    ) -> Result<Vec<u8>, Error> {
+        // Safety: see the safety comments on State
+        let decrypt_model = unsafe { &*self.decrypt_model.get() };
+
+        match decrypt_model {
+            Some(decrypt_model) => decrypt_model(name, encrypted),
+            None => anyhow::bail!(
+                "The \"{}\" model is encrypted, but no decryptor was set",
+                name
+            ),
+        }
+    }
+
+    fn open_file(&self, path: &str, mode: FileMode) -> Result<File, Error> {
+        // Safety: see the safety comments on State
+        let allowed_files = unsafe { &*self.allowed_files.get() };
+        let path = Path::new(path);
+
+        match allowed_files.get(path) {
+            Some(&allowed) if allowed == mode => mode.open(path),
+            Some(&allowed) => anyhow::bail!(
+                "The Rune tried to open \"{}\" for {}, but it is only allowed \
+                 for {}",
+                path.display(),
+                mode,
+                allowed
+            ),
+            None => anyhow::bail!(
+                "The Rune isn't allowed to access \"{}\"",
+                path.display()
+            ),
+        }
+    }
+
     fn log(&self, record: &Record<'_>) {
+        // Safety: see the safety comments on State
+        let max_log_level = unsafe { *self.max_log_level.get() };
+
+        if record.level() > max_log_level {
+            return;
+        }
+
         // Safety: see the safety comments on State
         let log = unsafe { &*self.log.get() };
         log(record);
     }
+
+    fn monotonic_ns(&self) -> u64 { self.started.elapsed().as_nanos() as u64 }
+
+    fn is_cancelled(&self) -> bool { self.cancellation.is_cancelled() }
+
+    fn record_timing(&self, stage: Stage, id: u32, elapsed: Duration) {
+        // Safety: see the safety comments on State
+        let metrics = unsafe { &mut *self.metrics.get() };
+        metrics.record(stage, id, elapsed);
+    }
+
+    fn record_stage_timing(&self, stage: &str, elapsed: Duration) {
+        // Safety: see the safety comments on State
+        let metrics = unsafe { &mut *self.metrics.get() };
+        metrics.record_stage(stage, elapsed);
+    }
+}
+
+/// Check the capability's arguments to see whether its input tensor should be
+/// converted to another element type before giving it to the Rune.
+fn requested_conversion(
+    meta: &NodeMetadata,
+) -> Result<Option<(ElementType, Conversion)>, Error> {
+    let args = &meta.arguments;
+
+    let element_type = match args.get("convert_to") {
+        Some(value) => value.trim().parse::<ElementType>().with_context(|| {
+            format!("\"{}\" isn't a valid element type", value)
+        })?,
+        None => return Ok(None),
+    };
+
+    let conversion = match args.get("conversion").map(|s| s.trim()) {
+        None | Some("saturate") => Conversion::Saturate,
+        Some("normalize") => Conversion::Normalize,
+        Some("quantize") => {
+            let scale = args
+                .get("scale")
+                .context("Quantizing requires a \"scale\" argument")?
+                .trim()
+                .parse()
+                .context("Unable to parse the \"scale\" argument")?;
+            let zero_point = match args.get("zero_point") {
+                Some(value) => value
+                    .trim()
+                    .parse()
+                    .context("Unable to parse the \"zero_point\" argument")?,
+                None => 0,
+            };
+
+            Conversion::Quantize { scale, zero_point }
+        },
+        Some(other) => anyhow::bail!(
+            "Expected the conversion to be one of \"saturate\", \
+             \"normalize\", or \"quantize\", not \"{}\"",
+            other
+        ),
+    };
+
+    Ok(Some((element_type, conversion)))
+}
+
+fn replay_capability(
+    trace: &mut dyn Read,
+    id: u32,
+    meta: &NodeMetadata,
+    buffer: &mut [u8],
+) -> Result<usize, Error> {
+    let (recorded_id, data) = trace::read_frame(trace)
+        .context("Unable to read the capability trace")?
+        .with_context(|| {
+            format!(
+                "The trace has no more data for the \"{}\" capability with ID \
+                 {}",
+                meta.kind, id
+            )
+        })?;
+
+    anyhow::ensure!(
+        recorded_id == id,
+        "Expected the next frame to be for capability {}, but it was for {}",
+        id,
+        recorded_id
+    );
+    anyhow::ensure!(
+        data.len() == buffer.len(),
+        "The Rune provided a {} byte buffer, but {} bytes were recorded for \
+         the \"{}\" capability",
+        buffer.len(),
+        data.len(),
+        meta.kind
+    );
+
+    buffer.copy_from_slice(&data);
+
+    Ok(data.len())
+}
+
+/// Replay one chunk of a capability stream, where an empty frame marks the
+/// end of the stream.
+fn replay_capability_stream(
+    trace: &mut dyn Read,
+    id: u32,
+    meta: &NodeMetadata,
+    buffer: &mut [u8],
+) -> Result<usize, Error> {
+    let (recorded_id, data) = trace::read_frame(trace)
+        .context("Unable to read the capability trace")?
+        .with_context(|| {
+            format!(
+                "The trace has no more data for the \"{}\" capability stream \
+                 with ID {}",
+                meta.kind, id
+            )
+        })?;
+
+    anyhow::ensure!(
+        recorded_id == id,
+        "Expected the next frame to be for capability {}, but it was for {}",
+        id,
+        recorded_id
+    );
+    anyhow::ensure!(
+        data.len() <= buffer.len(),
+        "The Rune asked for at most {} bytes, but {} bytes were recorded for \
+         the \"{}\" capability stream",
+        buffer.len(),
+        data.len(),
+        meta.kind
+    );
+
+    buffer[..data.len()].copy_from_slice(&data);
+
+    Ok(data.len())
 }
 
 // Safety: see comments on the `State` type itself.
 unsafe impl Sync for State {}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+
+    #[derive(Debug, Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> { Ok(()) }
+    }
+
+    fn read_chunks(state: &State, meta: &NodeMetadata) -> Vec<Vec<u8>> {
+        let mut stream = state.open_capability_stream(1, meta).unwrap();
+        let mut chunks = Vec::new();
+
+        loop {
+            let mut buffer = [0; 5];
+            let bytes_read = state
+                .read_capability_stream(1, meta, &mut stream, &mut buffer)
+                .unwrap();
+            chunks.push(buffer[..bytes_read].to_vec());
+
+            if bytes_read == 0 {
+                return chunks;
+            }
+        }
+    }
+
+    fn convert_to_f32() -> (State, NodeMetadata) {
+        let mut arguments = HashMap::new();
+        arguments.insert("convert_to".to_string(), "f32".to_string());
+        let meta = NodeMetadata {
+            kind: "RAW".to_string(),
+            arguments,
+        };
+        let state = State::default();
+        unsafe {
+            state
+                .input_tensors()
+                .insert(1, Tensor::new(&[1_u8, 2, 3], &[3]));
+        }
+
+        (state, meta)
+    }
+
+    #[test]
+    fn converted_capabilities_are_written_in_place() {
+        let (state, meta) = convert_to_f32();
+        let mut floats = [0.0_f32; 3];
+        let buffer = unsafe {
+            std::slice::from_raw_parts_mut(floats.as_mut_ptr().cast(), 12)
+        };
+
+        state.read_capability(1, &meta, buffer).unwrap();
+
+        assert_eq!(floats, [1.0, 2.0, 3.0]);
+        let stats = unsafe { state.buffer_pool().stats() };
+        assert_eq!(stats.hits + stats.misses, 0);
+    }
+
+    #[test]
+    fn misaligned_conversions_reuse_pooled_buffers() {
+        let (state, meta) = convert_to_f32();
+        let mut floats = [0.0_f32; 4];
+        let buffer = unsafe {
+            std::slice::from_raw_parts_mut(floats.as_mut_ptr().cast::<u8>(), 16)
+        };
+        let buffer = &mut buffer[1..13];
+
+        for _ in 0..3 {
+            state.read_capability(1, &meta, buffer).unwrap();
+        }
+
+        let expected: Vec<u8> =
+            [1.0_f32, 2.0, 3.0].iter().flat_map(|f| f.to_ne_bytes()).collect();
+        assert_eq!(&*buffer, expected.as_slice());
+        let stats = unsafe { state.buffer_pool().stats() };
+        assert_eq!(stats.misses, 1);
+        assert_eq!(stats.hits, 2);
+    }
+
+    #[test]
+    fn record_and_replay_a_capability_stream() {
+        let meta = NodeMetadata {
+            kind: "SOUND".to_string(),
+            arguments: HashMap::new(),
+        };
+        let trace = SharedBuffer::default();
+        let recording = State::default();
+        unsafe {
+            recording.set_capability_trace(CapabilityTrace::Record(Box::new(
+                trace.clone(),
+            )));
+            recording.set_capability_stream_handler(|_, _| {
+                let stream: CapabilityStream =
+                    Box::new(Cursor::new(b"Hello, World!".to_vec()));
+                Ok(stream)
+            });
+        }
+
+        let recorded = read_chunks(&recording, &meta);
+
+        assert_eq!(
+            recorded,
+            [b"Hello".to_vec(), b", Wor".to_vec(), b"ld!".to_vec(), vec![]]
+        );
+
+        let replaying = State::default();
+        let trace = trace.0.lock().unwrap().clone();
+        unsafe {
+            replaying.set_capability_trace(CapabilityTrace::Replay(Box::new(
+                Cursor::new(trace),
+            )));
+        }
+
+        let replayed = read_chunks(&replaying, &meta);
+
+        assert_eq!(replayed, recorded);
+        // and the trace has been used up
+        let mut stream = replaying.open_capability_stream(1, &meta).unwrap();
+        assert!(replaying
+            .read_capability_stream(1, &meta, &mut stream, &mut [0; 5])
+            .is_err());
+    }
+}
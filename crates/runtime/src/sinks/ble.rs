@@ -0,0 +1,266 @@
+use anyhow::{Context, Error};
+
+use crate::OutputTensor;
+
+/// The payload size that every BLE connection is guaranteed to support (the
+/// default ATT MTU of 23 bytes, minus the 3 byte header).
+pub const DEFAULT_MAX_PAYLOAD: usize = 20;
+
+/// Something which can deliver a payload to subscribers of a GATT
+/// characteristic.
+///
+/// Devices with their own BLE stack can implement this to send notifications
+/// from their GATT server, while desktops can use [`BtleplugNotifier`] (with
+/// the `ble` feature).
+pub trait GattNotifier: Send {
+    fn notify(&mut self, payload: &[u8]) -> Result<(), Error>;
+}
+
+impl<F> GattNotifier for F
+where
+    F: FnMut(&[u8]) -> Result<(), Error> + Send,
+{
+    fn notify(&mut self, payload: &[u8]) -> Result<(), Error> { self(payload) }
+}
+
+/// Publishes the outputs from each call to [`crate::Runtime::predict()`] as
+/// BLE GATT notifications.
+///
+/// Each result is encoded as a line of JSON (the same format as `rune run`)
+/// and split into chunks that fit in a single notification, so receivers
+/// should keep reading until they see a `\n`.
+pub struct BleSink<N> {
+    notifier: N,
+    max_payload: usize,
+}
+
+impl<N: GattNotifier> BleSink<N> {
+    pub fn new(notifier: N) -> Self {
+        BleSink {
+            notifier,
+            max_payload: DEFAULT_MAX_PAYLOAD,
+        }
+    }
+
+    /// Send larger notifications when the connection has negotiated a
+    /// bigger MTU.
+    pub fn with_max_payload(self, max_payload: usize) -> Self {
+        assert!(max_payload > 0, "The max payload must be positive");
+        BleSink {
+            max_payload,
+            ..self
+        }
+    }
+
+    pub fn publish(&mut self, tensors: &[OutputTensor]) -> Result<(), Error> {
+        let mut message = serde_json::to_vec(tensors)
+            .context("Unable to serialize the tensors to JSON")?;
+        message.push(b'\n');
+
+        for chunk in message.chunks(self.max_payload) {
+            self.notifier
+                .notify(chunk)
+                .context("Unable to send a BLE notification")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "ble")]
+pub use self::btleplug_notifier::{BleConfig, BtleplugNotifier};
+
+#[cfg(feature = "ble")]
+mod btleplug_notifier {
+    use std::time::{Duration, Instant};
+
+    use anyhow::{Context, Error};
+    use btleplug::{
+        api::{
+            Central, Characteristic, Manager as _, Peripheral as _,
+            ScanFilter, WriteType,
+        },
+        platform::{Manager, Peripheral},
+    };
+    use tokio::runtime::Runtime;
+    use uuid::Uuid;
+
+    use super::GattNotifier;
+
+    /// Which BLE device and characteristic results should be sent to.
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct BleConfig {
+        /// The characteristic's UUID.
+        pub characteristic: Uuid,
+        /// Only connect to a device advertising this name.
+        pub device_name: Option<String>,
+        /// How long to scan for the device before giving up.
+        pub scan_timeout: Duration,
+    }
+
+    impl BleConfig {
+        pub fn new(characteristic: Uuid) -> Self {
+            BleConfig {
+                characteristic,
+                device_name: None,
+                scan_timeout: Duration::from_secs(10),
+            }
+        }
+    }
+
+    /// A [`GattNotifier`] for desktops, backed by
+    /// [btleplug](https://crates.io/crates/btleplug).
+    ///
+    /// btleplug can only act as a BLE central, so rather than hosting a
+    /// GATT server this connects to a peripheral (e.g. a gateway or dev
+    /// board) and writes each chunk to its characteristic, which is then
+    /// responsible for notifying its own subscribers.
+    pub struct BtleplugNotifier {
+        runtime: Runtime,
+        peripheral: Peripheral,
+        characteristic: Characteristic,
+    }
+
+    impl BtleplugNotifier {
+        pub fn connect(config: &BleConfig) -> Result<Self, Error> {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .context("Unable to start the BLE event loop")?;
+
+            let (peripheral, characteristic) =
+                runtime.block_on(find_characteristic(config))?;
+
+            Ok(BtleplugNotifier {
+                runtime,
+                peripheral,
+                characteristic,
+            })
+        }
+    }
+
+    impl GattNotifier for BtleplugNotifier {
+        fn notify(&mut self, payload: &[u8]) -> Result<(), Error> {
+            let BtleplugNotifier {
+                runtime,
+                peripheral,
+                characteristic,
+            } = self;
+
+            runtime.block_on(peripheral.write(
+                characteristic,
+                payload,
+                WriteType::WithoutResponse,
+            ))?;
+
+            Ok(())
+        }
+    }
+
+    async fn find_characteristic(
+        config: &BleConfig,
+    ) -> Result<(Peripheral, Characteristic), Error> {
+        let manager = Manager::new().await?;
+        let central = manager
+            .adapters()
+            .await?
+            .into_iter()
+            .next()
+            .context("No Bluetooth adapters were found")?;
+
+        central.start_scan(ScanFilter::default()).await?;
+        let deadline = Instant::now() + config.scan_timeout;
+
+        loop {
+            for peripheral in central.peripherals().await? {
+                if !matches_name(&peripheral, config).await? {
+                    continue;
+                }
+
+                if let Some(characteristic) =
+                    characteristic_on(&peripheral, config).await?
+                {
+                    central.stop_scan().await?;
+                    return Ok((peripheral, characteristic));
+                }
+            }
+
+            anyhow::ensure!(
+                Instant::now() < deadline,
+                "Unable to find a device with the {} characteristic",
+                config.characteristic
+            );
+            tokio::time::sleep(Duration::from_millis(500)).await;
+        }
+    }
+
+    async fn matches_name(
+        peripheral: &Peripheral,
+        config: &BleConfig,
+    ) -> Result<bool, Error> {
+        let expected = match &config.device_name {
+            Some(name) => name,
+            None => return Ok(true),
+        };
+
+        let name = peripheral
+            .properties()
+            .await?
+            .and_then(|properties| properties.local_name);
+
+        Ok(name.as_ref() == Some(expected))
+    }
+
+    async fn characteristic_on(
+        peripheral: &Peripheral,
+        config: &BleConfig,
+    ) -> Result<Option<Characteristic>, Error> {
+        if !peripheral.is_connected().await? {
+            if let Err(e) = peripheral.connect().await {
+                log::debug!("Unable to connect to a BLE device: {}", e);
+                return Ok(None);
+            }
+        }
+
+        peripheral.discover_services().await?;
+
+        let characteristic = peripheral
+            .characteristics()
+            .into_iter()
+            .find(|c| c.uuid == config.characteristic);
+
+        if characteristic.is_none() && config.device_name.is_none() {
+            // Don't hold onto devices we aren't going to use
+            peripheral.disconnect().await?;
+        }
+
+        Ok(characteristic)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Tensor;
+
+    #[test]
+    fn results_are_split_into_notifications() {
+        let mut notifications = Vec::new();
+        let notifier = |payload: &[u8]| {
+            notifications.push(payload.to_vec());
+            Ok::<_, Error>(())
+        };
+        let mut sink = BleSink::new(notifier).with_max_payload(8);
+        let tensors: Vec<OutputTensor> =
+            vec![Tensor::new(&[1_u8, 2], &[2]).into()];
+
+        sink.publish(&tensors).unwrap();
+        drop(sink);
+
+        assert!(notifications.iter().all(|n| n.len() <= 8));
+        let message = notifications.concat();
+        let expected = serde_json::to_vec(&tensors).unwrap();
+        assert_eq!(&message[..message.len() - 1], expected.as_slice());
+        assert_eq!(message.last(), Some(&b'\n'));
+    }
+}
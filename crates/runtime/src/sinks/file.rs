@@ -0,0 +1,387 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::{File, OpenOptions},
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Context, Error};
+use serde_json::Value;
+
+use crate::OutputTensor;
+
+/// Where results should be written and when the file should be rotated.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileSinkConfig {
+    pub path: PathBuf,
+    pub format: FileFormat,
+    /// Start a new file once the current one would grow past this many bytes.
+    pub max_bytes: Option<u64>,
+    /// How many rotated files (`results.jsonl.1`, `results.jsonl.2`, ...) to
+    /// keep around before deleting the oldest.
+    pub max_files: usize,
+}
+
+impl FileSinkConfig {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+
+        FileSinkConfig {
+            format: FileFormat::from_path(&path),
+            path,
+            max_bytes: None,
+            max_files: 5,
+        }
+    }
+
+    fn rotated_path(&self, n: usize) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".{}", n));
+        PathBuf::from(path)
+    }
+}
+
+/// How each result is written to the file.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FileFormat {
+    /// One JSON object per line, in the same format `rune run` prints.
+    JsonLines,
+    /// One row per tensor, containing a timestamp, the output ID, the
+    /// tensor's index, its element type, its dimensions, and then its
+    /// elements.
+    Csv,
+    /// Each tensor's bytes, back to back.
+    Raw,
+}
+
+impl FileFormat {
+    /// Guess the format from a file's extension, defaulting to
+    /// [`FileFormat::JsonLines`].
+    pub fn from_path(path: impl AsRef<Path>) -> Self {
+        match path.as_ref().extension().and_then(|ext| ext.to_str()) {
+            Some("csv") => FileFormat::Csv,
+            Some("bin") | Some("raw") => FileFormat::Raw,
+            _ => FileFormat::JsonLines,
+        }
+    }
+}
+
+impl FromStr for FileFormat {
+    type Err = UnknownFileFormat;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "jsonl" | "json-lines" | "json" => Ok(FileFormat::JsonLines),
+            "csv" => Ok(FileFormat::Csv),
+            "raw" | "binary" => Ok(FileFormat::Raw),
+            _ => Err(UnknownFileFormat),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, thiserror::Error)]
+#[error("Expected one of \"jsonl\", \"csv\", or \"raw\"")]
+pub struct UnknownFileFormat;
+
+/// Appends the outputs from each call to [`crate::Runtime::predict()`] to a
+/// file, optionally rotating it once it gets too big.
+#[derive(Debug)]
+pub struct FileSink {
+    config: FileSinkConfig,
+    writer: BufWriter<File>,
+    bytes_written: u64,
+}
+
+impl FileSink {
+    /// Open the file for appending, creating it if it doesn't already exist.
+    pub fn open(config: FileSinkConfig) -> Result<Self, Error> {
+        let (writer, bytes_written) = open_for_appending(&config.path)?;
+
+        Ok(FileSink {
+            config,
+            writer,
+            bytes_written,
+        })
+    }
+
+    pub fn path(&self) -> &Path { &self.config.path }
+
+    /// Write the tensors from every output.
+    pub fn write(
+        &mut self,
+        outputs: &HashMap<u32, Vec<OutputTensor>>,
+    ) -> Result<(), Error> {
+        // Make sure outputs are always written in the same order
+        let outputs: BTreeMap<_, _> = outputs.iter().collect();
+
+        let entry = match self.config.format {
+            FileFormat::JsonLines => json_line(&outputs)?,
+            FileFormat::Csv => csv_rows(&outputs)?,
+            FileFormat::Raw => raw_bytes(&outputs)?,
+        };
+
+        if self.should_rotate(entry.len()) {
+            self.rotate()?;
+        }
+
+        self.writer.write_all(&entry).with_context(|| {
+            format!("Unable to write to \"{}\"", self.config.path.display())
+        })?;
+        self.writer.flush()?;
+        self.bytes_written += entry.len() as u64;
+
+        Ok(())
+    }
+
+    fn should_rotate(&self, entry_length: usize) -> bool {
+        match self.config.max_bytes {
+            Some(max_bytes) => {
+                self.bytes_written > 0
+                    && self.bytes_written + entry_length as u64 > max_bytes
+            },
+            None => false,
+        }
+    }
+
+    /// Shuffle `path.1` to `path.2`, `path` to `path.1`, and so on, then
+    /// start writing to a fresh file.
+    fn rotate(&mut self) -> Result<(), Error> {
+        self.writer.flush()?;
+        let max_files = self.config.max_files;
+        log::debug!("Rotating \"{}\"", self.config.path.display());
+
+        if max_files == 0 {
+            std::fs::remove_file(&self.config.path)?;
+        } else {
+            let oldest = self.config.rotated_path(max_files);
+            if oldest.exists() {
+                std::fs::remove_file(&oldest)?;
+            }
+
+            for n in (1..max_files).rev() {
+                let from = self.config.rotated_path(n);
+                if from.exists() {
+                    std::fs::rename(&from, self.config.rotated_path(n + 1))?;
+                }
+            }
+
+            std::fs::rename(&self.config.path, self.config.rotated_path(1))
+                .with_context(|| {
+                    format!(
+                        "Unable to rotate \"{}\"",
+                        self.config.path.display()
+                    )
+                })?;
+        }
+
+        let (writer, bytes_written) = open_for_appending(&self.config.path)?;
+        self.writer = writer;
+        self.bytes_written = bytes_written;
+
+        Ok(())
+    }
+}
+
+fn open_for_appending(path: &Path) -> Result<(BufWriter<File>, u64), Error> {
+    let f = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Unable to open \"{}\"", path.display()))?;
+    let length = f.metadata()?.len();
+
+    Ok((BufWriter::new(f), length))
+}
+
+fn json_line(
+    outputs: &BTreeMap<&u32, &Vec<OutputTensor>>,
+) -> Result<Vec<u8>, Error> {
+    let mut line = serde_json::to_vec(outputs)
+        .context("Unable to serialize the output tensors to JSON")?;
+    line.push(b'\n');
+    Ok(line)
+}
+
+fn csv_rows(
+    outputs: &BTreeMap<&u32, &Vec<OutputTensor>>,
+) -> Result<Vec<u8>, Error> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+    let mut rows = Vec::new();
+
+    for (id, tensors) in outputs {
+        for (i, tensor) in tensors.iter().enumerate() {
+            let (element_type, dimensions, elements) = csv_columns(tensor)?;
+
+            let mut row = vec![
+                timestamp.to_string(),
+                id.to_string(),
+                i.to_string(),
+                element_type,
+                dimensions,
+            ];
+            row.extend(elements);
+
+            let row: Vec<_> = row.iter().map(|f| csv_field(f)).collect();
+            writeln!(rows, "{}", row.join(","))?;
+        }
+    }
+
+    Ok(rows)
+}
+
+/// Get the element type, dimensions (e.g. `1x10`), and elements to write for
+/// a tensor.
+fn csv_columns(
+    tensor: &OutputTensor,
+) -> Result<(String, String, Vec<String>), Error> {
+    let dimensions = |dims: &mut dyn Iterator<Item = usize>| {
+        dims.map(|d| d.to_string()).collect::<Vec<_>>().join("x")
+    };
+
+    match tensor {
+        OutputTensor::Tensor(t) => {
+            let serialized = serde_json::to_value(t.serializable())?;
+            let elements = match serialized.get("elements") {
+                Some(Value::Array(elements)) => {
+                    elements.iter().map(value_to_string).collect()
+                },
+                _ => Vec::new(),
+            };
+
+            Ok((
+                t.element_type().to_string(),
+                dimensions(&mut t.dimensions().iter().map(|d| d.get())),
+                elements,
+            ))
+        },
+        OutputTensor::StringTensor {
+            dimensions: dims,
+            strings,
+        } => Ok((
+            String::from("utf8"),
+            dimensions(&mut dims.iter().copied()),
+            strings.clone(),
+        )),
+        OutputTensor::Record(record) => {
+            let elements = record
+                .schema
+                .iter()
+                .map(|field| {
+                    record
+                        .get(&field.name)
+                        .map(value_to_string)
+                        .unwrap_or_default()
+                })
+                .collect();
+
+            Ok((String::from("record"), String::from("1"), elements))
+        },
+    }
+}
+
+fn value_to_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Quote a field if it contains anything that would confuse a CSV parser.
+fn csv_field(field: &str) -> String {
+    if field.contains(|c| matches!(c, ',' | '"' | '\n' | '\r')) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+pub(super) fn raw_bytes(
+    outputs: &BTreeMap<&u32, &Vec<OutputTensor>>,
+) -> Result<Vec<u8>, Error> {
+    let mut bytes = Vec::new();
+
+    for tensors in outputs.values() {
+        for tensor in tensors.iter() {
+            match tensor {
+                OutputTensor::Tensor(t) => bytes.extend_from_slice(t.buffer()),
+                _ => anyhow::bail!(
+                    "Only numeric tensors can be written as raw bytes"
+                ),
+            }
+        }
+    }
+
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Tensor;
+
+    fn outputs() -> HashMap<u32, Vec<OutputTensor>> {
+        let mut outputs = HashMap::new();
+        outputs.insert(
+            1,
+            vec![
+                Tensor::new(&[1_u8, 2], &[1, 2]).into(),
+                OutputTensor::StringTensor {
+                    dimensions: vec![1],
+                    strings: vec![String::from("a, b")],
+                },
+            ],
+        );
+        outputs
+    }
+
+    #[test]
+    fn formats_from_file_extensions() {
+        assert_eq!(FileFormat::from_path("log.csv"), FileFormat::Csv);
+        assert_eq!(FileFormat::from_path("log.bin"), FileFormat::Raw);
+        assert_eq!(FileFormat::from_path("log.jsonl"), FileFormat::JsonLines);
+    }
+
+    #[test]
+    fn write_csv_rows() {
+        let outputs = outputs();
+        let outputs: BTreeMap<_, _> = outputs.iter().collect();
+
+        let got = String::from_utf8(csv_rows(&outputs).unwrap()).unwrap();
+
+        // skip the timestamp column
+        let rows: Vec<&str> = got
+            .lines()
+            .map(|line| line.splitn(2, ',').nth(1).unwrap())
+            .collect();
+        assert_eq!(rows, vec!["1,0,u8,1x2,1,2", "1,1,utf8,1,\"a, b\""]);
+    }
+
+    #[test]
+    fn rotate_when_the_file_gets_too_big() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("results.jsonl");
+        let config = FileSinkConfig {
+            max_bytes: Some(1),
+            max_files: 2,
+            ..FileSinkConfig::new(&path)
+        };
+        let mut sink = FileSink::open(config).unwrap();
+        let mut outputs = HashMap::new();
+
+        for i in 10..14_u8 {
+            outputs.insert(0, vec![Tensor::new(&[i], &[1]).into()]);
+            sink.write(&outputs).unwrap();
+        }
+
+        let read = |path: PathBuf| std::fs::read_to_string(path).unwrap();
+        assert!(read(path.clone()).contains("[13]"));
+        assert!(read(dir.path().join("results.jsonl.1")).contains("[12]"));
+        assert!(read(dir.path().join("results.jsonl.2")).contains("[11]"));
+        assert!(!dir.path().join("results.jsonl.3").exists());
+    }
+}
@@ -0,0 +1,307 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    convert::TryInto,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use anyhow::{Context, Error};
+use image::{DynamicImage, GrayImage, RgbImage, RgbaImage};
+
+use crate::{ElementType, OutputTensor, Tensor};
+
+/// Where images should be saved and how they are encoded.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageSinkConfig {
+    pub directory: PathBuf,
+    pub format: ImageFormat,
+    /// Stretch each image's values so they cover the full `0-255` range,
+    /// which makes things like segmentation masks (where each pixel is a
+    /// class ID) visible.
+    pub normalize: bool,
+}
+
+impl ImageSinkConfig {
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        ImageSinkConfig {
+            directory: directory.into(),
+            format: ImageFormat::Png,
+            normalize: false,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ImageFormat {
+    Png,
+    Jpeg,
+}
+
+impl ImageFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            ImageFormat::Png => "png",
+            ImageFormat::Jpeg => "jpg",
+        }
+    }
+}
+
+impl FromStr for ImageFormat {
+    type Err = UnknownImageFormat;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "png" => Ok(ImageFormat::Png),
+            "jpeg" | "jpg" => Ok(ImageFormat::Jpeg),
+            _ => Err(UnknownImageFormat),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, thiserror::Error)]
+#[error("Expected either \"png\" or \"jpeg\"")]
+pub struct UnknownImageFormat;
+
+/// Saves every output tensor that looks like an image (e.g. a segmentation
+/// mask or the result of style transfer) to its own file, once per call to
+/// [`crate::Runtime::predict()`].
+///
+/// Files are named `{output}_{tensor}_{call}.png`, where `call` counts up
+/// from `0`.
+#[derive(Debug)]
+pub struct ImageSink {
+    config: ImageSinkConfig,
+    calls: usize,
+}
+
+impl ImageSink {
+    pub fn new(config: ImageSinkConfig) -> Result<Self, Error> {
+        std::fs::create_dir_all(&config.directory).with_context(|| {
+            format!(
+                "Unable to create the \"{}\" directory",
+                config.directory.display()
+            )
+        })?;
+
+        Ok(ImageSink { config, calls: 0 })
+    }
+
+    /// Save each image tensor, returning the paths that were written to.
+    ///
+    /// Tensors which can't be interpreted as an image are skipped.
+    pub fn write(
+        &mut self,
+        outputs: &HashMap<u32, Vec<OutputTensor>>,
+    ) -> Result<Vec<PathBuf>, Error> {
+        let outputs: BTreeMap<_, _> = outputs.iter().collect();
+        let mut saved = Vec::new();
+
+        for (id, tensors) in outputs {
+            for (i, tensor) in tensors.iter().enumerate() {
+                let tensor = match tensor {
+                    OutputTensor::Tensor(t) => t,
+                    _ => continue,
+                };
+
+                let normalize = self.config.normalize;
+                let image = match tensor_to_image(tensor, normalize) {
+                    Ok(image) => image,
+                    Err(e) => {
+                        log::debug!(
+                            "Not saving tensor {} from output {}: {}",
+                            i,
+                            id,
+                            e
+                        );
+                        continue;
+                    },
+                };
+
+                let filename = format!(
+                    "{}_{}_{:06}.{}",
+                    id,
+                    i,
+                    self.calls,
+                    self.config.format.extension()
+                );
+                let path = self.config.directory.join(filename);
+                self.save(image, &path)?;
+                saved.push(path);
+            }
+        }
+
+        self.calls += 1;
+
+        Ok(saved)
+    }
+
+    fn save(&self, image: DynamicImage, path: &Path) -> Result<(), Error> {
+        let result = match self.config.format {
+            ImageFormat::Png => {
+                image.save_with_format(path, image::ImageFormat::Png)
+            },
+            // JPEGs don't support transparency
+            ImageFormat::Jpeg => DynamicImage::ImageRgb8(image.to_rgb8())
+                .save_with_format(path, image::ImageFormat::Jpeg),
+        };
+
+        result.with_context(|| format!("Unable to save \"{}\"", path.display()))
+    }
+}
+
+/// Interpret a `[height, width]` or `[height, width, channels]` tensor (with
+/// any number of leading `1`s) as a grayscale, RGB, or RGBA image.
+///
+/// `u8` values are used as-is, floats between `0` and `1` are scaled up to
+/// `0-255`, and anything else is clamped to `0-255` unless `normalize` is
+/// set.
+pub fn tensor_to_image(
+    tensor: &Tensor,
+    normalize: bool,
+) -> Result<DynamicImage, Error> {
+    let mut dimensions: Vec<usize> =
+        tensor.dimensions().iter().map(|d| d.get()).collect();
+    while dimensions.len() > 2 && dimensions[0] == 1 {
+        dimensions.remove(0);
+    }
+
+    let (height, width, channels) = match *dimensions.as_slice() {
+        [height, width] => (height, width, 1),
+        [height, width, channels @ 1]
+        | [height, width, channels @ 3]
+        | [height, width, channels @ 4] => (height, width, channels),
+        _ => anyhow::bail!("A {} tensor isn't an image", tensor.shape()),
+    };
+
+    let pixels = to_pixels(tensor, normalize)?;
+    let width: u32 = width.try_into()?;
+    let height: u32 = height.try_into()?;
+
+    let image = match channels {
+        1 => GrayImage::from_raw(width, height, pixels)
+            .map(DynamicImage::ImageLuma8),
+        3 => RgbImage::from_raw(width, height, pixels)
+            .map(DynamicImage::ImageRgb8),
+        _ => RgbaImage::from_raw(width, height, pixels)
+            .map(DynamicImage::ImageRgba8),
+    };
+
+    image.context("The tensor doesn't have enough pixels")
+}
+
+fn to_pixels(tensor: &Tensor, normalize: bool) -> Result<Vec<u8>, Error> {
+    if tensor.element_type() == ElementType::U8 && !normalize {
+        return Ok(tensor.buffer().to_vec());
+    }
+
+    let values = to_f64(tensor);
+
+    if normalize {
+        let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+        let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        let range = max - min;
+
+        return Ok(values
+            .iter()
+            .map(|v| {
+                if range > 0.0 {
+                    ((v - min) / range * 255.0).round() as u8
+                } else {
+                    0
+                }
+            })
+            .collect());
+    }
+
+    let is_float =
+        matches!(tensor.element_type(), ElementType::F32 | ElementType::F64);
+    let unit_range = values.iter().all(|v| (0.0..=1.0).contains(v));
+    let scale = if is_float && unit_range { 255.0 } else { 1.0 };
+
+    Ok(values
+        .iter()
+        .map(|v| (v * scale).round().max(0.0).min(255.0) as u8)
+        .collect())
+}
+
+fn to_f64(tensor: &Tensor) -> Vec<f64> {
+    macro_rules! convert {
+        ($ty:ty) => {
+            tensor
+                .buffer()
+                .chunks_exact(std::mem::size_of::<$ty>())
+                .map(|bytes| {
+                    <$ty>::from_ne_bytes(bytes.try_into().unwrap()) as f64
+                })
+                .collect()
+        };
+    }
+
+    match tensor.element_type() {
+        ElementType::U8 => convert!(u8),
+        ElementType::I8 => convert!(i8),
+        ElementType::U16 => convert!(u16),
+        ElementType::I16 => convert!(i16),
+        ElementType::U32 => convert!(u32),
+        ElementType::I32 => convert!(i32),
+        ElementType::F32 => convert!(f32),
+        ElementType::U64 => convert!(u64),
+        ElementType::I64 => convert!(i64),
+        ElementType::F64 => convert!(f64),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rgb_tensors_with_a_batch_dimension() {
+        let pixels: Vec<u8> = (0..12).collect();
+        let tensor = Tensor::new(&pixels, &[1, 2, 2, 3]);
+
+        let got = tensor_to_image(&tensor, false).unwrap();
+
+        assert_eq!(got.to_rgb8().into_raw(), pixels);
+    }
+
+    #[test]
+    fn float_images_are_scaled() {
+        let tensor = Tensor::new(&[0.0_f32, 0.5, 1.0, 0.25], &[2, 2]);
+
+        let got = tensor_to_image(&tensor, false).unwrap();
+
+        assert_eq!(got.to_luma8().into_raw(), vec![0, 128, 255, 64]);
+    }
+
+    #[test]
+    fn normalize_segmentation_masks() {
+        let tensor = Tensor::new(&[0_i32, 1, 2, 1], &[1, 2, 2, 1]);
+
+        let got = tensor_to_image(&tensor, true).unwrap();
+
+        assert_eq!(got.to_luma8().into_raw(), vec![0, 128, 255, 128]);
+    }
+
+    #[test]
+    fn save_one_file_per_call() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = ImageSinkConfig::new(dir.path());
+        let mut sink = ImageSink::new(config).unwrap();
+        let mut outputs = HashMap::new();
+        outputs.insert(
+            1,
+            vec![
+                Tensor::new(&[0_u8; 4], &[2, 2]).into(),
+                Tensor::new(&[0.0_f32; 5], &[5]).into(),
+            ],
+        );
+
+        let first = sink.write(&outputs).unwrap();
+        let second = sink.write(&outputs).unwrap();
+
+        assert_eq!(first, vec![dir.path().join("1_0_000000.png")]);
+        assert_eq!(second, vec![dir.path().join("1_0_000001.png")]);
+        assert!(second[0].exists());
+    }
+}
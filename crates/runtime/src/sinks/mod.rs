@@ -0,0 +1,28 @@
+//! Builtin destinations a host can forward a Rune's outputs to.
+
+mod ble;
+mod file;
+#[cfg(feature = "builtins")]
+mod image_writer;
+#[cfg(feature = "mqtt")]
+mod mqtt;
+#[cfg(feature = "builtins")]
+mod pipe;
+#[cfg(feature = "websocket")]
+mod websocket;
+
+#[cfg(feature = "ble")]
+pub use self::ble::{BleConfig, BtleplugNotifier};
+pub use self::ble::{BleSink, GattNotifier, DEFAULT_MAX_PAYLOAD};
+pub use self::file::{FileFormat, FileSink, FileSinkConfig, UnknownFileFormat};
+#[cfg(feature = "builtins")]
+pub use self::image_writer::{
+    tensor_to_image, ImageFormat, ImageSink, ImageSinkConfig,
+    UnknownImageFormat,
+};
+#[cfg(feature = "mqtt")]
+pub use self::mqtt::{MqttConfig, MqttSink, UnknownQos};
+#[cfg(feature = "builtins")]
+pub use self::pipe::{pipe, ChannelWriter, PipeSink};
+#[cfg(feature = "websocket")]
+pub use self::websocket::WebSocketSink;
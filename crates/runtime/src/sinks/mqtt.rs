@@ -0,0 +1,194 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use anyhow::{Context, Error};
+use rumqttc::{Client, ConnectionError, MqttOptions, QoS};
+
+use crate::OutputTensor;
+
+/// The port used when the broker's address doesn't specify one.
+pub const DEFAULT_PORT: u16 = 1883;
+
+/// How to connect to an MQTT broker and where messages should be published.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MqttConfig {
+    /// The broker's address, as `host` or `host:port`.
+    pub broker: String,
+    /// The topic to publish to, where `{id}` is replaced with the output's
+    /// ID.
+    pub topic: String,
+    /// The MQTT quality of service level (0, 1, or 2).
+    pub qos: u8,
+    pub client_id: String,
+}
+
+impl MqttConfig {
+    pub fn new(broker: impl Into<String>) -> Self {
+        MqttConfig {
+            broker: broker.into(),
+            topic: String::from("rune/outputs/{id}"),
+            qos: 0,
+            client_id: String::from("rune"),
+        }
+    }
+
+    fn host_and_port(&self) -> Result<(&str, u16), Error> {
+        let broker = self.broker.trim_start_matches("mqtt://");
+
+        match broker.rsplit_once(':') {
+            Some((host, port)) => {
+                let port = port.parse().with_context(|| {
+                    format!("\"{}\" isn't a valid port number", port)
+                })?;
+                Ok((host, port))
+            },
+            None => Ok((broker, DEFAULT_PORT)),
+        }
+    }
+
+    fn topic_for(&self, output_id: u32) -> String {
+        self.topic.replace("{id}", &output_id.to_string())
+    }
+}
+
+/// Publishes the outputs from each call to [`crate::Runtime::predict()`] to
+/// an MQTT broker as JSON.
+///
+/// The connection is driven by a background thread which automatically
+/// reconnects when the broker goes away.
+pub struct MqttSink {
+    config: MqttConfig,
+    qos: QoS,
+    client: Client,
+    connection: Option<JoinHandle<()>>,
+    /// Tells the connection thread to stop reconnecting once the sink is
+    /// dropped.
+    shutdown: Arc<AtomicBool>,
+}
+
+impl MqttSink {
+    pub fn connect(config: MqttConfig) -> Result<Self, Error> {
+        let qos = parse_qos(config.qos)?;
+        let (host, port) = config.host_and_port()?;
+
+        log::debug!("Connecting to the MQTT broker at {}:{}", host, port);
+        let options = MqttOptions::new(&config.client_id, host, port);
+        let (client, mut connection) = Client::new(options, 16);
+        let shutdown = Arc::new(AtomicBool::new(false));
+
+        let connection = thread::Builder::new()
+            .name(String::from("mqtt"))
+            .spawn({
+                let shutdown = Arc::clone(&shutdown);
+                move || {
+                    for notification in connection.iter() {
+                        match notification {
+                            Ok(event) => log::trace!("MQTT event: {:?}", event),
+                            Err(ConnectionError::RequestsDone) => break,
+                            // The broker is unreachable so our disconnect
+                            // request will never be sent. Give up instead of
+                            // retrying forever.
+                            Err(_) if shutdown.load(Ordering::SeqCst) => break,
+                            Err(e) => {
+                                log::warn!("MQTT connection error: {}", e);
+                                thread::sleep(Duration::from_secs(1));
+                            },
+                        }
+                    }
+                }
+            })
+            .context("Unable to start the MQTT connection thread")?;
+
+        Ok(MqttSink {
+            config,
+            qos,
+            client,
+            connection: Some(connection),
+            shutdown,
+        })
+    }
+
+    /// Publish an output's tensors using the same JSON format as
+    /// `rune run`.
+    pub fn publish(
+        &mut self,
+        output_id: u32,
+        tensors: &[OutputTensor],
+    ) -> Result<(), Error> {
+        let payload = serde_json::to_vec(tensors)
+            .context("Unable to serialize the tensors to JSON")?;
+        let topic = self.config.topic_for(output_id);
+
+        self.client
+            .publish(&topic, self.qos, false, payload)
+            .with_context(|| format!("Unable to publish to \"{}\"", topic))
+    }
+}
+
+impl Drop for MqttSink {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::SeqCst);
+        let _ = self.client.disconnect();
+
+        if let Some(connection) = self.connection.take() {
+            let _ = connection.join();
+        }
+    }
+}
+
+fn parse_qos(qos: u8) -> Result<QoS, UnknownQos> {
+    match qos {
+        0 => Ok(QoS::AtMostOnce),
+        1 => Ok(QoS::AtLeastOnce),
+        2 => Ok(QoS::ExactlyOnce),
+        _ => Err(UnknownQos(qos)),
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, thiserror::Error)]
+#[error("{0} isn't a valid MQTT quality of service (expected 0, 1, or 2)")]
+pub struct UnknownQos(pub u8);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn broker_addresses() {
+        let inputs = vec![
+            ("localhost", ("localhost", DEFAULT_PORT)),
+            ("localhost:1234", ("localhost", 1234)),
+            ("mqtt://broker.local:8883", ("broker.local", 8883)),
+        ];
+
+        for (broker, expected) in inputs {
+            let config = MqttConfig::new(broker);
+
+            assert_eq!(config.host_and_port().unwrap(), expected);
+        }
+
+        assert!(MqttConfig::new("localhost:port").host_and_port().is_err());
+    }
+
+    #[test]
+    fn output_ids_are_substituted_into_the_topic() {
+        let config = MqttConfig {
+            topic: String::from("devices/kitchen/{id}/result"),
+            ..MqttConfig::new("localhost")
+        };
+
+        assert_eq!(config.topic_for(3), "devices/kitchen/3/result");
+    }
+
+    #[test]
+    fn qos_levels() {
+        assert_eq!(parse_qos(1).unwrap(), QoS::AtLeastOnce);
+        assert_eq!(parse_qos(3).unwrap_err(), UnknownQos(3));
+    }
+}
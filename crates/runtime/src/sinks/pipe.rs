@@ -0,0 +1,126 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::{Read, Write},
+    sync::mpsc::{self, Receiver, Sender},
+};
+
+use anyhow::{Context, Error};
+
+use crate::{builtins::RawStream, sinks::file::raw_bytes, OutputTensor};
+
+/// Forwards the outputs from each call to [`crate::Runtime::predict()`] as
+/// raw bytes so they can be read by another Rune's `RAW` capability.
+///
+/// Each tensor's bytes are written back to back, in order of output ID, so
+/// the downstream Rune's `length` argument should match the total size of the
+/// upstream Rune's outputs.
+#[derive(Debug)]
+pub struct PipeSink<W> {
+    writer: W,
+}
+
+impl<W: Write> PipeSink<W> {
+    pub fn new(writer: W) -> Self { PipeSink { writer } }
+
+    pub fn write(
+        &mut self,
+        outputs: &HashMap<u32, Vec<OutputTensor>>,
+    ) -> Result<(), Error> {
+        let outputs: BTreeMap<_, _> = outputs.iter().collect();
+        let bytes = raw_bytes(&outputs)?;
+
+        self.writer
+            .write_all(&bytes)
+            .and_then(|_| self.writer.flush())
+            .context("Unable to forward the outputs")?;
+
+        Ok(())
+    }
+}
+
+/// Connect two Runes running in the same process, returning a [`PipeSink`]
+/// for the upstream Rune's outputs and a [`RawStream`] to use as the
+/// downstream Rune's `RAW` source.
+///
+/// The stream ends once the [`PipeSink`] is dropped.
+pub fn pipe(name: impl Into<String>) -> (PipeSink<ChannelWriter>, RawStream) {
+    let (sender, receiver) = mpsc::channel();
+
+    let writer = ChannelWriter { sender };
+    let reader = ChannelReader {
+        receiver,
+        buffer: Vec::new(),
+        position: 0,
+    };
+
+    (PipeSink::new(writer), RawStream::new(name, reader))
+}
+
+/// The writing half of a [`pipe()`].
+#[derive(Debug)]
+pub struct ChannelWriter {
+    sender: Sender<Vec<u8>>,
+}
+
+impl Write for ChannelWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.sender.send(buf.to_vec()).map_err(|_| {
+            std::io::Error::new(
+                std::io::ErrorKind::BrokenPipe,
+                "The downstream Rune has stopped reading",
+            )
+        })?;
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> { Ok(()) }
+}
+
+struct ChannelReader {
+    receiver: Receiver<Vec<u8>>,
+    buffer: Vec<u8>,
+    position: usize,
+}
+
+impl Read for ChannelReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        while self.position >= self.buffer.len() {
+            match self.receiver.recv() {
+                Ok(chunk) => {
+                    self.buffer = chunk;
+                    self.position = 0;
+                },
+                // The sender was dropped, so we've reached the end
+                Err(_) => return Ok(0),
+            }
+        }
+
+        let remaining = &self.buffer[self.position..];
+        let length = remaining.len().min(buf.len());
+        buf[..length].copy_from_slice(&remaining[..length]);
+        self.position += length;
+
+        Ok(length)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Tensor;
+
+    #[test]
+    fn outputs_are_read_by_the_downstream_rune() {
+        let (mut sink, mut stream) = pipe("upstream");
+        let mut outputs = HashMap::new();
+        outputs.insert(2, vec![Tensor::new(&[3_u8, 4], &[2]).into()]);
+        outputs.insert(1, vec![Tensor::new(&[1_u8, 2], &[2]).into()]);
+
+        sink.write(&outputs).unwrap();
+        drop(sink);
+
+        assert_eq!(stream.read_chunk(3).unwrap(), [1, 2, 3]);
+        assert!(stream.read_chunk(3).is_err());
+    }
+}
@@ -0,0 +1,112 @@
+use std::{
+    net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    sync::{Arc, Mutex},
+    thread,
+};
+
+use anyhow::{Context, Error};
+use tungstenite::{Message, WebSocket};
+
+type Clients = Arc<Mutex<Vec<WebSocket<TcpStream>>>>;
+
+/// A WebSocket server which pushes each result frame to every connected
+/// client (e.g. a live dashboard).
+///
+/// Clients are accepted on a background thread and are dropped as soon as
+/// writing to them fails.
+pub struct WebSocketSink {
+    local_addr: SocketAddr,
+    clients: Clients,
+}
+
+impl WebSocketSink {
+    pub fn bind(addr: impl ToSocketAddrs) -> Result<Self, Error> {
+        let listener = TcpListener::bind(addr)
+            .context("Unable to bind the WebSocket listener")?;
+        let local_addr = listener.local_addr()?;
+        let clients = Clients::default();
+
+        let accepted = Arc::clone(&clients);
+        thread::Builder::new()
+            .name(String::from("websocket"))
+            .spawn(move || accept_clients(listener, accepted))
+            .context("Unable to start the WebSocket thread")?;
+
+        log::info!("Streaming results over WebSockets on {}", local_addr);
+
+        Ok(WebSocketSink {
+            local_addr,
+            clients,
+        })
+    }
+
+    /// The address clients should connect to.
+    pub fn local_addr(&self) -> SocketAddr { self.local_addr }
+
+    /// The number of clients currently connected.
+    pub fn connected_clients(&self) -> usize {
+        self.clients.lock().unwrap().len()
+    }
+
+    /// Send a frame to every connected client.
+    pub fn broadcast(&self, frame: &str) {
+        let mut clients = self.clients.lock().unwrap();
+
+        let still_connected = clients
+            .drain(..)
+            .filter_map(|mut client| {
+                match client.write_message(Message::Text(frame.to_string())) {
+                    Ok(_) => Some(client),
+                    Err(e) => {
+                        log::debug!("Dropping a WebSocket client: {}", e);
+                        None
+                    },
+                }
+            })
+            .collect();
+
+        *clients = still_connected;
+    }
+}
+
+fn accept_clients(listener: TcpListener, clients: Clients) {
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(s) => s,
+            Err(e) => {
+                log::warn!("Unable to accept a WebSocket connection: {}", e);
+                continue;
+            },
+        };
+
+        match tungstenite::accept(stream) {
+            Ok(client) => clients.lock().unwrap().push(client),
+            Err(e) => log::warn!("The WebSocket handshake failed: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::*;
+
+    #[test]
+    fn connected_clients_receive_each_frame() {
+        let sink = WebSocketSink::bind("127.0.0.1:0").unwrap();
+        let url = format!("ws://{}/", sink.local_addr());
+        let (mut client, _) = tungstenite::connect(url).unwrap();
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while sink.connected_clients() == 0 {
+            assert!(Instant::now() < deadline, "The client never connected");
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        sink.broadcast(r#"{"1":[]}"#);
+
+        let got = client.read_message().unwrap();
+        assert_eq!(got, Message::Text(String::from(r#"{"1":[]}"#)));
+    }
+}
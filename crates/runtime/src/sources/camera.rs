@@ -0,0 +1,46 @@
+use anyhow::{Context, Error};
+use image::DynamicImage;
+
+/// A webcam attached to the host machine.
+///
+/// Frames are captured on demand, so [`Camera::capture()`] always returns
+/// the most recent image rather than one that has been sitting in a queue.
+pub struct Camera {
+    index: usize,
+    inner: nokhwa::Camera,
+}
+
+impl Camera {
+    /// Open the `index`'th camera (starting from `0`) and start streaming
+    /// from it using its default resolution and frame rate.
+    pub fn open(index: usize) -> Result<Self, Error> {
+        let mut inner = nokhwa::Camera::new(index, None)
+            .with_context(|| format!("Unable to open camera {}", index))?;
+        inner.open_stream().with_context(|| {
+            format!("Unable to start streaming from camera {}", index)
+        })?;
+
+        log::debug!("Opened camera {} at {}", index, inner.resolution());
+
+        Ok(Camera { index, inner })
+    }
+
+    pub fn index(&self) -> usize { self.index }
+
+    /// Grab the next frame from the camera.
+    pub fn capture(&mut self) -> Result<DynamicImage, Error> {
+        let frame = self.inner.frame().with_context(|| {
+            format!("Unable to capture a frame from camera {}", self.index)
+        })?;
+
+        Ok(DynamicImage::ImageRgb8(frame))
+    }
+}
+
+impl Drop for Camera {
+    fn drop(&mut self) {
+        if let Err(e) = self.inner.stop_stream() {
+            log::warn!("Unable to stop camera {}: {}", self.index, e);
+        }
+    }
+}
@@ -0,0 +1,176 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context, Error};
+use cpal::{
+    traits::{DeviceTrait, HostTrait, StreamTrait},
+    Device, InputCallbackInfo, Sample, SampleFormat, Stream, StreamConfig,
+};
+
+use crate::builtins::{resample, AudioClip};
+
+/// The most audio we'll hold onto before discarding old samples.
+const MAX_BUFFERED: Duration = Duration::from_secs(30);
+/// How much longer than the requested duration we'll wait for audio before
+/// assuming the microphone has stopped working.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A microphone attached to the host machine.
+///
+/// Audio is recorded in the background as soon as the microphone is opened,
+/// with clips being read from a [`Recording`].
+pub struct Microphone {
+    // Note: the stream stops recording when it is dropped
+    _stream: Stream,
+    recording: Recording,
+}
+
+impl Microphone {
+    /// Open the `index`'th input device, or the host's default input device
+    /// if no index is provided.
+    pub fn open(index: Option<usize>) -> Result<Self, Error> {
+        let host = cpal::default_host();
+
+        let device = match index {
+            Some(index) => host
+                .input_devices()
+                .context("Unable to list the input devices")?
+                .nth(index)
+                .with_context(|| format!("There is no microphone {}", index))?,
+            None => host
+                .default_input_device()
+                .context("There is no default microphone")?,
+        };
+
+        let supported = device
+            .default_input_config()
+            .context("Unable to determine the microphone's configuration")?;
+        let sample_format = supported.sample_format();
+        let config: StreamConfig = supported.into();
+
+        log::debug!(
+            "Recording from \"{}\" with {:?}",
+            device.name().unwrap_or_default(),
+            config
+        );
+
+        let recording = Recording {
+            buffer: Arc::new(Mutex::new(VecDeque::new())),
+            sample_rate: config.sample_rate.0,
+        };
+
+        let stream = match sample_format {
+            SampleFormat::F32 => {
+                build_stream::<f32>(&device, &config, recording.clone())
+            },
+            SampleFormat::I16 => {
+                build_stream::<i16>(&device, &config, recording.clone())
+            },
+            SampleFormat::U16 => {
+                build_stream::<u16>(&device, &config, recording.clone())
+            },
+        }
+        .context("Unable to start recording")?;
+
+        stream.play().context("Unable to start recording")?;
+
+        Ok(Microphone {
+            _stream: stream,
+            recording,
+        })
+    }
+
+    /// Get a handle to the audio being recorded by this microphone.
+    ///
+    /// Unlike the [`Microphone`] itself, a [`Recording`] can be sent to
+    /// other threads.
+    pub fn recording(&self) -> Recording { self.recording.clone() }
+}
+
+fn build_stream<T: Sample>(
+    device: &Device,
+    config: &StreamConfig,
+    recording: Recording,
+) -> Result<Stream, Error> {
+    let channels = usize::from(config.channels);
+    let max_buffered =
+        (recording.sample_rate as f32 * MAX_BUFFERED.as_secs_f32()) as usize;
+
+    let stream = device.build_input_stream(
+        config,
+        move |data: &[T], _: &InputCallbackInfo| {
+            let mut buffer = recording.buffer.lock().unwrap();
+
+            // Downmix to mono by averaging each frame's channels
+            for frame in data.chunks(channels) {
+                let sum: f32 = frame.iter().map(|s| s.to_f32()).sum();
+                buffer.push_back(sum / frame.len() as f32);
+            }
+
+            let excess = buffer.len().saturating_sub(max_buffered);
+            buffer.drain(..excess);
+        },
+        |e| log::warn!("Microphone error: {}", e),
+    )?;
+
+    Ok(stream)
+}
+
+/// A thread-safe handle to the mono audio recorded by a [`Microphone`].
+#[derive(Debug, Clone)]
+pub struct Recording {
+    buffer: Arc<Mutex<VecDeque<f32>>>,
+    sample_rate: u32,
+}
+
+impl Recording {
+    /// The microphone's native sample rate.
+    pub fn sample_rate(&self) -> u32 { self.sample_rate }
+
+    /// Wait until `duration` worth of audio has been recorded, then return
+    /// the most recent audio resampled to `sample_rate`.
+    ///
+    /// Everything recorded up to this point is consumed, so consecutive
+    /// clips never overlap.
+    pub fn clip(
+        &self,
+        sample_rate: u32,
+        duration: Duration,
+    ) -> Result<AudioClip, Error> {
+        anyhow::ensure!(sample_rate > 0, "The sample rate must be positive");
+
+        let required =
+            (self.sample_rate as f64 * duration.as_secs_f64()).ceil() as usize;
+        let output_len =
+            (sample_rate as f64 * duration.as_secs_f64()).ceil() as usize;
+        let deadline = Instant::now() + duration + TIMEOUT;
+
+        loop {
+            {
+                let mut buffer = self.buffer.lock().unwrap();
+
+                if buffer.len() >= required {
+                    let skip = buffer.len() - required;
+                    let samples: Vec<f32> =
+                        buffer.drain(..).skip(skip).collect();
+                    let samples = resample(&samples, output_len)
+                        .into_iter()
+                        .map(|s| s.to_i16())
+                        .collect();
+
+                    return Ok(AudioClip::mono(sample_rate, samples));
+                }
+            }
+
+            anyhow::ensure!(
+                Instant::now() < deadline,
+                "Timed out waiting for audio from the microphone"
+            );
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+}
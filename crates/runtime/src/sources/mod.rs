@@ -0,0 +1,12 @@
+//! Live devices a host can read capability data from, as an alternative to
+//! pre-recorded files.
+
+#[cfg(feature = "camera")]
+mod camera;
+#[cfg(feature = "microphone")]
+mod microphone;
+
+#[cfg(feature = "camera")]
+pub use self::camera::Camera;
+#[cfg(feature = "microphone")]
+pub use self::microphone::{Microphone, Recording};
@@ -1,16 +1,22 @@
-use std::{
+use alloc::{vec, vec::Vec};
+use core::{
     fmt::{self, Debug, Display, Formatter},
     num::NonZeroUsize,
+    str::FromStr,
 };
 
+use hotg_rune_core::{Dim, Quantization};
 use serde::ser::{Serialize, SerializeStruct};
 
+use crate::BufferPool;
+
 /// A n-dimension array of numbers.
 #[derive(Clone, PartialEq)]
 pub struct Tensor {
     element_type: ElementType,
     dimensions: Vec<NonZeroUsize>,
     buffer: Vec<u8>,
+    quantization: Option<Quantization>,
 }
 
 impl Tensor {
@@ -30,9 +36,34 @@ impl Tensor {
             element_type: E::ELEMENT_TYPE,
             dimensions,
             buffer,
+            quantization: None,
         }
     }
 
+    /// Create a new [`Tensor`], reusing a buffer from the [`BufferPool`] if
+    /// possible.
+    pub fn new_pooled<E>(
+        elements: &[E],
+        dimensions: &[usize],
+        pool: &mut BufferPool,
+    ) -> Self
+    where
+        E: TensorElement,
+    {
+        let bytes = E::to_bytes(elements);
+        let mut buffer = pool.acquire(bytes.len());
+        buffer.copy_from_slice(bytes);
+
+        let dimensions = dimensions
+            .iter()
+            .map(|&d| {
+                NonZeroUsize::new(d).expect("All dimensions must be nonzero")
+            })
+            .collect();
+
+        Tensor::new_raw(E::ELEMENT_TYPE, dimensions, buffer)
+    }
+
     pub fn new_raw(
         element_type: ElementType,
         dimensions: Vec<NonZeroUsize>,
@@ -54,6 +85,7 @@ impl Tensor {
             element_type,
             dimensions,
             buffer,
+            quantization: None,
         }
     }
 
@@ -73,12 +105,42 @@ impl Tensor {
     /// Get a reference to the tensor's dimensions.
     pub fn dimensions(&self) -> &[NonZeroUsize] { self.dimensions.as_ref() }
 
+    /// The parameters for interpreting a quantized tensor's elements, if
+    /// there are any.
+    pub fn quantization(&self) -> Option<&Quantization> {
+        self.quantization.as_ref()
+    }
+
+    /// Attach [`Quantization`] parameters to this tensor.
+    ///
+    /// # Panics
+    ///
+    /// This will panic if per-channel parameters don't match the tensor's
+    /// dimensions.
+    pub fn with_quantization(self, quantization: Quantization) -> Self {
+        let dimensions: Vec<Dim> =
+            self.dimensions.iter().map(|d| Dim::Fixed(d.get())).collect();
+
+        if let Err(e) = quantization.check(&dimensions) {
+            panic!("Unable to quantize a {} tensor: {}", self.shape(), e);
+        }
+
+        Tensor {
+            quantization: Some(quantization),
+            ..self
+        }
+    }
+
     /// Get a reference to the tensor's buffer.
     pub fn buffer(&self) -> &[u8] { &self.buffer }
 
     /// Get a mutable reference to the tensor's buffer.
     pub fn buffer_mut(&mut self) -> &mut [u8] { &mut self.buffer }
 
+    /// Consume the tensor, returning its buffer so it can be given back to a
+    /// [`BufferPool`].
+    pub fn into_buffer(self) -> Vec<u8> { self.buffer }
+
     pub fn shape(&self) -> impl Display + '_ {
         Shape::new(self.element_type(), self.dimensions())
     }
@@ -95,8 +157,155 @@ impl Tensor {
 
         E::from_bytes(&self.buffer)
     }
+
+    /// Borrow the tensor's elements as a [`TensorView`].
+    pub fn view<E>(&self) -> Option<TensorView<'_, E>>
+    where
+        E: TensorElement,
+    {
+        let elements = self.elements()?;
+        let dimensions = self.dimensions.iter().map(|d| d.get()).collect();
+
+        Some(TensorView {
+            elements,
+            dimensions,
+        })
+    }
+}
+
+/// A typed view over a tensor's elements which are stored somewhere else,
+/// typically a [`Tensor`]'s buffer or directly in a Rune's linear memory.
+///
+/// Unlike [`Tensor`], creating a [`TensorView`] never copies the elements.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TensorView<'a, E> {
+    elements: &'a [E],
+    dimensions: Vec<usize>,
+}
+
+impl<'a, E: TensorElement> TensorView<'a, E> {
+    /// Interpret some bytes as a tensor with the provided dimensions.
+    ///
+    /// This will fail if `bytes` isn't correctly aligned for `E` or contains
+    /// the wrong number of elements.
+    pub fn new(
+        bytes: &'a [u8],
+        dimensions: &[usize],
+    ) -> Result<Self, ViewError> {
+        check_length::<E>(bytes.len(), dimensions)?;
+        let elements = E::from_bytes(bytes)
+            .ok_or_else(|| ViewError::misaligned::<E>(bytes.as_ptr()))?;
+
+        Ok(TensorView {
+            elements,
+            dimensions: dimensions.to_vec(),
+        })
+    }
+
+    pub fn element_type(&self) -> ElementType { E::ELEMENT_TYPE }
+
+    pub fn dimensions(&self) -> &[usize] { &self.dimensions }
+
+    pub fn elements(&self) -> &'a [E] { self.elements }
+
+    /// Copy the elements into a new [`Tensor`].
+    pub fn to_tensor(&self) -> Tensor {
+        Tensor::new(self.elements, &self.dimensions)
+    }
+}
+
+/// The mutable version of [`TensorView`], useful for writing a capability's
+/// data straight into the buffer the Rune provided.
+#[derive(Debug, PartialEq)]
+pub struct TensorViewMut<'a, E> {
+    elements: &'a mut [E],
+    dimensions: Vec<usize>,
+}
+
+impl<'a, E: TensorElement> TensorViewMut<'a, E> {
+    /// Interpret some bytes as a tensor with the provided dimensions.
+    ///
+    /// This will fail if `bytes` isn't correctly aligned for `E` or contains
+    /// the wrong number of elements.
+    pub fn new(
+        bytes: &'a mut [u8],
+        dimensions: &[usize],
+    ) -> Result<Self, ViewError> {
+        check_length::<E>(bytes.len(), dimensions)?;
+        let ptr = bytes.as_ptr();
+        let elements = E::from_bytes_mut(bytes)
+            .ok_or_else(|| ViewError::misaligned::<E>(ptr))?;
+
+        Ok(TensorViewMut {
+            elements,
+            dimensions: dimensions.to_vec(),
+        })
+    }
+
+    pub fn element_type(&self) -> ElementType { E::ELEMENT_TYPE }
+
+    pub fn dimensions(&self) -> &[usize] { &self.dimensions }
+
+    pub fn elements(&self) -> &[E] { self.elements }
+
+    pub fn elements_mut(&mut self) -> &mut [E] { self.elements }
+}
+
+fn check_length<E: TensorElement>(
+    byte_length: usize,
+    dimensions: &[usize],
+) -> Result<(), ViewError> {
+    let expected = dimensions.iter().product::<usize>()
+        * E::ELEMENT_TYPE.byte_size();
+
+    if byte_length == expected {
+        Ok(())
+    } else {
+        Err(ViewError::LengthMismatch {
+            expected,
+            found: byte_length,
+        })
+    }
 }
 
+/// The reason a [`TensorView`] couldn't be created.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ViewError {
+    /// The buffer doesn't start at a multiple of the element's alignment.
+    Misaligned { address: usize, alignment: usize },
+    /// The buffer isn't the right size for the tensor's dimensions.
+    LengthMismatch { expected: usize, found: usize },
+}
+
+impl ViewError {
+    fn misaligned<E>(ptr: *const u8) -> Self {
+        ViewError::Misaligned {
+            address: ptr as usize,
+            alignment: core::mem::align_of::<E>(),
+        }
+    }
+}
+
+impl Display for ViewError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ViewError::Misaligned { address, alignment } => write!(
+                f,
+                "The buffer at {:#x} isn't aligned to a multiple of {} bytes",
+                address, alignment
+            ),
+            ViewError::LengthMismatch { expected, found } => write!(
+                f,
+                "Expected a {} byte buffer, but found {} bytes",
+                expected, found
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ViewError {}
+
 #[derive(Debug)]
 struct Shape<'a> {
     element_type: ElementType,
@@ -140,11 +349,13 @@ impl Debug for Tensor {
             element_type,
             dimensions,
             buffer: _,
+            quantization,
         } = self;
 
         f.debug_struct("Tensor")
             .field("element_type", element_type)
             .field("dimensions", dimensions)
+            .field("quantization", quantization)
             .finish()
     }
 }
@@ -156,17 +367,23 @@ impl Serialize for Serializable<'_> {
     where
         S: serde::Serializer,
     {
-        let mut ser = ser.serialize_struct("Tensor", 3)?;
+        let mut ser = ser.serialize_struct("Tensor", 4)?;
 
         let Tensor {
             element_type,
             dimensions,
+            quantization,
             ..
         } = self.0;
 
         ser.serialize_field("element-type", element_type)?;
         ser.serialize_field("dimensions", dimensions)?;
 
+        match quantization {
+            Some(q) => ser.serialize_field("quantization", q)?,
+            None => ser.skip_field("quantization")?,
+        }
+
         macro_rules! serialize {
             ($s:expr, $self:expr, $ty:ty) => {{
                 $s.serialize_field(
@@ -222,16 +439,16 @@ pub enum ElementType {
 impl ElementType {
     pub fn byte_size(self) -> usize {
         match self {
-            ElementType::U8 => std::mem::size_of::<u8>(),
-            ElementType::I8 => std::mem::size_of::<i8>(),
-            ElementType::U16 => std::mem::size_of::<u16>(),
-            ElementType::I16 => std::mem::size_of::<i16>(),
-            ElementType::U32 => std::mem::size_of::<u32>(),
-            ElementType::I32 => std::mem::size_of::<i32>(),
-            ElementType::F32 => std::mem::size_of::<f32>(),
-            ElementType::U64 => std::mem::size_of::<u64>(),
-            ElementType::I64 => std::mem::size_of::<i64>(),
-            ElementType::F64 => std::mem::size_of::<f64>(),
+            ElementType::U8 => core::mem::size_of::<u8>(),
+            ElementType::I8 => core::mem::size_of::<i8>(),
+            ElementType::U16 => core::mem::size_of::<u16>(),
+            ElementType::I16 => core::mem::size_of::<i16>(),
+            ElementType::U32 => core::mem::size_of::<u32>(),
+            ElementType::I32 => core::mem::size_of::<i32>(),
+            ElementType::F32 => core::mem::size_of::<f32>(),
+            ElementType::U64 => core::mem::size_of::<u64>(),
+            ElementType::I64 => core::mem::size_of::<i64>(),
+            ElementType::F64 => core::mem::size_of::<f64>(),
         }
     }
 }
@@ -253,12 +470,45 @@ impl Display for ElementType {
     }
 }
 
+impl FromStr for ElementType {
+    type Err = UnknownElementType;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "u8" => Ok(ElementType::U8),
+            "i8" => Ok(ElementType::I8),
+            "u16" => Ok(ElementType::U16),
+            "i16" => Ok(ElementType::I16),
+            "u32" => Ok(ElementType::U32),
+            "i32" => Ok(ElementType::I32),
+            "f32" => Ok(ElementType::F32),
+            "u64" => Ok(ElementType::U64),
+            "i64" => Ok(ElementType::I64),
+            "f64" => Ok(ElementType::F64),
+            _ => Err(UnknownElementType),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct UnknownElementType;
+
+impl Display for UnknownElementType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Unknown element type")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for UnknownElementType {}
+
 /// A numeric type that can be stored in a [`Tensor`].
 pub trait TensorElement: sealed::Sealed + Copy + 'static {
     const ELEMENT_TYPE: ElementType;
 
     fn to_bytes(slice: &[Self]) -> &[u8];
     fn from_bytes(bytes: &[u8]) -> Option<&[Self]>;
+    fn from_bytes_mut(bytes: &mut [u8]) -> Option<&mut [Self]>;
 }
 
 mod sealed {
@@ -274,9 +524,9 @@ macro_rules! impl_tensor_element {
                 // Safey: Always valid because primitive integers have no
                 // padding or references to other parts of memory.
                 unsafe {
-                    std::slice::from_raw_parts(
+                    core::slice::from_raw_parts(
                         slice.as_ptr().cast(),
-                        std::mem::size_of_val(slice),
+                        core::mem::size_of_val(slice),
                     )
                 }
             }
@@ -295,6 +545,19 @@ macro_rules! impl_tensor_element {
                     }
                 }
             }
+
+            fn from_bytes_mut(bytes: &mut [u8]) -> Option<&mut [Self]> {
+                // Safety: See from_bytes()
+                unsafe {
+                    let (head, elements, tail) = bytes.align_to_mut();
+
+                    if head.is_empty() && tail.is_empty() {
+                        Some(elements)
+                    } else {
+                        None
+                    }
+                }
+            }
         }
 
         impl sealed::Sealed for $type {}
@@ -311,3 +574,56 @@ impl_tensor_element!(f32 => ElementType::F32);
 impl_tensor_element!(u64 => ElementType::U64);
 impl_tensor_element!(i64 => ElementType::I64);
 impl_tensor_element!(f64 => ElementType::F64);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn view_a_tensor_without_copying() {
+        let tensor = Tensor::new(&[1.0_f32, 2.0, 3.0, 4.0], &[2, 2]);
+
+        let view = tensor.view::<f32>().unwrap();
+
+        assert_eq!(view.dimensions(), &[2, 2]);
+        assert_eq!(
+            view.elements().as_ptr().cast::<u8>(),
+            tensor.buffer().as_ptr()
+        );
+        assert_eq!(view.to_tensor(), tensor);
+        assert!(tensor.view::<u8>().is_none());
+    }
+
+    #[test]
+    fn write_through_a_mutable_view() {
+        let mut buffer = vec![0_u32; 3];
+        let length = core::mem::size_of_val(buffer.as_slice());
+        let bytes = unsafe {
+            core::slice::from_raw_parts_mut(buffer.as_mut_ptr().cast(), length)
+        };
+
+        let mut view = TensorViewMut::<u32>::new(bytes, &[3]).unwrap();
+        view.elements_mut().copy_from_slice(&[1, 2, 3]);
+
+        assert_eq!(buffer, [1, 2, 3]);
+    }
+
+    #[test]
+    fn views_check_alignment_and_length() {
+        let buffer = vec![0_u32; 4];
+        let bytes = u32::to_bytes(&buffer);
+
+        assert_eq!(
+            TensorView::<u32>::new(bytes, &[3]).unwrap_err(),
+            ViewError::LengthMismatch {
+                expected: 12,
+                found: 16
+            }
+        );
+        assert!(matches!(
+            TensorView::<u16>::new(&bytes[1..7], &[3]),
+            Err(ViewError::Misaligned { alignment: 2, .. })
+        ));
+        assert!(TensorView::<u16>::new(&bytes[2..8], &[3]).is_ok());
+    }
+}
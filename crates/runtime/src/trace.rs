@@ -0,0 +1,130 @@
+//! Recording and replaying the data delivered by capabilities.
+//!
+//! A trace is a flat sequence of frames, one per capability read, where each
+//! frame is the capability ID and the number of bytes (both little-endian
+//! `u32`s) followed by the capability's data, stored as a `u8` tensor using
+//! the [`hotg_rune_core::encoding`] format.
+
+use std::io::{ErrorKind, Read, Write};
+
+use anyhow::{Context, Error};
+use hotg_rune_core::{encoding, ElementType, Shape};
+
+/// What to do with the data that flows through the Rune's capabilities.
+pub(crate) enum CapabilityTrace {
+    /// Write every byte given to a capability to a trace.
+    Record(Box<dyn Write + Send>),
+    /// Ignore the input tensors and read capability data from a trace.
+    Replay(Box<dyn Read + Send>),
+}
+
+pub(crate) fn write_frame(
+    writer: &mut dyn Write,
+    capability_id: u32,
+    data: &[u8],
+) -> Result<(), Error> {
+    let dims = [data.len()];
+    let shape = Shape::new(ElementType::U8, &dims[..]);
+    let mut encoded = Vec::new();
+    encoding::encode(&shape, data, &mut encoded)?;
+
+    writer.write_all(&capability_id.to_le_bytes())?;
+    writer.write_all(&(encoded.len() as u32).to_le_bytes())?;
+    writer.write_all(&encoded)?;
+    writer.flush()?;
+
+    Ok(())
+}
+
+/// Read the next frame from a trace, returning `None` when the trace has been
+/// exhausted.
+pub(crate) fn read_frame(
+    reader: &mut dyn Read,
+) -> Result<Option<(u32, Vec<u8>)>, Error> {
+    let mut header = [0_u8; 8];
+    let mut filled = 0;
+
+    // Note: we can't use read_exact() here because only running out of data
+    // before the header starts is a clean EOF
+    while filled < header.len() {
+        match reader.read(&mut header[filled..]) {
+            Ok(0) if filled == 0 => return Ok(None),
+            Ok(0) => anyhow::bail!(
+                "The trace ended part way through a frame header ({} of {} \
+                 bytes)",
+                filled,
+                header.len()
+            ),
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    let capability_id =
+        u32::from_le_bytes([header[0], header[1], header[2], header[3]]);
+    let len = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+
+    let mut frame = vec![0; len as usize];
+    reader.read_exact(&mut frame).with_context(|| {
+        format!(
+            "The trace ended part way through a {} byte frame for capability \
+             {}",
+            len, capability_id
+        )
+    })?;
+
+    let (tensor, _) = encoding::decode(&frame).with_context(|| {
+        format!(
+            "Unable to decode the frame for capability {}",
+            capability_id
+        )
+    })?;
+
+    Ok(Some((capability_id, tensor.data.to_vec())))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn round_trip_frames() {
+        let mut buffer = Vec::new();
+        write_frame(&mut buffer, 1, b"Hello").unwrap();
+        write_frame(&mut buffer, 42, &[]).unwrap();
+
+        let mut reader = Cursor::new(buffer);
+
+        assert_eq!(
+            read_frame(&mut reader).unwrap(),
+            Some((1, b"Hello".to_vec()))
+        );
+        assert_eq!(read_frame(&mut reader).unwrap(), Some((42, Vec::new())));
+        assert_eq!(read_frame(&mut reader).unwrap(), None);
+    }
+
+    #[test]
+    fn truncated_headers_are_an_error() {
+        for len in 1..8 {
+            let mut buffer = Vec::new();
+            write_frame(&mut buffer, 1, b"Hello").unwrap();
+            buffer.truncate(len);
+
+            let err = read_frame(&mut Cursor::new(buffer)).unwrap_err();
+
+            assert!(err.to_string().contains("frame header"), "{}", err);
+        }
+    }
+
+    #[test]
+    fn truncated_frames_are_an_error() {
+        let mut buffer = Vec::new();
+        write_frame(&mut buffer, 1, b"Hello").unwrap();
+        buffer.truncate(buffer.len() - 1);
+
+        assert!(read_frame(&mut Cursor::new(buffer)).is_err());
+    }
+}
@@ -1,3 +1,4 @@
+use alloc::{string::ToString, vec::Vec};
 use core::marker::PhantomData;
 
 use hotg_rune_core::{Shape, Tensor, Value};
@@ -16,6 +17,19 @@ impl<T: Clone + Default> Capability<T> {
         unsafe {
             let id = intrinsics::request_capability(kind);
 
+            // Tell the runtime which shape we expect so it can check the
+            // data it provides (e.g. when combining several sources) before
+            // the Rune starts running
+            let key = "shape";
+            let descriptor = shape.to_string();
+            intrinsics::rune_capability_set_string_param(
+                id,
+                key.as_ptr(),
+                key.len() as u32,
+                descriptor.as_ptr(),
+                descriptor.len() as u32,
+            );
+
             Capability {
                 id,
                 shape,
@@ -25,9 +39,12 @@ impl<T: Clone + Default> Capability<T> {
     }
 
     pub fn generate(&mut self) -> Tensor<T> {
-        let output_dimensions = self.shape.dimensions();
+        let output_dimensions = self
+            .shape
+            .fixed_dimensions()
+            .expect("Capabilities must have a fixed output shape");
 
-        let mut buffer = Tensor::zeroed(output_dimensions.to_vec());
+        let mut buffer = Tensor::zeroed(output_dimensions);
 
         let elements = buffer.make_elements_mut();
         let byte_length = (elements.len() * core::mem::size_of::<T>()) as u32;
@@ -45,6 +62,29 @@ impl<T: Clone + Default> Capability<T> {
         buffer
     }
 
+    /// Open a stream for pulling data from this capability in chunks (e.g.
+    /// audio or high-rate IMU readings) instead of fixed-size snapshots.
+    ///
+    /// # Panics
+    ///
+    /// Zero-sized types (e.g. `()`) can't be streamed.
+    pub fn stream(&self) -> CapabilityStream<T> {
+        assert!(
+            core::mem::size_of::<T>() > 0,
+            "Zero-sized types can't be read from a capability stream",
+        );
+
+        unsafe {
+            let id = intrinsics::rune_capability_stream_open(self.id);
+
+            CapabilityStream {
+                id,
+                leftover: Vec::new(),
+                _type: PhantomData,
+            }
+        }
+    }
+
     pub fn set_parameter(
         &mut self,
         key: &str,
@@ -68,4 +108,234 @@ impl<T: Clone + Default> Capability<T> {
 
         self
     }
+
+    /// Set a parameter which isn't a number (e.g. a list of values), leaving
+    /// the runtime to parse it.
+    pub fn set_string_parameter(
+        &mut self,
+        key: &str,
+        value: &str,
+    ) -> &mut Self {
+        unsafe {
+            intrinsics::rune_capability_set_string_param(
+                self.id,
+                key.as_ptr(),
+                key.len() as u32,
+                value.as_ptr(),
+                value.len() as u32,
+            );
+        }
+
+        self
+    }
+}
+
+/// A stream of samples pulled from a [`Capability`] on demand.
+///
+/// The Rune decides how much data to ask for and when, so a slow pipeline
+/// naturally applies backpressure to the capability. The stream is closed
+/// when it is dropped.
+#[derive(Debug, PartialEq)]
+pub struct CapabilityStream<T> {
+    id: u32,
+    /// Bytes from the end of the previous read which didn't make up a whole
+    /// sample.
+    leftover: Vec<u8>,
+    _type: PhantomData<fn() -> T>,
+}
+
+impl<T: Copy> CapabilityStream<T> {
+    /// Read up to `buffer.len()` samples into the buffer, returning the
+    /// number of samples read.
+    ///
+    /// A return value of `0` means the stream has finished. If the stream
+    /// finishes part way through a sample, those bytes are discarded.
+    pub fn read(&mut self, buffer: &mut [T]) -> usize {
+        let id = self.id;
+
+        read_samples(&mut self.leftover, buffer, |bytes| unsafe {
+            intrinsics::rune_capability_stream_read(
+                id,
+                bytes.as_mut_ptr(),
+                bytes.len() as u32,
+            ) as usize
+        })
+    }
+}
+
+/// Fill `buffer` with whole samples using `read_bytes`, which may return any
+/// number of bytes (`0` meaning the end of the stream).
+///
+/// Bytes which don't make up a complete sample are kept in `leftover` and
+/// will be at the start of the next read.
+fn read_samples<T: Copy>(
+    leftover: &mut Vec<u8>,
+    buffer: &mut [T],
+    mut read_bytes: impl FnMut(&mut [u8]) -> usize,
+) -> usize {
+    let sample_size = core::mem::size_of::<T>();
+    debug_assert!(sample_size > 0);
+
+    if buffer.is_empty() {
+        return 0;
+    }
+
+    // Safety: samples are plain old data which the host writes to directly,
+    // so it's fine to treat the buffer as bytes.
+    let bytes = unsafe {
+        core::slice::from_raw_parts_mut(
+            buffer.as_mut_ptr().cast::<u8>(),
+            core::mem::size_of_val(buffer),
+        )
+    };
+
+    let mut filled = leftover.len();
+    bytes[..filled].copy_from_slice(leftover);
+    leftover.clear();
+
+    // Keep reading until we have at least one whole sample, otherwise a
+    // short read would look like the end of the stream.
+    while filled < sample_size {
+        let bytes_read = read_bytes(&mut bytes[filled..]);
+
+        if bytes_read == 0 {
+            return 0;
+        }
+
+        filled += bytes_read;
+    }
+
+    let samples = filled / sample_size;
+    leftover.extend_from_slice(&bytes[samples * sample_size..filled]);
+
+    samples
+}
+
+impl<T> Drop for CapabilityStream<T> {
+    fn drop(&mut self) {
+        unsafe {
+            intrinsics::rune_capability_stream_close(self.id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+
+    /// Create a `read_bytes` callback which hands out `data` in chunks of
+    /// the given sizes.
+    fn chunked<'a>(
+        mut data: &'a [u8],
+        chunk_sizes: &'a [usize],
+    ) -> impl FnMut(&mut [u8]) -> usize + 'a {
+        let mut chunk_sizes = chunk_sizes.iter();
+
+        move |buffer| {
+            let len = chunk_sizes
+                .next()
+                .copied()
+                .unwrap_or(usize::MAX)
+                .min(buffer.len())
+                .min(data.len());
+            let (head, tail) = data.split_at(len);
+            buffer[..len].copy_from_slice(head);
+            data = tail;
+            len
+        }
+    }
+
+    #[test]
+    fn whole_samples_are_passed_through() {
+        let data: Vec<u8> =
+            [1_u16, 2, 3].iter().flat_map(|s| s.to_ne_bytes()).collect();
+        let mut read_bytes = chunked(&data, &[]);
+        let mut leftover = Vec::new();
+        let mut buffer = [0_u16; 2];
+
+        assert_eq!(
+            read_samples(&mut leftover, &mut buffer, &mut read_bytes),
+            2
+        );
+        assert_eq!(buffer, [1, 2]);
+        assert_eq!(
+            read_samples(&mut leftover, &mut buffer, &mut read_bytes),
+            1
+        );
+        assert_eq!(buffer[0], 3);
+        assert_eq!(
+            read_samples(&mut leftover, &mut buffer, &mut read_bytes),
+            0
+        );
+        assert!(leftover.is_empty());
+    }
+
+    #[test]
+    fn partial_samples_are_kept_for_the_next_read() {
+        let samples = [1.0_f32, 2.0, 3.0];
+        let data: Vec<u8> =
+            samples.iter().flat_map(|s| s.to_ne_bytes()).collect();
+        // The host hands over 6 bytes (1.5 samples), then the rest
+        let mut read_bytes = chunked(&data, &[6, 6]);
+        let mut leftover = Vec::new();
+        let mut buffer = [0.0_f32; 3];
+
+        assert_eq!(
+            read_samples(&mut leftover, &mut buffer, &mut read_bytes),
+            1
+        );
+        assert_eq!(buffer[0], 1.0);
+        assert_eq!(leftover.len(), 2);
+
+        assert_eq!(
+            read_samples(&mut leftover, &mut buffer, &mut read_bytes),
+            2
+        );
+        assert_eq!(buffer[..2], [2.0, 3.0]);
+        assert!(leftover.is_empty());
+    }
+
+    #[test]
+    fn short_reads_arent_mistaken_for_the_end_of_the_stream() {
+        let data = 0x0102_0304_u32.to_ne_bytes();
+        let mut read_bytes = chunked(&data, &[1, 1, 1, 1]);
+        let mut leftover = Vec::new();
+        let mut buffer = [0_u32; 4];
+
+        assert_eq!(
+            read_samples(&mut leftover, &mut buffer, &mut read_bytes),
+            1
+        );
+        assert_eq!(buffer[0], 0x0102_0304);
+    }
+
+    #[test]
+    fn a_trailing_partial_sample_is_dropped() {
+        let data = vec![1_u8, 2, 3];
+        let mut read_bytes = chunked(&data, &[]);
+        let mut leftover = Vec::new();
+        let mut buffer = [0_u16; 4];
+
+        assert_eq!(
+            read_samples(&mut leftover, &mut buffer, &mut read_bytes),
+            1
+        );
+        assert_eq!(
+            read_samples(&mut leftover, &mut buffer, &mut read_bytes),
+            0
+        );
+    }
+
+    #[test]
+    fn reading_into_an_empty_buffer_does_nothing() {
+        let mut leftover = Vec::new();
+
+        let got = read_samples(&mut leftover, &mut [0_u8; 0], |_| {
+            panic!("The host shouldn't be called")
+        });
+
+        assert_eq!(got, 0);
+    }
 }
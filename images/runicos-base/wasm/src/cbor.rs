@@ -0,0 +1,205 @@
+//! A minimal [CBOR](https://www.rfc-editor.org/rfc/rfc8949) encoder for
+//! [`serde_json::Value`]s, so serial outputs can send a more compact binary
+//! encoding without pulling in another dependency.
+
+use alloc::vec::Vec;
+
+use serde_json::Value;
+
+const UNSIGNED: u8 = 0;
+const NEGATIVE: u8 = 1;
+const TEXT: u8 = 3;
+const ARRAY: u8 = 4;
+const MAP: u8 = 5;
+
+const FALSE: u8 = 0xf4;
+const TRUE: u8 = 0xf5;
+const NULL: u8 = 0xf6;
+const DOUBLE: u8 = 0xfb;
+
+/// Append the CBOR encoding of a [`Value`] to a buffer.
+pub(crate) fn encode(value: &Value, buffer: &mut Vec<u8>) {
+    match value {
+        Value::Null => buffer.push(NULL),
+        Value::Bool(false) => buffer.push(FALSE),
+        Value::Bool(true) => buffer.push(TRUE),
+        Value::Number(n) => {
+            if let Some(u) = n.as_u64() {
+                header(UNSIGNED, u, buffer);
+            } else if let Some(i) = n.as_i64() {
+                // CBOR stores a negative integer, n, as -1 - n
+                header(NEGATIVE, (-1 - i) as u64, buffer);
+            } else {
+                let f = n.as_f64().unwrap_or(f64::NAN);
+                buffer.push(DOUBLE);
+                buffer.extend_from_slice(&f.to_be_bytes());
+            }
+        },
+        Value::String(s) => text(s, buffer),
+        Value::Array(items) => {
+            header(ARRAY, items.len() as u64, buffer);
+            for item in items {
+                encode(item, buffer);
+            }
+        },
+        Value::Object(map) => {
+            header(MAP, map.len() as u64, buffer);
+            for (key, value) in map {
+                text(key, buffer);
+                encode(value, buffer);
+            }
+        },
+    }
+}
+
+fn text(s: &str, buffer: &mut Vec<u8>) {
+    header(TEXT, s.len() as u64, buffer);
+    buffer.extend_from_slice(s.as_bytes());
+}
+
+/// Write the initial byte for a data item and its argument, using the
+/// shortest encoding possible.
+fn header(major_type: u8, argument: u64, buffer: &mut Vec<u8>) {
+    let major_type = major_type << 5;
+
+    match argument {
+        0..=23 => buffer.push(major_type | argument as u8),
+        24..=0xff => {
+            buffer.push(major_type | 24);
+            buffer.push(argument as u8);
+        },
+        0x100..=0xffff => {
+            buffer.push(major_type | 25);
+            buffer.extend_from_slice(&(argument as u16).to_be_bytes());
+        },
+        0x1_0000..=0xffff_ffff => {
+            buffer.push(major_type | 26);
+            buffer.extend_from_slice(&(argument as u32).to_be_bytes());
+        },
+        _ => {
+            buffer.push(major_type | 27);
+            buffer.extend_from_slice(&argument.to_be_bytes());
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{string::String, vec};
+
+    use serde_json::json;
+
+    use super::*;
+
+    fn encoded(value: &Value) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        encode(value, &mut buffer);
+        buffer
+    }
+
+    /// Encode a value and make sure `serde_cbor` decodes the same value.
+    fn round_trip(value: Value) -> Vec<u8> {
+        let buffer = encoded(&value);
+        let decoded: Value = serde_cbor::from_slice(&buffer).unwrap();
+
+        assert_eq!(decoded, value);
+        buffer
+    }
+
+    #[test]
+    fn integers_use_the_shortest_encoding() {
+        let boundaries = [
+            0,
+            23,
+            24,
+            255,
+            256,
+            65535,
+            65536,
+            u64::from(u32::MAX),
+            u64::from(u32::MAX) + 1,
+            u64::MAX,
+        ];
+
+        for n in boundaries {
+            let buffer = round_trip(json!(n));
+
+            assert_eq!(buffer, serde_cbor::to_vec(&n).unwrap(), "{}", n);
+        }
+    }
+
+    #[test]
+    fn negative_integers() {
+        let boundaries = [
+            -1,
+            -24,
+            -25,
+            -256,
+            -257,
+            -65536,
+            -65537,
+            -i64::from(u32::MAX) - 1,
+            -i64::from(u32::MAX) - 2,
+            i64::MIN,
+        ];
+
+        for n in boundaries {
+            let buffer = round_trip(json!(n));
+
+            assert_eq!(buffer, serde_cbor::to_vec(&n).unwrap(), "{}", n);
+        }
+    }
+
+    #[test]
+    fn floats_are_always_doubles() {
+        for f in [0.5, -1.25, 1e300, f64::MIN_POSITIVE, f64::MAX] {
+            let buffer = round_trip(json!(f));
+
+            assert_eq!(buffer[0], DOUBLE);
+            assert_eq!(buffer[1..], f.to_be_bytes());
+        }
+    }
+
+    #[test]
+    fn string_lengths_use_the_shortest_encoding() {
+        for len in [0, 23, 24, 255, 256, 65535, 65536] {
+            let s: String = core::iter::repeat('a').take(len).collect();
+            let buffer = round_trip(json!(s));
+
+            assert_eq!(buffer, serde_cbor::to_vec(&s).unwrap(), "{}", len);
+        }
+    }
+
+    #[test]
+    fn nested_maps_and_arrays() {
+        let value = json!({
+            "name": "accelerometer",
+            "timestamp": 1_650_000_000_000_u64,
+            "valid": true,
+            "error": null,
+            "fields": {
+                "x": [1, -2, 300],
+                "y": { "nested": { "deeper": [] } },
+            },
+            "readings": [[0, 1], { "empty": {} }],
+        });
+
+        let buffer = round_trip(value.clone());
+
+        assert_eq!(buffer, serde_cbor::to_vec(&value).unwrap());
+    }
+
+    #[test]
+    fn maps_with_24_entries_need_a_length_byte() {
+        let map: serde_json::Map<String, Value> = (0..24)
+            .map(|i| (alloc::format!("{:02}", i), json!(i)))
+            .collect();
+        let value = Value::Object(map);
+
+        let buffer = round_trip(value.clone());
+
+        assert_eq!(buffer[..2], [MAP << 5 | 24, 24]);
+        assert_eq!(buffer, serde_cbor::to_vec(&value).unwrap());
+        assert_eq!(encoded(&json!(vec![0; 23]))[0], ARRAY << 5 | 23);
+    }
+}
@@ -0,0 +1,73 @@
+//! Reporting errors back to the runtime.
+//!
+//! The generated pipeline calls [`set_current_stage()`] before running each
+//! stage, so any error reported by [`report_error()`] (including panics) can
+//! tell the user *which* stage failed.
+
+use alloc::string::ToString;
+
+use hotg_rune_core::error::{display_chain, Error};
+
+use crate::intrinsics::{self, ErrorRecord};
+
+// Safety: Runes are single-threaded, so we can guarantee we'll never have
+// aliased mutation.
+static mut CURRENT_STAGE: &str = "";
+
+/// The broad category an error falls into.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[repr(u32)]
+pub enum ErrorKind {
+    Other = 0,
+    /// Something panicked.
+    Panic = 1,
+    /// A stage was given an argument or input it can't handle.
+    InvalidInput = 2,
+    /// Running a model failed.
+    Model = 3,
+}
+
+/// Record the name of the pipeline stage that is about to run.
+pub fn set_current_stage(name: &'static str) {
+    unsafe {
+        CURRENT_STAGE = name;
+    }
+}
+
+/// The name of the pipeline stage that is currently running, if any.
+pub fn current_stage() -> Option<&'static str> {
+    let stage = unsafe { CURRENT_STAGE };
+
+    if stage.is_empty() {
+        None
+    } else {
+        Some(stage)
+    }
+}
+
+pub(crate) fn clear_current_stage() { set_current_stage(""); }
+
+/// Tell the runtime that the current stage has failed, aborting the call.
+pub fn report_error(kind: ErrorKind, message: &str) -> ! {
+    let record = ErrorRecord {
+        kind: kind as u32,
+        stage: current_stage().unwrap_or_default().into(),
+        message: message.into(),
+    };
+
+    unsafe {
+        intrinsics::rune_error(&record);
+    }
+
+    // The runtime should have triggered a trap, but just in case...
+    #[cfg(target_arch = "wasm32")]
+    core::arch::wasm32::unreachable();
+    #[cfg(not(target_arch = "wasm32"))]
+    unreachable!("The runtime should have aborted the call");
+}
+
+/// Report an error to the runtime, including everything that caused it.
+pub fn report(kind: ErrorKind, error: &(dyn Error + 'static)) -> ! {
+    let message = display_chain(error).to_string();
+    report_error(kind, &message)
+}
@@ -0,0 +1,76 @@
+use core::fmt::{self, Write};
+
+use crate::intrinsics;
+
+const MODE_READ: u32 = 0;
+const MODE_APPEND: u32 = 1;
+
+/// A file on the host's filesystem.
+///
+/// The host needs to explicitly give the Rune access to each file, so
+/// opening a file that hasn't been allowed will abort at runtime.
+#[derive(Debug, PartialEq)]
+pub struct File {
+    id: u32,
+}
+
+impl File {
+    /// Open a file for reading.
+    pub fn open(path: &str) -> Self { File::open_with_mode(path, MODE_READ) }
+
+    /// Open a file for appending, creating it if it doesn't already exist.
+    pub fn append(path: &str) -> Self {
+        File::open_with_mode(path, MODE_APPEND)
+    }
+
+    fn open_with_mode(path: &str, mode: u32) -> Self {
+        unsafe {
+            let id = intrinsics::rune_file_open(
+                path.as_ptr(),
+                path.len() as u32,
+                mode,
+            );
+
+            File { id }
+        }
+    }
+
+    /// Read data into a buffer, returning the number of bytes read.
+    ///
+    /// A return value of `0` means the end of the file has been reached.
+    pub fn read(&mut self, buffer: &mut [u8]) -> usize {
+        unsafe {
+            intrinsics::rune_file_read(
+                self.id,
+                buffer.as_mut_ptr(),
+                buffer.len() as u32,
+            ) as usize
+        }
+    }
+
+    /// Append data to the end of the file.
+    pub fn write(&mut self, data: &[u8]) {
+        unsafe {
+            intrinsics::rune_file_write(
+                self.id,
+                data.as_ptr(),
+                data.len() as u32,
+            );
+        }
+    }
+}
+
+impl Write for File {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.write(s.as_bytes());
+        Ok(())
+    }
+}
+
+impl Drop for File {
+    fn drop(&mut self) {
+        unsafe {
+            intrinsics::rune_file_close(self.id);
+        }
+    }
+}
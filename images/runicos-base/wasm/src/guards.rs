@@ -77,6 +77,7 @@ impl Default for PipelineGuard {
 
 impl Drop for PipelineGuard {
     fn drop(&mut self) {
+        crate::error::clear_current_stage();
         log::debug!("Pipeline finished");
     }
 }
@@ -24,6 +24,35 @@ impl<'a> From<&'a str> for StringRef<'a> {
     }
 }
 
+/// A FFI-safe log record, passed to [`rune_log()`].
+///
+/// Optional fields (`module_path` and `file`) are represented by an empty
+/// string and a missing `line` is represented by `0`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[repr(C)]
+pub struct LogRecord<'a> {
+    /// The record's [`log::Level`], as an integer (`Error = 1` through
+    /// `Trace = 5`).
+    pub level: u32,
+    pub target: StringRef<'a>,
+    pub module_path: StringRef<'a>,
+    pub file: StringRef<'a>,
+    pub line: u32,
+    pub message: StringRef<'a>,
+}
+
+/// A FFI-safe error report, passed to [`rune_error()`].
+///
+/// An empty `stage` means the stage which failed isn't known.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[repr(C)]
+pub struct ErrorRecord<'a> {
+    /// The [`crate::error::ErrorKind`], as an integer.
+    pub kind: u32,
+    pub stage: StringRef<'a>,
+    pub message: StringRef<'a>,
+}
+
 extern "C" {
     /// Invoke the default model with the specified data.
     ///
@@ -81,6 +110,50 @@ extern "C" {
         output_len: u32,
     ) -> u32;
 
+    /// Ask the host for a model which isn't embedded in the Rune.
+    ///
+    /// This works just like [`rune_model_load()`], except the model is
+    /// identified by `name` and the host is responsible for providing the
+    /// model file and running inference with its own engine.
+    pub fn rune_model_load_external(
+        name: *const u8,
+        name_len: u32,
+        mimetype: *const u8,
+        mimetype_len: u32,
+        input_descriptors: *const StringRef<'_>,
+        input_len: u32,
+        output_descriptors: *const StringRef<'_>,
+        output_len: u32,
+    ) -> u32;
+
+    /// Load a model which was embedded in the Rune in encrypted form.
+    ///
+    /// This works just like [`rune_model_load()`], except the host decrypts
+    /// `model` before loading it.
+    pub fn rune_model_load_encrypted(
+        name: *const u8,
+        name_len: u32,
+        mimetype: *const u8,
+        mimetype_len: u32,
+        model: *const u8,
+        model_len: u32,
+        input_descriptors: *const StringRef<'_>,
+        input_len: u32,
+        output_descriptors: *const StringRef<'_>,
+        output_len: u32,
+    ) -> u32;
+
+    /// Ask the host which of a model's variants should be used.
+    ///
+    /// Returns `0` for the default model, or `i + 1` to use the `i`'th item
+    /// in `variants`.
+    pub fn rune_model_variant(
+        name: *const u8,
+        name_len: u32,
+        variants: *const StringRef<'_>,
+        variant_len: u32,
+    ) -> u32;
+
     /// Run inference using a model.
     ///
     /// The model's output will be written to the `output` buffers.
@@ -92,6 +165,71 @@ extern "C" {
         outputs: *mut *mut u8,
     ) -> u32;
 
+    /// Tell the host the model's inputs will have different dimensions to
+    /// the ones it was loaded with (e.g. variable-length audio or a bigger
+    /// batch size).
+    ///
+    /// This may change the model's output shapes, so they should be
+    /// re-read with [`rune_model_output_shape()`] afterwards.
+    pub fn rune_model_resize_inputs(
+        model_id: u32,
+        input_descriptors: *const StringRef<'_>,
+        input_len: u32,
+    ) -> u32;
+
+    /// Write the descriptor for one of a model's outputs (e.g.
+    /// `"f32[4, 10]"`) to `buffer`, returning its length.
+    ///
+    /// Nothing is written if the descriptor is longer than `buffer_len`.
+    pub fn rune_model_output_shape(
+        model_id: u32,
+        index: u32,
+        buffer: *mut u8,
+        buffer_len: u32,
+    ) -> u32;
+
+    /// Ask the host for a proc block which was compiled to a standalone
+    /// WebAssembly module instead of being embedded in the Rune.
+    ///
+    /// The return value is a unique identifier that can be used to refer to
+    /// the proc block.
+    pub fn rune_proc_block_load(name: *const u8, name_len: u32) -> u32;
+
+    /// Set one of a standalone proc block's parameters.
+    ///
+    /// Invalid parameters will trigger a trap and abort at runtime, although
+    /// this may not happen until the proc block is first used.
+    pub fn rune_proc_block_set_parameter(
+        proc_block_id: u32,
+        key: *const u8,
+        key_len: u32,
+        value: *const u8,
+        value_len: u32,
+    ) -> u32;
+
+    /// Run a standalone proc block, where `inputs` contains tensors encoded
+    /// using [`hotg_rune_core::encoding`].
+    ///
+    /// The return value is the length of the encoded outputs, which can be
+    /// retrieved with [`rune_proc_block_read_output()`].
+    pub fn rune_proc_block_transform(
+        proc_block_id: u32,
+        inputs: *const u8,
+        inputs_len: u32,
+    ) -> u32;
+
+    /// Copy the outputs from the most recent [`rune_proc_block_transform()`]
+    /// into `buffer`, which must be exactly the right length.
+    pub fn rune_proc_block_read_output(
+        proc_block_id: u32,
+        buffer: *mut u8,
+        buffer_len: u32,
+    ) -> u32;
+
+    /// Clear any state a standalone proc block accumulated during previous
+    /// transforms.
+    pub fn rune_proc_block_reset(proc_block_id: u32) -> u32;
+
     /// Load a model (as a byte buffer) into the runtime, telling it how many
     /// inputs and outputs there will be.
     ///
@@ -109,6 +247,14 @@ extern "C" {
     /// Write some text to the debug console.
     pub fn _debug(msg: *const u8, msg_len: u32) -> u32;
 
+    /// Send a structured log record to the runtime.
+    pub fn rune_log(record: *const LogRecord<'_>) -> u32;
+
+    /// Tell the runtime that the Rune has failed.
+    ///
+    /// This will trigger a trap and never return.
+    pub fn rune_error(record: *const ErrorRecord<'_>) -> u32;
+
     /// Request a capability with a particular type, yielding a unique handle
     /// that can be used to refer to the capability later on.
     ///
@@ -128,6 +274,18 @@ extern "C" {
         value_type: u32,
     ) -> u32;
 
+    /// Set a capability parameter to a UTF-8 string (e.g. a list like
+    /// `[0.485,0.456,0.406]`) which the runtime will parse itself.
+    ///
+    /// Invalid parameters will trigger a trap and abort at runtime.
+    pub fn rune_capability_set_string_param(
+        capability_id: u32,
+        key_ptr: *const u8,
+        key_len: u32,
+        value_ptr: *const u8,
+        value_len: u32,
+    ) -> u32;
+
     /// Ask the runtime to allocate a new output of the specified type.
     ///
     /// See [`hotg_rune_core::outputs`] to find out which outputs are available.
@@ -143,6 +301,10 @@ extern "C" {
 
     /// Ask a particular capability to fill the `buffer` with input.
     ///
+    /// The buffer is allocated by the Rune and the runtime will write directly
+    /// into it, so callers should pass the tensor's backing memory rather than
+    /// a temporary buffer.
+    ///
     /// Invalid parameters will trigger a trap and abort at runtime.
     pub fn request_provider_response(
         buffer: *mut u8,
@@ -150,6 +312,68 @@ extern "C" {
         capability_id: u32,
     ) -> u32;
 
+    /// Open a stream which can be used to pull data from a capability
+    /// incrementally instead of receiving a single fixed-size snapshot,
+    /// yielding a unique handle for the stream.
+    ///
+    /// Invalid parameters will trigger a trap and abort at runtime.
+    pub fn rune_capability_stream_open(capability_id: u32) -> u32;
+
+    /// Read up to `buffer_len` bytes from a capability stream, returning the
+    /// number of bytes written to the `buffer`.
+    ///
+    /// The runtime may provide fewer bytes than were asked for when there
+    /// isn't much data available. A return value of `0` means the stream has
+    /// finished.
+    pub fn rune_capability_stream_read(
+        stream_id: u32,
+        buffer: *mut u8,
+        buffer_len: u32,
+    ) -> u32;
+
+    /// Close a capability stream.
+    pub fn rune_capability_stream_close(stream_id: u32) -> u32;
+
+    /// The current wall-clock time, in nanoseconds since the Unix epoch.
+    pub fn rune_clock_realtime() -> u64;
+
+    /// The number of nanoseconds since the Rune was loaded.
+    ///
+    /// Unlike [`rune_clock_realtime()`], this will never go backwards.
+    pub fn rune_clock_monotonic() -> u64;
+
+    /// Let the runtime know a stage in the pipeline is about to run, so it
+    /// can time how long the stage takes.
+    pub fn rune_stage_started(name: *const u8, name_len: u32) -> u32;
+
+    /// Let the runtime know a stage in the pipeline has finished running.
+    pub fn rune_stage_finished(name: *const u8, name_len: u32) -> u32;
+
+    /// Open a file on the host, where `mode` is `0` for reading and `1` for
+    /// appending.
+    ///
+    /// The host must have explicitly allowed the Rune to access this file.
+    /// Anything else will trigger a trap and abort at runtime.
+    pub fn rune_file_open(path: *const u8, path_len: u32, mode: u32) -> u32;
+
+    /// Read data from a file into the provided buffer, returning the number
+    /// of bytes read (`0` means the end of the file has been reached).
+    pub fn rune_file_read(
+        file_id: u32,
+        buffer: *mut u8,
+        buffer_len: u32,
+    ) -> u32;
+
+    /// Append data to the end of a file.
+    pub fn rune_file_write(
+        file_id: u32,
+        data: *const u8,
+        data_len: u32,
+    ) -> u32;
+
+    /// Close a file.
+    pub fn rune_file_close(file_id: u32) -> u32;
+
     /// Open a named resource, returning a unique ID that can be used to .
     ///
     /// Invalid parameters will return a negative value.
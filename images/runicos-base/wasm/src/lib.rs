@@ -1,22 +1,35 @@
-#![cfg(target_arch = "wasm32")]
-#![no_std]
+// Note: The crate is also compiled natively when testing so the parts which
+// don't touch the host can be unit tested.
+#![cfg(any(target_arch = "wasm32", test))]
+#![cfg_attr(not(test), no_std)]
 // Note: The WebAssembly bindings need to provide alloc error handling.
-#![feature(core_intrinsics, lang_items, alloc_error_handler)]
+#![feature(
+    core_intrinsics,
+    lang_items,
+    alloc_error_handler,
+    panic_info_message
+)]
 
 extern crate alloc;
 
 pub mod allocator;
 mod buf_writer;
 mod capability;
+mod cbor;
+pub mod error;
+mod file;
 mod guards;
 pub mod intrinsics;
 mod logging;
 mod model;
+mod proc_block;
 mod resources;
 pub mod serial;
 mod stats_allocator;
 pub mod tensor_output;
+pub mod time;
 
+#[cfg(not(test))]
 use core::{alloc::Layout, fmt::Write, panic::PanicInfo};
 
 use dlmalloc::GlobalDlmalloc;
@@ -24,19 +37,23 @@ use dlmalloc::GlobalDlmalloc;
 use crate::allocator::Allocator;
 pub use crate::{
     buf_writer::BufWriter,
-    capability::Capability,
+    capability::{Capability, CapabilityStream},
+    error::{report_error, set_current_stage, ErrorKind},
+    file::File,
     guards::{PipelineGuard, SetupGuard},
     logging::Logger,
-    model::Model,
+    model::{model_variant, Model},
+    proc_block::ProcBlock,
     resources::{Resource, ResourceError},
-    serial::Serial,
+    serial::{Ble, Encoding, Mqtt, Serial},
     tensor_output::TensorOutput,
 };
 
-#[global_allocator]
+#[cfg_attr(not(test), global_allocator)]
 pub static ALLOCATOR: Allocator<GlobalDlmalloc> =
     Allocator::new(GlobalDlmalloc);
 
+#[cfg(not(test))]
 #[panic_handler]
 fn on_panic(info: &PanicInfo) -> ! {
     static mut PANICKING: bool = false;
@@ -55,10 +72,10 @@ fn on_panic(info: &PanicInfo) -> ! {
             log::error!("{}", info);
         }
 
-        // However, some times the runtime won't receive the log message (e.g.
-        // log level filtering or because an OOM in logging recursively
-        // triggered the panic handler). If that is the case, we still try to
-        // send *some* message to the runtime so they know the world is broken.
+        // Next, we report the panic as a structured error so the runtime can
+        // tell the user which stage failed. This also covers the times the
+        // runtime won't receive the log message (e.g. log level filtering or
+        // because an OOM in logging recursively triggered the panic handler).
 
         // Safety: We need our own buffer for panic messages in case the
         // allocator is fubar. Runes are single-threaded, so we can
@@ -66,16 +83,19 @@ fn on_panic(info: &PanicInfo) -> ! {
         static mut DEBUG_BUFFER: [u8; 1024] = [0; 1024];
         let mut w = BufWriter::new(&mut DEBUG_BUFFER);
 
-        if write!(w, "{}", info).is_ok() {
-            let written = w.written();
-            intrinsics::_debug(written.as_ptr(), written.len() as u32);
-        }
+        // Note: if the message doesn't fit we'll just send what we've got
+        let _ = match info.message() {
+            Some(msg) => write!(w, "{}", msg),
+            None => write!(w, "{}", info),
+        };
+        let message = core::str::from_utf8(w.written()).unwrap_or("panicked");
 
-        // And now we've done everything we can, we ungracefully crash.
-        core::arch::wasm32::unreachable()
+        // This tells the runtime which stage failed and aborts the call.
+        error::report_error(ErrorKind::Panic, message)
     }
 }
 
+#[cfg(not(test))]
 #[alloc_error_handler]
 fn on_alloc_error(layout: Layout) -> ! {
     panic!(
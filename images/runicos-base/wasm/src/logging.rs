@@ -1,13 +1,14 @@
-use alloc::borrow::Cow;
 use core::fmt::{Display, Write};
 
-use hotg_rune_core::SerializableRecord;
 use log::{Log, Metadata, Record};
 
-use crate::{intrinsics, BufWriter};
+use crate::{
+    intrinsics::{self, LogRecord, StringRef},
+    BufWriter,
+};
 
-/// An implementation of [`Log`] which uses [`intrinsics::_debug()`] to send
-/// log messages to the runtime.
+/// An implementation of [`Log`] which uses [`intrinsics::rune_log()`] to send
+/// structured log records to the runtime.
 #[derive(Debug, Clone)]
 #[non_exhaustive]
 pub struct Logger {}
@@ -16,12 +17,6 @@ impl Logger {
     pub const fn new() -> Self { Logger {} }
 }
 
-fn write(msg: &[u8]) {
-    unsafe {
-        intrinsics::_debug(msg.as_ptr(), msg.len() as u32);
-    }
-}
-
 impl Log for Logger {
     fn enabled(&self, _metadata: &Metadata<'_>) -> bool { true }
 
@@ -36,24 +31,17 @@ impl Log for Logger {
             Err(_) => return,
         };
 
-        let record = SerializableRecord {
-            level: r.level(),
-            message: Cow::Borrowed(message),
-            target: r.target().into(),
-            module_path: r.module_path().map(Cow::Borrowed),
-            file: r.file().map(Cow::Borrowed),
-            line: r.line(),
+        let record = LogRecord {
+            level: r.level() as u32,
+            target: StringRef::from(r.target()),
+            module_path: StringRef::from(r.module_path().unwrap_or("")),
+            file: StringRef::from(r.file().unwrap_or("")),
+            line: r.line().unwrap_or(0),
+            message: StringRef::from(message),
         };
 
-        let mut json_buffer = [0; 4096];
-
-        match serde_json_core::to_slice(&record, &mut json_buffer) {
-            Ok(bytes_written) => write(&json_buffer[..bytes_written]),
-            Err(_) => {
-                // Oh well, we tried
-                write(b"Unable to serialize a log message as JSON");
-                return;
-            },
+        unsafe {
+            intrinsics::rune_log(&record);
         }
     }
 
@@ -12,6 +12,8 @@ use crate::intrinsics::StringRef;
 pub struct Model<Input, Output> {
     id: u32,
     input_shapes: Vec<Shape<'static>>,
+    /// The input shapes the host's copy of the model currently expects.
+    resized_inputs: Vec<Shape<'static>>,
     output_shapes: Vec<Shape<'static>>,
     _types: PhantomData<fn(Input) -> Output>,
 }
@@ -23,39 +25,182 @@ impl<Input, Output> Model<Input, Output> {
         input_shapes: &[Shape<'static>],
         output_shapes: &[Shape<'static>],
     ) -> Self {
-        let id = unsafe {
-            let input_shape_descriptors: Vec<String> =
-                input_shapes.iter().map(|s| s.to_string()).collect();
-            let input_shape_descriptors: Vec<_> = input_shape_descriptors
-                .iter()
-                .map(|s| StringRef::from(s.as_str()))
-                .collect();
-            let output_shape_descriptors: Vec<String> =
-                output_shapes.iter().map(|s| s.to_string()).collect();
-            let output_shape_descriptors: Vec<_> = output_shape_descriptors
-                .iter()
-                .map(|s| StringRef::from(s.as_str()))
-                .collect();
+        let input_shape_descriptors = descriptors(input_shapes);
+        let inputs = string_refs(&input_shape_descriptors);
+        let output_shape_descriptors = descriptors(output_shapes);
+        let outputs = string_refs(&output_shape_descriptors);
 
+        let id = unsafe {
             crate::intrinsics::rune_model_load(
                 mimetype.as_ptr(),
                 mimetype.len() as u32,
                 model_data.as_ptr(),
                 model_data.len() as u32,
-                input_shape_descriptors.as_ptr(),
-                input_shape_descriptors.len() as u32,
-                output_shape_descriptors.as_ptr(),
-                output_shape_descriptors.len() as u32,
+                inputs.as_ptr(),
+                inputs.len() as u32,
+                outputs.as_ptr(),
+                outputs.len() as u32,
+            )
+        };
+
+        Model::new(id, input_shapes, output_shapes)
+    }
+
+    /// Use a model which the host provides and runs with its own engine,
+    /// rather than one embedded in the Rune.
+    pub fn load_external(
+        name: &str,
+        mimetype: &str,
+        input_shapes: &[Shape<'static>],
+        output_shapes: &[Shape<'static>],
+    ) -> Self {
+        let input_shape_descriptors = descriptors(input_shapes);
+        let inputs = string_refs(&input_shape_descriptors);
+        let output_shape_descriptors = descriptors(output_shapes);
+        let outputs = string_refs(&output_shape_descriptors);
+
+        let id = unsafe {
+            crate::intrinsics::rune_model_load_external(
+                name.as_ptr(),
+                name.len() as u32,
+                mimetype.as_ptr(),
+                mimetype.len() as u32,
+                inputs.as_ptr(),
+                inputs.len() as u32,
+                outputs.as_ptr(),
+                outputs.len() as u32,
             )
         };
 
+        Model::new(id, input_shapes, output_shapes)
+    }
+
+    /// Load a model whose weights were encrypted before being embedded in the
+    /// Rune, relying on the host to decrypt them.
+    pub fn load_encrypted(
+        name: &str,
+        mimetype: &str,
+        encrypted: &[u8],
+        input_shapes: &[Shape<'static>],
+        output_shapes: &[Shape<'static>],
+    ) -> Self {
+        let input_shape_descriptors = descriptors(input_shapes);
+        let inputs = string_refs(&input_shape_descriptors);
+        let output_shape_descriptors = descriptors(output_shapes);
+        let outputs = string_refs(&output_shape_descriptors);
+
+        let id = unsafe {
+            crate::intrinsics::rune_model_load_encrypted(
+                name.as_ptr(),
+                name.len() as u32,
+                mimetype.as_ptr(),
+                mimetype.len() as u32,
+                encrypted.as_ptr(),
+                encrypted.len() as u32,
+                inputs.as_ptr(),
+                inputs.len() as u32,
+                outputs.as_ptr(),
+                outputs.len() as u32,
+            )
+        };
+
+        Model::new(id, input_shapes, output_shapes)
+    }
+
+    fn new(
+        id: u32,
+        input_shapes: &[Shape<'static>],
+        output_shapes: &[Shape<'static>],
+    ) -> Self {
         Model {
             id,
             input_shapes: input_shapes.into(),
+            resized_inputs: input_shapes.into(),
             output_shapes: output_shapes.into(),
             _types: PhantomData,
         }
     }
+
+    /// Tell the host about new input dimensions (e.g. a longer audio clip or
+    /// bigger batch) and find out what the outputs will look like.
+    fn resize_inputs(&mut self, shapes: &[Shape<'_>]) {
+        let descriptors = descriptors(shapes);
+        let descriptors = string_refs(&descriptors);
+
+        unsafe {
+            crate::intrinsics::rune_model_resize_inputs(
+                self.id,
+                descriptors.as_ptr(),
+                descriptors.len() as u32,
+            );
+        }
+
+        self.resized_inputs = shapes.iter().map(|s| s.to_owned()).collect();
+
+        for (i, shape) in self.output_shapes.iter_mut().enumerate() {
+            *shape = output_shape(self.id, i as u32);
+        }
+    }
+}
+
+fn output_shape(model_id: u32, index: u32) -> Shape<'static> {
+    let mut buffer = [0_u8; 64];
+    let len = unsafe {
+        crate::intrinsics::rune_model_output_shape(
+            model_id,
+            index,
+            buffer.as_mut_ptr(),
+            buffer.len() as u32,
+        )
+    };
+
+    let descriptor = if len as usize <= buffer.len() {
+        String::from_utf8_lossy(&buffer[..len as usize]).into_owned()
+    } else {
+        let mut buffer = alloc::vec![0_u8; len as usize];
+        unsafe {
+            crate::intrinsics::rune_model_output_shape(
+                model_id,
+                index,
+                buffer.as_mut_ptr(),
+                buffer.len() as u32,
+            );
+        }
+        String::from_utf8_lossy(&buffer).into_owned()
+    };
+
+    descriptor.parse().unwrap_or_else(|e| {
+        panic!(
+            "The host gave an invalid output shape, {:?}: {}",
+            descriptor, e
+        )
+    })
+}
+
+/// Ask the host which variant of a model it would like to use, returning `0`
+/// for the default model or `i + 1` for the `i`'th item in `variants`.
+pub fn model_variant(name: &str, variants: &[&str]) -> usize {
+    let variants: Vec<StringRef<'_>> =
+        variants.iter().map(|v| StringRef::from(*v)).collect();
+
+    let index = unsafe {
+        crate::intrinsics::rune_model_variant(
+            name.as_ptr(),
+            name.len() as u32,
+            variants.as_ptr(),
+            variants.len() as u32,
+        )
+    };
+
+    index as usize
+}
+
+fn descriptors(shapes: &[Shape<'_>]) -> Vec<String> {
+    shapes.iter().map(|s| s.to_string()).collect()
+}
+
+fn string_refs(strings: &[String]) -> Vec<StringRef<'_>> {
+    strings.iter().map(|s| StringRef::from(s.as_str())).collect()
 }
 
 impl<Input, Output> Model<Input, Output>
@@ -64,11 +209,30 @@ where
     Output: TensorListMut,
 {
     pub fn transform(&mut self, inputs: Input) -> Output {
-        assert_eq!(
-            (&inputs).shape_list().as_ref(),
-            &self.input_shapes,
+        let shapes = (&inputs).shape_list();
+        let shapes = shapes.as_ref();
+        assert!(
+            shapes.len() == self.input_shapes.len()
+                && shapes
+                    .iter()
+                    .zip(&self.input_shapes)
+                    .all(|(actual, expected)| {
+                        if expected.is_fixed() {
+                            // the host will resize the model to match
+                            actual.element_type() == expected.element_type()
+                        } else {
+                            expected.accepts(actual)
+                        }
+                    }),
             "The input had the wrong shape",
         );
+
+        if shapes != self.resized_inputs.as_slice() {
+            // The input dimensions changed (e.g. a bigger batch), so the
+            // host needs to resize the model's tensors
+            self.resize_inputs(shapes);
+        }
+
         let mut outputs = <Output>::new_tensors(&self.output_shapes);
 
         unsafe {
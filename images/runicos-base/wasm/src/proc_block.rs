@@ -0,0 +1,79 @@
+use alloc::vec::Vec;
+
+use hotg_rune_core::encoding::TensorTuple;
+
+/// A proc block which was compiled to a standalone WebAssembly module and is
+/// run by the host.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProcBlock {
+    id: u32,
+}
+
+impl ProcBlock {
+    /// Ask the host for the proc block with this name.
+    pub fn load_external(name: &str) -> Self {
+        let id = unsafe {
+            crate::intrinsics::rune_proc_block_load(
+                name.as_ptr(),
+                name.len() as u32,
+            )
+        };
+
+        ProcBlock { id }
+    }
+
+    pub fn set_parameter(&mut self, key: &str, value: &str) {
+        unsafe {
+            crate::intrinsics::rune_proc_block_set_parameter(
+                self.id,
+                key.as_ptr(),
+                key.len() as u32,
+                value.as_ptr(),
+                value.len() as u32,
+            );
+        }
+    }
+
+    pub fn transform<Input, Output>(&mut self, inputs: Input) -> Output
+    where
+        Input: TensorTuple,
+        Output: TensorTuple,
+    {
+        let mut buffer = Vec::new();
+        inputs
+            .encode_tensors(&mut buffer)
+            .expect("Unable to encode the proc block's inputs");
+
+        let len = unsafe {
+            crate::intrinsics::rune_proc_block_transform(
+                self.id,
+                buffer.as_ptr(),
+                buffer.len() as u32,
+            )
+        };
+
+        buffer.clear();
+        buffer.resize(len as usize, 0);
+
+        unsafe {
+            crate::intrinsics::rune_proc_block_read_output(
+                self.id,
+                buffer.as_mut_ptr(),
+                buffer.len() as u32,
+            );
+        }
+
+        let (outputs, rest) = Output::decode_tensors(&buffer)
+            .expect("Unable to decode the proc block's outputs");
+        assert!(rest.is_empty(), "The proc block returned extra outputs");
+
+        outputs
+    }
+
+    /// Clear any state from previous transforms.
+    pub fn reset(&mut self) {
+        unsafe {
+            crate::intrinsics::rune_proc_block_reset(self.id);
+        }
+    }
+}
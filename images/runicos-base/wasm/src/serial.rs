@@ -1,9 +1,9 @@
-use alloc::vec::Vec;
+use alloc::{string::String, vec::Vec};
 use core::{cell::RefCell, fmt::Debug};
 
 use hotg_rune_core::{outputs, AsElementType, ElementType, Tensor};
 use serde::ser::{Serialize, SerializeMap, Serializer};
-use serde_json::Value;
+use serde_json::{Map, Value};
 
 use crate::intrinsics;
 
@@ -12,22 +12,60 @@ use crate::intrinsics;
 pub struct Serial {
     id: u32,
     buffer: RefCell<Vec<u8>>,
+    fields: Option<Vec<&'static str>>,
+    encoding: Encoding,
+}
+
+/// How messages are encoded before being sent to the runtime.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Encoding {
+    Json,
+    /// [CBOR](https://cbor.io/), a binary format with the same data model as
+    /// JSON.
+    Cbor,
+}
+
+impl Default for Encoding {
+    fn default() -> Self { Encoding::Json }
 }
 
 impl Serial {
     const INITIAL_BUFFER_SIZE: usize = 1024;
 
-    pub fn new() -> Self {
+    pub fn new() -> Self { Serial::with_output_type(outputs::SERIAL) }
+
+    /// Create an output which sends JSON-encoded messages to the runtime,
+    /// using `output_type` to tell the runtime where they should go.
+    fn with_output_type(output_type: u32) -> Self {
         unsafe {
             Serial {
-                id: intrinsics::request_output(outputs::SERIAL),
+                id: intrinsics::request_output(output_type),
                 buffer: RefCell::new(
                     alloc::vec![0; Serial::INITIAL_BUFFER_SIZE],
                 ),
+                fields: None,
+                encoding: Encoding::default(),
             }
         }
     }
 
+    /// Send each message as a structured record, where the tensors passed to
+    /// [`Serial::consume()`] are named using `fields` (in order).
+    ///
+    /// Records also contain a schema describing each field's type and
+    /// dimensions, plus a `timestamp` field with the number of milliseconds
+    /// since the Unix epoch (unless one of the `fields` is already called
+    /// `timestamp`).
+    pub fn with_fields(mut self, fields: &[&'static str]) -> Self {
+        self.fields = Some(fields.to_vec());
+        self
+    }
+
+    pub fn with_encoding(mut self, encoding: Encoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
     fn log(&self, msg: &[u8]) {
         unsafe {
             intrinsics::consume_output(self.id, msg.as_ptr(), msg.len() as u32);
@@ -59,19 +97,179 @@ impl Serial {
         }
     }
 
+    fn consume_cbor(&self, msg: &Value) {
+        let mut buffer = Vec::with_capacity(Serial::INITIAL_BUFFER_SIZE);
+        crate::cbor::encode(msg, &mut buffer);
+        self.log(&buffer);
+    }
+
     pub fn consume<T>(&mut self, input: T)
     where
         T: IntoSerialMessage,
     {
-        let msg = input.into_serial_message(self.id);
-        self.consume_serializable(&msg);
+        let mut msg = input.into_serial_message(self.id);
+
+        if let Some(fields) = &self.fields {
+            msg = record(self.id, fields, msg);
+        }
+
+        match self.encoding {
+            Encoding::Json => self.consume_serializable(&msg),
+            Encoding::Cbor => self.consume_cbor(&msg),
+        }
     }
 }
 
+/// Turn the messages for one or more tensors into a single record with named
+/// fields and a schema.
+fn record(channel: u32, fields: &[&'static str], msg: Value) -> Value {
+    let messages = match msg {
+        Value::Array(messages) => messages,
+        other => alloc::vec![other],
+    };
+
+    assert_eq!(
+        messages.len(),
+        fields.len(),
+        "The serial output was given {} tensors but has {} fields",
+        messages.len(),
+        fields.len(),
+    );
+
+    let mut schema = Vec::new();
+    let mut values = Map::new();
+
+    for (&name, message) in fields.iter().zip(messages) {
+        let (field, value) = record_field(name, message);
+        schema.push(field);
+        values.insert(String::from(name), value);
+    }
+
+    if !fields.contains(&"timestamp") {
+        let timestamp = crate::time::now().as_millis() as u64;
+        schema.push(schema_field(
+            "timestamp",
+            ElementType::U64.rune_name(),
+            Value::Array(alloc::vec![Value::from(1)]),
+        ));
+        values.insert(String::from("timestamp"), Value::from(timestamp));
+    }
+
+    let mut record = Map::new();
+    record.insert(String::from("type_name"), Value::from("record"));
+    record.insert(String::from("channel"), Value::from(channel));
+    record.insert(String::from("schema"), Value::Array(schema));
+    record.insert(String::from("fields"), Value::Object(values));
+
+    Value::Object(record)
+}
+
+/// Get a field's schema and value from the message for a single tensor,
+/// unwrapping tensors with a single element into scalars.
+fn record_field(name: &'static str, message: Value) -> (Value, Value) {
+    let mut message = match message {
+        Value::Object(map) => map,
+        other => panic!("Expected a tensor message but found {:?}", other),
+    };
+
+    let type_name = message.remove("type_name").unwrap_or(Value::Null);
+    let dimensions = message
+        .remove("dimensions")
+        .unwrap_or_else(|| Value::Array(alloc::vec![Value::from(1)]));
+
+    let value = match message.remove("elements") {
+        Some(Value::Array(mut elements)) if elements.len() == 1 => {
+            elements.remove(0)
+        },
+        Some(elements) => elements,
+        None => message.remove("string").unwrap_or(Value::Null),
+    };
+
+    let field = match type_name {
+        Value::String(type_name) => schema_field(name, &type_name, dimensions),
+        _ => schema_field(name, "unknown", dimensions),
+    };
+
+    (field, value)
+}
+
+fn schema_field(name: &str, type_name: &str, dimensions: Value) -> Value {
+    let mut field = Map::new();
+    field.insert(String::from("name"), Value::from(name));
+    field.insert(String::from("type"), Value::from(type_name));
+    field.insert(String::from("dimensions"), dimensions);
+    Value::Object(field)
+}
+
 impl Default for Serial {
     fn default() -> Self { Serial::new() }
 }
 
+/// An output which publishes JSON-encoded messages to an MQTT broker.
+///
+/// Messages use the same format as [`Serial`], with the broker and topic
+/// being configured by the host.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Mqtt(Serial);
+
+impl Mqtt {
+    pub fn new() -> Self { Mqtt(Serial::with_output_type(outputs::MQTT)) }
+
+    /// Publish structured records instead of raw tensors (see
+    /// [`Serial::with_fields()`]).
+    pub fn with_fields(self, fields: &[&'static str]) -> Self {
+        Mqtt(self.0.with_fields(fields))
+    }
+
+    pub fn with_encoding(self, encoding: Encoding) -> Self {
+        Mqtt(self.0.with_encoding(encoding))
+    }
+
+    pub fn consume<T>(&mut self, input: T)
+    where
+        T: IntoSerialMessage,
+    {
+        self.0.consume(input);
+    }
+}
+
+impl Default for Mqtt {
+    fn default() -> Self { Mqtt::new() }
+}
+
+/// An output which sends JSON-encoded messages to a Bluetooth Low Energy
+/// characteristic.
+///
+/// Messages use the same format as [`Serial`], with the device and
+/// characteristic being configured by the host.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Ble(Serial);
+
+impl Ble {
+    pub fn new() -> Self { Ble(Serial::with_output_type(outputs::BLE)) }
+
+    /// Send structured records instead of raw tensors (see
+    /// [`Serial::with_fields()`]).
+    pub fn with_fields(self, fields: &[&'static str]) -> Self {
+        Ble(self.0.with_fields(fields))
+    }
+
+    pub fn with_encoding(self, encoding: Encoding) -> Self {
+        Ble(self.0.with_encoding(encoding))
+    }
+
+    pub fn consume<T>(&mut self, input: T)
+    where
+        T: IntoSerialMessage,
+    {
+        self.0.consume(input);
+    }
+}
+
+impl Default for Ble {
+    fn default() -> Self { Ble::new() }
+}
+
 /// An intermediate trait which lets you convert from some input into a
 /// serializable form suitable for sending back to the Rune runtime.
 pub trait IntoSerialMessage {
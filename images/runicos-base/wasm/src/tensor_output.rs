@@ -1,6 +1,11 @@
-use alloc::{string::ToString, vec::Vec};
+use alloc::vec::Vec;
 
-use hotg_rune_core::{outputs, AsElementType, Tensor};
+use hotg_rune_core::{
+    encoding::{self, Element},
+    outputs, Tensor,
+};
+
+use crate::error::ErrorKind;
 
 pub struct TensorOutput {
     id: u32,
@@ -41,17 +46,12 @@ pub trait Writable {
 
 impl<E> Writable for Tensor<E>
 where
-    E: AsElementType,
+    E: Element,
 {
     fn encode(&self, buffer: &mut Vec<u8>) {
-        let shape = self.shape().to_string();
-        let shape_len = (shape.len() as u32).to_le_bytes();
-        buffer.extend(&shape_len);
-        buffer.extend(shape.as_bytes());
-
-        let (ptr, len) = self.as_ptr_and_byte_length();
-        let data = unsafe { core::slice::from_raw_parts(ptr, len) };
-        buffer.extend(data);
+        if let Err(e) = encoding::encode_tensor(self, buffer) {
+            crate::error::report(ErrorKind::Other, &e);
+        }
     }
 }
 
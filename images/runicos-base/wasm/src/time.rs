@@ -0,0 +1,37 @@
+//! Reading the host's clocks and timing the pipeline.
+//!
+//! The clocks are useful for proc-blocks that need to do things like
+//! debouncing or rate-limiting, while the generated pipeline calls
+//! [`stage_started()`] and [`stage_finished()`] around each stage so the
+//! runtime can report how long every stage took.
+
+use core::time::Duration;
+
+use crate::intrinsics;
+
+/// The current wall-clock time, as a duration since the Unix epoch.
+///
+/// This may jump forwards or backwards if the host's clock is adjusted, so
+/// prefer [`monotonic()`] when measuring elapsed time.
+pub fn now() -> Duration {
+    unsafe { Duration::from_nanos(intrinsics::rune_clock_realtime()) }
+}
+
+/// The time elapsed since the Rune was loaded.
+pub fn monotonic() -> Duration {
+    unsafe { Duration::from_nanos(intrinsics::rune_clock_monotonic()) }
+}
+
+/// Start timing a stage in the pipeline.
+pub fn stage_started(name: &str) {
+    unsafe {
+        intrinsics::rune_stage_started(name.as_ptr(), name.len() as u32);
+    }
+}
+
+/// Finish timing a stage started with [`stage_started()`].
+pub fn stage_finished(name: &str) {
+    unsafe {
+        intrinsics::rune_stage_finished(name.as_ptr(), name.len() as u32);
+    }
+}
@@ -23,12 +23,16 @@ pub(crate) fn rune_output(
 
     cmd.arg("run").arg(format!("{}.rune", full_name.name));
 
-    for entry in directory
+    // Note: inputs are passed in alphabetical order so tests with several
+    // sources for the same capability are deterministic
+    let mut filenames = directory
         .read_dir()
         .context("Unable to read the directory")?
-    {
-        let entry = entry?;
-        let filename = entry.path();
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    filenames.sort();
+
+    for filename in filenames {
         let extension = match filename.extension().and_then(|ext| ext.to_str())
         {
             Some(ext) => ext,
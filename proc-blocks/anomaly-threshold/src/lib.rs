@@ -0,0 +1,341 @@
+//! A stateful proc block for anomaly detection, typically used after an
+//! autoencoder in a predictive maintenance pipeline.
+//!
+//! Each input is reduced to a single score (the mean of its elements, so a
+//! per-feature reconstruction error works as well as a scalar one) which is
+//! compared against the rolling mean and standard deviation of recent
+//! scores. An anomaly starts when a score is more than `threshold` standard
+//! deviations above the mean and, to avoid flickering, only ends once the
+//! score drops to `release` standard deviations or less.
+//!
+//! The proc block has two outputs, a `[1]` flag which is `1` while an anomaly
+//! is in progress, and the `[1]` z-score that was compared against the
+//! thresholds.
+//!
+//! ```yaml
+//! anomaly:
+//!   proc-block: "hotg-ai/rune#proc-blocks/anomaly-threshold"
+//!   inputs:
+//!     - reconstruction_error
+//!   outputs:
+//!     - type: U8
+//!       dimensions: [1]
+//!     - type: F32
+//!       dimensions: [1]
+//!   args:
+//!     window: 200
+//!     warm-up: 20
+//!     threshold: 3.0
+//!     release: 2.0
+//! ```
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::collections::VecDeque;
+
+use hotg_rune_proc_blocks::{ProcBlock, Tensor, Transform};
+
+/// Flag scores which are unusually high compared to recent history.
+///
+/// Scores are only added to the rolling statistics while there is no
+/// anomaly, so a long-running fault doesn't gradually become "normal".
+#[derive(Debug, Clone, PartialEq, ProcBlock)]
+#[transform(inputs = [f32; _], outputs = ([u8; 1], [f32; 1]))]
+pub struct AnomalyThreshold {
+    /// The number of recent scores used to calculate the rolling mean and
+    /// standard deviation.
+    #[proc_block(validate = is_positive)]
+    window: usize,
+    /// The number of scores to collect before anything can be flagged as an
+    /// anomaly.
+    #[proc_block(validate = is_positive)]
+    warm_up: usize,
+    /// How many standard deviations above the mean a score must be to start
+    /// an anomaly.
+    #[proc_block(validate = is_non_negative)]
+    threshold: f32,
+    /// An anomaly ends once the score is this many standard deviations above
+    /// the mean or less. Must not be larger than `threshold`.
+    #[proc_block(validate = is_non_negative)]
+    release: f32,
+    /// The scores used for the rolling statistics.
+    #[proc_block(skip)]
+    history: VecDeque<f32>,
+    /// Is there an anomaly in progress?
+    #[proc_block(skip)]
+    active: bool,
+}
+
+impl AnomalyThreshold {
+    /// Forget all previous scores and end any anomaly in progress.
+    pub fn reset(&mut self) {
+        self.history.clear();
+        self.active = false;
+    }
+
+    fn update(&mut self, score: f32) -> (bool, f32) {
+        assert!(
+            self.warm_up <= self.window,
+            "The warm-up ({}) can't be longer than the window ({})",
+            self.warm_up,
+            self.window,
+        );
+        assert!(
+            self.release <= self.threshold,
+            "The release ({}) can't be larger than the threshold ({})",
+            self.release,
+            self.threshold,
+        );
+
+        let z_score = if self.history.len() >= self.warm_up {
+            let (mean, std_dev) = mean_and_std_dev(&self.history);
+            z_score(score, mean, std_dev)
+        } else {
+            0.0
+        };
+
+        self.active = if self.history.len() < self.warm_up {
+            false
+        } else if self.active {
+            z_score > self.release
+        } else {
+            z_score > self.threshold
+        };
+
+        if !self.active {
+            self.history.push_back(score);
+
+            while self.history.len() > self.window {
+                self.history.pop_front();
+            }
+        }
+
+        (self.active, z_score)
+    }
+}
+
+impl Default for AnomalyThreshold {
+    fn default() -> Self {
+        AnomalyThreshold {
+            window: 100,
+            warm_up: 10,
+            threshold: 3.0,
+            release: 2.0,
+            history: VecDeque::new(),
+            active: false,
+        }
+    }
+}
+
+impl Transform<Tensor<f32>> for AnomalyThreshold {
+    type Output = (Tensor<u8>, Tensor<f32>);
+
+    fn transform(&mut self, input: Tensor<f32>) -> Self::Output {
+        let errors = input.elements();
+        assert!(!errors.is_empty(), "No reconstruction errors were provided");
+        let score = errors.iter().sum::<f32>() / errors.len() as f32;
+
+        let (active, z_score) = self.update(score);
+
+        (
+            Tensor::new_vector(alloc::vec![active as u8]),
+            Tensor::new_vector(alloc::vec![z_score]),
+        )
+    }
+}
+
+fn mean_and_std_dev(values: &VecDeque<f32>) -> (f32, f32) {
+    let n = values.len() as f32;
+    let mean = values.iter().sum::<f32>() / n;
+    let variance =
+        values.iter().map(|&x| (x - mean) * (x - mean)).sum::<f32>() / n;
+
+    (mean, libm::sqrtf(variance))
+}
+
+fn z_score(score: f32, mean: f32, std_dev: f32) -> f32 {
+    if std_dev > 0.0 {
+        (score - mean) / std_dev
+    } else if score > mean {
+        // Every previous score was identical, so any increase is infinitely
+        // unlikely
+        f32::INFINITY
+    } else {
+        0.0
+    }
+}
+
+fn is_positive(value: &usize) -> Result<(), &'static str> {
+    if *value > 0 {
+        Ok(())
+    } else {
+        Err("must be greater than zero")
+    }
+}
+
+fn is_non_negative(value: &f32) -> Result<(), &'static str> {
+    if *value >= 0.0 {
+        Ok(())
+    } else {
+        Err("must not be negative")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+
+    fn anomaly_threshold(warm_up: &str) -> AnomalyThreshold {
+        let mut proc_block = AnomalyThreshold::default();
+        proc_block.set_warm_up(warm_up).unwrap();
+        proc_block
+    }
+
+    fn score(proc_block: &mut AnomalyThreshold, value: f32) -> (u8, f32) {
+        let (active, z_score) =
+            proc_block.transform(Tensor::new_vector(vec![value]));
+
+        (active.elements()[0], z_score.elements()[0])
+    }
+
+    #[test]
+    fn nothing_is_flagged_while_warming_up() {
+        let mut proc_block = anomaly_threshold("3");
+
+        assert_eq!(score(&mut proc_block, 1.0), (0, 0.0));
+        assert_eq!(score(&mut proc_block, 100.0), (0, 0.0));
+        assert_eq!(score(&mut proc_block, 1000.0), (0, 0.0));
+    }
+
+    #[test]
+    fn hysteresis() {
+        let mut proc_block = anomaly_threshold("4");
+        for &value in &[1.0, 3.0, 1.0, 3.0] {
+            score(&mut proc_block, value);
+        }
+
+        // The mean is 2 and standard deviation is 1
+        assert_eq!(score(&mut proc_block, 7.0), (1, 5.0));
+        assert_eq!(score(&mut proc_block, 4.5), (1, 2.5));
+        // Anomalies aren't added to the rolling statistics
+        assert_eq!(proc_block.history, vec![1.0, 3.0, 1.0, 3.0]);
+        assert_eq!(score(&mut proc_block, 3.5), (0, 1.5));
+        assert_eq!(proc_block.history, vec![1.0, 3.0, 1.0, 3.0, 3.5]);
+    }
+
+    #[test]
+    fn the_score_is_the_mean_error() {
+        let mut proc_block = anomaly_threshold("1");
+        score(&mut proc_block, 1.0);
+
+        let (active, z_score) =
+            proc_block.transform(Tensor::new_vector(vec![0.0_f32, 1.0, 2.0]));
+
+        assert_eq!(active.elements(), &[0]);
+        assert_eq!(z_score.elements(), &[0.0]);
+    }
+
+    #[test]
+    fn any_increase_over_a_constant_baseline_is_an_anomaly() {
+        let mut proc_block = anomaly_threshold("2");
+        score(&mut proc_block, 0.5);
+        score(&mut proc_block, 0.5);
+
+        assert_eq!(score(&mut proc_block, 0.5), (0, 0.0));
+        assert_eq!(score(&mut proc_block, 0.6), (1, f32::INFINITY));
+    }
+
+    #[test]
+    fn history_is_bounded() {
+        let mut proc_block = anomaly_threshold("5");
+        proc_block.set_window("5").unwrap();
+
+        for i in 0..20 {
+            score(&mut proc_block, (i % 2) as f32);
+        }
+
+        assert_eq!(proc_block.history.len(), 5);
+    }
+
+    #[test]
+    fn reset_forgets_everything() {
+        let mut proc_block = anomaly_threshold("1");
+        score(&mut proc_block, 1.0);
+        score(&mut proc_block, 2.0);
+
+        proc_block.reset();
+
+        assert_eq!(proc_block, anomaly_threshold("1"));
+    }
+
+    #[test]
+    #[should_panic = "The release (4) can't be larger than the threshold (3)"]
+    fn release_must_not_exceed_threshold() {
+        let mut proc_block = AnomalyThreshold::default();
+        proc_block.set_release("4").unwrap();
+
+        score(&mut proc_block, 1.0);
+    }
+
+    /// A proc block whose history has a mean of 2 and a standard deviation
+    /// of 1, so a score's z-score is `score - 2`.
+    fn warmed_up() -> AnomalyThreshold {
+        let mut proc_block = anomaly_threshold("4");
+        for &value in &[1.0, 3.0, 1.0, 3.0] {
+            score(&mut proc_block, value);
+        }
+        proc_block
+    }
+
+    #[test]
+    fn anomalies_start_above_the_threshold() {
+        let mut proc_block = warmed_up();
+
+        assert_eq!(score(&mut proc_block, 5.0), (0, 3.0));
+        assert_eq!(score(&mut warmed_up(), 5.5), (1, 3.5));
+    }
+
+    #[test]
+    fn anomalies_end_at_the_release() {
+        let mut proc_block = warmed_up();
+        assert_eq!(score(&mut proc_block, 7.0), (1, 5.0));
+
+        assert_eq!(score(&mut proc_block, 4.0), (0, 2.0));
+        assert_eq!(proc_block.history, vec![1.0, 3.0, 1.0, 3.0, 4.0]);
+    }
+
+    #[test]
+    fn the_release_can_equal_the_threshold() {
+        let mut proc_block = warmed_up();
+        proc_block.set_release("3").unwrap();
+        assert_eq!(score(&mut proc_block, 7.0), (1, 5.0));
+
+        // Without any hysteresis, the anomaly ends as soon as the score is
+        // back at the threshold
+        assert_eq!(score(&mut proc_block, 5.0), (0, 3.0));
+    }
+
+    #[test]
+    fn the_warm_up_can_fill_the_window() {
+        let mut proc_block = anomaly_threshold("3");
+        proc_block.set_window("3").unwrap();
+        for _ in 0..3 {
+            score(&mut proc_block, 1.0);
+        }
+
+        assert_eq!(score(&mut proc_block, 2.0), (1, f32::INFINITY));
+    }
+
+    #[test]
+    #[should_panic = "The warm-up (4) can't be longer than the window (3)"]
+    fn the_warm_up_must_fit_in_the_window() {
+        let mut proc_block = anomaly_threshold("4");
+        proc_block.set_window("3").unwrap();
+
+        score(&mut proc_block, 1.0);
+    }
+}
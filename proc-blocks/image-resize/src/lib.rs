@@ -0,0 +1,559 @@
+//! A proc block which resizes images inside the Rune, for when a camera's
+//! resolution doesn't match what the model expects and the host can't resize
+//! images for us.
+//!
+//! Images are expected to have dimensions like `[1, height, width, channels]`
+//! and the aspect ratio can be handled in one of three ways,
+//!
+//! - `stretch` - scale the width and height independently
+//! - `letterbox` - scale the image until it fits, padding the rest
+//! - `center-crop` - scale the image until it fills the output, cropping
+//!   whatever doesn't fit
+//!
+//! ```yaml
+//! resize:
+//!   proc-block: "hotg-ai/rune#proc-blocks/image-resize"
+//!   inputs:
+//!     - image
+//!   outputs:
+//!     - type: U8
+//!       dimensions: [1, 224, 224, 3]
+//!   args:
+//!     width: 224
+//!     height: 224
+//!     interpolation: bilinear
+//!     fit: center-crop
+//! ```
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::{string::String, vec::Vec};
+use core::{
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
+
+use hotg_rune_proc_blocks::{ProcBlock, Tensor, Transform};
+
+/// Resize an image.
+#[derive(Debug, Clone, PartialEq, ProcBlock)]
+#[transform(inputs = [u8; 4], outputs = [u8; 4])]
+#[transform(inputs = [f32; 4], outputs = [f32; 4])]
+pub struct ImageResize {
+    /// The width of the resized image, in pixels.
+    #[proc_block(validate = is_positive)]
+    width: usize,
+    /// The height of the resized image, in pixels.
+    #[proc_block(validate = is_positive)]
+    height: usize,
+    /// How pixels are sampled (`nearest` or `bilinear`).
+    interpolation: Interpolation,
+    /// How to handle a change in aspect ratio (`stretch`, `letterbox`, or
+    /// `center-crop`).
+    fit: Fit,
+    /// The value used for the padding added by `letterbox`.
+    padding: f32,
+}
+
+impl ImageResize {
+    fn resize<T: Element>(&self, image: &Tensor<T>) -> Tensor<T> {
+        let dimensions = image.dimensions();
+        assert!(
+            dimensions.len() >= 3,
+            "Expected an image with dimensions like [1, height, width, \
+             channels], not {:?}",
+            dimensions
+        );
+
+        let (batch, rest) = dimensions.split_at(dimensions.len() - 3);
+        let (src_height, src_width, channels) = (rest[0], rest[1], rest[2]);
+        assert!(
+            src_width > 0 && src_height > 0 && channels > 0,
+            "Unable to resize an empty image"
+        );
+
+        let placement =
+            self.fit
+                .placement(src_width, src_height, self.width, self.height);
+        let padding = T::from_f32(self.padding);
+
+        let image_size = src_height * src_width * channels;
+        let images = image.elements().len() / image_size;
+        let mut elements =
+            Vec::with_capacity(images * self.height * self.width * channels);
+
+        for pixels in image.elements().chunks_exact(image_size) {
+            let src = Image {
+                pixels,
+                width: src_width,
+                height: src_height,
+                channels,
+            };
+
+            for y in 0..self.height {
+                for x in 0..self.width {
+                    match placement.source(x, y) {
+                        Some((src_x, src_y)) => {
+                            elements.extend((0..channels).map(|channel| {
+                                let value = self
+                                    .interpolation
+                                    .sample(&src, src_x, src_y, channel);
+                                T::from_f32(value)
+                            }));
+                        },
+                        None => elements
+                            .extend(core::iter::repeat(padding).take(channels)),
+                    }
+                }
+            }
+        }
+
+        let mut new_dimensions = batch.to_vec();
+        new_dimensions.extend([self.height, self.width, channels]);
+
+        Tensor::new_row_major(elements.into(), new_dimensions)
+    }
+}
+
+impl Default for ImageResize {
+    fn default() -> Self {
+        ImageResize {
+            width: 224,
+            height: 224,
+            interpolation: Interpolation::default(),
+            fit: Fit::default(),
+            padding: 0.0,
+        }
+    }
+}
+
+impl<T: Element> Transform<Tensor<T>> for ImageResize {
+    type Output = Tensor<T>;
+
+    fn transform(&mut self, input: Tensor<T>) -> Self::Output {
+        self.resize(&input)
+    }
+}
+
+/// A pixel component which can be resized.
+pub trait Element: Copy {
+    fn to_f32(self) -> f32;
+    fn from_f32(value: f32) -> Self;
+}
+
+impl Element for u8 {
+    fn to_f32(self) -> f32 { self as f32 }
+
+    // Note: float-to-int casts saturate
+    fn from_f32(value: f32) -> Self { libm::roundf(value) as u8 }
+}
+
+impl Element for f32 {
+    fn to_f32(self) -> f32 { self }
+
+    fn from_f32(value: f32) -> Self { value }
+}
+
+struct Image<'a, T> {
+    pixels: &'a [T],
+    width: usize,
+    height: usize,
+    channels: usize,
+}
+
+impl<'a, T: Element> Image<'a, T> {
+    /// Get a pixel's value, clamping the coordinates to the image's edges.
+    fn get(&self, x: isize, y: isize, channel: usize) -> f32 {
+        let x = x.clamp(0, self.width as isize - 1) as usize;
+        let y = y.clamp(0, self.height as isize - 1) as usize;
+
+        self.pixels[(y * self.width + x) * self.channels + channel].to_f32()
+    }
+}
+
+/// How pixels are sampled when resizing.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Interpolation {
+    /// Use the closest pixel.
+    Nearest,
+    /// Interpolate between the 4 closest pixels.
+    Bilinear,
+}
+
+impl Interpolation {
+    /// Sample the image at a point, where `(0, 0)` is the center of the
+    /// top-left pixel.
+    fn sample<T: Element>(
+        self,
+        image: &Image<'_, T>,
+        x: f32,
+        y: f32,
+        channel: usize,
+    ) -> f32 {
+        match self {
+            Interpolation::Nearest => {
+                let x = libm::floorf(x + 0.5) as isize;
+                let y = libm::floorf(y + 0.5) as isize;
+                image.get(x, y, channel)
+            },
+            Interpolation::Bilinear => {
+                let (x0, y0) = (libm::floorf(x), libm::floorf(y));
+                let (dx, dy) = (x - x0, y - y0);
+                let (x0, y0) = (x0 as isize, y0 as isize);
+
+                let top = lerp(
+                    image.get(x0, y0, channel),
+                    image.get(x0 + 1, y0, channel),
+                    dx,
+                );
+                let bottom = lerp(
+                    image.get(x0, y0 + 1, channel),
+                    image.get(x0 + 1, y0 + 1, channel),
+                    dx,
+                );
+
+                lerp(top, bottom, dy)
+            },
+        }
+    }
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 { a + (b - a) * t }
+
+impl Default for Interpolation {
+    fn default() -> Self { Interpolation::Bilinear }
+}
+
+impl FromStr for Interpolation {
+    type Err = UnknownOption;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "nearest" => Ok(Interpolation::Nearest),
+            "bilinear" => Ok(Interpolation::Bilinear),
+            _ => Err(UnknownOption::new(s, &["nearest", "bilinear"])),
+        }
+    }
+}
+
+impl Display for Interpolation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Interpolation::Nearest => write!(f, "nearest"),
+            Interpolation::Bilinear => write!(f, "bilinear"),
+        }
+    }
+}
+
+/// How to handle an image with a different aspect ratio to the output.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Fit {
+    /// Scale the width and height independently, distorting the image.
+    Stretch,
+    /// Scale the image so it fits inside the output, centering it and
+    /// padding the edges.
+    Letterbox,
+    /// Scale the image so it covers the output, centering it and cropping
+    /// the edges.
+    CenterCrop,
+}
+
+impl Fit {
+    fn placement(
+        self,
+        src_width: usize,
+        src_height: usize,
+        dst_width: usize,
+        dst_height: usize,
+    ) -> Placement {
+        let full_image = |width: usize, height: usize| Rect {
+            x: 0.0,
+            y: 0.0,
+            width: width as f32,
+            height: height as f32,
+        };
+        let x_scale = dst_width as f32 / src_width as f32;
+        let y_scale = dst_height as f32 / src_height as f32;
+
+        match self {
+            Fit::Stretch => Placement {
+                src: full_image(src_width, src_height),
+                dst: full_image(dst_width, dst_height),
+            },
+            Fit::Letterbox => {
+                let scale = x_scale.min(y_scale);
+                let width = libm::roundf(src_width as f32 * scale) as usize;
+                let height = libm::roundf(src_height as f32 * scale) as usize;
+
+                Placement {
+                    src: full_image(src_width, src_height),
+                    dst: Rect {
+                        x: ((dst_width - width) / 2) as f32,
+                        y: ((dst_height - height) / 2) as f32,
+                        width: width as f32,
+                        height: height as f32,
+                    },
+                }
+            },
+            Fit::CenterCrop => {
+                let scale = x_scale.max(y_scale);
+                let width = dst_width as f32 / scale;
+                let height = dst_height as f32 / scale;
+
+                Placement {
+                    src: Rect {
+                        x: (src_width as f32 - width) / 2.0,
+                        y: (src_height as f32 - height) / 2.0,
+                        width,
+                        height,
+                    },
+                    dst: full_image(dst_width, dst_height),
+                }
+            },
+        }
+    }
+}
+
+impl Default for Fit {
+    fn default() -> Self { Fit::Stretch }
+}
+
+impl FromStr for Fit {
+    type Err = UnknownOption;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "stretch" => Ok(Fit::Stretch),
+            "letterbox" => Ok(Fit::Letterbox),
+            "center-crop" => Ok(Fit::CenterCrop),
+            _ => Err(UnknownOption::new(
+                s,
+                &["stretch", "letterbox", "center-crop"],
+            )),
+        }
+    }
+}
+
+impl Display for Fit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Fit::Stretch => write!(f, "stretch"),
+            Fit::Letterbox => write!(f, "letterbox"),
+            Fit::CenterCrop => write!(f, "center-crop"),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Rect {
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+}
+
+impl Rect {
+    fn contains(&self, x: f32, y: f32) -> bool {
+        self.x <= x
+            && x < self.x + self.width
+            && self.y <= y
+            && y < self.y + self.height
+    }
+}
+
+/// Which part of the original image ends up where in the output.
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Placement {
+    src: Rect,
+    dst: Rect,
+}
+
+impl Placement {
+    /// Find the point in the original image that an output pixel comes from,
+    /// or `None` if the output pixel is padding.
+    fn source(&self, x: usize, y: usize) -> Option<(f32, f32)> {
+        let Placement { src, dst } = self;
+        let (x, y) = (x as f32 + 0.5, y as f32 + 0.5);
+
+        if !dst.contains(x, y) {
+            return None;
+        }
+
+        let src_x = src.x + (x - dst.x) * src.width / dst.width - 0.5;
+        let src_y = src.y + (y - dst.y) * src.height / dst.height - 0.5;
+
+        Some((src_x, src_y))
+    }
+}
+
+/// The error returned when parsing an [`Interpolation`] or [`Fit`] fails.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownOption {
+    pub found: String,
+    pub expected: &'static [&'static str],
+}
+
+impl UnknownOption {
+    fn new(found: &str, expected: &'static [&'static str]) -> Self {
+        UnknownOption {
+            found: found.into(),
+            expected,
+        }
+    }
+}
+
+impl Display for UnknownOption {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Expected one of ")?;
+
+        for (i, option) in self.expected.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "\"{}\"", option)?;
+        }
+
+        write!(f, ", but found \"{}\"", self.found)
+    }
+}
+
+impl hotg_rune_core::Error for UnknownOption {}
+
+fn is_positive(value: &usize) -> Result<(), &'static str> {
+    if *value > 0 {
+        Ok(())
+    } else {
+        Err("must be greater than zero")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{string::ToString, vec};
+
+    use super::*;
+
+    fn resizer(
+        width: usize,
+        height: usize,
+        options: &[(&str, &str)],
+    ) -> ImageResize {
+        let mut proc_block = ImageResize::default();
+        proc_block.set_width(&width.to_string()).unwrap();
+        proc_block.set_height(&height.to_string()).unwrap();
+
+        for (name, value) in options {
+            proc_block.set_parameter(name, value).unwrap();
+        }
+
+        proc_block
+    }
+
+    /// Create a `[1, height, width, 1]` greyscale image.
+    fn image<T: Copy>(rows: &[&[T]]) -> Tensor<T> {
+        let height = rows.len();
+        let width = rows[0].len();
+        let pixels: Vec<T> =
+            rows.iter().flat_map(|row| row.iter().copied()).collect();
+
+        Tensor::new_row_major(pixels.into(), vec![1, height, width, 1])
+    }
+
+    #[test]
+    fn same_size_is_a_no_op() {
+        let input = image::<u8>(&[&[1, 2, 3], &[4, 5, 6]]);
+
+        for interpolation in ["nearest", "bilinear"] {
+            let mut proc_block =
+                resizer(3, 2, &[("interpolation", interpolation)]);
+
+            assert_eq!(proc_block.transform(input.clone()), input);
+        }
+    }
+
+    #[test]
+    fn nearest_neighbour_downscaling() {
+        let input = image::<u8>(&[
+            &[0, 1, 2, 3],
+            &[4, 5, 6, 7],
+            &[8, 9, 10, 11],
+            &[12, 13, 14, 15],
+        ]);
+        let mut proc_block = resizer(2, 2, &[("interpolation", "nearest")]);
+
+        let got = proc_block.transform(input);
+
+        assert_eq!(got, image(&[&[5, 7], &[13, 15]]));
+    }
+
+    #[test]
+    fn bilinear_upscaling() {
+        let input = image::<f32>(&[&[0.0, 10.0]]);
+        let mut proc_block = resizer(4, 1, &[("interpolation", "bilinear")]);
+
+        let got = proc_block.transform(input);
+
+        assert_eq!(got, image(&[&[0.0, 2.5, 7.5, 10.0]]));
+    }
+
+    #[test]
+    fn letterbox_a_wide_image() {
+        let input = image::<u8>(&[&[1, 2, 3, 4], &[5, 6, 7, 8]]);
+        let mut proc_block =
+            resizer(4, 4, &[("fit", "letterbox"), ("padding", "255")]);
+
+        let got = proc_block.transform(input);
+
+        assert_eq!(
+            got,
+            image(&[
+                &[255, 255, 255, 255],
+                &[1, 2, 3, 4],
+                &[5, 6, 7, 8],
+                &[255, 255, 255, 255],
+            ])
+        );
+    }
+
+    #[test]
+    fn center_crop_a_wide_image() {
+        let input = image::<u8>(&[&[1, 2, 3, 4], &[5, 6, 7, 8]]);
+        let mut proc_block = resizer(2, 2, &[("fit", "center-crop")]);
+
+        let got = proc_block.transform(input);
+
+        assert_eq!(got, image(&[&[2, 3], &[6, 7]]));
+    }
+
+    #[test]
+    fn channels_are_resized_independently() {
+        let input = Tensor::new_row_major(
+            vec![0_u8, 100, 200, 100].into(),
+            vec![1, 1, 2, 2],
+        );
+        let mut proc_block = resizer(4, 1, &[]);
+
+        let got = proc_block.transform(input);
+
+        let should_be = Tensor::new_row_major(
+            vec![0_u8, 100, 50, 100, 150, 100, 200, 100].into(),
+            vec![1, 1, 4, 2],
+        );
+        assert_eq!(got, should_be);
+    }
+
+    #[test]
+    fn unknown_options_are_rejected() {
+        let mut proc_block = ImageResize::default();
+
+        let err = proc_block.set_fit("squash").unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "Unable to set \"fit\" to \"squash\": Expected one of \
+             \"stretch\", \"letterbox\", \"center-crop\", but found \"squash\""
+        );
+    }
+}
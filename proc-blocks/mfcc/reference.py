@@ -0,0 +1,144 @@
+#!/usr/bin/env python3
+"""
+Generate the reference MFCCs used by the `mfcc` proc block's tests.
+
+This is a dependency-free port of the pipeline from TensorFlow's
+`tf.signal` module that most speech models are trained with:
+
+    stfts = tf.signal.stft(audio, frame_length, frame_step)
+    spectrogram = tf.abs(stfts)
+    mel_matrix = tf.signal.linear_to_mel_weight_matrix(
+        mel_filters, spectrogram.shape[-1], sample_rate,
+        lower_frequency, upper_frequency)
+    mel = tf.tensordot(spectrogram, mel_matrix, 1)
+    log_mel = tf.math.log(mel + 1e-6)
+    mfccs = tf.signal.mfccs_from_log_mel_spectrograms(log_mel)
+
+Usage: python3 reference.py > reference.txt
+"""
+
+import math
+
+SAMPLE_RATE = 16000
+FRAME_LENGTH = 480
+FRAME_STRIDE = 320
+MEL_FILTERS = 40
+COEFFICIENTS = 13
+LOWER_FREQUENCY = 125.0
+UPPER_FREQUENCY = 7500.0
+LOG_OFFSET = 1e-6
+
+
+def audio(length):
+    """A few tones plus some deterministic noise."""
+    state = 42
+    samples = []
+
+    for n in range(length):
+        state = (state * 1103515245 + 12345) % 2**31
+        noise = state / 2**31 * 2 - 1
+        t = n / SAMPLE_RATE
+        samples.append(
+            0.5 * math.sin(2 * math.pi * 440 * t)
+            + 0.25 * math.sin(2 * math.pi * 1250 * t)
+            + 0.1 * math.sin(2 * math.pi * 3000 * t)
+            + 0.01 * noise
+        )
+
+    return samples
+
+
+def stft_magnitudes(samples, fft_size):
+    window = [
+        0.5 - 0.5 * math.cos(2 * math.pi * n / FRAME_LENGTH)
+        for n in range(FRAME_LENGTH)
+    ]
+    frame_count = 1 + (len(samples) - FRAME_LENGTH) // FRAME_STRIDE
+    frames = []
+
+    for i in range(frame_count):
+        start = i * FRAME_STRIDE
+        frame = [
+            s * w
+            for s, w in zip(samples[start:start + FRAME_LENGTH], window)
+        ]
+        magnitudes = []
+
+        for k in range(fft_size // 2 + 1):
+            re = sum(
+                x * math.cos(2 * math.pi * k * n / fft_size)
+                for n, x in enumerate(frame)
+            )
+            im = sum(
+                -x * math.sin(2 * math.pi * k * n / fft_size)
+                for n, x in enumerate(frame)
+            )
+            magnitudes.append(math.hypot(re, im))
+
+        frames.append(magnitudes)
+
+    return frames
+
+
+def hertz_to_mel(frequency):
+    return 1127.0 * math.log(1.0 + frequency / 700.0)
+
+
+def mel_weight_matrix(bins):
+    """Port of tf.signal.linear_to_mel_weight_matrix()."""
+    nyquist = SAMPLE_RATE / 2
+    lower_mel = hertz_to_mel(LOWER_FREQUENCY)
+    upper_mel = hertz_to_mel(UPPER_FREQUENCY)
+    edges = [
+        lower_mel + (upper_mel - lower_mel) * i / (MEL_FILTERS + 1)
+        for i in range(MEL_FILTERS + 2)
+    ]
+
+    # The DC bin never contributes to a mel band
+    matrix = [[0.0] * MEL_FILTERS]
+
+    for k in range(1, bins):
+        mel = hertz_to_mel(nyquist * k / (bins - 1))
+        row = []
+
+        for m in range(MEL_FILTERS):
+            lower, center, upper = edges[m:m + 3]
+            lower_slope = (mel - lower) / (center - lower)
+            upper_slope = (upper - mel) / (upper - center)
+            row.append(max(0.0, min(lower_slope, upper_slope)))
+
+        matrix.append(row)
+
+    return matrix
+
+
+def mfccs(log_mel):
+    """Port of tf.signal.mfccs_from_log_mel_spectrograms()."""
+    n = len(log_mel)
+    scale = 1 / math.sqrt(2 * n)
+
+    return [
+        scale * 2 * sum(
+            x * math.cos(math.pi * k * (2 * i + 1) / (2 * n))
+            for i, x in enumerate(log_mel)
+        )
+        for k in range(COEFFICIENTS)
+    ]
+
+
+def main():
+    fft_size = 1 << (FRAME_LENGTH - 1).bit_length()
+    spectrogram = stft_magnitudes(audio(1600), fft_size)
+    matrix = mel_weight_matrix(fft_size // 2 + 1)
+
+    for frame in spectrogram:
+        mel = [
+            sum(frame[k] * matrix[k][m] for k in range(len(frame)))
+            for m in range(MEL_FILTERS)
+        ]
+        log_mel = [math.log(x + LOG_OFFSET) for x in mel]
+        print(", ".join(f"{c:.5}" for c in mfccs(log_mel)) + ",")
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,387 @@
+//! A proc block which extracts Mel-Frequency Cepstral Coefficients (MFCCs)
+//! from audio.
+//!
+//! This follows the same steps as the `tf.signal` functions most speech
+//! models are trained with,
+//!
+//! 1. Split the audio into frames and take the magnitude of each frame's FFT
+//!    (see the [`spectrogram`] proc block)
+//! 2. Combine the FFT bins into mel bands using a [`MelFilterbank`]
+//! 3. Take the logarithm of each band's energy
+//! 4. Apply a Discrete Cosine Transform and keep the first few coefficients
+//!
+//! A `[1, N]` tensor of samples becomes a `[1, frames, coefficients]` tensor,
+//! where `frames` is `1 + (N - frame_length) / frame_stride`.
+//!
+//! ```yaml
+//! mfcc:
+//!   proc-block: "hotg-ai/rune#proc-blocks/mfcc"
+//!   inputs:
+//!     - audio
+//!   outputs:
+//!     - type: F32
+//!       dimensions: [1, 49, 13]
+//!   args:
+//!     sample-rate: 16000
+//!     frame-length: 480
+//!     frame-stride: 320
+//!     mel-filters: 40
+//!     coefficients: 13
+//! ```
+//!
+//! The `reference.py` script next to this crate's `Cargo.toml` is a port of
+//! the `tf.signal` pipeline which was used to generate the expected values
+//! for this crate's tests.
+
+#![no_std]
+
+extern crate alloc;
+
+mod mel;
+
+use alloc::vec::Vec;
+use core::f32::consts::PI;
+
+use hotg_rune_proc_blocks::{ProcBlock, Tensor, Transform};
+use spectrogram::Window;
+
+pub use crate::mel::{hertz_to_mel, MelFilterbank};
+
+/// Added to each mel band's energy so silence doesn't become `log(0)`.
+const LOG_OFFSET: f32 = 1e-6;
+
+/// Extract MFCCs from audio.
+///
+/// `i16` samples are scaled to the range `[-1, 1]` first. The defaults match
+/// the 30ms frames every 20ms used by TensorFlow's micro speech example.
+#[derive(Debug, Clone, PartialEq, ProcBlock)]
+#[transform(inputs = [i16; _], outputs = [f32; 3])]
+#[transform(inputs = [f32; _], outputs = [f32; 3])]
+pub struct Mfcc {
+    /// The audio's sample rate, in Hz.
+    #[proc_block(validate = is_positive, default = "16000")]
+    sample_rate: u32,
+    /// The number of samples in each frame. Frames are zero-padded to the
+    /// next power of two before their FFT is taken.
+    #[proc_block(validate = is_positive, default = "480")]
+    frame_length: usize,
+    /// The number of samples between the start of one frame and the next.
+    #[proc_block(validate = is_positive, default = "320")]
+    frame_stride: usize,
+    /// The number of mel bands.
+    #[proc_block(validate = is_positive, default = "40")]
+    mel_filters: usize,
+    /// The number of coefficients to keep for each frame. This can't be more
+    /// than the number of mel bands.
+    #[proc_block(validate = is_positive, default = "13")]
+    coefficients: usize,
+    /// The frequency (in Hz) at the bottom of the lowest mel band.
+    #[proc_block(default = "125")]
+    lower_frequency: f32,
+    /// The frequency (in Hz) at the top of the highest mel band. This can't
+    /// be more than half the sample rate.
+    #[proc_block(validate = is_positive, default = "7500")]
+    upper_frequency: f32,
+}
+
+impl Mfcc {
+    fn mfccs(&self, samples: &[f32]) -> Tensor<f32> {
+        self.check_parameters();
+
+        let fft_size = self.frame_length.next_power_of_two();
+        let window = Window::Hann.coefficients(self.frame_length);
+        let spectrogram = spectrogram::spectrogram(
+            samples,
+            &window,
+            self.frame_stride,
+            fft_size,
+        );
+        let frames = spectrogram.dimensions()[1];
+        let bins = spectrogram.dimensions()[2];
+
+        let filterbank = MelFilterbank::new(
+            self.mel_filters,
+            bins,
+            self.sample_rate as f32,
+            self.lower_frequency,
+            self.upper_frequency,
+        );
+
+        let mut elements = Vec::with_capacity(frames * self.coefficients);
+        let mut log_mel = Vec::with_capacity(self.mel_filters);
+
+        for spectrum in spectrogram.elements().chunks_exact(bins) {
+            log_mel.clear();
+            log_mel.extend(
+                filterbank
+                    .apply(spectrum)
+                    .map(|energy| libm::logf(energy + LOG_OFFSET)),
+            );
+
+            elements.extend(dct(&log_mel, self.coefficients));
+        }
+
+        Tensor::new_row_major(
+            elements.into(),
+            alloc::vec![1, frames, self.coefficients],
+        )
+    }
+
+    fn check_parameters(&self) {
+        let nyquist = self.sample_rate as f32 / 2.0;
+
+        assert!(
+            self.coefficients <= self.mel_filters,
+            "Can't take {} coefficients from {} mel bands",
+            self.coefficients,
+            self.mel_filters
+        );
+        assert!(
+            0.0 <= self.lower_frequency
+                && self.lower_frequency < self.upper_frequency
+                && self.upper_frequency <= nyquist,
+            "The mel bands must cover a range between 0Hz and {}Hz, not {}Hz \
+             to {}Hz",
+            nyquist,
+            self.lower_frequency,
+            self.upper_frequency
+        );
+    }
+}
+
+impl Default for Mfcc {
+    fn default() -> Self {
+        Mfcc {
+            sample_rate: 16000,
+            frame_length: 480,
+            frame_stride: 320,
+            mel_filters: 40,
+            coefficients: 13,
+            lower_frequency: 125.0,
+            upper_frequency: 7500.0,
+        }
+    }
+}
+
+impl Transform<Tensor<i16>> for Mfcc {
+    type Output = Tensor<f32>;
+
+    fn transform(&mut self, input: Tensor<i16>) -> Self::Output {
+        let samples: Vec<f32> = input
+            .elements()
+            .iter()
+            .map(|&sample| sample as f32 / -(i16::MIN as f32))
+            .collect();
+
+        self.mfccs(&samples)
+    }
+}
+
+impl Transform<Tensor<f32>> for Mfcc {
+    type Output = Tensor<f32>;
+
+    fn transform(&mut self, input: Tensor<f32>) -> Self::Output {
+        self.mfccs(input.elements())
+    }
+}
+
+/// A type-II Discrete Cosine Transform, scaled the same way as
+/// `tf.signal.mfccs_from_log_mel_spectrograms()`, which only calculates the
+/// first `coefficients` values.
+fn dct(log_mel: &[f32], coefficients: usize) -> impl Iterator<Item = f32> + '_ {
+    let n = log_mel.len() as f32;
+    let scale = 2.0 / libm::sqrtf(2.0 * n);
+
+    (0..coefficients).map(move |k| {
+        let sum: f32 = log_mel
+            .iter()
+            .enumerate()
+            .map(|(i, &x)| {
+                x * libm::cosf(PI * k as f32 * (2 * i + 1) as f32 / (2.0 * n))
+            })
+            .sum();
+
+        scale * sum
+    })
+}
+
+fn is_positive<T>(value: &T) -> Result<(), &'static str>
+where
+    T: Default + PartialOrd,
+{
+    if *value > T::default() {
+        Ok(())
+    } else {
+        Err("must be greater than zero")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The audio used by `reference.py`.
+    fn audio(len: usize) -> Vec<f32> {
+        let mut state: u64 = 42;
+
+        (0..len)
+            .map(|n| {
+                state = (state * 1103515245 + 12345) % (1 << 31);
+                let noise = state as f64 / (1_u64 << 31) as f64 * 2.0 - 1.0;
+                let t = n as f64 / 16000.0;
+                let tone = |hz: f64, amplitude: f64| {
+                    amplitude * libm::sin(2.0 * core::f64::consts::PI * hz * t)
+                };
+
+                (tone(440.0, 0.5)
+                    + tone(1250.0, 0.25)
+                    + tone(3000.0, 0.1)
+                    + 0.01 * noise) as f32
+            })
+            .collect()
+    }
+
+    #[test]
+    fn matches_the_reference_implementation() {
+        // Generated by "python3 reference.py"
+        let should_be = [
+            [
+                -1.6312, 0.44235, 0.20805, 0.11869, -0.59403, -0.89349,
+                0.20894, -4.7473, -6.3121, -2.379, -0.89335, 1.1595, 2.2496,
+            ],
+            [
+                -1.5507, 0.42027, 0.59886, 0.31808, -1.0499, -0.87765,
+                -0.043122, -4.3011, -6.5756, -2.1709, -0.085486, 0.96258,
+                2.306,
+            ],
+            [
+                -1.6699, 0.75476, 0.58934, -0.40896, -1.3562, -1.0272, 0.42018,
+                -4.5068, -6.2799, -2.2048, -0.63205, 0.46624, 1.9396,
+            ],
+            [
+                -1.7844, 0.70227, 0.15529, 0.25603, -0.71328, -0.72936,
+                -0.22257, -4.6199, -6.3771, -1.9049, -0.13716, 0.70346, 2.2437,
+            ],
+        ];
+        let mut proc_block = Mfcc::default();
+
+        let got = proc_block.transform(Tensor::new_vector(audio(1600)));
+
+        assert_eq!(got.dimensions(), &[1, 4, 13]);
+        let expected = should_be.iter().flatten();
+        for (i, (&got, &expected)) in
+            got.elements().iter().zip(expected).enumerate()
+        {
+            let error = got - expected;
+            assert!(error * error < 1e-5, "{} != {} (at {})", got, expected, i);
+        }
+    }
+
+    #[test]
+    fn integer_samples_are_scaled() {
+        let samples: Vec<i16> = audio(1600)
+            .into_iter()
+            .map(|s| (s * 32768.0) as i16)
+            .collect();
+        let mut proc_block = Mfcc::default();
+
+        let from_integers = proc_block.transform(Tensor::new_vector(samples));
+        let from_floats = proc_block.transform(Tensor::new_vector(audio(1600)));
+
+        for (i, f) in
+            from_integers.elements().iter().zip(from_floats.elements())
+        {
+            assert!((i - f) * (i - f) < 1e-4, "{} != {}", i, f);
+        }
+    }
+
+    #[test]
+    fn one_second_of_audio() {
+        let mut proc_block = Mfcc::default();
+
+        let got = proc_block.transform(Tensor::new_vector(audio(16000)));
+
+        assert_eq!(got.dimensions(), &[1, 49, 13]);
+    }
+
+    #[test]
+    fn dct_of_a_constant_only_has_a_dc_component() {
+        let got: Vec<f32> = dct(&[2.0; 8], 3).collect();
+
+        // 2.0 * 8 samples * 2 / sqrt(16)
+        assert_eq!(got[0], 8.0);
+        assert!(got[1] * got[1] < 1e-10 && got[2] * got[2] < 1e-10);
+    }
+
+    #[test]
+    #[should_panic]
+    fn mel_bands_must_be_below_the_nyquist_frequency() {
+        let mut proc_block = Mfcc::default();
+        proc_block.set_sample_rate("8000").unwrap();
+
+        proc_block.transform(Tensor::new_vector(audio(1600)));
+    }
+
+    #[test]
+    fn silence_is_clamped_instead_of_becoming_infinite() {
+        let mut proc_block = Mfcc::default();
+
+        let got = proc_block.transform(Tensor::new_vector([0.0_f32; 1600]));
+
+        // Every band is log(LOG_OFFSET), so only the DC coefficient is set
+        let dc = libm::logf(LOG_OFFSET) * libm::sqrtf(2.0 * 40.0);
+        for frame in got.elements().chunks(13) {
+            assert!((frame[0] - dc).abs() < 1e-3, "{} != {}", frame[0], dc);
+            assert!(frame[1..].iter().all(|c| c.abs() < 1e-3), "{:?}", frame);
+        }
+    }
+
+    #[test]
+    fn coefficients_can_use_every_mel_band() {
+        let mut proc_block = Mfcc::default();
+        proc_block.set_mel_filters("13").unwrap();
+
+        let got = proc_block.transform(Tensor::new_vector(audio(1600)));
+
+        assert_eq!(got.dimensions(), &[1, 4, 13]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Can't take 13 coefficients from 12 mel bands")]
+    fn there_cant_be_more_coefficients_than_mel_bands() {
+        let mut proc_block = Mfcc::default();
+        proc_block.set_mel_filters("12").unwrap();
+
+        proc_block.transform(Tensor::new_vector(audio(1600)));
+    }
+
+    #[test]
+    fn mel_bands_can_reach_the_nyquist_frequency() {
+        let mut proc_block = Mfcc::default();
+        proc_block.set_upper_frequency("8000").unwrap();
+
+        let got = proc_block.transform(Tensor::new_vector(audio(1600)));
+
+        assert!(got.elements().iter().all(|c| c.is_finite()));
+    }
+
+    #[test]
+    #[should_panic(expected = "not 7500Hz to 7500Hz")]
+    fn mel_bands_cant_be_empty() {
+        let mut proc_block = Mfcc::default();
+        proc_block.set_lower_frequency("7500").unwrap();
+
+        proc_block.transform(Tensor::new_vector(audio(1600)));
+    }
+
+    #[test]
+    fn audio_shorter_than_a_frame_has_no_mfccs() {
+        let mut proc_block = Mfcc::default();
+
+        let one_frame = proc_block.transform(Tensor::new_vector(audio(480)));
+        let too_short = proc_block.transform(Tensor::new_vector(audio(479)));
+
+        assert_eq!(one_frame.dimensions(), &[1, 1, 13]);
+        assert_eq!(too_short.dimensions(), &[1, 0, 13]);
+    }
+}
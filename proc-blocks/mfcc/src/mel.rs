@@ -0,0 +1,117 @@
+use alloc::vec::Vec;
+
+/// Convert a frequency to the mel scale, using the same formula as HTK and
+/// `tf.signal`.
+pub fn hertz_to_mel(frequency: f32) -> f32 {
+    1127.0 * libm::logf(1.0 + frequency / 700.0)
+}
+
+/// A bank of overlapping triangular filters, evenly spaced on the mel scale,
+/// which combine the bins from a magnitude spectrum into mel bands.
+///
+/// This matches `tf.signal.linear_to_mel_weight_matrix()`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MelFilterbank {
+    filters: usize,
+    /// A `[bins, filters]` matrix in row-major order.
+    weights: Vec<f32>,
+}
+
+impl MelFilterbank {
+    pub fn new(
+        filters: usize,
+        bins: usize,
+        sample_rate: f32,
+        lower_frequency: f32,
+        upper_frequency: f32,
+    ) -> Self {
+        let nyquist = sample_rate / 2.0;
+        let lower_mel = hertz_to_mel(lower_frequency);
+        let upper_mel = hertz_to_mel(upper_frequency);
+        let band_width = (upper_mel - lower_mel) / (filters + 1) as f32;
+        let edge = |i: usize| lower_mel + band_width * i as f32;
+
+        let mut weights = Vec::with_capacity(bins * filters);
+
+        // Note: the DC bin never contributes to a mel band
+        weights.extend(core::iter::repeat(0.0).take(filters));
+
+        for bin in 1..bins {
+            let mel = hertz_to_mel(nyquist * bin as f32 / (bins - 1) as f32);
+
+            for filter in 0..filters {
+                let (lower, center, upper) =
+                    (edge(filter), edge(filter + 1), edge(filter + 2));
+                let lower_slope = (mel - lower) / (center - lower);
+                let upper_slope = (upper - mel) / (upper - center);
+
+                weights.push(lower_slope.min(upper_slope).max(0.0));
+            }
+        }
+
+        MelFilterbank { filters, weights }
+    }
+
+    /// Calculate the energy in each mel band.
+    pub fn apply<'a>(
+        &'a self,
+        spectrum: &'a [f32],
+    ) -> impl Iterator<Item = f32> + 'a {
+        assert_eq!(spectrum.len() * self.filters, self.weights.len());
+
+        (0..self.filters).map(move |filter| {
+            spectrum
+                .iter()
+                .zip(self.weights.iter().skip(filter).step_by(self.filters))
+                .map(|(&magnitude, &weight)| magnitude * weight)
+                .sum()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_mel_frequencies() {
+        assert_eq!(hertz_to_mel(0.0), 0.0);
+        // 700Hz is the "corner frequency" for the mel scale
+        let got = hertz_to_mel(700.0);
+        assert!(got > 781.17 && got < 781.18, "{}", got);
+    }
+
+    #[test]
+    fn filters_are_triangles() {
+        let filterbank = MelFilterbank::new(3, 257, 16000.0, 0.0, 8000.0);
+
+        for filter in 0..3 {
+            let weights: Vec<f32> = filterbank
+                .weights
+                .iter()
+                .skip(filter)
+                .step_by(3)
+                .copied()
+                .collect();
+            let peak = weights.iter().copied().fold(0.0, f32::max);
+            let first = weights.iter().position(|&w| w > 0.0).unwrap();
+            let last = weights.iter().rposition(|&w| w > 0.0).unwrap();
+
+            assert!(peak > 0.9 && peak <= 1.0);
+            assert!(weights[first..=last].iter().all(|&w| w > 0.0));
+        }
+    }
+
+    #[test]
+    fn apply_the_filterbank() {
+        let filterbank = MelFilterbank::new(2, 5, 16000.0, 0.0, 8000.0);
+        let spectrum = [1.0, 1.0, 1.0, 1.0, 1.0];
+
+        let got: Vec<f32> = filterbank.apply(&spectrum).collect();
+
+        let should_be: Vec<f32> = (0..2)
+            .map(|f| filterbank.weights.iter().skip(f).step_by(2).sum())
+            .collect();
+        assert_eq!(got, should_be);
+    }
+}
@@ -0,0 +1,563 @@
+//! A proc block which post-processes the output of an object detection model
+//! (e.g. SSD or YOLO) by removing overlapping detections using
+//! non-maximum suppression.
+//!
+//! The proc block has two inputs,
+//!
+//! - `boxes` - the bounding boxes, with dimensions like `[1, boxes, 4]`
+//! - `scores` - the confidence for each box and class, with dimensions like
+//!   `[1, boxes, classes]` (or `[1, boxes]` when there is only one class)
+//!
+//! The outputs mirror TensorFlow Lite's `TFLite_Detection_PostProcess`
+//! operator. They are the selected boxes (`[1, max_detections, 4]`), their
+//! classes and scores (`[1, max_detections]`), and the number of detections
+//! (`[1]`). Slots after the last detection are filled with zeros.
+//!
+//! If the inputs are malformed (e.g. there isn't a score for each box), the
+//! problem is logged and no detections are output.
+//!
+//! ```yaml
+//! nms:
+//!   proc-block: "hotg-ai/rune#proc-blocks/non-max-suppression"
+//!   inputs:
+//!     - model.0
+//!     - model.1
+//!   outputs:
+//!     - type: F32
+//!       dimensions: [1, 10, 4]
+//!     - type: F32
+//!       dimensions: [1, 10]
+//!     - type: F32
+//!       dimensions: [1, 10]
+//!     - type: F32
+//!       dimensions: [1]
+//!   args:
+//!     iou-threshold: 0.5
+//!     score-threshold: 0.4
+//!     max-detections: 10
+//!     box-format: corners
+//! ```
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::{string::String, vec::Vec};
+use core::{
+    cmp::Ordering,
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
+
+use hotg_rune_proc_blocks::{ProcBlock, Tensor, Transform};
+
+/// Select the most confident detections, discarding any boxes which overlap
+/// a more confident box of the same class.
+#[derive(Debug, Clone, PartialEq, ProcBlock)]
+#[transform(
+    inputs = ([f32; 3], [f32; _]),
+    outputs = ([f32; 3], [f32; 2], [f32; 2], [f32; 1])
+)]
+pub struct NonMaxSuppression {
+    /// Boxes which overlap a more confident box by more than this
+    /// intersection-over-union are discarded.
+    #[proc_block(validate = is_fraction)]
+    iou_threshold: f32,
+    /// Detections with a score less than this are ignored.
+    score_threshold: f32,
+    /// The maximum number of detections to output.
+    #[proc_block(validate = is_positive)]
+    max_detections: usize,
+    /// How each box's 4 values are laid out (`corners` or `center`).
+    box_format: BoxFormat,
+}
+
+impl NonMaxSuppression {
+    fn suppress(
+        &self,
+        boxes: &[f32],
+        scores: &[f32],
+    ) -> Result<Vec<Detection>, ShapeError> {
+        if boxes.len() % 4 != 0 {
+            return Err(ShapeError::IncompleteBox {
+                values: boxes.len(),
+            });
+        }
+
+        let box_count = boxes.len() / 4;
+
+        if box_count == 0 && scores.is_empty() {
+            return Ok(Vec::new());
+        }
+        if box_count == 0 || scores.len() % box_count != 0 {
+            return Err(ShapeError::MismatchedScores {
+                boxes: box_count,
+                scores: scores.len(),
+            });
+        }
+
+        let classes = scores.len() / box_count;
+
+        let mut candidates: Vec<Detection> = scores
+            .iter()
+            .enumerate()
+            // Note: this also drops NaNs
+            .filter(|&(_, &score)| score >= self.score_threshold)
+            .map(|(i, &score)| Detection {
+                index: i / classes,
+                class: i % classes,
+                score,
+            })
+            .collect();
+        // Note: sort_by() is stable, so ties keep their original order
+        candidates.sort_by(|a, b| {
+            b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal)
+        });
+
+        let bounding_box = |index: usize| {
+            let values = &boxes[index * 4..index * 4 + 4];
+            Rect::new(
+                self.box_format,
+                [values[0], values[1], values[2], values[3]],
+            )
+        };
+
+        let mut selected: Vec<Detection> = Vec::new();
+
+        for candidate in candidates {
+            if selected.len() >= self.max_detections {
+                break;
+            }
+
+            let candidate_box = bounding_box(candidate.index);
+            let overlaps = selected.iter().any(|s| {
+                s.class == candidate.class
+                    && bounding_box(s.index).iou(&candidate_box)
+                        > self.iou_threshold
+            });
+
+            if !overlaps {
+                selected.push(candidate);
+            }
+        }
+
+        Ok(selected)
+    }
+}
+
+impl Default for NonMaxSuppression {
+    fn default() -> Self {
+        NonMaxSuppression {
+            iou_threshold: 0.5,
+            score_threshold: 0.5,
+            max_detections: 10,
+            box_format: BoxFormat::default(),
+        }
+    }
+}
+
+impl Transform<(Tensor<f32>, Tensor<f32>)> for NonMaxSuppression {
+    type Output = (Tensor<f32>, Tensor<f32>, Tensor<f32>, Tensor<f32>);
+
+    fn transform(
+        &mut self,
+        (boxes, scores): (Tensor<f32>, Tensor<f32>),
+    ) -> Self::Output {
+        let detections =
+            match self.suppress(boxes.elements(), scores.elements()) {
+                Ok(detections) => detections,
+                Err(e) => {
+                    log::error!("Unable to run non-max suppression: {}", e);
+                    Vec::new()
+                },
+            };
+        let max = self.max_detections;
+
+        let mut selected_boxes = alloc::vec![0.0; max * 4];
+        let mut classes = alloc::vec![0.0; max];
+        let mut confidences = alloc::vec![0.0; max];
+
+        for (i, detection) in detections.iter().enumerate() {
+            let src = detection.index * 4;
+            selected_boxes[i * 4..i * 4 + 4]
+                .copy_from_slice(&boxes.elements()[src..src + 4]);
+            classes[i] = detection.class as f32;
+            confidences[i] = detection.score;
+        }
+
+        (
+            Tensor::new_row_major(
+                selected_boxes.into(),
+                alloc::vec![1, max, 4],
+            ),
+            Tensor::new_row_major(classes.into(), alloc::vec![1, max]),
+            Tensor::new_row_major(confidences.into(), alloc::vec![1, max]),
+            Tensor::new_vector(alloc::vec![detections.len() as f32]),
+        )
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Detection {
+    /// Which bounding box was detected.
+    index: usize,
+    class: usize,
+    score: f32,
+}
+
+/// Why the `boxes` and `scores` inputs couldn't be used.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum ShapeError {
+    IncompleteBox { values: usize },
+    MismatchedScores { boxes: usize, scores: usize },
+}
+
+impl Display for ShapeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ShapeError::IncompleteBox { values } => write!(
+                f,
+                "Each bounding box should have 4 values, but there are {} \
+                 values",
+                values
+            ),
+            ShapeError::MismatchedScores { boxes, scores } => write!(
+                f,
+                "Expected a score for each of the {} boxes, but found {} \
+                 scores",
+                boxes, scores
+            ),
+        }
+    }
+}
+
+/// How the 4 values for a bounding box are laid out.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum BoxFormat {
+    /// Opposite corners, `[y_min, x_min, y_max, x_max]` (e.g. SSD).
+    Corners,
+    /// The center and size, `[x_center, y_center, width, height]` (e.g.
+    /// YOLO).
+    Center,
+}
+
+impl Default for BoxFormat {
+    fn default() -> Self { BoxFormat::Corners }
+}
+
+impl FromStr for BoxFormat {
+    type Err = UnknownBoxFormat;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "corners" => Ok(BoxFormat::Corners),
+            "center" => Ok(BoxFormat::Center),
+            _ => Err(UnknownBoxFormat { name: s.into() }),
+        }
+    }
+}
+
+impl Display for BoxFormat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            BoxFormat::Corners => write!(f, "corners"),
+            BoxFormat::Center => write!(f, "center"),
+        }
+    }
+}
+
+/// The error returned when parsing an unknown [`BoxFormat`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownBoxFormat {
+    pub name: String,
+}
+
+impl Display for UnknownBoxFormat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Expected \"corners\" or \"center\", but found \"{}\"",
+            self.name
+        )
+    }
+}
+
+impl hotg_rune_core::Error for UnknownBoxFormat {}
+
+/// An axis-aligned rectangle.
+///
+/// Because intersection-over-union doesn't care which axis is which, the
+/// `a` and `b` axes may be either `x` and `y` or `y` and `x`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Rect {
+    a_min: f32,
+    b_min: f32,
+    a_max: f32,
+    b_max: f32,
+}
+
+impl Rect {
+    fn new(format: BoxFormat, [p, q, r, s]: [f32; 4]) -> Self {
+        let (a0, b0, a1, b1) = match format {
+            BoxFormat::Corners => (p, q, r, s),
+            BoxFormat::Center => {
+                (p - r / 2.0, q - s / 2.0, p + r / 2.0, q + s / 2.0)
+            },
+        };
+
+        // Some models don't guarantee the corners are in order
+        Rect {
+            a_min: a0.min(a1),
+            b_min: b0.min(b1),
+            a_max: a0.max(a1),
+            b_max: b0.max(b1),
+        }
+    }
+
+    fn area(&self) -> f32 {
+        (self.a_max - self.a_min) * (self.b_max - self.b_min)
+    }
+
+    /// The intersection-over-union between two rectangles.
+    fn iou(&self, other: &Rect) -> f32 {
+        let a = self.a_max.min(other.a_max) - self.a_min.max(other.a_min);
+        let b = self.b_max.min(other.b_max) - self.b_min.max(other.b_min);
+        let intersection = a.max(0.0) * b.max(0.0);
+        let union = self.area() + other.area() - intersection;
+
+        if union <= 0.0 {
+            0.0
+        } else {
+            intersection / union
+        }
+    }
+}
+
+fn is_fraction(value: &f32) -> Result<(), &'static str> {
+    if (0.0..=1.0).contains(value) {
+        Ok(())
+    } else {
+        Err("must be between 0 and 1")
+    }
+}
+
+fn is_positive(value: &usize) -> Result<(), &'static str> {
+    if *value > 0 {
+        Ok(())
+    } else {
+        Err("must be greater than zero")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+
+    fn nms(max_detections: &str) -> NonMaxSuppression {
+        let mut proc_block = NonMaxSuppression::default();
+        proc_block.set_max_detections(max_detections).unwrap();
+        proc_block
+    }
+
+    fn boxes(values: &[[f32; 4]]) -> Tensor<f32> {
+        let elements: Vec<f32> = values.iter().flatten().copied().collect();
+        Tensor::new_row_major(elements.into(), vec![1, values.len(), 4])
+    }
+
+    fn scores(values: &[f32], classes: usize) -> Tensor<f32> {
+        Tensor::new_row_major(
+            values.into(),
+            vec![1, values.len() / classes, classes],
+        )
+    }
+
+    #[test]
+    fn overlapping_boxes_are_suppressed() {
+        let mut proc_block = nms("3");
+        let input_boxes = boxes(&[
+            [0.0, 0.0, 1.0, 1.0],
+            [0.0, 0.1, 1.0, 1.1],
+            [0.0, 2.0, 1.0, 3.0],
+        ]);
+
+        let (got_boxes, classes, confidences, count) =
+            proc_block.transform((input_boxes, scores(&[0.8, 0.9, 0.7], 1)));
+
+        assert_eq!(
+            got_boxes,
+            boxes(&[
+                [0.0, 0.1, 1.0, 1.1],
+                [0.0, 2.0, 1.0, 3.0],
+                [0.0, 0.0, 0.0, 0.0],
+            ])
+        );
+        assert_eq!(classes.elements(), &[0.0, 0.0, 0.0]);
+        assert_eq!(confidences.elements(), &[0.9, 0.7, 0.0]);
+        assert_eq!(count.elements(), &[2.0]);
+    }
+
+    #[test]
+    fn different_classes_dont_suppress_each_other() {
+        let mut proc_block = nms("4");
+        let input_boxes = boxes(&[[0.0, 0.0, 1.0, 1.0], [0.0, 0.0, 1.0, 1.05]]);
+        let input_scores = scores(&[0.9, 0.1, 0.6, 0.8], 2);
+
+        let (_, classes, confidences, count) =
+            proc_block.transform((input_boxes, input_scores));
+
+        assert_eq!(classes.elements(), &[0.0, 1.0, 0.0, 0.0]);
+        assert_eq!(confidences.elements(), &[0.9, 0.8, 0.0, 0.0]);
+        assert_eq!(count.elements(), &[2.0]);
+    }
+
+    #[test]
+    fn low_scores_are_ignored() {
+        let mut proc_block = nms("2");
+        proc_block.set_score_threshold("0.75").unwrap();
+        let input_boxes = boxes(&[[0.0, 0.0, 1.0, 1.0], [5.0, 5.0, 6.0, 6.0]]);
+
+        let (_, _, confidences, count) =
+            proc_block.transform((input_boxes, scores(&[0.7, f32::NAN], 1)));
+
+        assert_eq!(confidences.elements(), &[0.0, 0.0]);
+        assert_eq!(count.elements(), &[0.0]);
+    }
+
+    #[test]
+    fn only_keep_max_detections() {
+        let mut proc_block = nms("1");
+        let input_boxes = boxes(&[[0.0, 0.0, 1.0, 1.0], [5.0, 5.0, 6.0, 6.0]]);
+
+        let (got_boxes, _, confidences, count) =
+            proc_block.transform((input_boxes, scores(&[0.6, 0.9], 1)));
+
+        assert_eq!(got_boxes, boxes(&[[5.0, 5.0, 6.0, 6.0]]));
+        assert_eq!(confidences.elements(), &[0.9]);
+        assert_eq!(count.elements(), &[1.0]);
+    }
+
+    #[test]
+    fn no_boxes_means_no_detections() {
+        let mut proc_block = nms("2");
+        let input_boxes = Tensor::new_row_major(vec![].into(), vec![1, 0, 4]);
+        let input_scores = Tensor::new_row_major(vec![].into(), vec![1, 0]);
+
+        let (got_boxes, _, confidences, count) =
+            proc_block.transform((input_boxes, input_scores));
+
+        assert_eq!(got_boxes.elements(), &[0.0; 8]);
+        assert_eq!(confidences.elements(), &[0.0, 0.0]);
+        assert_eq!(count.elements(), &[0.0]);
+    }
+
+    #[test]
+    fn malformed_inputs_are_reported() {
+        let proc_block = nms("2");
+
+        assert_eq!(
+            proc_block.suppress(&[0.0, 0.0, 1.0], &[0.9]),
+            Err(ShapeError::IncompleteBox { values: 3 })
+        );
+        assert_eq!(
+            proc_block.suppress(
+                &[0.0, 0.0, 1.0, 1.0, 5.0, 5.0, 6.0, 6.0],
+                &[0.9, 0.8, 0.7]
+            ),
+            Err(ShapeError::MismatchedScores {
+                boxes: 2,
+                scores: 3
+            })
+        );
+        assert_eq!(
+            proc_block.suppress(&[], &[0.9]),
+            Err(ShapeError::MismatchedScores {
+                boxes: 0,
+                scores: 1
+            })
+        );
+    }
+
+    #[test]
+    fn malformed_inputs_output_no_detections() {
+        let mut proc_block = nms("2");
+        let input_boxes = boxes(&[[0.0, 0.0, 1.0, 1.0], [5.0, 5.0, 6.0, 6.0]]);
+        let input_scores = Tensor::new_vector(vec![0.9, 0.8, 0.7]);
+
+        let (_, _, confidences, count) =
+            proc_block.transform((input_boxes, input_scores));
+
+        assert_eq!(confidences.elements(), &[0.0, 0.0]);
+        assert_eq!(count.elements(), &[0.0]);
+    }
+
+    #[test]
+    fn center_and_corner_boxes_are_equivalent() {
+        let corners = Rect::new(BoxFormat::Corners, [1.0, 2.0, 3.0, 6.0]);
+        let center = Rect::new(BoxFormat::Center, [2.0, 4.0, 2.0, 4.0]);
+
+        assert_eq!(corners, center);
+    }
+
+    #[test]
+    fn intersection_over_union() {
+        let a = Rect::new(BoxFormat::Corners, [0.0, 0.0, 2.0, 2.0]);
+        let b = Rect::new(BoxFormat::Corners, [1.0, 1.0, 3.0, 3.0]);
+        let c = Rect::new(BoxFormat::Corners, [5.0, 5.0, 6.0, 6.0]);
+
+        assert_eq!(a.iou(&a), 1.0);
+        assert_eq!(a.iou(&b), 1.0 / 7.0);
+        assert_eq!(a.iou(&c), 0.0);
+    }
+
+    #[test]
+    fn boxes_are_only_suppressed_above_the_iou_threshold() {
+        // The second box is twice the size of the first and contains it, so
+        // they have an IoU of exactly 0.5
+        let input_boxes = [[0.0, 0.0, 1.0, 1.0], [0.0, 0.0, 1.0, 2.0]];
+        let input_scores = [0.9, 0.8];
+        let mut at_threshold = nms("2");
+        let mut below_threshold = nms("2");
+        below_threshold.set_iou_threshold("0.49").unwrap();
+
+        let (_, _, _, kept) = at_threshold
+            .transform((boxes(&input_boxes), scores(&input_scores, 1)));
+        let (_, _, _, suppressed) = below_threshold
+            .transform((boxes(&input_boxes), scores(&input_scores, 1)));
+
+        assert_eq!(kept.elements(), &[2.0]);
+        assert_eq!(suppressed.elements(), &[1.0]);
+    }
+
+    #[test]
+    fn corners_can_be_in_any_order() {
+        let mut proc_block = nms("2");
+        let input_boxes = boxes(&[[0.0, 0.0, 1.0, 1.0], [1.0, 1.0, 0.0, 0.0]]);
+
+        let (got_boxes, _, confidences, count) =
+            proc_block.transform((input_boxes, scores(&[0.8, 0.9], 1)));
+
+        // The boxes are identical, so the less confident one is suppressed
+        assert_eq!(
+            got_boxes,
+            boxes(&[[1.0, 1.0, 0.0, 0.0], [0.0, 0.0, 0.0, 0.0]])
+        );
+        assert_eq!(confidences.elements(), &[0.9, 0.0]);
+        assert_eq!(count.elements(), &[1.0]);
+    }
+
+    #[test]
+    fn empty_boxes_never_overlap() {
+        let mut proc_block = nms("2");
+        proc_block.set_iou_threshold("0").unwrap();
+        // Both boxes have no area, so the IoU would be 0 / 0
+        let input_boxes = boxes(&[[1.0, 1.0, 1.0, 1.0], [1.0, 1.0, 1.0, 1.0]]);
+
+        let (_, _, confidences, count) =
+            proc_block.transform((input_boxes, scores(&[0.9, 0.8], 1)));
+
+        assert_eq!(confidences.elements(), &[0.9, 0.8]);
+        assert_eq!(count.elements(), &[2.0]);
+    }
+}
@@ -0,0 +1,421 @@
+//! A proc block which rescales a tensor's values, either by mapping them
+//! onto `[0, 1]` (min-max scaling) or by subtracting the mean and dividing by
+//! the standard deviation (standardization).
+//!
+//! The last dimension is treated as the channel (e.g. the `3` in a
+//! `[1, 224, 224, 3]` RGB image or a `[1, 128, 3]` accelerometer reading), and
+//! each channel can be given its own parameters. A 1D tensor only has one
+//! channel. Parameters which aren't provided are calculated from the data.
+//!
+//! ```yaml
+//! normalize:
+//!   proc-block: "hotg-ai/rune#proc-blocks/normalize"
+//!   inputs:
+//!     - image
+//!   outputs:
+//!     - type: F32
+//!       dimensions: [1, 224, 224, 3]
+//!   args:
+//!     mode: standard
+//!     mean: 123.675, 116.28, 103.53
+//!     std-dev: 58.395, 57.12, 57.375
+//! ```
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::{string::String, vec::Vec};
+use core::{
+    fmt::{self, Display, Formatter},
+    num::ParseFloatError,
+    str::FromStr,
+};
+
+use hotg_rune_proc_blocks::{ProcBlock, Tensor, Transform};
+
+/// Rescale a tensor's values, channel by channel.
+#[derive(Debug, Default, Clone, PartialEq, ProcBlock)]
+#[transform(inputs = [u8; _], outputs = [f32; _])]
+#[transform(inputs = [i8; _], outputs = [f32; _])]
+#[transform(inputs = [u16; _], outputs = [f32; _])]
+#[transform(inputs = [i16; _], outputs = [f32; _])]
+#[transform(inputs = [u32; _], outputs = [f32; _])]
+#[transform(inputs = [i32; _], outputs = [f32; _])]
+#[transform(inputs = [f32; _], outputs = [f32; _])]
+pub struct Normalize {
+    /// How values are rescaled (`min-max` or `standard`).
+    mode: Mode,
+    /// The smallest value for each channel when using `min-max`.
+    min: PerChannel,
+    /// The largest value for each channel when using `min-max`.
+    max: PerChannel,
+    /// The mean for each channel when using `standard`.
+    mean: PerChannel,
+    /// The standard deviation for each channel when using `standard`.
+    #[proc_block(validate = all_positive)]
+    std_dev: PerChannel,
+}
+
+impl Normalize {
+    fn normalize(&self, values: &[f64], dimensions: &[usize]) -> Tensor<f32> {
+        let channels = match dimensions {
+            [_, .., last] => (*last).max(1),
+            _ => 1,
+        };
+
+        let (offsets, scales): (Vec<f64>, Vec<f64>) = match self.mode {
+            Mode::MinMax => {
+                let min = self.min.resolve("min", channels, || {
+                    per_channel_stats(values, channels).min
+                });
+                let max = self.max.resolve("max", channels, || {
+                    per_channel_stats(values, channels).max
+                });
+                min.iter().zip(&max).map(|(&lo, &hi)| (lo, hi - lo)).unzip()
+            },
+            Mode::Standard => {
+                let mean = self.mean.resolve("mean", channels, || {
+                    per_channel_stats(values, channels).mean
+                });
+                let std_dev = self.std_dev.resolve("std_dev", channels, || {
+                    per_channel_stats(values, channels).std_dev
+                });
+                (mean, std_dev)
+            },
+        };
+
+        let elements: Vec<f32> = values
+            .iter()
+            .enumerate()
+            .map(|(i, &value)| {
+                let channel = i % channels;
+                let scale = scales[channel];
+
+                if scale == 0.0 {
+                    // Every value in this channel is the same
+                    0.0
+                } else {
+                    ((value - offsets[channel]) / scale) as f32
+                }
+            })
+            .collect();
+
+        Tensor::new_row_major(elements.into(), dimensions.to_vec())
+    }
+}
+
+impl<T> Transform<Tensor<T>> for Normalize
+where
+    T: Copy + Into<f64>,
+{
+    type Output = Tensor<f32>;
+
+    fn transform(&mut self, input: Tensor<T>) -> Self::Output {
+        let values: Vec<f64> =
+            input.elements().iter().map(|&x| x.into()).collect();
+
+        self.normalize(&values, input.dimensions())
+    }
+}
+
+/// How a [`Normalize`] rescales values.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Mode {
+    /// Map each channel's values onto `[0, 1]` using
+    /// `(x - min) / (max - min)`.
+    MinMax,
+    /// Give each channel a mean of `0` and a standard deviation of `1` using
+    /// `(x - mean) / std_dev`.
+    Standard,
+}
+
+impl Default for Mode {
+    fn default() -> Self { Mode::MinMax }
+}
+
+impl FromStr for Mode {
+    type Err = UnknownMode;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "min-max" => Ok(Mode::MinMax),
+            "standard" => Ok(Mode::Standard),
+            _ => Err(UnknownMode { name: s.into() }),
+        }
+    }
+}
+
+impl Display for Mode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Mode::MinMax => write!(f, "min-max"),
+            Mode::Standard => write!(f, "standard"),
+        }
+    }
+}
+
+/// The error returned when parsing an unknown [`Mode`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownMode {
+    pub name: String,
+}
+
+impl Display for UnknownMode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Expected \"min-max\" or \"standard\", but found \"{}\"",
+            self.name
+        )
+    }
+}
+
+impl hotg_rune_core::Error for UnknownMode {}
+
+/// A parameter with either one value for every channel, a single value which
+/// is used for all channels, or no values (meaning it should be calculated
+/// from the data).
+///
+/// It is parsed from a comma-separated list (e.g. `"0.485, 0.456, 0.406"`).
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct PerChannel(pub Vec<f64>);
+
+impl PerChannel {
+    fn resolve(
+        &self,
+        name: &str,
+        channels: usize,
+        calculate: impl FnOnce() -> Vec<f64>,
+    ) -> Vec<f64> {
+        match self.0.as_slice() {
+            [] => calculate(),
+            [value] => alloc::vec![*value; channels],
+            values if values.len() == channels => values.to_vec(),
+            values => panic!(
+                "Expected 1 or {} values for \"{}\", but found {}",
+                channels,
+                name,
+                values.len()
+            ),
+        }
+    }
+}
+
+impl FromStr for PerChannel {
+    type Err = ParseFloatError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split(',')
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .map(str::parse)
+            .collect::<Result<Vec<_>, _>>()
+            .map(PerChannel)
+    }
+}
+
+impl Display for PerChannel {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (i, value) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", value)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn all_positive(values: &PerChannel) -> Result<(), &'static str> {
+    if values.0.iter().all(|&value| value > 0.0) {
+        Ok(())
+    } else {
+        Err("every value must be greater than zero")
+    }
+}
+
+struct Stats {
+    min: Vec<f64>,
+    max: Vec<f64>,
+    mean: Vec<f64>,
+    std_dev: Vec<f64>,
+}
+
+fn per_channel_stats(values: &[f64], channels: usize) -> Stats {
+    let mut min = alloc::vec![f64::INFINITY; channels];
+    let mut max = alloc::vec![f64::NEG_INFINITY; channels];
+    let mut sum = alloc::vec![0.0; channels];
+    let mut sum_of_squares = alloc::vec![0.0; channels];
+    let mut counts = alloc::vec![0_usize; channels];
+
+    for (i, &value) in values.iter().enumerate() {
+        let channel = i % channels;
+
+        min[channel] = value.min(min[channel]);
+        max[channel] = value.max(max[channel]);
+        sum[channel] += value;
+        sum_of_squares[channel] += value * value;
+        counts[channel] += 1;
+    }
+
+    let mean: Vec<f64> = sum
+        .iter()
+        .zip(&counts)
+        .map(|(&sum, &n)| if n == 0 { 0.0 } else { sum / n as f64 })
+        .collect();
+    let std_dev = sum_of_squares
+        .iter()
+        .zip(&counts)
+        .zip(&mean)
+        .map(|((&squares, &n), &mean)| {
+            if n == 0 {
+                0.0
+            } else {
+                let variance = squares / n as f64 - mean * mean;
+                libm::sqrt(variance.max(0.0))
+            }
+        })
+        .collect();
+
+    Stats {
+        min,
+        max,
+        mean,
+        std_dev,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{string::ToString, vec};
+
+    use super::*;
+
+    #[test]
+    fn min_max_scaling_from_the_data() {
+        let mut proc_block = Normalize::default();
+        let input: Tensor<u8> = [[0, 100], [50, 200], [100, 150]].into();
+
+        let got = proc_block.transform(input);
+
+        let should_be: Tensor<f32> =
+            [[0.0, 0.0], [0.5, 1.0], [1.0, 0.5]].into();
+        assert_eq!(got, should_be);
+    }
+
+    #[test]
+    fn min_max_scaling_with_fixed_bounds() {
+        let mut proc_block = Normalize::default();
+        proc_block.set_min("0").unwrap();
+        proc_block.set_max("255").unwrap();
+        let input = Tensor::new_vector(vec![0_u8, 51, 255]);
+
+        let got = proc_block.transform(input);
+
+        assert_eq!(got, Tensor::new_vector(vec![0.0_f32, 0.2, 1.0]));
+    }
+
+    #[test]
+    fn standardize_each_channel() {
+        let mut proc_block = Normalize::default();
+        proc_block.set_mode("standard").unwrap();
+        proc_block.set_mean("10, 20").unwrap();
+        proc_block.set_std_dev("2, 5").unwrap();
+        let input: Tensor<f32> = [[10.0, 20.0], [14.0, 10.0]].into();
+
+        let got = proc_block.transform(input);
+
+        let should_be: Tensor<f32> = [[0.0, 0.0], [2.0, -2.0]].into();
+        assert_eq!(got, should_be);
+    }
+
+    #[test]
+    fn standardize_using_the_data() {
+        let mut proc_block = Normalize::default();
+        proc_block.set_mode("standard").unwrap();
+        let input = Tensor::new_vector(vec![2_i16, 4, 4, 4, 5, 5, 7, 9]);
+
+        let got = proc_block.transform(input);
+
+        let should_be = Tensor::new_vector(vec![
+            -1.5_f32, -0.5, -0.5, -0.5, 0.0, 0.0, 1.0, 2.0,
+        ]);
+        assert_eq!(got, should_be);
+    }
+
+    #[test]
+    fn constant_channels_become_zero() {
+        let mut proc_block = Normalize::default();
+        let input = Tensor::new_vector(vec![3.0_f32, 3.0, 3.0]);
+
+        let got = proc_block.transform(input);
+
+        assert_eq!(got, Tensor::new_vector(vec![0.0_f32; 3]));
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Expected 1 or 2 values for \"mean\", but found 3"
+    )]
+    fn parameters_must_match_the_channel_count() {
+        let mut proc_block = Normalize::default();
+        proc_block.set_mode("standard").unwrap();
+        proc_block.set_mean("1, 2, 3").unwrap();
+
+        proc_block.transform(Tensor::<f32>::from([[1.0, 2.0]]));
+    }
+
+    #[test]
+    fn parse_per_channel_values() {
+        let values: PerChannel = " 0.485, 0.456,0.406 ".parse().unwrap();
+
+        assert_eq!(values, PerChannel(vec![0.485, 0.456, 0.406]));
+        assert_eq!(values.to_string(), "0.485, 0.456, 0.406");
+        assert_eq!("".parse::<PerChannel>().unwrap(), PerChannel::default());
+        assert!("1, two".parse::<PerChannel>().is_err());
+    }
+
+    #[test]
+    fn a_single_value_is_used_for_every_channel() {
+        let mut proc_block = Normalize::default();
+        proc_block.set_mode("standard").unwrap();
+        proc_block.set_mean("128").unwrap();
+        proc_block.set_std_dev("64").unwrap();
+        let input: Tensor<u8> = [[0, 128, 192], [255, 64, 128]].into();
+
+        let got = proc_block.transform(input);
+
+        let should_be: Tensor<f32> =
+            [[-2.0, 0.0, 1.0], [1.984375, -1.0, 0.0]].into();
+        assert_eq!(got, should_be);
+    }
+
+    #[test]
+    fn values_outside_fixed_bounds_arent_clamped() {
+        let mut proc_block = Normalize::default();
+        proc_block.set_min("0").unwrap();
+        proc_block.set_max("100").unwrap();
+        let input = Tensor::new_vector(vec![-50_i32, 100, 150]);
+
+        let got = proc_block.transform(input);
+
+        assert_eq!(got, Tensor::new_vector(vec![-0.5_f32, 1.0, 1.5]));
+    }
+
+    #[test]
+    fn standard_deviations_must_be_positive() {
+        let mut proc_block = Normalize::default();
+
+        let err = proc_block.set_std_dev("1, 0").unwrap_err();
+
+        // Dividing by zero would turn every value into NaN or infinity
+        assert_eq!(
+            err.to_string(),
+            "Unable to set \"std_dev\" to \"1, 0\": every value must be \
+             greater than zero"
+        );
+        assert_eq!(proc_block, Normalize::default());
+    }
+}
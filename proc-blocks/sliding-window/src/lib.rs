@@ -0,0 +1,354 @@
+//! A stateful proc block which accumulates samples across calls and outputs
+//! overlapping windows, for models that look at a longer stretch of audio or
+//! sensor data than a capability provides at a time.
+//!
+//! Inputs are expected to have dimensions like `[1, samples]` or
+//! `[1, samples, channels]` (e.g. a 3-axis accelerometer) and the output has
+//! the same dimensions, except with `size` samples. A new window is started
+//! every `stride` samples, so successive windows overlap by
+//! `size - stride` samples.
+//!
+//! ```yaml
+//! window:
+//!   proc-block: "hotg-ai/rune#proc-blocks/sliding-window"
+//!   inputs:
+//!     - accelerometer
+//!   outputs:
+//!     - type: F32
+//!       dimensions: [1, 128, 3]
+//!   args:
+//!     size: 128
+//!     stride: 64
+//!     padding: zeros
+//! ```
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::{boxed::Box, collections::VecDeque, string::String, vec::Vec};
+use core::{
+    any::Any,
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
+
+use hotg_rune_proc_blocks::{ProcBlock, Tensor, Transform};
+
+/// Buffer incoming samples into overlapping windows.
+///
+/// Until enough samples have arrived, the start of the window is filled
+/// according to the `padding` policy.
+#[derive(Debug, ProcBlock)]
+#[transform(inputs = [u8; _], outputs = [u8; _])]
+#[transform(inputs = [i16; _], outputs = [i16; _])]
+#[transform(inputs = [i32; _], outputs = [i32; _])]
+#[transform(inputs = [f32; _], outputs = [f32; _])]
+pub struct SlidingWindow {
+    /// The number of samples in each window.
+    #[proc_block(validate = is_positive)]
+    size: usize,
+    /// The number of new samples between the start of one window and the
+    /// next.
+    #[proc_block(validate = is_positive)]
+    stride: usize,
+    /// What to fill the window with before enough samples have arrived
+    /// (`zeros` or `edge`).
+    padding: Padding,
+    /// The samples we've seen so far, stored as a `History<T>`.
+    #[proc_block(skip)]
+    history: Option<Box<dyn Any>>,
+}
+
+impl SlidingWindow {
+    /// Forget any samples we've seen so far.
+    pub fn reset(&mut self) { self.history = None; }
+
+    fn history<T: Copy + Default + 'static>(
+        &mut self,
+        channels: usize,
+    ) -> &mut History<T> {
+        let is_compatible = match &self.history {
+            Some(history) => history
+                .downcast_ref::<History<T>>()
+                .map(|h| h.channels == channels)
+                .unwrap_or(false),
+            None => false,
+        };
+
+        if !is_compatible {
+            // The input changed shape or type, so start again
+            self.history = Some(Box::new(History::<T>::new(channels)));
+        }
+
+        self.history
+            .as_mut()
+            .and_then(|history| history.downcast_mut())
+            .expect("Just initialized")
+    }
+}
+
+impl Default for SlidingWindow {
+    fn default() -> Self {
+        SlidingWindow {
+            size: 1,
+            stride: 1,
+            padding: Padding::default(),
+            history: None,
+        }
+    }
+}
+
+impl<T> Transform<Tensor<T>> for SlidingWindow
+where
+    T: Copy + Default + 'static,
+{
+    type Output = Tensor<T>;
+
+    fn transform(&mut self, input: Tensor<T>) -> Self::Output {
+        let mut dimensions = input.dimensions().to_vec();
+        let sample_axis = if dimensions.len() == 1 { 0 } else { 1 };
+        let channels: usize = dimensions[sample_axis + 1..].iter().product();
+
+        let (size, stride, padding) = (self.size, self.stride, self.padding);
+        let history = self.history::<T>(channels);
+        history.push(input.elements(), size + stride - 1);
+        let window = history.window(size, stride, padding);
+
+        dimensions[sample_axis] = size;
+        Tensor::new_row_major(window.into(), dimensions)
+    }
+}
+
+/// The most recent samples, flattened so each sample is `channels`
+/// elements.
+#[derive(Debug)]
+struct History<T> {
+    elements: VecDeque<T>,
+    channels: usize,
+    /// The total number of samples we've received.
+    received: usize,
+}
+
+impl<T: Copy + Default> History<T> {
+    fn new(channels: usize) -> Self {
+        History {
+            elements: VecDeque::new(),
+            channels,
+            received: 0,
+        }
+    }
+
+    fn samples(&self) -> usize { self.elements.len() / self.channels.max(1) }
+
+    /// Add new samples, only keeping the last `capacity` samples.
+    fn push(&mut self, elements: &[T], capacity: usize) {
+        self.elements.extend(elements);
+        self.received += elements.len() / self.channels.max(1);
+
+        let excess = self.samples().saturating_sub(capacity);
+        self.elements.drain(..excess * self.channels);
+    }
+
+    /// Get the most recent window which started on a multiple of `stride`.
+    ///
+    /// Note: as long as we keep at least `size + stride - 1` samples, the
+    /// window's samples will always be in our history.
+    fn window(&self, size: usize, stride: usize, padding: Padding) -> Vec<T> {
+        let end = self.received - self.received % stride;
+        let oldest = self.received - self.samples();
+        let pad = match padding {
+            Padding::Zeros => None,
+            // the first sample is only needed while the window still
+            // contains it, by which time it's guaranteed to be in history
+            Padding::Edge if end > 0 && end <= size => Some(0),
+            Padding::Edge => None,
+        };
+
+        let mut window = Vec::with_capacity(size * self.channels);
+
+        for i in end as isize - size as isize..end as isize {
+            let sample = if i >= 0 { Some(i as usize) } else { pad };
+
+            match sample {
+                Some(sample) => {
+                    let start = (sample - oldest) * self.channels;
+                    window.extend(
+                        self.elements.range(start..start + self.channels),
+                    );
+                },
+                None => window.extend(
+                    core::iter::repeat(T::default()).take(self.channels),
+                ),
+            }
+        }
+
+        window
+    }
+}
+
+/// How to fill a window before enough samples have arrived.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Padding {
+    /// Use zeros.
+    Zeros,
+    /// Repeat the first sample.
+    Edge,
+}
+
+impl Default for Padding {
+    fn default() -> Self { Padding::Zeros }
+}
+
+impl FromStr for Padding {
+    type Err = UnknownPadding;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "zeros" => Ok(Padding::Zeros),
+            "edge" => Ok(Padding::Edge),
+            _ => Err(UnknownPadding { name: s.into() }),
+        }
+    }
+}
+
+impl Display for Padding {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Padding::Zeros => write!(f, "zeros"),
+            Padding::Edge => write!(f, "edge"),
+        }
+    }
+}
+
+/// The error returned when parsing an unknown [`Padding`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownPadding {
+    pub name: String,
+}
+
+impl Display for UnknownPadding {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Expected \"zeros\" or \"edge\", but found \"{}\"",
+            self.name
+        )
+    }
+}
+
+impl hotg_rune_core::Error for UnknownPadding {}
+
+fn is_positive(value: &usize) -> Result<(), &'static str> {
+    if *value > 0 {
+        Ok(())
+    } else {
+        Err("must be greater than zero")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+
+    fn sliding_window(
+        size: &str,
+        stride: &str,
+        padding: &str,
+    ) -> SlidingWindow {
+        let mut proc_block = SlidingWindow::default();
+        proc_block.set_size(size).unwrap();
+        proc_block.set_stride(stride).unwrap();
+        proc_block.set_padding(padding).unwrap();
+        proc_block
+    }
+
+    fn samples(values: &[i16]) -> Tensor<i16> {
+        Tensor::new_row_major(values.into(), vec![1, values.len()])
+    }
+
+    #[test]
+    fn accumulate_overlapping_windows() {
+        let mut proc_block = sliding_window("4", "2", "zeros");
+
+        let got = proc_block.transform(samples(&[1, 2, 3]));
+        assert_eq!(got, samples(&[0, 0, 1, 2]));
+
+        let got = proc_block.transform(samples(&[4]));
+        assert_eq!(got, samples(&[1, 2, 3, 4]));
+
+        let got = proc_block.transform(samples(&[5]));
+        assert_eq!(got, samples(&[1, 2, 3, 4]));
+
+        let got = proc_block.transform(samples(&[6, 7, 8, 9]));
+        assert_eq!(got, samples(&[5, 6, 7, 8]));
+    }
+
+    #[test]
+    fn edge_padding_repeats_the_first_sample() {
+        let mut proc_block = sliding_window("4", "1", "edge");
+
+        let got = proc_block.transform(samples(&[7]));
+        assert_eq!(got, samples(&[7, 7, 7, 7]));
+
+        let got = proc_block.transform(samples(&[8, 9]));
+        assert_eq!(got, samples(&[7, 7, 8, 9]));
+
+        let got = proc_block.transform(samples(&[10, 11]));
+        assert_eq!(got, samples(&[8, 9, 10, 11]));
+    }
+
+    #[test]
+    fn nothing_received_yet() {
+        let mut proc_block = sliding_window("3", "2", "edge");
+
+        let got = proc_block.transform(samples(&[1]));
+
+        assert_eq!(got, samples(&[0, 0, 0]));
+    }
+
+    #[test]
+    fn each_sample_can_have_several_channels() {
+        let mut proc_block = sliding_window("2", "1", "zeros");
+        let input = Tensor::new_row_major(
+            vec![1.0_f32, 2.0, 3.0, 4.0, 5.0, 6.0].into(),
+            vec![1, 2, 3],
+        );
+
+        let got = proc_block.transform(input);
+        assert_eq!(got.dimensions(), &[1, 2, 3]);
+        assert_eq!(got.elements(), &[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+
+        let input = Tensor::new_row_major(
+            vec![7.0_f32, 8.0, 9.0].into(),
+            vec![1, 1, 3],
+        );
+        let got = proc_block.transform(input);
+        assert_eq!(got.elements(), &[4.0, 5.0, 6.0, 7.0, 8.0, 9.0]);
+    }
+
+    #[test]
+    fn history_is_bounded() {
+        let mut proc_block = sliding_window("4", "3", "zeros");
+
+        for i in 0..100 {
+            proc_block.transform(samples(&[i]));
+        }
+
+        let history = proc_block.history::<i16>(1);
+        assert_eq!(history.samples(), 4 + 3 - 1);
+        assert_eq!(history.received, 100);
+    }
+
+    #[test]
+    fn changing_the_input_type_starts_again() {
+        let mut proc_block = sliding_window("2", "1", "zeros");
+        proc_block.transform(samples(&[1, 2]));
+
+        let got = proc_block.transform(Tensor::new_vector(vec![3_u8]));
+
+        assert_eq!(got, Tensor::new_vector(vec![0, 3]));
+    }
+}
@@ -0,0 +1,181 @@
+//! A small radix-2 Fast Fourier Transform.
+
+use core::f32::consts::PI;
+
+use hotg_rune_core::Complex;
+
+/// Calculate the discrete Fourier transform of `buffer` in place.
+///
+/// # Panics
+///
+/// The buffer's length must be a power of two.
+pub fn fft(buffer: &mut [Complex<f32>]) {
+    let len = buffer.len();
+    assert!(
+        len.is_power_of_two(),
+        "The FFT's length must be a power of two, not {}",
+        len
+    );
+
+    bit_reverse_permutation(buffer);
+
+    let mut size = 2;
+
+    while size <= len {
+        let half = size / 2;
+        let step = -2.0 * PI / size as f32;
+
+        for chunk in buffer.chunks_exact_mut(size) {
+            let (evens, odds) = chunk.split_at_mut(half);
+
+            for (k, (even, odd)) in evens.iter_mut().zip(odds).enumerate() {
+                let angle = step * k as f32;
+                let twiddle =
+                    Complex::new(libm::cosf(angle), libm::sinf(angle));
+                let t = twiddle * *odd;
+
+                *odd = *even - t;
+                *even += t;
+            }
+        }
+
+        size *= 2;
+    }
+}
+
+fn bit_reverse_permutation(buffer: &mut [Complex<f32>]) {
+    let bits = buffer.len().trailing_zeros();
+
+    if bits == 0 {
+        return;
+    }
+
+    for i in 0..buffer.len() {
+        let j = i.reverse_bits() >> (usize::BITS - bits);
+
+        if i < j {
+            buffer.swap(i, j);
+        }
+    }
+}
+
+/// Calculate the magnitude of each frequency bin for a frame of real-valued
+/// samples, zero-padding the frame to `fft_size`.
+///
+/// Only the first `fft_size / 2 + 1` bins are returned because the rest are
+/// a mirror image of them.
+pub fn magnitudes(frame: &[f32], fft_size: usize) -> impl Iterator<Item = f32> {
+    assert!(
+        frame.len() <= fft_size,
+        "A frame with {} samples won't fit in an FFT of size {}",
+        frame.len(),
+        fft_size
+    );
+
+    let mut buffer = alloc::vec![Complex::new(0.0, 0.0); fft_size];
+
+    for (bin, &sample) in buffer.iter_mut().zip(frame) {
+        bin.re = sample;
+    }
+
+    fft(&mut buffer);
+
+    buffer
+        .into_iter()
+        .take(fft_size / 2 + 1)
+        .map(|c| libm::sqrtf(c.re * c.re + c.im * c.im))
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::*;
+
+    fn assert_close(left: &[f32], right: &[f32]) {
+        assert_eq!(left.len(), right.len());
+
+        for (i, (l, r)) in left.iter().zip(right).enumerate() {
+            assert!(
+                (l - r) * (l - r) < 1e-8,
+                "{:?} != {:?} (at {})",
+                left,
+                right,
+                i
+            );
+        }
+    }
+
+    /// A naive O(n²) DFT to compare against.
+    fn dft(input: &[Complex<f32>]) -> Vec<Complex<f32>> {
+        let n = input.len();
+
+        (0..n)
+            .map(|k| {
+                input.iter().enumerate().fold(
+                    Complex::new(0.0, 0.0),
+                    |sum, (t, &x)| {
+                        let angle = -2.0 * PI * (k * t) as f32 / n as f32;
+                        sum + x * Complex::new(
+                            libm::cosf(angle),
+                            libm::sinf(angle),
+                        )
+                    },
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn impulse_has_a_flat_spectrum() {
+        let mut buffer = alloc::vec![Complex::new(0.0, 0.0); 8];
+        buffer[0].re = 1.0;
+
+        fft(&mut buffer);
+
+        assert!(buffer.iter().all(|&c| c == Complex::new(1.0, 0.0)));
+    }
+
+    #[test]
+    fn matches_the_naive_dft() {
+        let input: Vec<_> = (0..16)
+            .map(|i| Complex::new(libm::sinf(i as f32), (i % 3) as f32))
+            .collect();
+        let should_be = dft(&input);
+
+        let mut got = input.clone();
+        fft(&mut got);
+
+        let flatten = |values: &[Complex<f32>]| -> Vec<f32> {
+            values.iter().flat_map(|c| [c.re, c.im]).collect()
+        };
+        assert_close(&flatten(&got), &flatten(&should_be));
+    }
+
+    #[test]
+    fn sine_wave_peaks_at_its_frequency() {
+        let fft_size = 64;
+        let frame: Vec<f32> = (0..fft_size)
+            .map(|i| libm::sinf(2.0 * PI * 4.0 * i as f32 / fft_size as f32))
+            .collect();
+
+        let got: Vec<f32> = magnitudes(&frame, fft_size).collect();
+
+        assert_eq!(got.len(), fft_size / 2 + 1);
+        let peak = got
+            .iter()
+            .enumerate()
+            .max_by(|(_, l), (_, r)| l.partial_cmp(r).unwrap())
+            .unwrap()
+            .0;
+        assert_eq!(peak, 4);
+        let error = got[4] - fft_size as f32 / 2.0;
+        assert!(error * error < 1e-6);
+    }
+
+    #[test]
+    #[should_panic]
+    fn length_must_be_a_power_of_two() {
+        fft(&mut [Complex::new(0.0, 0.0); 6]);
+    }
+}
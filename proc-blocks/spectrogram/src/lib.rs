@@ -0,0 +1,302 @@
+//! A proc block which turns audio into a magnitude spectrogram.
+//!
+//! The audio is split into overlapping frames, each frame is multiplied by a
+//! window function, and the magnitude of its FFT is calculated. A `[1, N]`
+//! tensor of samples becomes a `[1, frames, fft_size / 2 + 1]` tensor, where
+//! `frames` is `1 + (N - window_size) / hop_size`.
+//!
+//! ```yaml
+//! spectrogram:
+//!   proc-block: "hotg-ai/rune#proc-blocks/spectrogram"
+//!   inputs:
+//!     - audio
+//!   outputs:
+//!     - type: F32
+//!       dimensions: [1, 98, 257]
+//!   args:
+//!     window-size: 480
+//!     hop-size: 160
+//!     fft-size: 512
+//!     window: hann
+//! ```
+
+#![no_std]
+
+extern crate alloc;
+
+pub mod fft;
+mod window;
+
+use alloc::vec::Vec;
+
+use hotg_rune_proc_blocks::{ProcBlock, Tensor, Transform};
+
+pub use crate::window::{UnknownWindow, Window};
+
+/// Convert audio into a magnitude spectrogram.
+///
+/// `i16` samples are scaled to the range `[-1, 1]` before being transformed.
+#[derive(Debug, Clone, PartialEq, ProcBlock)]
+#[transform(inputs = [i16; _], outputs = [f32; 3])]
+#[transform(inputs = [f32; _], outputs = [f32; 3])]
+pub struct Spectrogram {
+    /// The number of samples in each frame.
+    #[proc_block(validate = is_positive)]
+    window_size: usize,
+    /// The number of samples between the start of one frame and the next.
+    #[proc_block(validate = is_positive)]
+    hop_size: usize,
+    /// The number of points in each FFT. Frames are zero-padded to this
+    /// length, so it must be a power of two no smaller than the window size.
+    #[proc_block(validate = is_power_of_two)]
+    fft_size: usize,
+    /// The window function (`hann`, `hamming`, or `rectangular`).
+    window: Window,
+}
+
+impl Spectrogram {
+    fn spectrogram(&self, samples: &[f32]) -> Tensor<f32> {
+        assert!(
+            self.window_size <= self.fft_size,
+            "The window size ({}) can't be larger than the FFT size ({})",
+            self.window_size,
+            self.fft_size
+        );
+
+        let window = self.window.coefficients(self.window_size);
+
+        spectrogram(samples, &window, self.hop_size, self.fft_size)
+    }
+}
+
+impl Default for Spectrogram {
+    /// 30ms frames every 10ms, assuming audio sampled at 16kHz.
+    fn default() -> Self {
+        Spectrogram {
+            window_size: 480,
+            hop_size: 160,
+            fft_size: 512,
+            window: Window::default(),
+        }
+    }
+}
+
+impl Transform<Tensor<i16>> for Spectrogram {
+    type Output = Tensor<f32>;
+
+    fn transform(&mut self, input: Tensor<i16>) -> Self::Output {
+        let samples: Vec<f32> = input
+            .elements()
+            .iter()
+            .map(|&sample| sample as f32 / -(i16::MIN as f32))
+            .collect();
+
+        self.spectrogram(&samples)
+    }
+}
+
+impl Transform<Tensor<f32>> for Spectrogram {
+    type Output = Tensor<f32>;
+
+    fn transform(&mut self, input: Tensor<f32>) -> Self::Output {
+        self.spectrogram(input.elements())
+    }
+}
+
+/// Calculate the magnitude spectrogram for some samples, returning a
+/// `[1, frames, fft_size / 2 + 1]` tensor.
+///
+/// Each frame is `window.len()` samples long and is multiplied by `window`
+/// before being transformed. Any samples left over after the last full frame
+/// are ignored.
+pub fn spectrogram(
+    samples: &[f32],
+    window: &[f32],
+    hop_size: usize,
+    fft_size: usize,
+) -> Tensor<f32> {
+    let frames = frame_count(samples.len(), window.len(), hop_size);
+    let bins = fft_size / 2 + 1;
+
+    let mut elements = Vec::with_capacity(frames * bins);
+    let mut frame = Vec::with_capacity(window.len());
+
+    for i in 0..frames {
+        let start = i * hop_size;
+        let samples = &samples[start..start + window.len()];
+
+        frame.clear();
+        frame.extend(samples.iter().zip(window).map(|(&s, &w)| s * w));
+
+        elements.extend(fft::magnitudes(&frame, fft_size));
+    }
+
+    Tensor::new_row_major(elements.into(), alloc::vec![1, frames, bins])
+}
+
+fn frame_count(samples: usize, window_size: usize, hop_size: usize) -> usize {
+    if samples < window_size {
+        0
+    } else {
+        1 + (samples - window_size) / hop_size
+    }
+}
+
+fn is_positive(value: &usize) -> Result<(), &'static str> {
+    if *value > 0 {
+        Ok(())
+    } else {
+        Err("must be greater than zero")
+    }
+}
+
+fn is_power_of_two(value: &usize) -> Result<(), &'static str> {
+    if value.is_power_of_two() {
+        Ok(())
+    } else {
+        Err("must be a power of two")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+    use core::f32::consts::PI;
+
+    use super::*;
+
+    fn sine_wave(len: usize, period: usize) -> Vec<f32> {
+        (0..len)
+            .map(|i| libm::sinf(2.0 * PI * i as f32 / period as f32))
+            .collect()
+    }
+
+    #[test]
+    fn count_frames() {
+        assert_eq!(frame_count(16000, 480, 160), 98);
+        assert_eq!(frame_count(480, 480, 160), 1);
+        assert_eq!(frame_count(479, 480, 160), 0);
+    }
+
+    #[test]
+    fn spectrogram_of_a_sine_wave() {
+        let mut proc_block = Spectrogram::default();
+        proc_block.set_window_size("64").unwrap();
+        proc_block.set_hop_size("32").unwrap();
+        proc_block.set_fft_size("64").unwrap();
+        proc_block.set_window("rectangular").unwrap();
+        // a wave with a period of 8 samples lands in bin 64/8 = 8
+        let input = Tensor::new_vector(sine_wave(160, 8));
+
+        let got = proc_block.transform(input);
+
+        assert_eq!(got.dimensions(), &[1, 4, 33]);
+        for frame in got.elements().chunks(33) {
+            let loudest = hotg_rune_proc_blocks::math::argmax(
+                &Tensor::new_vector(frame.iter().copied()),
+            );
+            assert_eq!(loudest, Some(8));
+        }
+    }
+
+    #[test]
+    fn integer_samples_are_scaled() {
+        let wave = sine_wave(1024, 16);
+        let integers: Vec<i16> =
+            wave.iter().map(|&s| (s * 32767.0) as i16).collect();
+        let mut proc_block = Spectrogram::default();
+
+        let from_floats = proc_block.transform(Tensor::new_vector(wave));
+        let from_integers = proc_block.transform(Tensor::new_vector(integers));
+
+        assert_eq!(from_floats.dimensions(), from_integers.dimensions());
+        for (f, i) in
+            from_floats.elements().iter().zip(from_integers.elements())
+        {
+            assert!((f - i) * (f - i) < 1e-3, "{} != {}", f, i);
+        }
+    }
+
+    #[test]
+    fn too_few_samples_gives_an_empty_spectrogram() {
+        let mut proc_block = Spectrogram::default();
+
+        let got =
+            proc_block.transform(Tensor::new_vector(alloc::vec![0.0_f32; 10]));
+
+        assert_eq!(got.dimensions(), &[1, 0, 257]);
+    }
+
+    fn rectangular(
+        window_size: &str,
+        hop_size: &str,
+        fft_size: &str,
+    ) -> Spectrogram {
+        let mut proc_block = Spectrogram::default();
+        proc_block.set_window_size(window_size).unwrap();
+        proc_block.set_hop_size(hop_size).unwrap();
+        proc_block.set_fft_size(fft_size).unwrap();
+        proc_block.set_window("rectangular").unwrap();
+        proc_block
+    }
+
+    fn assert_close(got: &[f32], expected: &[f32]) {
+        assert_eq!(got.len(), expected.len());
+        for (g, e) in got.iter().zip(expected) {
+            assert!((g - e).abs() < 1e-5, "{:?} != {:?}", got, expected);
+        }
+    }
+
+    #[test]
+    fn fft_size_must_be_a_power_of_two() {
+        let mut proc_block = Spectrogram::default();
+
+        let err = proc_block.set_fft_size("500").unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "Unable to set \"fft_size\" to \"500\": must be a power of two"
+        );
+        assert!(proc_block.set_fft_size("0").is_err());
+        assert_eq!(proc_block, Spectrogram::default());
+        proc_block.set_fft_size("1024").unwrap();
+        assert_eq!(proc_block.fft_size, 1024);
+    }
+
+    #[test]
+    fn short_windows_are_zero_padded() {
+        let mut proc_block = rectangular("3", "3", "4");
+
+        let got = proc_block
+            .transform(Tensor::new_vector(alloc::vec![1.0_f32, 1.0, 1.0]));
+
+        // [1, 1, 1, 0] has a DC component of 3, and the padding leaks into
+        // the other bins
+        assert_eq!(got.dimensions(), &[1, 1, 3]);
+        assert_close(got.elements(), &[3.0, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn hops_bigger_than_the_window_skip_samples() {
+        let mut proc_block = rectangular("2", "4", "2");
+        // Frames start at 0, 4 and 8, so the 100s are never seen
+        let samples = alloc::vec![
+            1.0_f32, 1.0, 100.0, 100.0, 0.0, 0.0, 100.0, 100.0, 2.0, 2.0,
+            100.0,
+        ];
+
+        let got = proc_block.transform(Tensor::new_vector(samples));
+
+        assert_eq!(got.dimensions(), &[1, 3, 2]);
+        assert_close(got.elements(), &[2.0, 0.0, 0.0, 0.0, 4.0, 0.0]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn window_must_fit_in_the_fft() {
+        let mut proc_block = Spectrogram::default();
+        proc_block.set_window_size("1024").unwrap();
+
+        proc_block.transform(Tensor::new_vector(alloc::vec![0.0_f32; 2048]));
+    }
+}
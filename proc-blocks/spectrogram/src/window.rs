@@ -0,0 +1,111 @@
+use alloc::{string::String, vec::Vec};
+use core::{
+    f32::consts::PI,
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
+
+/// The window function applied to each frame before it is transformed.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Window {
+    Hann,
+    Hamming,
+    /// Leave the samples as-is.
+    Rectangular,
+}
+
+impl Window {
+    /// Calculate the (periodic) window coefficients for a frame with `len`
+    /// samples.
+    pub fn coefficients(self, len: usize) -> Vec<f32> {
+        (0..len)
+            .map(|n| {
+                let cos = libm::cosf(2.0 * PI * n as f32 / len as f32);
+
+                match self {
+                    Window::Hann => 0.5 - 0.5 * cos,
+                    Window::Hamming => 0.54 - 0.46 * cos,
+                    Window::Rectangular => 1.0,
+                }
+            })
+            .collect()
+    }
+}
+
+impl Default for Window {
+    fn default() -> Self { Window::Hann }
+}
+
+impl FromStr for Window {
+    type Err = UnknownWindow;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "hann" => Ok(Window::Hann),
+            "hamming" => Ok(Window::Hamming),
+            "rectangular" => Ok(Window::Rectangular),
+            _ => Err(UnknownWindow { name: s.into() }),
+        }
+    }
+}
+
+impl Display for Window {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Window::Hann => write!(f, "hann"),
+            Window::Hamming => write!(f, "hamming"),
+            Window::Rectangular => write!(f, "rectangular"),
+        }
+    }
+}
+
+/// The error returned when parsing an unknown [`Window`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownWindow {
+    pub name: String,
+}
+
+impl Display for UnknownWindow {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Expected \"hann\", \"hamming\", or \"rectangular\", but found \
+             \"{}\"",
+            self.name
+        )
+    }
+}
+
+impl hotg_rune_core::Error for UnknownWindow {}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+
+    use super::*;
+
+    #[test]
+    fn hann_window_tapers_to_zero() {
+        let got = Window::Hann.coefficients(4);
+
+        assert_eq!(got[0], 0.0);
+        assert_eq!(got[2], 1.0);
+        assert!(got[1] > 0.49 && got[1] < 0.51);
+        assert!(got[3] > 0.49 && got[3] < 0.51);
+    }
+
+    #[test]
+    fn parse_window_names() {
+        for window in [Window::Hann, Window::Hamming, Window::Rectangular] {
+            let round_tripped: Window = window.to_string().parse().unwrap();
+            assert_eq!(round_tripped, window);
+        }
+
+        assert_eq!(
+            "blackman".parse::<Window>().unwrap_err(),
+            UnknownWindow {
+                name: "blackman".into()
+            }
+        );
+    }
+}
@@ -0,0 +1,251 @@
+//! A proc block which finds the most confident predictions from a classifier
+//! and looks up their labels.
+//!
+//! The labels are usually provided by a resource, with one label per line.
+//! The proc block has two outputs, the `count` most confident labels and
+//! their confidence values, both ordered from most to least confident.
+//!
+//! ```yaml
+//! top_labels:
+//!   proc-block: "hotg-ai/rune#proc-blocks/top-labels"
+//!   inputs:
+//!     - model
+//!   outputs:
+//!     - type: UTF8
+//!       dimensions: [3]
+//!     - type: F32
+//!       dimensions: [3]
+//!   args:
+//!     count: 3
+//!     labels: $LABELS
+//! ```
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::{borrow::Cow, string::String, vec::Vec};
+use core::{
+    cmp::Ordering,
+    convert::Infallible,
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
+
+use hotg_rune_proc_blocks::{ProcBlock, Tensor, Transform};
+
+/// Find the `count` most confident predictions and their labels.
+///
+/// Quantized integer inputs are dequantized first, if the tensor has
+/// quantization parameters.
+#[derive(Debug, Clone, PartialEq, ProcBlock)]
+#[transform(inputs = [f32; _], outputs = ([utf8; 1], [f32; 1]))]
+#[transform(inputs = [u8; _], outputs = ([utf8; 1], [f32; 1]))]
+#[transform(inputs = [i8; _], outputs = ([utf8; 1], [f32; 1]))]
+pub struct TopLabels {
+    /// The number of predictions to return.
+    #[proc_block(validate = is_positive)]
+    count: usize,
+    /// The label for each of the classifier's outputs, one per line.
+    labels: Labels,
+}
+
+impl TopLabels {
+    fn top_labels(
+        &self,
+        confidences: &[f32],
+    ) -> (Tensor<Cow<'static, str>>, Tensor<f32>) {
+        assert_eq!(
+            confidences.len(),
+            self.labels.0.len(),
+            "There should be a label for each confidence value",
+        );
+
+        let indices = most_confident(confidences, self.count);
+
+        let labels = indices
+            .iter()
+            .map(|&ix| Cow::Owned(self.labels.0[ix].clone()))
+            .collect();
+        let confidences = indices.iter().map(|&ix| confidences[ix]).collect();
+
+        (labels, confidences)
+    }
+}
+
+impl Default for TopLabels {
+    fn default() -> Self {
+        TopLabels {
+            count: 1,
+            labels: Labels::default(),
+        }
+    }
+}
+
+impl Transform<Tensor<f32>> for TopLabels {
+    type Output = (Tensor<Cow<'static, str>>, Tensor<f32>);
+
+    fn transform(&mut self, input: Tensor<f32>) -> Self::Output {
+        self.top_labels(input.elements())
+    }
+}
+
+macro_rules! quantized_transform {
+    ($($ty:ty),*) => {
+        $(
+            impl Transform<Tensor<$ty>> for TopLabels {
+                type Output = (Tensor<Cow<'static, str>>, Tensor<f32>);
+
+                fn transform(&mut self, input: Tensor<$ty>) -> Self::Output {
+                    let confidences = input
+                        .dequantize()
+                        .unwrap_or_else(|| input.map(|_, &x| f32::from(x)));
+
+                    self.top_labels(confidences.elements())
+                }
+            }
+        )*
+    };
+}
+
+quantized_transform!(u8, i8);
+
+/// The indices of the `count` largest values, from largest to smallest.
+///
+/// Ties go to the value that appears first and `NaN`s are treated as the
+/// smallest possible value.
+fn most_confident(confidences: &[f32], count: usize) -> Vec<usize> {
+    let key = |ix: usize| {
+        let confidence = confidences[ix];
+        if confidence.is_nan() {
+            f32::NEG_INFINITY
+        } else {
+            confidence
+        }
+    };
+
+    let mut indices: Vec<usize> = (0..confidences.len()).collect();
+    // Note: sort_by() is stable, so ties keep their original order
+    indices.sort_by(|&a, &b| {
+        key(b).partial_cmp(&key(a)).unwrap_or(Ordering::Equal)
+    });
+    indices.truncate(count);
+
+    indices
+}
+
+/// A list of labels, parsed from a string with one label per line.
+///
+/// Leading and trailing whitespace is removed from each label and blank lines
+/// are ignored.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Labels(pub Vec<String>);
+
+impl FromStr for Labels {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let labels = s
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(String::from)
+            .collect();
+
+        Ok(Labels(labels))
+    }
+}
+
+impl Display for Labels {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for label in &self.0 {
+            writeln!(f, "{}", label)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn is_positive(value: &usize) -> Result<(), &'static str> {
+    if *value > 0 {
+        Ok(())
+    } else {
+        Err("must be greater than zero")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{string::ToString, vec};
+
+    use hotg_rune_core::Quantization;
+
+    use super::*;
+
+    fn proc_block(count: &str) -> TopLabels {
+        let mut proc_block = TopLabels::default();
+        proc_block.set_count(count).unwrap();
+        proc_block
+            .set_labels("silence\nunknown\nup\ndown\nleft\nright\n")
+            .unwrap();
+        proc_block
+    }
+
+    #[test]
+    fn most_confident_labels() {
+        let mut proc_block = proc_block("3");
+        let input =
+            Tensor::new_vector(vec![0.05_f32, 0.1, 0.6, 0.0, 0.2, 0.05]);
+
+        let (labels, confidences) = proc_block.transform(input);
+
+        assert_eq!(labels.elements(), &["up", "left", "unknown"]);
+        assert_eq!(confidences.elements(), &[0.6, 0.2, 0.1]);
+    }
+
+    #[test]
+    fn ties_and_nans() {
+        let got = most_confident(&[0.5, f32::NAN, 0.5, 0.9], 4);
+
+        assert_eq!(got, vec![3, 0, 2, 1]);
+    }
+
+    #[test]
+    fn count_is_capped_at_the_number_of_classes() {
+        let mut proc_block = proc_block("10");
+        let input = Tensor::new_vector(vec![0.0_f32; 6]);
+
+        let (labels, confidences) = proc_block.transform(input);
+
+        assert_eq!(labels.dimensions(), &[6]);
+        assert_eq!(confidences.dimensions(), &[6]);
+    }
+
+    #[test]
+    fn dequantize_integer_inputs() {
+        let mut proc_block = proc_block("1");
+        let input = Tensor::new_vector(vec![-128_i8, -128, -128, -128, 127, 0])
+            .with_quantization(Quantization::per_tensor(1.0 / 256.0, -128))
+            .unwrap();
+
+        let (labels, confidences) = proc_block.transform(input);
+
+        assert_eq!(labels.elements(), &["left"]);
+        assert_eq!(confidences.elements(), &[255.0 / 256.0]);
+    }
+
+    #[test]
+    fn labels_ignore_blank_lines() {
+        let labels: Labels = "  cat \n\ndog\r\n".parse().unwrap();
+
+        assert_eq!(labels, Labels(vec!["cat".to_string(), "dog".to_string()]));
+    }
+
+    #[test]
+    #[should_panic = "There should be a label for each confidence value"]
+    fn every_class_needs_a_label() {
+        let mut proc_block = proc_block("1");
+
+        proc_block.transform(Tensor::new_vector(vec![0.5_f32, 0.5]));
+    }
+}
@@ -0,0 +1,241 @@
+//! A proc block which turns text into the token IDs expected by BERT-style
+//! NLP models, using a WordPiece vocabulary.
+//!
+//! The vocabulary is usually provided by a resource containing the model's
+//! `vocab.txt`, with one token per line. The proc block has two outputs, the
+//! `input_ids` and the `attention_mask`, which are both padded (or truncated)
+//! to `max-length` tokens.
+//!
+//! ```yaml
+//! tokenizer:
+//!   proc-block: "hotg-ai/rune#proc-blocks/wordpiece-tokenizer"
+//!   inputs:
+//!     - text
+//!   outputs:
+//!     - type: I32
+//!       dimensions: [1, 128]
+//!     - type: I32
+//!       dimensions: [1, 128]
+//!   args:
+//!     vocabulary: $VOCABULARY
+//!     max-length: 128
+//!     lowercase: true
+//! ```
+
+#![no_std]
+
+extern crate alloc;
+
+mod wordpiece;
+
+use alloc::{borrow::Cow, vec::Vec};
+
+use hotg_rune_proc_blocks::{ProcBlock, Tensor, Transform};
+
+pub use crate::wordpiece::{basic_tokenize, Vocabulary};
+
+const UNKNOWN: &str = "[UNK]";
+const CLASSIFY: &str = "[CLS]";
+const SEPARATOR: &str = "[SEP]";
+const PADDING: &str = "[PAD]";
+
+/// Split text into words and punctuation, then break each word into the
+/// longest tokens from the `vocabulary`.
+///
+/// Multiple input strings are joined with a space. Words that can't be
+/// represented by the vocabulary become `[UNK]`.
+#[derive(Debug, Clone, PartialEq, ProcBlock)]
+#[transform(inputs = [utf8; _], outputs = ([i32; 2], [i32; 2]))]
+pub struct WordpieceTokenizer {
+    /// The tokens the model knows about, one per line, where a token's ID is
+    /// its line number.
+    vocabulary: Vocabulary,
+    /// The number of tokens to output, including any special tokens.
+    #[proc_block(validate = is_long_enough)]
+    max_length: usize,
+    /// Convert the text to lowercase before tokenizing (for "uncased"
+    /// models).
+    lowercase: bool,
+    /// Surround the tokens with `[CLS]` and `[SEP]`.
+    add_special_tokens: bool,
+}
+
+impl WordpieceTokenizer {
+    fn tokenize(&self, text: &str) -> (Tensor<i32>, Tensor<i32>) {
+        let vocab = &self.vocabulary;
+        let special_token = |token: &str| {
+            vocab.id(token).unwrap_or_else(|| {
+                panic!("The vocabulary doesn't contain \"{}\"", token)
+            })
+        };
+        let unknown = special_token(UNKNOWN);
+
+        let mut max_tokens = self.max_length;
+        if self.add_special_tokens {
+            max_tokens = max_tokens.saturating_sub(2);
+        }
+
+        let mut ids: Vec<i32> = basic_tokenize(text, self.lowercase)
+            .iter()
+            .flat_map(|word| {
+                vocab
+                    .word_pieces(word)
+                    .unwrap_or_else(|| alloc::vec![unknown])
+            })
+            .take(max_tokens)
+            .collect();
+
+        if self.add_special_tokens {
+            ids.insert(0, special_token(CLASSIFY));
+            ids.push(special_token(SEPARATOR));
+        }
+
+        let mut attention_mask = alloc::vec![1; ids.len()];
+
+        // Note: BERT vocabularies always put [PAD] first
+        let padding = vocab.id(PADDING).unwrap_or(0);
+        ids.resize(self.max_length, padding);
+        attention_mask.resize(self.max_length, 0);
+
+        let dimensions = alloc::vec![1, self.max_length];
+
+        (
+            Tensor::new_row_major(ids.into(), dimensions.clone()),
+            Tensor::new_row_major(attention_mask.into(), dimensions),
+        )
+    }
+}
+
+impl Default for WordpieceTokenizer {
+    fn default() -> Self {
+        WordpieceTokenizer {
+            vocabulary: Vocabulary::default(),
+            max_length: 128,
+            lowercase: true,
+            add_special_tokens: true,
+        }
+    }
+}
+
+impl Transform<Tensor<Cow<'static, str>>> for WordpieceTokenizer {
+    type Output = (Tensor<i32>, Tensor<i32>);
+
+    fn transform(&mut self, input: Tensor<Cow<'static, str>>) -> Self::Output {
+        let text = input
+            .elements()
+            .iter()
+            .map(|s| s.as_ref())
+            .collect::<Vec<&str>>()
+            .join(" ");
+
+        self.tokenize(&text)
+    }
+}
+
+fn is_long_enough(value: &usize) -> Result<(), &'static str> {
+    // We need room for [CLS], [SEP], and at least one token
+    if *value >= 3 {
+        Ok(())
+    } else {
+        Err("must be at least 3")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+
+    const VOCAB: &str = concat!(
+        "[PAD]\n[UNK]\n[CLS]\n[SEP]\n",
+        "the\nquick\n,\nbrown\nfox\n##es\njump\n##ed\n!\n",
+    );
+
+    fn tokenizer(max_length: &str) -> WordpieceTokenizer {
+        let mut proc_block = WordpieceTokenizer::default();
+        proc_block.set_vocabulary(VOCAB).unwrap();
+        proc_block.set_max_length(max_length).unwrap();
+        proc_block
+    }
+
+    fn text(s: &'static str) -> Tensor<Cow<'static, str>> {
+        Tensor::new_vector(vec![Cow::Borrowed(s)])
+    }
+
+    fn row(values: &[i32]) -> Tensor<i32> {
+        Tensor::new_row_major(values.into(), vec![1, values.len()])
+    }
+
+    #[test]
+    fn tokenize_a_sentence() {
+        let mut proc_block = tokenizer("14");
+
+        let (ids, mask) =
+            proc_block.transform(text("The quick, brown foxes jumped!"));
+
+        assert_eq!(ids, row(&[2, 4, 5, 6, 7, 8, 9, 10, 11, 12, 3, 0, 0, 0]));
+        assert_eq!(mask, row(&[1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0]));
+    }
+
+    #[test]
+    fn unknown_words() {
+        let mut proc_block = tokenizer("6");
+
+        let (ids, _) = proc_block.transform(text("the lazy dog"));
+
+        assert_eq!(ids, row(&[2, 4, 1, 1, 3, 0]));
+    }
+
+    #[test]
+    fn truncate_long_inputs() {
+        let mut proc_block = tokenizer("4");
+
+        let (ids, mask) = proc_block.transform(text("the quick brown fox"));
+
+        assert_eq!(ids, row(&[2, 4, 5, 3]));
+        assert_eq!(mask, row(&[1, 1, 1, 1]));
+    }
+
+    #[test]
+    fn without_special_tokens_or_lowercasing() {
+        let mut proc_block = tokenizer("4");
+        proc_block.set_add_special_tokens("false").unwrap();
+        proc_block.set_lowercase("false").unwrap();
+
+        let (ids, mask) = proc_block.transform(text("The fox"));
+
+        assert_eq!(ids, row(&[1, 8, 0, 0]));
+        assert_eq!(mask, row(&[1, 1, 0, 0]));
+    }
+
+    #[test]
+    fn multiple_strings_are_joined() {
+        let mut proc_block = tokenizer("5");
+        let input = Tensor::new_vector(vec![
+            Cow::Borrowed("brown"),
+            Cow::Borrowed("fox"),
+        ]);
+
+        let (ids, _) = proc_block.transform(input);
+
+        assert_eq!(ids, row(&[2, 7, 8, 3, 0]));
+    }
+
+    #[test]
+    fn max_length_needs_room_for_special_tokens() {
+        let mut proc_block = WordpieceTokenizer::default();
+
+        assert!(proc_block.set_max_length("2").is_err());
+        assert!(proc_block.set_max_length("3").is_ok());
+    }
+
+    #[test]
+    #[should_panic = "The vocabulary doesn't contain \"[UNK]\""]
+    fn vocabulary_needs_special_tokens() {
+        let mut proc_block = WordpieceTokenizer::default();
+        proc_block.set_vocabulary("hello\nworld").unwrap();
+
+        proc_block.transform(text("hello"));
+    }
+}
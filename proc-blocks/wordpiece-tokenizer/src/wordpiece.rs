@@ -0,0 +1,202 @@
+use alloc::{
+    collections::BTreeMap,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{
+    convert::Infallible,
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
+
+/// Words longer than this are always treated as unknown.
+const MAX_CHARS_PER_WORD: usize = 100;
+
+/// The prefix used by tokens which continue a word (e.g. `"##ing"`).
+const CONTINUATION_PREFIX: &str = "##";
+
+/// A WordPiece vocabulary, parsed from a string with one token per line
+/// where each token's ID is its line number (i.e. BERT's `vocab.txt`).
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Vocabulary {
+    tokens: Vec<String>,
+    ids: BTreeMap<String, i32>,
+}
+
+impl Vocabulary {
+    pub fn id(&self, token: &str) -> Option<i32> {
+        self.ids.get(token).copied()
+    }
+
+    pub fn len(&self) -> usize { self.tokens.len() }
+
+    pub fn is_empty(&self) -> bool { self.tokens.is_empty() }
+
+    /// Split a single word into tokens using a greedy longest-match-first
+    /// search, or `None` if the word can't be represented by this
+    /// vocabulary.
+    pub fn word_pieces(&self, word: &str) -> Option<Vec<i32>> {
+        if word.chars().count() > MAX_CHARS_PER_WORD {
+            return None;
+        }
+
+        let mut pieces = Vec::new();
+        let mut rest = word;
+        let mut candidate = String::new();
+
+        while !rest.is_empty() {
+            let mut end = rest.len();
+
+            let id = loop {
+                candidate.clear();
+                if rest.len() != word.len() {
+                    candidate.push_str(CONTINUATION_PREFIX);
+                }
+                candidate.push_str(&rest[..end]);
+
+                if let Some(id) = self.id(&candidate) {
+                    break id;
+                }
+
+                // Try again with one less character
+                end = rest[..end].char_indices().last()?.0;
+
+                if end == 0 {
+                    return None;
+                }
+            };
+
+            pieces.push(id);
+            rest = &rest[end..];
+        }
+
+        Some(pieces)
+    }
+}
+
+impl FromStr for Vocabulary {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let tokens: Vec<String> =
+            s.lines().map(|line| line.trim_end().to_string()).collect();
+        let ids = tokens
+            .iter()
+            .enumerate()
+            .map(|(id, token)| (token.clone(), id as i32))
+            .collect();
+
+        Ok(Vocabulary { tokens, ids })
+    }
+}
+
+impl Display for Vocabulary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for token in &self.tokens {
+            writeln!(f, "{}", token)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Split text into words and punctuation, the same way as BERT's
+/// `BasicTokenizer`.
+///
+/// Control characters are removed, CJK characters are treated as words by
+/// themselves, and ASCII punctuation is split into separate tokens.
+///
+/// Note: unlike BERT, accents aren't stripped when lowercasing because that
+/// requires Unicode normalization tables.
+pub fn basic_tokenize(text: &str, lowercase: bool) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+
+    for c in text.chars() {
+        if c.is_whitespace() {
+            flush(&mut current, &mut words);
+        } else if c.is_control() || c == '\u{fffd}' {
+            continue;
+        } else if c.is_ascii_punctuation() || is_cjk(c) {
+            flush(&mut current, &mut words);
+            words.push(c.to_string());
+        } else if lowercase {
+            current.extend(c.to_lowercase());
+        } else {
+            current.push(c);
+        }
+    }
+
+    flush(&mut current, &mut words);
+
+    words
+}
+
+fn flush(current: &mut String, words: &mut Vec<String>) {
+    if !current.is_empty() {
+        words.push(core::mem::take(current));
+    }
+}
+
+/// Is this a character from the CJK Unified Ideographs blocks?
+fn is_cjk(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x4E00..=0x9FFF
+            | 0x3400..=0x4DBF
+            | 0x20000..=0x2A6DF
+            | 0x2A700..=0x2B73F
+            | 0x2B740..=0x2B81F
+            | 0x2B820..=0x2CEAF
+            | 0xF900..=0xFAFF
+            | 0x2F800..=0x2FA1F
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+
+    #[test]
+    fn split_words_and_punctuation() {
+        let got = basic_tokenize("  Hello,\tWORLD!\u{0}你好 ", true);
+
+        assert_eq!(got, vec!["hello", ",", "world", "!", "你", "好"]);
+    }
+
+    #[test]
+    fn keep_the_case() {
+        let got = basic_tokenize("Hello World", false);
+
+        assert_eq!(got, vec!["Hello", "World"]);
+    }
+
+    #[test]
+    fn greedy_longest_match_first() {
+        let vocab: Vocabulary =
+            "[UNK]\nun\n##aff\n##able\n##affable\naff".parse().unwrap();
+
+        assert_eq!(vocab.word_pieces("unaffable"), Some(vec![1, 4]));
+        assert_eq!(vocab.word_pieces("affable"), Some(vec![5, 3]));
+        assert_eq!(vocab.word_pieces("unknown"), None);
+    }
+
+    #[test]
+    fn multi_byte_characters() {
+        let vocab: Vocabulary = "é\n##é\ncaf\n".parse().unwrap();
+
+        assert_eq!(vocab.word_pieces("café"), Some(vec![2, 1]));
+    }
+
+    #[test]
+    fn vocabulary_ids_are_line_numbers() {
+        let vocab: Vocabulary = "[PAD]\r\n[UNK]\nhello\n".parse().unwrap();
+
+        assert_eq!(vocab.len(), 3);
+        assert_eq!(vocab.id("[UNK]"), Some(1));
+        assert_eq!(vocab.id("hello"), Some(2));
+        assert_eq!(vocab.id("missing"), None);
+    }
+}